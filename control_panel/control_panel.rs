@@ -2,7 +2,7 @@ use actix_server::ServerHandle;
 use anyhow::Result;
 use futures::{executor, future::BoxFuture, FutureExt};
 use jsonrpc_core_client::{transports::ipc, RpcError};
-use mmb_rpc::rest_api::{MmbRpcClient, IPC_ADDRESS};
+use mmb_rpc::rest_api::{ErrorCode, MmbRpcClient, IPC_ADDRESS};
 use mmb_utils::logger::print_info;
 use parking_lot::Mutex;
 use std::{sync::mpsc, sync::Arc, time::Duration};
@@ -74,10 +74,48 @@ impl ControlPanel {
             App::new()
                 .app_data(Data::new(client.clone()))
                 .service(endpoints::health)
+                .service(endpoints::healthz)
+                .service(endpoints::readyz)
                 .service(endpoints::stop)
                 .service(endpoints::stats)
                 .service(endpoints::get_config)
                 .service(endpoints::set_config)
+                .service(endpoints::get_effective_config)
+                .service(endpoints::withdrawals)
+                .service(endpoints::approve_withdrawal)
+                .service(endpoints::reject_withdrawal)
+                .service(endpoints::reduce_only)
+                .service(endpoints::enable_reduce_only)
+                .service(endpoints::disable_reduce_only)
+                .service(endpoints::performance_attribution)
+                .service(endpoints::balance_trees)
+                .service(endpoints::pause_market)
+                .service(endpoints::resume_market)
+                .service(endpoints::desired_amounts)
+                .service(endpoints::set_desired_amount)
+                .service(endpoints::market_modes)
+                .service(endpoints::set_market_mode)
+                .service(endpoints::export_state)
+                .service(endpoints::import_state)
+                .service(endpoints::host_stats)
+                .service(endpoints::connection_pool_stats)
+                .service(endpoints::parsing_stats)
+                .service(endpoints::request_queue_stats)
+                .service(endpoints::payload_anomalies)
+                .service(endpoints::error_codes)
+                .service(endpoints::tax_export)
+                .service(endpoints::fee_tiers)
+                .service(endpoints::compact_events)
+                .service(endpoints::event_compaction)
+                .service(endpoints::rebalancing_plans)
+                .service(endpoints::create_rebalancing_plan)
+                .service(endpoints::approve_rebalancing_plan)
+                .service(endpoints::reject_rebalancing_plan)
+                .service(endpoints::hedging)
+                .service(endpoints::value_at_risk)
+                .service(endpoints::stress_test)
+                .service(endpoints::news_restrictions)
+                .service(endpoints::news_signal)
                 .service(
                     actix_files::Files::new("/", webui_dir)
                         .use_last_modified(true)
@@ -132,8 +170,20 @@ impl ControlPanel {
     }
 }
 
+fn is_rpc_error_code(error: &RpcError, code: ErrorCode) -> bool {
+    matches!(error, RpcError::JsonRpcError(error)
+        if error.code == jsonrpc_core::ErrorCode::ServerError(code.code() as i64))
+}
+
 fn handle_rpc_error(error: RpcError) -> HttpResponse {
     match error {
+        // readiness probe should fail immediately while engine is starting
+        error if is_rpc_error_code(&error, ErrorCode::EngineIsNotReady) => {
+            HttpResponse::ServiceUnavailable().body(error.to_string())
+        }
+        error if is_rpc_error_code(&error, ErrorCode::Unauthorized) => {
+            HttpResponse::Unauthorized().body(error.to_string())
+        }
         RpcError::JsonRpcError(error) => {
             HttpResponse::InternalServerError().body(error.to_string())
         }
@@ -167,7 +217,8 @@ pub async fn send_request(
             match (action)(client).await {
                 Ok(response) => return HttpResponse::Ok().body(response),
                 Err(err) => {
-                    if try_counter > 2 {
+                    // engine responded, so reconnection doesn't help
+                    if try_counter > 2 || is_rpc_error_code(&err, ErrorCode::EngineIsNotReady) {
                         return handle_rpc_error(err);
                     }
                 }
@@ -1,4 +1,5 @@
-use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
 use futures::FutureExt;
 
 use crate::control_panel::{send_request, DataWebMmbRpcClient};
@@ -10,6 +11,18 @@ pub(super) async fn health(client: DataWebMmbRpcClient) -> impl Responder {
     send_request(client, |client| client.health().boxed()).await
 }
 
+/// Liveness probe: trading engine process is alive and responds
+#[get("/healthz")]
+pub(super) async fn healthz(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.health().boxed()).await
+}
+
+/// Readiness probe: all configured exchanges are connected, order books are received and balances are loaded
+#[get("/readyz")]
+pub(super) async fn readyz(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.ready().boxed()).await
+}
+
 #[post("/stop")]
 pub(super) async fn stop(client: DataWebMmbRpcClient) -> impl Responder {
     send_request(client, |client| client.stop().boxed()).await
@@ -20,8 +33,13 @@ pub(super) async fn get_config(client: DataWebMmbRpcClient) -> impl Responder {
     send_request(client, |client| client.get_config().boxed()).await
 }
 
+/// Operator is identified by token in `Authorization: Bearer <token>` header
 #[post("/config")]
-pub(super) async fn set_config(body: web::Bytes, client: DataWebMmbRpcClient) -> impl Responder {
+pub(super) async fn set_config(
+    body: web::Bytes,
+    request: HttpRequest,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
     let settings = match String::from_utf8((&body).to_vec()) {
         Ok(settings) => settings,
         Err(err) => {
@@ -31,13 +49,416 @@ pub(super) async fn set_config(body: web::Bytes, client: DataWebMmbRpcClient) ->
         }
     };
 
+    // engine without config has no operators, so token isn't required to set the first config
+    let operator_token = operator_token(&request).unwrap_or_default();
+
     send_request(client, move |client| {
-        client.set_config(settings.clone()).boxed()
+        client
+            .set_config(settings.clone(), operator_token.clone())
+            .boxed()
     })
     .await
 }
 
+/// Settings engine is running with after parsing, including default values. Credentials are redacted
+#[get("/config/effective")]
+pub(super) async fn get_effective_config(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.get_effective_config().boxed()).await
+}
+
 #[get("/stats")]
 pub(super) async fn stats(client: DataWebMmbRpcClient) -> impl Responder {
     send_request(client, |client| client.stats().boxed()).await
 }
+
+/// Withdrawals waiting for approval of second operator
+#[get("/withdrawals")]
+pub(super) async fn withdrawals(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.withdrawals().boxed()).await
+}
+
+/// Operator is identified by token in `Authorization: Bearer <token>` header
+#[post("/withdrawals/{withdrawal_id}/approve")]
+pub(super) async fn approve_withdrawal(
+    withdrawal_id: web::Path<String>,
+    request: HttpRequest,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let withdrawal_id = withdrawal_id.into_inner();
+    let operator_token = match operator_token(&request) {
+        Some(operator_token) => operator_token,
+        None => return missing_operator_token(),
+    };
+
+    send_request(client, move |client| {
+        client
+            .approve_withdrawal(withdrawal_id.clone(), operator_token.clone())
+            .boxed()
+    })
+    .await
+}
+
+/// Operator is identified by token in `Authorization: Bearer <token>` header
+#[post("/withdrawals/{withdrawal_id}/reject")]
+pub(super) async fn reject_withdrawal(
+    withdrawal_id: web::Path<String>,
+    request: HttpRequest,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let withdrawal_id = withdrawal_id.into_inner();
+    let operator_token = match operator_token(&request) {
+        Some(operator_token) => operator_token,
+        None => return missing_operator_token(),
+    };
+
+    send_request(client, move |client| {
+        client
+            .reject_withdrawal(withdrawal_id.clone(), operator_token.clone())
+            .boxed()
+    })
+    .await
+}
+
+/// Active reasons of reduce-only mode in which only position reducing orders are allowed
+#[get("/reduce_only")]
+pub(super) async fn reduce_only(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.reduce_only().boxed()).await
+}
+
+#[post("/reduce_only/enable")]
+pub(super) async fn enable_reduce_only(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.set_reduce_only(true).boxed()).await
+}
+
+#[post("/reduce_only/disable")]
+pub(super) async fn disable_reduce_only(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.set_reduce_only(false).boxed()).await
+}
+
+fn operator_token(request: &HttpRequest) -> Option<String> {
+    request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.strip_prefix("Bearer "))
+        .map(|x| x.trim().to_owned())
+        .filter(|x| !x.is_empty())
+}
+
+fn missing_operator_token() -> HttpResponse {
+    HttpResponse::Unauthorized().body("Operator token should be specified in Authorization header")
+}
+
+/// PnL of strategies per market decomposed into spread capture, inventory moves, fees and funding
+#[get("/performance_attribution")]
+pub(super) async fn performance_attribution(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.performance_attribution().boxed()).await
+}
+
+/// Reserved amounts and virtual balance diffs of strategies with sums by exchange accounts
+#[get("/balance_trees")]
+pub(super) async fn balance_trees(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.balance_trees().boxed()).await
+}
+
+/// Stop quoting on market: resting orders are canceled and strategies skip the market until resume
+#[post("/markets/{exchange_account_id}/{base}/{quote}/pause")]
+pub(super) async fn pause_market(
+    path: web::Path<(String, String, String)>,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let (exchange_account_id, base, quote) = path.into_inner();
+    let currency_pair = format!("{base}/{quote}");
+
+    send_request(client, move |client| {
+        client
+            .pause_market(exchange_account_id.clone(), currency_pair.clone())
+            .boxed()
+    })
+    .await
+}
+
+#[post("/markets/{exchange_account_id}/{base}/{quote}/resume")]
+pub(super) async fn resume_market(
+    path: web::Path<(String, String, String)>,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let (exchange_account_id, base, quote) = path.into_inner();
+    let currency_pair = format!("{base}/{quote}");
+
+    send_request(client, move |client| {
+        client
+            .resume_market(exchange_account_id.clone(), currency_pair.clone())
+            .boxed()
+    })
+    .await
+}
+
+/// Desired amounts of markets set at runtime which override max amounts from strategy settings
+#[get("/desired_amounts")]
+pub(super) async fn desired_amounts(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.desired_amounts().boxed()).await
+}
+
+/// Desired amount is specified in body, strategies apply it on the next trading context calculation
+#[put("/markets/{exchange_account_id}/{base}/{quote}/desired_amount")]
+pub(super) async fn set_desired_amount(
+    path: web::Path<(String, String, String)>,
+    body: web::Bytes,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let (exchange_account_id, base, quote) = path.into_inner();
+    let currency_pair = format!("{base}/{quote}");
+    let amount = match String::from_utf8(body.to_vec()) {
+        Ok(amount) => amount.trim().to_owned(),
+        Err(err) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Failed to convert desired amount({body:?}) to utf8 string: {err}",
+            ))
+        }
+    };
+
+    send_request(client, move |client| {
+        client
+            .set_desired_amount(
+                exchange_account_id.clone(),
+                currency_pair.clone(),
+                amount.clone(),
+            )
+            .boxed()
+    })
+    .await
+}
+
+/// Rollout modes of markets with metrics observed since the last mode change
+#[get("/market_modes")]
+pub(super) async fn market_modes(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.market_modes().boxed()).await
+}
+
+/// Mode is `shadow`, `reduced` or `full`. Reduced size in percents can be specified in body
+#[put("/markets/{exchange_account_id}/{base}/{quote}/mode/{mode}")]
+pub(super) async fn set_market_mode(
+    path: web::Path<(String, String, String, String)>,
+    body: web::Bytes,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let (exchange_account_id, base, quote, mode) = path.into_inner();
+    let currency_pair = format!("{base}/{quote}");
+    let reduced_size = match String::from_utf8(body.to_vec()) {
+        Ok(reduced_size) => reduced_size.trim().to_owned(),
+        Err(err) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Failed to convert reduced size({body:?}) to utf8 string: {err}",
+            ))
+        }
+    };
+
+    send_request(client, move |client| {
+        client
+            .set_market_mode(
+                exchange_account_id.clone(),
+                currency_pair.clone(),
+                mode.clone(),
+                reduced_size.clone(),
+            )
+            .boxed()
+    })
+    .await
+}
+
+/// Orders, balances, reservations and strategies persistent data in portable format
+#[get("/state/export")]
+pub(super) async fn export_state(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.export_state().boxed()).await
+}
+
+#[post("/state/import")]
+pub(super) async fn import_state(body: web::Bytes, client: DataWebMmbRpcClient) -> impl Responder {
+    let state = match String::from_utf8(body.to_vec()) {
+        Ok(state) => state,
+        Err(err) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Failed to convert input state({body:?}) to utf8 string: {err}",
+            ))
+        }
+    };
+
+    send_request(client, move |client| {
+        client.import_state(state.clone()).boxed()
+    })
+    .await
+}
+
+/// Latency probes and error counts of REST hosts selected by exchange accounts
+#[get("/host_stats")]
+pub(super) async fn host_stats(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.host_stats().boxed()).await
+}
+
+/// Requests and opened keep-alive connections of REST clients of exchange accounts
+#[get("/connection_pool_stats")]
+pub(super) async fn connection_pool_stats(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.connection_pool_stats().boxed()).await
+}
+
+/// Queue and parsing times of websocket messages of exchange accounts
+#[get("/parsing_stats")]
+pub(super) async fn parsing_stats(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.parsing_stats().boxed()).await
+}
+
+/// Waiting times of order requests queued by rate limits of exchange accounts by priority
+#[get("/request_queue_stats")]
+pub(super) async fn request_queue_stats(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.request_queue_stats().boxed()).await
+}
+
+/// Unknown enum values and missing fields in exchange payloads tolerated by connectors
+#[get("/payload_anomalies")]
+pub(super) async fn payload_anomalies(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.payload_anomalies().boxed()).await
+}
+
+/// Catalog of stable error codes with their classes and descriptions
+#[get("/error_codes")]
+pub(super) async fn error_codes(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.error_codes().boxed()).await
+}
+
+/// Fills in CSV format of tax tool: `koinly`, `cointracking` or `lots`
+#[get("/tax_export/{format}")]
+pub(super) async fn tax_export(
+    path: web::Path<String>,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let format = path.into_inner();
+    send_request(client, move |client| {
+        client.tax_export(format.clone()).boxed()
+    })
+    .await
+}
+
+/// Volume of the last 30 days, maker rebates and distance to the next fee tier
+#[get("/fee_tiers")]
+pub(super) async fn fee_tiers(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.fee_tiers().boxed()).await
+}
+
+/// Starts compaction of event tables by retention policies
+#[post("/events/compact")]
+pub(super) async fn compact_events(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.compact_events().boxed()).await
+}
+
+#[get("/events/compaction")]
+pub(super) async fn event_compaction(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.event_compaction().boxed()).await
+}
+
+/// Plans of transfers between exchange accounts to reach target distribution of balances
+#[get("/rebalancing/plans")]
+pub(super) async fn rebalancing_plans(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.rebalancing_plans().boxed()).await
+}
+
+/// Plans transfers by current balances, plan should be approved to be executed
+#[post("/rebalancing/plans")]
+pub(super) async fn create_rebalancing_plan(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.create_rebalancing_plan().boxed()).await
+}
+
+/// Operator is identified by token in `Authorization: Bearer <token>` header
+#[post("/rebalancing/plans/{plan_id}/approve")]
+pub(super) async fn approve_rebalancing_plan(
+    plan_id: web::Path<String>,
+    request: HttpRequest,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let plan_id = plan_id.into_inner();
+    let operator_token = match operator_token(&request) {
+        Some(operator_token) => operator_token,
+        None => return missing_operator_token(),
+    };
+
+    send_request(client, move |client| {
+        client
+            .approve_rebalancing_plan(plan_id.clone(), operator_token.clone())
+            .boxed()
+    })
+    .await
+}
+
+/// Operator is identified by token in `Authorization: Bearer <token>` header
+#[post("/rebalancing/plans/{plan_id}/reject")]
+pub(super) async fn reject_rebalancing_plan(
+    plan_id: web::Path<String>,
+    request: HttpRequest,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let plan_id = plan_id.into_inner();
+    let operator_token = match operator_token(&request) {
+        Some(operator_token) => operator_token,
+        None => return missing_operator_token(),
+    };
+
+    send_request(client, move |client| {
+        client
+            .reject_rebalancing_plan(plan_id.clone(), operator_token.clone())
+            .boxed()
+    })
+    .await
+}
+
+/// Spot inventory, perpetual hedge positions and basis PnL of hedges
+#[get("/hedging")]
+pub(super) async fn hedging(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.hedging().boxed()).await
+}
+
+/// Value at risk of current exposures, their volatilities and results of stress scenarios
+#[get("/value_at_risk")]
+pub(super) async fn value_at_risk(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.value_at_risk().boxed()).await
+}
+
+/// PnL of current exposures if price of currency changes by `change` percents, e.g. -20
+#[get("/value_at_risk/stress/{currency_code}/{change}")]
+pub(super) async fn stress_test(
+    path: web::Path<(String, String)>,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let (currency_code, change) = path.into_inner();
+
+    send_request(client, move |client| {
+        client
+            .stress_test(currency_code.clone(), change.clone())
+            .boxed()
+    })
+    .await
+}
+
+/// Assets restricted by news and compliance signals
+#[get("/news_restrictions")]
+pub(super) async fn news_restrictions(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.news_restrictions().boxed()).await
+}
+
+/// Webhook for news and compliance systems. Body is JSON of signal, e.g.
+/// {"type":"restrict","currency_codes":["btc"],"reason":"trading halt"}
+#[post("/news_signal")]
+pub(super) async fn news_signal(body: web::Bytes, client: DataWebMmbRpcClient) -> impl Responder {
+    let signal = match String::from_utf8(body.to_vec()) {
+        Ok(signal) => signal,
+        Err(err) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Failed to convert input signal({body:?}) to utf8 string: {err}",
+            ))
+        }
+    };
+
+    send_request(client, move |client| {
+        client.news_signal(signal.clone()).boxed()
+    })
+    .await
+}
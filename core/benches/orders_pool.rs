@@ -0,0 +1,159 @@
+//! Throughput of orders pool under concurrent access of many markets.
+//! Every thread works with its own market: looks up orders by client order id, scans not finished
+//! orders of market and replaces finished orders by new ones.
+//! Sharded pool is compared with flat map of not finished orders filtered by market on every scan.
+//!
+//! Run with `cargo bench -p mmb_core --bench orders_pool`
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use mmb_core::exchanges::common::{CurrencyPair, ExchangeAccountId};
+use mmb_core::orders::order::{ClientOrderId, OrderSide, OrderSnapshot, OrderType};
+use mmb_core::orders::pool::{OrderRef, OrdersPool};
+use parking_lot::RwLock;
+use rust_decimal_macros::dec;
+
+const MARKETS_COUNT: usize = 32;
+const ORDERS_PER_MARKET: usize = 50;
+const ITERATIONS_PER_THREAD: usize = 2_000;
+
+trait Pool: Send + Sync {
+    fn add(&self, snapshot: OrderSnapshot) -> OrderRef;
+    fn get(&self, client_order_id: &ClientOrderId) -> Option<OrderRef>;
+    fn market_orders(&self, currency_pair: CurrencyPair) -> Vec<OrderRef>;
+    fn finish(&self, order_ref: &OrderRef);
+}
+
+impl Pool for OrdersPool {
+    fn add(&self, snapshot: OrderSnapshot) -> OrderRef {
+        self.add_snapshot_initial(Arc::new(RwLock::new(snapshot)))
+    }
+
+    fn get(&self, client_order_id: &ClientOrderId) -> Option<OrderRef> {
+        self.get_by_client_id(client_order_id)
+    }
+
+    fn market_orders(&self, currency_pair: CurrencyPair) -> Vec<OrderRef> {
+        self.not_finished.by_market(currency_pair)
+    }
+
+    fn finish(&self, order_ref: &OrderRef) {
+        let _ = self.not_finished.remove(order_ref);
+    }
+}
+
+/// Layout of orders pool before sharding by market
+#[derive(Default)]
+struct FlatPool {
+    cache_by_client_id: DashMap<ClientOrderId, OrderRef>,
+    not_finished: DashMap<ClientOrderId, OrderRef>,
+}
+
+impl Pool for FlatPool {
+    fn add(&self, snapshot: OrderSnapshot) -> OrderRef {
+        let client_order_id = snapshot.header.client_order_id.clone();
+        let order_ref = OrderRef::new(Arc::new(RwLock::new(snapshot)));
+        let _ = self
+            .cache_by_client_id
+            .insert(client_order_id.clone(), order_ref.clone());
+        let _ = self.not_finished.insert(client_order_id, order_ref.clone());
+        order_ref
+    }
+
+    fn get(&self, client_order_id: &ClientOrderId) -> Option<OrderRef> {
+        self.cache_by_client_id
+            .get(client_order_id)
+            .map(|x| x.value().clone())
+    }
+
+    fn market_orders(&self, currency_pair: CurrencyPair) -> Vec<OrderRef> {
+        self.not_finished
+            .iter()
+            .filter(|x| x.currency_pair() == currency_pair)
+            .map(|x| x.value().clone())
+            .collect()
+    }
+
+    fn finish(&self, order_ref: &OrderRef) {
+        let _ = self.not_finished.remove(&order_ref.client_order_id());
+    }
+}
+
+fn currency_pair(market: usize) -> CurrencyPair {
+    CurrencyPair::from_codes(format!("c{market}").as_str().into(), "usdt".into())
+}
+
+fn order(currency_pair: CurrencyPair) -> OrderSnapshot {
+    OrderSnapshot::with_params(
+        ClientOrderId::unique_id(),
+        OrderType::Limit,
+        None,
+        ExchangeAccountId::new("Binance", 0),
+        currency_pair,
+        dec!(1000),
+        dec!(1),
+        OrderSide::Buy,
+        None,
+        "bench",
+    )
+}
+
+fn run(pool: Arc<dyn Pool>) -> Duration {
+    let markets = (0..MARKETS_COUNT)
+        .map(|market| {
+            let currency_pair = currency_pair(market);
+            let orders = (0..ORDERS_PER_MARKET)
+                .map(|_| pool.add(order(currency_pair)).client_order_id())
+                .collect::<Vec<_>>();
+            (currency_pair, orders)
+        })
+        .collect::<Vec<_>>();
+
+    let start = Instant::now();
+    let threads = markets
+        .into_iter()
+        .map(|(currency_pair, mut orders)| {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                for i in 0..ITERATIONS_PER_THREAD {
+                    let index = i % orders.len();
+                    let order_ref = pool.get(&orders[index]).expect("order should exist");
+
+                    let active_amount = pool
+                        .market_orders(currency_pair)
+                        .iter()
+                        .map(|x| x.amount() - x.filled_amount())
+                        .sum::<rust_decimal::Decimal>();
+                    assert!(!active_amount.is_zero());
+
+                    pool.finish(&order_ref);
+                    orders[index] = pool.add(order(currency_pair)).client_order_id();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().expect("bench thread failed");
+    }
+    start.elapsed()
+}
+
+fn report(name: &str, elapsed: Duration) -> f64 {
+    let operations = (MARKETS_COUNT * ITERATIONS_PER_THREAD) as f64;
+    let throughput = operations / elapsed.as_secs_f64();
+    println!("{name}: {elapsed:?}, {throughput:.0} iterations/sec");
+    throughput
+}
+
+fn main() {
+    let flat = report("flat pool", run(Arc::new(FlatPool::default())));
+    let sharded = report("sharded pool", run(OrdersPool::new()));
+    println!(
+        "sharded pool throughput is {:.1}x of flat pool",
+        sharded / flat
+    );
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use itertools::Itertools;
+
+use crate::backtesting::client::BacktestClientBuilder;
+use crate::backtesting::recording::Recording;
+use crate::exchanges::common::{Amount, CurrencyCode};
+use crate::exchanges::traits::ExchangeClientBuilder;
+use crate::lifecycle::launcher::EngineBuildConfig;
+use crate::misc::time::reset_replay_clock;
+
+#[derive(Debug, Clone)]
+pub struct BacktestSettings {
+    /// Market data recorded from `DataBridge` as JSON lines
+    pub recording_path: PathBuf,
+    /// Replay speed relative to recorded time, e.g. 10 replays an hour of market data in 6 minutes
+    pub speed: f64,
+    /// Simulated delay of order creation and cancellation by exchange
+    pub order_latency: Duration,
+    /// Balances of every simulated exchange account at the start of backtest
+    pub initial_balances: HashMap<CurrencyCode, Amount>,
+    /// Directory with order books recorded by `OrderBookDeltaRecorder`, which are replayed
+    /// together with market data of `recording_path`
+    pub order_book_deltas_dir: Option<PathBuf>,
+}
+
+impl BacktestSettings {
+    pub fn new(recording_path: impl Into<PathBuf>) -> Self {
+        BacktestSettings {
+            recording_path: recording_path.into(),
+            speed: 1.0,
+            order_latency: Duration::from_millis(50),
+            initial_balances: HashMap::new(),
+            order_book_deltas_dir: None,
+        }
+    }
+}
+
+/// Runs strategy on recorded market data instead of live exchanges. Connectors of recorded
+/// exchanges are replaced by simulated exchanges, so the same settings and strategy are launched
+/// by `launch_trading_engine` live or in backtest:
+///
+/// ```ignore
+/// let backtester = Backtester::load(BacktestSettings::new("recording.jsonl"))?;
+/// let engine = launch_trading_engine(
+///     &backtester.engine_build_config(),
+///     init_settings,
+///     build_strategy,
+/// )
+/// .await?;
+/// ```
+///
+/// Engine is stopped when recording of any exchange is replayed. Engine time follows replayed
+/// recording until backtester is dropped
+pub struct Backtester {
+    settings: Arc<BacktestSettings>,
+    recording: Arc<Recording>,
+}
+
+impl Backtester {
+    pub fn load(settings: BacktestSettings) -> Result<Self> {
+        if !settings.speed.is_finite() || settings.speed <= 0.0 {
+            bail!(
+                "Backtest speed should be positive, but it's {}",
+                settings.speed
+            );
+        }
+
+        let mut recording = Recording::load(&settings.recording_path)?;
+        if let Some(order_book_deltas_dir) = &settings.order_book_deltas_dir {
+            recording.load_order_book_deltas(order_book_deltas_dir)?;
+        }
+        if recording.exchange_ids().is_empty() {
+            bail!(
+                "Recording {} doesn't contain market data",
+                settings.recording_path.display()
+            );
+        }
+
+        // time of previous backtest isn't continued
+        reset_replay_clock();
+
+        Ok(Backtester {
+            settings: Arc::new(settings),
+            recording: Arc::new(recording),
+        })
+    }
+
+    pub fn engine_build_config(&self) -> EngineBuildConfig {
+        let client_builders = self
+            .recording
+            .exchange_ids()
+            .into_iter()
+            .map(|exchange_id| {
+                Box::new(BacktestClientBuilder::new(
+                    exchange_id,
+                    self.settings.clone(),
+                    self.recording.clone(),
+                )) as Box<dyn ExchangeClientBuilder>
+            })
+            .collect_vec();
+
+        EngineBuildConfig::new(client_builders)
+    }
+}
+
+impl Drop for Backtester {
+    fn drop(&mut self) {
+        reset_replay_clock();
+    }
+}
@@ -0,0 +1,961 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use url::Url;
+
+use crate::backtesting::backtester::BacktestSettings;
+use crate::backtesting::recording::Recording;
+use crate::backtesting::replay::Replay;
+use crate::connectivity::WebSocketRole;
+use crate::data_bridge::BridgeMessage;
+use crate::exchanges::common::{
+    send_event, ActivePosition, Amount, ClosedPosition, CurrencyCode, CurrencyId, CurrencyPair,
+    ExchangeAccountId, ExchangeError, ExchangeErrorType, ExchangeId, Price, SpecificCurrencyPair,
+};
+use crate::exchanges::events::{
+    AllowedEventSourceType, ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent,
+    TickDirection, Trade, TradeId,
+};
+use crate::exchanges::general::exchange::{BoxExchangeClient, RequestResult};
+use crate::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    WebSocketOptions,
+};
+use crate::exchanges::general::handlers::handle_order_filled::{FillAmount, FillEvent};
+use crate::exchanges::general::order::cancel::CancelOrderResult;
+use crate::exchanges::general::order::create::CreateOrderResult;
+use crate::exchanges::general::order::get_order_trades::OrderTrade;
+use crate::exchanges::general::paper_fills::{PaperFillSimulator, SimulatedFill};
+use crate::exchanges::general::symbol::Symbol;
+use crate::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use crate::exchanges::traits::{
+    ExchangeClient, ExchangeClientBuilder, ExchangeClientBuilderResult, HandleOrderFilledCb,
+    HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb, Support,
+};
+use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::misc::time::time_manager;
+use crate::order_book::event::{EventType, OrderBookEvent};
+use crate::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+use crate::order_book::order_book_data::OrderBookData;
+use crate::orders::fill::{EventSourceType, OrderFillType};
+use crate::orders::order::{
+    ClientOrderId, ExchangeOrderId, OrderCancelling, OrderExecutionType, OrderFillRole, OrderInfo,
+    OrderRole, OrderSide, OrderStatus, OrderType,
+};
+use crate::orders::pool::{OrderRef, OrdersPool};
+use crate::settings::ExchangeSettings;
+
+/// Order of backtested strategy on simulated exchange
+#[derive(Debug, Clone)]
+struct SimulatedOrderState {
+    currency_pair: CurrencyPair,
+    exchange_order_id: ExchangeOrderId,
+    side: OrderSide,
+    price: Price,
+    amount: Amount,
+    filled_amount: Amount,
+    filled_cost: Amount,
+    status: OrderStatus,
+}
+
+impl SimulatedOrderState {
+    fn to_order_info(&self, client_order_id: &ClientOrderId) -> OrderInfo {
+        let average_fill_price = match self.filled_amount.is_zero() {
+            true => dec!(0),
+            false => self.filled_cost / self.filled_amount,
+        };
+
+        OrderInfo::new(
+            self.currency_pair,
+            self.exchange_order_id.clone(),
+            client_order_id.clone(),
+            self.side,
+            self.status,
+            self.price,
+            self.amount,
+            average_fill_price,
+            self.filled_amount,
+            None,
+            None,
+            None,
+        )
+    }
+}
+
+/// Replayed order books, resting orders and balances of simulated exchange account
+#[derive(Default)]
+struct MatchingState {
+    order_books: HashMap<CurrencyPair, LocalOrderBookSnapshot>,
+    simulators: HashMap<CurrencyPair, PaperFillSimulator>,
+    orders: HashMap<ClientOrderId, SimulatedOrderState>,
+    balances: HashMap<CurrencyCode, Amount>,
+    last_trade_id: u64,
+}
+
+impl MatchingState {
+    /// Matches order with replayed order book immediately, rest amount of limit order is placed to
+    /// order book. Returns fill events of taken liquidity
+    fn add_order(
+        &mut self,
+        client_order_id: &ClientOrderId,
+        order: SimulatedOrderState,
+        order_type: OrderType,
+        execution_type: OrderExecutionType,
+    ) -> Result<Vec<FillEvent>, ExchangeError> {
+        let invalid_order =
+            |message: String| ExchangeError::new(ExchangeErrorType::InvalidOrder, message, None);
+
+        let snapshot = self
+            .order_books
+            .get(&order.currency_pair)
+            .ok_or_else(|| {
+                invalid_order(format!(
+                    "Order book of {} isn't replayed yet",
+                    order.currency_pair
+                ))
+            })?
+            .clone();
+
+        let limit_price = match order_type {
+            OrderType::Market => None,
+            _ => Some(order.price),
+        };
+        let taken = take_liquidity(&snapshot, order.side, order.amount, limit_price);
+        let taken_amount: Amount = taken.iter().map(|(_, amount)| amount).sum();
+
+        if execution_type == OrderExecutionType::MakerOnly && !taken.is_empty() {
+            return Err(invalid_order(
+                "Maker only order would be matched immediately".to_owned(),
+            ));
+        }
+        if order_type == OrderType::Market && taken_amount < order.amount {
+            return Err(invalid_order(format!(
+                "Order book depth {taken_amount} isn't enough for market order {}",
+                order.amount
+            )));
+        }
+
+        let rest_amount = order.amount - taken_amount;
+        if !rest_amount.is_zero() {
+            self.simulators
+                .entry(order.currency_pair)
+                .or_default()
+                .add_order(
+                    client_order_id.clone(),
+                    order.side,
+                    order.price,
+                    rest_amount,
+                    &snapshot,
+                );
+        }
+
+        let _ = self.orders.insert(client_order_id.clone(), order);
+        Ok(taken
+            .into_iter()
+            .filter_map(|(price, amount)| {
+                self.apply_fill(client_order_id, price, amount, OrderRole::Taker)
+            })
+            .collect_vec())
+    }
+
+    fn cancel_order(&mut self, client_order_id: &ClientOrderId) -> Result<(), ExchangeError> {
+        let order = match self.orders.get_mut(client_order_id) {
+            Some(order) => order,
+            None => {
+                return Err(ExchangeError::new(
+                    ExchangeErrorType::OrderNotFound,
+                    format!("Order {client_order_id} isn't found on simulated exchange"),
+                    None,
+                ))
+            }
+        };
+
+        if order.status != OrderStatus::Created {
+            return Err(ExchangeError::new(
+                ExchangeErrorType::OrderCompleted,
+                format!("Order {client_order_id} is {:?} already", order.status),
+                None,
+            ));
+        }
+
+        order.status = OrderStatus::Canceled;
+        if let Some(simulator) = self.simulators.get_mut(&order.currency_pair) {
+            let _ = simulator.cancel_order(client_order_id);
+        }
+
+        Ok(())
+    }
+
+    fn update_order_book(
+        &mut self,
+        currency_pair: CurrencyPair,
+        event_type: EventType,
+        data: &OrderBookData,
+    ) -> Vec<FillEvent> {
+        match event_type {
+            EventType::Snapshot => {
+                let _ = self
+                    .order_books
+                    .insert(currency_pair, data.to_local_order_book_snapshot());
+            }
+            EventType::Update => match self.order_books.get_mut(&currency_pair) {
+                Some(snapshot) => snapshot.apply_update(data, time_manager::now()),
+                // updates before the first snapshot can't be applied
+                None => return Vec::new(),
+            },
+        }
+
+        let fills = match (
+            self.simulators.get_mut(&currency_pair),
+            self.order_books.get(&currency_pair),
+        ) {
+            (Some(simulator), Some(snapshot)) => simulator.handle_order_book(snapshot),
+            _ => return Vec::new(),
+        };
+
+        self.apply_maker_fills(fills)
+    }
+
+    fn handle_trade(&mut self, currency_pair: CurrencyPair, trade: &Trade) -> Vec<FillEvent> {
+        let fills = match self.simulators.get_mut(&currency_pair) {
+            Some(simulator) => simulator.handle_trade(trade),
+            None => return Vec::new(),
+        };
+
+        self.apply_maker_fills(fills)
+    }
+
+    fn apply_maker_fills(&mut self, fills: Vec<SimulatedFill>) -> Vec<FillEvent> {
+        fills
+            .into_iter()
+            .filter_map(|fill| {
+                self.apply_fill(
+                    &fill.client_order_id,
+                    fill.price,
+                    fill.amount,
+                    OrderRole::Maker,
+                )
+            })
+            .collect_vec()
+    }
+
+    /// Updates order and balances by fill. Commission isn't charged
+    fn apply_fill(
+        &mut self,
+        client_order_id: &ClientOrderId,
+        price: Price,
+        amount: Amount,
+        role: OrderRole,
+    ) -> Option<FillEvent> {
+        let order = self.orders.get_mut(client_order_id)?;
+        order.filled_amount += amount;
+        order.filled_cost += price * amount;
+        if order.filled_amount >= order.amount {
+            order.status = OrderStatus::Completed;
+        }
+
+        let codes = order.currency_pair.to_codes();
+        let (base_change, quote_change) = match order.side {
+            OrderSide::Buy => (amount, -price * amount),
+            OrderSide::Sell => (-amount, price * amount),
+        };
+        *self.balances.entry(codes.base).or_default() += base_change;
+        *self.balances.entry(codes.quote).or_default() += quote_change;
+
+        self.last_trade_id += 1;
+        Some(FillEvent {
+            source_type: EventSourceType::WebSocket,
+            trade_id: Some(TradeId::Number(self.last_trade_id)),
+            client_order_id: Some(client_order_id.clone()),
+            exchange_order_id: order.exchange_order_id.clone(),
+            fill_price: price,
+            fill_amount: FillAmount::Incremental {
+                fill_amount: amount,
+                total_filled_amount: Some(order.filled_amount),
+            },
+            order_role: Some(role),
+            commission_currency_code: None,
+            commission_rate: None,
+            commission_amount: None,
+            fill_type: OrderFillType::UserTrade,
+            special_order_data: None,
+            fill_date: Some(time_manager::now()),
+        })
+    }
+}
+
+/// Price levels of order book taken by order with specified side, starting from the best price.
+/// Levels with price worse than `limit_price` aren't taken
+fn take_liquidity(
+    snapshot: &LocalOrderBookSnapshot,
+    side: OrderSide,
+    amount: Amount,
+    limit_price: Option<Price>,
+) -> Vec<(Price, Amount)> {
+    let mut rest_amount = amount;
+    let mut taken = Vec::new();
+    for (&price, &level_amount) in snapshot.get_price_levels_to_match(side) {
+        let is_acceptable = match (side, limit_price) {
+            (_, None) => true,
+            (OrderSide::Buy, Some(limit_price)) => price <= limit_price,
+            (OrderSide::Sell, Some(limit_price)) => price >= limit_price,
+        };
+        if !is_acceptable || rest_amount.is_zero() {
+            break;
+        }
+
+        let taken_amount = rest_amount.min(level_amount);
+        rest_amount -= taken_amount;
+        taken.push((price, taken_amount));
+    }
+
+    taken
+}
+
+/// Exchange client simulating exchange by recorded market data. Orders are matched immediately
+/// with replayed order book, rest amount of limit orders is filled by `PaperFillSimulator` when
+/// replayed trades or order book reach order price. Requests are delayed by configured latency
+pub struct BacktestClient {
+    id: ExchangeAccountId,
+    settings: ExchangeSettings,
+    symbols: Vec<Arc<Symbol>>,
+    replay: Arc<Replay>,
+    /// Markets which trades are recorded, so recorded fills aren't replayed as their trades
+    traded_markets: HashSet<CurrencyPair>,
+    /// Order latency in wall clock time, so it's configured one in replayed time
+    order_latency: Duration,
+    state: Mutex<MatchingState>,
+    supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    events_channel: broadcast::Sender<ExchangeEvent>,
+    lifetime_manager: Arc<AppLifetimeManager>,
+    order_created_callback: OrderCreatedCb,
+    order_cancelled_callback: OrderCancelledCb,
+    handle_order_filled_callback: HandleOrderFilledCb,
+    handle_trade_callback: HandleTradeCb,
+}
+
+impl BacktestClient {
+    pub fn new(
+        settings: ExchangeSettings,
+        backtest_settings: &BacktestSettings,
+        recording: Arc<Recording>,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+    ) -> Self {
+        let exchange_id = settings.exchange_account_id.exchange_id;
+        let state = MatchingState {
+            balances: backtest_settings.initial_balances.clone(),
+            ..MatchingState::default()
+        };
+
+        BacktestClient {
+            id: settings.exchange_account_id,
+            settings,
+            symbols: recording.symbols(exchange_id),
+            traded_markets: recording.traded_markets(exchange_id),
+            replay: Replay::new(
+                recording,
+                exchange_id,
+                backtest_settings.speed,
+                lifetime_manager.clone(),
+            ),
+            order_latency: backtest_settings
+                .order_latency
+                .div_f64(backtest_settings.speed),
+            state: Mutex::new(state),
+            supported_currencies: DashMap::new(),
+            events_channel,
+            lifetime_manager,
+            order_created_callback: Box::new(|_, _, _| {}),
+            order_cancelled_callback: Box::new(|_, _, _| {}),
+            handle_order_filled_callback: Box::new(|_| {}),
+            handle_trade_callback: Box::new(|_, _, _, _, _, _| {}),
+        }
+    }
+
+    fn handle_order_book(
+        &self,
+        currency_pair: CurrencyPair,
+        is_snapshot: bool,
+        asks: Vec<(Price, Amount)>,
+        bids: Vec<(Price, Amount)>,
+    ) -> Result<()> {
+        let event_type = match is_snapshot {
+            true => EventType::Snapshot,
+            false => EventType::Update,
+        };
+        let data = Arc::new(OrderBookData::new(
+            asks.into_iter().collect(),
+            bids.into_iter().collect(),
+        ));
+
+        let fills = self
+            .state
+            .lock()
+            .update_order_book(currency_pair, event_type, &data);
+
+        let event = OrderBookEvent::new(
+            time_manager::now(),
+            self.id,
+            currency_pair,
+            String::new(),
+            event_type,
+            data,
+        );
+        send_event(
+            &self.events_channel,
+            self.lifetime_manager.clone(),
+            self.id,
+            ExchangeEvent::OrderBookEvent(event),
+        )?;
+
+        self.report_fills(fills);
+        Ok(())
+    }
+
+    /// Replayed trades are received by engine as just happened ones
+    fn handle_trade(
+        &self,
+        currency_pair: CurrencyPair,
+        trade_id: &str,
+        price: Price,
+        quantity: Amount,
+        side: OrderSide,
+    ) {
+        let trade_id = match trade_id.parse() {
+            Ok(number) => TradeId::Number(number),
+            Err(_) => TradeId::String(trade_id.into()),
+        };
+        let trade = Trade {
+            trade_id: trade_id.clone(),
+            price,
+            quantity,
+            side,
+            transaction_time: time_manager::now(),
+            tick_direction: TickDirection::None,
+        };
+
+        (self.handle_trade_callback)(
+            currency_pair,
+            trade_id,
+            price,
+            quantity,
+            side,
+            trade.transaction_time,
+        );
+
+        let fills = self.state.lock().handle_trade(currency_pair, &trade);
+        self.report_fills(fills);
+    }
+
+    /// Fill of recorded session is a trade of exchange, so it's replayed as trade for markets
+    /// which trades weren't recorded. Taker of the trade is the opposite side of maker fill
+    fn handle_recorded_fill(
+        &self,
+        currency_pair: CurrencyPair,
+        trade_id: &str,
+        side: OrderSide,
+        role: OrderFillRole,
+        price: Price,
+        amount: Amount,
+    ) {
+        if self.traded_markets.contains(&currency_pair) {
+            return;
+        }
+
+        let taker_side = match role {
+            OrderFillRole::Taker => side,
+            OrderFillRole::Maker => side.change_side(),
+        };
+        self.handle_trade(currency_pair, trade_id, price, amount, taker_side);
+    }
+
+    fn report_fills(&self, fills: Vec<FillEvent>) {
+        for fill in fills {
+            (self.handle_order_filled_callback)(fill);
+        }
+    }
+
+    fn open_orders(&self, currency_pair: Option<CurrencyPair>) -> Vec<OrderInfo> {
+        self.state
+            .lock()
+            .orders
+            .iter()
+            .filter(|(_, order)| order.status == OrderStatus::Created)
+            .filter(|(_, order)| currency_pair.is_none_or(|x| x == order.currency_pair))
+            .map(|(client_order_id, order)| order.to_order_info(client_order_id))
+            .collect_vec()
+    }
+}
+
+#[async_trait]
+impl ExchangeClient for BacktestClient {
+    async fn create_order(&self, order: &OrderRef) -> CreateOrderResult {
+        sleep(self.order_latency).await;
+
+        let header = order.fn_ref(|x| x.header.clone());
+        let price = order.price();
+        let client_order_id = header.client_order_id.clone();
+        let exchange_order_id = ExchangeOrderId::unique_id();
+        let simulated_order = SimulatedOrderState {
+            currency_pair: header.currency_pair,
+            exchange_order_id: exchange_order_id.clone(),
+            side: header.side,
+            price,
+            amount: header.amount,
+            filled_amount: dec!(0),
+            filled_cost: dec!(0),
+            status: OrderStatus::Created,
+        };
+
+        let fills = self.state.lock().add_order(
+            &client_order_id,
+            simulated_order,
+            header.order_type,
+            header.execution_type,
+        );
+        let fills = match fills {
+            Ok(fills) => fills,
+            Err(error) => return CreateOrderResult::failed(error, EventSourceType::Rest),
+        };
+
+        (self.order_created_callback)(
+            client_order_id,
+            exchange_order_id.clone(),
+            EventSourceType::WebSocket,
+        );
+        self.report_fills(fills);
+
+        CreateOrderResult::succeed(&exchange_order_id, EventSourceType::Rest)
+    }
+
+    async fn cancel_order(&self, order: OrderCancelling) -> CancelOrderResult {
+        sleep(self.order_latency).await;
+
+        let client_order_id = order.header.client_order_id.clone();
+        if let Err(error) = self.state.lock().cancel_order(&client_order_id) {
+            return CancelOrderResult::failed(error, EventSourceType::Rest);
+        }
+
+        (self.order_cancelled_callback)(
+            client_order_id.clone(),
+            order.exchange_order_id,
+            EventSourceType::WebSocket,
+        );
+
+        CancelOrderResult::succeed(client_order_id, EventSourceType::Rest, None)
+    }
+
+    async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        sleep(self.order_latency).await;
+
+        let cancelled = {
+            let mut state = self.state.lock();
+            let open_orders = state
+                .orders
+                .iter()
+                .filter(|(_, x)| {
+                    x.currency_pair == currency_pair && x.status == OrderStatus::Created
+                })
+                .map(|(client_order_id, x)| (client_order_id.clone(), x.exchange_order_id.clone()))
+                .collect_vec();
+
+            for (client_order_id, _) in &open_orders {
+                state.cancel_order(client_order_id)?;
+            }
+            open_orders
+        };
+
+        for (client_order_id, exchange_order_id) in cancelled {
+            (self.order_cancelled_callback)(
+                client_order_id,
+                exchange_order_id,
+                EventSourceType::WebSocket,
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
+        Ok(self.open_orders(None))
+    }
+
+    async fn get_open_orders_by_currency_pair(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<Vec<OrderInfo>> {
+        Ok(self.open_orders(Some(currency_pair)))
+    }
+
+    async fn get_order_info(&self, order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
+        let client_order_id = order.client_order_id();
+        match self.state.lock().orders.get(&client_order_id) {
+            Some(simulated_order) => Ok(simulated_order.to_order_info(&client_order_id)),
+            None => Err(ExchangeError::new(
+                ExchangeErrorType::OrderNotFound,
+                format!("Order {client_order_id} isn't found on simulated exchange"),
+                None,
+            )),
+        }
+    }
+
+    async fn close_position(
+        &self,
+        _position: &ActivePosition,
+        _price: Option<Price>,
+    ) -> Result<ClosedPosition> {
+        bail!("Positions aren't supported by backtesting")
+    }
+
+    async fn get_active_positions(&self) -> Result<Vec<ActivePosition>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_balance(&self, _is_spot: bool) -> Result<ExchangeBalancesAndPositions> {
+        let balances = self
+            .state
+            .lock()
+            .balances
+            .iter()
+            .map(|(&currency_code, &balance)| ExchangeBalance {
+                currency_code,
+                balance,
+            })
+            .collect_vec();
+
+        Ok(ExchangeBalancesAndPositions {
+            balances,
+            positions: None,
+        })
+    }
+
+    async fn get_my_trades(
+        &self,
+        _symbol: &Symbol,
+        _last_date_time: Option<DateTime>,
+    ) -> Result<RequestResult<Vec<OrderTrade>>> {
+        // all fills are reported by events immediately
+        Ok(RequestResult::Success(Vec::new()))
+    }
+
+    async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>> {
+        Ok(self.symbols.clone())
+    }
+}
+
+#[async_trait]
+impl Support for BacktestClient {
+    fn on_websocket_message(&self, msg: &str) -> Result<()> {
+        let message: BridgeMessage =
+            serde_json::from_str(msg).context("Unable to parse replayed message")?;
+
+        match message {
+            BridgeMessage::OrderBook {
+                currency_pair,
+                is_snapshot,
+                asks,
+                bids,
+                ..
+            } => self.handle_order_book(currency_pair, is_snapshot, asks, bids)?,
+            // bad prints are excluded from trades used by trading logic
+            BridgeMessage::Trade { is_bad_print, .. } if is_bad_print => {}
+            BridgeMessage::Trade {
+                currency_pair,
+                trade_id,
+                price,
+                quantity,
+                side,
+                ..
+            } => self.handle_trade(currency_pair, &trade_id, price, quantity, side),
+            BridgeMessage::Fill {
+                currency_pair,
+                client_order_id,
+                side,
+                role,
+                price,
+                amount,
+                time,
+                ..
+            } => {
+                let trade_id =
+                    format!("recorded_fill_{client_order_id}_{}", time.timestamp_nanos());
+                self.handle_recorded_fill(currency_pair, &trade_id, side, role, price, amount)
+            }
+        }
+
+        Ok(())
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_send_websocket_message_callback(&self, _callback: SendWebsocketMessageCb) {}
+
+    fn set_order_created_callback(&mut self, callback: OrderCreatedCb) {
+        self.order_created_callback = callback;
+    }
+
+    fn set_order_cancelled_callback(&mut self, callback: OrderCancelledCb) {
+        self.order_cancelled_callback = callback;
+    }
+
+    fn set_handle_order_filled_callback(&mut self, callback: HandleOrderFilledCb) {
+        self.handle_order_filled_callback = callback;
+    }
+
+    fn set_handle_trade_callback(&mut self, callback: HandleTradeCb) {
+        self.handle_trade_callback = callback;
+    }
+
+    fn set_traded_specific_currencies(&self, _currencies: Vec<SpecificCurrencyPair>) {}
+
+    fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
+        role == WebSocketRole::Main
+    }
+
+    async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url> {
+        if role != WebSocketRole::Main {
+            bail!("Websocket {role:?} isn't used by backtesting");
+        }
+
+        self.replay.start_server().await
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        currency_pair.as_str().into()
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        &self.supported_currencies
+    }
+
+    fn should_log_message(&self, _message: &str) -> bool {
+        false
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        &self.settings
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Builder of simulated exchange clients for recorded exchange. It replaces builder of connector
+/// of the exchange, so exchange accounts from settings are backtested without changes
+pub struct BacktestClientBuilder {
+    exchange_id: ExchangeId,
+    settings: Arc<BacktestSettings>,
+    recording: Arc<Recording>,
+}
+
+impl BacktestClientBuilder {
+    pub fn new(
+        exchange_id: ExchangeId,
+        settings: Arc<BacktestSettings>,
+        recording: Arc<Recording>,
+    ) -> Self {
+        BacktestClientBuilder {
+            exchange_id,
+            settings,
+            recording,
+        }
+    }
+}
+
+impl ExchangeClientBuilder for BacktestClientBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+        _orders: Arc<OrdersPool>,
+    ) -> ExchangeClientBuilderResult {
+        let client = BacktestClient::new(
+            exchange_settings,
+            &self.settings,
+            self.recording.clone(),
+            events_channel,
+            lifetime_manager,
+        );
+
+        ExchangeClientBuilderResult {
+            client: Box::new(client) as BoxExchangeClient,
+            features: ExchangeFeatures::new(
+                OpenOrdersType::AllCurrencyPair,
+                RestFillsFeatures::default(),
+                OrderFeatures {
+                    supports_get_order_info_by_client_order_id: true,
+                    ..OrderFeatures::default()
+                },
+                OrderTradeOption::default(),
+                WebSocketOptions::default(),
+                false,
+                AllowedEventSourceType::All,
+                AllowedEventSourceType::All,
+                AllowedEventSourceType::All,
+            ),
+        }
+    }
+
+    /// Limits of typical exchange, so strategy is throttled as in live trading
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        RequestTimeoutArguments::from_requests_per_minute(1200)
+    }
+
+    fn get_exchange_id(&self) -> ExchangeId {
+        self.exchange_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book_data;
+    use chrono::Utc;
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    fn order(side: OrderSide, price: Price, amount: Amount) -> SimulatedOrderState {
+        SimulatedOrderState {
+            currency_pair: currency_pair(),
+            exchange_order_id: ExchangeOrderId::unique_id(),
+            side,
+            price,
+            amount,
+            filled_amount: dec!(0),
+            filled_cost: dec!(0),
+            status: OrderStatus::Created,
+        }
+    }
+
+    fn state() -> MatchingState {
+        let mut state = MatchingState::default();
+        let order_book: OrderBookData = order_book_data![
+            dec!(102) => dec!(1),
+            dec!(101) => dec!(1),
+            ;
+            dec!(100) => dec!(2),
+        ];
+        let _ = state.update_order_book(currency_pair(), EventType::Snapshot, &order_book);
+        state
+    }
+
+    fn filled_amounts(fills: &[FillEvent]) -> Vec<(Price, Option<Amount>)> {
+        fills
+            .iter()
+            .map(|x| (x.fill_price, x.fill_amount.total_filled_amount()))
+            .collect()
+    }
+
+    #[test]
+    fn crossing_limit_order_takes_liquidity_up_to_its_price() {
+        let mut state = state();
+        let client_order_id = ClientOrderId::unique_id();
+
+        let fills = state
+            .add_order(
+                &client_order_id,
+                order(OrderSide::Buy, dec!(101), dec!(1.5)),
+                OrderType::Limit,
+                OrderExecutionType::None,
+            )
+            .expect("in test");
+
+        assert_eq!(filled_amounts(&fills), vec![(dec!(101), Some(dec!(1)))]);
+        assert_eq!(fills[0].order_role, Some(OrderRole::Taker));
+        assert_eq!(state.balances[&CurrencyCode::from("btc")], dec!(1));
+        assert_eq!(state.balances[&CurrencyCode::from("usdt")], dec!(-101));
+
+        // rest amount is filled as maker when order book crosses order price
+        let crossed: OrderBookData = order_book_data![
+            dec!(100.5) => dec!(3),
+            ;
+        ];
+        let fills = state.update_order_book(currency_pair(), EventType::Update, &crossed);
+        assert_eq!(filled_amounts(&fills), vec![(dec!(101), Some(dec!(1.5)))]);
+        assert_eq!(fills[0].order_role, Some(OrderRole::Maker));
+        assert_eq!(
+            state.orders[&client_order_id].status,
+            OrderStatus::Completed
+        );
+    }
+
+    #[test]
+    fn orders_are_rejected_by_simulated_exchange() {
+        let mut state = state();
+
+        let result = state.add_order(
+            &ClientOrderId::unique_id(),
+            order(OrderSide::Sell, dec!(99), dec!(1)),
+            OrderType::Limit,
+            OrderExecutionType::MakerOnly,
+        );
+        assert_eq!(
+            result.expect_err("in test").error_type,
+            ExchangeErrorType::InvalidOrder
+        );
+
+        let result = state.add_order(
+            &ClientOrderId::unique_id(),
+            order(OrderSide::Buy, dec!(0), dec!(3)),
+            OrderType::Market,
+            OrderExecutionType::None,
+        );
+        assert_eq!(
+            result.expect_err("in test").error_type,
+            ExchangeErrorType::InvalidOrder
+        );
+        assert!(state.orders.is_empty());
+    }
+
+    #[test]
+    fn cancelled_order_is_not_filled() {
+        let mut state = state();
+        let client_order_id = ClientOrderId::unique_id();
+        let fills = state
+            .add_order(
+                &client_order_id,
+                order(OrderSide::Sell, dec!(103), dec!(1)),
+                OrderType::Limit,
+                OrderExecutionType::MakerOnly,
+            )
+            .expect("in test");
+        assert!(fills.is_empty());
+
+        state.cancel_order(&client_order_id).expect("in test");
+        assert_eq!(
+            state
+                .cancel_order(&client_order_id)
+                .expect_err("in test")
+                .error_type,
+            ExchangeErrorType::OrderCompleted
+        );
+
+        let trade = Trade {
+            trade_id: TradeId::Number(1),
+            price: dec!(104),
+            quantity: dec!(5),
+            side: OrderSide::Buy,
+            transaction_time: Utc::now(),
+            tick_direction: TickDirection::None,
+        };
+        assert!(state.handle_trade(currency_pair(), &trade).is_empty());
+    }
+}
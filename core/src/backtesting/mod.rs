@@ -0,0 +1,4 @@
+pub mod backtester;
+pub mod client;
+pub mod recording;
+mod replay;
@@ -0,0 +1,288 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+
+use crate::data_bridge::BridgeMessage;
+use crate::exchanges::common::{CurrencyPair, ExchangeId};
+use crate::exchanges::general::symbol::{Precision, Symbol};
+use crate::order_book::delta_codec::OrderBookDeltaReader;
+
+/// Recorded market data message with its original time
+#[derive(Debug, Clone)]
+pub struct RecordedMessage {
+    pub time: DateTime,
+    pub message: BridgeMessage,
+    /// Message as it was recorded, so it's replayed without serialization
+    pub raw: String,
+}
+
+/// Market data recorded from `DataBridge` as JSON lines, one message per line. Fills of recorded
+/// session are replayed as exchange trades of markets which trades weren't recorded, because
+/// orders of backtested strategy are filled by simulated matching
+#[derive(Debug, Default)]
+pub struct Recording {
+    messages: HashMap<ExchangeId, Vec<RecordedMessage>>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Unable to read recording {}", path.display()))?;
+
+        Self::parse(&content).with_context(|| format!("Invalid recording {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut recording = Recording::default();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let message: BridgeMessage = serde_json::from_str(line)
+                .with_context(|| format!("Unable to parse line {} of recording", index + 1))?;
+
+            let (exchange_account_id, time) = match &message {
+                BridgeMessage::OrderBook {
+                    exchange_account_id,
+                    time,
+                    ..
+                }
+                | BridgeMessage::Trade {
+                    exchange_account_id,
+                    time,
+                    ..
+                }
+                | BridgeMessage::Fill {
+                    exchange_account_id,
+                    time,
+                    ..
+                } => (*exchange_account_id, *time),
+            };
+
+            recording
+                .messages
+                .entry(exchange_account_id.exchange_id)
+                .or_default()
+                .push(RecordedMessage {
+                    time,
+                    message,
+                    raw: line.to_owned(),
+                });
+        }
+
+        // messages of different connections can be written out of order
+        for messages in recording.messages.values_mut() {
+            messages.sort_by_key(|x| x.time);
+        }
+
+        Ok(recording)
+    }
+
+    /// Adds order books recorded by `OrderBookDeltaRecorder` to files `*.obd` of directory
+    pub fn load_order_book_deltas(&mut self, directory: &Path) -> Result<()> {
+        let entries = std::fs::read_dir(directory)
+            .with_context(|| format!("Unable to read directory {}", directory.display()))?;
+        for entry in entries {
+            let path = entry
+                .with_context(|| format!("Unable to read directory {}", directory.display()))?
+                .path();
+            if path.extension().is_none_or(|x| x != "obd") {
+                continue;
+            }
+
+            let data = std::fs::read(&path)
+                .with_context(|| format!("Unable to read order book deltas {}", path.display()))?;
+            let reader = OrderBookDeltaReader::from_file(&data)
+                .with_context(|| format!("Invalid order book deltas {}", path.display()))?;
+            for event in reader {
+                let event = event
+                    .with_context(|| format!("Invalid order book deltas {}", path.display()))?;
+                let message = BridgeMessage::from_order_book(&event);
+                let raw = serde_json::to_string(&message)
+                    .context("Unable to serialize recorded order book")?;
+                self.messages
+                    .entry(event.exchange_account_id.exchange_id)
+                    .or_default()
+                    .push(RecordedMessage {
+                        time: event.creation_time,
+                        message,
+                        raw,
+                    });
+            }
+        }
+
+        for messages in self.messages.values_mut() {
+            messages.sort_by_key(|x| x.time);
+        }
+
+        Ok(())
+    }
+
+    pub fn exchange_ids(&self) -> Vec<ExchangeId> {
+        self.messages.keys().copied().collect()
+    }
+
+    /// Time of the first recorded message of all exchanges
+    pub fn start_time(&self) -> Option<DateTime> {
+        self.messages
+            .values()
+            .filter_map(|messages| messages.first())
+            .map(|x| x.time)
+            .min()
+    }
+
+    /// Markets of exchange which trades are recorded
+    pub fn traded_markets(&self, exchange_id: ExchangeId) -> HashSet<CurrencyPair> {
+        self.messages(exchange_id)
+            .iter()
+            .filter_map(|recorded| match &recorded.message {
+                BridgeMessage::Trade { currency_pair, .. } => Some(*currency_pair),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn messages(&self, exchange_id: ExchangeId) -> &[RecordedMessage] {
+        self.messages
+            .get(&exchange_id)
+            .map(|x| x.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Spot symbols of recorded markets of exchange. Ticks of price and amount are the smallest
+    /// ones which all recorded values are multiple of
+    pub fn symbols(&self, exchange_id: ExchangeId) -> Vec<Arc<Symbol>> {
+        let mut scales = HashMap::<CurrencyPair, (u32, u32)>::new();
+        let mut register = |currency_pair, price: &Decimal, amount: &Decimal| {
+            let (price_scale, amount_scale) = scales.entry(currency_pair).or_default();
+            *price_scale = (*price_scale).max(price.normalize().scale());
+            *amount_scale = (*amount_scale).max(amount.normalize().scale());
+        };
+
+        for recorded in self.messages(exchange_id) {
+            match &recorded.message {
+                BridgeMessage::OrderBook {
+                    currency_pair,
+                    asks,
+                    bids,
+                    ..
+                } => asks
+                    .iter()
+                    .chain(bids)
+                    .filter(|(_, amount)| !amount.is_zero())
+                    .for_each(|(price, amount)| register(*currency_pair, price, amount)),
+                BridgeMessage::Trade {
+                    currency_pair,
+                    price,
+                    quantity,
+                    ..
+                } => register(*currency_pair, price, quantity),
+                BridgeMessage::Fill {
+                    currency_pair,
+                    price,
+                    amount,
+                    ..
+                } => register(*currency_pair, price, amount),
+            }
+        }
+
+        scales
+            .into_iter()
+            .sorted_by(|(x, _), (y, _)| x.as_str().cmp(y.as_str()))
+            .map(|(currency_pair, (price_scale, amount_scale))| {
+                let codes = currency_pair.to_codes();
+                Arc::new(Symbol::new(
+                    true,
+                    false,
+                    codes.base.as_str().into(),
+                    codes.base,
+                    codes.quote.as_str().into(),
+                    codes.quote,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    codes.base,
+                    None,
+                    Precision::ByTick {
+                        tick: Decimal::new(1, price_scale),
+                    },
+                    Precision::ByTick {
+                        tick: Decimal::new(1, amount_scale),
+                    },
+                ))
+            })
+            .collect_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    const RECORDING: &str = r#"
+{"type":"order_book","exchange_account_id":"Binance_0","currency_pair":"btc/usdt","is_snapshot":true,"asks":[["101.5","0.25"]],"bids":[["100","2"]],"time":"2022-03-01T10:00:01Z"}
+{"type":"fill","exchange_account_id":"Binance_0","currency_pair":"btc/usdt","client_order_id":"1","side":"Buy","role":"Maker","price":"100","amount":"1","time":"2022-03-01T10:00:02Z"}
+{"type":"trade","exchange_account_id":"Binance_0","currency_pair":"btc/usdt","trade_id":"7","price":"101.25","quantity":"0.5","side":"Buy","time":"2022-03-01T10:00:00Z","is_bad_print":false}
+"#;
+
+    #[test]
+    fn recording_is_parsed_with_fills() {
+        let recording = Recording::parse(RECORDING).expect("in test");
+
+        let exchange_id = "Binance".into();
+        assert_eq!(recording.exchange_ids(), vec![exchange_id]);
+
+        let messages = recording.messages(exchange_id);
+        assert_eq!(messages.len(), 3);
+        // messages are ordered by recorded time
+        assert!(matches!(messages[0].message, BridgeMessage::Trade { .. }));
+        assert!(matches!(
+            messages[1].message,
+            BridgeMessage::OrderBook { .. }
+        ));
+        assert!(matches!(messages[2].message, BridgeMessage::Fill { .. }));
+        assert_eq!(recording.start_time(), Some(messages[0].time));
+        assert_eq!(
+            recording.traded_markets(exchange_id),
+            HashSet::from([CurrencyPair::from_codes("btc".into(), "usdt".into())])
+        );
+        assert!(recording.messages("Bitmex".into()).is_empty());
+    }
+
+    #[test]
+    fn symbol_precisions_are_inferred_from_recorded_values() {
+        let recording = Recording::parse(RECORDING).expect("in test");
+
+        let symbols = recording.symbols("Binance".into());
+        assert_eq!(symbols.len(), 1);
+        let symbol = &symbols[0];
+        assert_eq!(
+            symbol.currency_pair(),
+            CurrencyPair::from_codes("btc".into(), "usdt".into())
+        );
+        assert_eq!(
+            symbol.price_precision,
+            Precision::ByTick { tick: dec!(0.01) }
+        );
+        assert_eq!(
+            symbol.amount_precision,
+            Precision::ByTick { tick: dec!(0.01) }
+        );
+    }
+
+    #[test]
+    fn invalid_line_is_reported() {
+        let error = Recording::parse("{\"type\":\"unknown\"}").expect_err("in test");
+        assert!(error.to_string().contains("line 1"));
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use futures::{SinkExt, StreamExt};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{sleep_until, Instant};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+use url::Url;
+
+use crate::backtesting::recording::Recording;
+use crate::exchanges::common::ExchangeId;
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::misc::time::advance_replay_clock;
+
+/// Streams recorded market data of exchange to simulated exchange client through loopback
+/// websocket, so replayed messages pass the same connectivity and parsing path as live ones.
+/// Intervals between messages are recorded ones divided by speed, engine time follows recorded
+/// time of sent messages. Replay continues from the last sent message after reconnection.
+/// Engine is stopped when recording is replayed
+pub(crate) struct Replay {
+    recording: Arc<Recording>,
+    exchange_id: ExchangeId,
+    speed: f64,
+    position: AtomicUsize,
+    lifetime_manager: Arc<AppLifetimeManager>,
+}
+
+impl Replay {
+    pub fn new(
+        recording: Arc<Recording>,
+        exchange_id: ExchangeId,
+        speed: f64,
+        lifetime_manager: Arc<AppLifetimeManager>,
+    ) -> Arc<Self> {
+        Arc::new(Replay {
+            recording,
+            exchange_id,
+            speed,
+            position: AtomicUsize::new(0),
+            lifetime_manager,
+        })
+    }
+
+    /// Starts server for one connection and returns its url
+    pub async fn start_server(self: &Arc<Self>) -> Result<Url> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("Unable to bind backtest replay server")?;
+        let address = listener
+            .local_addr()
+            .context("Unable to get address of backtest replay server")?;
+
+        let _ = spawn_future(
+            "Backtest replay",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            self.clone().serve(listener),
+        );
+
+        Url::parse(&format!("ws://{address}")).context("Invalid url of backtest replay server")
+    }
+
+    async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<()> {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .context("Unable to accept connection to backtest replay server")?;
+        let mut websocket = tokio_tungstenite::accept_async(stream)
+            .await
+            .context("Backtest replay websocket handshake failed")?;
+
+        let position = self.position.load(Ordering::SeqCst);
+        let messages = &self.recording.messages(self.exchange_id)[position..];
+        let started_at = Instant::now();
+        // replays of all exchanges are paced from the start of recording, so their messages are
+        // interleaved as they were recorded. Replay after reconnection continues without delay
+        let first_time = match position {
+            0 => self.recording.start_time(),
+            _ => messages.first().map(|x| x.time),
+        };
+        for recorded in messages {
+            let recorded_delay = first_time
+                .and_then(|first_time| (recorded.time - first_time).to_std().ok())
+                .unwrap_or_default();
+            let send_at = started_at + recorded_delay.div_f64(self.speed);
+            if !wait_keeping_connection(&mut websocket, send_at).await? {
+                log::info!(
+                    "Backtest replay connection of {} is closed",
+                    self.exchange_id
+                );
+                return Ok(());
+            }
+
+            advance_replay_clock(recorded.time, self.speed);
+            websocket
+                .send(Message::Text(recorded.raw.clone()))
+                .await
+                .context("Unable to send replayed message")?;
+            let _ = self.position.fetch_add(1, Ordering::SeqCst);
+        }
+
+        log::info!("Recording of {} is replayed", self.exchange_id);
+        let _ = self
+            .lifetime_manager
+            .spawn_graceful_shutdown("Backtest recording is replayed");
+
+        // connection is kept until engine is stopped, so engine doesn't reconnect to finished replay
+        while let Some(incoming) = websocket.next().await {
+            match incoming {
+                Ok(Message::Close(_)) => break,
+                Err(err) => return Err(err).context("Backtest replay connection error"),
+                Ok(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Waits until `deadline` while incoming messages are read, so pings of engine are answered.
+/// Returns false if connection is closed by engine
+async fn wait_keeping_connection(
+    websocket: &mut WebSocketStream<TcpStream>,
+    deadline: Instant,
+) -> Result<bool> {
+    loop {
+        tokio::select! {
+            _ = sleep_until(deadline) => return Ok(true),
+            incoming = websocket.next() => match incoming {
+                None | Some(Ok(Message::Close(_))) => return Ok(false),
+                Some(Err(err)) => return Err(err).context("Backtest replay connection error"),
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
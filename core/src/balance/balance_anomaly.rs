@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use rust_decimal_macros::dec;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::database::audit_log::AuditLog;
+use crate::exchanges::common::{
+    Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, MarketAccountId,
+};
+use crate::exchanges::events::{ExchangeBalance, ExchangeEvent};
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::orders::event::OrderEventType;
+use crate::orders::fill::OrderFill;
+use crate::orders::order::OrderSide;
+use crate::orders::reduce_only::ReduceOnlyReason;
+use crate::settings::BalanceAnomalySettings;
+
+/// Balance change of currency reported by exchange which doesn't match balance changes by fills,
+/// e.g. because of missing fill, double fill or unexpected fee
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceAnomaly {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    /// Accumulated difference between balance changes reported by exchange and expected by fills
+    pub discrepancy: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BalanceAnomalyStatus {
+    Detected(BalanceAnomaly),
+    Resolved(BalanceAnomaly),
+}
+
+#[derive(Debug, Default)]
+struct CurrencyBalanceState {
+    reported_balance: Option<Amount>,
+    /// Balance change by fills since the last balance reported by exchange
+    expected_delta: Amount,
+    discrepancy: Amount,
+    mismatched_updates: u32,
+    is_anomaly: bool,
+}
+
+/// Compares balance deltas reported by exchange with deltas expected by fills between balance updates
+pub struct BalanceDeltaTracker {
+    tolerance: Percent,
+    confirmation_updates: u32,
+    states: HashMap<(ExchangeAccountId, CurrencyCode), CurrencyBalanceState>,
+}
+
+impl BalanceDeltaTracker {
+    pub fn new(settings: &BalanceAnomalySettings) -> Self {
+        BalanceDeltaTracker {
+            tolerance: settings.tolerance,
+            confirmation_updates: settings.confirmation_updates.max(1),
+            states: HashMap::new(),
+        }
+    }
+
+    pub fn register_delta(
+        &mut self,
+        exchange_account_id: ExchangeAccountId,
+        currency_code: CurrencyCode,
+        delta: Amount,
+    ) {
+        self.states
+            .entry((exchange_account_id, currency_code))
+            .or_default()
+            .expected_delta += delta;
+    }
+
+    pub fn update_exchange_balances(
+        &mut self,
+        exchange_account_id: ExchangeAccountId,
+        balances: &[ExchangeBalance],
+    ) -> Vec<BalanceAnomalyStatus> {
+        let mut statuses = vec![];
+        for balance in balances {
+            let state = self
+                .states
+                .entry((exchange_account_id, balance.currency_code))
+                .or_default();
+
+            if let Some(previous_balance) = state.reported_balance {
+                let reported_delta = balance.balance - previous_balance;
+                state.discrepancy += reported_delta - state.expected_delta;
+            }
+            state.reported_balance = Some(balance.balance);
+            state.expected_delta = dec!(0);
+
+            let max_discrepancy = balance.balance.abs() * self.tolerance / dec!(100);
+            let anomaly = BalanceAnomaly {
+                exchange_account_id,
+                currency_code: balance.currency_code,
+                discrepancy: state.discrepancy,
+            };
+            if state.discrepancy.abs() > max_discrepancy {
+                state.mismatched_updates += 1;
+                if state.mismatched_updates >= self.confirmation_updates && !state.is_anomaly {
+                    state.is_anomaly = true;
+                    statuses.push(BalanceAnomalyStatus::Detected(anomaly));
+                }
+            } else {
+                state.discrepancy = dec!(0);
+                state.mismatched_updates = 0;
+                if state.is_anomaly {
+                    state.is_anomaly = false;
+                    statuses.push(BalanceAnomalyStatus::Resolved(anomaly));
+                }
+            }
+        }
+
+        statuses
+    }
+}
+
+/// Balance changes of spot market currencies by fill
+pub fn fill_balance_deltas(
+    currency_pair: CurrencyPair,
+    side: OrderSide,
+    fill: &OrderFill,
+) -> Vec<(CurrencyCode, Amount)> {
+    let codes = currency_pair.to_codes();
+    let quote_amount = fill.price() * fill.amount();
+    let (base_delta, quote_delta) = match side {
+        OrderSide::Buy => (fill.amount(), -quote_amount),
+        OrderSide::Sell => (-fill.amount(), quote_amount),
+    };
+
+    vec![
+        (codes.base, base_delta),
+        (codes.quote, quote_delta),
+        (fill.commission_currency_code(), -fill.commission_amount()),
+    ]
+}
+
+/// Alerts about balance anomalies, records them as incidents to audit log and optionally moves
+/// markets of anomalous currency to reduce-only mode until anomaly is resolved
+pub(crate) fn start_balance_anomaly_detection(
+    settings: &BalanceAnomalySettings,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    audit_log: Option<Arc<AuditLog>>,
+    events_receiver: broadcast::Receiver<ExchangeEvent>,
+) {
+    let _ = spawn_future(
+        "Balance anomaly detection",
+        SpawnFutureFlags::STOP_BY_TOKEN,
+        detect_balance_anomalies(settings.clone(), exchanges, audit_log, events_receiver),
+    );
+}
+
+async fn detect_balance_anomalies(
+    settings: BalanceAnomalySettings,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    audit_log: Option<Arc<AuditLog>>,
+    mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+) -> Result<()> {
+    let mut tracker = BalanceDeltaTracker::new(&settings);
+
+    loop {
+        let event = match events_receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                log::warn!("Balance anomaly detection skipped {skipped} exchange events");
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+
+        match event {
+            ExchangeEvent::OrderEvent(event) => {
+                let cloned_order = match &event.event_type {
+                    OrderEventType::OrderFilled { cloned_order } => cloned_order.clone(),
+                    _ => continue,
+                };
+                let header = &cloned_order.header;
+                let exchange = match exchanges.get(&header.exchange_account_id) {
+                    Some(exchange) => exchange.clone(),
+                    None => continue,
+                };
+                // balances of derivative markets are changed by positions
+                let is_spot = exchange
+                    .symbols
+                    .get(&header.currency_pair)
+                    .is_some_and(|x| !x.is_derivative());
+                let fill = match cloned_order.fills.fills.last() {
+                    Some(fill) if is_spot => fill,
+                    _ => continue,
+                };
+
+                for (currency_code, delta) in
+                    fill_balance_deltas(header.currency_pair, header.side, fill)
+                {
+                    tracker.register_delta(header.exchange_account_id, currency_code, delta);
+                }
+            }
+            ExchangeEvent::BalanceUpdate(event) => {
+                for status in tracker.update_exchange_balances(
+                    event.exchange_account_id,
+                    &event.balances_and_positions.balances,
+                ) {
+                    handle_anomaly_status(&settings, &exchanges, audit_log.as_deref(), status);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn handle_anomaly_status(
+    settings: &BalanceAnomalySettings,
+    exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+    audit_log: Option<&AuditLog>,
+    status: BalanceAnomalyStatus,
+) {
+    let (anomaly, is_detected) = match &status {
+        BalanceAnomalyStatus::Detected(anomaly) => (anomaly, true),
+        BalanceAnomalyStatus::Resolved(anomaly) => (anomaly, false),
+    };
+    let BalanceAnomaly {
+        exchange_account_id,
+        currency_code,
+        discrepancy,
+    } = anomaly;
+
+    let details = match is_detected {
+        true => {
+            log::error!("Balance of {currency_code} on {exchange_account_id} differs from balance expected by fills by {discrepancy}");
+            format!("{currency_code} on {exchange_account_id}: discrepancy {discrepancy}")
+        }
+        false => {
+            log::warn!("Balance anomaly of {currency_code} on {exchange_account_id} is resolved");
+            format!("{currency_code} on {exchange_account_id}: resolved")
+        }
+    };
+    if let Some(audit_log) = audit_log {
+        audit_log.record_incident("balance_anomaly", details);
+    }
+
+    if !settings.reduce_only_on_anomaly {
+        return;
+    }
+
+    let exchange = match exchanges.get(exchange_account_id) {
+        Some(exchange) => exchange.clone(),
+        None => return,
+    };
+    let reduce_only_mode = exchange.reduce_only_mode();
+    for symbol in exchange.symbols.iter() {
+        if symbol.base_currency_code() != *currency_code
+            && symbol.quote_currency_code() != *currency_code
+        {
+            continue;
+        }
+
+        let reason = ReduceOnlyReason::BalanceAnomaly(MarketAccountId::new(
+            *exchange_account_id,
+            symbol.currency_pair(),
+        ));
+        match is_detected {
+            true => reduce_only_mode.enable(reason),
+            false => reduce_only_mode.disable(reason),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> BalanceDeltaTracker {
+        BalanceDeltaTracker::new(&BalanceAnomalySettings {
+            tolerance: dec!(0.1),
+            confirmation_updates: 2,
+            reduce_only_on_anomaly: false,
+        })
+    }
+
+    fn balance(amount: Amount) -> Vec<ExchangeBalance> {
+        vec![ExchangeBalance {
+            currency_code: "btc".into(),
+            balance: amount,
+        }]
+    }
+
+    #[test]
+    pub fn late_fill_is_not_anomaly() {
+        let mut tracker = tracker();
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+
+        assert_eq!(
+            tracker.update_exchange_balances(exchange_account_id, &balance(dec!(10))),
+            vec![]
+        );
+        // balance is updated before fill is received
+        assert_eq!(
+            tracker.update_exchange_balances(exchange_account_id, &balance(dec!(11))),
+            vec![]
+        );
+        tracker.register_delta(exchange_account_id, "btc".into(), dec!(1));
+        assert_eq!(
+            tracker.update_exchange_balances(exchange_account_id, &balance(dec!(11))),
+            vec![]
+        );
+    }
+
+    #[test]
+    pub fn missing_fill_is_detected_and_resolved() {
+        let mut tracker = tracker();
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        let anomaly = |discrepancy| BalanceAnomaly {
+            exchange_account_id,
+            currency_code: "btc".into(),
+            discrepancy,
+        };
+
+        let _ = tracker.update_exchange_balances(exchange_account_id, &balance(dec!(10)));
+        let _ = tracker.update_exchange_balances(exchange_account_id, &balance(dec!(9)));
+        assert_eq!(
+            tracker.update_exchange_balances(exchange_account_id, &balance(dec!(9))),
+            vec![BalanceAnomalyStatus::Detected(anomaly(dec!(-1)))]
+        );
+
+        tracker.register_delta(exchange_account_id, "btc".into(), dec!(-1));
+        assert_eq!(
+            tracker.update_exchange_balances(exchange_account_id, &balance(dec!(9))),
+            vec![BalanceAnomalyStatus::Resolved(anomaly(dec!(0)))]
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+
+use rust_decimal_macros::dec;
+
+use crate::exchanges::common::{Amount, CurrencyCode, ExchangeAccountId, Price};
+use crate::exchanges::general::symbol::{BeforeAfter, Symbol};
+use crate::explanation::{Explanation, OptionExplanationAddReasonExt};
+use crate::misc::reserve_parameters::ReserveParameters;
+use crate::orders::order::OrderSide;
+use crate::service_configuration::configuration_descriptor::{
+    ConfigurationDescriptor, ServiceName,
+};
+use crate::settings::CoreSettings;
+
+/// Sub-balances of strategies by exchange accounts
+pub type CapitalSubBalances =
+    HashMap<(ServiceName, ExchangeAccountId), HashMap<CurrencyCode, Amount>>;
+
+/// Virtual sub-balances of strategies with allocated capital. Sub-balance starts from allocated
+/// amounts on engine start and is changed by fills of strategy, so strategy can't reserve more than
+/// it owns even if shared exchange balance allows it.
+/// Only spot markets are supported: reservations on derivative markets aren't limited
+#[derive(Debug, Default, Clone)]
+pub(crate) struct CapitalAllocations {
+    sub_balances: CapitalSubBalances,
+}
+
+impl CapitalAllocations {
+    pub fn new(settings: &CoreSettings) -> Self {
+        CapitalAllocations {
+            sub_balances: settings
+                .strategy_capital_allocations
+                .iter()
+                .map(|x| {
+                    let balances = x
+                        .currencies
+                        .iter()
+                        .map(|currency| (currency.currency_code, currency.amount))
+                        .collect();
+                    ((x.service_name, x.exchange_account_id), balances)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sub_balances.is_empty()
+    }
+
+    pub fn get_sub_balances(&self) -> &CapitalSubBalances {
+        &self.sub_balances
+    }
+
+    /// Restores sub-balances changed by fills before restart. Strategies which capital isn't
+    /// allocated to anymore are skipped, newly allocated strategies keep amounts from settings
+    pub fn restore_sub_balances(&mut self, sub_balances: &CapitalSubBalances) {
+        for (key, balances) in sub_balances {
+            if let Some(current) = self.sub_balances.get_mut(key) {
+                *current = balances.clone();
+            }
+        }
+    }
+
+    /// Returns `None` if capital isn't allocated to strategy on exchange account
+    pub fn get_sub_balance(
+        &self,
+        configuration_descriptor: ConfigurationDescriptor,
+        exchange_account_id: ExchangeAccountId,
+        currency_code: CurrencyCode,
+    ) -> Option<Amount> {
+        self.sub_balances
+            .get(&(configuration_descriptor.service_name, exchange_account_id))
+            .map(|x| x.get(&currency_code).copied().unwrap_or(dec!(0)))
+    }
+
+    /// Fill moves capital of strategy from currency spent to currency received, commission is
+    /// subtracted from sub-balance of commission currency
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_fill(
+        &mut self,
+        configuration_descriptor: ConfigurationDescriptor,
+        exchange_account_id: ExchangeAccountId,
+        symbol: &Symbol,
+        side: OrderSide,
+        amount: Amount,
+        price: Price,
+        commission_currency_code: CurrencyCode,
+        commission_amount: Amount,
+    ) {
+        if symbol.is_derivative() {
+            return;
+        }
+
+        let balances = match self
+            .sub_balances
+            .get_mut(&(configuration_descriptor.service_name, exchange_account_id))
+        {
+            Some(balances) => balances,
+            None => return,
+        };
+
+        let spent_currency_code = symbol.get_trade_code(side, BeforeAfter::Before);
+        let received_currency_code = symbol.get_trade_code(side, BeforeAfter::After);
+        let spent =
+            symbol.convert_amount_from_amount_currency_code(spent_currency_code, amount, price);
+        let received =
+            symbol.convert_amount_from_amount_currency_code(received_currency_code, amount, price);
+
+        *balances.entry(spent_currency_code).or_default() -= spent;
+        *balances.entry(received_currency_code).or_default() += received;
+        *balances.entry(commission_currency_code).or_default() -= commission_amount;
+    }
+
+    /// Check that strategy has enough capital for reservation. `reserved` is amount already reserved
+    /// by strategy in reservation currency
+    pub fn can_reserve(
+        &self,
+        reserve_parameters: &ReserveParameters,
+        reserved: Amount,
+        explanation: &mut Option<Explanation>,
+    ) -> bool {
+        let symbol = &reserve_parameters.symbol;
+        if symbol.is_derivative() {
+            return true;
+        }
+
+        let currency_code = reservation_currency_code(reserve_parameters);
+        let sub_balance = match self.get_sub_balance(
+            reserve_parameters.configuration_descriptor,
+            reserve_parameters.exchange_account_id,
+            currency_code,
+        ) {
+            Some(sub_balance) => sub_balance,
+            None => return true,
+        };
+
+        let requested = symbol.convert_amount_from_amount_currency_code(
+            currency_code,
+            reserve_parameters.amount,
+            reserve_parameters.price,
+        );
+        let available = sub_balance - reserved;
+        if symbol.round_to_remove_amount_precision_error_expected(available - requested) < dec!(0) {
+            let service_name = reserve_parameters.configuration_descriptor.service_name;
+            let msg = format!("Capital allocated to strategy {service_name} on {} isn't enough: available {available} {currency_code}, requested {requested}", reserve_parameters.exchange_account_id);
+            log::warn!("{msg}");
+            explanation.add_reason(msg);
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Currency which is taken away by reservation
+pub(crate) fn reservation_currency_code(reserve_parameters: &ReserveParameters) -> CurrencyCode {
+    reserve_parameters
+        .symbol
+        .get_trade_code(reserve_parameters.order_side, BeforeAfter::Before)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::symbol::Precision;
+    use crate::service_configuration::configuration_descriptor::ServiceConfigurationKey;
+    use crate::settings::{CurrencyAllocationSettings, StrategyCapitalAllocationSettings};
+    use std::sync::Arc;
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", 0)
+    }
+
+    fn configuration_descriptor(service_name: &str) -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new(
+            ServiceName::new(service_name),
+            ServiceConfigurationKey::new("Binance_0;btc/usdt"),
+        )
+    }
+
+    fn symbol() -> Arc<Symbol> {
+        Arc::new(Symbol::new(
+            false,
+            false,
+            "btc".into(),
+            "btc".into(),
+            "usdt".into(),
+            "usdt".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "btc".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.01) },
+            Precision::ByTick { tick: dec!(0.0001) },
+        ))
+    }
+
+    fn allocations() -> CapitalAllocations {
+        let settings = CoreSettings {
+            strategy_capital_allocations: vec![StrategyCapitalAllocationSettings {
+                service_name: ServiceName::new("strategy_a"),
+                exchange_account_id: exchange_account_id(),
+                currencies: vec![
+                    CurrencyAllocationSettings {
+                        currency_code: "btc".into(),
+                        amount: dec!(2),
+                    },
+                    CurrencyAllocationSettings {
+                        currency_code: "usdt".into(),
+                        amount: dec!(30000),
+                    },
+                ],
+            }],
+            ..Default::default()
+        };
+        CapitalAllocations::new(&settings)
+    }
+
+    fn reserve_parameters(
+        service_name: &str,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+    ) -> ReserveParameters {
+        ReserveParameters::new(
+            configuration_descriptor(service_name),
+            exchange_account_id(),
+            symbol(),
+            side,
+            price,
+            amount,
+        )
+    }
+
+    #[test]
+    pub fn reservation_is_limited_by_allocated_capital() {
+        let allocations = allocations();
+
+        let buy = reserve_parameters("strategy_a", OrderSide::Buy, dec!(20000), dec!(1.5));
+        assert!(allocations.can_reserve(&buy, dec!(0), &mut None));
+        assert!(!allocations.can_reserve(&buy, dec!(1000), &mut None));
+
+        let sell = reserve_parameters("strategy_a", OrderSide::Sell, dec!(20000), dec!(2.1));
+        assert!(!allocations.can_reserve(&sell, dec!(0), &mut None));
+
+        // capital isn't allocated to other strategies
+        let other = reserve_parameters("strategy_b", OrderSide::Sell, dec!(20000), dec!(100));
+        assert!(allocations.can_reserve(&other, dec!(0), &mut None));
+    }
+
+    #[test]
+    pub fn fills_move_capital_between_currencies() {
+        let mut allocations = allocations();
+        let configuration_descriptor = configuration_descriptor("strategy_a");
+
+        allocations.register_fill(
+            configuration_descriptor,
+            exchange_account_id(),
+            &symbol(),
+            OrderSide::Buy,
+            dec!(1),
+            dec!(20000),
+            "btc".into(),
+            dec!(0.001),
+        );
+
+        let sub_balance = |currency_code: &str| {
+            allocations
+                .get_sub_balance(
+                    configuration_descriptor,
+                    exchange_account_id(),
+                    currency_code.into(),
+                )
+                .expect("in test")
+        };
+        assert_eq!(sub_balance("btc"), dec!(2.999));
+        assert_eq!(sub_balance("usdt"), dec!(10000));
+        assert_eq!(sub_balance("eth"), dec!(0));
+
+        let sell = reserve_parameters("strategy_a", OrderSide::Sell, dec!(20000), dec!(2.999));
+        assert!(allocations.can_reserve(&sell, dec!(0), &mut None));
+    }
+
+    #[test]
+    pub fn sub_balances_are_restored_for_allocated_strategies() {
+        let mut filled = allocations();
+        filled.register_fill(
+            configuration_descriptor("strategy_a"),
+            exchange_account_id(),
+            &symbol(),
+            OrderSide::Sell,
+            dec!(1),
+            dec!(20000),
+            "usdt".into(),
+            dec!(0),
+        );
+        let mut sub_balances = filled.get_sub_balances().clone();
+        let _ = sub_balances.insert(
+            (ServiceName::new("strategy_b"), exchange_account_id()),
+            HashMap::from([("btc".into(), dec!(5))]),
+        );
+
+        let mut restored = allocations();
+        restored.restore_sub_balances(&sub_balances);
+
+        assert_eq!(restored.get_sub_balances(), filled.get_sub_balances());
+        assert_eq!(
+            restored.get_sub_balance(
+                configuration_descriptor("strategy_b"),
+                exchange_account_id(),
+                "btc".into()
+            ),
+            None
+        );
+    }
+}
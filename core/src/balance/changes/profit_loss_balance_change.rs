@@ -1,6 +1,6 @@
 use std::fmt;
 use std::fmt::{Display, Formatter};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::AtomicU64;
 
 use mmb_utils::DateTime;
 use mmb_utils::{impl_u64_id, time::get_atomic_current_secs};
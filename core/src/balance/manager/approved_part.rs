@@ -23,4 +23,12 @@ impl ApprovedPart {
             unreserved_amount: amount,
         }
     }
+
+    pub(crate) fn approve_time(&self) -> DateTime {
+        self._approve_time
+    }
+
+    pub(crate) fn client_order_id(&self) -> &ClientOrderId {
+        &self._client_order_id
+    }
 }
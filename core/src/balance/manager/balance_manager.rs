@@ -2,24 +2,32 @@ use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use crate::balance::balance_reservation_manager::BalanceReservationManager;
+use crate::balance::capital_allocation::{reservation_currency_code, CapitalAllocations};
 use crate::balance::changes::balance_changes_service::BalanceChangesService;
 use crate::balance::manager::balance_reservation::BalanceReservation;
 use crate::balance::manager::position_change::PositionChange;
+use crate::balance::spending_limits::{
+    NotionalCap, OrderNotional, SpendingLimitBreach, SpendingLimits,
+};
+use crate::database::audit_log::AuditLog;
 use crate::exchanges::common::{Amount, Price};
 use crate::exchanges::common::{CurrencyCode, CurrencyPair, MarketAccountId};
 use crate::exchanges::events::ExchangeBalancesAndPositions;
 use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
 use crate::exchanges::general::symbol::{BeforeAfter, Symbol};
-use crate::explanation::Explanation;
+use crate::explanation::{Explanation, OptionExplanationAddReasonExt};
 use crate::misc::derivative_position::DerivativePosition;
 use crate::misc::reserve_parameters::ReserveParameters;
 use crate::misc::service_value_tree::ServiceValueTree;
+#[double]
+use crate::misc::time::time_manager;
 use crate::orders::fill::OrderFill;
 use crate::orders::order::{
     ClientOrderId, OrderSide, OrderSnapshot, OrderStatus, OrderType, ReservationId,
 };
 use crate::orders::pool::OrderRef;
 use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use crate::settings::CoreSettings;
 use crate::{balance::manager::balances::Balances, exchanges::common::ExchangeAccountId};
 
 use anyhow::{bail, Context, Result};
@@ -29,6 +37,7 @@ use log::log;
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::infrastructure::WithExpect;
 use mmb_utils::{impl_mock_initializer, DateTime};
+use mockall_double::double;
 use parking_lot::Mutex;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
@@ -45,6 +54,10 @@ pub struct BalanceManager {
     balance_reservation_manager: BalanceReservationManager,
     last_order_fills: HashMap<MarketAccountId, OrderFill>,
     balance_changes_service: Option<Arc<BalanceChangesService>>,
+    spending_limits: SpendingLimits,
+    capital_allocations: CapitalAllocations,
+    /// Breaches of spending limits are recorded as incidents
+    audit_log: Option<Arc<AuditLog>>,
 }
 
 impl BalanceManager {
@@ -58,6 +71,9 @@ impl BalanceManager {
             ),
             last_order_fills: HashMap::new(),
             balance_changes_service: None,
+            spending_limits: SpendingLimits::default(),
+            capital_allocations: CapitalAllocations::default(),
+            audit_log: None,
         }))
     }
 
@@ -99,6 +115,11 @@ impl BalanceManager {
                 .restore_fill_amount_limits(amount_limits.clone(), position_by_fill_amount.clone());
         }
 
+        if let Some(capital_sub_balances) = &balances.capital_sub_balances {
+            self.capital_allocations
+                .restore_sub_balances(capital_sub_balances);
+        }
+
         self.last_order_fills = balances.last_order_fills.clone();
     }
 
@@ -165,6 +186,7 @@ impl BalanceManager {
 
     pub fn get_balances(&self) -> Balances {
         let mut balances = self.balance_reservation_manager.get_state();
+        balances.capital_sub_balances = Some(self.capital_allocations.get_sub_balances().clone());
         balances.last_order_fills = self.last_order_fills.clone();
         balances
     }
@@ -456,9 +478,13 @@ impl BalanceManager {
         let exchanges_by_id = this_locked.balance_reservation_manager.exchanges_by_id();
         let new_balance_manager =
             Self::new(CurrencyPairToSymbolConverter::new(exchanges_by_id.clone()));
+        let spending_limits = this_locked.spending_limits.clone();
+        let capital_allocations = this_locked.capital_allocations.clone();
         drop(this_locked);
 
         let mut new_bm_lock = new_balance_manager.lock();
+        new_bm_lock.spending_limits = spending_limits;
+        new_bm_lock.capital_allocations = capital_allocations;
         new_bm_lock.restore_balance_state(&balances, true);
         new_bm_lock.balance_reservation_manager.is_call_from_clone = true;
         drop(new_bm_lock);
@@ -651,6 +677,26 @@ impl BalanceManager {
             order_fill.clone(),
         );
 
+        self.spending_limits.register_fill(
+            OrderNotional {
+                configuration_descriptor,
+                exchange_account_id,
+                quote_currency_code: symbol.quote_currency_code(),
+                notional: order_fill.price() * order_fill.amount(),
+            },
+            order_fill.receive_time(),
+        );
+        self.capital_allocations.register_fill(
+            configuration_descriptor,
+            exchange_account_id,
+            &symbol,
+            order_snapshot.header.side,
+            order_fill.amount(),
+            order_fill.price(),
+            order_fill.commission_currency_code(),
+            order_fill.commission_amount(),
+        );
+
         let position = self
             .balance_reservation_manager
             .get_position_in_amount_currency_code(
@@ -796,6 +842,7 @@ impl BalanceManager {
         reservation_id: ReservationId,
         new_price: Price,
     ) -> bool {
+        let old_price = self.get_reservation(reservation_id).map(|x| x.price);
         if !self
             .balance_reservation_manager
             .try_update_reservation_price(reservation_id, new_price)
@@ -803,6 +850,18 @@ impl BalanceManager {
             return false;
         }
 
+        if let Some(old_price) = old_price.filter(|&old_price| new_price > old_price) {
+            // limits are checked by reservations after update, so updated reservation is counted once
+            let reservation = self.get_reservation_expected(reservation_id);
+            let updated = ReserveParameters::from_reservation(reservation, dec!(0));
+            if !self.is_within_strategy_limits(&[&updated], &mut None) {
+                let _ = self
+                    .balance_reservation_manager
+                    .try_update_reservation_price(reservation_id, old_price);
+                return false;
+            }
+        }
+
         self.save_balances();
         true
     }
@@ -812,6 +871,10 @@ impl BalanceManager {
         reserve_parameters: &ReserveParameters,
         explanation: &mut Option<Explanation>,
     ) -> Option<ReservationId> {
+        if !self.is_within_strategy_limits(&[reserve_parameters], explanation) {
+            return None;
+        }
+
         if let Some(reservation_id) = self
             .balance_reservation_manager
             .try_reserve(reserve_parameters, explanation)
@@ -827,6 +890,10 @@ impl BalanceManager {
         order1: ReserveParameters,
         order2: ReserveParameters,
     ) -> Option<(ReservationId, ReservationId)> {
+        if !self.is_within_strategy_limits(&[&order1, &order2], &mut None) {
+            return None;
+        }
+
         let reservations_id = self
             .balance_reservation_manager
             .try_reserve_multiple(&[order1, order2], &mut None)?;
@@ -843,6 +910,10 @@ impl BalanceManager {
         order2: ReserveParameters,
         order3: ReserveParameters,
     ) -> Option<(ReservationId, ReservationId, ReservationId)> {
+        if !self.is_within_strategy_limits(&[&order1, &order2, &order3], &mut None) {
+            return None;
+        }
+
         let reservations_id = self
             .balance_reservation_manager
             .try_reserve_multiple(&[order1, order2, order3], &mut None)?;
@@ -858,8 +929,115 @@ impl BalanceManager {
         reserve_parameters: &ReserveParameters,
         explanation: &mut Option<Explanation>,
     ) -> bool {
-        self.balance_reservation_manager
-            .can_reserve(reserve_parameters, explanation)
+        let requested = [order_notional(reserve_parameters)];
+        if let Err(breach) = self.check_spending_limits(&requested) {
+            explanation.add_reason(breach.message);
+            return false;
+        }
+
+        self.is_within_capital_allocation(reserve_parameters, explanation)
+            && self
+                .balance_reservation_manager
+                .can_reserve(reserve_parameters, explanation)
+    }
+
+    /// Orders reserved together are checked against spending limits by their combined notional
+    fn is_within_strategy_limits(
+        &mut self,
+        orders: &[&ReserveParameters],
+        explanation: &mut Option<Explanation>,
+    ) -> bool {
+        self.is_within_spending_limits(orders, explanation)
+            && orders
+                .iter()
+                .all(|x| self.is_within_capital_allocation(x, explanation))
+    }
+
+    fn is_within_capital_allocation(
+        &self,
+        reserve_parameters: &ReserveParameters,
+        explanation: &mut Option<Explanation>,
+    ) -> bool {
+        if self.capital_allocations.is_empty() {
+            return true;
+        }
+
+        let currency_code = reservation_currency_code(reserve_parameters);
+        let reserved: Amount = self
+            .balance_reservation_manager
+            .balance_reservation_storage
+            .get_all_raw_reservations()
+            .values()
+            .filter(|x| {
+                x.configuration_descriptor.service_name
+                    == reserve_parameters.configuration_descriptor.service_name
+                    && x.exchange_account_id == reserve_parameters.exchange_account_id
+                    && x.reservation_currency_code == currency_code
+                    && !x.symbol.is_derivative()
+            })
+            .map(|x| {
+                x.get_proportional_cost_amount(x.unreserved_amount)
+                    .map(|cost| x.convert_in_reservation_currency(cost))
+                    .unwrap_or_default()
+            })
+            .sum();
+
+        self.capital_allocations
+            .can_reserve(reserve_parameters, reserved, explanation)
+    }
+
+    fn is_within_spending_limits(
+        &mut self,
+        orders: &[&ReserveParameters],
+        explanation: &mut Option<Explanation>,
+    ) -> bool {
+        let requested = orders.iter().map(|x| order_notional(x)).collect_vec();
+        let result = self.check_spending_limits(&requested);
+        let is_new_breach = self.spending_limits.track_breach(&requested, &result);
+
+        let breach = match result {
+            Ok(()) => return true,
+            Err(breach) => breach,
+        };
+        if is_new_breach {
+            log::error!("Reservation is rejected: {}", breach.message);
+            if let Some(audit_log) = &self.audit_log {
+                audit_log.record_incident("spending_limit_breach", breach.message.clone());
+            }
+        } else {
+            log::warn!("Reservation is rejected: {}", breach.message);
+        }
+        explanation.add_reason(breach.message);
+        false
+    }
+
+    fn check_spending_limits(
+        &self,
+        requested: &[OrderNotional],
+    ) -> Result<(), SpendingLimitBreach> {
+        if self.spending_limits.is_empty() {
+            return Ok(());
+        }
+
+        let mut open_notional = HashMap::<NotionalCap, Amount>::new();
+        let reservations = self
+            .balance_reservation_manager
+            .balance_reservation_storage
+            .get_all_raw_reservations();
+        for reservation in reservations.values() {
+            let reserved = OrderNotional {
+                configuration_descriptor: reservation.configuration_descriptor,
+                exchange_account_id: reservation.exchange_account_id,
+                quote_currency_code: reservation.symbol.quote_currency_code(),
+                notional: (reservation.price * reservation.unreserved_amount).abs(),
+            };
+            for cap in reserved.caps() {
+                *open_notional.entry(cap).or_default() += reserved.notional;
+            }
+        }
+
+        self.spending_limits
+            .check(requested, &open_notional, time_manager::now())
     }
 
     pub fn get_exchange_balance(
@@ -1011,6 +1189,18 @@ impl BalanceManager {
         self.balance_changes_service = Some(service);
     }
 
+    pub fn setup_spending_limits(&mut self, settings: &CoreSettings) {
+        self.spending_limits = SpendingLimits::new(settings);
+    }
+
+    pub fn set_audit_log(&mut self, audit_log: Arc<AuditLog>) {
+        self.audit_log = Some(audit_log);
+    }
+
+    pub fn setup_capital_allocations(&mut self, settings: &CoreSettings) {
+        self.capital_allocations = CapitalAllocations::new(settings);
+    }
+
     pub async fn update_balances_for_exchanges(
         this: Arc<Mutex<Self>>,
         cancellation_token: CancellationToken,
@@ -1063,6 +1253,15 @@ impl BalanceManager {
     // }
 }
 
+fn order_notional(reserve_parameters: &ReserveParameters) -> OrderNotional {
+    OrderNotional {
+        configuration_descriptor: reserve_parameters.configuration_descriptor,
+        exchange_account_id: reserve_parameters.exchange_account_id,
+        quote_currency_code: reserve_parameters.symbol.quote_currency_code(),
+        notional: reserve_parameters.price * reserve_parameters.amount,
+    }
+}
+
 #[cfg_attr(test, automock)]
 impl BalanceManager {
     pub fn get_last_position_change_before_period(
@@ -1083,6 +1282,17 @@ impl BalanceManager {
         self.balance_reservation_manager
             .get_position(exchange_account_id, currency_pair, side)
     }
+
+    /// Position of derivative market which can be closed by order of `side` in amount currency
+    pub fn get_closable_position(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        symbol: Arc<Symbol>,
+        side: OrderSide,
+    ) -> Amount {
+        self.balance_reservation_manager
+            .get_position_in_amount_currency_code(exchange_account_id, symbol, side)
+    }
 }
 
 impl_mock_initializer!(MockBalanceManager);
@@ -28,6 +28,10 @@ impl BalancePositionByFillAmount {
             .cloned()
     }
 
+    pub(crate) fn get_all(&self) -> &HashMap<MarketAccountId, Decimal> {
+        &self.position_by_fill_amount
+    }
+
     pub(crate) fn set(
         &mut self,
         exchange_account_id: ExchangeAccountId,
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use crate::balance::capital_allocation::CapitalSubBalances;
 use crate::balance::manager::{
     balance_position_by_fill_amount::BalancePositionByFillAmount,
     balance_reservation::BalanceReservation,
@@ -7,12 +8,13 @@ use crate::balance::manager::{
 use crate::exchanges::common::CurrencyCode;
 use crate::exchanges::common::ExchangeAccountId;
 use crate::exchanges::common::MarketAccountId;
-use crate::misc::service_value_tree::ServiceValueTree;
+use crate::misc::service_value_tree::{ServiceValueTree, ValueByCurrencyCode};
 use crate::orders::fill::OrderFill;
 use crate::orders::order::ReservationId;
 
 use mmb_utils::DateTime;
 use rust_decimal::Decimal;
+use serde::Serialize;
 
 pub struct Balances {
     pub version: usize,
@@ -30,6 +32,9 @@ pub struct Balances {
     pub amount_limits: Option<ServiceValueTree>,
     pub balance_reservations_by_reservation_id: Option<HashMap<ReservationId, BalanceReservation>>,
 
+    /// Capital of strategies changed by fills
+    pub capital_sub_balances: Option<CapitalSubBalances>,
+
     pub last_order_fills: HashMap<MarketAccountId, OrderFill>,
 }
 
@@ -52,6 +57,7 @@ impl Balances {
             position_by_fill_amount: Some(position_by_fill_amount),
             amount_limits: Some(amount_limits),
             balance_reservations_by_reservation_id: Some(balance_reservations_by_reservation_id),
+            capital_sub_balances: None,
             last_order_fills: HashMap::new(),
         }
     }
@@ -60,3 +66,28 @@ impl Balances {
         1
     }
 }
+
+/// Reserved amounts and virtual balance diffs of strategies with sums by exchange accounts
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceTreesReport {
+    pub reserved_amount: ServiceValueTree,
+    pub reserved_amount_by_exchange_account_id: HashMap<ExchangeAccountId, ValueByCurrencyCode>,
+    pub virtual_balance_diffs: ServiceValueTree,
+    pub virtual_balance_diffs_by_exchange_account_id:
+        HashMap<ExchangeAccountId, ValueByCurrencyCode>,
+}
+
+impl BalanceTreesReport {
+    pub fn new(balances: &Balances) -> Self {
+        let reserved_amount = balances.reserved_amount.clone().unwrap_or_default();
+        let virtual_balance_diffs = balances.virtual_diff_balances.clone().unwrap_or_default();
+
+        BalanceTreesReport {
+            reserved_amount_by_exchange_account_id: reserved_amount.sum_by_exchange_account_id(),
+            reserved_amount,
+            virtual_balance_diffs_by_exchange_account_id: virtual_balance_diffs
+                .sum_by_exchange_account_id(),
+            virtual_balance_diffs,
+        }
+    }
+}
@@ -161,11 +161,15 @@ mod tests {
     use crate::exchanges::common::{Amount, CurrencyCode, MarketAccountId, Price};
     use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
     use crate::exchanges::general::symbol::{Precision, Symbol};
+    use crate::exchanges::general::test_helper::get_test_exchange_with_symbol_and_id;
     use crate::misc::reserve_parameters::ReserveParameters;
     use crate::orders::order::{
         ClientOrderFillId, ClientOrderId, OrderSide, OrderSnapshot, OrderStatus, ReservationId,
     };
     use crate::orders::pool::OrdersPool;
+    use crate::settings::{
+        CoreSettings, CurrencyAllocationSettings, StrategyCapitalAllocationSettings,
+    };
     use crate::{
         balance::manager::tests::balance_manager_base::BalanceManagerBase,
         exchanges::common::ExchangeAccountId,
@@ -688,6 +692,61 @@ mod tests {
         assert_eq!(reservation.not_approved_amount, dec!(5));
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    pub async fn try_update_reservation_buy_worse_price_not_enough_allocated_capital() {
+        init_logger_file_named("log.txt");
+        let test_object = create_test_obj_by_currency_code(BalanceManagerBase::btc(), dec!(10));
+        test_object
+            .balance_manager()
+            .setup_capital_allocations(&CoreSettings {
+                strategy_capital_allocations: vec![StrategyCapitalAllocationSettings {
+                    service_name: test_object
+                        .balance_manager_base
+                        .configuration_descriptor
+                        .service_name,
+                    exchange_account_id: test_object.balance_manager_base.exchange_account_id_1,
+                    currencies: vec![CurrencyAllocationSettings {
+                        currency_code: BalanceManagerBase::btc(),
+                        amount: dec!(1.2),
+                    }],
+                }],
+                ..Default::default()
+            });
+
+        let reserve_parameters = test_object.balance_manager_base.create_reserve_parameters(
+            OrderSide::Buy,
+            dec!(0.2),
+            dec!(5),
+        );
+
+        let reservation_id = test_object
+            .balance_manager()
+            .try_reserve(&reserve_parameters, &mut None)
+            .expect("in test");
+
+        assert!(!test_object
+            .balance_manager()
+            .try_update_reservation(reservation_id, dec!(0.3)));
+        assert_eq!(
+            test_object
+                .balance_manager()
+                .get_reservation_expected(reservation_id)
+                .price,
+            dec!(0.2)
+        );
+
+        assert!(test_object
+            .balance_manager()
+            .try_update_reservation(reservation_id, dec!(0.24)));
+        assert_eq!(
+            test_object
+                .balance_manager()
+                .get_reservation_expected(reservation_id)
+                .price,
+            dec!(0.24)
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     pub async fn try_update_reservation_buy_worse_price_enough_balance() {
         init_logger_file_named("log.txt");
@@ -5309,6 +5368,144 @@ mod tests {
         );
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    pub async fn amount_reduced_before_order_creation_is_unreserved() {
+        init_logger_file_named("log.txt");
+        let mut test_object = create_test_obj_by_currency_code(BalanceManagerBase::eth(), dec!(5));
+
+        let reserve_parameters = test_object.balance_manager_base.create_reserve_parameters(
+            OrderSide::Sell,
+            dec!(0.2),
+            dec!(5),
+        );
+        let reservation_id = test_object
+            .balance_manager()
+            .try_reserve(&reserve_parameters, &mut None)
+            .expect("in test");
+
+        let exchange = get_test_exchange_with_symbol_and_id(
+            test_object.balance_manager_base.symbol(),
+            test_object.balance_manager_base.exchange_account_id_1,
+        )
+        .0;
+        exchange.setup_balance_manager(
+            test_object
+                .balance_manager_base
+                .balance_manager
+                .clone()
+                .expect("in test"),
+        );
+
+        // order amount is reduced from 5 to 2 before creation, e.g. in reduced rollout mode
+        let order = test_object.balance_manager_base.create_order_by_amount(
+            OrderSide::Sell,
+            dec!(2),
+            reservation_id,
+        );
+        let client_order_id = order.header.client_order_id.clone();
+        exchange.unreserve_reduced_amount(&order.header, dec!(3), false);
+        test_object.balance_manager().approve_reservation(
+            reservation_id,
+            &client_order_id,
+            dec!(2),
+        );
+        assert_eq!(
+            test_object
+                .balance_manager()
+                .get_balance_by_reserve_parameters(&reserve_parameters),
+            Some(dec!(3))
+        );
+
+        // the current order amount is unreserved when order is finished
+        test_object
+            .balance_manager()
+            .unreserve_by_client_order_id(reservation_id, client_order_id, dec!(2))
+            .expect("in test");
+        assert_eq!(
+            test_object
+                .balance_manager()
+                .get_balance_by_reserve_parameters(&reserve_parameters),
+            Some(dec!(5))
+        );
+        assert!(test_object
+            .balance_manager()
+            .get_reservation(reservation_id)
+            .is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    pub async fn order_reservation_is_released_with_approved_part() {
+        init_logger_file_named("log.txt");
+        let mut test_object = create_test_obj_by_currency_code(BalanceManagerBase::eth(), dec!(5));
+
+        let exchange = get_test_exchange_with_symbol_and_id(
+            test_object.balance_manager_base.symbol(),
+            test_object.balance_manager_base.exchange_account_id_1,
+        )
+        .0;
+        exchange.setup_balance_manager(
+            test_object
+                .balance_manager_base
+                .balance_manager
+                .clone()
+                .expect("in test"),
+        );
+
+        let reservation_id = exchange
+            .reserve_order_balance(
+                test_object.balance_manager_base.configuration_descriptor,
+                test_object.balance_manager_base.currency_pair,
+                OrderSide::Sell,
+                dec!(0.2),
+                dec!(5),
+            )
+            .expect("in test");
+        let reserve_parameters = test_object.balance_manager_base.create_reserve_parameters(
+            OrderSide::Sell,
+            dec!(0.2),
+            dec!(5),
+        );
+        assert_eq!(
+            test_object
+                .balance_manager()
+                .get_balance_by_reserve_parameters(&reserve_parameters),
+            Some(dec!(0))
+        );
+
+        assert!(exchange
+            .reserve_order_balance(
+                test_object.balance_manager_base.configuration_descriptor,
+                test_object.balance_manager_base.currency_pair,
+                OrderSide::Sell,
+                dec!(0.2),
+                dec!(1),
+            )
+            .is_err());
+
+        let order = test_object.balance_manager_base.create_order_by_amount(
+            OrderSide::Sell,
+            dec!(2),
+            reservation_id,
+        );
+        test_object.balance_manager().approve_reservation(
+            reservation_id,
+            &order.header.client_order_id,
+            dec!(2),
+        );
+
+        exchange.release_order_reservation(&order.header);
+        assert_eq!(
+            test_object
+                .balance_manager()
+                .get_balance_by_reserve_parameters(&reserve_parameters),
+            Some(dec!(5))
+        );
+        assert!(test_object
+            .balance_manager()
+            .get_reservation(reservation_id)
+            .is_none());
+    }
+
     fn order_was_filled(
         test_object: &mut BalanceManagerOrdinal,
         order: &mut OrderSnapshot,
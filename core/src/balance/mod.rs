@@ -1,7 +1,10 @@
+pub(crate) mod balance_anomaly;
 pub(crate) mod balance_position_model;
 pub(crate) mod balance_reservation_manager;
 pub(crate) mod balance_reservation_preset;
 pub(crate) mod balance_reservation_storage;
+pub(crate) mod capital_allocation;
 pub(crate) mod changes;
 pub mod manager;
+pub(crate) mod spending_limits;
 pub(crate) mod virtual_balance_holder;
@@ -0,0 +1,385 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use mmb_utils::DateTime;
+use rust_decimal_macros::dec;
+
+use crate::exchanges::common::{Amount, CurrencyCode, ExchangeAccountId};
+use crate::service_configuration::configuration_descriptor::{
+    ConfigurationDescriptor, ServiceName,
+};
+use crate::settings::CoreSettings;
+
+fn rolling_window() -> chrono::Duration {
+    chrono::Duration::hours(24)
+}
+
+/// Notional of fills during rolling window
+#[derive(Debug, Default, Clone)]
+struct RollingNotional {
+    fills: VecDeque<(DateTime, Amount)>,
+}
+
+impl RollingNotional {
+    fn add(&mut self, time: DateTime, notional: Amount) {
+        self.fills.push_back((time, notional));
+
+        let window_start = time - rolling_window();
+        while let Some((fill_time, _)) = self.fills.front() {
+            if *fill_time > window_start {
+                break;
+            }
+            let _ = self.fills.pop_front();
+        }
+    }
+
+    fn get(&self, now: DateTime) -> Amount {
+        let window_start = now - rolling_window();
+        self.fills
+            .iter()
+            .filter(|(fill_time, _)| *fill_time > window_start)
+            .map(|(_, notional)| *notional)
+            .sum()
+    }
+}
+
+/// Cap is applied to notional of orders with the same quote currency only, because notionals in
+/// different quote currencies aren't comparable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum NotionalCap {
+    ExchangeAccount(ExchangeAccountId, CurrencyCode),
+    Strategy(ServiceName, CurrencyCode),
+}
+
+impl Display for NotionalCap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotionalCap::ExchangeAccount(exchange_account_id, quote_currency_code) => write!(
+                f,
+                "daily notional cap of {quote_currency_code} for exchange account {exchange_account_id}"
+            ),
+            NotionalCap::Strategy(service_name, quote_currency_code) => write!(
+                f,
+                "daily notional cap of {quote_currency_code} for strategy {service_name}"
+            ),
+        }
+    }
+}
+
+/// Notional of order (or its part) in quote currency of order
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OrderNotional {
+    pub configuration_descriptor: ConfigurationDescriptor,
+    pub exchange_account_id: ExchangeAccountId,
+    pub quote_currency_code: CurrencyCode,
+    pub notional: Amount,
+}
+
+impl OrderNotional {
+    pub fn caps(&self) -> [NotionalCap; 2] {
+        [
+            NotionalCap::ExchangeAccount(self.exchange_account_id, self.quote_currency_code),
+            NotionalCap::Strategy(
+                self.configuration_descriptor.service_name,
+                self.quote_currency_code,
+            ),
+        ]
+    }
+}
+
+/// Reservation rejected because of exceeding of notional cap
+#[derive(Debug, Clone)]
+pub(crate) struct SpendingLimitBreach {
+    pub cap: NotionalCap,
+    pub message: String,
+}
+
+/// Caps of notional traded during last 24 hours plus open notional per exchange account and per
+/// strategy. It is the last line of defense against runaway strategies, so exceeding of cap
+/// rejects reservation
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SpendingLimits {
+    caps: HashMap<NotionalCap, Amount>,
+    traded: HashMap<NotionalCap, RollingNotional>,
+    breached_caps: HashSet<NotionalCap>,
+}
+
+impl SpendingLimits {
+    pub fn new(settings: &CoreSettings) -> Self {
+        let exchange_account_caps = settings.exchanges.iter().flat_map(|exchange| {
+            exchange.daily_notional_caps.iter().map(|cap| {
+                (
+                    NotionalCap::ExchangeAccount(
+                        exchange.exchange_account_id,
+                        cap.quote_currency_code,
+                    ),
+                    cap.max_daily_notional,
+                )
+            })
+        });
+        let strategy_caps = settings
+            .strategy_spending_limits
+            .iter()
+            .flat_map(|strategy| {
+                strategy.daily_notional_caps.iter().map(|cap| {
+                    (
+                        NotionalCap::Strategy(strategy.service_name, cap.quote_currency_code),
+                        cap.max_daily_notional,
+                    )
+                })
+            });
+
+        SpendingLimits {
+            caps: exchange_account_caps.chain(strategy_caps).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.caps.is_empty()
+    }
+
+    pub fn register_fill(&mut self, fill: OrderNotional, time: DateTime) {
+        for cap in fill.caps() {
+            if self.caps.contains_key(&cap) {
+                self.traded
+                    .entry(cap)
+                    .or_default()
+                    .add(time, fill.notional.abs());
+            }
+        }
+    }
+
+    /// Check that trading of all requested notionals together in addition to traded and open
+    /// notional doesn't exceed caps
+    pub fn check(
+        &self,
+        requested: &[OrderNotional],
+        open_notional: &HashMap<NotionalCap, Amount>,
+        now: DateTime,
+    ) -> Result<(), SpendingLimitBreach> {
+        let mut requested_by_cap = HashMap::<_, Amount>::new();
+        for order_notional in requested {
+            for cap in order_notional.caps() {
+                *requested_by_cap.entry(cap).or_default() += order_notional.notional.abs();
+            }
+        }
+
+        for (cap, requested) in requested_by_cap {
+            let limit = match self.caps.get(&cap) {
+                Some(limit) => *limit,
+                None => continue,
+            };
+
+            let traded = self.traded.get(&cap).map(|x| x.get(now)).unwrap_or(dec!(0));
+            let open = open_notional.get(&cap).copied().unwrap_or(dec!(0));
+            if traded + open + requested > limit {
+                return Err(SpendingLimitBreach {
+                    cap,
+                    message: format!("{cap} {limit} would be exceeded: traded {traded}, open {open}, requested {requested}"),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if cap is breached for the first time since it allowed trading last time,
+    /// so breach should be alerted. Repeated rejections by the same cap aren't alerted
+    pub fn track_breach(
+        &mut self,
+        requested: &[OrderNotional],
+        result: &Result<(), SpendingLimitBreach>,
+    ) -> bool {
+        match result {
+            Ok(()) => {
+                for cap in requested.iter().flat_map(|x| x.caps()) {
+                    let _ = self.breached_caps.remove(&cap);
+                }
+                false
+            }
+            Err(breach) => self.breached_caps.insert(breach.cap),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service_configuration::configuration_descriptor::ServiceConfigurationKey;
+    use crate::settings::{ExchangeSettings, NotionalCapSettings, StrategySpendingLimitSettings};
+    use chrono::Utc;
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", 0)
+    }
+
+    fn configuration_descriptor() -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new(
+            ServiceName::new("test_strategy"),
+            ServiceConfigurationKey::new("Binance_0;btc/usdt"),
+        )
+    }
+
+    fn usdt_caps(max_daily_notional: Option<Amount>) -> Vec<NotionalCapSettings> {
+        max_daily_notional
+            .map(|max_daily_notional| NotionalCapSettings {
+                quote_currency_code: "USDT".into(),
+                max_daily_notional,
+            })
+            .into_iter()
+            .collect()
+    }
+
+    fn spending_limits(
+        exchange_account_limit: Option<Amount>,
+        strategy_limit: Option<Amount>,
+    ) -> SpendingLimits {
+        let settings = CoreSettings {
+            exchanges: vec![ExchangeSettings {
+                exchange_account_id: exchange_account_id(),
+                daily_notional_caps: usdt_caps(exchange_account_limit),
+                ..Default::default()
+            }],
+            strategy_spending_limits: vec![StrategySpendingLimitSettings {
+                service_name: configuration_descriptor().service_name,
+                daily_notional_caps: usdt_caps(strategy_limit),
+            }],
+            ..Default::default()
+        };
+        SpendingLimits::new(&settings)
+    }
+
+    fn order_notional(quote_currency_code: &str, notional: Amount) -> OrderNotional {
+        OrderNotional {
+            configuration_descriptor: configuration_descriptor(),
+            exchange_account_id: exchange_account_id(),
+            quote_currency_code: quote_currency_code.into(),
+            notional,
+        }
+    }
+
+    fn can_trade(limits: &SpendingLimits, notional: Amount, now: DateTime) -> bool {
+        can_trade_with_open(limits, notional, HashMap::new(), now)
+    }
+
+    fn can_trade_with_open(
+        limits: &SpendingLimits,
+        notional: Amount,
+        open_notional: HashMap<NotionalCap, Amount>,
+        now: DateTime,
+    ) -> bool {
+        limits
+            .check(&[order_notional("USDT", notional)], &open_notional, now)
+            .is_ok()
+    }
+
+    fn register_fill(limits: &mut SpendingLimits, notional: Amount, time: DateTime) {
+        limits.register_fill(order_notional("USDT", notional), time);
+    }
+
+    #[test]
+    pub fn no_limits() {
+        let limits = spending_limits(None, None);
+
+        assert!(limits.is_empty());
+        assert!(can_trade(&limits, dec!(1_000_000_000), Utc::now()));
+    }
+
+    #[test]
+    pub fn exchange_account_limit_exceeded() {
+        let mut limits = spending_limits(Some(dec!(1000)), None);
+        let now = Utc::now();
+
+        register_fill(&mut limits, dec!(700), now);
+
+        assert!(can_trade(&limits, dec!(300), now));
+        assert!(!can_trade(&limits, dec!(301), now));
+    }
+
+    #[test]
+    pub fn strategy_limit_exceeded() {
+        let mut limits = spending_limits(None, Some(dec!(1000)));
+        let now = Utc::now();
+
+        register_fill(&mut limits, dec!(-900), now);
+
+        assert!(!can_trade(&limits, dec!(200), now));
+    }
+
+    #[test]
+    pub fn old_fills_are_out_of_window() {
+        let mut limits = spending_limits(Some(dec!(1000)), Some(dec!(1000)));
+        let now = Utc::now();
+
+        register_fill(&mut limits, dec!(900), now - chrono::Duration::hours(25));
+
+        assert!(can_trade(&limits, dec!(1000), now));
+    }
+
+    #[test]
+    pub fn open_notional_is_included() {
+        let mut limits = spending_limits(Some(dec!(1000)), Some(dec!(2000)));
+        let now = Utc::now();
+        let [exchange_account_cap, strategy_cap] = order_notional("USDT", dec!(0)).caps();
+
+        register_fill(&mut limits, dec!(500), now);
+
+        let open_notional =
+            HashMap::from([(exchange_account_cap, dec!(400)), (strategy_cap, dec!(400))]);
+        assert!(can_trade_with_open(
+            &limits,
+            dec!(100),
+            open_notional.clone(),
+            now
+        ));
+        assert!(!can_trade_with_open(&limits, dec!(101), open_notional, now));
+
+        let open_notional = HashMap::from([(strategy_cap, dec!(1500))]);
+        assert!(!can_trade_with_open(&limits, dec!(1), open_notional, now));
+    }
+
+    #[test]
+    pub fn quote_currencies_are_capped_separately() {
+        let mut limits = spending_limits(Some(dec!(1000)), None);
+        let now = Utc::now();
+
+        register_fill(&mut limits, dec!(1000), now);
+        limits.register_fill(order_notional("BTC", dec!(5)), now);
+
+        assert!(!can_trade(&limits, dec!(1), now));
+        let btc_order = [order_notional("BTC", dec!(1_000_000))];
+        assert!(limits.check(&btc_order, &HashMap::new(), now).is_ok());
+    }
+
+    #[test]
+    pub fn requested_notionals_are_checked_together() {
+        let limits = spending_limits(Some(dec!(1000)), None);
+        let now = Utc::now();
+
+        let orders = [
+            order_notional("USDT", dec!(600)),
+            order_notional("USDT", dec!(-600)),
+        ];
+        assert!(limits.check(&orders[..1], &HashMap::new(), now).is_ok());
+        assert!(limits.check(&orders, &HashMap::new(), now).is_err());
+    }
+
+    #[test]
+    pub fn breach_is_alerted_once_until_cap_allows_trading() {
+        let mut limits = spending_limits(Some(dec!(1000)), None);
+        let now = Utc::now();
+        let big_order = [order_notional("USDT", dec!(2000))];
+        let small_order = [order_notional("USDT", dec!(10))];
+
+        let mut track = |orders: &[OrderNotional]| {
+            let result = limits.check(orders, &HashMap::new(), now);
+            limits.track_breach(orders, &result)
+        };
+
+        assert!(track(&big_order));
+        assert!(!track(&big_order));
+        assert!(!track(&small_order));
+        assert!(track(&big_order));
+    }
+}
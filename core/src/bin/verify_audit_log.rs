@@ -0,0 +1,38 @@
+//! Verifies hash chain of audit log file written by engine with `audit_log` settings
+//!
+//! Usage: `verify_audit_log <path to audit log>`
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use mmb_core::database::audit_log::verify_audit_log_file;
+
+fn main() -> ExitCode {
+    let path = match std::env::args().nth(1) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            eprintln!("Usage: verify_audit_log <path to audit log>");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match verify_audit_log_file(&path) {
+        Ok(None) => {
+            println!("Audit log {} is empty", path.display());
+            ExitCode::SUCCESS
+        }
+        Ok(Some(last_record)) => {
+            println!(
+                "Audit log {} is valid: {} records, the last one at {}",
+                path.display(),
+                last_record.sequence + 1,
+                last_record.time
+            );
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("Audit log {} is tampered: {error:?}", path.display());
+            ExitCode::FAILURE
+        }
+    }
+}
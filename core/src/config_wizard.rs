@@ -0,0 +1,480 @@
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::fs::OpenOptions;
+use std::io::{BufRead, Write};
+
+use anyhow::{anyhow, bail, Context, Result};
+use itertools::Itertools;
+use serde::de::DeserializeOwned;
+use tokio::sync::broadcast;
+use toml_edit::{value, Array, ArrayOfTables, Document, InlineTable, Item, Table, Value};
+
+use crate::config::{parse_settings, API_KEY, CONFIG_PATH, CREDENTIALS_PATH, SECRET_KEY};
+use crate::exchanges::common::{CurrencyCode, ExchangeAccountId, ExchangeId};
+use crate::infrastructure::init_lifetime_manager;
+use crate::lifecycle::launcher::EngineBuildConfig;
+use crate::orders::pool::OrdersPool;
+use crate::settings::{BaseStrategySettings, ExchangeSettings};
+
+const API_KEY_PLACEHOLDER: &str = "<api key>";
+const SECRET_KEY_PLACEHOLDER: &str = "<secret key>";
+const DEFAULT_WEBSOCKET_CHANNEL: &str = "depth20";
+const MAX_AMOUNT_PLACEHOLDER: i64 = 1;
+
+/// Strategy which can be chosen in config wizard
+pub struct StrategyTemplate {
+    pub name: &'static str,
+    /// Toml lines of strategy specific settings. Exchange account, currency pair and max amount
+    /// of strategy are filled by wizard
+    pub settings: &'static str,
+    /// Checks that generated config and credentials are loaded as settings of strategy
+    pub validate: fn(&str, &str) -> Result<()>,
+}
+
+pub fn validate_settings<StrategySettings>(config: &str, credentials: &str) -> Result<()>
+where
+    StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned,
+{
+    parse_settings::<StrategySettings>(config, credentials).map(|_| ())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WizardExchange {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pairs: Vec<(CurrencyCode, CurrencyCode)>,
+    /// Api and secret keys. Placeholders are generated if they aren't entered
+    pub credentials: Option<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WizardAnswers {
+    pub exchanges: Vec<WizardExchange>,
+    /// Index of chosen strategy template
+    pub strategy: usize,
+}
+
+struct Prompter<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Prompter<R, W> {
+    fn ask(&mut self, question: &str) -> Result<String> {
+        write!(self.output, "{question}: ")?;
+        self.output.flush()?;
+
+        let mut answer = String::new();
+        if self.input.read_line(&mut answer)? == 0 {
+            bail!("Input is closed before config is generated");
+        }
+
+        Ok(answer.trim().to_owned())
+    }
+
+    /// Repeats question until answer is parsed
+    fn ask_until<T>(&mut self, question: &str, parse: impl Fn(&str) -> Result<T>) -> Result<T> {
+        loop {
+            let answer = self.ask(question)?;
+            match parse(&answer) {
+                Ok(parsed) => return Ok(parsed),
+                Err(err) => writeln!(self.output, "{err}")?,
+            }
+        }
+    }
+}
+
+/// Asks for exchanges, currency pairs and strategy type in terminal and writes `config.toml`
+/// and `credentials.toml` to the current directory. Currency pairs are validated against
+/// symbols of exchanges which credentials are entered for
+pub async fn run_config_wizard(
+    build_config: &EngineBuildConfig,
+    templates: &[StrategyTemplate],
+) -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut prompter = Prompter {
+        input: stdin.lock(),
+        output: std::io::stdout(),
+    };
+
+    let supported_exchanges = build_config
+        .supported_exchange_clients
+        .keys()
+        .copied()
+        .collect_vec();
+    let mut answers = ask_answers(&mut prompter, &supported_exchanges, templates)?;
+    validate_currency_pairs(&mut prompter, build_config, &mut answers).await?;
+
+    let config = generate_config(&answers, &templates[answers.strategy])?;
+    let credentials = generate_credentials(&answers);
+    (templates[answers.strategy].validate)(&config, &credentials)
+        .context("Generated config is invalid")?;
+
+    write_new_file(CONFIG_PATH, &config)?;
+    write_new_file(CREDENTIALS_PATH, &credentials)?;
+    writeln!(
+        prompter.output,
+        "{CONFIG_PATH} and {CREDENTIALS_PATH} are generated, fill placeholders before launching engine"
+    )?;
+
+    Ok(())
+}
+
+fn ask_answers<R: BufRead, W: Write>(
+    prompter: &mut Prompter<R, W>,
+    supported_exchanges: &[ExchangeId],
+    templates: &[StrategyTemplate],
+) -> Result<WizardAnswers> {
+    let exchange_account_ids = prompter.ask_until(
+        "Exchange accounts separated by comma, e.g. Binance_0",
+        |answer| parse_exchange_account_ids(answer, supported_exchanges),
+    )?;
+
+    let mut exchanges = Vec::new();
+    for exchange_account_id in exchange_account_ids {
+        let currency_pairs = prompter.ask_until(
+            &format!("Currency pairs of {exchange_account_id} separated by comma, e.g. btc/usdt"),
+            parse_currency_pairs,
+        )?;
+
+        let api_key = prompter.ask(&format!(
+            "API key of {exchange_account_id} (leave empty to fill it in {CREDENTIALS_PATH} later)"
+        ))?;
+        let credentials = match api_key.is_empty() {
+            true => None,
+            false => {
+                let secret_key = prompter.ask_until(
+                    &format!("Secret key of {exchange_account_id}"),
+                    |x| match x.is_empty() {
+                        true => bail!("Secret key can't be empty"),
+                        false => Ok(x.to_owned()),
+                    },
+                )?;
+                Some((api_key, secret_key))
+            }
+        };
+
+        exchanges.push(WizardExchange {
+            exchange_account_id,
+            currency_pairs,
+            credentials,
+        });
+    }
+
+    for (index, template) in templates.iter().enumerate() {
+        writeln!(prompter.output, "{}) {}", index + 1, template.name)?;
+    }
+    let strategy = prompter.ask_until("Strategy type", |answer| match answer.parse::<usize>() {
+        Ok(number) if (1..=templates.len()).contains(&number) => Ok(number - 1),
+        _ => templates
+            .iter()
+            .position(|x| x.name == answer)
+            .with_context(|| format!("Unknown strategy type {answer}")),
+    })?;
+
+    Ok(WizardAnswers {
+        exchanges,
+        strategy,
+    })
+}
+
+fn parse_exchange_account_ids(
+    answer: &str,
+    supported_exchanges: &[ExchangeId],
+) -> Result<Vec<ExchangeAccountId>> {
+    let mut exchange_account_ids = Vec::new();
+    for item in answer.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        let exchange_account_id: ExchangeAccountId = item
+            .parse()
+            .map_err(|err| anyhow!("Invalid exchange account {item}: {err:?}"))?;
+        if !supported_exchanges.contains(&exchange_account_id.exchange_id) {
+            bail!(
+                "Exchange {} isn't supported, supported exchanges: {}",
+                exchange_account_id.exchange_id,
+                supported_exchanges.iter().join(", ")
+            );
+        }
+        if !exchange_account_ids.contains(&exchange_account_id) {
+            exchange_account_ids.push(exchange_account_id);
+        }
+    }
+
+    if exchange_account_ids.is_empty() {
+        bail!("At least one exchange account should be specified");
+    }
+
+    Ok(exchange_account_ids)
+}
+
+fn parse_currency_pairs(answer: &str) -> Result<Vec<(CurrencyCode, CurrencyCode)>> {
+    let currency_pairs = answer
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|item| match item.split_once('/') {
+            Some((base, quote)) if !base.is_empty() && !quote.is_empty() => {
+                Ok((CurrencyCode::new(base), CurrencyCode::new(quote)))
+            }
+            _ => bail!("Invalid currency pair {item}, expected format is base/quote"),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if currency_pairs.is_empty() {
+        bail!("At least one currency pair should be specified");
+    }
+
+    Ok(currency_pairs)
+}
+
+/// Currency pairs of exchanges with entered credentials are checked against exchange symbols.
+/// Unknown currency pairs are asked again
+async fn validate_currency_pairs<R: BufRead, W: Write>(
+    prompter: &mut Prompter<R, W>,
+    build_config: &EngineBuildConfig,
+    answers: &mut WizardAnswers,
+) -> Result<()> {
+    let lifetime_manager = init_lifetime_manager();
+    for exchange in &mut answers.exchanges {
+        let (api_key, secret_key) = match &exchange.credentials {
+            Some(credentials) => credentials.clone(),
+            None => continue,
+        };
+
+        let exchange_account_id = exchange.exchange_account_id;
+        let builder = &build_config.supported_exchange_clients[&exchange_account_id.exchange_id];
+        let (events_sender, _) = broadcast::channel(1);
+        let exchange_client = builder.create_exchange_client(
+            ExchangeSettings::new_short(exchange_account_id, api_key, secret_key, false),
+            events_sender,
+            lifetime_manager.clone(),
+            OrdersPool::new(),
+        );
+        let symbols: HashSet<_> = exchange_client
+            .client
+            .build_all_symbols()
+            .await
+            .with_context(|| format!("Unable to get symbols of {exchange_account_id}"))?
+            .iter()
+            .map(|x| (x.base_currency_code, x.quote_currency_code))
+            .collect();
+
+        loop {
+            let unknown_pairs = exchange
+                .currency_pairs
+                .iter()
+                .filter(|x| !symbols.contains(x))
+                .map(|(base, quote)| format!("{base}/{quote}"))
+                .collect_vec();
+            if unknown_pairs.is_empty() {
+                break;
+            }
+
+            writeln!(
+                prompter.output,
+                "Currency pairs {} aren't found on {exchange_account_id}",
+                unknown_pairs.join(", ")
+            )?;
+            exchange.currency_pairs = prompter.ask_until(
+                &format!("Currency pairs of {exchange_account_id} separated by comma"),
+                parse_currency_pairs,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn currency_pair_value(base: CurrencyCode, quote: CurrencyCode) -> Value {
+    let mut currency_pair = InlineTable::new();
+    currency_pair.get_or_insert("base", base.as_str());
+    currency_pair.get_or_insert("quote", quote.as_str());
+    Value::InlineTable(currency_pair)
+}
+
+/// Strategy trades the first currency pair of the first exchange account
+pub fn generate_config(answers: &WizardAnswers, template: &StrategyTemplate) -> Result<String> {
+    let first_exchange = answers
+        .exchanges
+        .first()
+        .context("Config can't be generated without exchanges")?;
+    let (base, quote) = first_exchange.currency_pairs[0];
+
+    let mut strategy = Table::new();
+    strategy.insert(
+        "exchange_account_id",
+        value(first_exchange.exchange_account_id.to_string()),
+    );
+    strategy.insert("currency_pair", value(currency_pair_value(base, quote)));
+    strategy.insert("max_amount", value(MAX_AMOUNT_PLACEHOLDER));
+    let strategy_settings: Document = template
+        .settings
+        .parse()
+        .with_context(|| format!("Invalid settings template of strategy {}", template.name))?;
+    for (key, item) in strategy_settings.as_table().iter() {
+        strategy.insert(key, item.clone());
+    }
+
+    let mut exchanges = ArrayOfTables::new();
+    for exchange in &answers.exchanges {
+        let mut table = Table::new();
+        table.insert(
+            "exchange_account_id",
+            value(exchange.exchange_account_id.to_string()),
+        );
+        table.insert("is_margin_trading", value(false));
+        table.insert("request_trades", value(false));
+        table.insert("subscribe_to_market_data", value(true));
+        let mut websocket_channels = Array::new();
+        websocket_channels.push(DEFAULT_WEBSOCKET_CHANNEL);
+        table.insert("websocket_channels", value(websocket_channels));
+        let mut currency_pairs = Array::new();
+        for &(base, quote) in &exchange.currency_pairs {
+            currency_pairs.push(currency_pair_value(base, quote));
+        }
+        table.insert("currency_pairs", value(currency_pairs));
+        exchanges.push(table);
+    }
+
+    let mut core = Table::new();
+    core.set_implicit(true);
+    core.insert("exchanges", Item::ArrayOfTables(exchanges));
+
+    let mut document = Document::new();
+    document
+        .as_table_mut()
+        .insert("strategy", Item::Table(strategy));
+    document.as_table_mut().insert("core", Item::Table(core));
+
+    Ok(document.to_string())
+}
+
+pub fn generate_credentials(answers: &WizardAnswers) -> String {
+    let mut document = Document::new();
+    for exchange in &answers.exchanges {
+        let (api_key, secret_key) = exchange.credentials.clone().unwrap_or_else(|| {
+            (
+                API_KEY_PLACEHOLDER.to_owned(),
+                SECRET_KEY_PLACEHOLDER.to_owned(),
+            )
+        });
+
+        let mut table = Table::new();
+        table.insert(API_KEY, value(api_key));
+        table.insert(SECRET_KEY, value(secret_key));
+        document.as_table_mut().insert(
+            &exchange.exchange_account_id.to_string(),
+            Item::Table(table),
+        );
+    }
+
+    document.to_string()
+}
+
+/// Existing config of user is never overwritten
+fn write_new_file(path: &str, content: &str) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .with_context(|| format!("Unable to create {path}, probably it already exists"))?;
+    file.write_all(content.as_bytes())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::{Amount, CurrencyPair};
+    use crate::settings::CurrencyPairSetting;
+    use crate::settings_values::deserialize_decimal;
+    use serde::Deserialize;
+    use std::io::Cursor;
+
+    #[derive(Debug, Clone, Deserialize)]
+    struct TestStrategySettings {
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPairSetting,
+        #[serde(deserialize_with = "deserialize_decimal")]
+        max_amount: Amount,
+        #[allow(dead_code)]
+        #[serde(deserialize_with = "deserialize_decimal")]
+        spread: Amount,
+    }
+
+    impl BaseStrategySettings for TestStrategySettings {
+        fn exchange_account_id(&self) -> ExchangeAccountId {
+            self.exchange_account_id
+        }
+
+        fn currency_pair(&self) -> CurrencyPair {
+            match &self.currency_pair {
+                CurrencyPairSetting::Ordinary { base, quote } => {
+                    CurrencyPair::from_codes(*base, *quote)
+                }
+                CurrencyPairSetting::Specific(_) => panic!("Unexpected currency pair in test"),
+            }
+        }
+
+        fn max_amount(&self) -> Amount {
+            self.max_amount
+        }
+    }
+
+    fn templates() -> Vec<StrategyTemplate> {
+        vec![
+            StrategyTemplate {
+                name: "other",
+                settings: "",
+                validate: |_, _| Ok(()),
+            },
+            StrategyTemplate {
+                name: "test",
+                settings: "spread = 0.1\n",
+                validate: validate_settings::<TestStrategySettings>,
+            },
+        ]
+    }
+
+    #[test]
+    pub fn generated_config_is_loaded() {
+        let input = "Unknown_0\nBinance_0, Binance_1\nbtc/usdt, eth\nBTC/USDT, eth/btc\nkey\nsecret\nltc/btc\n\nfoo\n2\n";
+        let mut prompter = Prompter {
+            input: Cursor::new(input),
+            output: Vec::new(),
+        };
+
+        let templates = templates();
+        let answers = ask_answers(&mut prompter, &["Binance".into()], &templates).expect("in test");
+        assert_eq!(answers.strategy, 1);
+        assert_eq!(answers.exchanges.len(), 2);
+        assert_eq!(
+            answers.exchanges[0].currency_pairs,
+            vec![("btc".into(), "usdt".into()), ("eth".into(), "btc".into())]
+        );
+        assert_eq!(
+            answers.exchanges[0].credentials,
+            Some(("key".to_owned(), "secret".to_owned()))
+        );
+        assert_eq!(answers.exchanges[1].credentials, None);
+
+        let config = generate_config(&answers, &templates[1]).expect("in test");
+        let credentials = generate_credentials(&answers);
+        assert!(credentials.contains(API_KEY_PLACEHOLDER));
+
+        let settings =
+            parse_settings::<TestStrategySettings>(&config, &credentials).expect("in test");
+        assert_eq!(
+            settings.strategy.exchange_account_id,
+            ExchangeAccountId::new("Binance", 0)
+        );
+        assert_eq!(settings.core.exchanges.len(), 2);
+        assert_eq!(settings.core.exchanges[0].api_key, "key");
+        assert_eq!(
+            settings.core.exchanges[1].currency_pairs,
+            Some(vec![CurrencyPairSetting::Ordinary {
+                base: "ltc".into(),
+                quote: "btc".into()
+            }])
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use std::fmt::Write;
+use std::net::{IpAddr, SocketAddr};
+
+use anyhow::{bail, Context, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{lookup_host, TcpSocket, TcpStream};
+
+use crate::settings::{EgressSettings, ProxyKind, ProxySettings};
+
+/// Protection from endless response of HTTP proxy to CONNECT request
+const MAX_PROXY_RESPONSE_LEN: usize = 8 * 1024;
+
+const SOCKS5_VERSION: u8 = 5;
+const SOCKS5_NO_AUTH: u8 = 0;
+const SOCKS5_USERNAME_PASSWORD_AUTH: u8 = 2;
+const SOCKS5_USERNAME_PASSWORD_VERSION: u8 = 1;
+const SOCKS5_CONNECT_COMMAND: u8 = 1;
+const SOCKS5_IPV4_ADDRESS: u8 = 1;
+const SOCKS5_DOMAIN_ADDRESS: u8 = 3;
+const SOCKS5_IPV6_ADDRESS: u8 = 4;
+const SOCKS5_SUCCEEDED: u8 = 0;
+
+/// Opens TCP connection to host directly or through proxy from settings. Connection is bound
+/// to local address from settings if it's set
+pub async fn connect_tcp(settings: &EgressSettings, host: &str, port: u16) -> Result<TcpStream> {
+    let proxy = match &settings.proxy {
+        Some(proxy) => proxy,
+        None => return connect_from(settings.local_address, host, port).await,
+    };
+
+    let mut stream = connect_from(settings.local_address, &proxy.host, proxy.port)
+        .await
+        .with_context(|| format!("Failed to connect to proxy {}:{}", proxy.host, proxy.port))?;
+    match proxy.kind {
+        ProxyKind::Http => http_connect(&mut stream, proxy, host, port).await?,
+        ProxyKind::Socks5 => socks5_connect(&mut stream, proxy, host, port).await?,
+    }
+
+    Ok(stream)
+}
+
+async fn connect_from(local_address: Option<IpAddr>, host: &str, port: u16) -> Result<TcpStream> {
+    let addresses = lookup_host((host, port))
+        .await
+        .with_context(|| format!("Failed to resolve {host}"))?;
+
+    let mut last_error = None;
+    for address in addresses {
+        let socket = match address {
+            SocketAddr::V4(_) => TcpSocket::new_v4()?,
+            SocketAddr::V6(_) => TcpSocket::new_v6()?,
+        };
+        if let Some(local_address) = local_address {
+            if local_address.is_ipv4() != address.is_ipv4() {
+                continue;
+            }
+            socket.bind(SocketAddr::new(local_address, 0))?;
+        }
+
+        match socket.connect(address).await {
+            Ok(stream) => {
+                stream.set_nodelay(true)?;
+                return Ok(stream);
+            }
+            Err(err) => last_error = Some(err),
+        }
+    }
+
+    match last_error {
+        Some(err) => Err(err).with_context(|| format!("Failed to connect to {host}:{port}")),
+        None => bail!("There are no addresses of {host} to connect from {local_address:?}"),
+    }
+}
+
+/// Opens tunnel to host by CONNECT request to HTTP proxy
+async fn http_connect<S>(stream: &mut S, proxy: &ProxySettings, host: &str, port: u16) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if let Some(username) = &proxy.username {
+        let credentials = format!("{username}:{}", proxy.password.as_deref().unwrap_or(""));
+        write!(
+            request,
+            "Proxy-Authorization: Basic {}\r\n",
+            base64_encode(credentials.as_bytes())
+        )?;
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // response is read by single bytes, so bytes of tunneled connection aren't consumed
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() > MAX_PROXY_RESPONSE_LEN {
+            bail!("Response of proxy to CONNECT request is too long");
+        }
+        response.push(stream.read_u8().await?);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => bail!("Proxy refused to connect to {host}:{port}: {status_line}"),
+    }
+}
+
+/// Opens tunnel to host by SOCKS5 CONNECT command. Host name is resolved by proxy
+async fn socks5_connect<S>(
+    stream: &mut S,
+    proxy: &ProxySettings,
+    host: &str,
+    port: u16,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let auth_method = match proxy.username {
+        Some(_) => SOCKS5_USERNAME_PASSWORD_AUTH,
+        None => SOCKS5_NO_AUTH,
+    };
+    stream.write_all(&[SOCKS5_VERSION, 1, auth_method]).await?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply != [SOCKS5_VERSION, auth_method] {
+        bail!("SOCKS5 proxy doesn't accept authentication method {auth_method}");
+    }
+
+    if let Some(username) = &proxy.username {
+        let password = proxy.password.as_deref().unwrap_or("");
+        let mut request = vec![SOCKS5_USERNAME_PASSWORD_VERSION];
+        for field in [username.as_str(), password] {
+            let len = u8::try_from(field.len()).context("SOCKS5 credentials are too long")?;
+            request.push(len);
+            request.extend_from_slice(field.as_bytes());
+        }
+        stream.write_all(&request).await?;
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != SOCKS5_SUCCEEDED {
+            bail!("SOCKS5 proxy rejected credentials of user {username}");
+        }
+    }
+
+    let host_len = u8::try_from(host.len()).with_context(|| format!("Host {host} is too long"))?;
+    let mut request = vec![
+        SOCKS5_VERSION,
+        SOCKS5_CONNECT_COMMAND,
+        0,
+        SOCKS5_DOMAIN_ADDRESS,
+        host_len,
+    ];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != SOCKS5_SUCCEEDED {
+        bail!(
+            "SOCKS5 proxy failed to connect to {host}:{port} with reply code {}",
+            reply[1]
+        );
+    }
+
+    // bound address of proxy isn't used, but it should be read out before tunneled data
+    let address_len = match reply[3] {
+        SOCKS5_IPV4_ADDRESS => 4,
+        SOCKS5_IPV6_ADDRESS => 16,
+        SOCKS5_DOMAIN_ADDRESS => stream.read_u8().await? as usize,
+        address_type => bail!("Unknown SOCKS5 address type {address_type}"),
+    };
+    let mut bound_address = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound_address).await?;
+
+    Ok(())
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, byte)| acc | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(ALPHABET[(triple >> (18 - 6 * i) & 0x3f) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    fn proxy(kind: ProxyKind, username: Option<&str>) -> ProxySettings {
+        ProxySettings {
+            kind,
+            host: "proxy".to_owned(),
+            port: 1080,
+            username: username.map(|x| x.to_owned()),
+            password: Some("secret".to_owned()),
+        }
+    }
+
+    #[test]
+    pub fn base64_of_credentials() {
+        assert_eq!(base64_encode(b"user:secret"), "dXNlcjpzZWNyZXQ=");
+        assert_eq!(base64_encode(b"ab"), "YWI=");
+        assert_eq!(base64_encode(b"abc"), "YWJj");
+    }
+
+    #[tokio::test]
+    pub async fn http_proxy_tunnel() {
+        let (mut client, mut server) = duplex(1024);
+        let proxy = proxy(ProxyKind::Http, Some("user"));
+
+        server
+            .write_all(b"HTTP/1.1 200 Connection established\r\n\r\ntunneled")
+            .await
+            .expect("in test");
+        http_connect(&mut client, &proxy, "api.binance.com", 443)
+            .await
+            .expect("in test");
+
+        let mut tunneled = [0u8; 8];
+        client.read_exact(&mut tunneled).await.expect("in test");
+        assert_eq!(&tunneled, b"tunneled");
+
+        let mut request = vec![0u8; 128];
+        let len = server.read(&mut request).await.expect("in test");
+        let request = String::from_utf8_lossy(&request[..len]);
+        assert!(request.starts_with("CONNECT api.binance.com:443 HTTP/1.1\r\n"));
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpzZWNyZXQ=\r\n"));
+    }
+
+    #[tokio::test]
+    pub async fn socks5_proxy_tunnel_with_authentication() {
+        let (mut client, mut server) = duplex(1024);
+        let proxy = proxy(ProxyKind::Socks5, Some("user"));
+
+        let mut replies = vec![SOCKS5_VERSION, SOCKS5_USERNAME_PASSWORD_AUTH];
+        replies.extend_from_slice(&[SOCKS5_USERNAME_PASSWORD_VERSION, SOCKS5_SUCCEEDED]);
+        replies.extend_from_slice(&[SOCKS5_VERSION, SOCKS5_SUCCEEDED, 0, SOCKS5_IPV4_ADDRESS]);
+        replies.extend_from_slice(&[127, 0, 0, 1, 0x04, 0x38]);
+        server.write_all(&replies).await.expect("in test");
+
+        socks5_connect(&mut client, &proxy, "api.binance.com", 443)
+            .await
+            .expect("in test");
+
+        let mut requests = vec![0u8; 128];
+        let len = server.read(&mut requests).await.expect("in test");
+        let mut expected = vec![SOCKS5_VERSION, 1, SOCKS5_USERNAME_PASSWORD_AUTH];
+        expected.extend_from_slice(b"\x01\x04user\x06secret");
+        expected.extend_from_slice(&[SOCKS5_VERSION, SOCKS5_CONNECT_COMMAND, 0, 3, 15]);
+        expected.extend_from_slice(b"api.binance.com\x01\xbb");
+        assert_eq!(&requests[..len], expected.as_slice());
+    }
+}
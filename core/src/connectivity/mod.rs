@@ -1,7 +1,11 @@
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use thiserror::Error;
 use url::Url;
 
+use crate::settings::EgressSettings;
+
+pub mod egress;
 mod websocket;
 mod websocket_connection;
 
@@ -17,11 +21,13 @@ pub enum ConnectivityError {
     SecondaryConnectorIsNotPresent,
     #[error("not connected")]
     NotConnected,
+    #[error("failed to connect websockets of connector: `{0}`")]
+    FailedToConnectConnector(String),
 }
 
 pub type Result<T> = std::result::Result<T, ConnectivityError>;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub enum WebSocketRole {
     Main,
     Secondary,
@@ -39,11 +45,18 @@ impl Display for WebSocketRole {
 #[derive(Debug, Clone)]
 pub struct WebSocketParams {
     url: Url,
+    egress: Option<EgressSettings>,
 }
 
 impl WebSocketParams {
     pub fn new(url: Url) -> Self {
-        WebSocketParams { url }
+        WebSocketParams { url, egress: None }
+    }
+
+    /// Connection is opened through proxy and from local address of egress settings
+    pub fn with_egress(mut self, egress: Option<EgressSettings>) -> Self {
+        self.egress = egress;
+        self
     }
 }
 
@@ -1,3 +1,4 @@
+use super::egress;
 use super::{ConnectivityError, Result, WebSocketParams, WebSocketRole};
 use crate::exchanges::common::ExchangeAccountId;
 use crate::infrastructure::spawn_future_ok;
@@ -8,8 +9,8 @@ use std::fmt::Formatter;
 use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 use tokio::time::{timeout, timeout_at, Duration, Instant};
-use tokio_tungstenite::tungstenite::Message;
-use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{client_async_tls, connect_async, MaybeTlsStream, WebSocketStream};
 use tokio_util::sync::CancellationToken;
 
 /// Time interval between heartbeat pings are sent
@@ -292,6 +293,25 @@ impl ReaderHandle {
     }
 }
 
+async fn connect_websocket(
+    params: &WebSocketParams,
+) -> std::result::Result<WebSocketStream<MaybeTlsStream<TcpStream>>, tungstenite::Error> {
+    let egress = match &params.egress {
+        Some(egress) => egress,
+        None => return connect_async(params.url.clone()).await.map(|(x, _)| x),
+    };
+
+    let host = params.url.host_str().unwrap_or_default();
+    let port = params.url.port_or_known_default().unwrap_or(443);
+    let stream = egress::connect_tcp(egress, host, port)
+        .await
+        .map_err(|err| tungstenite::Error::Io(std::io::Error::other(format!("{err:?}"))))?;
+
+    client_async_tls(params.url.clone(), stream)
+        .await
+        .map(|(x, _)| x)
+}
+
 /// Open WebSocket connection.
 ///
 /// Provided cancellation token can be used to shutdown service futures instantly.
@@ -307,7 +327,7 @@ pub async fn open_connection(
     mpsc::UnboundedSender<Message>,
     mpsc::UnboundedReceiver<String>,
 )> {
-    let (ws_stream, _) = connect_async(params.url.clone())
+    let ws_stream = connect_websocket(&params)
         .await
         .map_err(|e| ConnectivityError::FailedToConnect(role, params.url.to_string(), e))?;
 
@@ -0,0 +1,324 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use futures::{SinkExt, StreamExt};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, Price, SortedOrderData};
+use crate::exchanges::events::{ExchangeEvent, TradesEvent};
+use crate::infrastructure::spawn_future;
+use crate::order_book::event::{EventType, OrderBookEvent};
+use crate::orders::event::{OrderEvent, OrderEventType};
+use crate::orders::order::{ClientOrderId, OrderFillRole, OrderSide};
+use crate::settings::DataBridgeSettings;
+
+const MESSAGES_CHANNEL_CAPACITY: usize = 10_000;
+
+/// Normalized market data and fills streamed to research clients. Messages recorded from the bridge
+/// are replayed by backtesting (see `Backtester`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BridgeMessage {
+    Trade {
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        trade_id: String,
+        price: Price,
+        quantity: Amount,
+        side: OrderSide,
+        time: DateTime,
+        /// Erroneous print which is excluded from trades used by trading logic
+        is_bad_print: bool,
+    },
+    OrderBook {
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        is_snapshot: bool,
+        asks: Vec<(Price, Amount)>,
+        bids: Vec<(Price, Amount)>,
+        time: DateTime,
+    },
+    Fill {
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        client_order_id: ClientOrderId,
+        side: OrderSide,
+        role: OrderFillRole,
+        price: Price,
+        amount: Amount,
+        time: DateTime,
+    },
+}
+
+impl BridgeMessage {
+    pub fn from_event(event: &ExchangeEvent) -> Vec<BridgeMessage> {
+        match event {
+            ExchangeEvent::Trades(trades_event) => Self::from_trades(trades_event),
+            ExchangeEvent::OrderBookEvent(order_book_event) => {
+                vec![Self::from_order_book(order_book_event)]
+            }
+            ExchangeEvent::OrderEvent(order_event) => {
+                Self::from_order_event(order_event).into_iter().collect()
+            }
+            ExchangeEvent::BalanceUpdate(_)
+            | ExchangeEvent::LiquidationPrice(_)
+            | ExchangeEvent::MarketDataStatus(_)
+            | ExchangeEvent::MarketPaused(_)
+            | ExchangeEvent::AccountAvailability(_)
+            | ExchangeEvent::QueuePosition(_) => vec![],
+        }
+    }
+
+    fn from_trades(event: &TradesEvent) -> Vec<BridgeMessage> {
+        let trades = event.trades.iter().map(|trade| (trade, false));
+        let bad_prints = event.bad_prints.iter().map(|trade| (trade, true));
+        trades
+            .chain(bad_prints)
+            .map(|(trade, is_bad_print)| BridgeMessage::Trade {
+                exchange_account_id: event.exchange_account_id,
+                currency_pair: event.currency_pair,
+                trade_id: trade.trade_id.to_string(),
+                price: trade.price,
+                quantity: trade.quantity,
+                side: trade.side,
+                time: trade.transaction_time,
+                is_bad_print,
+            })
+            .collect()
+    }
+
+    pub(crate) fn from_order_book(event: &OrderBookEvent) -> BridgeMessage {
+        let to_levels = |levels: &SortedOrderData| {
+            levels
+                .iter()
+                .map(|(&price, &amount)| (price, amount))
+                .collect()
+        };
+
+        BridgeMessage::OrderBook {
+            exchange_account_id: event.exchange_account_id,
+            currency_pair: event.currency_pair,
+            is_snapshot: matches!(event.event_type, EventType::Snapshot),
+            asks: to_levels(&event.data.asks),
+            bids: to_levels(&event.data.bids),
+            time: event.creation_time,
+        }
+    }
+
+    fn from_order_event(event: &OrderEvent) -> Option<BridgeMessage> {
+        let cloned_order = match &event.event_type {
+            OrderEventType::OrderFilled { cloned_order } => cloned_order,
+            _ => return None,
+        };
+
+        let fill = cloned_order.fills.fills.last()?;
+        Some(BridgeMessage::Fill {
+            exchange_account_id: cloned_order.header.exchange_account_id,
+            currency_pair: cloned_order.header.currency_pair,
+            client_order_id: cloned_order.header.client_order_id.clone(),
+            side: fill.side().unwrap_or(cloned_order.header.side),
+            role: fill.role(),
+            price: fill.price(),
+            amount: fill.amount(),
+            time: fill.receive_time(),
+        })
+    }
+}
+
+/// Read-only websocket endpoint streaming normalized trades, order books and fills of running engine
+/// as JSON messages, so researchers can tap production data without touching trading code paths.
+/// Incoming messages from clients are ignored. Slow clients skip messages instead of slowing engine down
+pub struct DataBridge {
+    messages_sender: broadcast::Sender<Arc<str>>,
+}
+
+impl DataBridge {
+    pub fn start(
+        settings: &DataBridgeSettings,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Arc<Self> {
+        let (messages_sender, _) = broadcast::channel(MESSAGES_CHANNEL_CAPACITY);
+        let data_bridge = Arc::new(DataBridge { messages_sender });
+
+        let _ = spawn_future(
+            "Data bridge events handling",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            data_bridge.clone().handle_events(events_receiver),
+        );
+        let _ = spawn_future(
+            "Data bridge listening",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            data_bridge.clone().listen(settings.address.clone()),
+        );
+
+        data_bridge
+    }
+
+    async fn handle_events(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Result<()> {
+        loop {
+            let event = match events_receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Data bridge skipped {skipped} exchange events");
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            // serialization is skipped while nobody is connected
+            if self.messages_sender.receiver_count() == 0 {
+                continue;
+            }
+
+            for message in BridgeMessage::from_event(&event) {
+                let message = serde_json::to_string(&message)
+                    .context("Unable to serialize data bridge message")?;
+                let _ = self.messages_sender.send(message.into());
+            }
+        }
+    }
+
+    async fn listen(self: Arc<Self>, address: String) -> Result<()> {
+        let listener = TcpListener::bind(&address)
+            .await
+            .with_context(|| format!("Unable to bind data bridge to {address}"))?;
+        log::info!("Data bridge is listening on {address}");
+
+        loop {
+            let (stream, peer_address) = listener
+                .accept()
+                .await
+                .context("Unable to accept data bridge connection")?;
+
+            log::info!("Data bridge client {peer_address} connected");
+            let _ = spawn_future(
+                "Data bridge client",
+                SpawnFutureFlags::STOP_BY_TOKEN,
+                serve_client(stream, self.messages_sender.subscribe()),
+            );
+        }
+    }
+}
+
+async fn serve_client(
+    stream: TcpStream,
+    mut messages_receiver: broadcast::Receiver<Arc<str>>,
+) -> Result<()> {
+    let mut websocket = tokio_tungstenite::accept_async(stream)
+        .await
+        .context("Data bridge websocket handshake failed")?;
+
+    loop {
+        tokio::select! {
+            message = messages_receiver.recv() => {
+                let message = match message {
+                    Ok(message) => message,
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Data bridge client lagged and skipped {skipped} messages");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                };
+
+                websocket
+                    .send(Message::Text(message.to_string()))
+                    .await
+                    .context("Unable to send message to data bridge client")?;
+            }
+            incoming = websocket.next() => match incoming {
+                None | Some(Ok(Message::Close(_))) => {
+                    log::info!("Data bridge client disconnected");
+                    return Ok(());
+                }
+                Some(Err(err)) => return Err(err).context("Data bridge client connection error"),
+                // bridge is read-only, so client messages are ignored
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::events::{TickDirection, Trade, TradeId};
+    use crate::order_book_data;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", 0)
+    }
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    #[test]
+    pub fn trades_are_normalized() {
+        let event = ExchangeEvent::Trades(TradesEvent {
+            exchange_account_id: exchange_account_id(),
+            currency_pair: currency_pair(),
+            trades: vec![Trade {
+                trade_id: TradeId::Number(42),
+                price: dec!(100),
+                quantity: dec!(1.5),
+                side: OrderSide::Sell,
+                transaction_time: Utc::now(),
+                tick_direction: TickDirection::None,
+            }],
+            bad_prints: Vec::new(),
+            receipt_time: Utc::now(),
+        });
+
+        let messages = BridgeMessage::from_event(&event);
+        assert_eq!(messages.len(), 1);
+
+        let json = serde_json::to_value(&messages[0]).expect("in test");
+        assert_eq!(json["type"], "trade");
+        assert_eq!(json["trade_id"], "42");
+        assert_eq!(json["exchange_account_id"], "Binance_0");
+        assert_eq!(json["side"], "Sell");
+        assert_eq!(json["is_bad_print"], false);
+    }
+
+    #[test]
+    pub fn order_book_is_normalized() {
+        let event = ExchangeEvent::OrderBookEvent(OrderBookEvent::new(
+            Utc::now(),
+            exchange_account_id(),
+            currency_pair(),
+            String::new(),
+            EventType::Snapshot,
+            Arc::new(order_book_data![
+                dec!(101) => dec!(1),
+                ;
+                dec!(100) => dec!(2),
+            ]),
+        ));
+
+        let messages = BridgeMessage::from_event(&event);
+        match &messages[..] {
+            [BridgeMessage::OrderBook {
+                is_snapshot,
+                asks,
+                bids,
+                ..
+            }] => {
+                assert!(is_snapshot);
+                assert_eq!(asks, &vec![(dec!(101), dec!(1))]);
+                assert_eq!(bids, &vec![(dec!(100), dec!(2))]);
+            }
+            _ => panic!("Unexpected messages {messages:?}"),
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use mmb_database::impl_event;
+use mmb_database::postgres_db::events::TableName;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::database::events::recorder::EventRecorder;
+use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, Price};
+use crate::exchanges::events::ExchangeEvent;
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+use crate::orders::event::{OrderEvent, OrderEventType};
+use crate::orders::order::{ClientOrderId, ExchangeOrderId, OrderSide};
+use crate::settings::AuditLogSettings;
+
+/// Previous hash of the first record in audit log
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum OrderAuditEvent {
+    Created,
+    CreationFailed,
+    AckTimeout,
+    Filled,
+    Completed,
+    Canceled,
+    CancellationFailed,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AuditAction {
+    Order {
+        event: OrderAuditEvent,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        client_order_id: ClientOrderId,
+        exchange_order_id: Option<ExchangeOrderId>,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        filled_amount: Amount,
+    },
+    /// Action of operator via control API
+    ManualIntervention { action: String, details: String },
+    /// Incident detected by engine which requires attention of operator
+    Incident { kind: String, details: String },
+}
+
+impl AuditAction {
+    fn from_order_event(event: &OrderEvent) -> Self {
+        let event_type = match event.event_type {
+            OrderEventType::CreateOrderSucceeded => OrderAuditEvent::Created,
+            OrderEventType::CreateOrderFailed => OrderAuditEvent::CreationFailed,
+            OrderEventType::AckTimeout => OrderAuditEvent::AckTimeout,
+            OrderEventType::OrderFilled { .. } => OrderAuditEvent::Filled,
+            OrderEventType::OrderCompleted { .. } => OrderAuditEvent::Completed,
+            OrderEventType::CancelOrderSucceeded => OrderAuditEvent::Canceled,
+            OrderEventType::CancelOrderFailed => OrderAuditEvent::CancellationFailed,
+        };
+        let order = &event.order;
+
+        AuditAction::Order {
+            event: event_type,
+            exchange_account_id: order.exchange_account_id(),
+            currency_pair: order.currency_pair(),
+            client_order_id: order.client_order_id(),
+            exchange_order_id: order.exchange_order_id(),
+            side: order.side(),
+            price: order.price(),
+            amount: order.amount(),
+            filled_amount: order.filled_amount(),
+        }
+    }
+}
+
+/// Record of audit log. Hash is calculated over all other fields including hash of the previous
+/// record, so any change of record breaks the chain
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub sequence: u64,
+    pub time: DateTime,
+    pub action: AuditAction,
+    pub previous_hash: String,
+    pub hash: String,
+}
+
+impl_event!(&AuditRecord, "audit_log");
+
+/// Hash of record json without `hash` field. Json object keys are ordered the same way on
+/// writing and on verification, so hash doesn't depend on fields order in file
+fn calculate_hash(record: &JsonValue) -> Result<String> {
+    let mut record = record.clone();
+    match record.as_object_mut() {
+        Some(fields) => {
+            let _ = fields.remove("hash");
+        }
+        None => bail!("Audit record should be json object: {record}"),
+    }
+
+    let json = serde_json::to_string(&record).context("Unable to serialize audit record")?;
+    Ok(hex::encode(Sha256::digest(json.as_bytes())))
+}
+
+struct ChainHead {
+    file: File,
+    sequence: u64,
+    last_hash: String,
+}
+
+/// Append-only audit log of order actions and manual interventions. Every record includes hash of
+/// the previous one, so tampering with file is detected by `verify_audit_log`.
+/// Records are also saved to database
+pub struct AuditLog {
+    head: Mutex<ChainHead>,
+    event_recorder: Arc<EventRecorder>,
+}
+
+impl AuditLog {
+    /// Opens audit log file and verifies existing records before continuing the chain
+    pub fn open(
+        settings: &AuditLogSettings,
+        event_recorder: Arc<EventRecorder>,
+    ) -> Result<Arc<Self>> {
+        let (sequence, last_hash) = match File::open(&settings.path) {
+            Ok(file) => {
+                let last_record = verify_audit_log(BufReader::new(file)).with_context(|| {
+                    format!("Audit log {} is corrupted", settings.path.display())
+                })?;
+                match last_record {
+                    Some(record) => (record.sequence + 1, record.hash),
+                    None => (0, GENESIS_HASH.to_owned()),
+                }
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => (0, GENESIS_HASH.to_owned()),
+            Err(error) => {
+                return Err(error).with_context(|| {
+                    format!("Unable to read audit log {}", settings.path.display())
+                })
+            }
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&settings.path)
+            .with_context(|| format!("Unable to open audit log {}", settings.path.display()))?;
+
+        Ok(Arc::new(AuditLog {
+            head: Mutex::new(ChainHead {
+                file,
+                sequence,
+                last_hash,
+            }),
+            event_recorder,
+        }))
+    }
+
+    pub fn record(&self, action: AuditAction) -> Result<()> {
+        let mut head = self.head.lock();
+
+        let mut record = AuditRecord {
+            sequence: head.sequence,
+            time: time_manager::now(),
+            action,
+            previous_hash: head.last_hash.clone(),
+            hash: String::new(),
+        };
+        record.hash = calculate_hash(&serde_json::to_value(&record)?)?;
+
+        let line = serde_json::to_string(&record).context("Unable to serialize audit record")?;
+        writeln!(head.file, "{line}").context("Unable to write audit record")?;
+        head.file.flush().context("Unable to flush audit log")?;
+
+        head.sequence += 1;
+        head.last_hash = record.hash.clone();
+        drop(head);
+
+        self.event_recorder.save(&record)
+    }
+
+    pub fn record_manual_intervention(&self, action: &str, details: String) {
+        let action = AuditAction::ManualIntervention {
+            action: action.to_owned(),
+            details,
+        };
+        if let Err(error) = self.record(action) {
+            log::error!("Failed to record manual intervention to audit log: {error:?}");
+        }
+    }
+
+    pub fn record_incident(&self, kind: &str, details: String) {
+        let action = AuditAction::Incident {
+            kind: kind.to_owned(),
+            details,
+        };
+        if let Err(error) = self.record(action) {
+            log::error!("Failed to record incident to audit log: {error:?}");
+        }
+    }
+
+    /// Order events are recorded until events channel is closed, so actions during graceful
+    /// shutdown are recorded too
+    pub(crate) fn start(self: &Arc<Self>, events_receiver: broadcast::Receiver<ExchangeEvent>) {
+        let _ = spawn_future(
+            "Audit log of order events",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            self.clone().record_order_events(events_receiver),
+        );
+    }
+
+    async fn record_order_events(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Result<()> {
+        loop {
+            match events_receiver.recv().await {
+                Ok(ExchangeEvent::OrderEvent(event)) => {
+                    if let Err(error) = self.record(AuditAction::from_order_event(&event)) {
+                        log::error!("Failed to record order event to audit log: {error:?}");
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    // gap in audit log can't be restored, so it's recorded explicitly
+                    log::error!("Audit log skipped {skipped} exchange events");
+                    self.record_manual_intervention(
+                        "skipped_events",
+                        format!("{skipped} exchange events are not recorded"),
+                    );
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Verifies sequence numbers and hash chain of audit log. Returns the last record or `None` if
+/// audit log is empty
+pub fn verify_audit_log(reader: impl BufRead) -> Result<Option<AuditRecord>> {
+    let mut last_record: Option<AuditRecord> = None;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.with_context(|| format!("Unable to read line {line_number}"))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let json: JsonValue = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid json on line {line_number}"))?;
+        let record: AuditRecord = serde_json::from_value(json.clone())
+            .with_context(|| format!("Invalid audit record on line {line_number}"))?;
+
+        let (expected_sequence, expected_previous_hash) = match &last_record {
+            Some(last) => (last.sequence + 1, last.hash.as_str()),
+            None => (0, GENESIS_HASH),
+        };
+        if record.sequence != expected_sequence {
+            bail!(
+                "Record on line {line_number} has sequence {} but {expected_sequence} is expected",
+                record.sequence
+            );
+        }
+        if record.previous_hash != expected_previous_hash {
+            bail!("Record on line {line_number} isn't chained to the previous record");
+        }
+        if record.hash != calculate_hash(&json)? {
+            bail!("Record on line {line_number} was modified: hash mismatch");
+        }
+
+        last_record = Some(record);
+    }
+
+    Ok(last_record)
+}
+
+pub fn verify_audit_log_file(path: &Path) -> Result<Option<AuditRecord>> {
+    let file =
+        File::open(path).with_context(|| format!("Unable to open audit log {}", path.display()))?;
+    verify_audit_log(BufReader::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    async fn write_records(path: &Path, count: usize) -> Vec<String> {
+        let event_recorder = EventRecorder::start(None).await.expect("in test");
+        let settings = AuditLogSettings {
+            path: path.to_path_buf(),
+        };
+
+        let audit_log = AuditLog::open(&settings, event_recorder).expect("in test");
+        for i in 0..count {
+            audit_log.record_manual_intervention("pause_market", format!("market {i}"));
+        }
+
+        std::fs::read_to_string(path)
+            .expect("in test")
+            .lines()
+            .map(|x| x.to_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn chain_is_continued_after_reopening() {
+        let path = std::env::temp_dir().join(format!("audit_log_{}.jsonl", uuid::Uuid::new_v4()));
+
+        let _ = write_records(&path, 2).await;
+        let lines = write_records(&path, 1).await;
+
+        let last_record = verify_audit_log_file(&path)
+            .expect("in test")
+            .expect("in test");
+        assert_eq!(lines.len(), 3);
+        assert_eq!(last_record.sequence, 2);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn tampering_is_detected() {
+        let path = std::env::temp_dir().join(format!("audit_log_{}.jsonl", uuid::Uuid::new_v4()));
+        let lines = write_records(&path, 3).await;
+        let _ = std::fs::remove_file(&path);
+
+        let modified = lines
+            .iter()
+            .map(|x| x.replace("market 1", "market 5"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let error = verify_audit_log(Cursor::new(modified)).expect_err("in test");
+        assert!(error.to_string().contains("line 2 was modified"));
+
+        let removed = [lines[0].clone(), lines[2].clone()].join("\n");
+        let error = verify_audit_log(Cursor::new(removed)).expect_err("in test");
+        assert!(error.to_string().contains("line 2 has sequence 2"));
+    }
+}
@@ -24,6 +24,12 @@ const SAVING_TIMEOUT: Duration = Duration::from_secs(1);
 /// timeout for checking connection health is 5 sec therefore timeout for restoring events should be significant bigger
 const RESTORING_EVENTS_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Custom storage of recorded events instead of database, e.g. storage of application
+/// which embeds engine
+pub trait EventSink: Send + Sync + 'static {
+    fn save(&self, table_name: TableName, event: InsertEvent) -> Result<()>;
+}
+
 pub struct DbSettings {
     pub database_url: String,
     pub postponed_events_dir: Option<PathBuf>,
@@ -88,6 +94,26 @@ impl EventRecorder {
         }))
     }
 
+    /// Events are saved to `sink` instead of database
+    pub fn start_with_sink(sink: Arc<dyn EventSink>) -> Arc<EventRecorder> {
+        let (data_tx, data_rx) = mpsc::channel(20_000);
+        let (shutdown_signal_tx, shutdown_signal_rx) = mpsc::unbounded_channel();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+        let _ = spawn_future(
+            "start sink event recorder",
+            SpawnFutureFlags::DENY_CANCELLATION | SpawnFutureFlags::STOP_BY_TOKEN,
+            start_sink_event_recorder(sink, data_rx, shutdown_signal_rx, shutdown_tx),
+        );
+        print_info("EventRecorder started with custom event sink");
+
+        Arc::new(Self {
+            data_tx,
+            shutdown_signal_tx,
+            shutdown_rx: Mutex::new(Some(shutdown_rx)),
+        })
+    }
+
     pub fn save(&self, event: impl Event) -> Result<()> {
         let table_name = event.get_table_name();
 
@@ -120,6 +146,37 @@ impl EventRecorder {
     }
 }
 
+async fn start_sink_event_recorder(
+    sink: Arc<dyn EventSink>,
+    mut data_rx: mpsc::Receiver<(TableName, InsertEvent)>,
+    mut shutdown_signal_rx: mpsc::UnboundedReceiver<()>,
+    shutdown_tx: oneshot::Sender<Result<()>>,
+) -> Result<()> {
+    let save = |table_name: TableName, event: InsertEvent| {
+        if let Err(err) = sink.save(table_name, event) {
+            log::error!("Failed to save event to {table_name} by event sink: {err:?}");
+        }
+    };
+
+    loop {
+        tokio::select! {
+            _ = shutdown_signal_rx.recv() => break,
+            result = data_rx.recv() => match result {
+                Some((table_name, event)) => save(table_name, event),
+                None => break,
+            },
+        }
+    }
+
+    while let Ok((table_name, event)) = data_rx.try_recv() {
+        save(table_name, event);
+    }
+
+    let _ = shutdown_tx.send(Ok(()));
+
+    Ok(())
+}
+
 async fn start_postponed_events_restoring(
     pool: PgPool,
     fallback: EventRecorderFallback,
@@ -275,11 +332,12 @@ async fn save_to_file(
 
 #[cfg(test)]
 mod tests {
-    use crate::database::events::recorder::{DbSettings, EventRecorder};
+    use super::*;
     use crate::infrastructure::init_lifetime_manager;
     use mmb_database::impl_event;
     use mmb_database::postgres_db::events::TableName;
     use serde::{Deserialize, Serialize};
+    use serde_json::json;
     use std::time::{Duration, Instant};
     use tokio::time::sleep;
     use tokio_postgres::{Client, NoTls};
@@ -436,4 +494,43 @@ mod tests {
 
         truncate_table(&client).await;
     }
+
+    #[derive(Default)]
+    struct VecEventSink {
+        events: Mutex<Vec<(TableName, InsertEvent)>>,
+    }
+
+    impl EventSink for VecEventSink {
+        fn save(&self, table_name: TableName, event: InsertEvent) -> Result<()> {
+            self.events.lock().push((table_name, event));
+            Ok(())
+        }
+    }
+
+    struct TestEvent;
+
+    impl Event for TestEvent {
+        fn get_table_name(&self) -> TableName {
+            "test_events"
+        }
+
+        fn get_json(&self) -> serde_json::Result<serde_json::Value> {
+            Ok(json!({ "value": 1 }))
+        }
+    }
+
+    #[tokio::test]
+    async fn events_are_saved_to_custom_sink() {
+        let _ = init_lifetime_manager();
+        let sink = Arc::new(VecEventSink::default());
+        let recorder = EventRecorder::start_with_sink(sink.clone());
+
+        recorder.save(TestEvent).expect("in test");
+        recorder.flush_and_stop().await.expect("in test");
+
+        let events = sink.events.lock();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, "test_events");
+        assert_eq!(events[0].1.json, json!({ "value": 1 }));
+    }
 }
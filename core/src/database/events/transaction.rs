@@ -1,10 +1,16 @@
+use crate::database::events::recorder::EventRecorder;
 use crate::exchanges::common::{Amount, ExchangeId, MarketId, Price};
 use crate::misc::time::time_manager;
-use crate::orders::order::{ExchangeOrderId, OrderSide};
+use crate::orders::order::{ClientOrderId, ExchangeOrderId, OrderSide, OrderSnapshot};
+use anyhow::{bail, Result};
+use dashmap::DashMap;
 use mmb_database::impl_event;
 use mmb_database::postgres_db::events::TableName;
 use mmb_utils::DateTime;
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +56,18 @@ impl TransactionStatus {
         use TransactionStatus::*;
         matches!(self, StopLoss | Finished)
     }
+
+    /// Saving new revision with the same status is allowed until transaction is finished
+    pub fn can_change_to(&self, next: TransactionStatus) -> bool {
+        use TransactionStatus::*;
+        match self {
+            New => true,
+            Hedging => !matches!(next, New),
+            Trailing => matches!(next, Trailing | Timeout | StopLoss | Finished),
+            Timeout => matches!(next, Timeout | Hedging | StopLoss | Finished),
+            StopLoss | Finished => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +85,9 @@ pub struct TransactionSnapshot {
     pub hedged: Option<Amount>,
     pub profit_loss_pct: Option<Amount>,
     pub trades: Vec<TransactionTrade>,
+    /// Orders created in scope of transaction
+    #[serde(default)]
+    pub orders: Vec<ClientOrderId>,
 }
 
 impl_event!(&mut TransactionSnapshot, "transactions");
@@ -93,6 +114,7 @@ impl TransactionSnapshot {
             hedged: None,
             profit_loss_pct: None,
             trades: vec![],
+            orders: vec![],
         }
     }
 
@@ -116,13 +138,22 @@ impl TransactionSnapshot {
 pub mod transaction_service {
     use crate::database::events::recorder::EventRecorder;
     use crate::database::events::transaction::{TransactionSnapshot, TransactionStatus};
-    use anyhow::Context;
+    use anyhow::{bail, Context};
 
     pub fn save(
         transaction: &mut TransactionSnapshot,
         status: TransactionStatus,
         event_recorder: &EventRecorder,
     ) -> anyhow::Result<()> {
+        if transaction.revisions() > 1 && !transaction.status.can_change_to(status) {
+            bail!(
+                "Transaction {} can't change status from {:?} to {:?}",
+                transaction.transaction_id(),
+                transaction.status,
+                status
+            );
+        }
+
         transaction.status = status;
         transaction.increment_revision();
 
@@ -131,3 +162,217 @@ pub mod transaction_service {
             .context("in transaction_service::save()")
     }
 }
+
+/// Groups related orders and fills of strategy action (e.g. quote and hedge) in transactions.
+/// Every change of transaction is saved to database as new revision
+pub struct TransactionsService {
+    event_recorder: Arc<EventRecorder>,
+    active: Mutex<HashMap<TransactionId, TransactionSnapshot>>,
+    by_order: DashMap<ClientOrderId, TransactionId>,
+}
+
+impl TransactionsService {
+    pub fn new(event_recorder: Arc<EventRecorder>) -> Arc<Self> {
+        Arc::new(TransactionsService {
+            event_recorder,
+            active: Default::default(),
+            by_order: Default::default(),
+        })
+    }
+
+    pub fn create(
+        &self,
+        market_id: MarketId,
+        side: OrderSide,
+        price: Option<Price>,
+        amount: Amount,
+        strategy_name: String,
+    ) -> Result<TransactionId> {
+        let status = TransactionStatus::New;
+        let mut transaction =
+            TransactionSnapshot::new(market_id, side, price, amount, status, strategy_name);
+        let transaction_id = transaction.transaction_id();
+
+        transaction_service::save(&mut transaction, status, &self.event_recorder)?;
+        let _ = self.active.lock().insert(transaction_id, transaction);
+
+        Ok(transaction_id)
+    }
+
+    pub fn get(&self, transaction_id: TransactionId) -> Option<TransactionSnapshot> {
+        self.active.lock().get(&transaction_id).cloned()
+    }
+
+    pub fn find_by_order(&self, client_order_id: &ClientOrderId) -> Option<TransactionId> {
+        self.by_order.get(client_order_id).map(|x| *x.value())
+    }
+
+    pub fn link_order(
+        &self,
+        transaction_id: TransactionId,
+        client_order_id: ClientOrderId,
+    ) -> Result<()> {
+        self.update(transaction_id, None, |transaction| {
+            transaction.orders.push(client_order_id.clone())
+        })?;
+
+        let _ = self.by_order.insert(client_order_id, transaction_id);
+        Ok(())
+    }
+
+    /// Add last fill of order to trades of transaction which the order is linked to
+    pub fn add_fill(&self, order: &OrderSnapshot) -> Result<()> {
+        let client_order_id = order.client_order_id();
+        let transaction_id = match self.find_by_order(&client_order_id) {
+            None => bail!("Order {client_order_id} is not linked to any transaction"),
+            Some(v) => v,
+        };
+
+        let fill = match order.fills.fills.last() {
+            None => bail!("Order {client_order_id} has no fills"),
+            Some(v) => v,
+        };
+
+        let exchange_order_id = match &order.props.exchange_order_id {
+            None => bail!("`exchange_order_id` must be set for order {client_order_id} before adding fill to transaction"),
+            Some(v) => v.clone(),
+        };
+
+        let trade = TransactionTrade {
+            exchange_order_id,
+            exchange_id: order.header.exchange_account_id.exchange_id,
+            price: Some(fill.price()),
+            amount: fill.amount(),
+            side: fill.side(),
+        };
+
+        self.update(transaction_id, None, |transaction| {
+            transaction.trades.push(trade)
+        })
+    }
+
+    pub fn set_status(
+        &self,
+        transaction_id: TransactionId,
+        status: TransactionStatus,
+    ) -> Result<()> {
+        self.update(transaction_id, Some(status), |_| {})
+    }
+
+    fn update(
+        &self,
+        transaction_id: TransactionId,
+        status: Option<TransactionStatus>,
+        action: impl FnOnce(&mut TransactionSnapshot),
+    ) -> Result<()> {
+        let mut active = self.active.lock();
+        let transaction = match active.get_mut(&transaction_id) {
+            None => bail!("Transaction {transaction_id} is not found among active transactions"),
+            Some(v) => v,
+        };
+
+        let status = status.unwrap_or(transaction.status);
+        if !transaction.status.can_change_to(status) {
+            bail!(
+                "Transaction {transaction_id} can't change status from {:?} to {status:?}",
+                transaction.status
+            );
+        }
+
+        // active transaction is kept unchanged if new revision isn't saved
+        let mut updated = transaction.clone();
+        action(&mut updated);
+        transaction_service::save(&mut updated, status, &self.event_recorder)?;
+
+        if status.is_finished() {
+            let _ = active.remove(&transaction_id);
+            for client_order_id in &updated.orders {
+                let _ = self.by_order.remove(client_order_id);
+            }
+        } else {
+            *transaction = updated;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use rust_decimal_macros::dec;
+
+    async fn transactions_service() -> Arc<TransactionsService> {
+        let event_recorder = EventRecorder::start(None).await.expect("in test");
+        TransactionsService::new(event_recorder)
+    }
+
+    fn market_id() -> MarketId {
+        MarketId::new(
+            "Binance".into(),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    #[tokio::test]
+    async fn transaction_lifecycle() {
+        let service = transactions_service().await;
+
+        let transaction_id = service
+            .create(
+                market_id(),
+                OrderSide::Buy,
+                Some(dec!(1000)),
+                dec!(1),
+                "test".to_string(),
+            )
+            .expect("in test");
+
+        let client_order_id = ClientOrderId::unique_id();
+        service
+            .link_order(transaction_id, client_order_id.clone())
+            .expect("in test");
+        assert_eq!(
+            service.find_by_order(&client_order_id),
+            Some(transaction_id)
+        );
+
+        service
+            .set_status(transaction_id, TransactionStatus::Hedging)
+            .expect("in test");
+
+        let transaction = service.get(transaction_id).expect("in test");
+        assert_eq!(transaction.revisions(), 4);
+        assert_eq!(transaction.orders, vec![client_order_id.clone()]);
+
+        service
+            .set_status(transaction_id, TransactionStatus::Finished)
+            .expect("in test");
+
+        assert!(service.get(transaction_id).is_none());
+        assert_eq!(service.find_by_order(&client_order_id), None);
+    }
+
+    #[tokio::test]
+    async fn unable_to_return_to_new_status() {
+        let service = transactions_service().await;
+
+        let transaction_id = service
+            .create(
+                market_id(),
+                OrderSide::Sell,
+                None,
+                dec!(1),
+                "test".to_string(),
+            )
+            .expect("in test");
+
+        service
+            .set_status(transaction_id, TransactionStatus::Hedging)
+            .expect("in test");
+
+        let result = service.set_status(transaction_id, TransactionStatus::New);
+        assert!(result.is_err());
+    }
+}
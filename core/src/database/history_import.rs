@@ -0,0 +1,354 @@
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, TimeZone, Utc};
+use hyper::{StatusCode, Uri};
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use zip::ZipArchive;
+
+use crate::database::parquet_writer::{Candle, ParquetDatasetWriter};
+use crate::exchanges::common::MarketId;
+use crate::exchanges::events::{TickDirection, Trade, TradeId};
+use crate::exchanges::rest_client::create_client;
+use crate::orders::order::OrderSide;
+
+pub const BINANCE_DATA_HOST: &str = "https://data.binance.vision";
+
+/// Timestamps greater than this value are in microseconds (Binance switched spot dumps to them in 2025)
+const MAX_TIMESTAMP_MILLIS: i64 = 100_000_000_000_000;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum HistoryKind {
+    /// Aggregated public trades
+    AggTrades,
+    /// Candles of interval in Binance notation, e.g. `1m` or `1h`
+    Klines { interval: String },
+}
+
+/// Url of daily dump of Binance spot market data, `symbol` is in exchange notation (e.g. `BTCUSDT`)
+pub fn binance_daily_dump_url(kind: &HistoryKind, symbol: &str, date: NaiveDate) -> String {
+    let date = date.format("%Y-%m-%d");
+    match kind {
+        HistoryKind::AggTrades => format!(
+            "{BINANCE_DATA_HOST}/data/spot/daily/aggTrades/{symbol}/{symbol}-aggTrades-{date}.zip"
+        ),
+        HistoryKind::Klines { interval } => format!(
+            "{BINANCE_DATA_HOST}/data/spot/daily/klines/{symbol}/{interval}/{symbol}-{interval}-{date}.zip"
+        ),
+    }
+}
+
+/// Parses Binance aggTrades csv:
+/// `agg_trade_id,price,quantity,first_trade_id,last_trade_id,transact_time,is_buyer_maker,is_best_match`
+pub fn parse_agg_trades(content: &str) -> Result<Vec<Trade>> {
+    csv_rows(content)
+        .map(|(line_number, fields)| {
+            parse_agg_trade(&fields)
+                .with_context(|| format!("Unable to parse aggTrades line {line_number}"))
+        })
+        .collect()
+}
+
+fn parse_agg_trade(fields: &[&str]) -> Result<Trade> {
+    if fields.len() < 7 {
+        bail!("Expected at least 7 fields but got {}", fields.len());
+    }
+
+    // taker of trade is seller when buyer is maker
+    let side = match fields[6].to_lowercase().as_str() {
+        "true" => OrderSide::Sell,
+        "false" => OrderSide::Buy,
+        other => bail!("Unexpected is_buyer_maker value '{other}'"),
+    };
+
+    Ok(Trade {
+        trade_id: TradeId::Number(fields[0].parse()?),
+        price: parse_decimal(fields[1])?,
+        quantity: parse_decimal(fields[2])?,
+        side,
+        transaction_time: parse_time(fields[5])?,
+        tick_direction: TickDirection::None,
+    })
+}
+
+/// Parses Binance klines csv:
+/// `open_time,open,high,low,close,volume,close_time,quote_volume,count,taker_buy_volume,taker_buy_quote_volume,ignore`
+pub fn parse_klines(content: &str) -> Result<Vec<Candle>> {
+    csv_rows(content)
+        .map(|(line_number, fields)| {
+            parse_kline(&fields)
+                .with_context(|| format!("Unable to parse klines line {line_number}"))
+        })
+        .collect()
+}
+
+fn parse_kline(fields: &[&str]) -> Result<Candle> {
+    if fields.len() < 9 {
+        bail!("Expected at least 9 fields but got {}", fields.len());
+    }
+
+    Ok(Candle {
+        open_time: parse_time(fields[0])?,
+        open: parse_decimal(fields[1])?,
+        high: parse_decimal(fields[2])?,
+        low: parse_decimal(fields[3])?,
+        close: parse_decimal(fields[4])?,
+        volume: parse_decimal(fields[5])?,
+        trades_count: fields[8].parse()?,
+    })
+}
+
+/// Non empty lines with their numbers. Header line is skipped if dump has it
+fn csv_rows(content: &str) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter(|(index, line)| *index != 0 || line.starts_with(|c: char| c.is_ascii_digit()))
+        .map(|(index, line)| (index + 1, line.split(',').map(str::trim).collect()))
+}
+
+fn parse_decimal(value: &str) -> Result<Decimal> {
+    value
+        .parse()
+        .with_context(|| format!("Unable to parse decimal from '{value}'"))
+}
+
+fn parse_time(value: &str) -> Result<DateTime> {
+    let timestamp: i64 = value
+        .parse()
+        .with_context(|| format!("Unable to parse timestamp from '{value}'"))?;
+
+    Ok(match timestamp > MAX_TIMESTAMP_MILLIS {
+        true => Utc.timestamp_nanos(timestamp * 1_000),
+        false => Utc.timestamp_millis(timestamp),
+    })
+}
+
+/// Content of csv dump. Zip archives are unpacked, the first file of archive is read
+pub fn read_dump(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    unpack_dump(bytes).with_context(|| format!("Failed to unpack {}", path.display()))
+}
+
+fn unpack_dump(bytes: Vec<u8>) -> Result<String> {
+    let is_zip = bytes.starts_with(b"PK");
+    if !is_zip {
+        return Ok(String::from_utf8(bytes)?);
+    }
+
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let mut file = archive.by_index(0)?;
+    let mut content = String::with_capacity(file.size() as usize);
+    let _ = file.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Returns `None` if dump isn't published (e.g. date is before listing of symbol)
+pub async fn download_dump(url: &str) -> Result<Option<String>> {
+    let uri: Uri = url
+        .parse()
+        .with_context(|| format!("Invalid dump url {url}"))?;
+    let response = create_client()
+        .get(uri)
+        .await
+        .with_context(|| format!("Failed to download {url}"))?;
+
+    let status = response.status();
+    if status == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        bail!("Failed to download {url}: status {status}");
+    }
+
+    let bytes = hyper::body::to_bytes(response.into_body()).await?;
+    unpack_dump(bytes.to_vec())
+        .map(Some)
+        .with_context(|| format!("Failed to unpack {url}"))
+}
+
+/// Imports historical market data to storage of recorder, so backtests can run on long histories
+/// without recording data first
+pub struct HistoryImporter {
+    writer: ParquetDatasetWriter,
+}
+
+impl HistoryImporter {
+    pub fn new(writer: ParquetDatasetWriter) -> Self {
+        HistoryImporter { writer }
+    }
+
+    /// Imports local csv or zip dump in Binance format
+    pub fn import_dump(
+        &self,
+        market_id: MarketId,
+        kind: &HistoryKind,
+        path: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        let content = read_dump(path)?;
+        self.write(market_id, kind, &content)
+    }
+
+    /// Downloads daily dumps of Binance public data for dates from `from` to `to` inclusive.
+    /// Dates without published dump are skipped
+    pub async fn download_binance(
+        &self,
+        market_id: MarketId,
+        kind: &HistoryKind,
+        symbol: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<PathBuf>> {
+        let mut paths = vec![];
+        let mut date = from;
+        while date <= to {
+            let url = binance_daily_dump_url(kind, symbol, date);
+            match download_dump(&url).await? {
+                Some(content) => {
+                    paths.extend(self.write(market_id, kind, &content)?);
+                    log::info!("Historical data {kind:?} of {symbol} for {date} is imported");
+                }
+                None => log::warn!("Historical data {kind:?} of {symbol} for {date} isn't found"),
+            }
+
+            date = date.succ();
+        }
+
+        Ok(paths)
+    }
+
+    fn write(
+        &self,
+        market_id: MarketId,
+        kind: &HistoryKind,
+        content: &str,
+    ) -> Result<Vec<PathBuf>> {
+        match kind {
+            HistoryKind::AggTrades => self
+                .writer
+                .write_trades(market_id, &parse_agg_trades(content)?),
+            HistoryKind::Klines { .. } => self
+                .writer
+                .write_candles(market_id, &parse_klines(content)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use rust_decimal_macros::dec;
+    use std::io::Write;
+    use uuid::Uuid;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    const AGG_TRADES: &str = "\
+agg_trade_id,price,quantity,first_trade_id,last_trade_id,transact_time,is_buyer_maker,is_best_match
+1,19000.10,0.5,10,11,1666224000000,true,true
+2,19000.20,1.5,12,12,1666224000000123,False,True
+";
+
+    fn market_id() -> MarketId {
+        MarketId::new(
+            "Binance".into(),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    #[test]
+    pub fn dump_url() {
+        let date = NaiveDate::from_ymd(2022, 10, 20);
+        assert_eq!(
+            binance_daily_dump_url(&HistoryKind::AggTrades, "BTCUSDT", date),
+            "https://data.binance.vision/data/spot/daily/aggTrades/BTCUSDT/BTCUSDT-aggTrades-2022-10-20.zip"
+        );
+        assert_eq!(
+            binance_daily_dump_url(
+                &HistoryKind::Klines {
+                    interval: "1m".to_owned()
+                },
+                "BTCUSDT",
+                date
+            ),
+            "https://data.binance.vision/data/spot/daily/klines/BTCUSDT/1m/BTCUSDT-1m-2022-10-20.zip"
+        );
+    }
+
+    #[test]
+    pub fn agg_trades_parsing() {
+        let trades = parse_agg_trades(AGG_TRADES).expect("in test");
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].trade_id, TradeId::Number(1));
+        assert_eq!(trades[0].price, dec!(19000.10));
+        assert_eq!(trades[0].side, OrderSide::Sell);
+        assert_eq!(
+            trades[0].transaction_time,
+            Utc.timestamp_millis(1666224000000)
+        );
+        assert_eq!(trades[1].quantity, dec!(1.5));
+        assert_eq!(trades[1].side, OrderSide::Buy);
+        assert_eq!(
+            trades[1].transaction_time,
+            Utc.timestamp_nanos(1666224000000123000)
+        );
+    }
+
+    #[test]
+    pub fn klines_parsing() {
+        let content =
+            "1666224000000,19000,19010,18990,19005,12.5,1666224059999,237500,42,6,114000,0";
+        let candles = parse_klines(content).expect("in test");
+
+        assert_eq!(
+            candles,
+            vec![Candle {
+                open_time: Utc.timestamp_millis(1666224000000),
+                open: dec!(19000),
+                high: dec!(19010),
+                low: dec!(18990),
+                close: dec!(19005),
+                volume: dec!(12.5),
+                trades_count: 42,
+            }]
+        );
+    }
+
+    #[test]
+    pub fn invalid_line_is_reported() {
+        let error =
+            parse_agg_trades("1,19000,abc,10,11,1666224000000,true,true").expect_err("in test");
+        assert!(format!("{error:#}").contains("line 1"));
+    }
+
+    #[test]
+    pub fn zip_dump_import() {
+        let root = std::env::temp_dir().join(format!("mmb_history_{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("in test");
+
+        let dump_path = root.join("BTCUSDT-aggTrades-2022-10-20.zip");
+        let mut zip = ZipWriter::new(fs::File::create(&dump_path).expect("in test"));
+        zip.start_file("BTCUSDT-aggTrades-2022-10-20.csv", FileOptions::default())
+            .expect("in test");
+        zip.write_all(AGG_TRADES.as_bytes()).expect("in test");
+        let _ = zip.finish().expect("in test");
+
+        let importer = HistoryImporter::new(ParquetDatasetWriter::new(root.join("recorded")));
+        let paths = importer
+            .import_dump(market_id(), &HistoryKind::AggTrades, &dump_path)
+            .expect("in test");
+
+        assert_eq!(paths.len(), 1);
+        assert!(paths[0].starts_with(
+            root.join("recorded/trades_v1/exchange=Binance/pair=btc-usdt/date=2022-10-20")
+        ));
+
+        fs::remove_dir_all(root).expect("in test");
+    }
+}
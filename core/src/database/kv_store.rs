@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use mmb_database::postgres_db::kv_store::{
+    init_kv_store, kv_delete, kv_get, kv_get_all, kv_get_all_namespaces, kv_set,
+};
+use mmb_database::postgres_db::PgPool;
+use mmb_utils::logger::print_info;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+enum Backend {
+    Database(PgPool),
+    /// Values aren't persisted across restarts when database isn't configured
+    Memory(Mutex<HashMap<String, HashMap<String, JsonValue>>>),
+}
+
+/// Value of strategy key-value store in portable format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyValueEntry {
+    pub namespace: String,
+    pub key: String,
+    pub value: JsonValue,
+}
+
+/// Persistent key-value store of engine database. Every strategy works with its own namespace,
+/// so strategies can store custom state (e.g. last rebalance time) without conflicts
+pub struct KeyValueStore {
+    backend: Backend,
+}
+
+impl KeyValueStore {
+    pub async fn start(database_url: Option<&str>) -> Result<Arc<Self>> {
+        let backend = match database_url {
+            None => {
+                print_info("Key-value store isn't persistent because `database_url` is not set in settings");
+                Backend::Memory(Default::default())
+            }
+            Some(database_url) => {
+                let pool = PgPool::create(database_url, 2).await.with_context(|| {
+                    format!("from `KeyValueStore::start` with connection_string: {database_url}")
+                })?;
+                init_kv_store(&pool).await?;
+                Backend::Database(pool)
+            }
+        };
+
+        Ok(Arc::new(KeyValueStore { backend }))
+    }
+
+    pub fn namespace(self: &Arc<Self>, namespace: impl Into<String>) -> NamespacedStore {
+        NamespacedStore {
+            store: self.clone(),
+            namespace: namespace.into(),
+        }
+    }
+
+    /// Values of all namespaces, e.g. to move them to another host
+    pub async fn export(&self) -> Result<Vec<KeyValueEntry>> {
+        let values = match &self.backend {
+            Backend::Database(pool) => kv_get_all_namespaces(pool).await?,
+            Backend::Memory(values) => values
+                .lock()
+                .iter()
+                .flat_map(|(namespace, values)| {
+                    values
+                        .iter()
+                        .map(move |(key, value)| (namespace.clone(), key.clone(), value.clone()))
+                })
+                .collect(),
+        };
+
+        Ok(values
+            .into_iter()
+            .map(|(namespace, key, value)| KeyValueEntry {
+                namespace,
+                key,
+                value,
+            })
+            .collect())
+    }
+
+    /// Sets exported values. Values of keys which aren't exported are kept
+    pub async fn import(&self, entries: Vec<KeyValueEntry>) -> Result<()> {
+        for entry in entries {
+            self.set(&entry.namespace, &entry.key, entry.value)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Unable to import value of key '{}' in '{}'",
+                        entry.key, entry.namespace
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<JsonValue>> {
+        match &self.backend {
+            Backend::Database(pool) => kv_get(pool, namespace, key).await,
+            Backend::Memory(values) => Ok(values
+                .lock()
+                .get(namespace)
+                .and_then(|x| x.get(key))
+                .cloned()),
+        }
+    }
+
+    async fn get_all(&self, namespace: &str) -> Result<Vec<(String, JsonValue)>> {
+        match &self.backend {
+            Backend::Database(pool) => kv_get_all(pool, namespace).await,
+            Backend::Memory(values) => Ok(values
+                .lock()
+                .get(namespace)
+                .map(|x| x.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+                .unwrap_or_default()),
+        }
+    }
+
+    async fn set(&self, namespace: &str, key: &str, value: JsonValue) -> Result<()> {
+        match &self.backend {
+            Backend::Database(pool) => kv_set(pool, namespace, key, &value).await,
+            Backend::Memory(values) => {
+                let _ = values
+                    .lock()
+                    .entry(namespace.to_owned())
+                    .or_default()
+                    .insert(key.to_owned(), value);
+                Ok(())
+            }
+        }
+    }
+
+    async fn delete(&self, namespace: &str, key: &str) -> Result<bool> {
+        match &self.backend {
+            Backend::Database(pool) => kv_delete(pool, namespace, key).await,
+            Backend::Memory(values) => Ok(values
+                .lock()
+                .get_mut(namespace)
+                .and_then(|x| x.remove(key))
+                .is_some()),
+        }
+    }
+}
+
+/// Part of key-value store available to one strategy. Values are stored as json
+pub struct NamespacedStore {
+    store: Arc<KeyValueStore>,
+    namespace: String,
+}
+
+impl NamespacedStore {
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        self.store
+            .get(&self.namespace, key)
+            .await?
+            .map(|value| {
+                serde_json::from_value(value).with_context(|| {
+                    format!(
+                        "Unable to deserialize value of key '{key}' in '{}'",
+                        self.namespace
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    pub async fn get_all(&self) -> Result<Vec<(String, JsonValue)>> {
+        self.store.get_all(&self.namespace).await
+    }
+
+    pub async fn set<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        let value = serde_json::to_value(value).with_context(|| {
+            format!(
+                "Unable to serialize value of key '{key}' in '{}'",
+                self.namespace
+            )
+        })?;
+        self.store.set(&self.namespace, key, value).await
+    }
+
+    /// Returns `true` if key existed
+    pub async fn delete(&self, key: &str) -> Result<bool> {
+        self.store.delete(&self.namespace, key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    #[tokio::test]
+    async fn namespaces_are_isolated() {
+        let store = KeyValueStore::start(None).await.expect("in test");
+        let first = store.namespace("first_strategy");
+        let second = store.namespace("second_strategy");
+        let rebalance_time = Utc.ymd(2022, 10, 20).and_hms(12, 0, 0);
+
+        first
+            .set("last_rebalance_time", &rebalance_time)
+            .await
+            .expect("in test");
+
+        let value: Option<DateTime<Utc>> = first.get("last_rebalance_time").await.expect("in test");
+        assert_eq!(value, Some(rebalance_time));
+        let value: Option<DateTime<Utc>> =
+            second.get("last_rebalance_time").await.expect("in test");
+        assert_eq!(value, None);
+
+        assert!(first.delete("last_rebalance_time").await.expect("in test"));
+        assert!(first.get_all().await.expect("in test").is_empty());
+    }
+
+    #[tokio::test]
+    async fn values_of_all_namespaces_are_moved_by_export() {
+        let source = KeyValueStore::start(None).await.expect("in test");
+        source
+            .namespace("first_strategy")
+            .set("rebalance_count", &3)
+            .await
+            .expect("in test");
+        source
+            .namespace("second_strategy")
+            .set("is_enabled", &true)
+            .await
+            .expect("in test");
+
+        let target = KeyValueStore::start(None).await.expect("in test");
+        let entries = source.export().await.expect("in test");
+        assert_eq!(entries.len(), 2);
+        target.import(entries).await.expect("in test");
+
+        let value: Option<u32> = target
+            .namespace("first_strategy")
+            .get("rebalance_count")
+            .await
+            .expect("in test");
+        assert_eq!(value, Some(3));
+        let value: Option<bool> = target
+            .namespace("second_strategy")
+            .get("is_enabled")
+            .await
+            .expect("in test");
+        assert_eq!(value, Some(true));
+    }
+}
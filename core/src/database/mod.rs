@@ -1 +1,9 @@
+pub mod audit_log;
 pub mod events;
+#[cfg(feature = "parquet")]
+pub mod history_import;
+pub mod kv_store;
+#[cfg(feature = "parquet")]
+pub mod parquet_writer;
+pub mod retention;
+pub mod tax_export;
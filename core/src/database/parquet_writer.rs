@@ -0,0 +1,447 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use arrow::array::{ArrayRef, Float64Array, StringArray, TimestampMillisecondArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use chrono::NaiveDate;
+use mmb_utils::DateTime;
+use parquet::arrow::ArrowWriter;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::exchanges::common::{Amount, MarketId, Price};
+use crate::exchanges::events::Trade;
+use crate::orders::fill::OrderFill;
+use crate::orders::order::OrderSnapshot;
+
+/// Version of schemas of written datasets. Should be incremented on any change of columns
+pub const SCHEMA_VERSION: u32 = 1;
+pub const SCHEMA_VERSION_METADATA_KEY: &str = "mmb.schema_version";
+pub const DATASET_METADATA_KEY: &str = "mmb.dataset";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Dataset {
+    Trades,
+    Candles,
+    Fills,
+}
+
+impl Dataset {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dataset::Trades => "trades",
+            Dataset::Candles => "candles",
+            Dataset::Fills => "fills",
+        }
+    }
+}
+
+/// OHLCV candle aggregated from public trades
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Candle {
+    pub open_time: DateTime,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: Amount,
+    pub trades_count: u64,
+}
+
+/// Aggregate trades to candles of specified period. Trades should be sorted by time
+pub fn build_candles(trades: &[Trade], period: chrono::Duration) -> Vec<Candle> {
+    let period_ms = period.num_milliseconds().max(1);
+
+    let mut candles: Vec<Candle> = vec![];
+    for trade in trades {
+        let time_ms = trade.transaction_time.timestamp_millis();
+        let open_time_ms = time_ms - time_ms.rem_euclid(period_ms);
+
+        match candles.last_mut() {
+            Some(candle) if candle.open_time.timestamp_millis() == open_time_ms => {
+                candle.high = candle.high.max(trade.price);
+                candle.low = candle.low.min(trade.price);
+                candle.close = trade.price;
+                candle.volume += trade.quantity;
+                candle.trades_count += 1;
+            }
+            _ => candles.push(Candle {
+                open_time: trade.transaction_time
+                    - chrono::Duration::milliseconds(time_ms - open_time_ms),
+                open: trade.price,
+                high: trade.price,
+                low: trade.price,
+                close: trade.price,
+                volume: trade.quantity,
+                trades_count: 1,
+            }),
+        }
+    }
+
+    candles
+}
+
+/// Writes recorded market data and fills to Parquet files for research in pandas/Polars.
+/// Files are partitioned in hive style:
+/// `{root}/{dataset}_v{SCHEMA_VERSION}/exchange={exchange_id}/pair={base}-{quote}/date={yyyy-mm-dd}/part-{uuid}.parquet`.
+/// Prices and amounts are written as `Float64` because decimals aren't supported by most of research tools
+pub struct ParquetDatasetWriter {
+    root: PathBuf,
+}
+
+impl ParquetDatasetWriter {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        ParquetDatasetWriter { root: root.into() }
+    }
+
+    pub fn write_trades(&self, market_id: MarketId, trades: &[Trade]) -> Result<Vec<PathBuf>> {
+        self.write_partitioned(
+            Dataset::Trades,
+            market_id,
+            trades,
+            |x| x.transaction_time,
+            trades_batch,
+        )
+    }
+
+    pub fn write_candles(&self, market_id: MarketId, candles: &[Candle]) -> Result<Vec<PathBuf>> {
+        self.write_partitioned(
+            Dataset::Candles,
+            market_id,
+            candles,
+            |x| x.open_time,
+            candles_batch,
+        )
+    }
+
+    /// Write fills of orders. Orders are partitioned by their exchange and currency pair
+    pub fn write_fills(&self, orders: &[OrderSnapshot]) -> Result<Vec<PathBuf>> {
+        let mut fills_by_market: HashMap<MarketId, Vec<RecordedFill>> = HashMap::new();
+        for order in orders {
+            let market_id = MarketId::new(
+                order.header.exchange_account_id.exchange_id,
+                order.header.currency_pair,
+            );
+            fills_by_market.entry(market_id).or_default().extend(
+                order
+                    .fills
+                    .fills
+                    .iter()
+                    .map(|fill| RecordedFill { order, fill }),
+            );
+        }
+
+        let mut paths = vec![];
+        for (market_id, fills) in fills_by_market {
+            paths.extend(self.write_partitioned(
+                Dataset::Fills,
+                market_id,
+                &fills,
+                |x| x.fill.receive_time(),
+                fills_batch,
+            )?);
+        }
+
+        Ok(paths)
+    }
+
+    fn write_partitioned<T>(
+        &self,
+        dataset: Dataset,
+        market_id: MarketId,
+        rows: &[T],
+        get_time: fn(&T) -> DateTime,
+        to_batch: fn(&[&T]) -> Result<RecordBatch>,
+    ) -> Result<Vec<PathBuf>> {
+        let mut rows_by_date: BTreeMap<NaiveDate, Vec<&T>> = BTreeMap::new();
+        for row in rows {
+            rows_by_date
+                .entry(get_time(row).date().naive_utc())
+                .or_default()
+                .push(row);
+        }
+
+        let mut paths = vec![];
+        for (date, rows) in rows_by_date {
+            let dir = self.partition_dir(dataset, market_id, date);
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+
+            let path = dir.join(format!("part-{}.parquet", Uuid::new_v4()));
+            let batch = to_batch(&rows)?;
+            write_batch(&path, dataset, batch)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    fn partition_dir(&self, dataset: Dataset, market_id: MarketId, date: NaiveDate) -> PathBuf {
+        let codes = market_id.currency_pair.to_codes();
+        self.root
+            .join(format!("{}_v{SCHEMA_VERSION}", dataset.as_str()))
+            .join(format!("exchange={}", market_id.exchange_id))
+            .join(format!("pair={}-{}", codes.base, codes.quote))
+            .join(format!("date={}", date.format("%Y-%m-%d")))
+    }
+}
+
+struct RecordedFill<'a> {
+    order: &'a OrderSnapshot,
+    fill: &'a OrderFill,
+}
+
+fn write_batch(path: &Path, dataset: Dataset, batch: RecordBatch) -> Result<()> {
+    let schema = with_version_metadata(batch.schema(), dataset);
+    let batch = RecordBatch::try_new(schema.clone(), batch.columns().to_vec())?;
+
+    let file = File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, schema, None)?;
+    writer.write(&batch)?;
+    let _ = writer.close()?;
+
+    Ok(())
+}
+
+fn with_version_metadata(schema: SchemaRef, dataset: Dataset) -> SchemaRef {
+    let metadata = HashMap::from([
+        (
+            SCHEMA_VERSION_METADATA_KEY.to_owned(),
+            SCHEMA_VERSION.to_string(),
+        ),
+        (DATASET_METADATA_KEY.to_owned(), dataset.as_str().to_owned()),
+    ]);
+    Arc::new(Schema::new_with_metadata(schema.fields().clone(), metadata))
+}
+
+fn time_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".to_owned())),
+        false,
+    )
+}
+
+fn time_array(times: impl Iterator<Item = DateTime>) -> ArrayRef {
+    Arc::new(TimestampMillisecondArray::from_vec(
+        times.map(|x| x.timestamp_millis()).collect(),
+        Some("UTC".to_owned()),
+    ))
+}
+
+fn decimal_array(values: impl Iterator<Item = Decimal>) -> ArrayRef {
+    Arc::new(Float64Array::from(
+        values
+            .map(|x| x.to_f64().unwrap_or(f64::NAN))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+fn string_array(values: impl Iterator<Item = String>) -> ArrayRef {
+    Arc::new(StringArray::from(values.collect::<Vec<_>>()))
+}
+
+fn trades_batch(trades: &[&Trade]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        time_field("time"),
+        Field::new("trade_id", DataType::Utf8, false),
+        Field::new("price", DataType::Float64, false),
+        Field::new("quantity", DataType::Float64, false),
+        Field::new("side", DataType::Utf8, false),
+    ]);
+
+    let batch = RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            time_array(trades.iter().map(|x| x.transaction_time)),
+            string_array(trades.iter().map(|x| x.trade_id.to_string())),
+            decimal_array(trades.iter().map(|x| x.price)),
+            decimal_array(trades.iter().map(|x| x.quantity)),
+            string_array(trades.iter().map(|x| x.side.to_string())),
+        ],
+    )?;
+    Ok(batch)
+}
+
+fn candles_batch(candles: &[&Candle]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        time_field("open_time"),
+        Field::new("open", DataType::Float64, false),
+        Field::new("high", DataType::Float64, false),
+        Field::new("low", DataType::Float64, false),
+        Field::new("close", DataType::Float64, false),
+        Field::new("volume", DataType::Float64, false),
+        Field::new("trades_count", DataType::UInt64, false),
+    ]);
+
+    let batch = RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            time_array(candles.iter().map(|x| x.open_time)),
+            decimal_array(candles.iter().map(|x| x.open)),
+            decimal_array(candles.iter().map(|x| x.high)),
+            decimal_array(candles.iter().map(|x| x.low)),
+            decimal_array(candles.iter().map(|x| x.close)),
+            decimal_array(candles.iter().map(|x| x.volume)),
+            Arc::new(UInt64Array::from(
+                candles.iter().map(|x| x.trades_count).collect::<Vec<_>>(),
+            )),
+        ],
+    )?;
+    Ok(batch)
+}
+
+fn fills_batch(fills: &[&RecordedFill]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        time_field("time"),
+        Field::new("exchange_account_id", DataType::Utf8, false),
+        Field::new("client_order_id", DataType::Utf8, false),
+        Field::new("trade_id", DataType::Utf8, true),
+        Field::new("side", DataType::Utf8, false),
+        Field::new("role", DataType::Utf8, false),
+        Field::new("price", DataType::Float64, false),
+        Field::new("amount", DataType::Float64, false),
+        Field::new("commission_currency_code", DataType::Utf8, false),
+        Field::new("commission_amount", DataType::Float64, false),
+    ]);
+
+    let batch = RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            time_array(fills.iter().map(|x| x.fill.receive_time())),
+            string_array(
+                fills
+                    .iter()
+                    .map(|x| x.order.header.exchange_account_id.to_string()),
+            ),
+            string_array(
+                fills
+                    .iter()
+                    .map(|x| x.order.header.client_order_id.to_string()),
+            ),
+            Arc::new(StringArray::from(
+                fills
+                    .iter()
+                    .map(|x| x.fill.trade_id().map(|id| id.to_string()))
+                    .collect::<Vec<_>>(),
+            )),
+            string_array(
+                fills
+                    .iter()
+                    .map(|x| x.fill.side().unwrap_or(x.order.side()).to_string()),
+            ),
+            string_array(fills.iter().map(|x| format!("{:?}", x.fill.role()))),
+            decimal_array(fills.iter().map(|x| x.fill.price())),
+            decimal_array(fills.iter().map(|x| x.fill.amount())),
+            string_array(
+                fills
+                    .iter()
+                    .map(|x| x.fill.commission_currency_code().to_string()),
+            ),
+            decimal_array(fills.iter().map(|x| x.fill.commission_amount())),
+        ],
+    )?;
+    Ok(batch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use crate::exchanges::events::{TickDirection, TradeId};
+    use crate::orders::order::OrderSide;
+    use chrono::{TimeZone, Utc};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use rust_decimal_macros::dec;
+
+    fn market_id() -> MarketId {
+        MarketId::new(
+            "Binance".into(),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    fn trade(time_ms: i64, price: Price, quantity: Amount) -> Trade {
+        Trade {
+            trade_id: TradeId::Number(time_ms as u64),
+            price,
+            quantity,
+            side: OrderSide::Buy,
+            transaction_time: Utc.timestamp_millis(time_ms),
+            tick_direction: TickDirection::None,
+        }
+    }
+
+    fn trades() -> Vec<Trade> {
+        const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+        vec![
+            trade(DAY_MS - 2_000, dec!(100), dec!(1)),
+            trade(DAY_MS - 1_500, dec!(102), dec!(2)),
+            trade(DAY_MS - 500, dec!(99), dec!(1)),
+            trade(DAY_MS + 100, dec!(101), dec!(3)),
+        ]
+    }
+
+    #[test]
+    pub fn candles_from_trades() {
+        let candles = build_candles(&trades(), chrono::Duration::seconds(1));
+
+        assert_eq!(candles.len(), 3);
+        assert_eq!(
+            candles[0],
+            Candle {
+                open_time: Utc.timestamp_millis(24 * 60 * 60 * 1000 - 2_000),
+                open: dec!(100),
+                high: dec!(102),
+                low: dec!(100),
+                close: dec!(102),
+                volume: dec!(3),
+                trades_count: 2,
+            }
+        );
+        assert_eq!(candles[1].trades_count, 1);
+        assert_eq!(candles[2].close, dec!(101));
+    }
+
+    #[test]
+    pub fn trades_are_partitioned_by_date() {
+        let root = std::env::temp_dir().join(format!("mmb_parquet_{}", Uuid::new_v4()));
+        let writer = ParquetDatasetWriter::new(&root);
+
+        let paths = writer
+            .write_trades(market_id(), &trades())
+            .expect("in test");
+
+        assert_eq!(paths.len(), 2);
+        let partition = root.join("trades_v1/exchange=Binance/pair=btc-usdt");
+        assert!(paths[0].starts_with(partition.join("date=1970-01-01")));
+        assert!(paths[1].starts_with(partition.join("date=1970-01-02")));
+
+        let file = File::open(&paths[0]).expect("in test");
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .expect("in test")
+            .build()
+            .expect("in test");
+        let metadata = reader.schema().metadata().clone();
+        let rows_count: usize = reader.map(|x| x.expect("in test").num_rows()).sum();
+
+        assert_eq!(rows_count, 3);
+        assert_eq!(
+            metadata.get(SCHEMA_VERSION_METADATA_KEY),
+            Some(&SCHEMA_VERSION.to_string())
+        );
+        assert_eq!(
+            metadata.get(DATASET_METADATA_KEY),
+            Some(&"trades".to_owned())
+        );
+
+        fs::remove_dir_all(root).expect("in test");
+    }
+}
@@ -0,0 +1,267 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use mmb_database::postgres_db::retention::{
+    delete_events_up_to, get_events_range, get_retention_boundary,
+};
+use mmb_database::postgres_db::PgPool;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::json;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+use crate::settings::{EventRetentionSettings, TableRetentionSettings};
+
+/// Count of events loaded from database at once while archiving
+const ARCHIVE_BATCH_SIZE: i64 = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactionReport {
+    pub table_name: String,
+    pub removed_events: u64,
+    pub archive: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompactionStatus {
+    pub is_running: bool,
+    pub last_finish_time: Option<DateTime>,
+    pub last_reports: Vec<CompactionReport>,
+    pub last_error: Option<String>,
+}
+
+/// Keeps event tables within retention limits, so long-running deployments don't grow unbounded.
+/// Events are archived before removing, so nothing is lost if archiving fails
+pub struct EventRetention {
+    pool: PgPool,
+    settings: EventRetentionSettings,
+    is_running: AtomicBool,
+    status: Mutex<CompactionStatus>,
+}
+
+fn is_valid_table_name(table_name: &str) -> bool {
+    !table_name.is_empty()
+        && !table_name.starts_with(|c: char| c.is_ascii_digit())
+        && table_name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+impl EventRetention {
+    pub async fn start(database_url: &str, settings: EventRetentionSettings) -> Result<Arc<Self>> {
+        // table names are inserted to sql queries as is
+        if let Some(table) = settings
+            .tables
+            .iter()
+            .find(|x| !is_valid_table_name(&x.table_name))
+        {
+            bail!(
+                "Invalid table name '{}' in retention settings",
+                table.table_name
+            );
+        }
+
+        let pool = PgPool::create(database_url, 2).await.with_context(|| {
+            format!("from `EventRetention::start` with connection_string: {database_url}")
+        })?;
+
+        let compaction_interval = settings
+            .compaction_interval_ms
+            .map(|x| Duration::from_millis(x.max(1)));
+        let retention = Arc::new(EventRetention {
+            pool,
+            settings,
+            is_running: AtomicBool::new(false),
+            status: Default::default(),
+        });
+
+        if let Some(compaction_interval) = compaction_interval {
+            let _ = spawn_future(
+                "Periodic event log compaction",
+                SpawnFutureFlags::STOP_BY_TOKEN,
+                retention.clone().compact_periodically(compaction_interval),
+            );
+        }
+
+        Ok(retention)
+    }
+
+    async fn compact_periodically(self: Arc<Self>, compaction_interval: Duration) -> Result<()> {
+        let mut interval = tokio::time::interval(compaction_interval);
+        loop {
+            let _ = interval.tick().await;
+            if !self.trigger_compaction() {
+                log::warn!("Periodic event log compaction is skipped because previous one is still running");
+            }
+        }
+    }
+
+    /// Starts compaction in background. Returns `false` if compaction is already running
+    pub fn trigger_compaction(self: &Arc<Self>) -> bool {
+        if self.is_running.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        self.status.lock().is_running = true;
+
+        let retention = self.clone();
+        let _ = spawn_future(
+            "Event log compaction",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            async move {
+                let result = retention.compact().await;
+
+                let mut status = retention.status.lock();
+                status.is_running = false;
+                status.last_finish_time = Some(time_manager::now());
+                match result {
+                    Ok(reports) => {
+                        status.last_reports = reports;
+                        status.last_error = None;
+                    }
+                    Err(err) => {
+                        log::error!("Event log compaction failed: {err:?}");
+                        status.last_error = Some(format!("{err:?}"));
+                    }
+                }
+                retention.is_running.store(false, Ordering::SeqCst);
+                Ok(())
+            },
+        );
+
+        true
+    }
+
+    pub fn status(&self) -> CompactionStatus {
+        self.status.lock().clone()
+    }
+
+    async fn compact(&self) -> Result<Vec<CompactionReport>> {
+        let mut reports = Vec::with_capacity(self.settings.tables.len());
+        for table in &self.settings.tables {
+            let report = self
+                .compact_table(table)
+                .await
+                .with_context(|| format!("Unable to compact table {}", table.table_name))?;
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+
+    async fn compact_table(&self, table: &TableRetentionSettings) -> Result<CompactionReport> {
+        let table_name = table.table_name.as_str();
+        let inserted_before = table
+            .max_age_ms
+            .map(|ms| time_manager::now() - chrono::Duration::milliseconds(ms as i64));
+        let max_rows = table.max_rows.map(|x| x as i64);
+
+        let boundary = match get_retention_boundary(
+            &self.pool,
+            table_name,
+            inserted_before,
+            max_rows,
+        )
+        .await?
+        {
+            Some(boundary) => boundary,
+            None => {
+                return Ok(CompactionReport {
+                    table_name: table_name.to_owned(),
+                    removed_events: 0,
+                    archive: None,
+                })
+            }
+        };
+
+        let archive = match &self.settings.archive_dir {
+            Some(archive_dir) => Some(self.archive(archive_dir, table_name, boundary).await?),
+            None => None,
+        };
+        let removed_events = delete_events_up_to(&self.pool, table_name, boundary).await?;
+        log::info!("{removed_events} events are removed from {table_name} by compaction");
+
+        Ok(CompactionReport {
+            table_name: table_name.to_owned(),
+            removed_events,
+            archive,
+        })
+    }
+
+    /// Writes events up to `boundary` id to zip file with json line per event
+    async fn archive(
+        &self,
+        archive_dir: &Path,
+        table_name: &str,
+        boundary: i64,
+    ) -> Result<PathBuf> {
+        fs::create_dir_all(archive_dir)
+            .with_context(|| format!("Unable to create archive directory {archive_dir:?}"))?;
+        let path = archive_dir.join(format!(
+            "{table_name}_{}.zip",
+            time_manager::now().format("%Y%m%dT%H%M%S")
+        ));
+        let file = File::create(&path).with_context(|| format!("Unable to create {path:?}"))?;
+
+        let mut writer = ZipWriter::new(file);
+        writer
+            .start_file(
+                format!("{table_name}.jsonl"),
+                FileOptions::default().compression_method(CompressionMethod::Deflated),
+            )
+            .context("Unable to start archive entry")?;
+
+        let mut after_id = 0;
+        loop {
+            let events = get_events_range(
+                &self.pool,
+                table_name,
+                after_id,
+                boundary,
+                ARCHIVE_BATCH_SIZE,
+            )
+            .await?;
+            let last_event = match events.last() {
+                Some(last_event) => last_event,
+                None => break,
+            };
+            after_id = last_event.id as i64;
+
+            for event in &events {
+                let line = json!({
+                    "id": event.id,
+                    "insert_time": event.insert_time,
+                    "version": event.version,
+                    "json": event.json,
+                });
+                writeln!(writer, "{line}").context("Unable to write event to archive")?;
+            }
+        }
+
+        writer.finish().context("Unable to finish archive")?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_names_inserted_to_sql_are_validated() {
+        assert!(is_valid_table_name("liquidity_order_books"));
+        assert!(is_valid_table_name("trades_2022"));
+        assert!(!is_valid_table_name(""));
+        assert!(!is_valid_table_name("1trades"));
+        assert!(!is_valid_table_name("trades; DROP TABLE orders"));
+        assert!(!is_valid_table_name("Trades"));
+    }
+}
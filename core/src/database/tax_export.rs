@@ -0,0 +1,410 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use mmb_utils::DateTime;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price};
+use crate::exchanges::events::TradeId;
+use crate::orders::fill::OrderFillType;
+use crate::orders::order::{ClientOrderId, ExchangeOrderId, OrderSide, OrderSnapshot};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TaxExportFormat {
+    /// Koinly universal CSV template
+    Koinly,
+    /// CoinTracking CSV import template
+    CoinTracking,
+    /// FIFO lots of acquisitions and disposals with lineage to orders and trades
+    Lots,
+}
+
+impl TaxExportFormat {
+    pub fn parse(format: &str) -> Result<Self> {
+        match format.to_lowercase().as_str() {
+            "koinly" => Ok(TaxExportFormat::Koinly),
+            "cointracking" => Ok(TaxExportFormat::CoinTracking),
+            "lots" => Ok(TaxExportFormat::Lots),
+            _ => bail!("Unknown tax export format {format}, expected koinly, cointracking or lots"),
+        }
+    }
+}
+
+/// Fill with lineage to exchange order and trade which it comes from
+#[derive(Debug, Clone, Serialize)]
+pub struct FillLineage {
+    pub fill_id: Uuid,
+    pub time: DateTime,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub client_order_id: ClientOrderId,
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    pub trade_id: Option<TradeId>,
+    pub side: OrderSide,
+    pub price: Price,
+    pub amount: Amount,
+    /// Amount in quote currency
+    pub cost: Amount,
+    pub fee_amount: Amount,
+    pub fee_currency: CurrencyCode,
+}
+
+impl FillLineage {
+    fn lineage_id(&self) -> String {
+        match &self.trade_id {
+            Some(trade_id) => format!("{}:{trade_id}", self.exchange_account_id),
+            None => self.fill_id.to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        let exchange_order_id = self
+            .exchange_order_id
+            .as_ref()
+            .map_or("unknown".to_owned(), |x| x.to_string());
+        format!(
+            "{} {} order {exchange_order_id} (client order {})",
+            self.side, self.currency_pair, self.client_order_id
+        )
+    }
+}
+
+/// Trading fills of orders sorted by time. Funding fills aren't trades, so they are skipped
+pub fn fill_lineage(orders: &[OrderSnapshot]) -> Vec<FillLineage> {
+    let mut fills: Vec<_> = orders
+        .iter()
+        .flat_map(|order| {
+            order
+                .fills
+                .fills
+                .iter()
+                .filter(|fill| fill.fill_type() != OrderFillType::Funding)
+                .map(move |fill| FillLineage {
+                    fill_id: fill.id(),
+                    time: fill.receive_time(),
+                    exchange_account_id: order.header.exchange_account_id,
+                    currency_pair: order.header.currency_pair,
+                    client_order_id: order.header.client_order_id.clone(),
+                    exchange_order_id: order.props.exchange_order_id.clone(),
+                    trade_id: fill.trade_id().cloned(),
+                    side: fill.side().unwrap_or(order.header.side),
+                    price: fill.price(),
+                    amount: fill.amount(),
+                    cost: fill.cost(),
+                    fee_amount: fill.commission_amount(),
+                    fee_currency: fill.commission_currency_code(),
+                })
+        })
+        .collect();
+
+    fills.sort_by_key(|x| x.time);
+    fills
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub enum LotRecordType {
+    Acquisition,
+    Disposal,
+}
+
+/// Acquisition opens lot of base currency by buy fill. Disposal closes part of the oldest open
+/// lot of the same currency pair (FIFO), so one sell fill can produce several disposals
+#[derive(Debug, Clone, Serialize)]
+pub struct LotRecord {
+    pub record_type: LotRecordType,
+    /// Lot which is opened or closed. Disposal without open lots has no lot
+    pub lot_id: Option<u64>,
+    pub time: DateTime,
+    pub currency_pair: CurrencyPair,
+    pub amount: Amount,
+    /// Cost of acquired amount in quote currency including fees paid in quote currency
+    pub cost_basis: Amount,
+    /// Revenue of disposed amount in quote currency after fees paid in quote currency
+    pub proceeds: Amount,
+    pub fill: FillLineage,
+}
+
+struct OpenLot {
+    lot_id: u64,
+    amount: Amount,
+    cost_per_unit: Price,
+}
+
+/// Matches disposals with acquisitions by FIFO per currency pair. Fees in base currency reduce
+/// acquired amount, fees in other currencies are reported by fills only
+pub fn lot_records(fills: &[FillLineage]) -> Vec<LotRecord> {
+    let mut open_lots: HashMap<CurrencyPair, VecDeque<OpenLot>> = HashMap::new();
+    let mut next_lot_id = 1;
+    let mut records = vec![];
+
+    for fill in fills {
+        if fill.amount <= dec!(0) {
+            continue;
+        }
+
+        let codes = fill.currency_pair.to_codes();
+        let quote_fee = match fill.fee_currency == codes.quote {
+            true => fill.fee_amount,
+            false => dec!(0),
+        };
+        let lots = open_lots.entry(fill.currency_pair).or_default();
+
+        match fill.side {
+            OrderSide::Buy => {
+                let amount = match fill.fee_currency == codes.base {
+                    true => fill.amount - fill.fee_amount,
+                    false => fill.amount,
+                };
+                if amount <= dec!(0) {
+                    continue;
+                }
+
+                let cost_basis = fill.cost + quote_fee;
+                lots.push_back(OpenLot {
+                    lot_id: next_lot_id,
+                    amount,
+                    cost_per_unit: cost_basis / amount,
+                });
+                records.push(LotRecord {
+                    record_type: LotRecordType::Acquisition,
+                    lot_id: Some(next_lot_id),
+                    time: fill.time,
+                    currency_pair: fill.currency_pair,
+                    amount,
+                    cost_basis,
+                    proceeds: dec!(0),
+                    fill: fill.clone(),
+                });
+                next_lot_id += 1;
+            }
+            OrderSide::Sell => {
+                let proceeds_per_unit = (fill.cost - quote_fee) / fill.amount;
+                let mut left_amount = fill.amount;
+                while left_amount > dec!(0) {
+                    let (lot_id, amount, cost_basis) = match lots.front_mut() {
+                        Some(lot) => {
+                            let amount = left_amount.min(lot.amount);
+                            lot.amount -= amount;
+                            let lot_id = lot.lot_id;
+                            let cost_basis = amount * lot.cost_per_unit;
+                            if lot.amount <= dec!(0) {
+                                let _ = lots.pop_front();
+                            }
+                            (Some(lot_id), amount, cost_basis)
+                        }
+                        // inventory acquired before exported history or short position
+                        None => (None, left_amount, dec!(0)),
+                    };
+
+                    left_amount -= amount;
+                    records.push(LotRecord {
+                        record_type: LotRecordType::Disposal,
+                        lot_id,
+                        time: fill.time,
+                        currency_pair: fill.currency_pair,
+                        amount,
+                        cost_basis,
+                        proceeds: amount * proceeds_per_unit,
+                        fill: fill.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    records
+}
+
+/// CSV of fills of orders in specified format of tax tool
+pub fn export_csv(orders: &[OrderSnapshot], format: TaxExportFormat) -> String {
+    let fills = fill_lineage(orders);
+    match format {
+        TaxExportFormat::Koinly => koinly_csv(&fills),
+        TaxExportFormat::CoinTracking => cointracking_csv(&fills),
+        TaxExportFormat::Lots => lots_csv(&lot_records(&fills)),
+    }
+}
+
+fn koinly_csv(fills: &[FillLineage]) -> String {
+    let mut csv = "Date,Sent Amount,Sent Currency,Received Amount,Received Currency,Fee Amount,Fee Currency,Net Worth Amount,Net Worth Currency,Label,Description,TxHash\n".to_owned();
+    for fill in fills {
+        let codes = fill.currency_pair.to_codes();
+        let (sent, received) = match fill.side {
+            OrderSide::Buy => ((fill.cost, codes.quote), (fill.amount, codes.base)),
+            OrderSide::Sell => ((fill.amount, codes.base), (fill.cost, codes.quote)),
+        };
+        write_row(
+            &mut csv,
+            &[
+                fill.time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+                sent.0.normalize().to_string(),
+                sent.1.to_string().to_uppercase(),
+                received.0.normalize().to_string(),
+                received.1.to_string().to_uppercase(),
+                fill.fee_amount.normalize().to_string(),
+                fill.fee_currency.to_string().to_uppercase(),
+                String::new(),
+                String::new(),
+                String::new(),
+                fill.description(),
+                fill.lineage_id(),
+            ],
+        );
+    }
+
+    csv
+}
+
+fn cointracking_csv(fills: &[FillLineage]) -> String {
+    let mut csv = "Type,Buy Amount,Buy Currency,Sell Amount,Sell Currency,Fee,Fee Currency,Exchange,Trade-Group,Comment,Date,Tx-ID\n".to_owned();
+    for fill in fills {
+        let codes = fill.currency_pair.to_codes();
+        let (bought, sold) = match fill.side {
+            OrderSide::Buy => ((fill.amount, codes.base), (fill.cost, codes.quote)),
+            OrderSide::Sell => ((fill.cost, codes.quote), (fill.amount, codes.base)),
+        };
+        write_row(
+            &mut csv,
+            &[
+                "Trade".to_owned(),
+                bought.0.normalize().to_string(),
+                bought.1.to_string().to_uppercase(),
+                sold.0.normalize().to_string(),
+                sold.1.to_string().to_uppercase(),
+                fill.fee_amount.normalize().to_string(),
+                fill.fee_currency.to_string().to_uppercase(),
+                fill.exchange_account_id.exchange_id.to_string(),
+                fill.exchange_account_id.to_string(),
+                fill.description(),
+                fill.time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                fill.lineage_id(),
+            ],
+        );
+    }
+
+    csv
+}
+
+fn lots_csv(records: &[LotRecord]) -> String {
+    let mut csv = "time,type,lot_id,exchange_account_id,currency_pair,amount,cost_basis,proceeds,client_order_id,exchange_order_id,trade_id,fill_id\n".to_owned();
+    for record in records {
+        let fill = &record.fill;
+        write_row(
+            &mut csv,
+            &[
+                record.time.to_rfc3339(),
+                format!("{:?}", record.record_type),
+                record.lot_id.map(|x| x.to_string()).unwrap_or_default(),
+                fill.exchange_account_id.to_string(),
+                record.currency_pair.to_string(),
+                record.amount.normalize().to_string(),
+                record.cost_basis.normalize().to_string(),
+                record.proceeds.normalize().to_string(),
+                fill.client_order_id.to_string(),
+                fill.exchange_order_id
+                    .as_ref()
+                    .map(|x| x.to_string())
+                    .unwrap_or_default(),
+                fill.trade_id
+                    .as_ref()
+                    .map(|x| x.to_string())
+                    .unwrap_or_default(),
+                fill.fill_id.to_string(),
+            ],
+        );
+    }
+
+    csv
+}
+
+fn write_row(csv: &mut String, fields: &[String]) {
+    let row = fields
+        .iter()
+        .map(|field| match field.contains([',', '"', '\n']) {
+            true => format!("\"{}\"", field.replace('"', "\"\"")),
+            false => field.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    let _ = writeln!(csv, "{row}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn fill(
+        time_secs: i64,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        fee_amount: Amount,
+        fee_currency: &str,
+    ) -> FillLineage {
+        FillLineage {
+            fill_id: Uuid::new_v4(),
+            time: Utc.timestamp_millis(time_secs * 1000),
+            exchange_account_id: ExchangeAccountId::new("Binance", 0),
+            currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            client_order_id: "client".into(),
+            exchange_order_id: Some("exchange".into()),
+            trade_id: Some(TradeId::Number(time_secs as u64)),
+            side,
+            price,
+            amount,
+            cost: price * amount,
+            fee_amount,
+            fee_currency: fee_currency.into(),
+        }
+    }
+
+    #[test]
+    pub fn disposals_are_matched_with_acquisitions_by_fifo() {
+        let fills = vec![
+            fill(1, OrderSide::Buy, dec!(100), dec!(1), dec!(1), "usdt"),
+            fill(2, OrderSide::Buy, dec!(200), dec!(1), dec!(0), "bnb"),
+            fill(3, OrderSide::Sell, dec!(300), dec!(1.5), dec!(4.5), "usdt"),
+            fill(4, OrderSide::Sell, dec!(300), dec!(1), dec!(0), "usdt"),
+        ];
+
+        let records = lot_records(&fills);
+        let summary: Vec<_> = records
+            .iter()
+            .map(|x| (x.record_type, x.lot_id, x.amount, x.cost_basis, x.proceeds))
+            .collect();
+
+        use LotRecordType::*;
+        assert_eq!(
+            summary,
+            vec![
+                (Acquisition, Some(1), dec!(1), dec!(101), dec!(0)),
+                (Acquisition, Some(2), dec!(1), dec!(200), dec!(0)),
+                (Disposal, Some(1), dec!(1), dec!(101), dec!(297)),
+                (Disposal, Some(2), dec!(0.5), dec!(100), dec!(148.5)),
+                (Disposal, Some(2), dec!(0.5), dec!(100), dec!(150)),
+                (Disposal, None, dec!(0.5), dec!(0), dec!(150)),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn koinly_row_keeps_order_lineage() {
+        let csv = koinly_csv(&[fill(
+            0,
+            OrderSide::Sell,
+            dec!(300),
+            dec!(2),
+            dec!(0.6),
+            "usdt",
+        )]);
+
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("1970-01-01 00:00:00 UTC,2,BTC,600,USDT,0.6,USDT,,,,Sell btc/usdt order exchange (client order client),Binance_0:0")
+        );
+    }
+}
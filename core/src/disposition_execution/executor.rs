@@ -1,5 +1,6 @@
 use std::fmt::{Display, Formatter};
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::{anyhow, bail, Context, Result};
 use chrono::Utc;
@@ -11,33 +12,48 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use tokio::sync::{broadcast, oneshot};
 
+use crate::disposition_execution::order_throttle::{
+    OrderThrottle, ThrottleDecision, ThrottledAction,
+};
+use crate::disposition_execution::rejection_analytics::{
+    apply_quoting_adjustment, classify_rejection, QuotingAdjustment, RejectionAnalytics,
+};
+use crate::disposition_execution::spread_floor::apply_spread_floor;
+use crate::disposition_execution::tick_budget::TickBudget;
 use crate::disposition_execution::trading_context_calculation::calculate_trading_context;
 use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, MarketAccountId, Price};
 use crate::exchanges::events::ExchangeEvent;
 use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::general::request_type::RequestType;
 use crate::exchanges::general::symbol::Symbol;
+use crate::exchanges::timeouts::request_priority_queue::RequestPriority;
+use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
 use crate::explanation::{Explanation, WithExplanation};
 use crate::lifecycle::trading_engine::{EngineContext, Service};
 use crate::misc::reserve_parameters::ReserveParameters;
 use crate::order_book::local_snapshot_service::LocalSnapshotsService;
 use crate::orders::event::OrderEventType;
+use crate::orders::fill::OrderFillType;
 use crate::orders::order::{
     ClientOrderId, OrderCreating, OrderExecutionType, OrderHeader, OrderSide, OrderSnapshot,
-    OrderStatus, OrderType,
+    OrderStatus, OrderType, ReservationId,
 };
 use crate::orders::pool::OrderRef;
+use crate::settings::SpreadFloorSettings;
 use crate::strategies::disposition_strategy::DispositionStrategy;
 use crate::{
     disposition_execution::trade_limit::is_enough_amount_and_cost, infrastructure::spawn_future,
 };
 use crate::{
     disposition_execution::{
-        CompositeOrder, OrderRecord, OrdersState, PriceSlot, TradeCycle, TradingContext,
+        CompositeOrder, OrderRecord, OrdersState, PriceSlot, PriceSlotId, TradeCycle,
+        TradingContext,
     },
     statistic_service::StatisticService,
 };
 use chrono::Duration;
+use futures::future::BoxFuture;
+use futures::FutureExt;
 use mmb_utils::cancellation_token::CancellationToken;
 
 static DISPOSITION_EXECUTOR: &str = "DispositionExecutor";
@@ -45,6 +61,22 @@ static DISPOSITION_EXECUTOR_REQUESTS_GROUP: &str = "DispositionExecutorRG";
 const ALLOWED_AMOUNT_DEVIATION_RATE: Decimal = dec!(0.001);
 const GROUP_REQUESTS_COUNT: usize = 4;
 
+type ThrottledFuture = (&'static str, BoxFuture<'static, Result<()>>);
+
+/// Order which creation is queued by throttle. Balance and requests are reserved for it
+/// only when it's released, so queued orders don't hold them
+struct QueuedOrder {
+    side: OrderSide,
+    price_slot_id: PriceSlotId,
+    amount: Amount,
+    estimating: TradeCycle,
+}
+
+enum ThrottledItem {
+    Action(ThrottledFuture),
+    CreateOrder(QueuedOrder),
+}
+
 struct DisplaySmallOrder {
     price: Decimal,
     amount: Decimal,
@@ -127,6 +159,10 @@ struct DispositionExecutor {
     work_finished_sender: Option<oneshot::Sender<Result<()>>>,
     cancellation_token: CancellationToken,
     statistics: Arc<StatisticService>,
+    spread_floor: Option<SpreadFloorSettings>,
+    throttle: Mutex<OrderThrottle<ThrottledItem>>,
+    tick_budget: Mutex<TickBudget>,
+    rejection_analytics: Option<RejectionAnalytics>,
 }
 
 impl DispositionExecutor {
@@ -149,6 +185,36 @@ impl DispositionExecutor {
             .get_symbol(currency_pair)
             .expect("Currency pair symbol should exists for target trading place");
 
+        let spread_floor = engine_ctx
+            .core_settings
+            .exchanges
+            .iter()
+            .find(|x| x.exchange_account_id == exchange_account_id)
+            .and_then(|x| x.get_spread_floor(currency_pair))
+            .cloned();
+
+        let service_name = strategy.configuration_descriptor().service_name;
+        let throttle = OrderThrottle::new(
+            engine_ctx
+                .core_settings
+                .strategy_order_throttles
+                .iter()
+                .find(|x| x.service_name == service_name),
+        );
+        let tick_budget = TickBudget::new(
+            engine_ctx
+                .core_settings
+                .strategy_tick_budgets
+                .iter()
+                .find(|x| x.service_name == service_name),
+        );
+
+        let rejection_analytics = engine_ctx
+            .core_settings
+            .rejection_analytics
+            .clone()
+            .map(RejectionAnalytics::new);
+
         DispositionExecutor {
             engine_ctx,
             events_receiver,
@@ -160,6 +226,10 @@ impl DispositionExecutor {
             work_finished_sender: Some(work_finished_sender),
             cancellation_token,
             statistics,
+            spread_floor,
+            throttle: Mutex::new(throttle),
+            tick_budget: Mutex::new(tick_budget),
+            rejection_analytics,
         }
     }
 
@@ -184,9 +254,29 @@ impl DispositionExecutor {
         event: ExchangeEvent,
         last_trading_context: &mut Option<TradingContext>,
     ) -> Result<()> {
+        if let ExchangeEvent::OrderBookEvent(order_book_event) = &event {
+            // resent depth frame shouldn't trigger strategy again
+            if self.local_snapshots_service.is_duplicate(order_book_event) {
+                self.statistics.register_duplicate_order_book_event();
+                return Ok(());
+            }
+        }
+
         let now = now();
         let need_recalculate_trading_context = self.prepare_estimate_trading_context(&event, now);
 
+        let released_items = self.throttle.lock().start_tick(now);
+        for item in released_items {
+            match item {
+                ThrottledItem::Action((action_name, action)) => {
+                    spawn_throttled_action(action_name, action)
+                }
+                ThrottledItem::CreateOrder(queued_order) => {
+                    self.create_queued_order(queued_order, now)
+                }
+            }
+        }
+
         match event {
             ExchangeEvent::OrderBookEvent(order_book_event) => {
                 let _ = self.local_snapshots_service.update(order_book_event);
@@ -197,14 +287,39 @@ impl DispositionExecutor {
                     return Ok(());
                 }
 
+                // orders filled by internal crossing aren't tracked by executor, their fills are
+                // applied to balances by `InternalCrossingEngine`
+                let is_internal_order = order.fn_ref(|s| {
+                    s.fills
+                        .fills
+                        .iter()
+                        .any(|x| x.fill_type() == OrderFillType::Internal)
+                });
+                if is_internal_order {
+                    return Ok(());
+                }
+
                 match order_event.event_type {
-                    OrderEventType::CreateOrderSucceeded => nothing_to_do(),
+                    // price slot is released when order is canceled after ack timeout
+                    OrderEventType::CreateOrderSucceeded | OrderEventType::AckTimeout => {
+                        nothing_to_do()
+                    }
                     OrderEventType::CreateOrderFailed => {
                         let client_order_id = order.client_order_id();
                         log::trace!(
                             "Started handling event CreateOrderFailed {} in DispositionExecutor",
                             client_order_id
                         );
+                        if let Some(rejection_analytics) = &mut self.rejection_analytics {
+                            let reason = order.fn_ref(|x| {
+                                classify_rejection(
+                                    x.internal_props.last_creation_error_type,
+                                    &x.internal_props.last_creation_error_message,
+                                )
+                            });
+                            rejection_analytics.register(reason, now);
+                        }
+
                         let price_slot = self.get_price_slot(order);
                         let price_slot = match price_slot {
                             None => return Ok(()),
@@ -286,12 +401,61 @@ impl DispositionExecutor {
             _ => nothing_to_do(),
         };
 
+        // strategy isn't activated until engine is warmed up
+        if !self.engine_ctx.warm_up.is_completed() {
+            return Ok(());
+        }
+
+        // suspended strategy isn't called anymore, its orders are already cancelled
+        if self.tick_budget.lock().is_suspended() {
+            return Ok(());
+        }
+
+        let started_at = Instant::now();
         let mut new_trading_context = estimate_trading_context(
             need_recalculate_trading_context,
             self.strategy.as_mut(),
             &self.local_snapshots_service,
             now,
         )?;
+        if need_recalculate_trading_context {
+            self.register_strategy_tick(started_at);
+        }
+
+        if let (Some(trading_context), Some(spread_floor)) =
+            (new_trading_context.as_mut(), &self.spread_floor)
+        {
+            apply_spread_floor(
+                trading_context,
+                self.exchange().get_commission(),
+                spread_floor,
+            );
+        }
+
+        if let Some(rejection_analytics) = &mut self.rejection_analytics {
+            rejection_analytics.relax(now);
+            if let Some(trading_context) = new_trading_context.as_mut() {
+                apply_quoting_adjustment(
+                    trading_context,
+                    rejection_analytics.adjustment(),
+                    &self.symbol,
+                    "order rejections",
+                );
+            }
+        }
+
+        if let (Some(trading_context), Some(news_restrictions)) = (
+            new_trading_context.as_mut(),
+            &self.engine_ctx.news_restrictions,
+        ) {
+            if let Some((price_collar, cause)) = news_restrictions.quote_widening(&self.symbol) {
+                let adjustment = QuotingAdjustment {
+                    price_collar,
+                    removed_levels: 0,
+                };
+                apply_quoting_adjustment(trading_context, adjustment, &self.symbol, &cause);
+            }
+        }
 
         if last_trading_context == &mut new_trading_context {
             return Ok(());
@@ -542,9 +706,25 @@ impl DispositionExecutor {
             );
             return;
         }
-        order_record.is_cancellation_requested = true;
 
         let order = order_record.order.clone();
+        let decision = self
+            .throttle
+            .lock()
+            .acquire(ThrottledAction::CancelOrder, now());
+        if decision == ThrottleDecision::Drop {
+            self.statistics.register_throttled_dropped_action();
+            let _ = log_trace(
+                format!(
+                    "Cancelling order {} is dropped because cancels budget is exhausted",
+                    order.client_order_id()
+                ),
+                explanation,
+            );
+            return;
+        }
+        order_record.is_cancellation_requested = true;
+
         explanation.add_reason(format!(
             "Cancelling order {} {}",
             order.client_order_id(),
@@ -567,13 +747,37 @@ impl DispositionExecutor {
 
             Ok(())
         };
-        spawn_future(
-            "Start wait_cancel_order from DispositionExecutor::cancel_order()",
-            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
-            action,
+        self.issue_throttled_action(
+            ThrottledAction::CancelOrder,
+            decision,
+            (
+                "Start wait_cancel_order from DispositionExecutor::cancel_order()",
+                action.boxed(),
+            ),
         );
     }
 
+    fn issue_throttled_action(
+        &self,
+        action_type: ThrottledAction,
+        decision: ThrottleDecision,
+        action: ThrottledFuture,
+    ) {
+        match decision {
+            ThrottleDecision::Allowed => spawn_throttled_action(action.0, action.1),
+            ThrottleDecision::Queue => {
+                self.statistics.register_throttled_queued_action();
+                self.throttle
+                    .lock()
+                    .enqueue(action_type, ThrottledItem::Action(action));
+            }
+            ThrottleDecision::Drop => {
+                self.statistics.register_throttled_dropped_action();
+                log::warn!("Throttled action {} is dropped", action.0);
+            }
+        }
+    }
+
     fn start_cancelling_orders_with_cause<'a>(
         &self,
         cause: &str,
@@ -628,6 +832,121 @@ impl DispositionExecutor {
             );
         }
 
+        if price_slot.is_creation_queued.get() {
+            return log_trace(
+                "Finished `try_create_order` because order creation is queued by throttle",
+                explanation,
+            );
+        }
+
+        if self
+            .engine_ctx
+            .timeout_manager
+            .has_higher_priority_requests(self.exchange_account_id, RequestPriority::Quote)
+        {
+            return log_trace(
+                "Finished `try_create_order` because cancels and risk reducing orders are waiting for requests",
+                explanation,
+            );
+        }
+
+        // budget is spent only when order is issued, queued order reserves balance and requests
+        // when it's released by throttle
+        match self
+            .throttle
+            .lock()
+            .check(ThrottledAction::CreateOrder, now)
+        {
+            ThrottleDecision::Allowed => {}
+            ThrottleDecision::Queue => {
+                self.statistics.register_throttled_queued_action();
+                price_slot.is_creation_queued.set(true);
+                self.throttle.lock().enqueue(
+                    ThrottledAction::CreateOrder,
+                    ThrottledItem::CreateOrder(QueuedOrder {
+                        side,
+                        price_slot_id: price_slot.id.clone(),
+                        amount: new_order_amount,
+                        estimating: new_estimating.clone(),
+                    }),
+                );
+                return log_trace(
+                    "Finished `try_create_order` because order creation is queued by throttle",
+                    explanation,
+                );
+            }
+            ThrottleDecision::Drop => {
+                self.statistics.register_throttled_dropped_action();
+                return log_trace(
+                    "Finished `try_create_order` because orders budget is exhausted",
+                    explanation,
+                );
+            }
+        }
+
+        self.reserve_and_create_order(
+            price_slot,
+            new_estimating,
+            new_order_amount,
+            true,
+            now,
+            explanation,
+        )
+    }
+
+    /// Creates order which creation was queued by throttle. Budget for it is already spent by
+    /// throttle when it's released
+    fn create_queued_order(&self, queued_order: QueuedOrder, now: DateTime) {
+        let price_slot = match self.orders_state.by_side[queued_order.side]
+            .traverse_price_slots()
+            .find(|x| x.id == queued_order.price_slot_id)
+        {
+            Some(price_slot) => price_slot,
+            None => return,
+        };
+        price_slot.is_creation_queued.set(false);
+
+        let mut explanation = Explanation::default();
+        let result = match self.find_new_order_crossing_existing_orders(
+            queued_order.estimating.disposition.price(),
+            queued_order.side,
+        ) {
+            Some(crossed_order) => log_trace(
+                format!(
+                    "Queued order creation is skipped because there is order {} crossing its price",
+                    crossed_order.client_order_id()
+                ),
+                &mut explanation,
+            ),
+            None => self.reserve_and_create_order(
+                price_slot,
+                &queued_order.estimating,
+                queued_order.amount,
+                false,
+                now,
+                &mut explanation,
+            ),
+        };
+
+        if let Err(error) = result {
+            log::error!("Failed to create queued order: {error:?}");
+        }
+    }
+
+    /// Reserves balance and requests for new order and issues it. Throttle budget is acquired
+    /// right before order is issued if `acquire_budget` is set
+    fn reserve_and_create_order(
+        &self,
+        price_slot: &PriceSlot,
+        new_estimating: &TradeCycle,
+        new_order_amount: Amount,
+        acquire_budget: bool,
+        now: DateTime,
+        explanation: &mut Explanation,
+    ) -> Result<()> {
+        let new_disposition = &new_estimating.disposition;
+        let new_price = new_disposition.order.price;
+
         let new_client_order_id = ClientOrderId::unique_id();
 
         let requests_group_id = self.engine_ctx.timeout_manager.try_reserve_group(
@@ -699,23 +1018,7 @@ impl DispositionExecutor {
             RequestType::CancelOrder,
             Some(requests_group_id),
         )? {
-            self.engine_ctx
-                .balance_manager
-                .lock()
-                .unreserve_rest(
-                    reservation_id,
-                )
-                .with_expect(|| {
-                    format!(
-                        "DispositionExecutor::try_create_order() failed to unreserve_rest for: {:?}",
-                        reservation_id
-                    )
-                });
-
-            let _ = self
-                .engine_ctx
-                .timeout_manager
-                .remove_group(self.exchange_account_id, requests_group_id)?;
+            self.release_order_reservations(reservation_id, requests_group_id)?;
 
             return log_trace(
                 "Finished `try_create_order` because can't reserve requests",
@@ -723,6 +1026,21 @@ impl DispositionExecutor {
             );
         }
 
+        if acquire_budget
+            && self
+                .throttle
+                .lock()
+                .acquire(ThrottledAction::CreateOrder, now)
+                != ThrottleDecision::Allowed
+        {
+            self.release_order_reservations(reservation_id, requests_group_id)?;
+            self.statistics.register_throttled_dropped_action();
+            return log_trace(
+                "Finished `try_create_order` because orders budget is exhausted",
+                explanation,
+            );
+        }
+
         *price_slot.estimating.borrow_mut() = Some(Box::new(new_estimating.clone()));
 
         let new_order_header = OrderHeader::new(
@@ -761,6 +1079,8 @@ impl DispositionExecutor {
         {
             let new_client_order_id = new_client_order_id.clone();
             let cancellation_token = self.cancellation_token.clone();
+            let internal_crossing = self.engine_ctx.internal_crossing.clone();
+            let configuration_descriptor = self.strategy.configuration_descriptor();
 
             let action = async move {
                 log::trace!("Begin create_order {}", new_client_order_id);
@@ -770,25 +1090,54 @@ impl DispositionExecutor {
                     price: new_price,
                 };
 
-                exchange
-                    .create_order(order_creating, None, cancellation_token)
-                    .await?;
+                match internal_crossing {
+                    Some(internal_crossing) => {
+                        internal_crossing
+                            .submit(order_creating, configuration_descriptor, cancellation_token)
+                            .await?;
+                    }
+                    None => {
+                        exchange
+                            .create_order(order_creating, None, cancellation_token)
+                            .await?;
+                    }
+                }
 
                 log::trace!("Finished create_order {}", new_client_order_id);
 
                 Ok(())
             };
-            spawn_future(
-                "wait_cancel_order in blocking cancel_order",
-                SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
-                action,
-            );
+            spawn_throttled_action("Create order in DispositionExecutor", action.boxed());
         }
 
         log::trace!("Begin try_create_order {}", new_client_order_id);
         Ok(())
     }
 
+    fn release_order_reservations(
+        &self,
+        reservation_id: ReservationId,
+        requests_group_id: RequestGroupId,
+    ) -> Result<()> {
+        self.engine_ctx
+            .balance_manager
+            .lock()
+            .unreserve_rest(reservation_id)
+            .with_expect(|| {
+                format!(
+                    "DispositionExecutor::try_create_order() failed to unreserve_rest for: {:?}",
+                    reservation_id
+                )
+            });
+
+        let _ = self
+            .engine_ctx
+            .timeout_manager
+            .remove_group(self.exchange_account_id, requests_group_id)?;
+
+        Ok(())
+    }
+
     fn find_new_order_crossing_existing_orders(
         &self,
         new_order_price: Price,
@@ -904,19 +1253,44 @@ impl DispositionExecutor {
         cloned_order: &Arc<OrderSnapshot>,
         price_slot: &PriceSlot,
     ) -> Result<()> {
+        if self.tick_budget.lock().is_suspended() {
+            return Ok(());
+        }
+
         log::trace!("Begin handle_order_fill");
 
+        let started_at = Instant::now();
         let result = self.strategy.handle_order_fill(
             cloned_order,
             price_slot,
             self.exchange_account_id,
             self.cancellation_token.clone(),
         );
+        self.register_strategy_tick(started_at);
 
         log::trace!("Finish handle_order_fill");
         result
     }
 
+    /// Strategy suspended because of exceeding tick budget doesn't manage its orders anymore,
+    /// so all of them are cancelled
+    fn register_strategy_tick(&self, started_at: Instant) {
+        if !self.tick_budget.lock().register_tick(started_at.elapsed()) {
+            return;
+        }
+
+        let mut explanation = Explanation::default();
+        for (_, state_by_side) in self.orders_state.by_side.iter() {
+            for price_slot in &state_by_side.slots {
+                self.start_cancelling_all_orders(
+                    "strategy is suspended by tick budget",
+                    &mut price_slot.order.borrow_mut(),
+                    &mut explanation,
+                );
+            }
+        }
+    }
+
     fn exchange(&self) -> Arc<Exchange> {
         self.engine_ctx
             .exchanges
@@ -927,6 +1301,14 @@ impl DispositionExecutor {
     }
 
     fn prepare_estimate_trading_context(&self, event: &ExchangeEvent, now: DateTime) -> bool {
+        // quotes aren't calculated for paused market, its orders are canceled by exchange
+        if self
+            .exchange()
+            .is_market_paused(self.symbol.currency_pair())
+        {
+            return false;
+        }
+
         let event_time = match event {
             ExchangeEvent::OrderBookEvent(order_book_event) => order_book_event.creation_time,
             ExchangeEvent::LiquidationPrice(liquidation_price) => {
@@ -1005,6 +1387,14 @@ fn get_cancelling_orders<'a>(
     cancelling_orders
 }
 
+fn spawn_throttled_action(action_name: &str, action: BoxFuture<'static, Result<()>>) {
+    spawn_future(
+        action_name,
+        SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+        action,
+    );
+}
+
 fn now() -> DateTime {
     Utc::now()
 }
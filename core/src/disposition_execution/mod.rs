@@ -1,8 +1,12 @@
 pub mod executor;
+mod order_throttle;
+pub mod rejection_analytics;
+pub mod spread_floor;
+mod tick_budget;
 pub mod trade_limit;
 mod trading_context_calculation;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
@@ -251,6 +255,8 @@ pub struct PriceSlot {
     pub id: PriceSlotId,
     pub estimating: RefCell<Option<Box<TradeCycle>>>,
     pub order: RefCell<CompositeOrder>,
+    /// Order creation is queued by throttle, so slot doesn't get new orders until it's released
+    pub is_creation_queued: Cell<bool>,
 }
 
 impl PriceSlot {
@@ -259,6 +265,7 @@ impl PriceSlot {
             id,
             estimating: RefCell::new(None),
             order: RefCell::new(CompositeOrder::new(side)),
+            is_creation_queued: Cell::new(false),
         }
     }
 
@@ -0,0 +1,245 @@
+use std::collections::VecDeque;
+
+use chrono::Duration;
+use mmb_utils::DateTime;
+
+use crate::settings::{StrategyOrderThrottleSettings, ThrottlePolicy};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum ThrottledAction {
+    CreateOrder,
+    CancelOrder,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum ThrottleDecision {
+    Allowed,
+    Queue,
+    Drop,
+}
+
+#[derive(Debug, Default)]
+struct ActionBudget {
+    max_per_second: Option<usize>,
+    max_per_tick: Option<usize>,
+    issue_times: VecDeque<DateTime>,
+    issued_in_tick: usize,
+}
+
+impl ActionBudget {
+    fn new(max_per_second: Option<usize>, max_per_tick: Option<usize>) -> Self {
+        ActionBudget {
+            max_per_second,
+            max_per_tick,
+            ..Default::default()
+        }
+    }
+
+    fn has_capacity(&mut self, now: DateTime) -> bool {
+        let window_start = now - Duration::seconds(1);
+        while matches!(self.issue_times.front(), Some(time) if *time <= window_start) {
+            let _ = self.issue_times.pop_front();
+        }
+
+        self.max_per_tick
+            .is_none_or(|max| self.issued_in_tick < max)
+            && self
+                .max_per_second
+                .is_none_or(|max| self.issue_times.len() < max)
+    }
+
+    fn register(&mut self, now: DateTime) {
+        if self.max_per_second.is_some() {
+            self.issue_times.push_back(now);
+        }
+        self.issued_in_tick += 1;
+    }
+}
+
+/// Limits how many orders and cancels strategy issues per second and per tick
+/// to respect exchange limits and to contain buggy loops
+pub(crate) struct OrderThrottle<T> {
+    policy: ThrottlePolicy,
+    max_queue_size: Option<usize>,
+    orders: ActionBudget,
+    cancels: ActionBudget,
+    queue: VecDeque<(ThrottledAction, T)>,
+}
+
+impl<T> OrderThrottle<T> {
+    pub fn new(settings: Option<&StrategyOrderThrottleSettings>) -> Self {
+        match settings {
+            None => OrderThrottle {
+                policy: ThrottlePolicy::Drop,
+                max_queue_size: None,
+                orders: ActionBudget::default(),
+                cancels: ActionBudget::default(),
+                queue: VecDeque::new(),
+            },
+            Some(settings) => OrderThrottle {
+                policy: settings.policy,
+                max_queue_size: settings.max_queue_size,
+                orders: ActionBudget::new(
+                    settings.max_orders_per_second,
+                    settings.max_orders_per_tick,
+                ),
+                cancels: ActionBudget::new(
+                    settings.max_cancels_per_second,
+                    settings.max_cancels_per_tick,
+                ),
+                queue: VecDeque::new(),
+            },
+        }
+    }
+
+    fn budget(&mut self, action: ThrottledAction) -> &mut ActionBudget {
+        match action {
+            ThrottledAction::CreateOrder => &mut self.orders,
+            ThrottledAction::CancelOrder => &mut self.cancels,
+        }
+    }
+
+    /// Start new tick and return queued actions which fit budget now
+    pub fn start_tick(&mut self, now: DateTime) -> Vec<T> {
+        self.orders.issued_in_tick = 0;
+        self.cancels.issued_in_tick = 0;
+
+        let mut released = vec![];
+        while let Some(&(action, _)) = self.queue.front() {
+            let budget = self.budget(action);
+            if !budget.has_capacity(now) {
+                break;
+            }
+            budget.register(now);
+
+            if let Some((_, item)) = self.queue.pop_front() {
+                released.push(item);
+            }
+        }
+
+        released
+    }
+
+    /// Decision for action without counting it in budget, so action which isn't issued
+    /// doesn't spend budget. Allowed action should be counted by `acquire` when it's issued
+    pub fn check(&mut self, action: ThrottledAction, now: DateTime) -> ThrottleDecision {
+        // new actions shouldn't overtake queued ones
+        if self.queue.is_empty() && self.budget(action).has_capacity(now) {
+            return ThrottleDecision::Allowed;
+        }
+
+        match self.policy {
+            ThrottlePolicy::Drop => ThrottleDecision::Drop,
+            ThrottlePolicy::Queue => match self.max_queue_size {
+                Some(max) if self.queue.len() >= max => ThrottleDecision::Drop,
+                _ => ThrottleDecision::Queue,
+            },
+        }
+    }
+
+    /// Allowed action is counted in budget immediately.
+    /// Action should be passed to `enqueue` if decision is `Queue`
+    pub fn acquire(&mut self, action: ThrottledAction, now: DateTime) -> ThrottleDecision {
+        let decision = self.check(action, now);
+        if decision == ThrottleDecision::Allowed {
+            self.budget(action).register(now);
+        }
+
+        decision
+    }
+
+    pub fn enqueue(&mut self, action: ThrottledAction, item: T) {
+        self.queue.push_back((action, item));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service_configuration::configuration_descriptor::ServiceName;
+    use chrono::Utc;
+
+    fn throttle(policy: ThrottlePolicy) -> OrderThrottle<u32> {
+        OrderThrottle::new(Some(&StrategyOrderThrottleSettings {
+            service_name: ServiceName::new("test"),
+            max_orders_per_second: Some(3),
+            max_orders_per_tick: Some(2),
+            max_cancels_per_second: None,
+            max_cancels_per_tick: Some(1),
+            policy,
+            max_queue_size: Some(2),
+        }))
+    }
+
+    #[test]
+    pub fn unlimited_without_settings() {
+        let mut throttle = OrderThrottle::<u32>::new(None);
+        let now = Utc::now();
+
+        for _ in 0..100 {
+            assert_eq!(
+                throttle.acquire(ThrottledAction::CreateOrder, now),
+                ThrottleDecision::Allowed
+            );
+        }
+    }
+
+    #[test]
+    pub fn per_tick_and_per_second_limits() {
+        let mut throttle = throttle(ThrottlePolicy::Drop);
+        let now = Utc::now();
+        use ThrottleDecision::*;
+        use ThrottledAction::*;
+
+        assert_eq!(throttle.acquire(CreateOrder, now), Allowed);
+        assert_eq!(throttle.acquire(CreateOrder, now), Allowed);
+        assert_eq!(throttle.acquire(CreateOrder, now), Drop);
+        assert_eq!(throttle.acquire(CancelOrder, now), Allowed);
+        assert_eq!(throttle.acquire(CancelOrder, now), Drop);
+
+        assert!(throttle.start_tick(now).is_empty());
+        assert_eq!(throttle.acquire(CreateOrder, now), Allowed);
+        // per second limit is exhausted
+        assert_eq!(throttle.acquire(CreateOrder, now), Drop);
+        assert_eq!(throttle.acquire(CancelOrder, now), Allowed);
+
+        let next_second = now + Duration::seconds(1);
+        let _ = throttle.start_tick(next_second);
+        assert_eq!(throttle.acquire(CreateOrder, next_second), Allowed);
+    }
+
+    #[test]
+    pub fn queued_actions_are_released_in_order() {
+        let mut throttle = throttle(ThrottlePolicy::Queue);
+        let now = Utc::now();
+        use ThrottleDecision::*;
+        use ThrottledAction::*;
+
+        assert_eq!(throttle.acquire(CancelOrder, now), Allowed);
+        assert_eq!(throttle.acquire(CancelOrder, now), Queue);
+        throttle.enqueue(CancelOrder, 1);
+        // can't overtake queued cancel
+        assert_eq!(throttle.acquire(CreateOrder, now), Queue);
+        throttle.enqueue(CreateOrder, 2);
+        // queue is full
+        assert_eq!(throttle.acquire(CreateOrder, now), Drop);
+
+        assert_eq!(throttle.start_tick(now), vec![1, 2]);
+        assert_eq!(throttle.acquire(CreateOrder, now), Allowed);
+    }
+
+    #[test]
+    pub fn check_does_not_spend_budget() {
+        let mut throttle = throttle(ThrottlePolicy::Drop);
+        let now = Utc::now();
+        use ThrottleDecision::*;
+        use ThrottledAction::*;
+
+        for _ in 0..5 {
+            assert_eq!(throttle.check(CreateOrder, now), Allowed);
+        }
+        assert_eq!(throttle.acquire(CreateOrder, now), Allowed);
+        assert_eq!(throttle.acquire(CreateOrder, now), Allowed);
+        assert_eq!(throttle.check(CreateOrder, now), Drop);
+    }
+}
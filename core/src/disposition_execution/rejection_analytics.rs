@@ -0,0 +1,352 @@
+use std::collections::VecDeque;
+
+use chrono::Duration;
+use mmb_utils::{nothing_to_do, DateTime};
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::disposition_execution::TradingContext;
+use crate::exchanges::common::ExchangeErrorType;
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::math::ConvertPercentToRate;
+use crate::orders::order::OrderSide;
+use crate::settings::RejectionAnalyticsSettings;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum RejectionReason {
+    MinNotional,
+    /// Price is out of allowed range or order would immediately match as taker
+    PriceFilter,
+    AmountFilter,
+    RateLimit,
+    InsufficientFunds,
+    Other,
+}
+
+impl RejectionReason {
+    /// Reasons which quoting can be adjusted for
+    fn is_adjustable(self) -> bool {
+        matches!(
+            self,
+            RejectionReason::PriceFilter
+                | RejectionReason::RateLimit
+                | RejectionReason::InsufficientFunds
+        )
+    }
+}
+
+/// Reason of order creation failure. Exchanges report filter failures as invalid order,
+/// so they are recognized by error message
+pub fn classify_rejection(error_type: Option<ExchangeErrorType>, message: &str) -> RejectionReason {
+    match error_type {
+        Some(ExchangeErrorType::RateLimit) => return RejectionReason::RateLimit,
+        Some(ExchangeErrorType::InsufficientFunds) => return RejectionReason::InsufficientFunds,
+        _ => {}
+    }
+
+    let message = message.to_lowercase();
+    let contains_any = |patterns: &[&str]| patterns.iter().any(|x| message.contains(x));
+
+    if contains_any(&["notional"]) {
+        RejectionReason::MinNotional
+    } else if contains_any(&["price", "immediately match", "post only", "post-only"]) {
+        RejectionReason::PriceFilter
+    } else if contains_any(&["lot_size", "quantity", "amount", "qty"]) {
+        RejectionReason::AmountFilter
+    } else if contains_any(&["too many requests", "rate limit"]) {
+        RejectionReason::RateLimit
+    } else if contains_any(&["insufficient"]) {
+        RejectionReason::InsufficientFunds
+    } else {
+        RejectionReason::Other
+    }
+}
+
+/// Changes of quoting caused by rejections
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct QuotingAdjustment {
+    /// Distance quotes are moved away from market by
+    pub price_collar: Percent,
+    /// Count of outer levels which aren't quoted
+    pub removed_levels: usize,
+}
+
+impl QuotingAdjustment {
+    fn is_empty(&self) -> bool {
+        self == &QuotingAdjustment::default()
+    }
+}
+
+/// Recent rejections of market orders and quoting adjustment caused by them
+pub struct RejectionAnalytics {
+    settings: RejectionAnalyticsSettings,
+    recent_rejections: VecDeque<(DateTime, RejectionReason)>,
+    adjustment: QuotingAdjustment,
+    last_change_time: Option<DateTime>,
+}
+
+impl RejectionAnalytics {
+    pub fn new(settings: RejectionAnalyticsSettings) -> Self {
+        RejectionAnalytics {
+            settings,
+            recent_rejections: VecDeque::new(),
+            adjustment: QuotingAdjustment::default(),
+            last_change_time: None,
+        }
+    }
+
+    pub fn adjustment(&self) -> QuotingAdjustment {
+        self.adjustment
+    }
+
+    fn window(&self) -> Duration {
+        Duration::milliseconds(self.settings.window_ms as i64)
+    }
+
+    pub fn register(&mut self, reason: RejectionReason, now: DateTime) {
+        self.remove_outdated(now);
+        self.recent_rejections.push_back((now, reason));
+
+        if !self.settings.auto_adjust || !reason.is_adjustable() {
+            return;
+        }
+
+        let count = self
+            .recent_rejections
+            .iter()
+            .filter(|(_, x)| *x == reason)
+            .count();
+        if count < self.settings.threshold {
+            return;
+        }
+
+        let previous = self.adjustment;
+        match reason {
+            RejectionReason::PriceFilter => {
+                self.adjustment.price_collar = (self.adjustment.price_collar
+                    + self.settings.price_collar_step)
+                    .min(self.settings.max_price_collar);
+            }
+            RejectionReason::RateLimit | RejectionReason::InsufficientFunds => {
+                self.adjustment.removed_levels =
+                    (self.adjustment.removed_levels + 1).min(self.settings.max_removed_levels);
+            }
+            RejectionReason::MinNotional
+            | RejectionReason::AmountFilter
+            | RejectionReason::Other => nothing_to_do(),
+        }
+
+        // next step requires new rejections
+        self.recent_rejections.retain(|(_, x)| *x != reason);
+        self.last_change_time = Some(now);
+
+        if previous != self.adjustment {
+            log::warn!(
+                "Quoting is adjusted to {:?} because of {count} rejections {reason:?}",
+                self.adjustment
+            );
+        }
+    }
+
+    /// Makes one step back to normal quoting if there were no rejections during window since last change
+    pub fn relax(&mut self, now: DateTime) {
+        self.remove_outdated(now);
+
+        let has_recent_rejections = self
+            .recent_rejections
+            .iter()
+            .any(|(_, x)| x.is_adjustable());
+        if self.adjustment.is_empty() || has_recent_rejections {
+            return;
+        }
+
+        let window = self.window();
+        if self
+            .last_change_time
+            .is_some_and(|time| now - time < window)
+        {
+            return;
+        }
+
+        self.adjustment.price_collar =
+            (self.adjustment.price_collar - self.settings.price_collar_step).max(dec!(0));
+        self.adjustment.removed_levels = self.adjustment.removed_levels.saturating_sub(1);
+        self.last_change_time = Some(now);
+
+        log::info!("Quoting adjustment is relaxed to {:?}", self.adjustment);
+    }
+
+    fn remove_outdated(&mut self, now: DateTime) {
+        let window = self.window();
+        while let Some((time, _)) = self.recent_rejections.front() {
+            if now - *time < window {
+                break;
+            }
+
+            let _ = self.recent_rejections.pop_front();
+        }
+    }
+}
+
+/// Moves quotes away from market by price collar and removes outer levels (the first level is always kept).
+/// `cause` of adjustment is added to explanations, e.g. "order rejections"
+pub fn apply_quoting_adjustment(
+    trading_context: &mut TradingContext,
+    adjustment: QuotingAdjustment,
+    symbol: &Symbol,
+    cause: &str,
+) {
+    if adjustment.is_empty() {
+        return;
+    }
+
+    let collar_rate = adjustment.price_collar.percent_to_rate();
+    for (side, trading_context_by_side) in trading_context.by_side.iter_mut() {
+        let levels_count = trading_context_by_side.estimating.len();
+        let quoted_levels = levels_count
+            .saturating_sub(adjustment.removed_levels)
+            .max(1);
+
+        for (level_index, with_explanation) in
+            trading_context_by_side.estimating.iter_mut().enumerate()
+        {
+            let (trade_cycle, explanation) = with_explanation.as_mut_all();
+            if level_index >= quoted_levels {
+                if trade_cycle.take().is_some() {
+                    explanation
+                        .add_reason(format!("Level {level_index} is removed because of {cause}"));
+                }
+                continue;
+            }
+
+            if let Some(trade_cycle) = trade_cycle {
+                if collar_rate.is_zero() {
+                    continue;
+                }
+
+                let order = &mut trade_cycle.disposition.order;
+                let old_price = order.price;
+                order.price = match side {
+                    OrderSide::Buy => {
+                        symbol.price_round(old_price * (dec!(1) - collar_rate), Round::Floor)
+                    }
+                    OrderSide::Sell => {
+                        symbol.price_round(old_price * (dec!(1) + collar_rate), Round::Ceiling)
+                    }
+                };
+                explanation.add_reason(format!(
+                    "Price {old_price} is moved to {} by price collar {}% because of {cause}",
+                    order.price, adjustment.price_collar
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use rstest::rstest;
+
+    fn settings(auto_adjust: bool) -> RejectionAnalyticsSettings {
+        RejectionAnalyticsSettings {
+            window_ms: 10_000,
+            threshold: 3,
+            auto_adjust,
+            price_collar_step: dec!(0.1),
+            max_price_collar: dec!(0.2),
+            max_removed_levels: 2,
+        }
+    }
+
+    #[rstest]
+    #[case(Some(ExchangeErrorType::RateLimit), "", RejectionReason::RateLimit)]
+    #[case(
+        Some(ExchangeErrorType::InvalidOrder),
+        "Filter failure: MIN_NOTIONAL",
+        RejectionReason::MinNotional
+    )]
+    #[case(
+        Some(ExchangeErrorType::InvalidOrder),
+        "Filter failure: PERCENT_PRICE",
+        RejectionReason::PriceFilter
+    )]
+    #[case(
+        Some(ExchangeErrorType::InvalidOrder),
+        "Order would immediately match and take.",
+        RejectionReason::PriceFilter
+    )]
+    #[case(
+        Some(ExchangeErrorType::InvalidOrder),
+        "Filter failure: LOT_SIZE",
+        RejectionReason::AmountFilter
+    )]
+    #[case(
+        Some(ExchangeErrorType::Unknown),
+        "Account has insufficient balance",
+        RejectionReason::InsufficientFunds
+    )]
+    #[case(None, "Unexpected error", RejectionReason::Other)]
+    pub fn rejection_classification(
+        #[case] error_type: Option<ExchangeErrorType>,
+        #[case] message: &str,
+        #[case] expected: RejectionReason,
+    ) {
+        assert_eq!(classify_rejection(error_type, message), expected);
+    }
+
+    #[test]
+    pub fn adjustment_on_threshold() {
+        let now = Utc::now();
+        let mut analytics = RejectionAnalytics::new(settings(true));
+
+        for _ in 0..2 {
+            analytics.register(RejectionReason::PriceFilter, now);
+            analytics.register(RejectionReason::RateLimit, now);
+        }
+        assert_eq!(analytics.adjustment(), QuotingAdjustment::default());
+
+        analytics.register(RejectionReason::PriceFilter, now);
+        analytics.register(RejectionReason::RateLimit, now);
+        assert_eq!(
+            analytics.adjustment(),
+            QuotingAdjustment {
+                price_collar: dec!(0.1),
+                removed_levels: 1,
+            }
+        );
+    }
+
+    #[test]
+    pub fn adjustment_is_limited_and_relaxed() {
+        let now = Utc::now();
+        let mut analytics = RejectionAnalytics::new(settings(true));
+
+        for _ in 0..9 {
+            analytics.register(RejectionReason::PriceFilter, now);
+        }
+        assert_eq!(analytics.adjustment().price_collar, dec!(0.2));
+
+        analytics.relax(now + Duration::seconds(5));
+        assert_eq!(analytics.adjustment().price_collar, dec!(0.2));
+
+        analytics.relax(now + Duration::seconds(10));
+        assert_eq!(analytics.adjustment().price_collar, dec!(0.1));
+
+        analytics.relax(now + Duration::seconds(20));
+        assert_eq!(analytics.adjustment(), QuotingAdjustment::default());
+    }
+
+    #[test]
+    pub fn rejections_are_only_collected_without_auto_adjust() {
+        let now = Utc::now();
+        let mut analytics = RejectionAnalytics::new(settings(false));
+
+        for _ in 0..5 {
+            analytics.register(RejectionReason::RateLimit, now);
+        }
+        assert_eq!(analytics.adjustment(), QuotingAdjustment::default());
+    }
+}
@@ -0,0 +1,191 @@
+use itertools::Itertools;
+use rust_decimal_macros::dec;
+
+use crate::disposition_execution::{TradingContext, TradingContextBySide};
+use crate::exchanges::common::Price;
+use crate::exchanges::general::commission::{Commission, Percent};
+use crate::math::ConvertPercentToRate;
+use crate::orders::order::OrderSide;
+use crate::settings::SpreadFloorSettings;
+
+/// Minimal distance between buy and sell quotes that doesn't lead to guaranteed loss after
+/// paying maker fees on both sides
+pub fn calculate_spread_floor(
+    middle_price: Price,
+    commission: &Commission,
+    min_edge: Percent,
+) -> Price {
+    let maker_fee_rate = commission.maker.fee.percent_to_rate();
+    middle_price * (maker_fee_rate * dec!(2) + min_edge.percent_to_rate())
+}
+
+/// Removes estimations from trading context if best buy and sell quotes are closer to each other
+/// than spread floor (or only warns about it if `allow_below_floor` is set)
+pub fn apply_spread_floor(
+    trading_context: &mut TradingContext,
+    commission: &Commission,
+    settings: &SpreadFloorSettings,
+) {
+    let best_buy_price = best_price(&trading_context.by_side[OrderSide::Buy], OrderSide::Buy);
+    let best_sell_price = best_price(&trading_context.by_side[OrderSide::Sell], OrderSide::Sell);
+
+    let (buy_price, sell_price) = match (best_buy_price, best_sell_price) {
+        (Some(buy_price), Some(sell_price)) => (buy_price, sell_price),
+        // quoting one side only can't produce guaranteed loss
+        _ => return,
+    };
+
+    let middle_price = (buy_price + sell_price) / dec!(2);
+    let spread_floor = calculate_spread_floor(middle_price, commission, settings.min_edge);
+    let spread = sell_price - buy_price;
+    if spread >= spread_floor {
+        return;
+    }
+
+    let msg = format!(
+        "Spread {spread} between buy {buy_price} and sell {sell_price} is less than spread floor {spread_floor} for {}",
+        settings.currency_pair
+    );
+
+    if settings.allow_below_floor {
+        let msg = format!("{msg}. Quoting is allowed by settings");
+        log::warn!("{msg}");
+        add_reason_for_all(trading_context, &msg);
+        return;
+    }
+
+    log::warn!("{msg}. Quotes are removed");
+    for (_, trading_context_by_side) in trading_context.by_side.iter_mut() {
+        for with_explanation in &mut trading_context_by_side.estimating {
+            let (trade_cycle, explanation) = with_explanation.as_mut_all();
+            if trade_cycle.take().is_some() {
+                explanation.add_reason(format!("{msg}. Quote is removed"));
+            }
+        }
+    }
+}
+
+fn best_price(trading_context_by_side: &TradingContextBySide, side: OrderSide) -> Option<Price> {
+    let prices = trading_context_by_side
+        .estimating
+        .iter()
+        .filter_map(|x| x.value.as_ref().map(|tc| tc.disposition.price()))
+        .collect_vec();
+
+    match side {
+        OrderSide::Buy => prices.into_iter().max(),
+        OrderSide::Sell => prices.into_iter().min(),
+    }
+}
+
+fn add_reason_for_all(trading_context: &mut TradingContext, reason: &str) {
+    for (_, trading_context_by_side) in trading_context.by_side.iter_mut() {
+        for with_explanation in &mut trading_context_by_side.estimating {
+            with_explanation.explanation.add_reason(reason);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disposition_execution::{TradeCycle, TradeDisposition};
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId, MarketAccountId};
+    use crate::exchanges::general::commission::CommissionForType;
+    use crate::explanation::{Explanation, WithExplanation};
+    use crate::orders::order::OrderRole;
+    use rstest::rstest;
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    fn commission() -> Commission {
+        Commission::new(
+            CommissionForType::new(dec!(0.1), dec!(0)),
+            CommissionForType::new(dec!(0.2), dec!(0)),
+        )
+    }
+
+    fn side_context(side: OrderSide, price: Price) -> TradingContextBySide {
+        let market_account_id =
+            MarketAccountId::new(ExchangeAccountId::new("Binance", 0), currency_pair());
+
+        TradingContextBySide {
+            max_amount: dec!(1),
+            estimating: vec![WithExplanation {
+                value: Some(TradeCycle {
+                    order_role: OrderRole::Maker,
+                    strategy_name: "test".to_string(),
+                    disposition: TradeDisposition::new(market_account_id, side, price, dec!(1)),
+                }),
+                explanation: Explanation::default(),
+            }],
+        }
+    }
+
+    fn settings(allow_below_floor: bool) -> SpreadFloorSettings {
+        SpreadFloorSettings {
+            currency_pair: currency_pair(),
+            min_edge: dec!(0.05),
+            allow_below_floor,
+        }
+    }
+
+    fn is_quoting(trading_context: &TradingContext, side: OrderSide) -> bool {
+        trading_context.by_side[side].estimating[0].value.is_some()
+    }
+
+    #[test]
+    pub fn spread_floor_calculation() {
+        // 1000 * (0.1% * 2 + 0.05%)
+        assert_eq!(
+            calculate_spread_floor(dec!(1000), &commission(), dec!(0.05)),
+            dec!(2.5)
+        );
+    }
+
+    #[rstest]
+    #[case(dec!(998), dec!(1002), false, true)]
+    #[case(dec!(998.75), dec!(1001.25), false, true)]
+    #[case(dec!(999), dec!(1001), false, false)]
+    #[case(dec!(999), dec!(1001), true, true)]
+    pub fn apply_spread_floor_to_trading_context(
+        #[case] buy_price: Price,
+        #[case] sell_price: Price,
+        #[case] allow_below_floor: bool,
+        #[case] expected_quoting: bool,
+    ) {
+        let mut trading_context = TradingContext::new(
+            side_context(OrderSide::Buy, buy_price),
+            side_context(OrderSide::Sell, sell_price),
+        );
+
+        apply_spread_floor(
+            &mut trading_context,
+            &commission(),
+            &settings(allow_below_floor),
+        );
+
+        assert_eq!(
+            is_quoting(&trading_context, OrderSide::Buy),
+            expected_quoting
+        );
+        assert_eq!(
+            is_quoting(&trading_context, OrderSide::Sell),
+            expected_quoting
+        );
+    }
+
+    #[test]
+    pub fn one_side_quoting_is_not_limited() {
+        let mut trading_context = TradingContext::new(
+            side_context(OrderSide::Buy, dec!(1000)),
+            TradingContextBySide::empty(1, Explanation::default()),
+        );
+
+        apply_spread_floor(&mut trading_context, &commission(), &settings(false));
+
+        assert!(is_quoting(&trading_context, OrderSide::Buy));
+    }
+}
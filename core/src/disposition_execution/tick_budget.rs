@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use crate::settings::StrategyTickBudgetSettings;
+
+/// Checks time of strategy callbacks against budget of strategy. Callbacks are synchronous, so
+/// strategy exceeding budget repeatedly is suspended to avoid stalling its event loop
+pub(crate) struct TickBudget {
+    settings: Option<StrategyTickBudgetSettings>,
+    consecutive_overruns: usize,
+    is_suspended: bool,
+}
+
+impl TickBudget {
+    pub fn new(settings: Option<&StrategyTickBudgetSettings>) -> Self {
+        TickBudget {
+            settings: settings.cloned(),
+            consecutive_overruns: 0,
+            is_suspended: false,
+        }
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.is_suspended
+    }
+
+    /// Registers time of strategy callback. Returns true if strategy is suspended by this tick
+    pub fn register_tick(&mut self, elapsed: Duration) -> bool {
+        let settings = match &self.settings {
+            Some(settings) => settings,
+            None => return false,
+        };
+
+        let budget = Duration::from_millis(settings.budget_ms);
+        if elapsed <= budget {
+            self.consecutive_overruns = 0;
+            return false;
+        }
+
+        self.consecutive_overruns += 1;
+        log::debug!(
+            "Strategy {} exceeded tick budget {budget:?} with {elapsed:?}",
+            settings.service_name
+        );
+        if self.consecutive_overruns != settings.max_overruns {
+            return false;
+        }
+
+        log::error!(
+            "Strategy {} exceeded tick budget {budget:?} in {} ticks in a row, the last one took {elapsed:?}",
+            settings.service_name,
+            self.consecutive_overruns
+        );
+        if !settings.suspend || self.is_suspended {
+            return false;
+        }
+
+        log::error!(
+            "Strategy {} is suspended because of exceeding tick budget",
+            settings.service_name
+        );
+        self.is_suspended = true;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service_configuration::configuration_descriptor::ServiceName;
+
+    fn tick_budget(suspend: bool) -> TickBudget {
+        TickBudget::new(Some(&StrategyTickBudgetSettings {
+            service_name: ServiceName::new("test"),
+            budget_ms: 10,
+            max_overruns: 3,
+            suspend,
+        }))
+    }
+
+    #[test]
+    pub fn suspend_after_consecutive_overruns() {
+        let mut budget = tick_budget(true);
+        let slow = Duration::from_millis(20);
+
+        assert!(!budget.register_tick(slow));
+        assert!(!budget.register_tick(slow));
+        // fast tick resets overruns
+        assert!(!budget.register_tick(Duration::from_millis(5)));
+        assert!(!budget.register_tick(slow));
+        assert!(!budget.register_tick(slow));
+        assert!(!budget.is_suspended());
+
+        assert!(budget.register_tick(slow));
+        assert!(budget.is_suspended());
+    }
+
+    #[test]
+    pub fn overruns_are_only_alerted_without_suspension() {
+        let mut budget = tick_budget(false);
+
+        for _ in 0..5 {
+            assert!(!budget.register_tick(Duration::from_millis(20)));
+        }
+        assert!(!budget.is_suspended());
+    }
+}
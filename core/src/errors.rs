@@ -0,0 +1,156 @@
+use serde::Serialize;
+
+use crate::connectivity::ConnectivityError;
+use crate::exchanges::common::{ExchangeError, ExchangeErrorType};
+use crate::exchanges::margin::MarginRiskError;
+use crate::orders::currency_restrictions::CurrencyRestrictionError;
+use crate::orders::market_rollout::MarketRolloutError;
+use crate::orders::price_protection::PriceProtectionError;
+use crate::orders::reduce_only::ReduceOnlyError;
+use crate::services::value_at_risk::ValueAtRiskError;
+
+pub use mmb_rpc::error_codes::{ErrorClass, ErrorCode};
+
+/// Entry of error codes catalog returned by control API
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct ErrorCodeInfo {
+    pub code: u32,
+    pub name: ErrorCode,
+    pub class: ErrorClass,
+    pub description: &'static str,
+}
+
+pub fn error_codes_catalog() -> Vec<ErrorCodeInfo> {
+    ErrorCode::all()
+        .map(|code| ErrorCodeInfo {
+            code: code.code(),
+            name: code,
+            class: code.class(),
+            description: code.description(),
+        })
+        .collect()
+}
+
+pub trait WithErrorCode {
+    fn error_code(&self) -> ErrorCode;
+}
+
+impl WithErrorCode for ConnectivityError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            ConnectivityError::NotReady => ErrorCode::ConnectivityNotReady,
+            ConnectivityError::FailedToConnect(..)
+            | ConnectivityError::FailedToGetParams(..)
+            | ConnectivityError::FailedToConnectConnector(..) => {
+                ErrorCode::WebSocketConnectionFailed
+            }
+            ConnectivityError::SecondaryConnectorIsNotPresent | ConnectivityError::NotConnected => {
+                ErrorCode::NotConnected
+            }
+        }
+    }
+}
+
+impl WithErrorCode for ExchangeError {
+    fn error_code(&self) -> ErrorCode {
+        match self.error_type {
+            ExchangeErrorType::Unknown => ErrorCode::ExchangeUnknown,
+            ExchangeErrorType::SendError => ErrorCode::ExchangeSendError,
+            ExchangeErrorType::RateLimit => ErrorCode::ExchangeRateLimit,
+            ExchangeErrorType::OrderNotFound => ErrorCode::OrderNotFound,
+            ExchangeErrorType::OrderCompleted => ErrorCode::OrderCompleted,
+            ExchangeErrorType::InsufficientFunds => ErrorCode::InsufficientFunds,
+            ExchangeErrorType::InvalidOrder => ErrorCode::InvalidOrder,
+            ExchangeErrorType::Authentication => ErrorCode::ExchangeAuthentication,
+            ExchangeErrorType::ParsingError => ErrorCode::ExchangeParsingError,
+            ExchangeErrorType::PendingError(_) => ErrorCode::ExchangePending,
+            ExchangeErrorType::ServiceUnavailable => ErrorCode::ExchangeServiceUnavailable,
+            ExchangeErrorType::Unsupported => ErrorCode::ExchangeUnsupported,
+        }
+    }
+}
+
+impl WithErrorCode for anyhow::Error {
+    /// Code of the outermost classified error in chain of causes. Unclassified errors are internal
+    fn error_code(&self) -> ErrorCode {
+        if let Some(code) = self.downcast_ref::<ErrorCode>() {
+            return *code;
+        }
+
+        self.chain()
+            .find_map(|err| {
+                if let Some(err) = err.downcast_ref::<ExchangeError>() {
+                    Some(err.error_code())
+                } else if let Some(err) = err.downcast_ref::<ConnectivityError>() {
+                    Some(err.error_code())
+                } else if err.is::<PriceProtectionError>() {
+                    Some(ErrorCode::PriceProtection)
+                } else if err.is::<CurrencyRestrictionError>() {
+                    Some(ErrorCode::CurrencyRestriction)
+                } else if err.is::<ReduceOnlyError>() {
+                    Some(ErrorCode::ReduceOnly)
+                } else if err.is::<MarginRiskError>() {
+                    Some(ErrorCode::MarginRisk)
+                } else if err.is::<MarketRolloutError>() {
+                    Some(ErrorCode::MarketRollout)
+                } else if err.is::<ValueAtRiskError>() {
+                    Some(ErrorCode::ValueAtRisk)
+                } else if err.is::<toml_edit::TomlError>() || err.is::<toml_edit::de::Error>() {
+                    Some(ErrorCode::InvalidConfig)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(ErrorCode::Internal)
+    }
+}
+
+/// Used by spawned futures to add error code to logs of their errors
+pub(crate) fn format_error_code(err: &anyhow::Error) -> String {
+    err.error_code().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::collections::HashSet;
+
+    #[test]
+    pub fn codes_are_unique_and_match_classes() {
+        let codes: HashSet<_> = ErrorCode::all().map(|x| x.code()).collect();
+        assert_eq!(codes.len(), ErrorCode::all().count());
+        assert_eq!(error_codes_catalog().len(), codes.len());
+
+        assert_eq!(ErrorCode::NotConnected.class(), ErrorClass::Connectivity);
+        assert_eq!(ErrorCode::ExchangeRateLimit.class(), ErrorClass::Exchange);
+        assert_eq!(ErrorCode::MarginRisk.class(), ErrorClass::Risk);
+        assert_eq!(ErrorCode::InvalidConfig.class(), ErrorClass::Config);
+        assert_eq!(ErrorCode::Internal.class(), ErrorClass::Internal);
+        assert_eq!(ErrorCode::Unauthorized.class(), ErrorClass::Api);
+        assert_eq!(ErrorCode::ExchangeRateLimit.to_string(), "E2002");
+    }
+
+    #[test]
+    pub fn classify_anyhow_errors_by_causes() {
+        let rate_limit = ExchangeError::new(ExchangeErrorType::RateLimit, "limit".to_owned(), None);
+        let err = anyhow::Error::new(rate_limit).context("Failed to create order");
+        assert_eq!(err.error_code(), ErrorCode::ExchangeRateLimit);
+
+        let err = anyhow::Error::new(ReduceOnlyError::PositionIncreasing {
+            amount: Default::default(),
+            closable_position: Default::default(),
+        });
+        assert_eq!(err.error_code(), ErrorCode::ReduceOnly);
+
+        let err = anyhow::Error::new(MarginRiskError::MarginInfoUnavailable)
+            .context("Order creation for BTC/USDT is rejected by margin risk");
+        assert_eq!(err.error_code(), ErrorCode::MarginRisk);
+
+        let err = anyhow!("Something went wrong").context(ErrorCode::InvalidConfig);
+        assert_eq!(err.error_code(), ErrorCode::InvalidConfig);
+
+        let err = anyhow!("Something went wrong").context("Failed to do action");
+        assert_eq!(err.error_code(), ErrorCode::Internal);
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use mmb_utils::cancellation_token::CancellationToken;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+
+use crate::exchanges::common::{ExchangeAccountId, ExchangeError, ExchangeErrorType};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::venue_metrics::VenueMetricsSnapshot;
+use crate::exchanges::general::venue_selection::{
+    select_venue, VenueCandidate, WeightedVenueScorer,
+};
+use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
+use crate::orders::order::{OrderCreating, OrderSide};
+use crate::orders::pool::OrderRef;
+use crate::settings::{AccountGroupSettings, AccountRouting};
+
+struct AccountGroup {
+    members: Vec<ExchangeAccountId>,
+    routing: AccountRouting,
+    venue_scorer: WeightedVenueScorer,
+    next_index: AtomicUsize,
+    /// Accounts excluded from routing because their keys are failing
+    excluded: Mutex<HashSet<ExchangeAccountId>>,
+}
+
+impl AccountGroup {
+    fn new(settings: &AccountGroupSettings) -> Result<Self> {
+        let members = settings.exchange_account_ids.clone();
+        let first = match members.first() {
+            None => bail!("Account group {} has no accounts", settings.name),
+            Some(first) => first,
+        };
+        if let Some(other) = members.iter().find(|x| x.exchange_id != first.exchange_id) {
+            bail!(
+                "Account group {} contains accounts of different exchanges: {first} and {other}",
+                settings.name
+            );
+        }
+
+        Ok(AccountGroup {
+            members,
+            routing: settings.routing,
+            venue_scorer: settings.venue_scorer.clone(),
+            next_index: AtomicUsize::new(0),
+            excluded: Default::default(),
+        })
+    }
+
+    fn select(
+        &self,
+        available_requests_count: impl Fn(ExchangeAccountId) -> usize,
+        venue_metrics: impl Fn(ExchangeAccountId) -> VenueMetricsSnapshot,
+    ) -> Option<ExchangeAccountId> {
+        let excluded = self.excluded.lock();
+        let active_members = self.members.iter().filter(|x| !excluded.contains(x));
+
+        match self.routing {
+            AccountRouting::RoundRobin => {
+                let active_members = active_members.collect::<Vec<_>>();
+                if active_members.is_empty() {
+                    return None;
+                }
+                let index = self.next_index.fetch_add(1, Ordering::Relaxed);
+                Some(*active_members[index % active_members.len()])
+            }
+            // the first account is preferred when budgets are equal
+            AccountRouting::RateBudget => active_members
+                .rev()
+                .max_by_key(|&&x| available_requests_count(x))
+                .copied(),
+            AccountRouting::VenueScore => {
+                // accounts of group share order book, so they differ by latency and reject rate only
+                let candidates = active_members
+                    .map(|&exchange_account_id| VenueCandidate {
+                        exchange_account_id,
+                        price: dec!(0),
+                        metrics: venue_metrics(exchange_account_id),
+                    })
+                    .collect::<Vec<_>>();
+                select_venue(&candidates, OrderSide::Buy, dec!(0), &self.venue_scorer)
+                    .map(|x| x.exchange_account_id)
+            }
+        }
+    }
+}
+
+/// Routes orders across accounts of configured groups and excludes accounts whose keys start failing
+pub struct AccountGroups {
+    groups: HashMap<String, AccountGroup>,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    timeout_manager: Arc<TimeoutManager>,
+}
+
+impl AccountGroups {
+    pub fn new(
+        settings: &[AccountGroupSettings],
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        timeout_manager: Arc<TimeoutManager>,
+    ) -> Result<Arc<Self>> {
+        let mut groups = HashMap::new();
+        for group_settings in settings {
+            if let Some(unknown) = group_settings
+                .exchange_account_ids
+                .iter()
+                .find(|x| !exchanges.contains_key(*x))
+            {
+                bail!(
+                    "Account {unknown} of group {} isn't configured in exchanges",
+                    group_settings.name
+                );
+            }
+
+            let group = AccountGroup::new(group_settings)?;
+            if groups.insert(group_settings.name.clone(), group).is_some() {
+                bail!("Account group {} is duplicated", group_settings.name);
+            }
+        }
+
+        Ok(Arc::new(AccountGroups {
+            groups,
+            exchanges,
+            timeout_manager,
+        }))
+    }
+
+    /// Select account of group for next order
+    pub fn select_account(&self, group_name: &str) -> Result<ExchangeAccountId> {
+        let group = self.group(group_name)?;
+        group
+            .select(
+                |x| self.timeout_manager.get_available_requests_count(x),
+                |x| {
+                    self.exchanges
+                        .get(&x)
+                        .map(|exchange| exchange.get_venue_metrics())
+                        .unwrap_or_default()
+                },
+            )
+            .with_context(|| format!("All accounts of group {group_name} are excluded"))
+    }
+
+    /// Create order on account selected from group. Order is built for selected account by `make_order`,
+    /// so balance reservation should be made for this account inside it
+    pub async fn create_order(
+        &self,
+        group_name: &str,
+        make_order: impl FnOnce(ExchangeAccountId) -> Result<OrderCreating>,
+        cancellation_token: CancellationToken,
+    ) -> Result<OrderRef> {
+        let exchange_account_id = self.select_account(group_name)?;
+        let exchange = self
+            .exchanges
+            .get(&exchange_account_id)
+            .with_context(|| format!("Exchange {exchange_account_id} isn't found"))?
+            .clone();
+
+        let order_to_create = make_order(exchange_account_id)?;
+        let result = exchange
+            .create_order(order_to_create, None, cancellation_token)
+            .await;
+
+        if let Err(error) = &result {
+            let is_authentication_error = error
+                .chain()
+                .filter_map(|x| x.downcast_ref::<ExchangeError>())
+                .any(|x| x.error_type == ExchangeErrorType::Authentication);
+            if is_authentication_error {
+                self.exclude_account(exchange_account_id, &format!("{error:?}"));
+            }
+        }
+
+        result
+    }
+
+    /// Exclude account from routing in all groups
+    pub fn exclude_account(&self, exchange_account_id: ExchangeAccountId, reason: &str) {
+        for (group_name, group) in &self.groups {
+            if group.members.contains(&exchange_account_id)
+                && group.excluded.lock().insert(exchange_account_id)
+            {
+                log::error!(
+                    "Account {exchange_account_id} is excluded from group {group_name}: {reason}"
+                );
+            }
+        }
+    }
+
+    /// Return account to routing after its keys are fixed
+    pub fn restore_account(&self, exchange_account_id: ExchangeAccountId) {
+        for (group_name, group) in &self.groups {
+            if group.excluded.lock().remove(&exchange_account_id) {
+                log::info!("Account {exchange_account_id} is restored in group {group_name}");
+            }
+        }
+    }
+
+    fn group(&self, group_name: &str) -> Result<&AccountGroup> {
+        self.groups
+            .get(group_name)
+            .with_context(|| format!("Account group {group_name} isn't configured"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(number: u8) -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", number)
+    }
+
+    fn group(routing: AccountRouting) -> AccountGroup {
+        AccountGroup::new(&AccountGroupSettings {
+            name: "test".to_owned(),
+            exchange_account_ids: vec![account(0), account(1), account(2)],
+            routing,
+            venue_scorer: Default::default(),
+        })
+        .expect("in test")
+    }
+
+    #[test]
+    pub fn round_robin() {
+        let group = group(AccountRouting::RoundRobin);
+
+        let selected = (0..4)
+            .map(|_| group.select(|_| 0, |_| Default::default()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            selected,
+            vec![
+                Some(account(0)),
+                Some(account(1)),
+                Some(account(2)),
+                Some(account(0))
+            ]
+        );
+    }
+
+    #[test]
+    pub fn rate_budget() {
+        let group = group(AccountRouting::RateBudget);
+
+        let budgets = HashMap::from([(account(0), 5), (account(1), 10), (account(2), 10)]);
+        assert_eq!(
+            group.select(|x| budgets[&x], |_| Default::default()),
+            Some(account(1))
+        );
+    }
+
+    #[test]
+    pub fn venue_score() {
+        let group = group(AccountRouting::VenueScore);
+
+        let metrics = |roundtrip_ms, reject_rate| VenueMetricsSnapshot {
+            roundtrip_ms: Some(roundtrip_ms),
+            reject_rate,
+            requests_count: 10,
+        };
+        let venue_metrics = HashMap::from([
+            (account(0), metrics(dec!(300), dec!(0))),
+            (account(1), metrics(dec!(50), dec!(0.5))),
+            (account(2), metrics(dec!(60), dec!(0))),
+        ]);
+        assert_eq!(group.select(|_| 0, |x| venue_metrics[&x]), Some(account(2)));
+    }
+
+    #[test]
+    pub fn excluded_account_is_skipped() {
+        let group = group(AccountRouting::RoundRobin);
+        let _ = group.excluded.lock().insert(account(1));
+
+        let selected = (0..3)
+            .filter_map(|_| group.select(|_| 0, |_| Default::default()))
+            .collect::<Vec<_>>();
+        assert!(!selected.contains(&account(1)));
+
+        group.excluded.lock().extend([account(0), account(2)]);
+        assert_eq!(group.select(|_| 0, |_| Default::default()), None);
+    }
+
+    #[test]
+    pub fn accounts_of_different_exchanges_are_not_allowed() {
+        let result = AccountGroup::new(&AccountGroupSettings {
+            name: "test".to_owned(),
+            exchange_account_ids: vec![account(0), ExchangeAccountId::new("Bitmex", 0)],
+            routing: AccountRouting::RoundRobin,
+            venue_scorer: Default::default(),
+        });
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,392 @@
+use anyhow::{bail, Context, Result};
+use chrono::Duration;
+use dashmap::DashMap;
+use futures::future::join_all;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use tokio::time::sleep;
+
+use crate::errors::ErrorCode;
+use crate::exchanges::account_groups::AccountGroups;
+use crate::exchanges::common::{ExchangeAccountId, ExchangeError, ExchangeErrorType};
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+use crate::settings::{ApiKeyHealthSettings, CoreSettings, ExchangeSettings};
+
+const PERMISSIONS_REQUEST_ATTEMPTS: u32 = 3;
+const PERMISSIONS_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Permissions of API key reported by exchange
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ApiKeyPermissions {
+    pub can_read: bool,
+    pub can_trade: bool,
+    pub can_withdraw: bool,
+    /// Time when key or its trading permission expires, e.g. for keys without IP whitelist
+    pub expiration_time: Option<DateTime>,
+}
+
+/// Maximal permissions API key may have according to configured mode
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ApiKeyPolicy {
+    pub trading_allowed: bool,
+    pub withdrawals_allowed: bool,
+}
+
+impl ApiKeyPolicy {
+    pub fn new(core_settings: &CoreSettings, exchange_settings: &ExchangeSettings) -> Self {
+        ApiKeyPolicy {
+            trading_allowed: !core_settings.market_data_only,
+            withdrawals_allowed: !core_settings.market_data_only
+                && exchange_settings.withdrawals.is_some(),
+        }
+    }
+
+    /// Returns list of permissions exceeding policy
+    pub fn violations(&self, permissions: ApiKeyPermissions) -> Vec<&'static str> {
+        let mut violations = vec![];
+        if permissions.can_trade && !self.trading_allowed {
+            violations.push("trade");
+        }
+        if permissions.can_withdraw && !self.withdrawals_allowed {
+            violations.push("withdraw");
+        }
+        violations
+    }
+}
+
+/// Check permissions of API keys of all exchanges and fail if any key has more permissions than allowed
+pub async fn check_api_key_permissions(
+    core_settings: &CoreSettings,
+    exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+) -> Result<()> {
+    let checks = core_settings
+        .exchanges
+        .iter()
+        // market data only mode may be run without credentials
+        .filter(|x| !x.api_key.is_empty())
+        .map(|exchange_settings| async move {
+            let exchange_account_id = exchange_settings.exchange_account_id;
+            let exchange = exchanges
+                .get(&exchange_account_id)
+                .with_context(|| format!("Exchange {exchange_account_id} isn't found"))?
+                .clone();
+
+            let permissions = get_api_key_permissions_with_retries(&exchange)
+                .await
+                .with_context(|| {
+                    format!("Unable to get API key permissions for {exchange_account_id}")
+                })?;
+
+            let permissions = match permissions {
+                Some(permissions) => permissions,
+                None => {
+                    log::warn!("API key permissions can't be checked for {exchange_account_id}");
+                    return Ok(());
+                }
+            };
+
+            log::info!("API key permissions for {exchange_account_id}: {permissions:?}");
+
+            let violations =
+                ApiKeyPolicy::new(core_settings, exchange_settings).violations(permissions);
+            if !violations.is_empty() {
+                bail!(
+                    "API key for {exchange_account_id} has permissions exceeding policy: {}",
+                    violations.join(", ")
+                );
+            }
+
+            Ok(())
+        });
+
+    join_all(checks).await.into_iter().collect()
+}
+
+/// Permissions request is retried, so transient errors of exchange don't block startup
+async fn get_api_key_permissions_with_retries(
+    exchange: &Exchange,
+) -> Result<Option<ApiKeyPermissions>> {
+    let mut attempt = 1;
+    loop {
+        match exchange.exchange_client.get_api_key_permissions().await {
+            Ok(permissions) => return Ok(permissions),
+            Err(err) if attempt < PERMISSIONS_REQUEST_ATTEMPTS => log::warn!(
+                "Attempt {attempt} to get API key permissions for {} failed: {err:?}",
+                exchange.exchange_account_id
+            ),
+            Err(err) => return Err(err),
+        }
+
+        attempt += 1;
+        sleep(PERMISSIONS_RETRY_DELAY).await;
+    }
+}
+
+/// Problem of API key detected by periodic health check
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ApiKeyAlert {
+    /// Signed request is rejected: key is revoked, expired or IP isn't whitelisted
+    Rejected(String),
+    PermissionsChanged {
+        previous: ApiKeyPermissions,
+        current: ApiKeyPermissions,
+    },
+    PolicyViolated(Vec<&'static str>),
+    Expiring(DateTime),
+}
+
+impl Display for ApiKeyAlert {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiKeyAlert::Rejected(message) => write!(
+                f,
+                "API key is rejected (revoked, expired or IP isn't whitelisted): {message}"
+            ),
+            ApiKeyAlert::PermissionsChanged { previous, current } => {
+                write!(
+                    f,
+                    "API key permissions changed from {previous:?} to {current:?}"
+                )
+            }
+            ApiKeyAlert::PolicyViolated(violations) => write!(
+                f,
+                "API key has permissions exceeding policy: {}",
+                violations.join(", ")
+            ),
+            ApiKeyAlert::Expiring(expiration_time) => {
+                write!(f, "API key expires at {expiration_time}")
+            }
+        }
+    }
+}
+
+/// Compares permissions received by health check with permissions of previous check
+pub fn evaluate_api_key_health(
+    previous: Option<ApiKeyPermissions>,
+    current: ApiKeyPermissions,
+    policy: ApiKeyPolicy,
+    expiry_warning: Duration,
+    now: DateTime,
+) -> Vec<ApiKeyAlert> {
+    let mut alerts = vec![];
+    if let Some(previous) = previous.filter(|x| *x != current) {
+        alerts.push(ApiKeyAlert::PermissionsChanged { previous, current });
+    }
+
+    let violations = policy.violations(current);
+    if !violations.is_empty() {
+        alerts.push(ApiKeyAlert::PolicyViolated(violations));
+    }
+
+    if let Some(expiration_time) = current.expiration_time {
+        if expiration_time - now <= expiry_warning {
+            alerts.push(ApiKeyAlert::Expiring(expiration_time));
+        }
+    }
+
+    alerts
+}
+
+/// Periodically validates API keys of all exchanges by signed request of permissions, so revoked
+/// keys, whitelist failures and permission changes are alerted before private requests start failing.
+/// Accounts with rejected keys are excluded from account groups until their keys pass the check again
+pub(crate) fn start_api_key_health_checks(
+    settings: &ApiKeyHealthSettings,
+    core_settings: &CoreSettings,
+    exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+    account_groups: Arc<AccountGroups>,
+    cancellation_token: CancellationToken,
+) {
+    for exchange_settings in core_settings
+        .exchanges
+        .iter()
+        .filter(|x| !x.api_key.is_empty())
+    {
+        let exchange = match exchanges.get(&exchange_settings.exchange_account_id) {
+            Some(exchange) => exchange.clone(),
+            None => continue,
+        };
+
+        let _ = spawn_future(
+            "API key health checks",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            check_api_key_health(
+                exchange,
+                ApiKeyPolicy::new(core_settings, exchange_settings),
+                settings.clone(),
+                account_groups.clone(),
+                cancellation_token.clone(),
+            ),
+        );
+    }
+}
+
+async fn check_api_key_health(
+    exchange: Arc<Exchange>,
+    policy: ApiKeyPolicy,
+    settings: ApiKeyHealthSettings,
+    account_groups: Arc<AccountGroups>,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    let exchange_account_id = exchange.exchange_account_id;
+    let expiry_warning = Duration::milliseconds(settings.expiry_warning_ms as i64);
+    let mut check_interval = tokio::time::interval(std::time::Duration::from_millis(
+        settings.check_interval_ms.max(1),
+    ));
+    let mut last_permissions = None;
+    let mut last_alerts: Vec<ApiKeyAlert> = vec![];
+
+    loop {
+        tokio::select! {
+            _ = check_interval.tick() => {}
+            _ = cancellation_token.when_cancelled() => return Ok(()),
+        }
+
+        let alerts = match exchange.exchange_client.get_api_key_permissions().await {
+            Ok(Some(permissions)) => {
+                let alerts = evaluate_api_key_health(
+                    last_permissions,
+                    permissions,
+                    policy,
+                    expiry_warning,
+                    time_manager::now(),
+                );
+                last_permissions = Some(permissions);
+                account_groups.restore_account(exchange_account_id);
+                alerts
+            }
+            Ok(None) => {
+                log::warn!("API key health of {exchange_account_id} can't be checked");
+                return Ok(());
+            }
+            Err(error) => match error.downcast_ref::<ExchangeError>() {
+                Some(exchange_error)
+                    if exchange_error.error_type == ExchangeErrorType::Authentication =>
+                {
+                    account_groups.exclude_account(exchange_account_id, &exchange_error.message);
+                    vec![ApiKeyAlert::Rejected(exchange_error.message.clone())]
+                }
+                _ => {
+                    log::warn!("API key health check of {exchange_account_id} failed: {error:?}");
+                    continue;
+                }
+            },
+        };
+
+        // the same alerts are raised once until key state changes
+        for alert in alerts.iter().filter(|x| !last_alerts.contains(*x)) {
+            log::error!(
+                "API key health check of {exchange_account_id} [{}]: {alert}",
+                ErrorCode::ExchangeAuthentication
+            );
+        }
+        if alerts.is_empty() && !last_alerts.is_empty() {
+            log::info!("API key of {exchange_account_id} is healthy again");
+        }
+        last_alerts = alerts;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::WithdrawalSettings;
+    use chrono::{TimeZone, Utc};
+    use rstest::rstest;
+
+    fn permissions(can_trade: bool, can_withdraw: bool) -> ApiKeyPermissions {
+        ApiKeyPermissions {
+            can_read: true,
+            can_trade,
+            can_withdraw,
+            expiration_time: None,
+        }
+    }
+
+    #[rstest]
+    #[case(false, false, false, vec![])]
+    #[case(false, true, true, vec![])]
+    #[case(true, true, false, vec!["trade"])]
+    #[case(true, false, true, vec!["withdraw"])]
+    #[case(true, true, true, vec!["trade", "withdraw"])]
+    pub fn violations(
+        #[case] market_data_only: bool,
+        #[case] can_trade: bool,
+        #[case] can_withdraw: bool,
+        #[case] expected: Vec<&'static str>,
+    ) {
+        let core_settings = CoreSettings {
+            market_data_only,
+            ..Default::default()
+        };
+        let exchange_settings = ExchangeSettings {
+            withdrawals: Some(WithdrawalSettings {
+                whitelist: vec![],
+                require_approval: false,
+            }),
+            ..Default::default()
+        };
+
+        let policy = ApiKeyPolicy::new(&core_settings, &exchange_settings);
+
+        assert_eq!(
+            policy.violations(permissions(can_trade, can_withdraw)),
+            expected
+        );
+    }
+
+    #[test]
+    pub fn withdrawals_are_not_allowed_without_settings() {
+        let policy = ApiKeyPolicy::new(&CoreSettings::default(), &ExchangeSettings::default());
+
+        assert_eq!(policy.violations(permissions(true, true)), vec!["withdraw"]);
+    }
+
+    #[test]
+    pub fn health_alerts() {
+        let policy = ApiKeyPolicy {
+            trading_allowed: true,
+            withdrawals_allowed: false,
+        };
+        let now = Utc.ymd(2022, 10, 20).and_hms(12, 0, 0);
+        let previous = permissions(true, false);
+        let current = ApiKeyPermissions {
+            expiration_time: Some(now + Duration::days(1)),
+            ..permissions(false, false)
+        };
+
+        let alerts =
+            evaluate_api_key_health(Some(previous), current, policy, Duration::days(3), now);
+
+        assert_eq!(
+            alerts,
+            vec![
+                ApiKeyAlert::PermissionsChanged { previous, current },
+                ApiKeyAlert::Expiring(now + Duration::days(1)),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn healthy_key_has_no_alerts() {
+        let policy = ApiKeyPolicy {
+            trading_allowed: true,
+            withdrawals_allowed: false,
+        };
+        let now = Utc.ymd(2022, 10, 20).and_hms(12, 0, 0);
+        let current = ApiKeyPermissions {
+            expiration_time: Some(now + Duration::days(30)),
+            ..permissions(true, false)
+        };
+
+        let alerts =
+            evaluate_api_key_health(Some(current), current, policy, Duration::days(3), now);
+
+        assert!(alerts.is_empty(), "{alerts:?}");
+    }
+}
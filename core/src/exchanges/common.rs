@@ -4,8 +4,10 @@ use anyhow::{anyhow, Result};
 use hyper::StatusCode;
 use itertools::Itertools;
 use mmb_utils::infrastructure::WithExpect;
+use mmb_utils::time::next_id;
 use mmb_utils::{impl_table_type, impl_table_type_raw};
 use once_cell::sync::Lazy;
+use parking_lot::RwLock;
 use regex::Regex;
 use rust_decimal::*;
 use rust_decimal_macros::dec;
@@ -13,9 +15,10 @@ use serde::de::{self, Deserializer, Visitor};
 use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
 use smallstr::SmallString;
+use std::collections::HashMap;
 use std::fmt::{self, Debug, Display, Formatter};
 use std::str::FromStr;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{collections::BTreeMap, time::Duration};
@@ -160,6 +163,56 @@ impl From<&str> for CurrencyCode {
     }
 }
 
+/// Currencies named differently on some venues and their canonical codes
+const CURRENCY_CODE_ALIASES: [(&str, &str); 3] = [("xbt", "btc"), ("bcc", "bch"), ("xdg", "doge")];
+
+/// Suffixes of perpetual instruments which aren't part of currency name, e.g. BTC-PERP
+const PERPETUAL_SUFFIXES: [&str; 2] = ["-perp", "_perp"];
+
+/// Normalizes venue-specific currency names to canonical `CurrencyCode`s, so consolidated books and
+/// portfolio aggregation don't fragment the same asset by naming. Connectors register overrides for
+/// names which differ from the common aliases
+#[derive(Default)]
+pub struct CurrencyCodeRegistry {
+    overrides: RwLock<HashMap<ExchangeId, HashMap<String, CurrencyCode>>>,
+}
+
+pub static CURRENCY_CODE_REGISTRY: Lazy<CurrencyCodeRegistry> = Lazy::new(Default::default);
+
+impl CurrencyCodeRegistry {
+    /// Overrides are pairs of venue-specific name and canonical currency code
+    pub fn register_overrides(&self, exchange_id: ExchangeId, overrides: &[(&str, &str)]) {
+        let mut all_overrides = self.overrides.write();
+        let exchange_overrides = all_overrides.entry(exchange_id).or_default();
+        for (currency_id, currency_code) in overrides {
+            exchange_overrides.insert(currency_id.to_lowercase(), CurrencyCode::new(currency_code));
+        }
+    }
+
+    pub fn normalize(&self, exchange_id: ExchangeId, currency_id: &str) -> CurrencyCode {
+        let name = currency_id.to_lowercase();
+        let name = PERPETUAL_SUFFIXES
+            .iter()
+            .find_map(|suffix| name.strip_suffix(*suffix))
+            .unwrap_or(&name);
+
+        if let Some(currency_code) = self
+            .overrides
+            .read()
+            .get(&exchange_id)
+            .and_then(|x| x.get(name))
+        {
+            return *currency_code;
+        }
+
+        let name = CURRENCY_CODE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map_or(name, |(_, currency_code)| *currency_code);
+        CurrencyCode::new(name)
+    }
+}
+
 pub struct CurrencyPairCodes {
     pub base: CurrencyCode,
     pub quote: CurrencyCode,
@@ -246,6 +299,8 @@ pub enum ExchangeErrorType {
     ParsingError,
     PendingError(Duration),
     ServiceUnavailable,
+    /// Operation isn't provided by exchange
+    Unsupported,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize, Error)]
@@ -330,6 +385,7 @@ impl ToStdExpected for chrono::Duration {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ClosedPosition {
     _exchange_order_id: ExchangeOrderId,
     _amount: Amount,
@@ -342,9 +398,17 @@ impl ClosedPosition {
             _amount,
         }
     }
+
+    pub fn exchange_order_id(&self) -> &ExchangeOrderId {
+        &self._exchange_order_id
+    }
+
+    pub fn amount(&self) -> Amount {
+        self._amount
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ActivePositionId(String16);
 
 static ACTIVE_POSITION_ID_COUNTER: Lazy<AtomicU64> = Lazy::new(|| {
@@ -358,7 +422,7 @@ static ACTIVE_POSITION_ID_COUNTER: Lazy<AtomicU64> = Lazy::new(|| {
 
 impl ActivePositionId {
     pub fn unique_id() -> Self {
-        let new_id = ACTIVE_POSITION_ID_COUNTER.fetch_add(1, Ordering::AcqRel);
+        let new_id = next_id(&ACTIVE_POSITION_ID_COUNTER);
         ActivePositionId(new_id.to_string().into())
     }
 
@@ -389,9 +453,10 @@ impl Display for ActivePositionId {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ActivePosition {
     pub id: ActivePositionId,
+    #[serde(with = "status_code_serde")]
     pub status: StatusCode,
     pub time_stamp: u128,
     pub pl: Amount,
@@ -410,6 +475,19 @@ impl ActivePosition {
     }
 }
 
+mod status_code_serde {
+    use hyper::StatusCode;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(status: &StatusCode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(status.as_u16())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<StatusCode, D::Error> {
+        StatusCode::from_u16(u16::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
 pub fn send_event(
     events_channel: &broadcast::Sender<ExchangeEvent>,
     lifetime_manager: Arc<AppLifetimeManager>,
@@ -546,4 +624,64 @@ mod tests {
             assert_eq!(result, "Binance_1".to_string())
         }
     }
+
+    mod currency_code_registry {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        pub fn normalize_aliases_and_perpetual_suffixes() {
+            let registry = CurrencyCodeRegistry::default();
+            let exchange_id = ExchangeId::new("Bitmex");
+
+            assert_eq!(
+                registry.normalize(exchange_id, "XBT"),
+                CurrencyCode::new("btc")
+            );
+            assert_eq!(
+                registry.normalize(exchange_id, "BTC-PERP"),
+                CurrencyCode::new("btc")
+            );
+            assert_eq!(
+                registry.normalize(exchange_id, "ETH"),
+                CurrencyCode::new("eth")
+            );
+        }
+
+        #[test]
+        pub fn overrides_are_applied_only_for_own_exchange() {
+            let registry = CurrencyCodeRegistry::default();
+            let exchange_id = ExchangeId::new("Kraken");
+            registry.register_overrides(exchange_id, &[("XXBT", "btc"), ("XDG", "xdg")]);
+
+            assert_eq!(
+                registry.normalize(exchange_id, "XXBT"),
+                CurrencyCode::new("btc")
+            );
+            assert_eq!(
+                registry.normalize(exchange_id, "XDG"),
+                CurrencyCode::new("xdg")
+            );
+            assert_eq!(
+                registry.normalize(ExchangeId::new("Binance"), "XXBT"),
+                CurrencyCode::new("xxbt")
+            );
+        }
+    }
+
+    mod deterministic_ids {
+        use super::*;
+        use crate::orders::order::ClientOrderId;
+        use mmb_utils::time::use_deterministic_ids;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        pub fn ids_are_sequential() {
+            let _guard = use_deterministic_ids(100);
+
+            assert_eq!(ClientOrderId::unique_id().as_str(), "100");
+            assert_eq!(ActivePositionId::unique_id().as_str(), "101");
+            assert_eq!(ClientOrderId::unique_id().as_str(), "102");
+        }
+    }
 }
@@ -0,0 +1,243 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use hyper::client::HttpConnector;
+use hyper::service::Service;
+use hyper::{Body, Client, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use serde::Serialize;
+use tokio::net::TcpStream;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::connectivity::egress;
+use crate::settings::{EgressSettings, RestClientSettings};
+
+pub type PooledClient = Client<CountingConnector<HttpsConnector<EgressConnector>>>;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Usage of keep-alive connections of REST client
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ConnectionPoolStats {
+    pub requests_count: u64,
+    /// Connections opened by pool. Other requests reused idle keep-alive connections
+    pub connections_count: u64,
+    pub active_requests: u64,
+    pub max_active_requests: u64,
+}
+
+#[derive(Debug, Default)]
+struct ConnectionPoolCounters {
+    requests_count: AtomicU64,
+    connections_count: AtomicU64,
+    active_requests: AtomicU64,
+    max_active_requests: AtomicU64,
+}
+
+/// Connector which counts connections opened by pool of hyper client
+#[derive(Clone)]
+pub struct CountingConnector<C> {
+    inner: C,
+    counters: Arc<ConnectionPoolCounters>,
+}
+
+impl<C: Service<Uri>> Service<Uri> for CountingConnector<C> {
+    type Response = C::Response;
+    type Error = C::Error;
+    type Future = C::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        self.counters
+            .connections_count
+            .fetch_add(1, Ordering::Relaxed);
+        self.inner.call(uri)
+    }
+}
+
+/// Connector which opens connections directly or according to egress settings if they are set
+#[derive(Clone)]
+pub struct EgressConnector {
+    http: HttpConnector,
+    egress: Option<Arc<EgressSettings>>,
+}
+
+impl Service<Uri> for EgressConnector {
+    type Response = TcpStream;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<TcpStream, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.http.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let egress = match &self.egress {
+            Some(egress) => egress.clone(),
+            None => {
+                let connecting = self.http.call(uri);
+                return Box::pin(async move { connecting.await.map_err(Into::into) });
+            }
+        };
+
+        Box::pin(async move {
+            let host = uri.host().ok_or("Uri without host")?;
+            let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+                Some("http") => 80,
+                _ => 443,
+            });
+            egress::connect_tcp(&egress, host, port)
+                .await
+                .map_err(Into::into)
+        })
+    }
+}
+
+/// Shared client of exchange account with keep-alive connections. TLS sessions are resumed
+/// for new connections because all of them are created by the same TLS config
+pub struct ConnectionPool {
+    client: PooledClient,
+    counters: Arc<ConnectionPoolCounters>,
+    requests_limiter: Option<Semaphore>,
+}
+
+impl ConnectionPool {
+    pub fn new(settings: &RestClientSettings, egress: Option<EgressSettings>) -> Self {
+        let counters = Arc::new(ConnectionPoolCounters::default());
+
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_nodelay(true);
+        http.set_keepalive(settings.tcp_keepalive_sec.map(Duration::from_secs));
+        let egress_connector = EgressConnector {
+            http,
+            egress: egress.map(Arc::new),
+        };
+
+        let https_builder = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_only();
+        let https = match settings.http2 {
+            None => https_builder
+                .enable_http1()
+                .enable_http2()
+                .wrap_connector(egress_connector),
+            Some(true) => https_builder
+                .enable_http2()
+                .wrap_connector(egress_connector),
+            Some(false) => https_builder
+                .enable_http1()
+                .wrap_connector(egress_connector),
+        };
+        let connector = CountingConnector {
+            inner: https,
+            counters: counters.clone(),
+        };
+
+        let mut builder = Client::builder();
+        if let Some(idle_timeout) = settings.pool_idle_timeout_sec {
+            builder.pool_idle_timeout(Duration::from_secs(idle_timeout));
+        }
+        if let Some(max_idle) = settings.pool_max_idle_per_host {
+            builder.pool_max_idle_per_host(max_idle);
+        }
+        if settings.http2 == Some(true) {
+            builder.http2_only(true);
+            builder.http2_adaptive_window(true);
+        }
+        if let Some(interval) = settings.http2_keep_alive_interval_sec {
+            builder.http2_keep_alive_interval(Duration::from_secs(interval));
+            builder.http2_keep_alive_while_idle(true);
+        }
+
+        ConnectionPool {
+            client: builder.build::<_, Body>(connector),
+            counters,
+            requests_limiter: settings.max_concurrent_requests.map(Semaphore::new),
+        }
+    }
+
+    pub fn client(&self) -> &PooledClient {
+        &self.client
+    }
+
+    /// Waits for free slot if count of concurrent requests is limited. Request is active
+    /// until returned guard is dropped
+    pub(crate) async fn start_request(&self) -> ActiveRequest<'_> {
+        let permit = match &self.requests_limiter {
+            Some(limiter) => limiter.acquire().await.ok(),
+            None => None,
+        };
+
+        let counters = &self.counters;
+        counters.requests_count.fetch_add(1, Ordering::Relaxed);
+        let active_requests = counters.active_requests.fetch_add(1, Ordering::Relaxed) + 1;
+        counters
+            .max_active_requests
+            .fetch_max(active_requests, Ordering::Relaxed);
+
+        ActiveRequest {
+            counters,
+            _permit: permit,
+        }
+    }
+
+    pub fn stats(&self) -> ConnectionPoolStats {
+        let counters = &self.counters;
+        ConnectionPoolStats {
+            requests_count: counters.requests_count.load(Ordering::Relaxed),
+            connections_count: counters.connections_count.load(Ordering::Relaxed),
+            active_requests: counters.active_requests.load(Ordering::Relaxed),
+            max_active_requests: counters.max_active_requests.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub(crate) struct ActiveRequest<'a> {
+    counters: &'a ConnectionPoolCounters,
+    _permit: Option<SemaphorePermit<'a>>,
+}
+
+impl Drop for ActiveRequest<'_> {
+    fn drop(&mut self) {
+        self.counters
+            .active_requests
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    pub async fn active_requests_are_counted_until_guard_is_dropped() {
+        let pool = ConnectionPool::new(
+            &RestClientSettings {
+                max_concurrent_requests: Some(2),
+                ..Default::default()
+            },
+            None,
+        );
+
+        let first = pool.start_request().await;
+        let second = pool.start_request().await;
+        assert_eq!(pool.stats().active_requests, 2);
+        drop(first);
+        drop(second);
+        let _third = pool.start_request().await;
+
+        let stats = pool.stats();
+        assert_eq!(stats.requests_count, 3);
+        assert_eq!(stats.active_requests, 1);
+        assert_eq!(stats.max_active_requests, 2);
+        assert_eq!(stats.connections_count, 0);
+    }
+}
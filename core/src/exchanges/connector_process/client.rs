@@ -0,0 +1,832 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::{Mutex, RwLock};
+use tokio::net::unix::OwnedReadHalf;
+use tokio::net::UnixListener;
+use tokio::process::{Child, Command};
+use tokio::sync::{broadcast, mpsc, oneshot, Notify};
+use tokio::time::{sleep, timeout};
+use url::Url;
+
+use crate::connectivity::WebSocketRole;
+use crate::exchanges::common::{
+    send_event, ActivePosition, ClosedPosition, CurrencyCode, CurrencyId, CurrencyPair,
+    ExchangeAccountId, ExchangeError, ExchangeErrorType, Price, SpecificCurrencyPair,
+};
+use crate::exchanges::connector_process::host::CONNECTOR_SOCKET_ENV;
+use crate::exchanges::connector_process::protocol::{
+    read_frame, start_frames_writer, write_frame, ConnectorInfo, ConnectorNotification,
+    ConnectorRequest, ConnectorResponse, ConnectorResult, ConnectorSymbols, Frame, FrameMessage,
+};
+use crate::exchanges::events::{ExchangeBalancesAndPositions, ExchangeEvent};
+use crate::exchanges::general::exchange::RequestResult;
+use crate::exchanges::general::order::cancel::CancelOrderResult;
+use crate::exchanges::general::order::create::CreateOrderResult;
+use crate::exchanges::general::order::get_order_trades::OrderTrade;
+use crate::exchanges::general::symbol::Symbol;
+use crate::exchanges::traits::{
+    ExchangeClient, ExchangeClientBuilderResult, HandleOrderFilledCb, HandleTradeCb,
+    OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb, Support,
+};
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::order_book::event::{EventType, OrderBookEvent};
+use crate::order_book::order_book_data::OrderBookData;
+use crate::orders::fill::EventSourceType;
+use crate::orders::order::{OrderCancelling, OrderInfo, OrderSnapshot};
+use crate::orders::pool::OrderRef;
+use crate::settings::{ConnectorProcessSettings, ExchangeSettings};
+
+/// Time for started connector process to connect to engine and initialize connector
+const INITIALIZATION_TIMEOUT: Duration = Duration::from_secs(30);
+/// Delay before the first restart of exited connector process
+const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+/// Connector process which doesn't respond to request in this time is treated as hung and
+/// is restarted
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn next_restart_delay(delay: Duration) -> Duration {
+    (delay * 2).min(MAX_RESTART_DELAY)
+}
+
+fn not_connected_error(exchange_account_id: ExchangeAccountId) -> ExchangeError {
+    ExchangeError::new(
+        ExchangeErrorType::ServiceUnavailable,
+        format!("Connector process of {exchange_account_id} isn't connected"),
+        None,
+    )
+}
+
+/// Callbacks of engine invoked by notifications of connector process
+struct Callbacks {
+    order_created: OrderCreatedCb,
+    order_cancelled: OrderCancelledCb,
+    handle_order_filled: HandleOrderFilledCb,
+    handle_trade: HandleTradeCb,
+}
+
+/// Running connector process which is served by `Connection::serve`
+struct ConnectedProcess {
+    process: Child,
+    reader: OwnedReadHalf,
+    /// Fired when request to the process times out
+    hung: oneshot::Receiver<()>,
+}
+
+/// Connection to connector process shared by client and supervisor of the process
+struct Connection {
+    exchange_account_id: ExchangeAccountId,
+    settings: ExchangeSettings,
+    process_settings: ConnectorProcessSettings,
+    socket_path: PathBuf,
+    events_channel: broadcast::Sender<ExchangeEvent>,
+    lifetime_manager: Arc<AppLifetimeManager>,
+    callbacks: RwLock<Callbacks>,
+    /// `None` while connector process is restarted
+    frames: Mutex<Option<mpsc::UnboundedSender<Frame>>>,
+    /// Notified when connector process is connected after restart
+    connected: Notify,
+    /// Sender of channel which engine watches while websockets of connector process are
+    /// connected. It's dropped when they are disconnected or connector process exits
+    websockets: Mutex<Option<mpsc::UnboundedSender<String>>>,
+    pending_requests: Mutex<HashMap<u64, oneshot::Sender<ConnectorResult>>>,
+    last_request_id: AtomicU64,
+    request_timeout: Duration,
+    /// Sender of `ConnectedProcess::hung` of current connector process. It's taken by the
+    /// first timed out request, so hung process is restarted once
+    hung: Mutex<Option<oneshot::Sender<()>>>,
+    /// Restored in restarted connector process
+    traded_specific_currencies: Mutex<Option<Vec<SpecificCurrencyPair>>>,
+}
+
+impl Connection {
+    /// Request which isn't responded within `request_timeout` fails and connector process is
+    /// restarted, so other pending requests fail too
+    async fn request<T: TryFrom<ConnectorResponse, Error = ExchangeError>>(
+        &self,
+        request: ConnectorRequest,
+    ) -> Result<T, ExchangeError> {
+        let request_id = self.last_request_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let frame = Frame::request(request_id, &request)?;
+
+        let (response_sender, response_receiver) = oneshot::channel();
+        let _ = self
+            .pending_requests
+            .lock()
+            .insert(request_id, response_sender);
+
+        if !self.send_frame(frame) {
+            let _ = self.pending_requests.lock().remove(&request_id);
+            return Err(not_connected_error(self.exchange_account_id));
+        }
+
+        // sender of response is dropped if connector process exits before response
+        let response = match timeout(self.request_timeout, response_receiver).await {
+            Ok(response) => {
+                response.map_err(|_| not_connected_error(self.exchange_account_id))??
+            }
+            Err(_) => {
+                let _ = self.pending_requests.lock().remove(&request_id);
+                self.restart_hung_process(request_id);
+                return Err(ExchangeError::new(
+                    ExchangeErrorType::ServiceUnavailable,
+                    format!(
+                        "Connector process of {} didn't respond in {:?}",
+                        self.exchange_account_id, self.request_timeout
+                    ),
+                    None,
+                ));
+            }
+        };
+
+        T::try_from(response)
+    }
+
+    fn restart_hung_process(&self, request_id: u64) {
+        if let Some(hung) = self.hung.lock().take() {
+            log::error!(
+                "Connector process of {} didn't respond to request {request_id} in {:?} \
+                 and will be restarted",
+                self.exchange_account_id,
+                self.request_timeout
+            );
+            let _ = hung.send(());
+        }
+    }
+
+    fn notify(&self, notification: ConnectorNotification) -> Result<()> {
+        let frame = Frame::notification(&notification)?;
+        if !self.send_frame(frame) {
+            bail!(not_connected_error(self.exchange_account_id));
+        }
+
+        Ok(())
+    }
+
+    async fn wait_connected(&self) {
+        loop {
+            let connected = self.connected.notified();
+            if self.frames.lock().is_some() {
+                return;
+            }
+            connected.await;
+        }
+    }
+
+    /// Engine is notified about disconnection of websockets by closing of returned channel
+    async fn connect_websockets(
+        &self,
+        is_market_data_only: bool,
+    ) -> Result<mpsc::UnboundedReceiver<String>> {
+        // reconnection of websockets waits for restart of connector process
+        self.wait_connected().await;
+
+        // channel is set before request, so disconnection during request isn't missed
+        let (sender, receiver) = mpsc::unbounded_channel();
+        *self.websockets.lock() = Some(sender);
+
+        let request = ConnectorRequest::ConnectWebsockets {
+            is_market_data_only,
+        };
+        if let Err(error) = self.request::<()>(request).await {
+            let _ = self.websockets.lock().take();
+            return Err(error.into());
+        }
+
+        Ok(receiver)
+    }
+
+    fn send_frame(&self, frame: Frame) -> bool {
+        match &*self.frames.lock() {
+            Some(frames) => frames.send(frame).is_ok(),
+            None => false,
+        }
+    }
+
+    fn start_process(&self) -> Result<Child> {
+        let executable = match &self.process_settings.executable {
+            Some(executable) => PathBuf::from(executable),
+            None => std::env::current_exe().context("Unable to get executable of engine")?,
+        };
+
+        Command::new(&executable)
+            .args(&self.process_settings.args)
+            .env(CONNECTOR_SOCKET_ENV, &self.socket_path)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "Unable to start connector process {} of {}",
+                    executable.display(),
+                    self.exchange_account_id
+                )
+            })
+    }
+
+    /// Starts connector process and initializes connector in it
+    async fn connect(&self, listener: &UnixListener) -> Result<(ConnectedProcess, ConnectorInfo)> {
+        let mut process = self.start_process()?;
+
+        let (stream, _) = tokio::select! {
+            accepted = timeout(INITIALIZATION_TIMEOUT, listener.accept()) => accepted
+                .context("Connector process didn't connect in time")?
+                .context("Unable to accept connection of connector process")?,
+            status = process.wait() => {
+                bail!("Connector process exited before connection with {:?}", status?)
+            }
+        };
+        let (mut reader, mut writer) = stream.into_split();
+
+        let init = ConnectorRequest::Init {
+            settings: Box::new(self.settings.clone()),
+            traded_specific_currencies: self.traded_specific_currencies.lock().clone(),
+        };
+        write_frame(&mut writer, &Frame::request(0, &init)?).await?;
+
+        let response = timeout(INITIALIZATION_TIMEOUT, read_frame(&mut reader))
+            .await
+            .context("Connector process wasn't initialized in time")??
+            .context("Connector process closed connection during initialization")?;
+        let info: ConnectorInfo = match response.into_message()? {
+            FrameMessage::Response(response) => response
+                .context("Initialization of connector process failed")?
+                .try_into()?,
+            _ => bail!("Unexpected frame during initialization of connector process"),
+        };
+
+        let (hung_sender, hung) = oneshot::channel();
+        *self.hung.lock() = Some(hung_sender);
+        *self.frames.lock() = Some(start_frames_writer(writer));
+        self.connected.notify_waiters();
+
+        let connected = ConnectedProcess {
+            process,
+            reader,
+            hung,
+        };
+        Ok((connected, info))
+    }
+
+    /// Serves frames of connector process until it exits, closes connection or hangs
+    async fn serve(&self, connected: &mut ConnectedProcess) -> Result<()> {
+        loop {
+            let frame = tokio::select! {
+                frame = read_frame(&mut connected.reader) => frame?,
+                status = connected.process.wait() => {
+                    bail!("Connector process exited with {:?}", status?)
+                }
+                Ok(()) = &mut connected.hung => bail!("Connector process doesn't respond to requests"),
+            };
+
+            match frame {
+                None => bail!("Connector process closed connection"),
+                Some(frame) => self.handle_frame(frame),
+            }
+        }
+    }
+
+    fn handle_frame(&self, frame: Frame) {
+        let request_id = frame.request_id;
+        let outcome = frame.into_message().and_then(|message| match message {
+            FrameMessage::Response(response) => {
+                match self.pending_requests.lock().remove(&request_id) {
+                    Some(response_sender) => {
+                        let _ = response_sender.send(response);
+                    }
+                    // request can be timed out already
+                    None => log::warn!(
+                        "Response to unknown request {request_id} from connector process of {}",
+                        self.exchange_account_id
+                    ),
+                }
+                Ok(())
+            }
+            FrameMessage::Notification(notification) => self.handle_notification(notification),
+            FrameMessage::Request(_) => Err(anyhow!("Engine doesn't serve requests")),
+        });
+
+        if let Err(error) = outcome {
+            log::warn!(
+                "Unable to handle frame from connector process of {}: {error:?}",
+                self.exchange_account_id
+            );
+        }
+    }
+
+    fn handle_notification(&self, notification: ConnectorNotification) -> Result<()> {
+        let callbacks = self.callbacks.read();
+        match notification {
+            ConnectorNotification::WebsocketsDisconnected => {
+                let _ = self.websockets.lock().take();
+            }
+            ConnectorNotification::OrderCreated {
+                client_order_id,
+                exchange_order_id,
+                source_type,
+            } => (callbacks.order_created)(client_order_id, exchange_order_id, source_type),
+            ConnectorNotification::OrderCancelled {
+                client_order_id,
+                exchange_order_id,
+                source_type,
+            } => (callbacks.order_cancelled)(client_order_id, exchange_order_id, source_type),
+            ConnectorNotification::OrderFilled(fill_event) => {
+                (callbacks.handle_order_filled)(*fill_event)
+            }
+            ConnectorNotification::Trade {
+                currency_pair,
+                trade_id,
+                price,
+                amount,
+                side,
+                transaction_time,
+            } => (callbacks.handle_trade)(
+                currency_pair,
+                trade_id,
+                price,
+                amount,
+                side,
+                transaction_time,
+            ),
+            ConnectorNotification::OrderBook {
+                creation_time,
+                currency_pair,
+                is_snapshot,
+                asks,
+                bids,
+            } => {
+                let event_type = match is_snapshot {
+                    true => EventType::Snapshot,
+                    false => EventType::Update,
+                };
+                let event = OrderBookEvent::new(
+                    creation_time,
+                    self.exchange_account_id,
+                    currency_pair,
+                    String::new(),
+                    event_type,
+                    Arc::new(OrderBookData::new(
+                        asks.into_iter().collect(),
+                        bids.into_iter().collect(),
+                    )),
+                );
+                send_event(
+                    &self.events_channel,
+                    self.lifetime_manager.clone(),
+                    self.exchange_account_id,
+                    ExchangeEvent::OrderBookEvent(event),
+                )?
+            }
+            ConnectorNotification::TradedSpecificCurrencies(_) => {
+                bail!("Unexpected notification {notification:?}")
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Requests in progress fail, new requests fail until connector process is restarted.
+    /// Websockets of exited connector process are reconnected by engine after restart
+    fn disconnect(&self) {
+        let _ = self.frames.lock().take();
+        let _ = self.websockets.lock().take();
+        let _ = self.hung.lock().take();
+        self.pending_requests.lock().clear();
+    }
+
+    /// Restarts connector process with growing delay whenever it exits
+    async fn supervise(
+        self: Arc<Self>,
+        listener: UnixListener,
+        mut connected: ConnectedProcess,
+    ) -> Result<()> {
+        let stop_token = self.lifetime_manager.stop_token();
+        loop {
+            if let Err(error) = self.serve(&mut connected).await {
+                log::error!(
+                    "Connector process of {} failed and will be restarted: {error:?}",
+                    self.exchange_account_id
+                );
+            }
+            self.disconnect();
+            let _ = connected.process.kill().await;
+
+            let mut delay = INITIAL_RESTART_DELAY;
+            let mut attempt = 1;
+            loop {
+                tokio::select! {
+                    _ = sleep(delay) => {}
+                    _ = stop_token.when_cancelled() => {
+                        let _ = std::fs::remove_file(&self.socket_path);
+                        return Ok(());
+                    }
+                }
+
+                match self.connect(&listener).await {
+                    Ok((restarted, _)) => {
+                        connected = restarted;
+                        break;
+                    }
+                    Err(error) => log::error!(
+                        "Restart attempt {attempt} of connector process of {} failed: {error:?}",
+                        self.exchange_account_id
+                    ),
+                }
+
+                attempt += 1;
+                delay = next_restart_delay(delay);
+            }
+
+            log::info!(
+                "Connector process of {} is restarted after {attempt} attempts",
+                self.exchange_account_id
+            );
+        }
+    }
+}
+
+/// Exchange client which forwards calls to connector running in separate process (see
+/// `ConnectorProcessSettings`). Websockets of exchange are kept by connector process, engine
+/// reconnects them and resyncs orders when they are disconnected or connector process is
+/// restarted. Optional requests of `ExchangeClient` aren't supported yet
+pub struct ConnectorProcessClient {
+    connection: Arc<Connection>,
+    enabled_websockets: Vec<WebSocketRole>,
+    specific_currency_pairs: DashMap<CurrencyPair, SpecificCurrencyPair>,
+    supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+}
+
+impl ConnectorProcessClient {
+    fn order_snapshot(order: &OrderRef) -> OrderSnapshot {
+        order.fn_ref(|x| x.clone())
+    }
+}
+
+#[async_trait]
+impl ExchangeClient for ConnectorProcessClient {
+    async fn create_order(&self, order: &OrderRef) -> CreateOrderResult {
+        let request = ConnectorRequest::CreateOrder {
+            order: Self::order_snapshot(order),
+        };
+        match self.connection.request(request).await {
+            Ok(result) => result,
+            Err(error) => CreateOrderResult::failed(error, EventSourceType::Rest),
+        }
+    }
+
+    async fn cancel_order(&self, order: OrderCancelling) -> CancelOrderResult {
+        match self
+            .connection
+            .request(ConnectorRequest::CancelOrder { order })
+            .await
+        {
+            Ok(result) => result,
+            Err(error) => CancelOrderResult::failed(error, EventSourceType::Rest),
+        }
+    }
+
+    async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        Ok(self
+            .connection
+            .request(ConnectorRequest::CancelAllOrders { currency_pair })
+            .await?)
+    }
+
+    async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
+        Ok(self
+            .connection
+            .request(ConnectorRequest::GetOpenOrders {
+                currency_pair: None,
+            })
+            .await?)
+    }
+
+    async fn get_open_orders_by_currency_pair(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<Vec<OrderInfo>> {
+        Ok(self
+            .connection
+            .request(ConnectorRequest::GetOpenOrders {
+                currency_pair: Some(currency_pair),
+            })
+            .await?)
+    }
+
+    async fn get_order_info(&self, order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
+        let request = ConnectorRequest::GetOrderInfo {
+            order: Self::order_snapshot(order),
+        };
+        self.connection.request(request).await
+    }
+
+    async fn close_position(
+        &self,
+        position: &ActivePosition,
+        price: Option<Price>,
+    ) -> Result<ClosedPosition> {
+        let request = ConnectorRequest::ClosePosition {
+            position: position.clone(),
+            price,
+        };
+        Ok(self.connection.request(request).await?)
+    }
+
+    async fn get_active_positions(&self) -> Result<Vec<ActivePosition>> {
+        Ok(self
+            .connection
+            .request(ConnectorRequest::GetActivePositions)
+            .await?)
+    }
+
+    async fn get_balance(&self, is_spot: bool) -> Result<ExchangeBalancesAndPositions> {
+        Ok(self
+            .connection
+            .request(ConnectorRequest::GetBalance { is_spot })
+            .await?)
+    }
+
+    async fn get_my_trades(
+        &self,
+        symbol: &Symbol,
+        last_date_time: Option<DateTime>,
+    ) -> Result<RequestResult<Vec<OrderTrade>>> {
+        let request = ConnectorRequest::GetMyTrades {
+            currency_pair: symbol.currency_pair(),
+            last_date_time,
+        };
+        match self.connection.request(request).await {
+            Ok(result) => Ok(result),
+            Err(error) => Ok(RequestResult::Error(error)),
+        }
+    }
+
+    async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>> {
+        let response: ConnectorSymbols = self
+            .connection
+            .request(ConnectorRequest::BuildAllSymbols)
+            .await?;
+
+        for (currency_pair, specific_currency_pair) in response.specific_currency_pairs {
+            let _ = self
+                .specific_currency_pairs
+                .insert(currency_pair, specific_currency_pair);
+        }
+        for (currency_id, currency_code) in response.supported_currencies {
+            let _ = self.supported_currencies.insert(currency_id, currency_code);
+        }
+
+        Ok(response.symbols.into_iter().map(Arc::new).collect())
+    }
+}
+
+#[async_trait]
+impl Support for ConnectorProcessClient {
+    /// Messages of websockets are parsed by connector process
+    fn on_websocket_message(&self, _msg: &str) -> Result<()> {
+        bail!("Websockets of connector process aren't kept by engine")
+    }
+
+    /// Connector in connector process is notified when its websockets are connected
+    fn on_connecting(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Messages are sent to websockets by connector process itself
+    fn set_send_websocket_message_callback(&self, _callback: SendWebsocketMessageCb) {}
+
+    fn set_order_created_callback(&mut self, callback: OrderCreatedCb) {
+        self.connection.callbacks.write().order_created = callback;
+    }
+
+    fn set_order_cancelled_callback(&mut self, callback: OrderCancelledCb) {
+        self.connection.callbacks.write().order_cancelled = callback;
+    }
+
+    fn set_handle_order_filled_callback(&mut self, callback: HandleOrderFilledCb) {
+        self.connection.callbacks.write().handle_order_filled = callback;
+    }
+
+    fn set_handle_trade_callback(&mut self, callback: HandleTradeCb) {
+        self.connection.callbacks.write().handle_trade = callback;
+    }
+
+    fn set_traded_specific_currencies(&self, currencies: Vec<SpecificCurrencyPair>) {
+        *self.connection.traded_specific_currencies.lock() = Some(currencies.clone());
+
+        let notification = ConnectorNotification::TradedSpecificCurrencies(currencies);
+        if let Err(error) = self.connection.notify(notification) {
+            log::warn!("Unable to set traded currencies in connector process: {error:?}");
+        }
+    }
+
+    fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
+        self.enabled_websockets.contains(&role)
+    }
+
+    async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url> {
+        bail!("Websocket {role:?} is opened by connector process")
+    }
+
+    async fn connect_own_websockets(
+        &self,
+        is_market_data_only: bool,
+    ) -> Option<Result<mpsc::UnboundedReceiver<String>>> {
+        Some(
+            self.connection
+                .connect_websockets(is_market_data_only)
+                .await,
+        )
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        match self.specific_currency_pairs.get(&currency_pair) {
+            Some(specific_currency_pair) => *specific_currency_pair,
+            None => currency_pair.as_str().into(),
+        }
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        &self.supported_currencies
+    }
+
+    /// Messages are logged by connector process
+    fn should_log_message(&self, _message: &str) -> bool {
+        false
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        &self.connection.settings
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Starts connector process of exchange account and creates client forwarding calls to it.
+/// Connector process is restarted until engine is stopped
+pub async fn start_connector_process(
+    exchange_settings: ExchangeSettings,
+    process_settings: ConnectorProcessSettings,
+    events_channel: broadcast::Sender<ExchangeEvent>,
+    lifetime_manager: Arc<AppLifetimeManager>,
+) -> Result<ExchangeClientBuilderResult> {
+    let exchange_account_id = exchange_settings.exchange_account_id;
+    let socket_path = std::env::temp_dir().join(format!(
+        "mmb_connector_{}_{exchange_account_id}.sock",
+        std::process::id()
+    ));
+    // socket of previous engine with the same pid can be left after crash
+    let _ = std::fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path).with_context(|| {
+        format!(
+            "Unable to bind socket {} for connector process",
+            socket_path.display()
+        )
+    })?;
+
+    let connection = Arc::new(Connection {
+        exchange_account_id,
+        settings: exchange_settings,
+        process_settings,
+        socket_path,
+        events_channel,
+        lifetime_manager,
+        callbacks: RwLock::new(Callbacks {
+            order_created: Box::new(|_, _, _| {}),
+            order_cancelled: Box::new(|_, _, _| {}),
+            handle_order_filled: Box::new(|_| {}),
+            handle_trade: Box::new(|_, _, _, _, _, _| {}),
+        }),
+        frames: Mutex::new(None),
+        connected: Notify::new(),
+        websockets: Mutex::new(None),
+        pending_requests: Mutex::new(HashMap::new()),
+        last_request_id: AtomicU64::new(0),
+        request_timeout: REQUEST_TIMEOUT,
+        hung: Mutex::new(None),
+        traded_specific_currencies: Mutex::new(None),
+    });
+
+    let (connected, info) = connection.connect(&listener).await?;
+    let _ = spawn_future(
+        &format!("Connector process supervision of {exchange_account_id}"),
+        SpawnFutureFlags::STOP_BY_TOKEN,
+        connection.clone().supervise(listener, connected),
+    );
+
+    let client = ConnectorProcessClient {
+        connection,
+        enabled_websockets: info.enabled_websockets,
+        specific_currency_pairs: DashMap::new(),
+        supported_currencies: DashMap::new(),
+    };
+
+    Ok(ExchangeClientBuilderResult {
+        client: Box::new(client),
+        features: info.features,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_utils::cancellation_token::CancellationToken;
+
+    fn test_connection(
+        frames: mpsc::UnboundedSender<Frame>,
+        hung: oneshot::Sender<()>,
+    ) -> Connection {
+        Connection {
+            exchange_account_id: ExchangeAccountId::new("Binance", 0),
+            settings: ExchangeSettings::default(),
+            process_settings: ConnectorProcessSettings {
+                executable: None,
+                args: Vec::new(),
+            },
+            socket_path: PathBuf::new(),
+            events_channel: broadcast::channel(10).0,
+            lifetime_manager: AppLifetimeManager::new(CancellationToken::new()),
+            callbacks: RwLock::new(Callbacks {
+                order_created: Box::new(|_, _, _| {}),
+                order_cancelled: Box::new(|_, _, _| {}),
+                handle_order_filled: Box::new(|_| {}),
+                handle_trade: Box::new(|_, _, _, _, _, _| {}),
+            }),
+            frames: Mutex::new(Some(frames)),
+            connected: Notify::new(),
+            websockets: Mutex::new(None),
+            pending_requests: Mutex::new(HashMap::new()),
+            last_request_id: AtomicU64::new(0),
+            request_timeout: Duration::from_millis(50),
+            hung: Mutex::new(Some(hung)),
+            traded_specific_currencies: Mutex::new(None),
+        }
+    }
+
+    #[tokio::test]
+    async fn response_is_returned_to_request() {
+        let (frames_sender, mut frames_receiver) = mpsc::unbounded_channel();
+        let (hung_sender, mut hung) = oneshot::channel();
+        let connection = test_connection(frames_sender, hung_sender);
+
+        let request =
+            connection.request::<Vec<ActivePosition>>(ConnectorRequest::GetActivePositions);
+        let respond = async {
+            let frame = frames_receiver.recv().await.expect("in test");
+            connection.handle_frame(Frame::response(
+                frame.request_id,
+                &Ok(ConnectorResponse::ActivePositions(Vec::new())),
+            ));
+        };
+        let (positions, _) = tokio::join!(request, respond);
+
+        assert!(positions.expect("in test").is_empty());
+        assert!(hung.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn request_without_response_fails_and_restarts_process() {
+        let (frames_sender, mut frames_receiver) = mpsc::unbounded_channel();
+        let (hung_sender, mut hung) = oneshot::channel();
+        let connection = test_connection(frames_sender, hung_sender);
+
+        let error = connection
+            .request::<Vec<ActivePosition>>(ConnectorRequest::GetActivePositions)
+            .await
+            .expect_err("in test");
+
+        assert_eq!(error.error_type, ExchangeErrorType::ServiceUnavailable);
+        assert!(frames_receiver.try_recv().is_ok());
+        assert!(connection.pending_requests.lock().is_empty());
+        assert!(hung.try_recv().is_ok());
+        assert!(connection.hung.lock().is_none());
+    }
+
+    #[test]
+    pub fn restart_delay_grows_up_to_limit() {
+        let mut delay = INITIAL_RESTART_DELAY;
+        for _ in 0..10 {
+            delay = next_restart_delay(delay);
+        }
+
+        assert_eq!(
+            next_restart_delay(INITIAL_RESTART_DELAY),
+            Duration::from_secs(2)
+        );
+        assert_eq!(delay, MAX_RESTART_DELAY);
+    }
+}
@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use itertools::Itertools;
+use mmb_utils::infrastructure::{init_infrastructure, SpawnFutureFlags};
+use parking_lot::{Mutex, RwLock};
+use tokio::net::UnixStream;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+
+use crate::connectivity::{
+    websocket_open, ConnectivityError, WebSocketParams, WebSocketRole, WsSender,
+};
+use crate::exchanges::common::{CurrencyPair, ExchangeAccountId, ExchangeError};
+use crate::exchanges::connector_process::protocol::{
+    read_frame, start_frames_writer, ConnectorInfo, ConnectorNotification, ConnectorRequest,
+    ConnectorResponse, ConnectorResult, ConnectorSymbols, Frame, FrameMessage,
+};
+use crate::exchanges::events::{ExchangeEvent, CHANNEL_MAX_EVENTS_COUNT};
+use crate::exchanges::general::exchange::BoxExchangeClient;
+use crate::exchanges::general::symbol::Symbol;
+use crate::exchanges::traits::ExchangeClientBuilderResult;
+use crate::infrastructure::{init_lifetime_manager, spawn_future_ok};
+use crate::lifecycle::launcher::EngineBuildConfig;
+use crate::order_book::event::EventType;
+use crate::orders::order::OrderSnapshot;
+use crate::orders::pool::{OrderRef, OrdersPool};
+
+/// Path of engine socket is passed to started connector process by this environment variable
+pub const CONNECTOR_SOCKET_ENV: &str = "MMB_CONNECTOR_SOCKET";
+
+/// Path of engine socket if current process is started by engine as connector process
+pub fn connector_socket_from_env() -> Option<PathBuf> {
+    std::env::var_os(CONNECTOR_SOCKET_ENV).map(PathBuf::from)
+}
+
+fn to_response<T>(
+    result: Result<T, impl Into<ExchangeError>>,
+    response: impl FnOnce(T) -> ConnectorResponse,
+) -> ConnectorResult {
+    result.map(response).map_err(Into::into)
+}
+
+/// Connector of exchange account serving engine in connector process
+struct ConnectorHost {
+    exchange_account_id: ExchangeAccountId,
+    client: BoxExchangeClient,
+    /// Copies of engine orders, because connectors get orders from engine by `OrderRef`
+    orders: Arc<OrdersPool>,
+    symbols: RwLock<HashMap<CurrencyPair, Arc<Symbol>>>,
+    frames: mpsc::UnboundedSender<Frame>,
+    /// Shared with callback of connector sending websocket messages
+    ws_sender: Arc<Mutex<Option<WsSender>>>,
+    /// Id of the last websockets connection, so disconnection of replaced websockets is ignored
+    websockets_id: AtomicU64,
+}
+
+impl ConnectorHost {
+    fn order_ref(&self, order: OrderSnapshot) -> OrderRef {
+        let client_order_id = order.header.client_order_id.clone();
+        match self.orders.get_by_client_id(&client_order_id) {
+            Some(order_ref) => {
+                order_ref.fn_mut(|x| *x = order);
+                order_ref
+            }
+            None => self
+                .orders
+                .add_snapshot_initial(Arc::new(RwLock::new(order))),
+        }
+    }
+
+    async fn build_all_symbols(&self) -> Result<ConnectorSymbols> {
+        let symbols = self.client.build_all_symbols().await?;
+        *self.symbols.write() = symbols
+            .iter()
+            .map(|symbol| (symbol.currency_pair(), symbol.clone()))
+            .collect();
+
+        Ok(ConnectorSymbols {
+            specific_currency_pairs: symbols
+                .iter()
+                .map(|symbol| {
+                    let currency_pair = symbol.currency_pair();
+                    (
+                        currency_pair,
+                        self.client.get_specific_currency_pair(currency_pair),
+                    )
+                })
+                .collect_vec(),
+            supported_currencies: self
+                .client
+                .get_supported_currencies()
+                .iter()
+                .map(|x| (*x.key(), *x.value()))
+                .collect_vec(),
+            symbols: symbols.iter().map(|x| x.as_ref().clone()).collect_vec(),
+        })
+    }
+
+    async fn websocket_params(&self, role: WebSocketRole) -> Result<WebSocketParams> {
+        let ws_url = self.client.create_ws_url(role).await?;
+        let egress = self.client.get_settings().egress.clone();
+        Ok(WebSocketParams::new(ws_url).with_egress(egress))
+    }
+
+    /// Websockets of exchange are kept by connector process, so their messages are parsed
+    /// without sending them to engine. Engine is notified when websockets are disconnected
+    async fn connect_websockets(self: &Arc<Self>, is_market_data_only: bool) -> Result<()> {
+        self.client.on_connecting()?;
+
+        let main = self.websocket_params(WebSocketRole::Main).await?;
+        let secondary = match !is_market_data_only
+            && self.client.is_websocket_enabled(WebSocketRole::Secondary)
+        {
+            true => Some(self.websocket_params(WebSocketRole::Secondary).await?),
+            false => None,
+        };
+        let (ws_sender, mut receiver) =
+            websocket_open(self.exchange_account_id, main, secondary).await?;
+        let websockets_id = self.websockets_id.fetch_add(1, Ordering::SeqCst) + 1;
+        *self.ws_sender.lock() = Some(ws_sender);
+
+        let host = self.clone();
+        let _ = spawn_future_ok(
+            "Connector process websockets reader",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            async move {
+                while let Some(message) = receiver.recv().await {
+                    if host.client.should_log_message(&message) {
+                        log::info!(
+                            "Websocket message from {}: {}",
+                            host.exchange_account_id,
+                            message
+                        );
+                    }
+                    if let Err(error) = host.client.on_websocket_message(&message) {
+                        log::warn!("Error occurred while websocket message processing: {error:?}");
+                    }
+                }
+
+                if host.websockets_id.load(Ordering::SeqCst) == websockets_id {
+                    let _ = host.ws_sender.lock().take();
+                    log_notify_error(
+                        Frame::notification(&ConnectorNotification::WebsocketsDisconnected)
+                            .and_then(|frame| {
+                                host.frames
+                                    .send(frame)
+                                    .map_err(|_| anyhow::anyhow!("Connection to engine is closed"))
+                            }),
+                    );
+                }
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn handle_request(self: &Arc<Self>, request: ConnectorRequest) -> ConnectorResult {
+        match request {
+            ConnectorRequest::Init { .. } => Err(ExchangeError::unknown(
+                "Connector process is initialized already",
+            )),
+            ConnectorRequest::CreateOrder { order } => {
+                let order = self.order_ref(order);
+                Ok(ConnectorResponse::CreateOrder(
+                    self.client.create_order(&order).await,
+                ))
+            }
+            ConnectorRequest::CancelOrder { order } => Ok(ConnectorResponse::CancelOrder(
+                self.client.cancel_order(order).await,
+            )),
+            ConnectorRequest::CancelAllOrders { currency_pair } => {
+                to_response(self.client.cancel_all_orders(currency_pair).await, |_| {
+                    ConnectorResponse::Done
+                })
+            }
+            ConnectorRequest::GetOpenOrders {
+                currency_pair: None,
+            } => to_response(
+                self.client.get_open_orders().await,
+                ConnectorResponse::Orders,
+            ),
+            ConnectorRequest::GetOpenOrders {
+                currency_pair: Some(currency_pair),
+            } => to_response(
+                self.client
+                    .get_open_orders_by_currency_pair(currency_pair)
+                    .await,
+                ConnectorResponse::Orders,
+            ),
+            ConnectorRequest::GetOrderInfo { order } => {
+                let order = self.order_ref(order);
+                to_response(
+                    self.client.get_order_info(&order).await,
+                    ConnectorResponse::OrderInfo,
+                )
+            }
+            ConnectorRequest::GetBalance { is_spot } => to_response(
+                self.client.get_balance(is_spot).await,
+                ConnectorResponse::Balance,
+            ),
+            ConnectorRequest::GetMyTrades {
+                currency_pair,
+                last_date_time,
+            } => {
+                let symbol = self.symbols.read().get(&currency_pair).cloned();
+                match symbol {
+                    Some(symbol) => to_response(
+                        self.client.get_my_trades(&symbol, last_date_time).await,
+                        ConnectorResponse::MyTrades,
+                    ),
+                    None => Err(ExchangeError::unknown(&format!(
+                        "Symbol {currency_pair} isn't built in connector process"
+                    ))),
+                }
+            }
+            ConnectorRequest::BuildAllSymbols => {
+                to_response(self.build_all_symbols().await, ConnectorResponse::Symbols)
+            }
+            ConnectorRequest::ConnectWebsockets {
+                is_market_data_only,
+            } => to_response(self.connect_websockets(is_market_data_only).await, |_| {
+                ConnectorResponse::Done
+            }),
+            ConnectorRequest::GetActivePositions => to_response(
+                self.client.get_active_positions().await,
+                ConnectorResponse::ActivePositions,
+            ),
+            ConnectorRequest::ClosePosition { position, price } => to_response(
+                self.client.close_position(&position, price).await,
+                ConnectorResponse::ClosedPosition,
+            ),
+        }
+    }
+
+    fn handle_notification(&self, notification: ConnectorNotification) -> Result<()> {
+        match notification {
+            ConnectorNotification::TradedSpecificCurrencies(currencies) => {
+                self.client.set_traded_specific_currencies(currencies);
+                Ok(())
+            }
+            notification => bail!("Unexpected notification {notification:?}"),
+        }
+    }
+}
+
+/// Callbacks of connector are sent to engine as notifications, websocket messages of connector
+/// are sent to websockets of connector process
+fn set_callbacks(
+    client: &mut BoxExchangeClient,
+    frames: &mpsc::UnboundedSender<Frame>,
+    ws_sender: Arc<Mutex<Option<WsSender>>>,
+) {
+    let notify = {
+        let frames = frames.clone();
+        move |notification: ConnectorNotification| -> Result<()> {
+            let frame = Frame::notification(&notification)?;
+            frames
+                .send(frame)
+                .map_err(|_| anyhow::anyhow!("Connection to engine is closed"))
+        }
+    };
+    let notify = Arc::new(notify);
+
+    client.set_send_websocket_message_callback(Box::new(move |role, message| {
+        match &*ws_sender.lock() {
+            Some(sender) => match role {
+                WebSocketRole::Main => sender.send_main(message),
+                WebSocketRole::Secondary => sender.send_secondary(message),
+            }
+            .map_err(|error| error.into()),
+            None => Err(ConnectivityError::NotConnected.into()),
+        }
+    }));
+
+    let notify_clone = notify.clone();
+    client.set_order_created_callback(Box::new(
+        move |client_order_id, exchange_order_id, source_type| {
+            log_notify_error(notify_clone(ConnectorNotification::OrderCreated {
+                client_order_id,
+                exchange_order_id,
+                source_type,
+            }))
+        },
+    ));
+
+    let notify_clone = notify.clone();
+    client.set_order_cancelled_callback(Box::new(
+        move |client_order_id, exchange_order_id, source_type| {
+            log_notify_error(notify_clone(ConnectorNotification::OrderCancelled {
+                client_order_id,
+                exchange_order_id,
+                source_type,
+            }))
+        },
+    ));
+
+    let notify_clone = notify.clone();
+    client.set_handle_order_filled_callback(Box::new(move |fill_event| {
+        log_notify_error(notify_clone(ConnectorNotification::OrderFilled(Box::new(
+            fill_event,
+        ))))
+    }));
+
+    client.set_handle_trade_callback(Box::new(
+        move |currency_pair, trade_id, price, amount, side, transaction_time| {
+            log_notify_error(notify(ConnectorNotification::Trade {
+                currency_pair,
+                trade_id,
+                price,
+                amount,
+                side,
+                transaction_time,
+            }))
+        },
+    ));
+}
+
+fn log_notify_error(outcome: Result<()>) {
+    if let Err(error) = outcome {
+        log::error!("Unable to notify engine: {error:?}");
+    }
+}
+
+/// Order book events of connector are sent to engine. Other exchange events aren't produced by
+/// connectors
+async fn forward_events(
+    mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+    frames: mpsc::UnboundedSender<Frame>,
+) {
+    loop {
+        let event = match events_receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                log::warn!("{skipped} exchange events of connector aren't sent to engine");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        let notification = match event {
+            ExchangeEvent::OrderBookEvent(event) => ConnectorNotification::OrderBook {
+                creation_time: event.creation_time,
+                currency_pair: event.currency_pair,
+                is_snapshot: matches!(event.event_type, EventType::Snapshot),
+                asks: event.data.asks.iter().map(|(&x, &y)| (x, y)).collect_vec(),
+                bids: event.data.bids.iter().map(|(&x, &y)| (x, y)).collect_vec(),
+            },
+            event => {
+                log::warn!("Exchange event isn't supported by connector process: {event:?}");
+                continue;
+            }
+        };
+
+        let sent = Frame::notification(&notification).map(|frame| frames.send(frame).is_ok());
+        match sent {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(error) => log::error!("Unable to send order book to engine: {error:?}"),
+        }
+    }
+}
+
+/// Runs connector in current process started by engine (see `ConnectorProcessSettings`).
+/// Connector of exchange account which engine requests is built by `build_config`, so engine
+/// executable runs connector process like this:
+///
+/// ```ignore
+/// if let Some(socket_path) = connector_socket_from_env() {
+///     return run_connector_process(&build_config, &socket_path).await;
+/// }
+/// ```
+///
+/// Requests of engine are served until engine closes connection. Connector process writes its
+/// own log file named by exchange account
+pub async fn run_connector_process(
+    build_config: &EngineBuildConfig,
+    socket_path: &Path,
+) -> Result<()> {
+    let lifetime_manager = init_lifetime_manager();
+    let stream = UnixStream::connect(socket_path)
+        .await
+        .with_context(|| format!("Unable to connect to engine by {}", socket_path.display()))?;
+    let (mut reader, writer) = stream.into_split();
+
+    let init = read_frame(&mut reader)
+        .await?
+        .context("Engine closed connection before initialization of connector")?;
+    let init_request_id = init.request_id;
+    let (settings, traded_specific_currencies) = match init.into_message()? {
+        FrameMessage::Request(ConnectorRequest::Init {
+            settings,
+            traded_specific_currencies,
+        }) => (*settings, traded_specific_currencies),
+        _ => bail!("Unexpected first frame of engine"),
+    };
+
+    let exchange_account_id = settings.exchange_account_id;
+    init_infrastructure(&format!("log_{exchange_account_id}.txt"));
+
+    let exchange_id = exchange_account_id.exchange_id;
+    let builder = build_config
+        .supported_exchange_clients
+        .get(&exchange_id)
+        .with_context(|| format!("Connector of {exchange_id} isn't built in connector process"))?;
+
+    let frames = start_frames_writer(writer);
+    let (events_sender, events_receiver) = broadcast::channel(CHANNEL_MAX_EVENTS_COUNT);
+    let orders = OrdersPool::new();
+    let ExchangeClientBuilderResult {
+        mut client,
+        features,
+    } = builder.create_exchange_client(settings, events_sender, lifetime_manager, orders.clone());
+    let ws_sender = Arc::new(Mutex::new(None));
+    set_callbacks(&mut client, &frames, ws_sender.clone());
+
+    let _ = spawn_future_ok(
+        "Connector process events forwarding",
+        SpawnFutureFlags::STOP_BY_TOKEN,
+        forward_events(events_receiver, frames.clone()),
+    );
+
+    let host = Arc::new(ConnectorHost {
+        exchange_account_id,
+        client,
+        orders,
+        symbols: RwLock::new(HashMap::new()),
+        frames: frames.clone(),
+        ws_sender,
+        websockets_id: AtomicU64::new(0),
+    });
+
+    let restore = async {
+        if let Some(currencies) = traded_specific_currencies {
+            let _ = host.build_all_symbols().await?;
+            host.client.set_traded_specific_currencies(currencies);
+        }
+
+        Ok::<_, anyhow::Error>(ConnectorInfo {
+            features,
+            enabled_websockets: [WebSocketRole::Main, WebSocketRole::Secondary]
+                .into_iter()
+                .filter(|&role| host.client.is_websocket_enabled(role))
+                .collect_vec(),
+        })
+    };
+    let info = to_response(restore.await, ConnectorResponse::Info);
+    let _ = frames.send(Frame::response(init_request_id, &info));
+    if let Err(error) = info {
+        bail!("Initialization of connector {exchange_account_id} failed: {error:?}");
+    }
+
+    log::info!("Connector process of {exchange_account_id} is initialized");
+    while let Some(frame) = read_frame(&mut reader).await? {
+        let request_id = frame.request_id;
+        match frame.into_message() {
+            Ok(FrameMessage::Request(request)) => {
+                let host = host.clone();
+                let frames = frames.clone();
+                let _ = spawn_future_ok(
+                    "Connector process request",
+                    SpawnFutureFlags::STOP_BY_TOKEN,
+                    async move {
+                        let response = host.handle_request(request).await;
+                        let _ = frames.send(Frame::response(request_id, &response));
+                    },
+                );
+            }
+            Ok(FrameMessage::Notification(notification)) => {
+                if let Err(error) = host.handle_notification(notification) {
+                    log::warn!("Error occurred while notification of engine processing: {error:?}");
+                }
+            }
+            Ok(FrameMessage::Response(_)) => log::warn!("Unexpected response from engine"),
+            // engine waits for response until timeout, so request which can't be parsed fails
+            Err(error) => {
+                log::warn!("Unable to parse frame {request_id} of engine: {error:?}");
+                let error = ExchangeError::parsing(format!("Unable to parse request: {error}"));
+                let _ = frames.send(Frame::response(request_id, &Err(error)));
+            }
+        }
+    }
+
+    log::info!("Engine closed connection to connector process of {exchange_account_id}");
+    Ok(())
+}
@@ -0,0 +1,4 @@
+pub mod client;
+pub mod host;
+pub mod proto;
+pub mod protocol;
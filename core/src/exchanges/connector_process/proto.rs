@@ -0,0 +1,725 @@
+//! Protobuf messages between engine and connector process. They are defined by prost derives
+//! instead of `.proto` file, so build of core doesn't need `protoc`.
+//!
+//! Conventions of the schema:
+//! * decimals are strings, so values are sent without loss of precision
+//! * times are `int64` nanoseconds since UNIX epoch
+//! * ids, currency codes and currency pairs are strings
+//! * enums are `int32` with values of discriminants of Rust enums (see `ProtoEnum` impls in
+//!   `protocol`)
+//!
+//! Fields can be added with new tags only, so engine and connector process of different
+//! versions skip unknown fields of each other.
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Empty {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Request {
+    #[prost(
+        oneof = "request::Kind",
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12"
+    )]
+    pub kind: Option<request::Kind>,
+}
+
+pub mod request {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Kind {
+        #[prost(message, tag = "1")]
+        Init(super::Init),
+        #[prost(message, tag = "2")]
+        CreateOrder(super::Order),
+        #[prost(message, tag = "3")]
+        CancelOrder(super::OrderCancelling),
+        #[prost(message, tag = "4")]
+        CancelAllOrders(super::CancelAllOrders),
+        #[prost(message, tag = "5")]
+        GetOpenOrders(super::GetOpenOrders),
+        #[prost(message, tag = "6")]
+        GetOrderInfo(super::Order),
+        #[prost(message, tag = "7")]
+        GetBalance(super::GetBalance),
+        #[prost(message, tag = "8")]
+        GetMyTrades(super::GetMyTrades),
+        #[prost(message, tag = "9")]
+        BuildAllSymbols(super::Empty),
+        #[prost(message, tag = "10")]
+        ConnectWebsockets(super::ConnectWebsockets),
+        #[prost(message, tag = "11")]
+        GetActivePositions(super::Empty),
+        #[prost(message, tag = "12")]
+        ClosePosition(super::ClosePosition),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Init {
+    /// `ExchangeSettings` as TOML document of engine configuration, so settings aren't
+    /// duplicated by the schema
+    #[prost(string, tag = "1")]
+    pub settings_toml: String,
+    #[prost(message, optional, tag = "2")]
+    pub traded_specific_currencies: Option<SpecificCurrencyPairs>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SpecificCurrencyPairs {
+    #[prost(string, repeated, tag = "1")]
+    pub values: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderHeader {
+    #[prost(string, tag = "1")]
+    pub client_order_id: String,
+    #[prost(int64, tag = "2")]
+    pub init_time: i64,
+    #[prost(string, tag = "3")]
+    pub exchange_account_id: String,
+    #[prost(string, tag = "4")]
+    pub currency_pair: String,
+    #[prost(int32, tag = "5")]
+    pub order_type: i32,
+    #[prost(int32, tag = "6")]
+    pub side: i32,
+    #[prost(string, tag = "7")]
+    pub amount: String,
+    #[prost(int32, tag = "8")]
+    pub execution_type: i32,
+    #[prost(uint64, optional, tag = "9")]
+    pub reservation_id: Option<u64>,
+    #[prost(string, optional, tag = "10")]
+    pub signal_id: Option<String>,
+    #[prost(string, tag = "11")]
+    pub strategy_name: String,
+    #[prost(string, optional, tag = "12")]
+    pub quote_amount: Option<String>,
+    #[prost(int64, optional, tag = "13")]
+    pub expire_time: Option<i64>,
+    #[prost(bool, tag = "14")]
+    pub reduce_only: bool,
+    #[prost(uint64, optional, tag = "15")]
+    pub latency_budget_nanos: Option<u64>,
+    #[prost(bool, tag = "16")]
+    pub skip_price_sanity_check: bool,
+}
+
+/// Part of order which connectors use to send requests about the order
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Order {
+    #[prost(message, optional, tag = "1")]
+    pub header: Option<OrderHeader>,
+    #[prost(string, optional, tag = "2")]
+    pub price: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub exchange_order_id: Option<String>,
+    #[prost(int32, tag = "4")]
+    pub status: i32,
+    /// JSON of connector specific `OrderInfoExtensionData`, which is opaque for engine
+    #[prost(string, optional, tag = "5")]
+    pub extension_data: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderCancelling {
+    #[prost(message, optional, tag = "1")]
+    pub header: Option<OrderHeader>,
+    #[prost(string, tag = "2")]
+    pub exchange_order_id: String,
+    #[prost(string, optional, tag = "3")]
+    pub extension_data: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CancelAllOrders {
+    #[prost(string, tag = "1")]
+    pub currency_pair: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetOpenOrders {
+    #[prost(string, optional, tag = "1")]
+    pub currency_pair: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetBalance {
+    #[prost(bool, tag = "1")]
+    pub is_spot: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetMyTrades {
+    #[prost(string, tag = "1")]
+    pub currency_pair: String,
+    #[prost(int64, optional, tag = "2")]
+    pub last_date_time: Option<i64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ConnectWebsockets {
+    #[prost(bool, tag = "1")]
+    pub is_market_data_only: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ClosePosition {
+    #[prost(message, optional, tag = "1")]
+    pub position: Option<ActivePosition>,
+    #[prost(string, optional, tag = "2")]
+    pub price: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ActivePosition {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    /// HTTP status code
+    #[prost(uint32, tag = "2")]
+    pub status: u32,
+    #[prost(uint64, tag = "3")]
+    pub time_stamp: u64,
+    #[prost(string, tag = "4")]
+    pub pl: String,
+    #[prost(message, optional, tag = "5")]
+    pub derivative: Option<DerivativePosition>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DerivativePosition {
+    #[prost(string, tag = "1")]
+    pub currency_pair: String,
+    #[prost(string, tag = "2")]
+    pub position: String,
+    #[prost(int32, optional, tag = "3")]
+    pub side: Option<i32>,
+    #[prost(string, tag = "4")]
+    pub average_entry_price: String,
+    #[prost(string, tag = "5")]
+    pub liquidation_price: String,
+    #[prost(string, tag = "6")]
+    pub leverage: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Response {
+    #[prost(
+        oneof = "response::Kind",
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12"
+    )]
+    pub kind: Option<response::Kind>,
+}
+
+pub mod response {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Kind {
+        #[prost(message, tag = "1")]
+        Error(super::ExchangeError),
+        #[prost(message, tag = "2")]
+        Info(super::ConnectorInfo),
+        #[prost(message, tag = "3")]
+        CreateOrder(super::CreateOrderResult),
+        #[prost(message, tag = "4")]
+        CancelOrder(super::CancelOrderResult),
+        #[prost(message, tag = "5")]
+        Done(super::Empty),
+        #[prost(message, tag = "6")]
+        Orders(super::OrderInfos),
+        #[prost(message, tag = "7")]
+        OrderInfo(super::OrderInfo),
+        #[prost(message, tag = "8")]
+        Balance(super::ExchangeBalancesAndPositions),
+        #[prost(message, tag = "9")]
+        MyTrades(super::MyTrades),
+        #[prost(message, tag = "10")]
+        Symbols(super::ConnectorSymbols),
+        #[prost(message, tag = "11")]
+        ActivePositions(super::ActivePositions),
+        #[prost(message, tag = "12")]
+        ClosedPosition(super::ClosedPosition),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExchangeError {
+    #[prost(int32, tag = "1")]
+    pub error_type: i32,
+    /// Delay of `ExchangeErrorType::PendingError`
+    #[prost(uint64, optional, tag = "2")]
+    pub pending_delay_nanos: Option<u64>,
+    #[prost(string, tag = "3")]
+    pub message: String,
+    #[prost(int64, optional, tag = "4")]
+    pub code: Option<i64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ConnectorInfo {
+    #[prost(message, optional, tag = "1")]
+    pub features: Option<ExchangeFeatures>,
+    #[prost(int32, repeated, tag = "2")]
+    pub enabled_websockets: Vec<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExchangeFeatures {
+    #[prost(int32, tag = "1")]
+    pub open_orders_type: i32,
+    #[prost(int32, tag = "2")]
+    pub rest_fills_type: i32,
+    #[prost(message, optional, tag = "3")]
+    pub order_features: Option<OrderFeatures>,
+    #[prost(message, optional, tag = "4")]
+    pub trade_option: Option<OrderTradeOption>,
+    #[prost(message, optional, tag = "5")]
+    pub websocket_options: Option<WebSocketOptions>,
+    #[prost(bool, tag = "6")]
+    pub empty_response_is_ok: bool,
+    #[prost(int32, tag = "7")]
+    pub balance_position_option: i32,
+    #[prost(int32, tag = "8")]
+    pub allowed_create_event_source_type: i32,
+    #[prost(int32, tag = "9")]
+    pub allowed_fill_event_source_type: i32,
+    #[prost(int32, tag = "10")]
+    pub allowed_cancel_event_source_type: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderFeatures {
+    #[prost(bool, tag = "1")]
+    pub maker_only: bool,
+    #[prost(bool, tag = "2")]
+    pub supports_get_order_info_by_client_order_id: bool,
+    #[prost(bool, tag = "3")]
+    pub cancellation_response_from_rest_only_for_errors: bool,
+    #[prost(bool, tag = "4")]
+    pub creation_response_from_rest_only_for_errors: bool,
+    #[prost(bool, tag = "5")]
+    pub order_was_completed_error_for_cancellation: bool,
+    #[prost(bool, tag = "6")]
+    pub supports_already_cancelled_order: bool,
+    #[prost(bool, tag = "7")]
+    pub supports_stop_loss_order: bool,
+    #[prost(bool, tag = "8")]
+    pub supports_good_till_date: bool,
+    #[prost(bool, tag = "9")]
+    pub supports_reduce_only: bool,
+    #[prost(bool, tag = "10")]
+    pub supports_quote_order_amount: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderTradeOption {
+    #[prost(bool, tag = "1")]
+    pub supports_trade_time: bool,
+    #[prost(bool, tag = "2")]
+    pub supports_trade_incremented_id: bool,
+    #[prost(bool, tag = "3")]
+    pub notification_on_each_currency_pair: bool,
+    #[prost(bool, tag = "4")]
+    pub supports_get_prints: bool,
+    #[prost(bool, tag = "5")]
+    pub supports_tick_direction: bool,
+    #[prost(bool, tag = "6")]
+    pub supports_my_trades_from_time: bool,
+    #[prost(bool, tag = "7")]
+    pub supports_trades_backfill: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct WebSocketOptions {
+    #[prost(bool, tag = "1")]
+    pub execution_notification: bool,
+    #[prost(bool, tag = "2")]
+    pub cancellation_notification: bool,
+    #[prost(bool, tag = "3")]
+    pub supports_ping_pong: bool,
+    #[prost(bool, tag = "4")]
+    pub supports_subscription_response: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CreateOrderResult {
+    #[prost(oneof = "create_order_result::Outcome", tags = "1, 2")]
+    pub outcome: Option<create_order_result::Outcome>,
+    #[prost(int32, tag = "3")]
+    pub source_type: i32,
+}
+
+pub mod create_order_result {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Outcome {
+        #[prost(string, tag = "1")]
+        ExchangeOrderId(String),
+        #[prost(message, tag = "2")]
+        Error(super::ExchangeError),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CancelOrderResult {
+    #[prost(oneof = "cancel_order_result::Outcome", tags = "1, 2")]
+    pub outcome: Option<cancel_order_result::Outcome>,
+    #[prost(int32, tag = "3")]
+    pub source_type: i32,
+    #[prost(string, optional, tag = "4")]
+    pub filled_amount: Option<String>,
+}
+
+pub mod cancel_order_result {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Outcome {
+        #[prost(string, tag = "1")]
+        ClientOrderId(String),
+        #[prost(message, tag = "2")]
+        Error(super::ExchangeError),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderInfos {
+    #[prost(message, repeated, tag = "1")]
+    pub orders: Vec<OrderInfo>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderInfo {
+    #[prost(string, tag = "1")]
+    pub currency_pair: String,
+    #[prost(string, tag = "2")]
+    pub exchange_order_id: String,
+    #[prost(string, tag = "3")]
+    pub client_order_id: String,
+    #[prost(int32, tag = "4")]
+    pub order_side: i32,
+    #[prost(int32, tag = "5")]
+    pub order_status: i32,
+    #[prost(string, tag = "6")]
+    pub price: String,
+    #[prost(string, tag = "7")]
+    pub amount: String,
+    #[prost(string, tag = "8")]
+    pub average_fill_price: String,
+    #[prost(string, tag = "9")]
+    pub filled_amount: String,
+    #[prost(string, optional, tag = "10")]
+    pub commission_currency_code: Option<String>,
+    #[prost(string, optional, tag = "11")]
+    pub commission_rate: Option<String>,
+    #[prost(string, optional, tag = "12")]
+    pub commission_amount: Option<String>,
+    #[prost(string, optional, tag = "13")]
+    pub extension_data: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExchangeBalancesAndPositions {
+    #[prost(message, repeated, tag = "1")]
+    pub balances: Vec<ExchangeBalance>,
+    /// Not set for exchanges without derivatives
+    #[prost(message, optional, tag = "2")]
+    pub positions: Option<DerivativePositions>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExchangeBalance {
+    #[prost(string, tag = "1")]
+    pub currency_code: String,
+    #[prost(string, tag = "2")]
+    pub balance: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DerivativePositions {
+    #[prost(message, repeated, tag = "1")]
+    pub positions: Vec<DerivativePosition>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MyTrades {
+    #[prost(oneof = "my_trades::Outcome", tags = "1, 2")]
+    pub outcome: Option<my_trades::Outcome>,
+}
+
+pub mod my_trades {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Outcome {
+        #[prost(message, tag = "1")]
+        Trades(super::OrderTrades),
+        #[prost(message, tag = "2")]
+        Error(super::ExchangeError),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderTrades {
+    #[prost(message, repeated, tag = "1")]
+    pub trades: Vec<OrderTrade>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderTrade {
+    #[prost(string, tag = "1")]
+    pub exchange_order_id: String,
+    #[prost(message, optional, tag = "2")]
+    pub trade_id: Option<TradeId>,
+    #[prost(int64, tag = "3")]
+    pub datetime: i64,
+    #[prost(string, tag = "4")]
+    pub price: String,
+    #[prost(string, tag = "5")]
+    pub amount: String,
+    #[prost(int32, tag = "6")]
+    pub order_role: i32,
+    #[prost(string, tag = "7")]
+    pub fee_currency_code: String,
+    #[prost(string, optional, tag = "8")]
+    pub fee_rate: Option<String>,
+    #[prost(string, optional, tag = "9")]
+    pub fee_amount: Option<String>,
+    #[prost(int32, tag = "10")]
+    pub fill_type: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TradeId {
+    #[prost(oneof = "trade_id::Id", tags = "1, 2")]
+    pub id: Option<trade_id::Id>,
+}
+
+pub mod trade_id {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Id {
+        #[prost(uint64, tag = "1")]
+        Number(u64),
+        #[prost(string, tag = "2")]
+        String(String),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ConnectorSymbols {
+    #[prost(message, repeated, tag = "1")]
+    pub symbols: Vec<Symbol>,
+    #[prost(message, repeated, tag = "2")]
+    pub specific_currency_pairs: Vec<SpecificCurrencyPairEntry>,
+    #[prost(message, repeated, tag = "3")]
+    pub supported_currencies: Vec<SupportedCurrency>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SpecificCurrencyPairEntry {
+    #[prost(string, tag = "1")]
+    pub currency_pair: String,
+    #[prost(string, tag = "2")]
+    pub specific_currency_pair: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SupportedCurrency {
+    #[prost(string, tag = "1")]
+    pub currency_id: String,
+    #[prost(string, tag = "2")]
+    pub currency_code: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Symbol {
+    #[prost(bool, tag = "1")]
+    pub is_active: bool,
+    #[prost(bool, tag = "2")]
+    pub is_derivative: bool,
+    #[prost(string, tag = "3")]
+    pub base_currency_id: String,
+    #[prost(string, tag = "4")]
+    pub base_currency_code: String,
+    #[prost(string, tag = "5")]
+    pub quote_currency_id: String,
+    #[prost(string, tag = "6")]
+    pub quote_currency_code: String,
+    #[prost(string, optional, tag = "7")]
+    pub min_price: Option<String>,
+    #[prost(string, optional, tag = "8")]
+    pub max_price: Option<String>,
+    #[prost(string, optional, tag = "9")]
+    pub min_amount: Option<String>,
+    #[prost(string, optional, tag = "10")]
+    pub max_amount: Option<String>,
+    #[prost(string, optional, tag = "11")]
+    pub min_cost: Option<String>,
+    #[prost(string, tag = "12")]
+    pub amount_currency_code: String,
+    #[prost(string, optional, tag = "13")]
+    pub balance_currency_code: Option<String>,
+    #[prost(string, tag = "14")]
+    pub amount_multiplier: String,
+    #[prost(message, optional, tag = "15")]
+    pub price_precision: Option<Precision>,
+    #[prost(message, optional, tag = "16")]
+    pub amount_precision: Option<Precision>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Precision {
+    #[prost(oneof = "precision::Kind", tags = "1, 2")]
+    pub kind: Option<precision::Kind>,
+}
+
+pub mod precision {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Kind {
+        #[prost(string, tag = "1")]
+        Tick(String),
+        #[prost(uint32, tag = "2")]
+        Mantissa(u32),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ActivePositions {
+    #[prost(message, repeated, tag = "1")]
+    pub positions: Vec<ActivePosition>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ClosedPosition {
+    #[prost(string, tag = "1")]
+    pub exchange_order_id: String,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Notification {
+    #[prost(oneof = "notification::Kind", tags = "1, 2, 3, 4, 5, 6, 7")]
+    pub kind: Option<notification::Kind>,
+}
+
+pub mod notification {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Kind {
+        #[prost(message, tag = "1")]
+        TradedSpecificCurrencies(super::SpecificCurrencyPairs),
+        #[prost(message, tag = "2")]
+        WebsocketsDisconnected(super::Empty),
+        #[prost(message, tag = "3")]
+        OrderCreated(super::OrderEvent),
+        #[prost(message, tag = "4")]
+        OrderCancelled(super::OrderEvent),
+        #[prost(message, tag = "5")]
+        OrderFilled(super::FillEvent),
+        #[prost(message, tag = "6")]
+        Trade(super::Trade),
+        #[prost(message, tag = "7")]
+        OrderBook(super::OrderBook),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderEvent {
+    #[prost(string, tag = "1")]
+    pub client_order_id: String,
+    #[prost(string, tag = "2")]
+    pub exchange_order_id: String,
+    #[prost(int32, tag = "3")]
+    pub source_type: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FillEvent {
+    #[prost(int32, tag = "1")]
+    pub source_type: i32,
+    #[prost(message, optional, tag = "2")]
+    pub trade_id: Option<TradeId>,
+    #[prost(string, optional, tag = "3")]
+    pub client_order_id: Option<String>,
+    #[prost(string, tag = "4")]
+    pub exchange_order_id: String,
+    #[prost(string, tag = "5")]
+    pub fill_price: String,
+    #[prost(oneof = "fill_event::FillAmount", tags = "6, 7")]
+    pub fill_amount: Option<fill_event::FillAmount>,
+    #[prost(int32, optional, tag = "8")]
+    pub order_role: Option<i32>,
+    #[prost(string, optional, tag = "9")]
+    pub commission_currency_code: Option<String>,
+    #[prost(string, optional, tag = "10")]
+    pub commission_rate: Option<String>,
+    #[prost(string, optional, tag = "11")]
+    pub commission_amount: Option<String>,
+    #[prost(int32, tag = "12")]
+    pub fill_type: i32,
+    #[prost(message, optional, tag = "13")]
+    pub special_order_data: Option<SpecialOrderData>,
+    #[prost(int64, optional, tag = "14")]
+    pub fill_date: Option<i64>,
+}
+
+pub mod fill_event {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum FillAmount {
+        #[prost(message, tag = "6")]
+        Incremental(super::IncrementalFill),
+        /// Total filled amount of order
+        #[prost(string, tag = "7")]
+        Total(String),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct IncrementalFill {
+    #[prost(string, tag = "1")]
+    pub fill_amount: String,
+    #[prost(string, optional, tag = "2")]
+    pub total_filled_amount: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SpecialOrderData {
+    #[prost(string, tag = "1")]
+    pub currency_pair: String,
+    #[prost(int32, tag = "2")]
+    pub order_side: i32,
+    #[prost(string, tag = "3")]
+    pub order_amount: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Trade {
+    #[prost(string, tag = "1")]
+    pub currency_pair: String,
+    #[prost(message, optional, tag = "2")]
+    pub trade_id: Option<TradeId>,
+    #[prost(string, tag = "3")]
+    pub price: String,
+    #[prost(string, tag = "4")]
+    pub amount: String,
+    #[prost(int32, tag = "5")]
+    pub side: i32,
+    #[prost(int64, tag = "6")]
+    pub transaction_time: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderBook {
+    #[prost(int64, tag = "1")]
+    pub creation_time: i64,
+    #[prost(string, tag = "2")]
+    pub currency_pair: String,
+    #[prost(bool, tag = "3")]
+    pub is_snapshot: bool,
+    #[prost(message, repeated, tag = "4")]
+    pub asks: Vec<PriceLevel>,
+    #[prost(message, repeated, tag = "5")]
+    pub bids: Vec<PriceLevel>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PriceLevel {
+    #[prost(string, tag = "1")]
+    pub price: String,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
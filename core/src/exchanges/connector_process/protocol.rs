@@ -0,0 +1,1914 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{TimeZone, Utc};
+use hyper::StatusCode;
+use itertools::Itertools;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use prost::Message;
+use rust_decimal::Decimal;
+use serde::de::IntoDeserializer;
+use serde::Deserialize;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::sync::mpsc;
+
+use crate::connectivity::WebSocketRole;
+use crate::exchanges::common::{
+    ActivePosition, ActivePositionId, Amount, ClosedPosition, CurrencyCode, CurrencyId,
+    CurrencyPair, ExchangeError, ExchangeErrorType, Price, SpecificCurrencyPair,
+};
+use crate::exchanges::connector_process::proto;
+use crate::exchanges::events::{
+    AllowedEventSourceType, ExchangeBalance, ExchangeBalancesAndPositions, TradeId,
+};
+use crate::exchanges::general::exchange::RequestResult;
+use crate::exchanges::general::features::{
+    BalancePositionOption, ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption,
+    RestFillsFeatures, RestFillsType, WebSocketOptions,
+};
+use crate::exchanges::general::handlers::handle_order_filled::{
+    FillAmount, FillEvent, SpecialOrderData,
+};
+use crate::exchanges::general::order::cancel::CancelOrderResult;
+use crate::exchanges::general::order::create::CreateOrderResult;
+use crate::exchanges::general::order::get_order_trades::OrderTrade;
+use crate::exchanges::general::symbol::{Precision, Symbol};
+use crate::infrastructure::spawn_future_ok;
+use crate::misc::derivative_position::DerivativePosition;
+use crate::orders::fill::{EventSourceType, OrderFillType};
+use crate::orders::order::{
+    ClientOrderId, ExchangeOrderId, OrderCancelling, OrderExecutionType, OrderHeader, OrderInfo,
+    OrderInfoExtensionData, OrderRole, OrderSide, OrderSimpleProps, OrderSnapshot, OrderStatus,
+    OrderType, ReservationId,
+};
+use crate::settings::ExchangeSettings;
+
+/// Frames longer than this are treated as corrupted stream
+pub const MAX_FRAME_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Message between engine and connector process. Frame is protobuf message:
+///
+/// ```protobuf
+/// message Frame {
+///     uint64 request_id = 1;
+///     oneof body {
+///         Request request = 2;
+///         Response response = 3;
+///         Notification notification = 4;
+///     }
+/// }
+/// ```
+///
+/// Messages of body are defined in `proto` module. Frames are prefixed by their length as
+/// big-endian u32
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Frame {
+    /// Response has id of its request. Notifications don't have id
+    #[prost(uint64, tag = "1")]
+    pub request_id: u64,
+    #[prost(oneof = "FrameBody", tags = "2, 3, 4")]
+    pub body: Option<FrameBody>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum FrameBody {
+    #[prost(message, tag = "2")]
+    Request(proto::Request),
+    #[prost(message, tag = "3")]
+    Response(proto::Response),
+    #[prost(message, tag = "4")]
+    Notification(proto::Notification),
+}
+
+/// Decoded body of frame
+pub enum FrameMessage {
+    Request(ConnectorRequest),
+    /// Response which can't be converted to domain types is parsing error of request
+    Response(ConnectorResult),
+    Notification(ConnectorNotification),
+}
+
+impl Frame {
+    pub fn request(request_id: u64, request: &ConnectorRequest) -> Result<Self> {
+        Ok(Frame {
+            request_id,
+            body: Some(FrameBody::Request(request.to_proto()?)),
+        })
+    }
+
+    /// Response which can't be converted to protobuf is sent as error of request
+    pub fn response(request_id: u64, response: &ConnectorResult) -> Self {
+        let response = response_to_proto(response).unwrap_or_else(|error| {
+            log::error!("Unable to convert response of connector to protobuf: {error:?}");
+            let error = ExchangeError::parsing(format!(
+                "Unable to convert response of connector to protobuf: {error}"
+            ));
+            proto::Response {
+                kind: Some(proto::response::Kind::Error(error_to_proto(&error))),
+            }
+        });
+
+        Frame {
+            request_id,
+            body: Some(FrameBody::Response(response)),
+        }
+    }
+
+    pub fn notification(notification: &ConnectorNotification) -> Result<Self> {
+        Ok(Frame {
+            request_id: 0,
+            body: Some(FrameBody::Notification(notification.to_proto()?)),
+        })
+    }
+
+    pub fn into_message(self) -> Result<FrameMessage> {
+        Ok(match self.body {
+            Some(FrameBody::Request(request)) => {
+                FrameMessage::Request(ConnectorRequest::from_proto(request)?)
+            }
+            Some(FrameBody::Response(response)) => {
+                FrameMessage::Response(response_from_proto(response).unwrap_or_else(|error| {
+                    Err(ExchangeError::parsing(format!(
+                        "Unable to parse response of connector process: {error:?}"
+                    )))
+                }))
+            }
+            Some(FrameBody::Notification(notification)) => {
+                FrameMessage::Notification(ConnectorNotification::from_proto(notification)?)
+            }
+            None => bail!("Frame {} doesn't have body", self.request_id),
+        })
+    }
+
+    pub fn encode_to_bytes(&self) -> Vec<u8> {
+        self.encode_to_vec()
+    }
+
+    /// Unknown fields are skipped, so newer peer can add fields to messages
+    pub fn decode_from_bytes(bytes: &[u8]) -> Result<Self> {
+        let frame = Frame::decode(bytes).context("Unable to decode frame")?;
+        if frame.body.is_none() {
+            bail!("Frame {} doesn't have body", frame.request_id);
+        }
+
+        Ok(frame)
+    }
+}
+
+pub async fn write_frame(writer: &mut (impl AsyncWrite + Unpin), frame: &Frame) -> Result<()> {
+    let bytes = frame.encode_to_bytes();
+    if bytes.len() > MAX_FRAME_LENGTH {
+        bail!("Frame of length {} is too long", bytes.len());
+    }
+
+    writer.write_u32(bytes.len() as u32).await?;
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Returns `None` if connection is closed between frames
+pub async fn read_frame(reader: &mut (impl AsyncRead + Unpin)) -> Result<Option<Frame>> {
+    let length = match reader.read_u32().await {
+        Ok(length) => length as usize,
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    if length > MAX_FRAME_LENGTH {
+        bail!("Frame of length {length} is too long");
+    }
+
+    let mut bytes = vec![0; length];
+    let _ = reader
+        .read_exact(&mut bytes)
+        .await
+        .context("Connection is closed inside of frame")?;
+
+    Frame::decode_from_bytes(&bytes).map(Some)
+}
+
+/// Frames are written to socket by separate future, so they can be sent from sync callbacks.
+/// Writer is stopped when connection is broken or all senders are dropped
+pub fn start_frames_writer(mut writer: OwnedWriteHalf) -> mpsc::UnboundedSender<Frame> {
+    let (frames_sender, mut frames_receiver) = mpsc::unbounded_channel::<Frame>();
+    let _ = spawn_future_ok(
+        "Connector process frames writer",
+        SpawnFutureFlags::STOP_BY_TOKEN,
+        async move {
+            while let Some(frame) = frames_receiver.recv().await {
+                if let Err(error) = write_frame(&mut writer, &frame).await {
+                    log::warn!("Unable to write frame to connector process connection: {error:?}");
+                    return;
+                }
+            }
+        },
+    );
+
+    frames_sender
+}
+
+/// Request of engine to connector process
+#[derive(Debug)]
+pub enum ConnectorRequest {
+    /// The first request after connector process is connected
+    Init {
+        settings: Box<ExchangeSettings>,
+        /// Set when connector process is restarted, so connector restores its symbols and traded
+        /// currencies before it serves other requests
+        traded_specific_currencies: Option<Vec<SpecificCurrencyPair>>,
+    },
+    /// Connectors get header, price, status, exchange order id and extension data of order
+    CreateOrder {
+        order: OrderSnapshot,
+    },
+    CancelOrder {
+        order: OrderCancelling,
+    },
+    CancelAllOrders {
+        currency_pair: CurrencyPair,
+    },
+    GetOpenOrders {
+        currency_pair: Option<CurrencyPair>,
+    },
+    GetOrderInfo {
+        order: OrderSnapshot,
+    },
+    GetBalance {
+        is_spot: bool,
+    },
+    GetMyTrades {
+        currency_pair: CurrencyPair,
+        last_date_time: Option<DateTime>,
+    },
+    BuildAllSymbols,
+    /// Websockets of exchange are opened and kept by connector process
+    ConnectWebsockets {
+        is_market_data_only: bool,
+    },
+    GetActivePositions,
+    ClosePosition {
+        position: ActivePosition,
+        price: Option<Price>,
+    },
+}
+
+/// Value returned by connector process for `ConnectorRequest`
+pub enum ConnectorResponse {
+    Info(ConnectorInfo),
+    CreateOrder(CreateOrderResult),
+    CancelOrder(CancelOrderResult),
+    /// Response to requests without value
+    Done,
+    Orders(Vec<OrderInfo>),
+    OrderInfo(OrderInfo),
+    Balance(ExchangeBalancesAndPositions),
+    MyTrades(RequestResult<Vec<OrderTrade>>),
+    Symbols(ConnectorSymbols),
+    ActivePositions(Vec<ActivePosition>),
+    ClosedPosition(ClosedPosition),
+}
+
+/// Response of connector process or its error
+pub type ConnectorResult = Result<ConnectorResponse, ExchangeError>;
+
+macro_rules! impl_response_value {
+    ($($variant:ident($value:ty)),+ $(,)?) => {
+        $(
+            impl TryFrom<ConnectorResponse> for $value {
+                type Error = ExchangeError;
+
+                fn try_from(response: ConnectorResponse) -> Result<Self, ExchangeError> {
+                    match response {
+                        ConnectorResponse::$variant(value) => Ok(value),
+                        _ => Err(unexpected_response(stringify!($variant))),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_response_value!(
+    Info(ConnectorInfo),
+    CreateOrder(CreateOrderResult),
+    CancelOrder(CancelOrderResult),
+    Orders(Vec<OrderInfo>),
+    OrderInfo(OrderInfo),
+    Balance(ExchangeBalancesAndPositions),
+    MyTrades(RequestResult<Vec<OrderTrade>>),
+    Symbols(ConnectorSymbols),
+    ActivePositions(Vec<ActivePosition>),
+    ClosedPosition(ClosedPosition),
+);
+
+impl TryFrom<ConnectorResponse> for () {
+    type Error = ExchangeError;
+
+    fn try_from(response: ConnectorResponse) -> Result<Self, ExchangeError> {
+        match response {
+            ConnectorResponse::Done => Ok(()),
+            _ => Err(unexpected_response("Done")),
+        }
+    }
+}
+
+fn unexpected_response(expected: &str) -> ExchangeError {
+    ExchangeError::parsing(format!(
+        "Connector process responded by unexpected value instead of {expected}"
+    ))
+}
+
+/// Response of connector process to `ConnectorRequest::Init`
+pub struct ConnectorInfo {
+    pub features: ExchangeFeatures,
+    pub enabled_websockets: Vec<WebSocketRole>,
+}
+
+/// Response of connector process to `ConnectorRequest::BuildAllSymbols` with values which are
+/// requested by engine synchronously later
+#[derive(Debug)]
+pub struct ConnectorSymbols {
+    pub symbols: Vec<Symbol>,
+    pub specific_currency_pairs: Vec<(CurrencyPair, SpecificCurrencyPair)>,
+    pub supported_currencies: Vec<(CurrencyId, CurrencyCode)>,
+}
+
+/// Message without response. Engine sends traded currencies to connector process, connector
+/// process sends callbacks of connector, market data and state of its websockets to engine
+#[derive(Debug)]
+pub enum ConnectorNotification {
+    TradedSpecificCurrencies(Vec<SpecificCurrencyPair>),
+    /// Websockets of connector process are disconnected, so engine should connect them again
+    WebsocketsDisconnected,
+    OrderCreated {
+        client_order_id: ClientOrderId,
+        exchange_order_id: ExchangeOrderId,
+        source_type: EventSourceType,
+    },
+    OrderCancelled {
+        client_order_id: ClientOrderId,
+        exchange_order_id: ExchangeOrderId,
+        source_type: EventSourceType,
+    },
+    OrderFilled(Box<FillEvent>),
+    Trade {
+        currency_pair: CurrencyPair,
+        trade_id: TradeId,
+        price: Price,
+        amount: Amount,
+        side: OrderSide,
+        transaction_time: DateTime,
+    },
+    OrderBook {
+        creation_time: DateTime,
+        currency_pair: CurrencyPair,
+        is_snapshot: bool,
+        asks: Vec<(Price, Amount)>,
+        bids: Vec<(Price, Amount)>,
+    },
+}
+
+/// Conversion between domain type and its protobuf message
+trait ProtoMessage: Sized {
+    type Proto;
+
+    fn to_proto(&self) -> Result<Self::Proto>;
+
+    /// Missing required fields and invalid values are errors
+    fn from_proto(proto: Self::Proto) -> Result<Self>;
+}
+
+/// Enum sent as `int32` field
+trait ProtoEnum: Sized {
+    fn to_proto(&self) -> i32;
+
+    fn from_proto(value: i32) -> Result<Self>;
+}
+
+macro_rules! impl_proto_enum {
+    ($enum:ident { $($variant:ident = $value:literal),+ $(,)? }) => {
+        impl ProtoEnum for $enum {
+            fn to_proto(&self) -> i32 {
+                match self {
+                    $($enum::$variant => $value,)+
+                }
+            }
+
+            fn from_proto(value: i32) -> Result<Self> {
+                match value {
+                    $($value => Ok($enum::$variant),)+
+                    _ => bail!("Unknown {} {value}", stringify!($enum)),
+                }
+            }
+        }
+    };
+}
+
+impl_proto_enum!(OrderSide { Buy = 1, Sell = 2 });
+impl_proto_enum!(OrderRole {
+    Maker = 1,
+    Taker = 2
+});
+impl_proto_enum!(OrderType {
+    Unknown = 0,
+    Limit = 1,
+    Market = 2,
+    StopLoss = 3,
+    TrailingStop = 4,
+    Liquidation = 5,
+    ClosePosition = 6,
+    MissedFill = 7,
+});
+impl_proto_enum!(OrderExecutionType {
+    None = 0,
+    MakerOnly = 1
+});
+impl_proto_enum!(OrderStatus {
+    Creating = 1,
+    Created = 2,
+    FailedToCreate = 3,
+    Canceling = 4,
+    Canceled = 5,
+    FailedToCancel = 6,
+    Completed = 7,
+});
+impl_proto_enum!(OrderFillType {
+    UserTrade = 1,
+    Liquidation = 2,
+    Funding = 3,
+    ClosePosition = 4,
+    Internal = 5,
+});
+impl_proto_enum!(EventSourceType {
+    RestFallback = 1,
+    Rest = 2,
+    WebSocket = 3,
+    Rpc = 4,
+});
+impl_proto_enum!(WebSocketRole {
+    Main = 0,
+    Secondary = 1
+});
+impl_proto_enum!(OpenOrdersType {
+    None = 0,
+    AllCurrencyPair = 1,
+    OneCurrencyPair = 2,
+});
+impl_proto_enum!(RestFillsType {
+    None = 0,
+    MyTrades = 1,
+    GetOrderInfo = 2,
+});
+impl_proto_enum!(BalancePositionOption {
+    NonDerivative = 0,
+    SingleRequest = 1,
+    IndividualRequests = 2,
+});
+impl_proto_enum!(AllowedEventSourceType {
+    All = 0,
+    FallbackOnly = 1,
+    NonFallback = 2,
+});
+
+fn required<T>(value: Option<T>, name: &str) -> Result<T> {
+    value.with_context(|| format!("Required field {name} is missing"))
+}
+
+fn decimal_from_proto(value: &str) -> Result<Decimal> {
+    Decimal::from_str(value).with_context(|| format!("Invalid decimal {value:?}"))
+}
+
+fn optional_decimal_to_proto(value: Option<Decimal>) -> Option<String> {
+    value.map(|x| x.to_string())
+}
+
+fn optional_decimal_from_proto(value: Option<String>) -> Result<Option<Decimal>> {
+    value.as_deref().map(decimal_from_proto).transpose()
+}
+
+fn time_to_proto(time: DateTime) -> i64 {
+    time.timestamp_nanos()
+}
+
+fn time_from_proto(nanos: i64) -> DateTime {
+    Utc.timestamp_nanos(nanos)
+}
+
+fn currency_pair_from_proto(value: &str) -> Result<CurrencyPair> {
+    CurrencyPair::deserialize(value.into_deserializer())
+        .map_err(|error: serde::de::value::Error| anyhow!("Invalid currency pair {value}: {error}"))
+}
+
+fn extension_data_to_proto(
+    extension_data: &Option<Box<dyn OrderInfoExtensionData>>,
+) -> Result<Option<String>> {
+    extension_data
+        .as_ref()
+        .map(|x| serde_json::to_string(x).context("Unable to serialize extension data of order"))
+        .transpose()
+}
+
+fn extension_data_from_proto(
+    extension_data: Option<String>,
+) -> Result<Option<Box<dyn OrderInfoExtensionData>>> {
+    extension_data
+        .map(|x| serde_json::from_str(&x).context("Unable to parse extension data of order"))
+        .transpose()
+}
+
+fn error_to_proto(error: &ExchangeError) -> proto::ExchangeError {
+    use ExchangeErrorType::*;
+    let (error_type, pending_delay) = match error.error_type {
+        Unknown => (0, None),
+        SendError => (1, None),
+        RateLimit => (2, None),
+        OrderNotFound => (3, None),
+        OrderCompleted => (4, None),
+        InsufficientFunds => (5, None),
+        InvalidOrder => (6, None),
+        Authentication => (7, None),
+        ParsingError => (8, None),
+        PendingError(delay) => (9, Some(delay)),
+        ServiceUnavailable => (10, None),
+        Unsupported => (11, None),
+    };
+
+    proto::ExchangeError {
+        error_type,
+        pending_delay_nanos: pending_delay.map(|x| x.as_nanos() as u64),
+        message: error.message.clone(),
+        code: error.code,
+    }
+}
+
+fn error_from_proto(error: proto::ExchangeError) -> Result<ExchangeError> {
+    use ExchangeErrorType::*;
+    let error_type = match error.error_type {
+        0 => Unknown,
+        1 => SendError,
+        2 => RateLimit,
+        3 => OrderNotFound,
+        4 => OrderCompleted,
+        5 => InsufficientFunds,
+        6 => InvalidOrder,
+        7 => Authentication,
+        8 => ParsingError,
+        9 => PendingError(Duration::from_nanos(required(
+            error.pending_delay_nanos,
+            "pending_delay_nanos",
+        )?)),
+        10 => ServiceUnavailable,
+        11 => Unsupported,
+        error_type => bail!("Unknown ExchangeErrorType {error_type}"),
+    };
+
+    Ok(ExchangeError::new(error_type, error.message, error.code))
+}
+
+fn response_to_proto(response: &ConnectorResult) -> Result<proto::Response> {
+    use proto::response::Kind;
+    let kind = match response {
+        Err(error) => Kind::Error(error_to_proto(error)),
+        Ok(ConnectorResponse::Info(info)) => Kind::Info(info.to_proto()?),
+        Ok(ConnectorResponse::CreateOrder(result)) => Kind::CreateOrder(result.to_proto()?),
+        Ok(ConnectorResponse::CancelOrder(result)) => Kind::CancelOrder(result.to_proto()?),
+        Ok(ConnectorResponse::Done) => Kind::Done(proto::Empty {}),
+        Ok(ConnectorResponse::Orders(orders)) => Kind::Orders(proto::OrderInfos {
+            orders: orders.iter().map(|x| x.to_proto()).try_collect()?,
+        }),
+        Ok(ConnectorResponse::OrderInfo(order)) => Kind::OrderInfo(order.to_proto()?),
+        Ok(ConnectorResponse::Balance(balance)) => Kind::Balance(balance.to_proto()?),
+        Ok(ConnectorResponse::MyTrades(trades)) => Kind::MyTrades(trades.to_proto()?),
+        Ok(ConnectorResponse::Symbols(symbols)) => Kind::Symbols(symbols.to_proto()?),
+        Ok(ConnectorResponse::ActivePositions(positions)) => {
+            Kind::ActivePositions(proto::ActivePositions {
+                positions: positions.iter().map(|x| x.to_proto()).try_collect()?,
+            })
+        }
+        Ok(ConnectorResponse::ClosedPosition(position)) => {
+            Kind::ClosedPosition(proto::ClosedPosition {
+                exchange_order_id: position.exchange_order_id().to_string(),
+                amount: position.amount().to_string(),
+            })
+        }
+    };
+
+    Ok(proto::Response { kind: Some(kind) })
+}
+
+fn response_from_proto(response: proto::Response) -> Result<ConnectorResult> {
+    use proto::response::Kind;
+    let response = match required(response.kind, "response")? {
+        Kind::Error(error) => return Ok(Err(error_from_proto(error)?)),
+        Kind::Info(info) => ConnectorResponse::Info(ConnectorInfo::from_proto(info)?),
+        Kind::CreateOrder(result) => {
+            ConnectorResponse::CreateOrder(CreateOrderResult::from_proto(result)?)
+        }
+        Kind::CancelOrder(result) => {
+            ConnectorResponse::CancelOrder(CancelOrderResult::from_proto(result)?)
+        }
+        Kind::Done(_) => ConnectorResponse::Done,
+        Kind::Orders(orders) => ConnectorResponse::Orders(
+            orders
+                .orders
+                .into_iter()
+                .map(OrderInfo::from_proto)
+                .try_collect()?,
+        ),
+        Kind::OrderInfo(order) => ConnectorResponse::OrderInfo(OrderInfo::from_proto(order)?),
+        Kind::Balance(balance) => {
+            ConnectorResponse::Balance(ExchangeBalancesAndPositions::from_proto(balance)?)
+        }
+        Kind::MyTrades(trades) => ConnectorResponse::MyTrades(RequestResult::from_proto(trades)?),
+        Kind::Symbols(symbols) => {
+            ConnectorResponse::Symbols(ConnectorSymbols::from_proto(symbols)?)
+        }
+        Kind::ActivePositions(positions) => ConnectorResponse::ActivePositions(
+            positions
+                .positions
+                .into_iter()
+                .map(ActivePosition::from_proto)
+                .try_collect()?,
+        ),
+        Kind::ClosedPosition(position) => ConnectorResponse::ClosedPosition(ClosedPosition::new(
+            position.exchange_order_id.as_str().into(),
+            decimal_from_proto(&position.amount)?,
+        )),
+    };
+
+    Ok(Ok(response))
+}
+
+impl ProtoMessage for ConnectorRequest {
+    type Proto = proto::Request;
+
+    fn to_proto(&self) -> Result<proto::Request> {
+        use proto::request::Kind;
+        let kind = match self {
+            ConnectorRequest::Init {
+                settings,
+                traded_specific_currencies,
+            } => Kind::Init(proto::Init {
+                settings_toml: toml_edit::ser::to_string(settings)
+                    .context("Unable to serialize settings of exchange")?,
+                traded_specific_currencies: traded_specific_currencies
+                    .as_ref()
+                    .map(|x| specific_currency_pairs_to_proto(x)),
+            }),
+            ConnectorRequest::CreateOrder { order } => Kind::CreateOrder(order.to_proto()?),
+            ConnectorRequest::CancelOrder { order } => Kind::CancelOrder(order.to_proto()?),
+            ConnectorRequest::CancelAllOrders { currency_pair } => {
+                Kind::CancelAllOrders(proto::CancelAllOrders {
+                    currency_pair: currency_pair.to_string(),
+                })
+            }
+            ConnectorRequest::GetOpenOrders { currency_pair } => {
+                Kind::GetOpenOrders(proto::GetOpenOrders {
+                    currency_pair: currency_pair.map(|x| x.to_string()),
+                })
+            }
+            ConnectorRequest::GetOrderInfo { order } => Kind::GetOrderInfo(order.to_proto()?),
+            ConnectorRequest::GetBalance { is_spot } => {
+                Kind::GetBalance(proto::GetBalance { is_spot: *is_spot })
+            }
+            ConnectorRequest::GetMyTrades {
+                currency_pair,
+                last_date_time,
+            } => Kind::GetMyTrades(proto::GetMyTrades {
+                currency_pair: currency_pair.to_string(),
+                last_date_time: last_date_time.map(time_to_proto),
+            }),
+            ConnectorRequest::BuildAllSymbols => Kind::BuildAllSymbols(proto::Empty {}),
+            ConnectorRequest::ConnectWebsockets {
+                is_market_data_only,
+            } => Kind::ConnectWebsockets(proto::ConnectWebsockets {
+                is_market_data_only: *is_market_data_only,
+            }),
+            ConnectorRequest::GetActivePositions => Kind::GetActivePositions(proto::Empty {}),
+            ConnectorRequest::ClosePosition { position, price } => {
+                Kind::ClosePosition(proto::ClosePosition {
+                    position: Some(position.to_proto()?),
+                    price: optional_decimal_to_proto(*price),
+                })
+            }
+        };
+
+        Ok(proto::Request { kind: Some(kind) })
+    }
+
+    fn from_proto(request: proto::Request) -> Result<Self> {
+        use proto::request::Kind;
+        Ok(match required(request.kind, "request")? {
+            Kind::Init(init) => ConnectorRequest::Init {
+                settings: Box::new(
+                    toml_edit::de::from_str(&init.settings_toml)
+                        .context("Unable to parse settings of exchange")?,
+                ),
+                traded_specific_currencies: init
+                    .traded_specific_currencies
+                    .map(specific_currency_pairs_from_proto),
+            },
+            Kind::CreateOrder(order) => ConnectorRequest::CreateOrder {
+                order: OrderSnapshot::from_proto(order)?,
+            },
+            Kind::CancelOrder(order) => ConnectorRequest::CancelOrder {
+                order: OrderCancelling::from_proto(order)?,
+            },
+            Kind::CancelAllOrders(request) => ConnectorRequest::CancelAllOrders {
+                currency_pair: currency_pair_from_proto(&request.currency_pair)?,
+            },
+            Kind::GetOpenOrders(request) => ConnectorRequest::GetOpenOrders {
+                currency_pair: request
+                    .currency_pair
+                    .as_deref()
+                    .map(currency_pair_from_proto)
+                    .transpose()?,
+            },
+            Kind::GetOrderInfo(order) => ConnectorRequest::GetOrderInfo {
+                order: OrderSnapshot::from_proto(order)?,
+            },
+            Kind::GetBalance(request) => ConnectorRequest::GetBalance {
+                is_spot: request.is_spot,
+            },
+            Kind::GetMyTrades(request) => ConnectorRequest::GetMyTrades {
+                currency_pair: currency_pair_from_proto(&request.currency_pair)?,
+                last_date_time: request.last_date_time.map(time_from_proto),
+            },
+            Kind::BuildAllSymbols(_) => ConnectorRequest::BuildAllSymbols,
+            Kind::ConnectWebsockets(request) => ConnectorRequest::ConnectWebsockets {
+                is_market_data_only: request.is_market_data_only,
+            },
+            Kind::GetActivePositions(_) => ConnectorRequest::GetActivePositions,
+            Kind::ClosePosition(request) => ConnectorRequest::ClosePosition {
+                position: ActivePosition::from_proto(required(request.position, "position")?)?,
+                price: optional_decimal_from_proto(request.price)?,
+            },
+        })
+    }
+}
+
+impl ProtoMessage for ConnectorNotification {
+    type Proto = proto::Notification;
+
+    fn to_proto(&self) -> Result<proto::Notification> {
+        use proto::notification::Kind;
+        let kind = match self {
+            ConnectorNotification::TradedSpecificCurrencies(currencies) => {
+                Kind::TradedSpecificCurrencies(specific_currency_pairs_to_proto(currencies))
+            }
+            ConnectorNotification::WebsocketsDisconnected => {
+                Kind::WebsocketsDisconnected(proto::Empty {})
+            }
+            ConnectorNotification::OrderCreated {
+                client_order_id,
+                exchange_order_id,
+                source_type,
+            } => Kind::OrderCreated(proto::OrderEvent {
+                client_order_id: client_order_id.to_string(),
+                exchange_order_id: exchange_order_id.to_string(),
+                source_type: source_type.to_proto(),
+            }),
+            ConnectorNotification::OrderCancelled {
+                client_order_id,
+                exchange_order_id,
+                source_type,
+            } => Kind::OrderCancelled(proto::OrderEvent {
+                client_order_id: client_order_id.to_string(),
+                exchange_order_id: exchange_order_id.to_string(),
+                source_type: source_type.to_proto(),
+            }),
+            ConnectorNotification::OrderFilled(fill_event) => {
+                Kind::OrderFilled(fill_event.as_ref().to_proto()?)
+            }
+            ConnectorNotification::Trade {
+                currency_pair,
+                trade_id,
+                price,
+                amount,
+                side,
+                transaction_time,
+            } => Kind::Trade(proto::Trade {
+                currency_pair: currency_pair.to_string(),
+                trade_id: Some(trade_id.to_proto()?),
+                price: price.to_string(),
+                amount: amount.to_string(),
+                side: side.to_proto(),
+                transaction_time: time_to_proto(*transaction_time),
+            }),
+            ConnectorNotification::OrderBook {
+                creation_time,
+                currency_pair,
+                is_snapshot,
+                asks,
+                bids,
+            } => Kind::OrderBook(proto::OrderBook {
+                creation_time: time_to_proto(*creation_time),
+                currency_pair: currency_pair.to_string(),
+                is_snapshot: *is_snapshot,
+                asks: price_levels_to_proto(asks),
+                bids: price_levels_to_proto(bids),
+            }),
+        };
+
+        Ok(proto::Notification { kind: Some(kind) })
+    }
+
+    fn from_proto(notification: proto::Notification) -> Result<Self> {
+        use proto::notification::Kind;
+        Ok(match required(notification.kind, "notification")? {
+            Kind::TradedSpecificCurrencies(currencies) => {
+                ConnectorNotification::TradedSpecificCurrencies(specific_currency_pairs_from_proto(
+                    currencies,
+                ))
+            }
+            Kind::WebsocketsDisconnected(_) => ConnectorNotification::WebsocketsDisconnected,
+            Kind::OrderCreated(event) => ConnectorNotification::OrderCreated {
+                client_order_id: event.client_order_id.as_str().into(),
+                exchange_order_id: event.exchange_order_id.as_str().into(),
+                source_type: EventSourceType::from_proto(event.source_type)?,
+            },
+            Kind::OrderCancelled(event) => ConnectorNotification::OrderCancelled {
+                client_order_id: event.client_order_id.as_str().into(),
+                exchange_order_id: event.exchange_order_id.as_str().into(),
+                source_type: EventSourceType::from_proto(event.source_type)?,
+            },
+            Kind::OrderFilled(fill_event) => {
+                ConnectorNotification::OrderFilled(Box::new(FillEvent::from_proto(fill_event)?))
+            }
+            Kind::Trade(trade) => ConnectorNotification::Trade {
+                currency_pair: currency_pair_from_proto(&trade.currency_pair)?,
+                trade_id: TradeId::from_proto(required(trade.trade_id, "trade_id")?)?,
+                price: decimal_from_proto(&trade.price)?,
+                amount: decimal_from_proto(&trade.amount)?,
+                side: OrderSide::from_proto(trade.side)?,
+                transaction_time: time_from_proto(trade.transaction_time),
+            },
+            Kind::OrderBook(order_book) => ConnectorNotification::OrderBook {
+                creation_time: time_from_proto(order_book.creation_time),
+                currency_pair: currency_pair_from_proto(&order_book.currency_pair)?,
+                is_snapshot: order_book.is_snapshot,
+                asks: price_levels_from_proto(order_book.asks)?,
+                bids: price_levels_from_proto(order_book.bids)?,
+            },
+        })
+    }
+}
+
+fn specific_currency_pairs_to_proto(
+    currencies: &[SpecificCurrencyPair],
+) -> proto::SpecificCurrencyPairs {
+    proto::SpecificCurrencyPairs {
+        values: currencies.iter().map(|x| x.to_string()).collect_vec(),
+    }
+}
+
+fn specific_currency_pairs_from_proto(
+    currencies: proto::SpecificCurrencyPairs,
+) -> Vec<SpecificCurrencyPair> {
+    currencies
+        .values
+        .iter()
+        .map(|x| x.as_str().into())
+        .collect_vec()
+}
+
+fn price_levels_to_proto(levels: &[(Price, Amount)]) -> Vec<proto::PriceLevel> {
+    levels
+        .iter()
+        .map(|(price, amount)| proto::PriceLevel {
+            price: price.to_string(),
+            amount: amount.to_string(),
+        })
+        .collect_vec()
+}
+
+fn price_levels_from_proto(levels: Vec<proto::PriceLevel>) -> Result<Vec<(Price, Amount)>> {
+    levels
+        .iter()
+        .map(|x| {
+            Ok((
+                decimal_from_proto(&x.price)?,
+                decimal_from_proto(&x.amount)?,
+            ))
+        })
+        .try_collect()
+}
+
+impl ProtoMessage for Arc<OrderHeader> {
+    type Proto = proto::OrderHeader;
+
+    fn to_proto(&self) -> Result<proto::OrderHeader> {
+        Ok(proto::OrderHeader {
+            client_order_id: self.client_order_id.to_string(),
+            init_time: time_to_proto(self.init_time),
+            exchange_account_id: self.exchange_account_id.to_string(),
+            currency_pair: self.currency_pair.to_string(),
+            order_type: self.order_type.to_proto(),
+            side: self.side.to_proto(),
+            amount: self.amount.to_string(),
+            execution_type: self.execution_type.to_proto(),
+            reservation_id: self.reservation_id.map(|x| x.as_u64()),
+            signal_id: self.signal_id.clone(),
+            strategy_name: self.strategy_name.clone(),
+            quote_amount: optional_decimal_to_proto(self.quote_amount),
+            expire_time: self.expire_time.map(time_to_proto),
+            reduce_only: self.reduce_only,
+            latency_budget_nanos: self.latency_budget.map(|x| x.as_nanos() as u64),
+            skip_price_sanity_check: self.skip_price_sanity_check,
+        })
+    }
+
+    fn from_proto(header: proto::OrderHeader) -> Result<Self> {
+        let mut order_header = OrderHeader::new(
+            header.client_order_id.as_str().into(),
+            time_from_proto(header.init_time),
+            header.exchange_account_id.parse().map_err(|error| {
+                anyhow!(
+                    "Invalid exchange account id {}: {error:?}",
+                    header.exchange_account_id
+                )
+            })?,
+            currency_pair_from_proto(&header.currency_pair)?,
+            OrderType::from_proto(header.order_type)?,
+            OrderSide::from_proto(header.side)?,
+            decimal_from_proto(&header.amount)?,
+            OrderExecutionType::from_proto(header.execution_type)?,
+            header.reservation_id.map(ReservationId::from_u64),
+            header.signal_id,
+            header.strategy_name,
+        );
+
+        let fields = Arc::make_mut(&mut order_header);
+        fields.quote_amount = optional_decimal_from_proto(header.quote_amount)?;
+        fields.expire_time = header.expire_time.map(time_from_proto);
+        fields.reduce_only = header.reduce_only;
+        fields.latency_budget = header.latency_budget_nanos.map(Duration::from_nanos);
+        fields.skip_price_sanity_check = header.skip_price_sanity_check;
+
+        Ok(order_header)
+    }
+}
+
+impl ProtoMessage for OrderSnapshot {
+    type Proto = proto::Order;
+
+    fn to_proto(&self) -> Result<proto::Order> {
+        Ok(proto::Order {
+            header: Some(self.header.to_proto()?),
+            price: optional_decimal_to_proto(self.props.raw_price),
+            exchange_order_id: self.props.exchange_order_id.as_ref().map(|x| x.to_string()),
+            status: self.props.status.to_proto(),
+            extension_data: extension_data_to_proto(&self.extension_data)?,
+        })
+    }
+
+    fn from_proto(order: proto::Order) -> Result<Self> {
+        let mut props = OrderSimpleProps::from_price(optional_decimal_from_proto(order.price)?);
+        props.exchange_order_id = order.exchange_order_id.as_deref().map(Into::into);
+        props.status = OrderStatus::from_proto(order.status)?;
+
+        Ok(OrderSnapshot::new(
+            Arc::<OrderHeader>::from_proto(required(order.header, "header")?)?,
+            props,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            extension_data_from_proto(order.extension_data)?,
+        ))
+    }
+}
+
+impl ProtoMessage for OrderCancelling {
+    type Proto = proto::OrderCancelling;
+
+    fn to_proto(&self) -> Result<proto::OrderCancelling> {
+        Ok(proto::OrderCancelling {
+            header: Some(self.header.to_proto()?),
+            exchange_order_id: self.exchange_order_id.to_string(),
+            extension_data: extension_data_to_proto(&self.extension_data)?,
+        })
+    }
+
+    fn from_proto(order: proto::OrderCancelling) -> Result<Self> {
+        Ok(OrderCancelling {
+            header: Arc::<OrderHeader>::from_proto(required(order.header, "header")?)?,
+            exchange_order_id: order.exchange_order_id.as_str().into(),
+            extension_data: extension_data_from_proto(order.extension_data)?,
+        })
+    }
+}
+
+impl ProtoMessage for DerivativePosition {
+    type Proto = proto::DerivativePosition;
+
+    fn to_proto(&self) -> Result<proto::DerivativePosition> {
+        Ok(proto::DerivativePosition {
+            currency_pair: self.currency_pair.to_string(),
+            position: self.position.to_string(),
+            side: self.side.map(|x| x.to_proto()),
+            average_entry_price: self.average_entry_price.to_string(),
+            liquidation_price: self.liquidation_price.to_string(),
+            leverage: self.leverage.to_string(),
+        })
+    }
+
+    fn from_proto(position: proto::DerivativePosition) -> Result<Self> {
+        Ok(DerivativePosition::new(
+            currency_pair_from_proto(&position.currency_pair)?,
+            decimal_from_proto(&position.position)?,
+            position.side.map(OrderSide::from_proto).transpose()?,
+            decimal_from_proto(&position.average_entry_price)?,
+            decimal_from_proto(&position.liquidation_price)?,
+            decimal_from_proto(&position.leverage)?,
+        ))
+    }
+}
+
+impl ProtoMessage for ActivePosition {
+    type Proto = proto::ActivePosition;
+
+    fn to_proto(&self) -> Result<proto::ActivePosition> {
+        Ok(proto::ActivePosition {
+            id: self.id.to_string(),
+            status: self.status.as_u16().into(),
+            time_stamp: self
+                .time_stamp
+                .try_into()
+                .context("Time stamp of active position is too large")?,
+            pl: self.pl.to_string(),
+            derivative: Some(self.derivative.to_proto()?),
+        })
+    }
+
+    fn from_proto(position: proto::ActivePosition) -> Result<Self> {
+        Ok(ActivePosition {
+            id: ActivePositionId::from(position.id.as_str()),
+            status: u16::try_from(position.status)
+                .ok()
+                .and_then(|x| StatusCode::from_u16(x).ok())
+                .with_context(|| format!("Invalid status code {}", position.status))?,
+            time_stamp: position.time_stamp.into(),
+            pl: decimal_from_proto(&position.pl)?,
+            derivative: DerivativePosition::from_proto(required(
+                position.derivative,
+                "derivative",
+            )?)?,
+        })
+    }
+}
+
+impl ProtoMessage for ConnectorInfo {
+    type Proto = proto::ConnectorInfo;
+
+    fn to_proto(&self) -> Result<proto::ConnectorInfo> {
+        Ok(proto::ConnectorInfo {
+            features: Some(self.features.to_proto()?),
+            enabled_websockets: self
+                .enabled_websockets
+                .iter()
+                .map(|x| x.to_proto())
+                .collect_vec(),
+        })
+    }
+
+    fn from_proto(info: proto::ConnectorInfo) -> Result<Self> {
+        Ok(ConnectorInfo {
+            features: ExchangeFeatures::from_proto(required(info.features, "features")?)?,
+            enabled_websockets: info
+                .enabled_websockets
+                .into_iter()
+                .map(WebSocketRole::from_proto)
+                .try_collect()?,
+        })
+    }
+}
+
+impl ProtoMessage for ExchangeFeatures {
+    type Proto = proto::ExchangeFeatures;
+
+    fn to_proto(&self) -> Result<proto::ExchangeFeatures> {
+        let order_features = &self.order_features;
+        let trade_option = &self.trade_option;
+        let websocket_options = &self.websocket_options;
+        Ok(proto::ExchangeFeatures {
+            open_orders_type: self.open_orders_type.to_proto(),
+            rest_fills_type: self.rest_fills_features.fills_type.to_proto(),
+            order_features: Some(proto::OrderFeatures {
+                maker_only: order_features.maker_only,
+                supports_get_order_info_by_client_order_id: order_features
+                    .supports_get_order_info_by_client_order_id,
+                cancellation_response_from_rest_only_for_errors: order_features
+                    .cancellation_response_from_rest_only_for_errors,
+                creation_response_from_rest_only_for_errors: order_features
+                    .creation_response_from_rest_only_for_errors,
+                order_was_completed_error_for_cancellation: order_features
+                    .order_was_completed_error_for_cancellation,
+                supports_already_cancelled_order: order_features.supports_already_cancelled_order,
+                supports_stop_loss_order: order_features.supports_stop_loss_order,
+                supports_good_till_date: order_features.supports_good_till_date,
+                supports_reduce_only: order_features.supports_reduce_only,
+                supports_quote_order_amount: order_features.supports_quote_order_amount,
+            }),
+            trade_option: Some(proto::OrderTradeOption {
+                supports_trade_time: trade_option.supports_trade_time,
+                supports_trade_incremented_id: trade_option.supports_trade_incremented_id,
+                notification_on_each_currency_pair: trade_option.notification_on_each_currency_pair,
+                supports_get_prints: trade_option.supports_get_prints,
+                supports_tick_direction: trade_option.supports_tick_direction,
+                supports_my_trades_from_time: trade_option.supports_my_trades_from_time,
+                supports_trades_backfill: trade_option.supports_trades_backfill,
+            }),
+            websocket_options: Some(proto::WebSocketOptions {
+                execution_notification: websocket_options.execution_notification,
+                cancellation_notification: websocket_options.cancellation_notification,
+                supports_ping_pong: websocket_options.supports_ping_pong,
+                supports_subscription_response: websocket_options.supports_subscription_response,
+            }),
+            empty_response_is_ok: self.empty_response_is_ok,
+            balance_position_option: self.balance_position_option.to_proto(),
+            allowed_create_event_source_type: self.allowed_create_event_source_type.to_proto(),
+            allowed_fill_event_source_type: self.allowed_fill_event_source_type.to_proto(),
+            allowed_cancel_event_source_type: self.allowed_cancel_event_source_type.to_proto(),
+        })
+    }
+
+    fn from_proto(features: proto::ExchangeFeatures) -> Result<Self> {
+        let order_features = required(features.order_features, "order_features")?;
+        let trade_option = required(features.trade_option, "trade_option")?;
+        let websocket_options = required(features.websocket_options, "websocket_options")?;
+
+        let mut exchange_features = ExchangeFeatures::new(
+            OpenOrdersType::from_proto(features.open_orders_type)?,
+            RestFillsFeatures::new(RestFillsType::from_proto(features.rest_fills_type)?),
+            OrderFeatures::new(
+                order_features.maker_only,
+                order_features.supports_get_order_info_by_client_order_id,
+                order_features.cancellation_response_from_rest_only_for_errors,
+                order_features.creation_response_from_rest_only_for_errors,
+                order_features.order_was_completed_error_for_cancellation,
+                order_features.supports_already_cancelled_order,
+                order_features.supports_stop_loss_order,
+                order_features.supports_good_till_date,
+                order_features.supports_reduce_only,
+                order_features.supports_quote_order_amount,
+            ),
+            OrderTradeOption {
+                supports_trade_time: trade_option.supports_trade_time,
+                supports_trade_incremented_id: trade_option.supports_trade_incremented_id,
+                notification_on_each_currency_pair: trade_option.notification_on_each_currency_pair,
+                supports_get_prints: trade_option.supports_get_prints,
+                supports_tick_direction: trade_option.supports_tick_direction,
+                supports_my_trades_from_time: trade_option.supports_my_trades_from_time,
+                supports_trades_backfill: trade_option.supports_trades_backfill,
+            },
+            WebSocketOptions::new(
+                websocket_options.execution_notification,
+                websocket_options.cancellation_notification,
+                websocket_options.supports_ping_pong,
+                websocket_options.supports_subscription_response,
+            ),
+            features.empty_response_is_ok,
+            AllowedEventSourceType::from_proto(features.allowed_create_event_source_type)?,
+            AllowedEventSourceType::from_proto(features.allowed_fill_event_source_type)?,
+            AllowedEventSourceType::from_proto(features.allowed_cancel_event_source_type)?,
+        );
+        exchange_features.balance_position_option =
+            BalancePositionOption::from_proto(features.balance_position_option)?;
+
+        Ok(exchange_features)
+    }
+}
+
+impl ProtoMessage for CreateOrderResult {
+    type Proto = proto::CreateOrderResult;
+
+    fn to_proto(&self) -> Result<proto::CreateOrderResult> {
+        use proto::create_order_result::Outcome;
+        Ok(proto::CreateOrderResult {
+            outcome: Some(match &self.outcome {
+                RequestResult::Success(exchange_order_id) => {
+                    Outcome::ExchangeOrderId(exchange_order_id.to_string())
+                }
+                RequestResult::Error(error) => Outcome::Error(error_to_proto(error)),
+            }),
+            source_type: self.source_type.to_proto(),
+        })
+    }
+
+    fn from_proto(result: proto::CreateOrderResult) -> Result<Self> {
+        use proto::create_order_result::Outcome;
+        Ok(CreateOrderResult {
+            outcome: match required(result.outcome, "outcome")? {
+                Outcome::ExchangeOrderId(id) => RequestResult::Success(id.as_str().into()),
+                Outcome::Error(error) => RequestResult::Error(error_from_proto(error)?),
+            },
+            source_type: EventSourceType::from_proto(result.source_type)?,
+        })
+    }
+}
+
+impl ProtoMessage for CancelOrderResult {
+    type Proto = proto::CancelOrderResult;
+
+    fn to_proto(&self) -> Result<proto::CancelOrderResult> {
+        use proto::cancel_order_result::Outcome;
+        Ok(proto::CancelOrderResult {
+            outcome: Some(match &self.outcome {
+                RequestResult::Success(client_order_id) => {
+                    Outcome::ClientOrderId(client_order_id.to_string())
+                }
+                RequestResult::Error(error) => Outcome::Error(error_to_proto(error)),
+            }),
+            source_type: self.source_type.to_proto(),
+            filled_amount: optional_decimal_to_proto(self.filled_amount),
+        })
+    }
+
+    fn from_proto(result: proto::CancelOrderResult) -> Result<Self> {
+        use proto::cancel_order_result::Outcome;
+        Ok(CancelOrderResult {
+            outcome: match required(result.outcome, "outcome")? {
+                Outcome::ClientOrderId(id) => RequestResult::Success(id.as_str().into()),
+                Outcome::Error(error) => RequestResult::Error(error_from_proto(error)?),
+            },
+            source_type: EventSourceType::from_proto(result.source_type)?,
+            filled_amount: optional_decimal_from_proto(result.filled_amount)?,
+        })
+    }
+}
+
+impl ProtoMessage for OrderInfo {
+    type Proto = proto::OrderInfo;
+
+    fn to_proto(&self) -> Result<proto::OrderInfo> {
+        Ok(proto::OrderInfo {
+            currency_pair: self.currency_pair.to_string(),
+            exchange_order_id: self.exchange_order_id.to_string(),
+            client_order_id: self.client_order_id.to_string(),
+            order_side: self.order_side.to_proto(),
+            order_status: self.order_status.to_proto(),
+            price: self.price.to_string(),
+            amount: self.amount.to_string(),
+            average_fill_price: self.average_fill_price.to_string(),
+            filled_amount: self.filled_amount.to_string(),
+            commission_currency_code: self.commission_currency_code.clone(),
+            commission_rate: optional_decimal_to_proto(self.commission_rate),
+            commission_amount: optional_decimal_to_proto(self.commission_amount),
+            extension_data: extension_data_to_proto(&self.extension_data)?,
+        })
+    }
+
+    fn from_proto(order: proto::OrderInfo) -> Result<Self> {
+        Ok(OrderInfo {
+            currency_pair: currency_pair_from_proto(&order.currency_pair)?,
+            exchange_order_id: order.exchange_order_id.as_str().into(),
+            client_order_id: order.client_order_id.as_str().into(),
+            order_side: OrderSide::from_proto(order.order_side)?,
+            order_status: OrderStatus::from_proto(order.order_status)?,
+            price: decimal_from_proto(&order.price)?,
+            amount: decimal_from_proto(&order.amount)?,
+            average_fill_price: decimal_from_proto(&order.average_fill_price)?,
+            filled_amount: decimal_from_proto(&order.filled_amount)?,
+            commission_currency_code: order.commission_currency_code,
+            commission_rate: optional_decimal_from_proto(order.commission_rate)?,
+            commission_amount: optional_decimal_from_proto(order.commission_amount)?,
+            extension_data: extension_data_from_proto(order.extension_data)?,
+        })
+    }
+}
+
+impl ProtoMessage for ExchangeBalancesAndPositions {
+    type Proto = proto::ExchangeBalancesAndPositions;
+
+    fn to_proto(&self) -> Result<proto::ExchangeBalancesAndPositions> {
+        Ok(proto::ExchangeBalancesAndPositions {
+            balances: self
+                .balances
+                .iter()
+                .map(|x| proto::ExchangeBalance {
+                    currency_code: x.currency_code.to_string(),
+                    balance: x.balance.to_string(),
+                })
+                .collect_vec(),
+            positions: self
+                .positions
+                .as_ref()
+                .map(|positions| {
+                    Ok::<_, anyhow::Error>(proto::DerivativePositions {
+                        positions: positions.iter().map(|x| x.to_proto()).try_collect()?,
+                    })
+                })
+                .transpose()?,
+        })
+    }
+
+    fn from_proto(balances: proto::ExchangeBalancesAndPositions) -> Result<Self> {
+        Ok(ExchangeBalancesAndPositions {
+            balances: balances
+                .balances
+                .iter()
+                .map(|x| {
+                    Ok(ExchangeBalance {
+                        currency_code: x.currency_code.as_str().into(),
+                        balance: decimal_from_proto(&x.balance)?,
+                    })
+                })
+                .try_collect::<_, _, anyhow::Error>()?,
+            positions: balances
+                .positions
+                .map(|x| {
+                    x.positions
+                        .into_iter()
+                        .map(DerivativePosition::from_proto)
+                        .try_collect()
+                })
+                .transpose()?,
+        })
+    }
+}
+
+impl ProtoMessage for TradeId {
+    type Proto = proto::TradeId;
+
+    fn to_proto(&self) -> Result<proto::TradeId> {
+        use proto::trade_id::Id;
+        Ok(proto::TradeId {
+            id: Some(match self {
+                TradeId::Number(number) => Id::Number(*number),
+                TradeId::String(string) => Id::String(string.to_string()),
+            }),
+        })
+    }
+
+    fn from_proto(trade_id: proto::TradeId) -> Result<Self> {
+        use proto::trade_id::Id;
+        Ok(match required(trade_id.id, "id")? {
+            Id::Number(number) => TradeId::Number(number),
+            Id::String(string) => TradeId::String(string.into_boxed_str()),
+        })
+    }
+}
+
+impl ProtoMessage for RequestResult<Vec<OrderTrade>> {
+    type Proto = proto::MyTrades;
+
+    fn to_proto(&self) -> Result<proto::MyTrades> {
+        use proto::my_trades::Outcome;
+        Ok(proto::MyTrades {
+            outcome: Some(match self {
+                RequestResult::Success(trades) => Outcome::Trades(proto::OrderTrades {
+                    trades: trades.iter().map(|x| x.to_proto()).try_collect()?,
+                }),
+                RequestResult::Error(error) => Outcome::Error(error_to_proto(error)),
+            }),
+        })
+    }
+
+    fn from_proto(trades: proto::MyTrades) -> Result<Self> {
+        use proto::my_trades::Outcome;
+        Ok(match required(trades.outcome, "outcome")? {
+            Outcome::Trades(trades) => RequestResult::Success(
+                trades
+                    .trades
+                    .into_iter()
+                    .map(OrderTrade::from_proto)
+                    .try_collect()?,
+            ),
+            Outcome::Error(error) => RequestResult::Error(error_from_proto(error)?),
+        })
+    }
+}
+
+impl ProtoMessage for OrderTrade {
+    type Proto = proto::OrderTrade;
+
+    fn to_proto(&self) -> Result<proto::OrderTrade> {
+        Ok(proto::OrderTrade {
+            exchange_order_id: self.exchange_order_id.to_string(),
+            trade_id: Some(self.trade_id.to_proto()?),
+            datetime: time_to_proto(self.datetime),
+            price: self.price.to_string(),
+            amount: self.amount.to_string(),
+            order_role: self.order_role.to_proto(),
+            fee_currency_code: self.fee_currency_code.to_string(),
+            fee_rate: optional_decimal_to_proto(self.fee_rate),
+            fee_amount: optional_decimal_to_proto(self.fee_amount),
+            fill_type: self.fill_type.to_proto(),
+        })
+    }
+
+    fn from_proto(trade: proto::OrderTrade) -> Result<Self> {
+        Ok(OrderTrade::new(
+            trade.exchange_order_id.as_str().into(),
+            TradeId::from_proto(required(trade.trade_id, "trade_id")?)?,
+            time_from_proto(trade.datetime),
+            decimal_from_proto(&trade.price)?,
+            decimal_from_proto(&trade.amount)?,
+            OrderRole::from_proto(trade.order_role)?,
+            trade.fee_currency_code.as_str().into(),
+            optional_decimal_from_proto(trade.fee_rate)?,
+            optional_decimal_from_proto(trade.fee_amount)?,
+            OrderFillType::from_proto(trade.fill_type)?,
+        ))
+    }
+}
+
+impl ProtoMessage for ConnectorSymbols {
+    type Proto = proto::ConnectorSymbols;
+
+    fn to_proto(&self) -> Result<proto::ConnectorSymbols> {
+        Ok(proto::ConnectorSymbols {
+            symbols: self.symbols.iter().map(|x| x.to_proto()).try_collect()?,
+            specific_currency_pairs: self
+                .specific_currency_pairs
+                .iter()
+                .map(
+                    |(currency_pair, specific_currency_pair)| proto::SpecificCurrencyPairEntry {
+                        currency_pair: currency_pair.to_string(),
+                        specific_currency_pair: specific_currency_pair.to_string(),
+                    },
+                )
+                .collect_vec(),
+            supported_currencies: self
+                .supported_currencies
+                .iter()
+                .map(|(currency_id, currency_code)| proto::SupportedCurrency {
+                    currency_id: currency_id.to_string(),
+                    currency_code: currency_code.to_string(),
+                })
+                .collect_vec(),
+        })
+    }
+
+    fn from_proto(symbols: proto::ConnectorSymbols) -> Result<Self> {
+        Ok(ConnectorSymbols {
+            symbols: symbols
+                .symbols
+                .into_iter()
+                .map(Symbol::from_proto)
+                .try_collect()?,
+            specific_currency_pairs: symbols
+                .specific_currency_pairs
+                .iter()
+                .map(|x| {
+                    Ok::<_, anyhow::Error>((
+                        currency_pair_from_proto(&x.currency_pair)?,
+                        x.specific_currency_pair.as_str().into(),
+                    ))
+                })
+                .try_collect()?,
+            supported_currencies: symbols
+                .supported_currencies
+                .iter()
+                .map(|x| {
+                    (
+                        x.currency_id.as_str().into(),
+                        x.currency_code.as_str().into(),
+                    )
+                })
+                .collect_vec(),
+        })
+    }
+}
+
+impl ProtoMessage for Precision {
+    type Proto = proto::Precision;
+
+    fn to_proto(&self) -> Result<proto::Precision> {
+        use proto::precision::Kind;
+        Ok(proto::Precision {
+            kind: Some(match self {
+                Precision::ByTick { tick } => Kind::Tick(tick.to_string()),
+                Precision::ByMantissa { precision } => Kind::Mantissa((*precision).into()),
+            }),
+        })
+    }
+
+    fn from_proto(precision: proto::Precision) -> Result<Self> {
+        use proto::precision::Kind;
+        Ok(match required(precision.kind, "kind")? {
+            Kind::Tick(tick) => Precision::ByTick {
+                tick: decimal_from_proto(&tick)?,
+            },
+            Kind::Mantissa(precision) => Precision::ByMantissa {
+                precision: precision
+                    .try_into()
+                    .with_context(|| format!("Invalid mantissa precision {precision}"))?,
+            },
+        })
+    }
+}
+
+impl ProtoMessage for Symbol {
+    type Proto = proto::Symbol;
+
+    fn to_proto(&self) -> Result<proto::Symbol> {
+        Ok(proto::Symbol {
+            is_active: self.is_active,
+            is_derivative: self.is_derivative,
+            base_currency_id: self.base_currency_id.to_string(),
+            base_currency_code: self.base_currency_code.to_string(),
+            quote_currency_id: self.quote_currency_id.to_string(),
+            quote_currency_code: self.quote_currency_code.to_string(),
+            min_price: optional_decimal_to_proto(self.min_price),
+            max_price: optional_decimal_to_proto(self.max_price),
+            min_amount: optional_decimal_to_proto(self.min_amount),
+            max_amount: optional_decimal_to_proto(self.max_amount),
+            min_cost: optional_decimal_to_proto(self.min_cost),
+            amount_currency_code: self.amount_currency_code.to_string(),
+            balance_currency_code: self.balance_currency_code.map(|x| x.to_string()),
+            amount_multiplier: self.amount_multiplier.to_string(),
+            price_precision: Some(self.price_precision.to_proto()?),
+            amount_precision: Some(self.amount_precision.to_proto()?),
+        })
+    }
+
+    fn from_proto(symbol: proto::Symbol) -> Result<Self> {
+        let mut result = Symbol::new(
+            symbol.is_active,
+            symbol.is_derivative,
+            symbol.base_currency_id.as_str().into(),
+            symbol.base_currency_code.as_str().into(),
+            symbol.quote_currency_id.as_str().into(),
+            symbol.quote_currency_code.as_str().into(),
+            optional_decimal_from_proto(symbol.min_price)?,
+            optional_decimal_from_proto(symbol.max_price)?,
+            optional_decimal_from_proto(symbol.min_amount)?,
+            optional_decimal_from_proto(symbol.max_amount)?,
+            optional_decimal_from_proto(symbol.min_cost)?,
+            symbol.amount_currency_code.as_str().into(),
+            symbol.balance_currency_code.as_deref().map(Into::into),
+            Precision::from_proto(required(symbol.price_precision, "price_precision")?)?,
+            Precision::from_proto(required(symbol.amount_precision, "amount_precision")?)?,
+        );
+        result.amount_multiplier = decimal_from_proto(&symbol.amount_multiplier)?;
+
+        Ok(result)
+    }
+}
+
+impl ProtoMessage for FillEvent {
+    type Proto = proto::FillEvent;
+
+    fn to_proto(&self) -> Result<proto::FillEvent> {
+        use proto::fill_event::FillAmount as ProtoFillAmount;
+        Ok(proto::FillEvent {
+            source_type: self.source_type.to_proto(),
+            trade_id: self.trade_id.as_ref().map(|x| x.to_proto()).transpose()?,
+            client_order_id: self.client_order_id.as_ref().map(|x| x.to_string()),
+            exchange_order_id: self.exchange_order_id.to_string(),
+            fill_price: self.fill_price.to_string(),
+            fill_amount: Some(match self.fill_amount {
+                FillAmount::Incremental {
+                    fill_amount,
+                    total_filled_amount,
+                } => ProtoFillAmount::Incremental(proto::IncrementalFill {
+                    fill_amount: fill_amount.to_string(),
+                    total_filled_amount: optional_decimal_to_proto(total_filled_amount),
+                }),
+                FillAmount::Total {
+                    total_filled_amount,
+                } => ProtoFillAmount::Total(total_filled_amount.to_string()),
+            }),
+            order_role: self.order_role.map(|x| x.to_proto()),
+            commission_currency_code: self.commission_currency_code.map(|x| x.to_string()),
+            commission_rate: optional_decimal_to_proto(self.commission_rate),
+            commission_amount: optional_decimal_to_proto(self.commission_amount),
+            fill_type: self.fill_type.to_proto(),
+            special_order_data: self
+                .special_order_data
+                .as_ref()
+                .map(|x| proto::SpecialOrderData {
+                    currency_pair: x.currency_pair.to_string(),
+                    order_side: x.order_side.to_proto(),
+                    order_amount: x.order_amount.to_string(),
+                }),
+            fill_date: self.fill_date.map(time_to_proto),
+        })
+    }
+
+    fn from_proto(fill_event: proto::FillEvent) -> Result<Self> {
+        use proto::fill_event::FillAmount as ProtoFillAmount;
+        Ok(FillEvent {
+            source_type: EventSourceType::from_proto(fill_event.source_type)?,
+            trade_id: fill_event.trade_id.map(TradeId::from_proto).transpose()?,
+            client_order_id: fill_event.client_order_id.as_deref().map(Into::into),
+            exchange_order_id: fill_event.exchange_order_id.as_str().into(),
+            fill_price: decimal_from_proto(&fill_event.fill_price)?,
+            fill_amount: match required(fill_event.fill_amount, "fill_amount")? {
+                ProtoFillAmount::Incremental(fill) => FillAmount::Incremental {
+                    fill_amount: decimal_from_proto(&fill.fill_amount)?,
+                    total_filled_amount: optional_decimal_from_proto(fill.total_filled_amount)?,
+                },
+                ProtoFillAmount::Total(total_filled_amount) => FillAmount::Total {
+                    total_filled_amount: decimal_from_proto(&total_filled_amount)?,
+                },
+            },
+            order_role: fill_event
+                .order_role
+                .map(OrderRole::from_proto)
+                .transpose()?,
+            commission_currency_code: fill_event
+                .commission_currency_code
+                .as_deref()
+                .map(Into::into),
+            commission_rate: optional_decimal_from_proto(fill_event.commission_rate)?,
+            commission_amount: optional_decimal_from_proto(fill_event.commission_amount)?,
+            fill_type: OrderFillType::from_proto(fill_event.fill_type)?,
+            special_order_data: fill_event
+                .special_order_data
+                .map(|x| {
+                    Ok::<_, anyhow::Error>(SpecialOrderData {
+                        currency_pair: currency_pair_from_proto(&x.currency_pair)?,
+                        order_side: OrderSide::from_proto(x.order_side)?,
+                        order_amount: decimal_from_proto(&x.order_amount)?,
+                    })
+                })
+                .transpose()?,
+            fill_date: fill_event.fill_date.map(time_from_proto),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::ExchangeAccountId;
+    use crate::settings::NotionalCapSettings;
+    use rust_decimal_macros::dec;
+
+    fn done_response(request_id: u64) -> Frame {
+        Frame::response(request_id, &Ok(ConnectorResponse::Done))
+    }
+
+    fn transfer(frame: Frame) -> FrameMessage {
+        Frame::decode_from_bytes(&frame.encode_to_bytes())
+            .expect("in test")
+            .into_message()
+            .expect("in test")
+    }
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    #[test]
+    fn frame_is_encoded_in_protobuf_wire_format() {
+        let encoded = done_response(300).encode_to_bytes();
+
+        // request_id = 300, response = { done = {} }
+        assert_eq!(encoded, vec![0x08, 0xac, 0x02, 0x1a, 0x02, 0x2a, 0x00]);
+    }
+
+    #[test]
+    fn frame_is_decoded_after_encoding() {
+        for frame in [
+            done_response(0),
+            done_response(u64::MAX),
+            Frame::request(1, &ConnectorRequest::BuildAllSymbols).expect("in test"),
+            Frame::notification(&ConnectorNotification::WebsocketsDisconnected).expect("in test"),
+        ] {
+            assert_eq!(
+                Frame::decode_from_bytes(&frame.encode_to_bytes()).expect("in test"),
+                frame
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_fields_are_skipped() {
+        let mut encoded = Vec::new();
+        // varint, fixed64, bytes and fixed32 fields with numbers 5..8
+        encoded.extend([0x28, 0x96, 0x01]);
+        encoded.extend([0x31, 0, 0, 0, 0, 0, 0, 0, 0]);
+        encoded.extend([0x3a, 0x01, 0x00]);
+        encoded.extend([0x45, 0, 0, 0, 0]);
+        encoded.extend(done_response(7).encode_to_bytes());
+
+        assert_eq!(
+            Frame::decode_from_bytes(&encoded).expect("in test"),
+            done_response(7)
+        );
+    }
+
+    #[test]
+    fn truncated_frame_or_frame_without_body_is_rejected() {
+        let encoded = done_response(1).encode_to_bytes();
+
+        assert!(Frame::decode_from_bytes(&encoded[..encoded.len() - 1]).is_err());
+        assert!(Frame::decode_from_bytes(&[0x08, 0x80]).is_err());
+        assert!(Frame::decode_from_bytes(&[0x08, 0x01]).is_err());
+    }
+
+    #[test]
+    fn unknown_enum_value_is_rejected() {
+        let mut notification = ConnectorNotification::OrderCreated {
+            client_order_id: "client".into(),
+            exchange_order_id: "exchange".into(),
+            source_type: EventSourceType::WebSocket,
+        }
+        .to_proto()
+        .expect("in test");
+        if let Some(proto::notification::Kind::OrderCreated(event)) = &mut notification.kind {
+            event.source_type = 100;
+        }
+
+        assert!(ConnectorNotification::from_proto(notification).is_err());
+    }
+
+    #[test]
+    fn exchange_settings_are_sent_to_connector_process() {
+        let settings = ExchangeSettings {
+            exchange_account_id: ExchangeAccountId::new("Binance", 0),
+            daily_notional_caps: vec![NotionalCapSettings {
+                quote_currency_code: "USDT".into(),
+                max_daily_notional: dec!(25000),
+            }],
+            ..Default::default()
+        };
+        let init = ConnectorRequest::Init {
+            settings: Box::new(settings.clone()),
+            traded_specific_currencies: Some(vec!["BTCUSDT".into()]),
+        };
+
+        match transfer(Frame::request(0, &init).expect("in test")) {
+            FrameMessage::Request(ConnectorRequest::Init {
+                settings: sent_settings,
+                traded_specific_currencies,
+            }) => {
+                assert_eq!(*sent_settings, settings);
+                assert_eq!(traded_specific_currencies, Some(vec!["BTCUSDT".into()]));
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn order_is_sent_to_connector_process() {
+        let mut order = OrderSnapshot::with_params(
+            "client".into(),
+            OrderType::Limit,
+            None,
+            ExchangeAccountId::new("Binance", 0),
+            currency_pair(),
+            dec!(20000.5),
+            dec!(0.25),
+            OrderSide::Sell,
+            Some(ReservationId::from_u64(42)),
+            "strategy",
+        );
+        order.header = order
+            .header
+            .clone()
+            .with_expire_time(Utc.timestamp(1_700_000_000, 123))
+            .with_latency_budget(Duration::from_millis(150))
+            .with_reduce_only();
+        order.props.exchange_order_id = Some("exchange".into());
+        order.props.status = OrderStatus::Created;
+
+        let request = ConnectorRequest::CreateOrder { order };
+        match transfer(Frame::request(1, &request).expect("in test")) {
+            FrameMessage::Request(ConnectorRequest::CreateOrder { order: sent }) => {
+                let header = &sent.header;
+                assert_eq!(header.client_order_id.as_str(), "client");
+                assert_eq!(header.currency_pair, currency_pair());
+                assert_eq!(header.order_type, OrderType::Limit);
+                assert_eq!(header.side, OrderSide::Sell);
+                assert_eq!(header.amount, dec!(0.25));
+                assert_eq!(header.reservation_id, Some(ReservationId::from_u64(42)));
+                assert_eq!(header.expire_time, Some(Utc.timestamp(1_700_000_000, 123)));
+                assert_eq!(header.latency_budget, Some(Duration::from_millis(150)));
+                assert!(header.reduce_only);
+                assert_eq!(header.strategy_name, "strategy");
+                assert_eq!(sent.props.raw_price, Some(dec!(20000.5)));
+                assert_eq!(
+                    sent.props.exchange_order_id.as_ref().map(|x| x.as_str()),
+                    Some("exchange")
+                );
+                assert_eq!(sent.props.status, OrderStatus::Created);
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn position_is_sent_to_connector_process() {
+        let position = ActivePosition::new(DerivativePosition::new(
+            currency_pair(),
+            dec!(0.5),
+            Some(OrderSide::Buy),
+            dec!(20000),
+            dec!(15000),
+            dec!(3),
+        ));
+        let request = ConnectorRequest::ClosePosition {
+            position: position.clone(),
+            price: Some(dec!(21000)),
+        };
+
+        match transfer(Frame::request(1, &request).expect("in test")) {
+            FrameMessage::Request(ConnectorRequest::ClosePosition {
+                position: sent_position,
+                price,
+            }) => {
+                assert_eq!(sent_position.id.as_str(), position.id.as_str());
+                assert_eq!(sent_position.status, position.status);
+                assert_eq!(sent_position.derivative.position, dec!(0.5));
+                assert_eq!(sent_position.derivative.side, Some(OrderSide::Buy));
+                assert_eq!(price, Some(dec!(21000)));
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn error_is_sent_to_engine() {
+        let error = ExchangeError::new(
+            ExchangeErrorType::PendingError(Duration::from_millis(1500)),
+            "Too many requests".to_owned(),
+            Some(-1003),
+        );
+
+        match transfer(Frame::response(3, &Err(error.clone()))) {
+            FrameMessage::Response(Err(sent_error)) => assert_eq!(sent_error, error),
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn fill_is_sent_to_engine() {
+        let fill_event = FillEvent {
+            source_type: EventSourceType::WebSocket,
+            trade_id: Some(TradeId::Number(7)),
+            client_order_id: Some("client".into()),
+            exchange_order_id: "exchange".into(),
+            fill_price: dec!(100.5),
+            fill_amount: FillAmount::Incremental {
+                fill_amount: dec!(0.1),
+                total_filled_amount: Some(dec!(0.3)),
+            },
+            order_role: Some(OrderRole::Maker),
+            commission_currency_code: Some("usdt".into()),
+            commission_rate: None,
+            commission_amount: Some(dec!(0.01)),
+            fill_type: OrderFillType::UserTrade,
+            special_order_data: None,
+            fill_date: Some(Utc.timestamp(1_700_000_000, 0)),
+        };
+        let notification = ConnectorNotification::OrderFilled(Box::new(fill_event));
+
+        match transfer(Frame::notification(&notification).expect("in test")) {
+            FrameMessage::Notification(ConnectorNotification::OrderFilled(sent)) => {
+                assert_eq!(sent.trade_id, Some(TradeId::Number(7)));
+                assert_eq!(sent.fill_price, dec!(100.5));
+                assert!(matches!(
+                    sent.fill_amount,
+                    FillAmount::Incremental {
+                        fill_amount,
+                        total_filled_amount: Some(total_filled_amount),
+                    } if fill_amount == dec!(0.1) && total_filled_amount == dec!(0.3)
+                ));
+                assert_eq!(sent.order_role, Some(OrderRole::Maker));
+                assert_eq!(sent.commission_currency_code, Some("usdt".into()));
+                assert_eq!(sent.commission_rate, None);
+                assert_eq!(sent.fill_date, Some(Utc.timestamp(1_700_000_000, 0)));
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[tokio::test]
+    async fn frames_are_read_after_writing() {
+        let first = Frame::notification(&ConnectorNotification::OrderBook {
+            creation_time: Utc::now(),
+            currency_pair: currency_pair(),
+            is_snapshot: true,
+            asks: vec![(dec!(101), dec!(1.5))],
+            bids: vec![(dec!(100), dec!(2))],
+        })
+        .expect("in test");
+        let second = Frame::response(2, &Ok(ConnectorResponse::Orders(Vec::new())));
+
+        let mut bytes = Vec::new();
+        write_frame(&mut bytes, &first).await.expect("in test");
+        write_frame(&mut bytes, &second).await.expect("in test");
+
+        let mut reader = bytes.as_slice();
+        let read = read_frame(&mut reader).await.expect("in test");
+        assert_eq!(read.as_ref(), Some(&first));
+        match read.expect("in test").into_message().expect("in test") {
+            FrameMessage::Notification(ConnectorNotification::OrderBook { asks, .. }) => {
+                assert_eq!(asks, vec![(dec!(101), dec!(1.5))])
+            }
+            _ => panic!("Unexpected message"),
+        }
+
+        let read = read_frame(&mut reader).await.expect("in test");
+        assert_eq!(read, Some(second));
+        assert_eq!(read_frame(&mut reader).await.expect("in test"), None);
+    }
+}
@@ -12,16 +12,18 @@ use crate::misc::derivative_position::DerivativePosition;
 use crate::order_book::event::OrderBookEvent;
 use crate::orders::event::OrderEvent;
 use crate::orders::order::OrderSide;
+use crate::orders::pool::OrderRef;
+use crate::orders::queue_position::QueuePosition;
 
 pub const CHANNEL_MAX_EVENTS_COUNT: usize = 200_000;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExchangeBalance {
     pub currency_code: CurrencyCode,
     pub balance: Decimal,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExchangeBalancesAndPositions {
     pub balances: Vec<ExchangeBalance>,
     pub positions: Option<Vec<DerivativePosition>>,
@@ -147,9 +149,48 @@ pub struct TradesEvent {
     pub exchange_account_id: ExchangeAccountId,
     pub currency_pair: CurrencyPair,
     pub trades: Vec<Trade>,
+    /// Erroneous prints excluded from `trades`. They are passed for recording and diagnostics
+    /// only and shouldn't affect trading logic
+    pub bad_prints: Vec<Trade>,
     pub receipt_time: DateTime,
 }
 
+/// Order book of market stopped updating while connection is healthy or resumed updating
+#[derive(Debug, Clone)]
+pub struct MarketDataStatusEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub is_stale: bool,
+    pub last_update_time: DateTime,
+}
+
+/// Quoting on market is paused or resumed by operator. Resting orders of market are canceled
+/// on pause, so strategies should stop calculating quotes for it until it's resumed
+#[derive(Debug, Clone)]
+pub struct MarketPausedEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub is_paused: bool,
+}
+
+/// Connector of exchange account failed and is restarted or it's available again after restart.
+/// Strategies shouldn't expect orders on unavailable account to be created or canceled
+#[derive(Debug, Clone)]
+pub struct AccountAvailabilityEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub is_available: bool,
+    /// Failure which made account unavailable
+    pub reason: Option<String>,
+}
+
+/// Estimated queue position of resting maker order changed materially, e.g. strategy can reprice
+/// quote which was pushed back in queue. The latest estimate is also available by `OrderRef::queue_position()`
+#[derive(Debug, Clone)]
+pub struct QueuePositionEvent {
+    pub order: OrderRef,
+    pub queue_position: QueuePosition,
+}
+
 #[derive(Debug, Clone)]
 pub enum ExchangeEvent {
     OrderBookEvent(OrderBookEvent),
@@ -157,6 +198,10 @@ pub enum ExchangeEvent {
     BalanceUpdate(BalanceUpdateEvent),
     LiquidationPrice(LiquidationPriceEvent),
     Trades(TradesEvent),
+    MarketDataStatus(MarketDataStatusEvent),
+    MarketPaused(MarketPausedEvent),
+    AccountAvailability(AccountAvailabilityEvent),
+    QueuePosition(QueuePositionEvent),
 }
 
 pub(crate) struct ExchangeEvents {
@@ -173,7 +218,7 @@ impl ExchangeEvents {
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Copy)]
+#[derive(Debug, Default, Clone, PartialEq, Copy, Serialize, Deserialize)]
 pub enum AllowedEventSourceType {
     #[default]
     All,
@@ -0,0 +1,613 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, Instant};
+use url::Url;
+
+use crate::connectivity::WebSocketRole;
+use crate::exchanges::api_key_permissions::ApiKeyPermissions;
+use crate::exchanges::common::{
+    ActivePosition, Amount, ClosedPosition, CurrencyCode, CurrencyId, CurrencyPair,
+    ExchangeAccountId, ExchangeError, ExchangeErrorType, ExchangeId, Price, SpecificCurrencyPair,
+};
+use crate::exchanges::connection_pool::ConnectionPoolStats;
+use crate::exchanges::events::{ExchangeBalancesAndPositions, ExchangeEvent, Trade};
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::exchange::{BoxExchangeClient, RequestResult};
+use crate::exchanges::general::order::cancel::CancelOrderResult;
+use crate::exchanges::general::order::create::CreateOrderResult;
+use crate::exchanges::general::order::get_order_trades::OrderTrade;
+use crate::exchanges::general::symbol::Symbol;
+use crate::exchanges::host_selection::HostStats;
+use crate::exchanges::margin::MarginInfo;
+use crate::exchanges::payload_anomalies::PayloadAnomaly;
+use crate::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use crate::exchanges::traits::{
+    ExchangeClient, ExchangeClientBuilder, ExchangeClientBuilderResult, HandleOrderFilledCb,
+    HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb, Support,
+};
+use crate::infrastructure::spawn_future_ok;
+use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::order_book::order_book_data::OrderBookData;
+use crate::orders::fill::EventSourceType;
+use crate::orders::order::{
+    OrderCancelling, OrderHeader, OrderInfo, OrderInfoExtensionData, OrderSide,
+};
+use crate::orders::pool::{OrderRef, OrdersPool};
+use crate::services::funding_rates::FundingRate;
+use crate::settings::{Environment, ExchangeSettings};
+
+/// Degraded behavior of exchange emulated by `FaultInjectingClient`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaultMode {
+    /// Exchange is unavailable: REST requests fail and websocket is down
+    Downtime,
+    /// Acknowledgments of order creation and cancellation are delayed
+    SlowAcks { delay: Duration },
+    /// Share of order creations in percents is rejected by exchange
+    RandomRejects { rejection_rate: Percent },
+    /// Websocket is down while REST requests work
+    WebSocketOutage,
+}
+
+/// Fault mode which is active during `duration` since `start` offset from script creation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaultWindow {
+    pub start: Duration,
+    pub duration: Duration,
+    pub mode: FaultMode,
+}
+
+/// Script of exchange faults for integration tests of engine and strategies resilience.
+/// Fault modes are activated by scheduled windows or toggled manually
+pub struct FaultScript {
+    started_at: Instant,
+    windows: Vec<FaultWindow>,
+    toggled: Mutex<Vec<FaultMode>>,
+    /// State of xorshift generator, so random rejects are reproducible by seed
+    random_state: Mutex<u64>,
+}
+
+impl FaultScript {
+    pub fn new(windows: Vec<FaultWindow>, seed: u64) -> Arc<Self> {
+        Arc::new(FaultScript {
+            started_at: Instant::now(),
+            windows,
+            toggled: Mutex::new(Vec::new()),
+            // xorshift generator doesn't leave zero state
+            random_state: Mutex::new(seed.max(1)),
+        })
+    }
+
+    pub fn toggle_on(&self, mode: FaultMode) {
+        log::warn!("Fault mode {mode:?} is toggled on");
+        self.toggled.lock().push(mode);
+    }
+
+    pub fn toggle_off(&self, mode: &FaultMode) {
+        log::warn!("Fault mode {mode:?} is toggled off");
+        self.toggled.lock().retain(|x| x != mode);
+    }
+
+    pub fn active_modes(&self) -> Vec<FaultMode> {
+        self.active_modes_at(self.started_at.elapsed())
+    }
+
+    fn active_modes_at(&self, elapsed: Duration) -> Vec<FaultMode> {
+        let mut modes = self.toggled.lock().clone();
+        modes.extend(
+            self.windows
+                .iter()
+                .filter(|x| x.start <= elapsed && elapsed < x.start + x.duration)
+                .map(|x| x.mode.clone()),
+        );
+        modes
+    }
+
+    fn is_down(&self) -> bool {
+        self.active_modes().contains(&FaultMode::Downtime)
+    }
+
+    fn is_websocket_down(&self) -> bool {
+        self.active_modes()
+            .iter()
+            .any(|x| matches!(x, FaultMode::Downtime | FaultMode::WebSocketOutage))
+    }
+
+    fn ack_delay(&self) -> Option<Duration> {
+        self.active_modes()
+            .iter()
+            .filter_map(|x| match x {
+                FaultMode::SlowAcks { delay } => Some(*delay),
+                _ => None,
+            })
+            .max()
+    }
+
+    fn should_reject(&self) -> bool {
+        let rejection_rate = self
+            .active_modes()
+            .iter()
+            .filter_map(|x| match x {
+                FaultMode::RandomRejects { rejection_rate } => Some(*rejection_rate),
+                _ => None,
+            })
+            .max();
+
+        match rejection_rate {
+            Some(rejection_rate) => self.next_random_percent() < rejection_rate,
+            None => false,
+        }
+    }
+
+    /// Pseudo-random value in range [0, 100) with precision of 0.01
+    fn next_random_percent(&self) -> Percent {
+        let mut state = self.random_state.lock();
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+
+        Decimal::new((*state % 10_000) as i64, 2)
+    }
+}
+
+fn unavailable_error() -> ExchangeError {
+    ExchangeError::new(
+        ExchangeErrorType::ServiceUnavailable,
+        "Exchange is down by fault injection".to_owned(),
+        None,
+    )
+}
+
+/// Exchange client which emulates faults of exchange by `FaultScript` on top of inner client,
+/// e.g. simulated exchange or testnet connector
+pub struct FaultInjectingClient {
+    inner: BoxExchangeClient,
+    script: Arc<FaultScript>,
+}
+
+impl FaultInjectingClient {
+    pub fn new(inner: BoxExchangeClient, script: Arc<FaultScript>) -> Self {
+        FaultInjectingClient { inner, script }
+    }
+
+    fn check_available(&self) -> Result<()> {
+        if self.script.is_down() {
+            bail!(unavailable_error());
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExchangeClient for FaultInjectingClient {
+    async fn create_order(&self, order: &OrderRef) -> CreateOrderResult {
+        if self.script.is_down() {
+            return CreateOrderResult::failed(unavailable_error(), EventSourceType::Rest);
+        }
+
+        if self.script.should_reject() {
+            let error = ExchangeError::unknown("Order is rejected by fault injection");
+            return CreateOrderResult::failed(error, EventSourceType::Rest);
+        }
+
+        let result = self.inner.create_order(order).await;
+        if let Some(delay) = self.script.ack_delay() {
+            sleep(delay).await;
+        }
+
+        result
+    }
+
+    async fn cancel_order(&self, order: OrderCancelling) -> CancelOrderResult {
+        if self.script.is_down() {
+            return CancelOrderResult::failed(unavailable_error(), EventSourceType::Rest);
+        }
+
+        let result = self.inner.cancel_order(order).await;
+        if let Some(delay) = self.script.ack_delay() {
+            sleep(delay).await;
+        }
+
+        result
+    }
+
+    async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        self.check_available()?;
+        self.inner.cancel_all_orders(currency_pair).await
+    }
+
+    async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
+        self.check_available()?;
+        self.inner.get_open_orders().await
+    }
+
+    async fn get_open_orders_by_currency_pair(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<Vec<OrderInfo>> {
+        self.check_available()?;
+        self.inner
+            .get_open_orders_by_currency_pair(currency_pair)
+            .await
+    }
+
+    async fn get_order_info(&self, order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
+        if self.script.is_down() {
+            return Err(unavailable_error());
+        }
+
+        self.inner.get_order_info(order).await
+    }
+
+    async fn close_position(
+        &self,
+        position: &ActivePosition,
+        price: Option<Price>,
+    ) -> Result<ClosedPosition> {
+        self.check_available()?;
+        self.inner.close_position(position, price).await
+    }
+
+    async fn get_active_positions(&self) -> Result<Vec<ActivePosition>> {
+        self.check_available()?;
+        self.inner.get_active_positions().await
+    }
+
+    async fn get_balance(&self, is_spot: bool) -> Result<ExchangeBalancesAndPositions> {
+        self.check_available()?;
+        self.inner.get_balance(is_spot).await
+    }
+
+    async fn get_my_trades(
+        &self,
+        symbol: &Symbol,
+        last_date_time: Option<DateTime>,
+    ) -> Result<RequestResult<Vec<OrderTrade>>> {
+        if self.script.is_down() {
+            return Ok(RequestResult::Error(unavailable_error()));
+        }
+
+        self.inner.get_my_trades(symbol, last_date_time).await
+    }
+
+    async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>> {
+        self.check_available()?;
+        self.inner.build_all_symbols().await
+    }
+
+    async fn get_api_key_permissions(&self) -> Result<Option<ApiKeyPermissions>> {
+        self.check_available()?;
+        self.inner.get_api_key_permissions().await
+    }
+
+    async fn get_margin_info(&self) -> Result<Option<MarginInfo>> {
+        self.check_available()?;
+        self.inner.get_margin_info().await
+    }
+
+    async fn get_daily_volumes(&self) -> Result<Option<HashMap<CurrencyPair, Amount>>> {
+        self.check_available()?;
+        self.inner.get_daily_volumes().await
+    }
+
+    async fn get_order_history(
+        &self,
+        currency_pair: CurrencyPair,
+        from: DateTime,
+        to: DateTime,
+    ) -> Result<Option<Vec<OrderInfo>>> {
+        self.check_available()?;
+        self.inner.get_order_history(currency_pair, from, to).await
+    }
+
+    async fn get_trades_after(
+        &self,
+        currency_pair: CurrencyPair,
+        last_trade_id: u64,
+    ) -> Result<Option<Vec<Trade>>> {
+        self.check_available()?;
+        self.inner
+            .get_trades_after(currency_pair, last_trade_id)
+            .await
+    }
+
+    async fn get_funding_rate(&self, currency_pair: CurrencyPair) -> Result<Option<FundingRate>> {
+        self.check_available()?;
+        self.inner.get_funding_rate(currency_pair).await
+    }
+
+    async fn get_order_book(&self, currency_pair: CurrencyPair) -> Result<Option<OrderBookData>> {
+        self.check_available()?;
+        self.inner.get_order_book(currency_pair).await
+    }
+
+    async fn reduce_order(
+        &self,
+        order: &OrderRef,
+        new_amount: Amount,
+    ) -> Result<(), ExchangeError> {
+        if self.script.is_down() {
+            return Err(unavailable_error());
+        }
+
+        self.inner.reduce_order(order, new_amount).await
+    }
+
+    async fn cancel_order_by_client_order_id(
+        &self,
+        header: &OrderHeader,
+    ) -> Result<(), ExchangeError> {
+        if self.script.is_down() {
+            return Err(unavailable_error());
+        }
+
+        self.inner.cancel_order_by_client_order_id(header).await
+    }
+}
+
+#[async_trait]
+impl Support for FaultInjectingClient {
+    /// Messages received while websocket is down are dropped
+    fn on_websocket_message(&self, msg: &str) -> Result<()> {
+        if self.script.is_websocket_down() {
+            return Ok(());
+        }
+
+        self.inner.on_websocket_message(msg)
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        self.inner.on_connecting()
+    }
+
+    fn set_send_websocket_message_callback(&self, callback: SendWebsocketMessageCb) {
+        self.inner.set_send_websocket_message_callback(callback)
+    }
+
+    /// Acknowledgments by websocket are delayed as well as REST ones
+    fn set_order_created_callback(&mut self, callback: OrderCreatedCb) {
+        let callback = Arc::new(callback);
+        let script = self.script.clone();
+        self.inner.set_order_created_callback(Box::new(
+            move |client_order_id, exchange_order_id, source_type| match script.ack_delay() {
+                None => callback(client_order_id, exchange_order_id, source_type),
+                Some(delay) => {
+                    let callback = callback.clone();
+                    let _ = spawn_future_ok(
+                        "Delayed order creation acknowledgment",
+                        SpawnFutureFlags::STOP_BY_TOKEN,
+                        async move {
+                            sleep(delay).await;
+                            callback(client_order_id, exchange_order_id, source_type)
+                        },
+                    );
+                }
+            },
+        ))
+    }
+
+    fn set_order_cancelled_callback(&mut self, callback: OrderCancelledCb) {
+        let callback = Arc::new(callback);
+        let script = self.script.clone();
+        self.inner.set_order_cancelled_callback(Box::new(
+            move |client_order_id, exchange_order_id, source_type| match script.ack_delay() {
+                None => callback(client_order_id, exchange_order_id, source_type),
+                Some(delay) => {
+                    let callback = callback.clone();
+                    let _ = spawn_future_ok(
+                        "Delayed order cancellation acknowledgment",
+                        SpawnFutureFlags::STOP_BY_TOKEN,
+                        async move {
+                            sleep(delay).await;
+                            callback(client_order_id, exchange_order_id, source_type)
+                        },
+                    );
+                }
+            },
+        ))
+    }
+
+    fn set_handle_order_filled_callback(&mut self, callback: HandleOrderFilledCb) {
+        self.inner.set_handle_order_filled_callback(callback)
+    }
+
+    fn set_handle_trade_callback(&mut self, callback: HandleTradeCb) {
+        self.inner.set_handle_trade_callback(callback)
+    }
+
+    fn set_traded_specific_currencies(&self, currencies: Vec<SpecificCurrencyPair>) {
+        self.inner.set_traded_specific_currencies(currencies)
+    }
+
+    fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
+        self.inner.is_websocket_enabled(role)
+    }
+
+    /// Connection attempts fail while websocket is down, so reconnection is exercised
+    async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url> {
+        if self.script.is_websocket_down() {
+            bail!("Websocket {role:?} is down by fault injection");
+        }
+
+        self.inner.create_ws_url(role).await
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        self.inner.get_specific_currency_pair(currency_pair)
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        self.inner.get_supported_currencies()
+    }
+
+    fn should_log_message(&self, message: &str) -> bool {
+        self.inner.should_log_message(message)
+    }
+
+    fn get_message_partition_key<'a>(&self, message: &'a str) -> Option<&'a str> {
+        self.inner.get_message_partition_key(message)
+    }
+
+    fn log_unknown_message(&self, exchange_account_id: ExchangeAccountId, message: &str) {
+        self.inner.log_unknown_message(exchange_account_id, message)
+    }
+
+    fn get_balance_reservation_currency_code(
+        &self,
+        symbol: Arc<Symbol>,
+        side: OrderSide,
+    ) -> CurrencyCode {
+        self.inner
+            .get_balance_reservation_currency_code(symbol, side)
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        self.inner.get_settings()
+    }
+
+    /// Inner connector is returned, so exchange-specific features are available with fault injection too
+    fn as_any(&self) -> &dyn Any {
+        self.inner.as_any()
+    }
+
+    fn get_initial_extension_data(&self) -> Option<Box<dyn OrderInfoExtensionData>> {
+        self.inner.get_initial_extension_data()
+    }
+
+    fn get_host_stats(&self) -> Vec<HostStats> {
+        self.inner.get_host_stats()
+    }
+
+    fn get_connection_pool_stats(&self) -> Option<ConnectionPoolStats> {
+        self.inner.get_connection_pool_stats()
+    }
+
+    fn get_payload_anomalies(&self) -> Vec<PayloadAnomaly> {
+        self.inner.get_payload_anomalies()
+    }
+}
+
+/// Builder of exchange clients wrapped by `FaultInjectingClient`. All clients created by it
+/// share the same fault script
+pub struct FaultInjectingClientBuilder {
+    inner: Box<dyn ExchangeClientBuilder>,
+    script: Arc<FaultScript>,
+}
+
+impl FaultInjectingClientBuilder {
+    pub fn new(inner: Box<dyn ExchangeClientBuilder>, script: Arc<FaultScript>) -> Self {
+        FaultInjectingClientBuilder { inner, script }
+    }
+}
+
+impl ExchangeClientBuilder for FaultInjectingClientBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+        orders: Arc<OrdersPool>,
+    ) -> ExchangeClientBuilderResult {
+        let result = self.inner.create_exchange_client(
+            exchange_settings,
+            events_channel,
+            lifetime_manager,
+            orders,
+        );
+
+        ExchangeClientBuilderResult {
+            client: Box::new(FaultInjectingClient::new(
+                result.client,
+                self.script.clone(),
+            )),
+            features: result.features,
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        self.inner.get_timeout_arguments()
+    }
+
+    fn get_exchange_id(&self) -> ExchangeId {
+        self.inner.get_exchange_id()
+    }
+
+    fn get_hosts_environment(&self, exchange_settings: &ExchangeSettings) -> Option<Environment> {
+        self.inner.get_hosts_environment(exchange_settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    pub fn modes_are_active_within_windows_and_toggles() {
+        let script = FaultScript::new(
+            vec![
+                FaultWindow {
+                    start: secs(10),
+                    duration: secs(5),
+                    mode: FaultMode::Downtime,
+                },
+                FaultWindow {
+                    start: secs(12),
+                    duration: secs(10),
+                    mode: FaultMode::WebSocketOutage,
+                },
+            ],
+            42,
+        );
+
+        assert!(script.active_modes_at(secs(9)).is_empty());
+        assert_eq!(script.active_modes_at(secs(10)), vec![FaultMode::Downtime]);
+        assert_eq!(
+            script.active_modes_at(secs(14)),
+            vec![FaultMode::Downtime, FaultMode::WebSocketOutage]
+        );
+        assert_eq!(
+            script.active_modes_at(secs(15)),
+            vec![FaultMode::WebSocketOutage]
+        );
+
+        let slow_acks = FaultMode::SlowAcks {
+            delay: Duration::from_millis(500),
+        };
+        script.toggle_on(slow_acks.clone());
+        assert_eq!(script.active_modes_at(secs(30)), vec![slow_acks.clone()]);
+        assert_eq!(script.ack_delay(), Some(Duration::from_millis(500)));
+
+        script.toggle_off(&slow_acks);
+        assert!(script.active_modes_at(secs(30)).is_empty());
+    }
+
+    #[test]
+    pub fn random_rejects_follow_rejection_rate() {
+        let script = FaultScript::new(vec![], 7);
+        assert!(!script.should_reject());
+
+        script.toggle_on(FaultMode::RandomRejects {
+            rejection_rate: dec!(30),
+        });
+        let rejected = (0..10_000).filter(|_| script.should_reject()).count();
+
+        assert!((2_700..3_300).contains(&rejected), "rejected {rejected}");
+    }
+}
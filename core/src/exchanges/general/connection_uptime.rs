@@ -0,0 +1,86 @@
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct ConnectionUptimeSnapshot {
+    /// Total time of established websocket connection including current one
+    pub connected_duration: Duration,
+    pub disconnections_count: u64,
+}
+
+#[derive(Debug, Default)]
+struct ConnectionUptimeState {
+    connected_since: Option<DateTime>,
+    /// Duration of finished connections
+    connected_duration: Duration,
+    disconnections_count: u64,
+}
+
+/// Time of established websocket connection of exchange account
+#[derive(Debug, Default)]
+pub struct ConnectionUptime {
+    state: Mutex<ConnectionUptimeState>,
+}
+
+impl ConnectionUptime {
+    pub fn register_connected(&self, time: DateTime) {
+        let mut state = self.state.lock();
+        if state.connected_since.is_none() {
+            state.connected_since = Some(time);
+        }
+    }
+
+    /// Failed connection attempts aren't counted as disconnections
+    pub fn register_disconnected(&self, time: DateTime) {
+        let mut state = self.state.lock();
+        if let Some(connected_since) = state.connected_since.take() {
+            state.connected_duration += elapsed(connected_since, time);
+            state.disconnections_count += 1;
+        }
+    }
+
+    pub fn snapshot(&self, now: DateTime) -> ConnectionUptimeSnapshot {
+        let state = self.state.lock();
+        let current_connection = state
+            .connected_since
+            .map(|connected_since| elapsed(connected_since, now))
+            .unwrap_or_default();
+
+        ConnectionUptimeSnapshot {
+            connected_duration: state.connected_duration + current_connection,
+            disconnections_count: state.disconnections_count,
+        }
+    }
+}
+
+fn elapsed(from: DateTime, to: DateTime) -> Duration {
+    (to - from).to_std().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    pub fn uptime_with_reconnection() {
+        let uptime = ConnectionUptime::default();
+        let start = Utc.ymd(2022, 10, 25).and_hms(12, 0, 0);
+        let at = |secs| start + chrono::Duration::seconds(secs);
+
+        // failed connection attempt
+        uptime.register_disconnected(at(0));
+        uptime.register_connected(at(10));
+        uptime.register_disconnected(at(70));
+        uptime.register_connected(at(100));
+
+        assert_eq!(
+            uptime.snapshot(at(130)),
+            ConnectionUptimeSnapshot {
+                connected_duration: Duration::from_secs(90),
+                disconnections_count: 1,
+            }
+        );
+    }
+}
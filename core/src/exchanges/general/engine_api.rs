@@ -27,10 +27,20 @@ impl EngineApi {
             self.exchange.exchange_account_id
         );
 
-        let active_positions = self
+        let active_positions = match self
             .exchange
             .get_active_positions(cancellation_token.clone())
-            .await;
+            .await
+        {
+            Ok(active_positions) => active_positions,
+            Err(err) => {
+                log::error!(
+                    "Unable to close active positions for exchange {}: {err:?}",
+                    self.exchange.exchange_account_id
+                );
+                return Vec::new();
+            }
+        };
 
         let get_closed_positions_futures = active_positions
             .iter()
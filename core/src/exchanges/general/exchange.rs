@@ -1,37 +1,57 @@
 use std::sync::{Arc, Weak};
 
 use anyhow::{bail, Context, Result};
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
 use itertools::Itertools;
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::send_expected::SendExpectedByRef;
 use mmb_utils::{nothing_to_do, DateTime};
 use parking_lot::Mutex;
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast, oneshot};
 
 use super::commission::Commission;
+use super::connection_uptime::{ConnectionUptime, ConnectionUptimeSnapshot};
+use super::fee_tiers::{FeeTierReport, FeeTierTracker};
+use super::parsing_workers::{ParsingMetrics, ParsingStats, ParsingWorkers};
 use super::polling_timeout_manager::PollingTimeoutManager;
+use super::refresh::OnDemandRefreshes;
 use super::symbol::Symbol;
+use super::venue_metrics::{VenueMetrics, VenueMetricsSnapshot};
 use crate::exchanges::common::{ActivePosition, ClosedPosition, MarketId, SpecificCurrencyPair};
+use crate::exchanges::connection_pool::ConnectionPoolStats;
 use crate::exchanges::events::{
     BalanceUpdateEvent, ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent,
-    LiquidationPriceEvent, Trade,
+    LiquidationPriceEvent, MarketPausedEvent, Trade,
 };
 use crate::exchanges::general::features::{BalancePositionOption, ExchangeFeatures};
 use crate::exchanges::general::order::cancel::CancelOrderResult;
 use crate::exchanges::general::order::create::CreateOrderResult;
 use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::host_selection::HostStats;
+use crate::exchanges::margin::{LiquidationRisk, MarginInfo, MarginRisk};
+use crate::exchanges::payload_anomalies::PayloadAnomaly;
 use crate::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
 use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
+use crate::lifecycle::leader_election::Leadership;
 use crate::misc::derivative_position::DerivativePosition;
 use crate::misc::time::time_manager;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
 use crate::orders::buffered_fills::buffered_canceled_orders_manager::BufferedCanceledOrdersManager;
 use crate::orders::buffered_fills::buffered_fills_manager::BufferedFillsManager;
+use crate::orders::currency_restrictions::CurrencyRestrictions;
 use crate::orders::event::OrderEventType;
+use crate::orders::good_till_date::GoodTillDateScheduler;
+use crate::orders::market_rollout::MarketRollout;
 use crate::orders::order::OrderSide;
 use crate::orders::pool::OrdersPool;
+use crate::orders::reduce_only::ReduceOnlyMode;
+use crate::orders::risk_engine::RiskEngine;
 use crate::orders::{order::ExchangeOrderId, pool::OrderRef};
+use crate::services::index_price::IndexPriceService;
+use crate::services::value_at_risk::ValueAtRiskService;
+use crate::settings::{CurrencyRestrictionsSettings, MarginRiskSettings};
 use crate::{
     exchanges::common::ExchangeAccountId,
     exchanges::{
@@ -47,21 +67,22 @@ use crate::connectivity::{
 };
 use crate::exchanges::block_reasons::WEBSOCKET_DISCONNECTED;
 use crate::exchanges::exchange_blocker::{BlockType, ExchangeBlocker};
-use crate::infrastructure::spawn_future;
+use crate::infrastructure::{spawn_future, spawn_isolated_future};
 use crate::orders::order::ClientOrderId;
 use crate::{
     exchanges::common::{Amount, CurrencyCode, Price},
     orders::event::OrderEvent,
 };
+use futures::future::join_all;
 use futures::FutureExt;
 use mmb_utils::infrastructure::SpawnFutureFlags;
 use std::fmt::Debug;
 use std::ops::DerefMut;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum RequestResult<T> {
     Success(T),
     Error(ExchangeError),
@@ -96,11 +117,19 @@ pub struct Exchange {
     pub currencies: Mutex<Vec<CurrencyCode>>,
     pub leverage_by_currency_pair: DashMap<CurrencyPair, Decimal>,
     pub order_book_top: DashMap<CurrencyPair, OrderBookTop>,
+    /// Order books of engine which are updated by internal events loop
+    pub(super) local_snapshots: Mutex<Option<Arc<Mutex<LocalSnapshotsService>>>>,
+    /// Scheduler of cancellations of created good-till-date orders
+    pub(super) good_till_date: Mutex<Option<Arc<GoodTillDateScheduler>>>,
     pub exchange_client: BoxExchangeClient,
     pub(super) features: ExchangeFeatures,
     pub(super) events_channel: broadcast::Sender<ExchangeEvent>,
     pub(super) lifetime_manager: Arc<AppLifetimeManager>,
     pub(super) commission: Commission,
+    pub(super) venue_metrics: VenueMetrics,
+    parsing_metrics: ParsingMetrics,
+    pub(super) fee_tiers: FeeTierTracker,
+    connection_uptime: ConnectionUptime,
     pub(super) wait_cancel_order: DashMap<ClientOrderId, broadcast::Sender<()>>,
     pub(super) wait_finish_order: DashMap<ClientOrderId, broadcast::Sender<OrderRef>>,
     pub(super) polling_trades_counts: DashMap<ExchangeAccountId, u32>,
@@ -109,8 +138,30 @@ pub struct Exchange {
     pub(super) orders_created_events: DashMap<ClientOrderId, oneshot::Sender<()>>,
     pub(super) last_trades_update_time: DashMap<MarketId, DateTime>,
     pub(super) last_trades: DashMap<MarketId, Trade>,
+    /// Price and receipt time of the last trade which isn't bad print. It's updated regardless
+    /// of trades requesting, so it's reference price of price sanity check
+    pub(super) last_trade_prices: DashMap<MarketId, (Price, DateTime)>,
+    /// Counts of erroneous trade prints excluded from trades events
+    pub(super) bad_prints_counts: DashMap<CurrencyPair, u64>,
+    /// Last trade ids of trades stream to detect gaps if exchange supports trades backfill
+    pub(super) last_stream_trade_ids: DashMap<MarketId, u64>,
+    pub(super) on_demand_refreshes: OnDemandRefreshes,
     pub(super) timeout_manager: Arc<TimeoutManager>,
     pub(crate) balance_manager: Mutex<Option<Weak<Mutex<BalanceManager>>>>,
+    pub(super) currency_restrictions: Mutex<CurrencyRestrictions>,
+    pub(super) margin_risk: Mutex<MarginRisk>,
+    pub(super) reduce_only_mode: Mutex<Arc<ReduceOnlyMode>>,
+    pub(super) market_rollout: Mutex<Arc<MarketRollout>>,
+    pub(super) risk_engine: Mutex<Option<Arc<dyn RiskEngine>>>,
+    pub(super) value_at_risk: Mutex<Option<Arc<ValueAtRiskService>>>,
+    pub(super) index_prices: Mutex<Option<Arc<IndexPriceService>>>,
+    leadership: Mutex<Arc<Leadership>>,
+    /// Only public market data is received, authenticated requests are not allowed
+    market_data_only: AtomicBool,
+    /// Markets which order books stopped updating while connection is healthy
+    stale_markets: DashSet<CurrencyPair>,
+    /// Markets which quoting is paused by operator
+    paused_markets: DashSet<CurrencyPair>,
     pub(super) buffered_fills_manager: Mutex<BufferedFillsManager>,
     pub(super) buffered_canceled_orders_manager: Mutex<BufferedCanceledOrdersManager>,
     // It allows to send and receive notification about event in websocket channel
@@ -131,9 +182,13 @@ pub struct Exchange {
             Option<oneshot::Receiver<CancelOrderResult>>,
         ),
     >,
-    exchange_blocker: Weak<ExchangeBlocker>,
-    ws_sender: Mutex<Option<WsSender>>,
-    auto_reconnect: AtomicBool,
+    pub(super) exchange_blocker: Weak<ExchangeBlocker>,
+    pub(super) ws_sender: Mutex<Option<WsSender>>,
+    pub(super) auto_reconnect: AtomicBool,
+    /// Connector failed and is restarted by supervision
+    pub(super) is_account_failed: AtomicBool,
+    /// Websockets were connected before, so events could be missed while they were reconnected
+    was_connected: AtomicBool,
 
     // Temporary fix before integration ExchangeBlocker to wait_order_finish/wait_cancel_order fallbacks #641
     timeout: Duration,
@@ -156,6 +211,13 @@ impl Exchange {
         commission: Commission,
     ) -> Arc<Self> {
         let polling_timeout_manager = PollingTimeoutManager::new(timeout_arguments);
+        let fee_tiers = FeeTierTracker::new(
+            exchange_client
+                .get_settings()
+                .fee_tiers
+                .clone()
+                .unwrap_or_default(),
+        );
 
         Arc::new_cyclic(move |e| {
             Self::setup_exchange_client(e.clone(), exchange_client.as_mut());
@@ -173,9 +235,15 @@ impl Exchange {
                 events_channel,
                 timeout_manager,
                 commission,
+                venue_metrics: Default::default(),
+                parsing_metrics: Default::default(),
+                fee_tiers,
+                connection_uptime: Default::default(),
                 symbols: Default::default(),
                 currencies: Default::default(),
                 order_book_top: Default::default(),
+                local_snapshots: Mutex::new(None),
+                good_till_date: Mutex::new(None),
                 wait_cancel_order: DashMap::new(),
                 wait_finish_order: DashMap::new(),
                 polling_trades_counts: DashMap::new(),
@@ -185,11 +253,28 @@ impl Exchange {
                 leverage_by_currency_pair: DashMap::new(),
                 last_trades_update_time: DashMap::new(),
                 last_trades: DashMap::new(),
+                last_trade_prices: DashMap::new(),
+                bad_prints_counts: DashMap::new(),
+                last_stream_trade_ids: DashMap::new(),
+                on_demand_refreshes: Default::default(),
                 balance_manager: Mutex::new(None),
+                currency_restrictions: Default::default(),
+                margin_risk: Default::default(),
+                reduce_only_mode: Default::default(),
+                market_rollout: Default::default(),
+                risk_engine: Mutex::new(None),
+                value_at_risk: Mutex::new(None),
+                index_prices: Mutex::new(None),
+                leadership: Default::default(),
+                market_data_only: AtomicBool::new(false),
+                stale_markets: Default::default(),
+                paused_markets: Default::default(),
                 buffered_fills_manager: Default::default(),
                 exchange_blocker,
                 buffered_canceled_orders_manager: Default::default(),
                 auto_reconnect: AtomicBool::new(false),
+                is_account_failed: AtomicBool::new(false),
+                was_connected: AtomicBool::new(false),
                 timeout,
             }
         })
@@ -232,6 +317,7 @@ impl Exchange {
             move |currency_pair, trade_id, price, quantity, order_side, transaction_time| {
                 match exchange_weak.upgrade() {
                     Some(exchange) => {
+                        exchange.check_trades_gap(currency_pair, &trade_id);
                         exchange.handle_trade(
                             currency_pair,
                             trade_id,
@@ -259,7 +345,27 @@ impl Exchange {
         }));
     }
 
-    fn on_websocket_message(&self, msg: &str) {
+    fn on_websocket_message(&self, msg: String, parsing_workers: Option<&ParsingWorkers>) {
+        let receive_time = Instant::now();
+        match parsing_workers {
+            Some(parsing_workers) => {
+                let worker_index = parsing_workers
+                    .worker_index(self.exchange_client.get_message_partition_key(&msg));
+                if !parsing_workers.dispatch(worker_index, msg) {
+                    log::warn!(
+                        "Parsing worker {worker_index} of {} is stopped, message is dropped",
+                        self.exchange_account_id
+                    );
+                }
+            }
+            None => self.parse_websocket_message(&msg, Duration::ZERO),
+        }
+        self.parsing_metrics
+            .register_received(receive_time.elapsed());
+    }
+
+    pub(super) fn parse_websocket_message(&self, msg: &str, queue_time: Duration) {
+        let parse_start_time = Instant::now();
         self.maybe_log_websocket_message(msg);
 
         let callback_outcome = self.exchange_client.on_websocket_message(msg);
@@ -269,6 +375,8 @@ impl Exchange {
                 error
             );
         }
+        self.parsing_metrics
+            .register_parsed(queue_time, parse_start_time.elapsed());
     }
 
     fn on_connecting(&self) {
@@ -289,11 +397,17 @@ impl Exchange {
         }
     }
 
-    fn on_connected(&self) {
+    fn on_connected(self: &Arc<Self>) {
         log::info!("Exchange account id {} connected", self.exchange_account_id);
+        self.connection_uptime
+            .register_connected(time_manager::now());
         if let Some(exchange_blocker) = self.exchange_blocker.upgrade() {
             exchange_blocker.unblock(self.exchange_account_id, WEBSOCKET_DISCONNECTED);
         }
+
+        if self.was_connected.swap(true, Ordering::SeqCst) {
+            self.spawn_orders_resync();
+        }
     }
 
     fn on_disconnected(self: &Arc<Self>) {
@@ -301,6 +415,8 @@ impl Exchange {
             "Exchange account id {} disconnected",
             self.exchange_account_id
         );
+        self.connection_uptime
+            .register_disconnected(time_manager::now());
 
         if let Some(x) = self.exchange_blocker.upgrade() {
             x.block(
@@ -326,7 +442,12 @@ impl Exchange {
             Ok(())
         }
         .boxed();
-        spawn_future(&action, SpawnFutureFlags::STOP_BY_TOKEN, future);
+        spawn_isolated_future(
+            &action,
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            future,
+            self.account_failure_handler(),
+        );
     }
 
     fn maybe_log_websocket_message(&self, msg: &str) {
@@ -343,6 +464,193 @@ impl Exchange {
         *self.balance_manager.lock() = Some(Arc::downgrade(&balance_manager));
     }
 
+    pub fn setup_local_snapshots(&self, local_snapshots: Arc<Mutex<LocalSnapshotsService>>) {
+        *self.local_snapshots.lock() = Some(local_snapshots);
+    }
+
+    pub fn setup_good_till_date(&self, good_till_date: Arc<GoodTillDateScheduler>) {
+        *self.good_till_date.lock() = Some(good_till_date);
+    }
+
+    pub fn setup_currency_restrictions(&self, settings: &CurrencyRestrictionsSettings) {
+        *self.currency_restrictions.lock() = CurrencyRestrictions::new(settings);
+    }
+
+    pub fn setup_margin_risk(&self, settings: &MarginRiskSettings) {
+        *self.margin_risk.lock() = MarginRisk::new(settings);
+    }
+
+    pub(crate) fn is_margin_risk_enabled(&self) -> bool {
+        self.margin_risk.lock().is_enabled()
+    }
+
+    /// Saves margin state polled from exchange and returns liquidation risk level
+    pub fn update_margin_info(&self, margin_info: MarginInfo) -> LiquidationRisk {
+        self.margin_risk.lock().update(margin_info)
+    }
+
+    pub fn margin_info(&self) -> Option<MarginInfo> {
+        self.margin_risk.lock().margin_info()
+    }
+
+    pub(crate) fn set_margin_info_unsupported(&self) {
+        self.margin_risk.lock().set_unsupported();
+    }
+
+    /// Reduce-only mode is shared by all exchanges of engine
+    pub fn setup_reduce_only_mode(&self, reduce_only_mode: Arc<ReduceOnlyMode>) {
+        *self.reduce_only_mode.lock() = reduce_only_mode;
+    }
+
+    pub fn reduce_only_mode(&self) -> Arc<ReduceOnlyMode> {
+        self.reduce_only_mode.lock().clone()
+    }
+
+    /// Rollout modes of markets are shared by all exchanges of engine
+    pub fn setup_market_rollout(&self, market_rollout: Arc<MarketRollout>) {
+        *self.market_rollout.lock() = market_rollout;
+    }
+
+    pub fn market_rollout(&self) -> Arc<MarketRollout> {
+        self.market_rollout.lock().clone()
+    }
+
+    pub fn setup_risk_engine(&self, risk_engine: Arc<dyn RiskEngine>) {
+        *self.risk_engine.lock() = Some(risk_engine);
+    }
+
+    /// Orders are checked by marginal value at risk limit of engine
+    pub fn setup_value_at_risk(&self, value_at_risk: Arc<ValueAtRiskService>) {
+        *self.value_at_risk.lock() = Some(value_at_risk);
+    }
+
+    /// Order prices are checked by collar around index prices
+    pub fn setup_index_prices(&self, index_prices: Arc<IndexPriceService>) {
+        *self.index_prices.lock() = Some(index_prices);
+    }
+
+    pub fn setup_leadership(&self, leadership: Arc<Leadership>) {
+        *self.leadership.lock() = leadership;
+    }
+
+    /// Orders can be created only by leader instance
+    pub fn is_leader(&self) -> bool {
+        self.leadership.lock().is_leader()
+    }
+
+    /// Fencing token of leadership which authorizes order creation
+    pub fn fencing_token(&self) -> Option<u64> {
+        self.leadership.lock().fencing_token()
+    }
+
+    pub fn is_fencing_token_valid(&self, fencing_token: Option<u64>) -> bool {
+        self.leadership.lock().is_fencing_token_valid(fencing_token)
+    }
+
+    pub fn supports_reduce_only(&self) -> bool {
+        self.features.order_features.supports_reduce_only
+    }
+
+    /// Should be called before connecting, so private websocket isn't opened
+    pub fn setup_market_data_only(&self) {
+        self.market_data_only.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_market_data_only(&self) -> bool {
+        self.market_data_only.load(Ordering::SeqCst)
+    }
+
+    pub fn supports_good_till_date(&self) -> bool {
+        self.features.order_features.supports_good_till_date
+    }
+
+    pub fn supports_quote_order_amount(&self) -> bool {
+        self.features.order_features.supports_quote_order_amount
+    }
+
+    pub fn set_market_stale(&self, currency_pair: CurrencyPair, is_stale: bool) {
+        match is_stale {
+            true => {
+                let _ = self.stale_markets.insert(currency_pair);
+            }
+            false => {
+                let _ = self.stale_markets.remove(&currency_pair);
+            }
+        }
+    }
+
+    pub fn is_market_stale(&self, currency_pair: CurrencyPair) -> bool {
+        self.stale_markets.contains(&currency_pair)
+    }
+
+    /// Pauses quoting on market: new orders aren't created and resting orders are canceled.
+    /// Strategies are notified by `ExchangeEvent::MarketPaused`. Returns `false` if market is
+    /// already paused
+    pub fn pause_market(
+        self: &Arc<Self>,
+        currency_pair: CurrencyPair,
+        cancellation_token: CancellationToken,
+    ) -> bool {
+        if !self.paused_markets.insert(currency_pair) {
+            return false;
+        }
+
+        log::warn!(
+            "Quoting on {currency_pair} on {} is paused",
+            self.exchange_account_id
+        );
+        self.send_market_paused_event(currency_pair, true);
+
+        let orders = self.orders.not_finished.by_market(currency_pair);
+        let exchange = self.clone();
+        let action = async move {
+            let cancellations = orders.into_iter().map(|order| {
+                exchange.wait_cancel_order(order, None, true, cancellation_token.clone())
+            });
+            for result in join_all(cancellations).await {
+                if let Err(error) = result {
+                    log::error!("Failed to cancel order of paused market: {error:?}");
+                }
+            }
+            Ok(())
+        };
+        let _ = spawn_future(
+            "Cancel orders of paused market",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action,
+        );
+
+        true
+    }
+
+    /// Returns `false` if market isn't paused
+    pub fn resume_market(&self, currency_pair: CurrencyPair) -> bool {
+        if self.paused_markets.remove(&currency_pair).is_none() {
+            return false;
+        }
+
+        log::info!(
+            "Quoting on {currency_pair} on {} is resumed",
+            self.exchange_account_id
+        );
+        self.send_market_paused_event(currency_pair, false);
+
+        true
+    }
+
+    pub fn is_market_paused(&self, currency_pair: CurrencyPair) -> bool {
+        self.paused_markets.contains(&currency_pair)
+    }
+
+    fn send_market_paused_event(&self, currency_pair: CurrencyPair, is_paused: bool) {
+        self.events_channel
+            .send_expected(ExchangeEvent::MarketPaused(MarketPausedEvent {
+                exchange_account_id: self.exchange_account_id,
+                currency_pair,
+                is_paused,
+            }));
+    }
+
     pub async fn disconnect(self: Arc<Self>) {
         // prevent auto reconnect
         self.auto_reconnect.store(false, Ordering::SeqCst);
@@ -357,10 +665,20 @@ impl Exchange {
             Ok(reader) => {
                 // enable auto reconnect after first success
                 self.auto_reconnect.store(true, Ordering::SeqCst);
-                spawn_future(
+                let workers_count = self
+                    .exchange_client
+                    .get_settings()
+                    .parsing_workers
+                    .as_ref()
+                    .map_or(0, |x| x.workers_count);
+                self.parsing_metrics.set_workers_count(workers_count);
+                let parsing_workers =
+                    (workers_count > 0).then(|| ParsingWorkers::start(self, workers_count));
+                spawn_isolated_future(
                     &format!("Exchange account id {} reader", self.exchange_account_id),
                     SpawnFutureFlags::STOP_BY_TOKEN,
-                    Self::reader_future(Arc::downgrade(self), reader).boxed(),
+                    Self::reader_future(Arc::downgrade(self), reader, parsing_workers).boxed(),
+                    self.account_failure_handler(),
                 );
                 self.on_connected();
                 Ok(())
@@ -372,14 +690,15 @@ impl Exchange {
         }
     }
 
-    /// Read websocket messages and forward to upstream callbacks
+    /// Read websocket messages and forward to upstream callbacks directly or through parsing workers
     async fn reader_future(
         instance: Weak<Self>,
         mut reader: tokio::sync::mpsc::UnboundedReceiver<String>,
+        parsing_workers: Option<ParsingWorkers>,
     ) -> Result<()> {
         while let Some(msg) = reader.recv().await {
             match instance.upgrade() {
-                Some(strong) => strong.on_websocket_message(&msg),
+                Some(strong) => strong.on_websocket_message(msg, parsing_workers.as_ref()),
                 None => {
                     // Exchange doesn't exist
                     return Ok(());
@@ -401,6 +720,15 @@ impl Exchange {
     ) -> Result<tokio::sync::mpsc::UnboundedReceiver<String>, ConnectivityError> {
         log::info!("Websocket: Connecting on {}", self.exchange_account_id);
 
+        let own_websockets = self
+            .exchange_client
+            .connect_own_websockets(self.is_market_data_only())
+            .await;
+        if let Some(connected) = own_websockets {
+            return connected
+                .map_err(|e| ConnectivityError::FailedToConnectConnector(e.to_string()));
+        }
+
         if !self
             .exchange_client
             .is_websocket_enabled(WebSocketRole::Main)
@@ -420,7 +748,13 @@ impl Exchange {
                 ConnectivityError::FailedToGetParams(WebSocketRole::Main, e.to_string())
             })?;
 
-        let secondary = if self
+        let secondary = if self.is_market_data_only() {
+            log::info!(
+                "Secondary websocket isn't used in market data only mode for {}",
+                self.exchange_account_id
+            );
+            None
+        } else if self
             .exchange_client
             .is_websocket_enabled(WebSocketRole::Secondary)
         {
@@ -469,7 +803,8 @@ impl Exchange {
         role: WebSocketRole,
     ) -> Result<WebSocketParams> {
         let ws_url = self.exchange_client.create_ws_url(role).await?;
-        Ok(WebSocketParams::new(ws_url))
+        let egress = self.exchange_client.get_settings().egress.clone();
+        Ok(WebSocketParams::new(ws_url).with_egress(egress))
     }
 
     pub(crate) fn add_event_on_order_change(
@@ -482,10 +817,7 @@ impl Exchange {
         }
 
         if order_ref.is_finished() {
-            let _ = self
-                .orders
-                .not_finished
-                .remove(&order_ref.client_order_id());
+            let _ = self.orders.not_finished.remove(order_ref);
         }
 
         let event = ExchangeEvent::OrderEvent(OrderEvent::new(order_ref.clone(), event_type));
@@ -501,6 +833,10 @@ impl Exchange {
         cancellation_token: CancellationToken,
         add_missing_open_orders: bool,
     ) {
+        if self.is_market_data_only() {
+            return;
+        }
+
         match self.get_open_orders(add_missing_open_orders).await {
             Err(error) => {
                 log::error!(
@@ -586,7 +922,7 @@ impl Exchange {
     pub async fn get_active_positions(
         &self,
         cancellation_token: CancellationToken,
-    ) -> Vec<ActivePosition> {
+    ) -> Result<Vec<ActivePosition>> {
         for retry_attempt in 1..=5 {
             self.timeout_manager
                 .reserve_when_available(
@@ -599,7 +935,7 @@ impl Exchange {
                 .await;
 
             match self.get_active_positions_by_features().await {
-                Ok(positions) => return positions,
+                Ok(positions) => return Ok(positions),
                 Err(error) => {
                     print_warn(
                         retry_attempt,
@@ -612,12 +948,10 @@ impl Exchange {
             }
         }
 
-        log::warn!(
-            "Get active positions with for {} reached maximum retries - reconnecting",
+        bail!(
+            "Get active positions for {} reached maximum retries",
             self.exchange_account_id
-        );
-
-        Vec::new()
+        )
     }
 
     async fn get_active_positions_by_features(&self) -> Result<Vec<ActivePosition>> {
@@ -832,6 +1166,45 @@ impl Exchange {
     pub(crate) fn get_timeout(&self) -> Duration {
         self.timeout
     }
+
+    pub(crate) fn get_commission(&self) -> &Commission {
+        &self.commission
+    }
+
+    /// Recent order creation roundtrip and reject rate for choosing venue of taker orders
+    pub fn get_venue_metrics(&self) -> VenueMetricsSnapshot {
+        self.venue_metrics.snapshot()
+    }
+
+    /// Volume of the last 30 days, maker rebates and distance to the next fee tier
+    pub fn get_fee_tier_report(&self) -> FeeTierReport {
+        self.fee_tiers.report(time_manager::now())
+    }
+
+    /// Timings of websocket messages parsing inline or by parsing workers
+    pub fn get_parsing_stats(&self) -> ParsingStats {
+        self.parsing_metrics.snapshot()
+    }
+
+    pub fn get_connection_uptime(&self) -> ConnectionUptimeSnapshot {
+        self.connection_uptime.snapshot(time_manager::now())
+    }
+
+    pub fn get_bad_prints_count(&self, currency_pair: CurrencyPair) -> u64 {
+        self.bad_prints_counts.get(&currency_pair).map_or(0, |x| *x)
+    }
+
+    pub fn get_host_stats(&self) -> Vec<HostStats> {
+        self.exchange_client.get_host_stats()
+    }
+
+    pub fn get_connection_pool_stats(&self) -> Option<ConnectionPoolStats> {
+        self.exchange_client.get_connection_pool_stats()
+    }
+
+    pub fn get_payload_anomalies(&self) -> Vec<PayloadAnomaly> {
+        self.exchange_client.get_payload_anomalies()
+    }
 }
 
 /// Helper method only for tests
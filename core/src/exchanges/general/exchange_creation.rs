@@ -1,12 +1,15 @@
 use std::sync::{Arc, Weak};
 
 use super::commission::Commission;
+use crate::exchanges::common::ExchangeAccountId;
+#[cfg(unix)]
+use crate::exchanges::connector_process::client::start_connector_process;
 use crate::exchanges::events::ExchangeEvent;
 use crate::exchanges::exchange_blocker::ExchangeBlocker;
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
 use crate::lifecycle::launcher::EngineBuildConfig;
 use crate::orders::pool::OrdersPool;
-use crate::settings::ExchangeSettings;
+use crate::settings::{Environment, ExchangeSettings};
 use crate::{
     exchanges::{
         general::exchange::Exchange,
@@ -15,7 +18,9 @@ use crate::{
     },
     settings::CoreSettings,
 };
+use anyhow::{bail, Result};
 use mmb_utils::infrastructure::WithExpect;
+use mmb_utils::logger::print_info;
 use tokio::sync::broadcast;
 
 pub fn create_timeout_manager(
@@ -43,6 +48,56 @@ pub fn create_timeout_manager(
     TimeoutManager::new(request_timeout_managers)
 }
 
+/// Checks that hosts of every exchange account belong to environment configured in settings,
+/// so test configs don't trade real funds and vice versa
+pub fn check_environments(
+    core_settings: &CoreSettings,
+    build_settings: &EngineBuildConfig,
+) -> Result<()> {
+    for exchange_settings in &core_settings.exchanges {
+        let exchange_account_id = exchange_settings.exchange_account_id;
+        let hosts_environment = build_settings.supported_exchange_clients
+            [&exchange_account_id.exchange_id]
+            .get_hosts_environment(exchange_settings);
+
+        check_environment(
+            exchange_account_id,
+            exchange_settings.environment,
+            hosts_environment,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn check_environment(
+    exchange_account_id: ExchangeAccountId,
+    environment: Option<Environment>,
+    hosts_environment: Option<Environment>,
+) -> Result<()> {
+    match (environment, hosts_environment) {
+        (Some(environment), Some(hosts_environment)) if environment != hosts_environment => bail!(
+            "Exchange account {exchange_account_id} is configured for {environment} environment, but its hosts belong to {hosts_environment} environment"
+        ),
+        (Some(environment), Some(_)) => {
+            print_info(format!(
+                "Exchange account {exchange_account_id} works in {environment} environment"
+            ));
+        }
+        (Some(environment), None) => log::warn!(
+            "Exchange account {exchange_account_id} is configured for {environment} environment, but environment of its hosts is unknown"
+        ),
+        (None, Some(hosts_environment)) => log::warn!(
+            "Environment isn't set for exchange account {exchange_account_id}, its hosts belong to {hosts_environment} environment"
+        ),
+        (None, None) => log::warn!(
+            "Environment isn't set for exchange account {exchange_account_id} and environment of its hosts is unknown"
+        ),
+    }
+
+    Ok(())
+}
+
 pub async fn create_exchange(
     user_settings: &ExchangeSettings,
     build_settings: &EngineBuildConfig,
@@ -50,18 +105,30 @@ pub async fn create_exchange(
     lifetime_manager: Arc<AppLifetimeManager>,
     timeout_manager: Arc<TimeoutManager>,
     exchange_blocker: Weak<ExchangeBlocker>,
+    market_data_only: bool,
 ) -> Arc<Exchange> {
     let exchange_account_id = user_settings.exchange_account_id;
     let exchange_client_builder =
         &build_settings.supported_exchange_clients[&exchange_account_id.exchange_id];
     let orders = OrdersPool::new();
 
-    let exchange_client = exchange_client_builder.create_exchange_client(
-        user_settings.clone(),
-        events_channel.clone(),
-        lifetime_manager.clone(),
-        orders.clone(),
-    );
+    let exchange_client = match &user_settings.connector_process {
+        #[cfg(unix)]
+        Some(process_settings) => start_connector_process(
+            user_settings.clone(),
+            process_settings.clone(),
+            events_channel.clone(),
+            lifetime_manager.clone(),
+        )
+        .await
+        .with_expect(|| format!("Unable to start connector process of {exchange_account_id}")),
+        _ => exchange_client_builder.create_exchange_client(
+            user_settings.clone(),
+            events_channel.clone(),
+            lifetime_manager.clone(),
+            orders.clone(),
+        ),
+    };
 
     let exchange = Exchange::new(
         exchange_account_id,
@@ -76,7 +143,16 @@ pub async fn create_exchange(
         Commission::default(),
     );
 
-    exchange.build_symbols(&user_settings.currency_pairs).await;
+    if market_data_only {
+        exchange.setup_market_data_only();
+    }
+
+    exchange
+        .build_symbols(
+            &user_settings.currency_pairs,
+            &user_settings.currency_pairs_filter,
+        )
+        .await;
 
     exchange
         .connect()
@@ -85,3 +161,29 @@ pub async fn create_exchange(
 
     exchange
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Some(Environment::Sandbox), Some(Environment::Sandbox), true)]
+    #[case(Some(Environment::Production), Some(Environment::Production), true)]
+    #[case(Some(Environment::Sandbox), Some(Environment::Production), false)]
+    #[case(Some(Environment::Production), Some(Environment::Sandbox), false)]
+    #[case(Some(Environment::Sandbox), None, true)]
+    #[case(None, Some(Environment::Production), true)]
+    pub fn environment_mismatch_is_refused(
+        #[case] environment: Option<Environment>,
+        #[case] hosts_environment: Option<Environment>,
+        #[case] is_ok: bool,
+    ) {
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+
+        assert_eq!(
+            check_environment(exchange_account_id, environment, hosts_environment).is_ok(),
+            is_ok
+        );
+    }
+}
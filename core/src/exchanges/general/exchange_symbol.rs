@@ -2,15 +2,20 @@ use dashmap::DashMap;
 use itertools::Itertools;
 use mmb_utils::infrastructure::WithExpect;
 use rust_decimal_macros::dec;
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::exchanges::common::{CurrencyCode, CurrencyId, ExchangeAccountId};
-use crate::settings::CurrencyPairSetting;
+use crate::exchanges::common::{Amount, CurrencyCode, CurrencyId, CurrencyPair, ExchangeAccountId};
+use crate::settings::{CurrencyPairSetting, CurrencyPairsFilterSettings};
 
 use super::{exchange::Exchange, symbol::Symbol};
 
 impl Exchange {
-    pub async fn build_symbols(&self, currency_pair_settings: &Option<Vec<CurrencyPairSetting>>) {
+    pub async fn build_symbols(
+        &self,
+        currency_pair_settings: &Option<Vec<CurrencyPairSetting>>,
+        currency_pairs_filter: &Option<CurrencyPairsFilterSettings>,
+    ) {
         let exchange_symbols = &self.request_symbols_with_retries().await;
 
         let supported_currencies = get_supported_currencies(exchange_symbols);
@@ -28,11 +33,57 @@ impl Exchange {
             )
         });
 
-        self.setup_symbols(get_symbols(
-            currency_pairs,
-            exchange_symbols,
-            self.exchange_account_id,
-        ));
+        let mut symbols = get_symbols(currency_pairs, exchange_symbols, self.exchange_account_id);
+
+        let patterns = currency_pairs
+            .iter()
+            .filter_map(|x| match x {
+                CurrencyPairSetting::Specific(pattern) if is_wildcard(pattern) => {
+                    Some(pattern.as_str())
+                }
+                _ => None,
+            })
+            .collect_vec();
+        if !patterns.is_empty() {
+            let filter = currency_pairs_filter.clone().unwrap_or_default();
+            let daily_volumes = match filter.min_daily_volume {
+                Some(_) => self.request_daily_volumes().await,
+                None => HashMap::new(),
+            };
+
+            let expanded = expand_wildcards(&patterns, exchange_symbols, &filter, &daily_volumes);
+            log::info!(
+                "Currency pairs {patterns:?} are expanded to {} symbols on exchange {}",
+                expanded.len(),
+                self.exchange_account_id
+            );
+            symbols.extend(expanded);
+        }
+
+        self.setup_symbols(
+            symbols
+                .into_iter()
+                .unique_by(|x| x.currency_pair())
+                .collect(),
+        );
+    }
+
+    /// Volumes aren't filtered if exchange doesn't provide them
+    async fn request_daily_volumes(&self) -> HashMap<CurrencyPair, Amount> {
+        match self.exchange_client.get_daily_volumes().await {
+            Ok(Some(volumes)) => volumes,
+            Ok(None) => {
+                log::warn!(
+                    "Exchange {} doesn't provide daily volumes, min volume filter is ignored",
+                    self.exchange_account_id
+                );
+                HashMap::new()
+            }
+            Err(error) => panic!(
+                "Unable to get daily volumes for {}: {error:?}",
+                self.exchange_account_id
+            ),
+        }
     }
 
     async fn request_symbols_with_retries(&self) -> Vec<Arc<Symbol>> {
@@ -109,6 +160,7 @@ fn get_symbols(
 ) -> Vec<Arc<Symbol>> {
     currency_pairs
         .iter()
+        .filter(|x| !matches!(x, CurrencyPairSetting::Specific(pattern) if is_wildcard(pattern)))
         .filter_map(|x| get_matched_currency_pair(x, exchange_symbols, exchange_account_id))
         .collect()
 }
@@ -154,3 +206,173 @@ fn get_matched_currency_pair(
 
     None
 }
+
+fn is_wildcard(currency_pair: &str) -> bool {
+    currency_pair.contains('*')
+}
+
+/// Glob matching where `*` matches any sequence of characters. Case is ignored
+fn matches_wildcard(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let value = value.to_lowercase();
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match value.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let parts = parts.collect_vec();
+    for (index, part) in parts.iter().enumerate() {
+        let is_last = index == parts.len() - 1;
+        if is_last {
+            return rest.ends_with(part);
+        }
+
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+
+    // pattern without `*`
+    rest.is_empty()
+}
+
+/// Active symbols matching any of patterns like `*-USDT` or `BTC/*` and passing filter
+fn expand_wildcards(
+    patterns: &[&str],
+    exchange_symbols: &[Arc<Symbol>],
+    filter: &CurrencyPairsFilterSettings,
+    daily_volumes: &HashMap<CurrencyPair, Amount>,
+) -> Vec<Arc<Symbol>> {
+    let is_listed = |list: &[CurrencyCode], symbol: &Symbol| {
+        list.contains(&symbol.base_currency_code) || list.contains(&symbol.quote_currency_code)
+    };
+
+    exchange_symbols
+        .iter()
+        .filter(|symbol| symbol.is_active)
+        .filter(|symbol| {
+            patterns
+                .iter()
+                .any(|pattern| match pattern.split_once(['-', '/']) {
+                    Some((base, quote)) => {
+                        matches_wildcard(base, symbol.base_currency_code.as_str())
+                            && matches_wildcard(quote, symbol.quote_currency_code.as_str())
+                    }
+                    None => matches_wildcard(pattern, symbol.currency_pair().as_str()),
+                })
+        })
+        .filter(|symbol| {
+            filter.include_currencies.is_empty() || is_listed(&filter.include_currencies, symbol)
+        })
+        .filter(|symbol| !is_listed(&filter.exclude_currencies, symbol))
+        .filter(
+            |symbol| match (filter.min_daily_volume, daily_volumes.is_empty()) {
+                (Some(min_daily_volume), false) => daily_volumes
+                    .get(&symbol.currency_pair())
+                    .is_some_and(|volume| *volume >= min_daily_volume),
+                _ => true,
+            },
+        )
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::symbol::Precision;
+    use rstest::rstest;
+    use rust_decimal_macros::dec;
+
+    fn symbol(base: &str, quote: &str, is_active: bool) -> Arc<Symbol> {
+        Arc::new(Symbol::new(
+            is_active,
+            false,
+            base.into(),
+            base.into(),
+            quote.into(),
+            quote.into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            base.into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(0.01) },
+        ))
+    }
+
+    fn exchange_symbols() -> Vec<Arc<Symbol>> {
+        vec![
+            symbol("BTC", "USDT", true),
+            symbol("ETH", "USDT", true),
+            symbol("LUNA", "USDT", false),
+            symbol("ETH", "BTC", true),
+            symbol("USDC", "USDT", true),
+        ]
+    }
+
+    fn currency_pairs(symbols: &[Arc<Symbol>]) -> Vec<String> {
+        symbols
+            .iter()
+            .map(|x| x.currency_pair().to_string())
+            .collect()
+    }
+
+    #[rstest]
+    #[case("*", "btc", true)]
+    #[case("b*", "btc", true)]
+    #[case("*c", "btc", true)]
+    #[case("b*c", "btc", true)]
+    #[case("b*t*c", "btc", true)]
+    #[case("BTC", "btc", true)]
+    #[case("e*", "btc", false)]
+    #[case("bt", "btc", false)]
+    pub fn wildcard_matching(#[case] pattern: &str, #[case] value: &str, #[case] expected: bool) {
+        assert_eq!(matches_wildcard(pattern, value), expected);
+    }
+
+    #[test]
+    pub fn expand_active_symbols_by_quote() {
+        let symbols = expand_wildcards(
+            &["*-USDT"],
+            &exchange_symbols(),
+            &CurrencyPairsFilterSettings::default(),
+            &HashMap::new(),
+        );
+
+        assert_eq!(
+            currency_pairs(&symbols),
+            vec!["btc/usdt", "eth/usdt", "usdc/usdt"]
+        );
+    }
+
+    #[test]
+    pub fn expand_with_filters() {
+        let filter = CurrencyPairsFilterSettings {
+            include_currencies: vec![],
+            exclude_currencies: vec!["usdc".into()],
+            min_daily_volume: Some(dec!(1_000_000)),
+        };
+        let daily_volumes = HashMap::from([
+            (symbol("BTC", "USDT", true).currency_pair(), dec!(5_000_000)),
+            (symbol("ETH", "USDT", true).currency_pair(), dec!(500_000)),
+            (symbol("ETH", "BTC", true).currency_pair(), dec!(2_000_000)),
+        ]);
+
+        let symbols = expand_wildcards(
+            &["*-USDT", "ETH/*"],
+            &exchange_symbols(),
+            &filter,
+            &daily_volumes,
+        );
+
+        assert_eq!(currency_pairs(&symbols), vec!["btc/usdt", "eth/btc"]);
+    }
+}
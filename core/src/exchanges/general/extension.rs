@@ -0,0 +1,98 @@
+use std::any::type_name;
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use mmb_utils::cancellation_token::CancellationToken;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::request_type::RequestType;
+
+impl Exchange {
+    /// Connector of exchange as concrete type `T`, e.g. `Binance`, if exchange is served by it.
+    /// Exchange-specific features are provided by connectors with their own extension traits,
+    /// so they don't extend common `ExchangeClient` trait
+    pub fn client_as<T: 'static>(&self) -> Option<&T> {
+        self.exchange_client.as_any().downcast_ref::<T>()
+    }
+
+    /// Executes exchange-specific `request` of connector `T` after reservation of request by rate
+    /// limits of exchange account, so extension requests share limits with other requests of engine.
+    /// Fails if exchange isn't served by connector `T`
+    pub async fn execute_extension_request<T, R>(
+        &self,
+        request: impl for<'a> FnOnce(&'a T) -> BoxFuture<'a, Result<R>>,
+        cancellation_token: CancellationToken,
+    ) -> Result<R>
+    where
+        T: 'static,
+    {
+        let client = self.client_as::<T>().with_context(|| {
+            format!(
+                "Exchange account {} isn't served by connector {}",
+                self.exchange_account_id,
+                type_name::<T>()
+            )
+        })?;
+
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::Extension,
+                None,
+                cancellation_token,
+            )?
+            .await
+            .into_result()?;
+
+        request(client).await.with_context(|| {
+            format!(
+                "Extension request of {} failed on {}",
+                type_name::<T>(),
+                self.exchange_account_id
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+
+    use super::*;
+    use crate::exchanges::general::test_helper::{get_test_exchange, TestClient};
+    use crate::infrastructure::init_lifetime_manager;
+
+    struct OtherClient;
+
+    #[tokio::test]
+    async fn client_is_downcasted_to_its_type_only() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange(false);
+
+        assert!(exchange.client_as::<TestClient>().is_some());
+        assert!(exchange.client_as::<OtherClient>().is_none());
+    }
+
+    #[tokio::test]
+    async fn extension_request_is_executed_by_matching_client() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange(false);
+
+        let result = exchange
+            .execute_extension_request(
+                |_: &TestClient| async { Ok(42) }.boxed(),
+                CancellationToken::default(),
+            )
+            .await
+            .expect("in test");
+        assert_eq!(result, 42);
+
+        let result = exchange
+            .execute_extension_request(
+                |_: &OtherClient| async { Ok(42) }.boxed(),
+                CancellationToken::default(),
+            )
+            .await;
+        assert!(result.is_err());
+    }
+}
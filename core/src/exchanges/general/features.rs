@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use crate::exchanges::events::AllowedEventSourceType;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum OpenOrdersType {
     None,
     AllCurrencyPair,
@@ -9,7 +11,7 @@ pub enum OpenOrdersType {
     OneCurrencyPair,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum RestFillsType {
     None,
     MyTrades,
@@ -22,7 +24,7 @@ impl Default for RestFillsType {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Serialize, Deserialize)]
 pub struct RestFillsFeatures {
     pub fills_type: RestFillsType,
     // TODO all over fields for check_order_fills()
@@ -33,7 +35,7 @@ impl RestFillsFeatures {
         Self { fills_type }
     }
 }
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct WebSocketOptions {
     pub execution_notification: bool,
     pub cancellation_notification: bool,
@@ -57,7 +59,7 @@ impl WebSocketOptions {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct OrderFeatures {
     pub maker_only: bool,
     pub supports_get_order_info_by_client_order_id: bool,
@@ -66,9 +68,17 @@ pub struct OrderFeatures {
     pub order_was_completed_error_for_cancellation: bool,
     pub supports_already_cancelled_order: bool,
     pub supports_stop_loss_order: bool,
+    /// Exchange cancels orders at `OrderHeader::expire_time` itself
+    pub supports_good_till_date: bool,
+    /// Exchange rejects orders with `OrderHeader::reduce_only` flag which would increase position
+    pub supports_reduce_only: bool,
+    /// Exchange sizes market orders by `OrderHeader::quote_amount` itself, otherwise base amount
+    /// is calculated by local order book before order is sent
+    pub supports_quote_order_amount: bool,
 }
 
 impl OrderFeatures {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         maker_only: bool,
         supports_get_order_info_by_client_order_id: bool,
@@ -77,6 +87,9 @@ impl OrderFeatures {
         order_was_completed_error_for_cancellation: bool,
         supports_already_cancelled_order: bool,
         supports_stop_loss_order: bool,
+        supports_good_till_date: bool,
+        supports_reduce_only: bool,
+        supports_quote_order_amount: bool,
     ) -> Self {
         Self {
             maker_only,
@@ -86,11 +99,14 @@ impl OrderFeatures {
             order_was_completed_error_for_cancellation,
             supports_already_cancelled_order,
             supports_stop_loss_order,
+            supports_good_till_date,
+            supports_reduce_only,
+            supports_quote_order_amount,
         }
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct OrderTradeOption {
     pub supports_trade_time: bool,
     pub supports_trade_incremented_id: bool,
@@ -100,14 +116,19 @@ pub struct OrderTradeOption {
     pub supports_get_prints: bool,
     pub supports_tick_direction: bool,
     pub supports_my_trades_from_time: bool,
+    /// Trade ids of trades stream are consecutive, so missed trades are detected by gaps of ids
+    /// and requested by `ExchangeClient::get_trades_after`
+    pub supports_trades_backfill: bool,
 }
 
+#[derive(Serialize, Deserialize)]
 pub enum BalancePositionOption {
     NonDerivative,
     SingleRequest,
     IndividualRequests,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct ExchangeFeatures {
     pub open_orders_type: OpenOrdersType,
     pub rest_fills_features: RestFillsFeatures,
@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+
+use chrono::NaiveDate;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::exchanges::common::Amount;
+use crate::orders::order::OrderRole;
+use crate::settings::FeeTierSettings;
+
+/// Exchanges determine fee tier by volume of the last 30 days
+const VOLUME_WINDOW_DAYS: i64 = 30;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct DailyVolume {
+    volume: Amount,
+    maker_volume: Amount,
+    maker_fees: Amount,
+}
+
+/// Current fee tier of exchange account and distance to the next one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeeTierReport {
+    pub volume_30d: Amount,
+    pub maker_volume_30d: Amount,
+    /// Fees of maker fills in quote currency, negative value is received rebates
+    pub maker_fees_30d: Amount,
+    pub current_tier: Option<FeeTierSettings>,
+    pub next_tier: Option<FeeTierSettings>,
+    pub volume_to_next_tier: Option<Amount>,
+    /// Fees which would be saved on volume of the last 30 days by next tier
+    pub next_tier_savings_30d: Option<Amount>,
+}
+
+/// Volume traded on exchange account by days for comparing it with fee schedule of exchange
+pub struct FeeTierTracker {
+    tiers: Vec<FeeTierSettings>,
+    days: Mutex<VecDeque<(NaiveDate, DailyVolume)>>,
+}
+
+impl FeeTierTracker {
+    pub fn new(mut tiers: Vec<FeeTierSettings>) -> Self {
+        tiers.sort_by_key(|x| x.min_volume);
+        FeeTierTracker {
+            tiers,
+            days: Default::default(),
+        }
+    }
+
+    /// `cost` and `fee` of fill are in quote currency
+    pub fn register_fill(&self, time: DateTime, role: OrderRole, cost: Amount, fee: Amount) {
+        let date = time.date().naive_utc();
+        let mut days = self.days.lock();
+        if days.back().is_none_or(|(last_date, _)| *last_date < date) {
+            days.push_back((date, DailyVolume::default()));
+        }
+        // fills are registered in order of receiving, so late fill is counted in the last day
+        let (_, day) = days.back_mut().expect("Day is added above");
+
+        day.volume += cost.abs();
+        if role == OrderRole::Maker {
+            day.maker_volume += cost.abs();
+            day.maker_fees += fee;
+        }
+
+        let window_start = date - chrono::Duration::days(VOLUME_WINDOW_DAYS);
+        while days.front().is_some_and(|(date, _)| *date <= window_start) {
+            let _ = days.pop_front();
+        }
+    }
+
+    pub fn report(&self, now: DateTime) -> FeeTierReport {
+        let window_start = now.date().naive_utc() - chrono::Duration::days(VOLUME_WINDOW_DAYS);
+        let total = self
+            .days
+            .lock()
+            .iter()
+            .filter(|(date, _)| *date > window_start)
+            .fold(DailyVolume::default(), |total, (_, day)| DailyVolume {
+                volume: total.volume + day.volume,
+                maker_volume: total.maker_volume + day.maker_volume,
+                maker_fees: total.maker_fees + day.maker_fees,
+            });
+
+        let current_tier = self
+            .tiers
+            .iter()
+            .rev()
+            .find(|x| x.min_volume <= total.volume);
+        let next_tier = self.tiers.iter().find(|x| x.min_volume > total.volume);
+
+        let next_tier_savings_30d = current_tier.zip(next_tier).map(|(current, next)| {
+            let taker_volume = total.volume - total.maker_volume;
+            (total.maker_volume * (current.maker_fee - next.maker_fee)
+                + taker_volume * (current.taker_fee - next.taker_fee))
+                / dec!(100)
+        });
+
+        FeeTierReport {
+            volume_30d: total.volume,
+            maker_volume_30d: total.maker_volume,
+            maker_fees_30d: total.maker_fees,
+            current_tier: current_tier.cloned(),
+            next_tier: next_tier.cloned(),
+            volume_to_next_tier: next_tier.map(|x| x.min_volume - total.volume),
+            next_tier_savings_30d,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn tier(
+        name: &str,
+        min_volume: Amount,
+        maker_fee: Amount,
+        taker_fee: Amount,
+    ) -> FeeTierSettings {
+        FeeTierSettings {
+            name: name.to_owned(),
+            min_volume,
+            maker_fee,
+            taker_fee,
+        }
+    }
+
+    #[test]
+    pub fn tier_by_volume_of_last_30_days() {
+        let tracker = FeeTierTracker::new(vec![
+            tier("vip1", dec!(1000), dec!(0.05), dec!(0.08)),
+            tier("regular", dec!(0), dec!(0.1), dec!(0.1)),
+        ]);
+        let day = |day| Utc.ymd(2022, 1, day).and_hms(12, 0, 0);
+
+        tracker.register_fill(day(1), OrderRole::Taker, dec!(500), dec!(0.5));
+        tracker.register_fill(day(20), OrderRole::Maker, dec!(300), dec!(0.3));
+        tracker.register_fill(day(20), OrderRole::Maker, dec!(-100), dec!(0.1));
+
+        let report = tracker.report(day(25));
+        assert_eq!(report.volume_30d, dec!(900));
+        assert_eq!(report.maker_volume_30d, dec!(400));
+        assert_eq!(report.maker_fees_30d, dec!(0.4));
+        assert_eq!(
+            report.current_tier.map(|x| x.name),
+            Some("regular".to_owned())
+        );
+        assert_eq!(report.next_tier.map(|x| x.name), Some("vip1".to_owned()));
+        assert_eq!(report.volume_to_next_tier, Some(dec!(100)));
+        // maker 400 * 0.05% + taker 500 * 0.02%
+        assert_eq!(report.next_tier_savings_30d, Some(dec!(0.3)));
+
+        // volume of the first day is out of window
+        let report = tracker.report(day(31));
+        assert_eq!(report.volume_30d, dec!(400));
+        assert_eq!(report.volume_to_next_tier, Some(dec!(600)));
+    }
+}
@@ -23,7 +23,7 @@ impl Exchange {
             return;
         }
 
-        match self.orders.cache_by_exchange_id.get(exchange_order_id) {
+        match self.orders.get_by_exchange_id(exchange_order_id) {
             None => log::error!("cancel_order_failed was called for an order which is not in the local order pool: {exchange_order_id:?} on {}", self.exchange_account_id),
             Some(order) => self.react_based_on_order_status(&order, error, exchange_order_id, event_source_type),
         }
@@ -35,7 +35,7 @@ impl Exchange {
             panic!("Received HandleOrderFilled with an empty exchangeOrderId {args_to_log:?}",);
         }
 
-        match self.orders.cache_by_exchange_id.get(exchange_order_id) {
+        match self.orders.get_by_exchange_id(exchange_order_id) {
             None => {
                 self.buffered_canceled_orders_manager
                     .lock()
@@ -3,6 +3,7 @@ use mmb_utils::DateTime;
 use parking_lot::RwLock;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -45,7 +46,7 @@ type ArgsToLog = (
     EventSourceType,
 );
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum FillAmount {
     Incremental {
         // Volume of order fill for current event
@@ -73,7 +74,7 @@ impl FillAmount {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpecialOrderData {
     // For ClosePosition order currency pair can be empty string
     pub currency_pair: CurrencyPair,
@@ -81,7 +82,7 @@ pub struct SpecialOrderData {
     pub order_amount: Amount,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FillEvent {
     pub source_type: EventSourceType,
     pub trade_id: Option<TradeId>,
@@ -125,8 +126,7 @@ impl Exchange {
 
         match self
             .orders
-            .cache_by_exchange_id
-            .get(&fill_event.exchange_order_id)
+            .get_by_exchange_id(&fill_event.exchange_order_id)
         {
             None => {
                 log::info!("Received a fill for not existing order {args_to_log:?}",);
@@ -571,6 +571,17 @@ impl Exchange {
             self.exchange_account_id
         );
 
+        let fee_in_quote = match converted_commission_currency_code == symbol.quote_currency_code {
+            true => converted_commission_amount,
+            false => converted_commission_amount * last_fill_price,
+        };
+        self.fee_tiers.register_fill(
+            order_fill.receive_time(),
+            order_role,
+            last_fill_cost,
+            fee_in_quote,
+        );
+
         order_ref.fn_mut(move |order| order.add_fill(order_fill));
     }
 
@@ -700,8 +711,7 @@ impl Exchange {
 
         match self
             .orders
-            .cache_by_exchange_id
-            .get(&fill_event.exchange_order_id)
+            .get_by_exchange_id(&fill_event.exchange_order_id)
         {
             Some(order_ref) => fill_event.client_order_id = Some(order_ref.client_order_id()),
             None => {
@@ -1,4 +1,9 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
 use itertools::Itertools;
+use mmb_utils::infrastructure::SpawnFutureFlags;
 use mmb_utils::DateTime;
 
 use crate::{
@@ -8,10 +13,189 @@ use crate::{
         general::exchange::Exchange,
         timeouts::timeout_manager,
     },
-    orders::order::OrderSide,
+    infrastructure::spawn_future,
+    orders::{order::OrderSide, price_protection::is_bad_print},
 };
 
+/// Larger gaps aren't backfilled to avoid exhausting request limits of exchange
+const MAX_BACKFILL_TRADES: u64 = 10_000;
+
+/// Registers trade id of trades stream and returns last registered trade id if there is a gap
+/// between them. Trades with ids less than the last one don't move it back
+fn register_stream_trade_id(
+    last_trade_ids: &DashMap<MarketId, u64>,
+    market_id: MarketId,
+    trade_id: u64,
+) -> Option<u64> {
+    let mut last_trade_id = last_trade_ids.entry(market_id).or_insert(trade_id);
+    let prev_trade_id = *last_trade_id;
+    if trade_id <= prev_trade_id {
+        return None;
+    }
+
+    *last_trade_id = trade_id;
+    (trade_id > prev_trade_id + 1).then_some(prev_trade_id)
+}
+
 impl Exchange {
+    /// Trades stream with consecutive trade ids is checked for gaps (e.g. after reconnection)
+    /// and missed trades are requested in background. They are sent by separate trades event
+    /// after the trade which revealed the gap
+    pub(crate) fn check_trades_gap(
+        self: &Arc<Self>,
+        currency_pair: CurrencyPair,
+        trade_id: &TradeId,
+    ) {
+        if !self.features.trade_option.supports_trades_backfill {
+            return;
+        }
+
+        let trade_id = match trade_id {
+            TradeId::Number(trade_id) => *trade_id,
+            TradeId::String(_) => return,
+        };
+        let market_id = MarketId::new(self.exchange_account_id.exchange_id, currency_pair);
+        let last_trade_id =
+            match register_stream_trade_id(&self.last_stream_trade_ids, market_id, trade_id) {
+                Some(last_trade_id) => last_trade_id,
+                None => return,
+            };
+
+        let missed_trades = trade_id - last_trade_id - 1;
+        if missed_trades > MAX_BACKFILL_TRADES {
+            log::warn!(
+                "Gap of {missed_trades} trades of {currency_pair} on {} between {last_trade_id} and {trade_id} is too large for backfill",
+                self.exchange_account_id
+            );
+            return;
+        }
+
+        log::info!(
+            "Backfilling gap of {missed_trades} trades of {currency_pair} on {} between {last_trade_id} and {trade_id}",
+            self.exchange_account_id
+        );
+        let _ = spawn_future(
+            "Backfill missed trades",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            self.clone()
+                .backfill_trades(currency_pair, last_trade_id, trade_id),
+        );
+    }
+
+    async fn backfill_trades(
+        self: Arc<Self>,
+        currency_pair: CurrencyPair,
+        last_trade_id: u64,
+        next_trade_id: u64,
+    ) -> Result<()> {
+        let mut trades = Vec::new();
+        let mut from_trade_id = last_trade_id;
+        while from_trade_id + 1 < next_trade_id {
+            let page = self
+                .exchange_client
+                .get_trades_after(currency_pair, from_trade_id)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Unable to backfill trades of {currency_pair} on {}",
+                        self.exchange_account_id
+                    )
+                })?;
+            let page = match page {
+                Some(page) => page,
+                None => {
+                    log::warn!(
+                        "Exchange {} doesn't provide history of trades for backfill",
+                        self.exchange_account_id
+                    );
+                    return Ok(());
+                }
+            };
+
+            let page = page
+                .into_iter()
+                .filter(|x| {
+                    matches!(x.trade_id, TradeId::Number(id) if id > from_trade_id && id < next_trade_id)
+                })
+                .collect_vec();
+            from_trade_id = match page.last() {
+                Some(trade) => trade.trade_id.get_number(),
+                None => break,
+            };
+            trades.extend(page);
+        }
+
+        log::info!(
+            "Backfilled {} trades of {currency_pair} on {} between {last_trade_id} and {next_trade_id}",
+            trades.len(),
+            self.exchange_account_id
+        );
+        if trades.is_empty() {
+            return Ok(());
+        }
+
+        let (trades, bad_prints) = self.split_bad_prints(currency_pair, trades);
+        let trades_event = TradesEvent {
+            exchange_account_id: self.exchange_account_id,
+            currency_pair,
+            trades,
+            bad_prints,
+            receipt_time: timeout_manager::now(),
+        };
+        if self
+            .events_channel
+            .send(ExchangeEvent::Trades(trades_event))
+            .is_err()
+        {
+            log::warn!(
+                "Unable to send backfilled trades event. Probably receiver is already dropped"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Splits out trades with price deviating from order book top more than max deviation
+    /// from settings. Such prints are counted and excluded from trades for trading logic
+    fn split_bad_prints(
+        &self,
+        currency_pair: CurrencyPair,
+        trades: Vec<Trade>,
+    ) -> (Vec<Trade>, Vec<Trade>) {
+        let max_deviation = match self
+            .exchange_client
+            .get_settings()
+            .max_trade_deviation_from_book
+        {
+            Some(max_deviation) => max_deviation,
+            None => return (trades, Vec::new()),
+        };
+        let (bid, ask) = match self.order_book_top.get(&currency_pair) {
+            Some(top) => (
+                top.bid.as_ref().map(|x| x.price),
+                top.ask.as_ref().map(|x| x.price),
+            ),
+            None => return (trades, Vec::new()),
+        };
+
+        let (bad_prints, trades): (Vec<_>, Vec<_>) = trades
+            .into_iter()
+            .partition(|trade| is_bad_print(trade.price, bid, ask, max_deviation));
+        if !bad_prints.is_empty() {
+            *self.bad_prints_counts.entry(currency_pair).or_insert(0) += bad_prints.len() as u64;
+            for bad_print in &bad_prints {
+                log::warn!(
+                    "Bad print {} of {currency_pair} on {} with price {} is excluded from trades, order book top is {bid:?}/{ask:?}",
+                    bad_print.trade_id,
+                    self.exchange_account_id,
+                    bad_print.price
+                );
+            }
+        }
+
+        (trades, bad_prints)
+    }
+
     pub fn handle_trade(
         &self,
         currency_pair: CurrencyPair,
@@ -29,10 +213,12 @@ impl Exchange {
             transaction_time,
             tick_direction: TickDirection::None,
         }];
+        let (trades, bad_prints) = self.split_bad_prints(currency_pair, trades);
         let mut trades_event = TradesEvent {
             exchange_account_id: self.exchange_account_id,
             currency_pair,
             trades,
+            bad_prints,
             receipt_time: timeout_manager::now(),
         };
 
@@ -40,6 +226,11 @@ impl Exchange {
 
         self.last_trades_update_time
             .insert(market_id, trades_event.receipt_time);
+        if let Some(trade) = trades_event.trades.last() {
+            let _ = self
+                .last_trade_prices
+                .insert(market_id, (trade.price, trades_event.receipt_time));
+        }
 
         if self.exchange_client.get_settings().subscribe_to_market_data {
             return;
@@ -78,10 +269,14 @@ impl Exchange {
                 false
             };
 
+            // bad prints don't become last trade, but they are still sent for recording
             match trades_event.trades.first() {
-                Some(trade) => self.last_trades.insert(market_id, trade.clone()),
-                None => return,
-            };
+                Some(trade) => {
+                    self.last_trades.insert(market_id, trade.clone());
+                }
+                None if trades_event.bad_prints.is_empty() => return,
+                None => {}
+            }
 
             if !should_add_event {
                 return;
@@ -95,3 +290,43 @@ impl Exchange {
         // TODO DataRecorder.save(trades) if needed;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn register_stream_trade_ids_with_gaps() {
+        let last_trade_ids = DashMap::new();
+        let market_id = MarketId::new(
+            "Binance".into(),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        );
+
+        assert_eq!(
+            register_stream_trade_id(&last_trade_ids, market_id, 10),
+            None
+        );
+        assert_eq!(
+            register_stream_trade_id(&last_trade_ids, market_id, 11),
+            None
+        );
+        assert_eq!(
+            register_stream_trade_id(&last_trade_ids, market_id, 15),
+            Some(11)
+        );
+        // late trade doesn't move last trade id back
+        assert_eq!(
+            register_stream_trade_id(&last_trade_ids, market_id, 13),
+            None
+        );
+        assert_eq!(
+            register_stream_trade_id(&last_trade_ids, market_id, 16),
+            None
+        );
+        assert_eq!(
+            register_stream_trade_id(&last_trade_ids, market_id, 18),
+            Some(16)
+        );
+    }
+}
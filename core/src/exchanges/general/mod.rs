@@ -1,15 +1,24 @@
 pub mod commission;
+pub mod connection_uptime;
 pub mod currency_pair_to_symbol_converter;
 pub mod engine_api;
 pub mod exchange;
 pub mod exchange_creation;
 pub mod exchange_symbol;
+pub mod extension;
 pub mod features;
+pub mod fee_tiers;
 pub mod handlers;
 pub mod order;
+pub mod paper_fills;
+pub mod parsing_workers;
 pub mod polling_timeout_manager;
+pub mod refresh;
 pub mod request_type;
+pub mod supervision;
 pub mod symbol;
+pub mod venue_metrics;
+pub mod venue_selection;
 
 #[cfg(test)]
 pub mod test_helper;
@@ -2,6 +2,7 @@ use anyhow::{anyhow, Result};
 use futures::future::join_all;
 use itertools::Itertools;
 use mmb_utils::cancellation_token::CancellationToken;
+use serde::{Deserialize, Serialize};
 use tokio::sync::oneshot;
 
 use crate::misc::time::time_manager;
@@ -19,7 +20,7 @@ use crate::{
     orders::{fill::EventSourceType, order::OrderCancelling},
 };
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct CancelOrderResult {
     pub outcome: RequestResult<ClientOrderId>,
     pub source_type: EventSourceType,
@@ -204,14 +205,10 @@ impl Exchange {
         let mut not_found_orders = Vec::new();
 
         for order in orders {
-            match self
-                .orders
-                .cache_by_exchange_id
-                .get(&order.exchange_order_id)
-            {
+            match self.orders.get_by_exchange_id(&order.exchange_order_id) {
                 None => not_found_orders.push(order.exchange_order_id.clone()),
                 Some(order_ref) => futures.push(self.wait_cancel_order(
-                    order_ref.clone(),
+                    order_ref,
                     None,
                     true,
                     cancellation_token.clone(),
@@ -1,22 +1,39 @@
 use anyhow::{bail, Context, Result};
 use chrono::Utc;
-use futures::pin_mut;
+use futures::{join, pin_mut};
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::{nothing_to_do, OPERATION_CANCELED_MSG};
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::time::Duration;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::oneshot;
 use tokio::time::{sleep, timeout};
 
-use crate::exchanges::common::ToStdExpected;
+use crate::exchanges::common::{Amount, ToStdExpected};
 use crate::exchanges::events::AllowedEventSourceType;
+use crate::exchanges::general::commission::Percent;
 use crate::exchanges::general::exchange::RequestResult::{Error, Success};
 use crate::exchanges::general::handlers::should_ignore_event;
 use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::general::symbol::Round;
+use crate::exchanges::margin::MarginRiskError;
+use crate::exchanges::timeouts::request_priority_queue::RequestPriority;
 use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
 use crate::misc::time::time_manager;
+use crate::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
 use crate::orders::event::OrderEventType;
-use crate::orders::order::OrderInfo;
+use crate::orders::market_rollout::{reduce_amount, MarketRolloutError};
+use crate::orders::order::{OrderHeader, OrderInfo};
+use crate::orders::price_protection::{
+    check_last_trade_deviation, check_price_collar, check_price_protection, fresh_last_trade_price,
+    PriceProtectionError,
+};
+use crate::orders::reduce_only::{check_reduce_only, ReduceOnlyError};
+use crate::services::value_at_risk::ValueAtRiskError;
+use crate::settings::MarketMode;
 use crate::{
     exchanges::common::ExchangeAccountId,
     exchanges::common::ExchangeError,
@@ -31,7 +48,7 @@ use crate::{
     orders::{fill::EventSourceType, order::OrderCreating},
 };
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct CreateOrderResult {
     pub outcome: RequestResult<ExchangeOrderId>,
     pub source_type: EventSourceType,
@@ -54,14 +71,287 @@ impl CreateOrderResult {
 }
 
 impl Exchange {
-    pub async fn create_order(
+    /// Create market or taker order only if its expected slippage from middle price by local
+    /// order book doesn't exceed `max_slippage`. Rejection error contains `PriceProtectionError`
+    pub async fn create_order_with_price_protection(
         &self,
         order_to_create: OrderCreating,
+        snapshot: &LocalOrderBookSnapshot,
+        max_slippage: Percent,
+        pre_reservation_group_id: Option<RequestGroupId>,
+        cancellation_token: CancellationToken,
+    ) -> Result<OrderRef> {
+        let header = &order_to_create.header;
+        let analysis = check_price_protection(
+            snapshot,
+            header.market_id(),
+            header.side,
+            header.amount,
+            max_slippage,
+        )
+        .with_context(|| {
+            format!(
+                "Order {} is rejected by price protection",
+                header.client_order_id
+            )
+        })?;
+        log::info!(
+            "Price protection check is passed for order {}: {analysis:?}",
+            header.client_order_id
+        );
+
+        self.create_order(
+            order_to_create,
+            pre_reservation_group_id,
+            cancellation_token,
+        )
+        .await
+    }
+
+    fn check_price_sanity(&self, order: &OrderCreating) -> Result<(), PriceProtectionError> {
+        let header = &order.header;
+        if header.skip_price_sanity_check || header.order_type == OrderType::Market {
+            return Ok(());
+        }
+
+        self.check_last_trade_price(order)?;
+        self.check_index_price_collar(order)
+    }
+
+    fn check_index_price_collar(&self, order: &OrderCreating) -> Result<(), PriceProtectionError> {
+        let header = &order.header;
+        let max_deviation = match self
+            .exchange_client
+            .get_settings()
+            .max_price_deviation_from_index
+        {
+            Some(max_deviation) => max_deviation,
+            None => return Ok(()),
+        };
+
+        let index_price = self
+            .index_prices
+            .lock()
+            .as_ref()
+            .and_then(|x| x.get_index_price(header.currency_pair));
+        match index_price {
+            Some(index_price) => check_price_collar(order.price, index_price.price, max_deviation),
+            None => {
+                log::warn!(
+                    "Price of order {} isn't checked by collar because there is no index price of {}",
+                    header.client_order_id,
+                    header.currency_pair
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn check_last_trade_price(&self, order: &OrderCreating) -> Result<(), PriceProtectionError> {
+        let header = &order.header;
+        let settings = self.exchange_client.get_settings();
+        let max_deviation = match settings.max_price_deviation_from_last_trade {
+            Some(max_deviation) => max_deviation,
+            None => return Ok(()),
+        };
+
+        let last_trade = self.last_trade_prices.get(&header.market_id()).map(|x| *x);
+        match fresh_last_trade_price(
+            last_trade,
+            time_manager::now(),
+            settings.max_last_trade_age_ms,
+        ) {
+            Some(last_trade_price) => {
+                check_last_trade_deviation(order.price, last_trade_price, max_deviation)
+            }
+            None => {
+                log::warn!(
+                    "Price of order {} isn't checked because there is no recent trade of {} on {}",
+                    header.client_order_id,
+                    header.currency_pair,
+                    self.exchange_account_id
+                );
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn create_order(
+        &self,
+        mut order_to_create: OrderCreating,
         pre_reservation_group_id: Option<RequestGroupId>,
         cancellation_token: CancellationToken,
     ) -> Result<OrderRef> {
         use AllowedEventSourceType::*;
 
+        if self.is_market_data_only() {
+            bail!(
+                "Order {} can't be created on {} in market data only mode",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+        }
+
+        if !self.is_leader() {
+            bail!(
+                "Order {} can't be created on {} because engine instance isn't leader",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+        }
+        let fencing_token = self.fencing_token();
+
+        let currency_pair = order_to_create.header.currency_pair;
+        if let Err(err) = self.currency_restrictions.lock().check(currency_pair) {
+            log::error!(
+                "Order {} on {} is rejected by currency restrictions: {err}",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+            return Err(anyhow::Error::new(err)
+                .context(format!("Order creation for {currency_pair} is restricted")));
+        }
+
+        if self.is_market_stale(currency_pair) {
+            bail!(
+                "Order {} can't be created because market data of {currency_pair} on {} is stale",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+        }
+
+        if self.is_market_paused(currency_pair) {
+            bail!(
+                "Order {} can't be created because quoting on {currency_pair} on {} is paused",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+        }
+
+        if let Err(err) = self.apply_quote_amount(&mut order_to_create) {
+            log::error!(
+                "Order {} on {} is rejected because its quote amount can't be converted: {err:?}",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+            return Err(err.context(format!(
+                "Order creation for {currency_pair} is rejected by quote amount conversion"
+            )));
+        }
+
+        let market_mode = match self.apply_market_rollout(&mut order_to_create) {
+            Ok(market_mode) => market_mode,
+            Err(err) => {
+                log::warn!(
+                    "Order {} on {} is rejected in reduced rollout mode: {err}",
+                    order_to_create.header.client_order_id,
+                    self.exchange_account_id
+                );
+                return Err(anyhow::Error::new(err).context(format!(
+                    "Order creation for {currency_pair} is rejected in reduced rollout mode"
+                )));
+            }
+        };
+
+        if let Err(err) = self.check_price_sanity(&order_to_create) {
+            log::error!(
+                "Order {} on {} is rejected by price sanity check: {err}",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+            return Err(anyhow::Error::new(err).context(format!(
+                "Order creation for {currency_pair} is rejected by price sanity check"
+            )));
+        }
+
+        if let Err(err) = self.apply_reduce_only(&mut order_to_create) {
+            log::error!(
+                "Order {} on {} is rejected in reduce-only mode: {err}",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+            return Err(anyhow::Error::new(err).context(format!(
+                "Order creation for {currency_pair} is rejected in reduce-only mode"
+            )));
+        }
+
+        // reduce-only flag is set above, so risk reducing orders aren't blocked by margin risk
+        if let Err(err) = self.check_margin_risk(&order_to_create) {
+            log::error!(
+                "Order {} on {} is rejected by margin risk: {err}",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+            return Err(anyhow::Error::new(err).context(format!(
+                "Order creation for {currency_pair} is rejected by margin risk"
+            )));
+        }
+
+        if let Err(err) = self.check_value_at_risk(&order_to_create) {
+            log::error!(
+                "Order {} on {} is rejected by value at risk limit: {err}",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+            return Err(anyhow::Error::new(err).context(format!(
+                "Order creation for {currency_pair} is rejected by value at risk limit"
+            )));
+        }
+
+        let risk_engine = self.risk_engine.lock().clone();
+        if let Some(risk_engine) = risk_engine {
+            if let Err(err) = risk_engine.check_order(&order_to_create) {
+                log::error!(
+                    "Order {} on {} is rejected by risk engine: {err:?}",
+                    order_to_create.header.client_order_id,
+                    self.exchange_account_id
+                );
+                return Err(err.context(format!(
+                    "Order creation for {currency_pair} is rejected by risk engine"
+                )));
+            }
+        }
+
+        if market_mode == MarketMode::Shadow {
+            self.market_rollout()
+                .register_shadow_order(order_to_create.header.market_account_id());
+            log::info!("Order {order_to_create:?} isn't submitted in shadow rollout mode");
+            bail!(
+                "Order {} isn't created because {currency_pair} on {} is in shadow rollout mode",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+        }
+
+        // orders are queued by rate limits, risk reducing orders ahead of new quotes.
+        // Request of order with pre-reserved group is taken from the group
+        let is_risk_reducing = order_to_create.header.reduce_only
+            || self
+                .reduce_only_mode()
+                .is_enabled_for(order_to_create.header.market_account_id());
+        let priority = match is_risk_reducing {
+            true => RequestPriority::RiskReducing,
+            false => RequestPriority::Quote,
+        };
+        self.timeout_manager
+            .reserve_by_priority(
+                self.exchange_account_id,
+                RequestType::CreateOrder,
+                priority,
+                pre_reservation_group_id,
+                cancellation_token.clone(),
+            )
+            .await?;
+
+        // leadership could be lost or changed while order was waiting for checks and rate limits
+        if !self.is_fencing_token_valid(fencing_token) {
+            bail!(
+                "Order {} isn't created on {} because its fencing token {fencing_token:?} is stale",
+                order_to_create.header.client_order_id,
+                self.exchange_account_id
+            );
+        }
+
         log::info!("Submitting order {order_to_create:?}");
 
         let order = self.orders.add_simple_initial(
@@ -72,6 +362,7 @@ impl Exchange {
 
         let linked_ct = cancellation_token.create_linked_token();
 
+        let roundtrip_start = Instant::now();
         let create_order_fut = self.create_order_base(&order, linked_ct.clone());
 
         let duration = Duration::from_secs(5 * 60);
@@ -118,7 +409,8 @@ impl Exchange {
                             .exchange_order_id()
                             .expect("exchange_order_id should exists after check_order_creation");
                     } else {
-                        bail!("failed create_order: {}", exchange_error.message);
+                        let message = format!("failed create_order: {}", exchange_error.message);
+                        return Err(anyhow::Error::new(exchange_error).context(message));
                     }
                 }
             }
@@ -139,58 +431,429 @@ impl Exchange {
             Ok(())
         }
 
-        match self.features.allowed_create_event_source_type {
-            All => {
-                tokio::select! {
-                    created_order_result = create_order_fut => {
-                        handle_create_order_res(
-                            self,
-                            &order,
-                            pre_reservation_group_id,
-                            created_order_result,
-                            linked_ct.clone(),
-                            cancellation_token.clone(),
-                        ).await?;
-                    },
-                    poll_result = poll_creation_fut => handle_poll_creation_order_res(&order, poll_result, linked_ct)?,
-                };
-            }
-            FallbackOnly => {
-                pin_mut!(poll_creation_fut);
-                let need_poll = tokio::select! {
-                    _ = create_order_fut => true,
-                    poll_result = &mut poll_creation_fut => {
-                        handle_poll_creation_order_res(&order, poll_result, linked_ct.clone())?;
-                        false
-                    },
-                };
-
-                if need_poll {
-                    let poll_result = poll_creation_fut.await;
-                    handle_poll_creation_order_res(&order, poll_result, linked_ct)?;
+        let creation_fut = async {
+            match self.features.allowed_create_event_source_type {
+                All => {
+                    tokio::select! {
+                        created_order_result = create_order_fut => {
+                            handle_create_order_res(
+                                self,
+                                &order,
+                                pre_reservation_group_id,
+                                created_order_result,
+                                linked_ct.clone(),
+                                cancellation_token.clone(),
+                            ).await?;
+                        },
+                        poll_result = poll_creation_fut => handle_poll_creation_order_res(&order, poll_result, linked_ct)?,
+                    };
+                }
+                FallbackOnly => {
+                    pin_mut!(poll_creation_fut);
+                    let need_poll = tokio::select! {
+                        _ = create_order_fut => true,
+                        poll_result = &mut poll_creation_fut => {
+                            handle_poll_creation_order_res(&order, poll_result, linked_ct.clone())?;
+                            false
+                        },
+                    };
+
+                    if need_poll {
+                        let poll_result = poll_creation_fut.await;
+                        handle_poll_creation_order_res(&order, poll_result, linked_ct)?;
+                    }
+                }
+                NonFallback => {
+                    let created_order_result = create_order_fut.await;
+                    handle_create_order_res(
+                        self,
+                        &order,
+                        pre_reservation_group_id,
+                        created_order_result,
+                        linked_ct.clone(),
+                        cancellation_token.clone(),
+                    )
+                    .await?;
                 }
             }
-            NonFallback => {
-                let created_order_result = create_order_fut.await;
-                handle_create_order_res(
-                    self,
+
+            Ok(())
+        };
+
+        let creation_result = match order.fn_ref(|x| x.header.latency_budget) {
+            Some(latency_budget) => {
+                self.wait_creation_within_latency_budget(
                     &order,
-                    pre_reservation_group_id,
-                    created_order_result,
-                    linked_ct.clone(),
+                    latency_budget,
+                    creation_fut,
                     cancellation_token.clone(),
                 )
-                .await?;
+                .await
             }
-        }
+            None => creation_fut.await,
+        };
 
-        self.handle_created_order(&order, pre_reservation_group_id, cancellation_token)
+        self.register_order_creation_metrics(&order, &creation_result, roundtrip_start);
+        creation_result?;
+
+        self.handle_created_order(&order, pre_reservation_group_id, cancellation_token.clone())
             .await
             .unwrap_or_else(|err| log::error!("failed handle_created_order: {err}"));
 
+        if let Some(good_till_date) = self.good_till_date.lock().clone() {
+            good_till_date.schedule_created_order(&order, self.supports_good_till_date());
+        }
+
+        let is_late_created_order =
+            order.fn_ref(|x| x.internal_props.is_ack_timeout && x.status() == OrderStatus::Created);
+        if is_late_created_order {
+            log::warn!(
+                "Canceling order {} on {} because its creation was acknowledged after latency budget",
+                order.client_order_id(),
+                self.exchange_account_id
+            );
+            self.wait_cancel_order(
+                order.clone(),
+                pre_reservation_group_id,
+                false,
+                cancellation_token,
+            )
+            .await
+            .unwrap_or_else(|err| log::error!("failed to cancel order after ack timeout: {err:?}"));
+        }
+
         Ok(order)
     }
 
+    /// Waits for order creation. If acknowledgment isn't received within latency budget, order is
+    /// marked by `AckTimeout` and canceled by client order id while creation is awaited further.
+    /// Order is canceled after its creation is acknowledged if exchange can't cancel it by
+    /// client order id
+    async fn wait_creation_within_latency_budget(
+        &self,
+        order: &OrderRef,
+        latency_budget: Duration,
+        creation_fut: impl Future<Output = Result<()>>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        pin_mut!(creation_fut);
+        if let Ok(creation_result) = timeout(latency_budget, &mut creation_fut).await {
+            return creation_result;
+        }
+
+        log::warn!(
+            "Creation of order {} on {} isn't acknowledged within latency budget {latency_budget:?}",
+            order.client_order_id(),
+            self.exchange_account_id
+        );
+        order.fn_mut(|x| x.internal_props.is_ack_timeout = true);
+        self.add_event_on_order_change(order, OrderEventType::AckTimeout)
+            .unwrap_or_else(|err| log::error!("failed to raise AckTimeout event: {err:?}"));
+
+        let (creation_result, _) = join!(
+            creation_fut,
+            self.cancel_by_client_order_id(order, cancellation_token)
+        );
+        creation_result
+    }
+
+    async fn cancel_by_client_order_id(
+        &self,
+        order: &OrderRef,
+        cancellation_token: CancellationToken,
+    ) {
+        let header = order.fn_ref(|x| x.header.clone());
+        let client_order_id = &header.client_order_id;
+
+        let reserved = self
+            .timeout_manager
+            .reserve_by_priority(
+                self.exchange_account_id,
+                RequestType::CancelOrder,
+                RequestPriority::Cancel,
+                None,
+                cancellation_token,
+            )
+            .await;
+        if let Err(error) = reserved {
+            log::warn!("Order {client_order_id} isn't canceled by client order id: {error:?}");
+            return;
+        }
+
+        match self
+            .exchange_client
+            .cancel_order_by_client_order_id(&header)
+            .await
+        {
+            Ok(()) => log::info!(
+                "Order {client_order_id} on {} is canceled by client order id after latency budget",
+                self.exchange_account_id
+            ),
+            Err(error) if error.error_type == ExchangeErrorType::Unsupported => log::info!(
+                "Order {client_order_id} on {} will be canceled after its creation is acknowledged",
+                self.exchange_account_id
+            ),
+            Err(error) => log::warn!(
+                "Failed to cancel order {client_order_id} on {} by client order id: {error:?}",
+                self.exchange_account_id
+            ),
+        }
+    }
+
+    /// Initial margin of order on derivative market is estimated as its notional in quote currency
+    /// divided by leverage. Margin balance is expected in quote currency of derivative markets
+    /// Orders which only reduce position don't require additional margin, so they are allowed
+    /// even if margin state is unknown or margin usage is exceeded
+    fn check_margin_risk(&self, order_to_create: &OrderCreating) -> Result<(), MarginRiskError> {
+        if !self.margin_risk.lock().is_enabled() {
+            return Ok(());
+        }
+
+        let header = &order_to_create.header;
+        let symbol = match self.symbols.get(&header.currency_pair) {
+            Some(symbol) if symbol.is_derivative() => symbol.clone(),
+            _ => return Ok(()),
+        };
+
+        if header.reduce_only || header.amount <= self.get_closable_position(header) {
+            return Ok(());
+        }
+
+        let notional = symbol.convert_amount_from_amount_currency_code(
+            symbol.quote_currency_code,
+            header.amount,
+            order_to_create.price,
+        );
+        let leverage = self
+            .leverage_by_currency_pair
+            .get(&header.currency_pair)
+            .map(|x| *x)
+            .filter(|x| *x > dec!(0))
+            .unwrap_or(dec!(1));
+
+        self.margin_risk.lock().check(notional / leverage)
+    }
+
+    fn check_value_at_risk(&self, order_to_create: &OrderCreating) -> Result<(), ValueAtRiskError> {
+        let value_at_risk = match self.value_at_risk.lock().clone() {
+            Some(value_at_risk) => value_at_risk,
+            None => return Ok(()),
+        };
+        let symbol = match self.symbols.get(&order_to_create.header.currency_pair) {
+            Some(symbol) => symbol.clone(),
+            None => return Ok(()),
+        };
+
+        value_at_risk.check_order(order_to_create, &symbol)
+    }
+
+    /// Base amount of market order sized by quote amount is calculated by local order book and
+    /// rounded to amount precision if exchange doesn't size orders by quote amount itself.
+    /// Order is rejected if order book depth doesn't cover quote amount
+    fn apply_quote_amount(&self, order_to_create: &mut OrderCreating) -> Result<()> {
+        let header = &order_to_create.header;
+        let quote_amount = match header.quote_amount {
+            Some(quote_amount) if !self.supports_quote_order_amount() => quote_amount,
+            _ => return Ok(()),
+        };
+
+        let symbol = self
+            .symbols
+            .get(&header.currency_pair)
+            .map(|x| x.clone())
+            .with_context(|| {
+                format!(
+                    "Symbol {} isn't found on {}",
+                    header.currency_pair, self.exchange_account_id
+                )
+            })?;
+        let local_snapshots = self
+            .local_snapshots
+            .lock()
+            .clone()
+            .context("Order books aren't available")?;
+        let amount = local_snapshots
+            .lock()
+            .get_snapshot(header.market_id())
+            .and_then(|snapshot| snapshot.calculate_amount_by_quote(header.side, quote_amount))
+            .with_context(|| {
+                format!(
+                    "Order book of {} on {} doesn't cover quote amount {quote_amount}",
+                    header.currency_pair, self.exchange_account_id
+                )
+            })?;
+
+        let amount = symbol.amount_round(amount, Round::Floor);
+        if amount <= dec!(0) {
+            bail!("Quote amount {quote_amount} is less than amount precision");
+        }
+        if header.reservation_id.is_some() && amount > header.amount {
+            bail!(
+                "Amount {amount} by quote amount {quote_amount} exceeds estimated amount {} which balance is reserved for",
+                header.amount
+            );
+        }
+
+        let header = Arc::make_mut(&mut order_to_create.header);
+        log::info!(
+            "Amount of order {} is {amount} by quote amount {quote_amount}",
+            header.client_order_id
+        );
+        let reduced_amount = header.amount - amount;
+        header.amount = amount;
+
+        // reservation isn't approved yet, so it's released from not approved part
+        self.unreserve_reduced_amount(&order_to_create.header, reduced_amount, false);
+
+        Ok(())
+    }
+
+    /// Amount of order on market in reduced rollout mode is scaled down to reduced size
+    fn apply_market_rollout(
+        &self,
+        order_to_create: &mut OrderCreating,
+    ) -> Result<MarketMode, MarketRolloutError> {
+        let market_rollout = self.market_rollout();
+        let market_account_id = order_to_create.header.market_account_id();
+        let reduced_size = match market_rollout.reduced_size(market_account_id) {
+            Some(reduced_size) => reduced_size,
+            None => return Ok(market_rollout.mode(market_account_id)),
+        };
+
+        let symbol = self
+            .symbols
+            .get(&market_account_id.currency_pair)
+            .map(|x| x.clone());
+        let amount = reduce_amount(order_to_create.header.amount, reduced_size, |x| {
+            symbol
+                .as_ref()
+                .map_or(x, |symbol| symbol.amount_round(x, Round::Floor))
+        })?;
+
+        let header = Arc::make_mut(&mut order_to_create.header);
+        log::info!(
+            "Amount of order {} is reduced from {} to {amount} in reduced rollout mode",
+            header.client_order_id,
+            header.amount
+        );
+        let reduced_amount = header.amount - amount;
+        header.amount = amount;
+        if let Some(quote_amount) = header.quote_amount.as_mut() {
+            *quote_amount = *quote_amount * reduced_size / dec!(100);
+        }
+
+        // reservation isn't approved yet, so it's released from not approved part
+        self.unreserve_reduced_amount(&order_to_create.header, reduced_amount, false);
+
+        Ok(MarketMode::Reduced)
+    }
+
+    /// Releases reserved balance of amount which order doesn't need anymore after its amount is
+    /// reduced, because only the current order amount is unreserved when order is finished
+    pub(crate) fn unreserve_reduced_amount(
+        &self,
+        header: &OrderHeader,
+        reduced_amount: Amount,
+        is_reservation_approved: bool,
+    ) {
+        let reservation_id = match header.reservation_id {
+            Some(reservation_id) if reduced_amount > dec!(0) => reservation_id,
+            _ => return,
+        };
+
+        let balance_manager = match self.get_balance_manager() {
+            Some(balance_manager) => balance_manager,
+            None => {
+                log::warn!(
+                    "BalanceManager isn't available to unreserve reduced amount {reduced_amount} of order {}",
+                    header.client_order_id
+                );
+                return;
+            }
+        };
+
+        let mut balance_manager = balance_manager.lock();
+        let unreserve_result = if is_reservation_approved {
+            balance_manager.unreserve_by_client_order_id(
+                reservation_id,
+                header.client_order_id.clone(),
+                reduced_amount,
+            )
+        } else {
+            balance_manager.unreserve(reservation_id, reduced_amount)
+        };
+        unreserve_result.unwrap_or_else(|err| {
+            log::error!(
+                "Failed to unreserve reduced amount {reduced_amount} of order {} from {reservation_id}: {err:?}",
+                header.client_order_id
+            )
+        });
+    }
+
+    /// In reduce-only mode order is marked by reduce-only flag if exchange supports it natively,
+    /// otherwise order is checked locally by position of derivative market. Positions of spot
+    /// markets aren't tracked, so their orders are rejected
+    fn apply_reduce_only(
+        &self,
+        order_to_create: &mut OrderCreating,
+    ) -> Result<(), ReduceOnlyError> {
+        if !self
+            .reduce_only_mode()
+            .is_enabled_for(order_to_create.header.market_account_id())
+        {
+            return Ok(());
+        }
+
+        if self.supports_reduce_only() {
+            order_to_create.header = order_to_create.header.clone().with_reduce_only();
+            return Ok(());
+        }
+
+        let header = &order_to_create.header;
+        let position = self.get_closable_position(header);
+
+        let active_orders_amount = self
+            .orders
+            .not_finished
+            .by_market(header.currency_pair)
+            .iter()
+            .filter(|x| x.client_order_id() != header.client_order_id && x.side() == header.side)
+            .map(|x| x.amount() - x.filled_amount())
+            .sum();
+
+        check_reduce_only(header.amount, position, active_orders_amount)
+    }
+
+    /// Position of derivative which order of `header` side reduces
+    fn get_closable_position(&self, header: &OrderHeader) -> Amount {
+        let symbol = match self.symbols.get(&header.currency_pair) {
+            Some(symbol) if symbol.is_derivative() => symbol.clone(),
+            _ => return dec!(0),
+        };
+
+        self.get_balance_manager()
+            .map(|x| {
+                x.lock()
+                    .get_closable_position(self.exchange_account_id, symbol, header.side)
+            })
+            .unwrap_or(dec!(0))
+    }
+
+    fn register_order_creation_metrics(
+        &self,
+        order: &OrderRef,
+        creation_result: &Result<()>,
+        roundtrip_start: Instant,
+    ) {
+        let is_failed_to_create = order.fn_ref(|o| o.status() == OrderStatus::FailedToCreate);
+        if creation_result.is_err() || is_failed_to_create {
+            self.venue_metrics.register_order_rejected();
+        } else {
+            self.venue_metrics
+                .register_order_created(roundtrip_start.elapsed());
+        }
+    }
+
     async fn handle_created_order(
         &self,
         order: &OrderRef,
@@ -545,7 +1208,7 @@ impl Exchange {
             bail!("{error_msg}");
         }
 
-        let order_ref = self.orders.cache_by_client_id.get(client_order_id).with_context(|| {
+        let order_ref = self.orders.get_by_client_id(client_order_id).with_context(|| {
             let error_msg = format!(
                 "CreateOrderSucceeded was received for an order which is not in the local orders pool {args_to_log:?}");
 
@@ -638,7 +1301,7 @@ impl Exchange {
             bail!("{error_msg}");
         }
 
-        match self.orders.cache_by_client_id.get(client_order_id) {
+        match self.orders.get_by_client_id(client_order_id) {
             None => {
                 log::warn!("CreateOrderSucceeded was received for an order which is not in the local orders pool {args_to_log:?}");
                 Ok(())
@@ -817,3 +1480,146 @@ impl Exchange {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+    use mmb_utils::hashmap;
+    use parking_lot::Mutex;
+
+    use crate::exchanges::common::{CurrencyPair, MarketId, SortedOrderData};
+    use crate::exchanges::general::symbol::{Precision, Symbol};
+    use crate::exchanges::general::test_helper::{
+        create_order_ref, get_test_exchange, get_test_exchange_with_symbol, TestClient,
+    };
+    use crate::infrastructure::init_lifetime_manager;
+    use crate::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+    use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+    use crate::orders::order::OrderSide;
+
+    fn exchange_with_asks(asks: SortedOrderData) -> Arc<Exchange> {
+        let symbol = Arc::new(Symbol::new(
+            false,
+            false,
+            "PHB".into(),
+            "phb".into(),
+            "BTC".into(),
+            "btc".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "phb".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(0.1) },
+        ));
+        let exchange = get_test_exchange_with_symbol(symbol).0;
+
+        let market_id = MarketId::new(
+            exchange.exchange_account_id.exchange_id,
+            CurrencyPair::from_codes("phb".into(), "btc".into()),
+        );
+        let snapshot = LocalOrderBookSnapshot::new(asks, SortedOrderData::new(), Utc::now());
+        exchange.setup_local_snapshots(Arc::new(Mutex::new(LocalSnapshotsService::new(
+            hashmap![market_id => snapshot],
+        ))));
+
+        exchange
+    }
+
+    fn order_by_quote_amount(exchange: &Exchange, quote_amount: Amount) -> OrderCreating {
+        OrderCreating {
+            header: OrderHeader::new_by_quote_amount(
+                ClientOrderId::unique_id(),
+                Utc::now(),
+                exchange.exchange_account_id,
+                CurrencyPair::from_codes("phb".into(), "btc".into()),
+                OrderSide::Buy,
+                quote_amount,
+                dec!(3),
+                None,
+                None,
+                "test".to_owned(),
+            ),
+            price: dec!(0),
+        }
+    }
+
+    #[tokio::test]
+    async fn quote_amount_is_converted_by_order_book_and_rounded() {
+        let _ = init_lifetime_manager();
+        let mut asks = SortedOrderData::new();
+        asks.insert(dec!(10), dec!(1));
+        asks.insert(dec!(20), dec!(2));
+        let exchange = exchange_with_asks(asks);
+        let mut order = order_by_quote_amount(&exchange, dec!(41));
+
+        exchange.apply_quote_amount(&mut order).expect("in test");
+
+        // 10 * 1 + 20 * 1.55 floored to amount tick
+        assert_eq!(order.header.amount, dec!(2.5));
+        assert_eq!(order.header.quote_amount, Some(dec!(41)));
+    }
+
+    #[tokio::test]
+    async fn order_is_rejected_if_order_book_does_not_cover_quote_amount() {
+        let _ = init_lifetime_manager();
+        let mut asks = SortedOrderData::new();
+        asks.insert(dec!(10), dec!(1));
+        let exchange = exchange_with_asks(asks);
+        let mut order = order_by_quote_amount(&exchange, dec!(11));
+
+        assert!(exchange.apply_quote_amount(&mut order).is_err());
+        assert_eq!(order.header.amount, dec!(3));
+    }
+
+    async fn wait_creation(creation_delay: Duration) -> (Arc<Exchange>, OrderRef) {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange(false);
+        let order = create_order_ref(
+            &ClientOrderId::unique_id(),
+            None,
+            exchange.exchange_account_id,
+            CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+            dec!(0.8),
+            dec!(12),
+            OrderSide::Buy,
+        );
+
+        exchange
+            .wait_creation_within_latency_budget(
+                &order,
+                Duration::from_millis(20),
+                sleep(creation_delay).map(Ok),
+                CancellationToken::new(),
+            )
+            .await
+            .expect("in test");
+
+        (exchange, order)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn order_is_canceled_by_client_order_id_after_latency_budget() {
+        let (exchange, order) = wait_creation(Duration::from_millis(100)).await;
+
+        assert!(order.fn_ref(|x| x.internal_props.is_ack_timeout));
+        let client = exchange.client_as::<TestClient>().expect("in test");
+        assert_eq!(
+            *client.canceled_by_client_order_id.lock(),
+            vec![order.client_order_id()]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn order_created_within_latency_budget_is_not_canceled() {
+        let (exchange, order) = wait_creation(Duration::ZERO).await;
+
+        assert!(!order.fn_ref(|x| x.internal_props.is_ack_timeout));
+        let client = exchange.client_as::<TestClient>().expect("in test");
+        assert!(client.canceled_by_client_order_id.lock().is_empty());
+    }
+}
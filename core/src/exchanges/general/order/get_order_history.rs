@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use chrono::Duration;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+
+use crate::exchanges::common::CurrencyPair;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::request_type::RequestType;
+use crate::orders::fill::EventSourceType;
+use crate::orders::order::{OrderInfo, OrderStatus};
+
+/// Max time range of one order history request, exchanges reject requests of longer ranges
+const MAX_ORDER_HISTORY_WINDOW_HOURS: i64 = 24;
+
+impl Exchange {
+    /// Orders created in time range [from, to] including completed and canceled ones
+    pub async fn get_order_history(
+        &self,
+        currency_pair: CurrencyPair,
+        from: DateTime,
+        to: DateTime,
+    ) -> Result<Vec<OrderInfo>> {
+        self.request_order_history(currency_pair, from, to)
+            .await?
+            .with_context(|| {
+                format!(
+                    "Order history isn't supported on {}",
+                    self.exchange_account_id
+                )
+            })
+    }
+
+    /// Order history is requested by windows of `MAX_ORDER_HISTORY_WINDOW_HOURS`.
+    /// Returns `None` if exchange doesn't provide history of orders
+    async fn request_order_history(
+        &self,
+        currency_pair: CurrencyPair,
+        from: DateTime,
+        to: DateTime,
+    ) -> Result<Option<Vec<OrderInfo>>> {
+        let mut orders: Vec<OrderInfo> = vec![];
+        for (window_from, window_to) in order_history_windows(from, to) {
+            self.timeout_manager
+                .reserve_when_available(
+                    self.exchange_account_id,
+                    RequestType::GetOrderHistory,
+                    None,
+                    CancellationToken::default(),
+                )?
+                .await
+                .into_result()?;
+
+            let window_orders = match self
+                .exchange_client
+                .get_order_history(currency_pair, window_from, window_to)
+                .await?
+            {
+                Some(window_orders) => window_orders,
+                None => return Ok(None),
+            };
+
+            // orders created at the bound of windows are returned twice
+            for order in window_orders {
+                if !orders
+                    .iter()
+                    .any(|x| x.exchange_order_id == order.exchange_order_id)
+                {
+                    orders.push(order);
+                }
+            }
+        }
+
+        Ok(Some(orders))
+    }
+
+    /// Updates not finished local orders by their state in order history, so fills and
+    /// cancellations missed by websocket aren't lost. Returns count of updated orders,
+    /// it's zero if exchange doesn't provide history of orders
+    pub async fn reconcile_order_history(&self, from: DateTime, to: DateTime) -> Result<usize> {
+        let currency_pairs = self.symbols.iter().map(|x| *x.key()).collect_vec();
+
+        let mut reconciled_count = 0;
+        for currency_pair in currency_pairs {
+            let orders = match self.request_order_history(currency_pair, from, to).await? {
+                Some(orders) => orders,
+                None => return Ok(0),
+            };
+
+            for order_info in orders {
+                if self.reconcile_order(&order_info)? {
+                    reconciled_count += 1;
+                }
+            }
+        }
+
+        if reconciled_count > 0 {
+            log::warn!(
+                "Reconciled {reconciled_count} orders by order history on {}",
+                self.exchange_account_id
+            );
+        }
+
+        Ok(reconciled_count)
+    }
+
+    pub(super) fn reconcile_order(&self, order_info: &OrderInfo) -> Result<bool> {
+        let order = match self
+            .orders
+            .cache_by_exchange_id
+            .get(&order_info.exchange_order_id)
+        {
+            Some(order) => order.clone(),
+            None => return Ok(false),
+        };
+
+        if order.is_finished() {
+            return Ok(false);
+        }
+
+        let has_missed_fills = order_info.filled_amount > order.filled_amount();
+        if has_missed_fills {
+            self.handle_order_filled_by_order_info(&order, order_info)?;
+        }
+
+        let is_missed_cancellation =
+            order_info.order_status == OrderStatus::Canceled && !order.is_finished();
+        if is_missed_cancellation {
+            self.handle_cancel_order_succeeded(
+                Some(&order.client_order_id()),
+                &order_info.exchange_order_id,
+                Some(order_info.filled_amount),
+                EventSourceType::RestFallback,
+            );
+        }
+
+        Ok(has_missed_fills || is_missed_cancellation)
+    }
+}
+
+/// Splits time range [from, to] into consecutive windows of `MAX_ORDER_HISTORY_WINDOW_HOURS`
+fn order_history_windows(from: DateTime, to: DateTime) -> Vec<(DateTime, DateTime)> {
+    let max_window = Duration::hours(MAX_ORDER_HISTORY_WINDOW_HOURS);
+
+    let mut windows = vec![];
+    let mut window_from = from;
+    loop {
+        let window_to = (window_from + max_window).min(to);
+        windows.push((window_from, window_to));
+        if window_to >= to {
+            return windows;
+        }
+        window_from = window_to;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{TimeZone, Utc};
+    use parking_lot::RwLock;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::exchanges::common::ExchangeAccountId;
+    use crate::exchanges::general::symbol::{Precision, Symbol};
+    use crate::exchanges::general::test_helper::{get_test_exchange_with_client, TestClient};
+    use crate::orders::order::{
+        ClientOrderId, ExchangeOrderId, OrderRole, OrderSide, OrderSnapshot, OrderType,
+    };
+
+    #[test]
+    fn order_history_is_requested_by_windows() {
+        let from = Utc.ymd(2022, 1, 1).and_hms(0, 0, 0);
+
+        let windows = order_history_windows(from, from + Duration::hours(50));
+        assert_eq!(
+            windows,
+            vec![
+                (from, from + Duration::hours(24)),
+                (from + Duration::hours(24), from + Duration::hours(48)),
+                (from + Duration::hours(48), from + Duration::hours(50)),
+            ]
+        );
+
+        let windows = order_history_windows(from, from + Duration::hours(1));
+        assert_eq!(windows, vec![(from, from + Duration::hours(1))]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn closed_order_is_resynced_by_order_history() {
+        let symbol = Arc::new(Symbol::new(
+            false,
+            false,
+            "PHB".into(),
+            "PHB".into(),
+            "BTC".into(),
+            "BTC".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "PHB".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(0.1) },
+        ));
+        let currency_pair = symbol.currency_pair();
+        let exchange_order_id = ExchangeOrderId::new("1".into());
+        let client_order_id = ClientOrderId::unique_id();
+        let canceled_order = OrderInfo::new(
+            currency_pair,
+            exchange_order_id.clone(),
+            client_order_id.clone(),
+            OrderSide::Buy,
+            OrderStatus::Canceled,
+            dec!(0.8),
+            dec!(12),
+            dec!(0),
+            dec!(0),
+            None,
+            None,
+            None,
+        );
+        let client = TestClient {
+            order_history: vec![canceled_order],
+            ..TestClient::default()
+        };
+        let exchange_account_id = ExchangeAccountId::new("local_exchange_account_id", 0);
+        let (exchange, _rx) =
+            get_test_exchange_with_client(symbol, exchange_account_id, Box::new(client));
+
+        // order was created earlier than a day ago, so its history is requested by 2 windows
+        let mut order = OrderSnapshot::with_params(
+            client_order_id,
+            OrderType::Limit,
+            Some(OrderRole::Maker),
+            exchange_account_id,
+            currency_pair,
+            dec!(0.8),
+            dec!(12),
+            OrderSide::Buy,
+            None,
+            "StrategyInUnitTests",
+        );
+        let mut header = (*order.header).clone();
+        header.init_time = Utc::now() - Duration::hours(30);
+        order.header = Arc::new(header);
+        order.props.exchange_order_id = Some(exchange_order_id.clone());
+        order.set_status(OrderStatus::Created, Utc::now());
+        let order = exchange
+            .orders
+            .add_snapshot_initial(Arc::new(RwLock::new(order)));
+        let _ = exchange
+            .orders
+            .cache_by_exchange_id
+            .insert(exchange_order_id, order.clone());
+
+        let resynced_count = exchange.resync_orders().await.expect("in test");
+
+        assert_eq!(resynced_count, 1);
+        assert_eq!(order.status(), OrderStatus::Canceled);
+        let client = exchange.client_as::<TestClient>().expect("in test");
+        assert_eq!(client.order_history_requests.lock().len(), 2);
+    }
+}
@@ -3,6 +3,10 @@ pub mod create;
 pub mod create_websocket_based;
 pub mod get_info;
 pub mod get_open_orders;
+pub mod get_order_history;
 pub mod get_order_trades;
+pub mod reduce;
+pub mod reservation;
+pub mod resync;
 pub mod wait_cancel;
 pub mod wait_finish;
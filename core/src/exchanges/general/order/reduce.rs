@@ -0,0 +1,265 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use mmb_utils::cancellation_token::CancellationToken;
+
+use crate::exchanges::common::Amount;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::general::symbol::Round;
+use crate::orders::order::OrderStatus;
+use crate::orders::pool::OrderRef;
+
+impl Exchange {
+    /// Reduces amount of created order without re-creating it, so order keeps its priority in queue.
+    /// `new_amount` is total amount of order including filled part and it's rounded down by
+    /// amount precision of symbol. Fails with `ExchangeErrorType::Unsupported` on exchanges which
+    /// don't provide such operation. Reserved balance of reduced amount is released on success,
+    /// the rest is released as usual when order is finished
+    pub async fn reduce_order(&self, order: &OrderRef, new_amount: Amount) -> Result<Amount> {
+        let (client_order_id, currency_pair, status, amount, filled_amount) = order.fn_ref(|x| {
+            (
+                x.header.client_order_id.clone(),
+                x.header.currency_pair,
+                x.status(),
+                x.header.amount,
+                x.fills.filled_amount,
+            )
+        });
+
+        if status != OrderStatus::Created {
+            bail!("Unable to reduce order {client_order_id} with status {status:?}");
+        }
+
+        let symbol = self.get_symbol(currency_pair)?;
+        let new_amount = symbol.amount_round(new_amount, Round::Floor);
+        if new_amount >= amount {
+            bail!("New amount {new_amount} of order {client_order_id} should be less than current amount {amount}");
+        }
+        if new_amount <= filled_amount {
+            bail!("New amount {new_amount} of order {client_order_id} should be greater than filled amount {filled_amount}, cancel order instead");
+        }
+
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::ReduceOrder,
+                None,
+                CancellationToken::default(),
+            )?
+            .await
+            .into_result()?;
+
+        log::info!(
+            "Submitting reduction of order {client_order_id} from {amount} to {new_amount} on {}",
+            self.exchange_account_id
+        );
+
+        self.exchange_client
+            .reduce_order(order, new_amount)
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to reduce order {client_order_id} on {}",
+                    self.exchange_account_id
+                )
+            })?;
+
+        let header = order.fn_mut(|x| {
+            let mut header = (*x.header).clone();
+            header.amount = new_amount;
+            x.header = Arc::new(header);
+            x.header.clone()
+        });
+        self.unreserve_reduced_amount(&header, amount - new_amount, true);
+
+        log::info!(
+            "Order {client_order_id} is reduced from {amount} to {new_amount} on {}",
+            self.exchange_account_id
+        );
+
+        Ok(new_amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use mmb_utils::hashmap;
+    use parking_lot::RwLock;
+    use rust_decimal_macros::dec;
+
+    use crate::balance::manager::balance_manager::BalanceManager;
+    use crate::balance::manager::tests::balance_manager_base::BalanceManagerBase;
+    use crate::exchanges::fault_injection::{FaultInjectingClient, FaultMode, FaultScript};
+    use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+    use crate::exchanges::general::exchange::BoxExchangeClient;
+    use crate::exchanges::general::symbol::{Precision, Symbol};
+    use crate::exchanges::general::test_helper::{get_test_exchange_with_client, TestClient};
+    use crate::orders::order::{OrderSide, ReservationId};
+
+    struct TestContext {
+        base: BalanceManagerBase,
+        exchange: Arc<Exchange>,
+        order: OrderRef,
+        reservation_id: ReservationId,
+    }
+
+    impl TestContext {
+        /// Created sell order of amount 5 with approved reservation of the whole ETH balance
+        fn new(exchange_client: BoxExchangeClient) -> Self {
+            let mut base = BalanceManagerBase::new();
+            let symbol = Arc::new(Symbol::new(
+                false,
+                false,
+                BalanceManagerBase::eth().as_str().into(),
+                BalanceManagerBase::eth(),
+                BalanceManagerBase::btc().as_str().into(),
+                BalanceManagerBase::btc(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                BalanceManagerBase::eth(),
+                Some(BalanceManagerBase::btc()),
+                Precision::ByTick { tick: dec!(0.1) },
+                Precision::ByTick { tick: dec!(0.001) },
+            ));
+            base.set_symbol(symbol.clone());
+
+            let exchange =
+                get_test_exchange_with_client(symbol, base.exchange_account_id_1, exchange_client)
+                    .0;
+            let balance_manager = BalanceManager::new(CurrencyPairToSymbolConverter::new(
+                hashmap![exchange.exchange_account_id => exchange.clone()],
+            ));
+            exchange.setup_balance_manager(balance_manager.clone());
+            base.set_balance_manager(balance_manager);
+            BalanceManagerBase::update_balance(
+                &mut base.balance_manager(),
+                base.exchange_account_id_1,
+                hashmap![BalanceManagerBase::eth() => dec!(5)],
+            );
+
+            let reserve_parameters =
+                base.create_reserve_parameters(OrderSide::Sell, dec!(0.2), dec!(5));
+            let reservation_id = base
+                .balance_manager()
+                .try_reserve(&reserve_parameters, &mut None)
+                .expect("in test");
+            let order = base.create_order(OrderSide::Sell, reservation_id);
+            base.balance_manager().approve_reservation(
+                reservation_id,
+                &order.header.client_order_id,
+                order.header.amount,
+            );
+
+            let order = exchange
+                .orders
+                .add_snapshot_initial(Arc::new(RwLock::new(order)));
+            order.fn_mut(|x| x.set_status(OrderStatus::Created, Utc::now()));
+
+            TestContext {
+                base,
+                exchange,
+                order,
+                reservation_id,
+            }
+        }
+
+        fn available_balance(&self) -> Option<Amount> {
+            let reserve_parameters =
+                self.base
+                    .create_reserve_parameters(OrderSide::Sell, dec!(0.2), dec!(0));
+            self.base
+                .balance_manager()
+                .get_balance_by_reserve_parameters(&reserve_parameters)
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn reduced_amount_is_unreserved() {
+        let context = TestContext::new(Box::new(TestClient::default()));
+
+        let new_amount = context
+            .exchange
+            .reduce_order(&context.order, dec!(2.0004))
+            .await
+            .expect("in test");
+
+        assert_eq!(new_amount, dec!(2));
+        assert_eq!(context.order.amount(), dec!(2));
+        assert_eq!(context.available_balance(), Some(dec!(3)));
+
+        // the rest of reservation is released when order is finished
+        context
+            .base
+            .balance_manager()
+            .unreserve_by_client_order_id(
+                context.reservation_id,
+                context.order.client_order_id(),
+                context.order.amount(),
+            )
+            .expect("in test");
+        assert_eq!(context.available_balance(), Some(dec!(5)));
+        assert!(context
+            .base
+            .balance_manager()
+            .get_reservation(context.reservation_id)
+            .is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn reservation_is_kept_if_reduction_is_rejected_by_exchange() {
+        let script = FaultScript::new(Vec::new(), 1);
+        script.toggle_on(FaultMode::Downtime);
+        let context = TestContext::new(Box::new(FaultInjectingClient::new(
+            Box::new(TestClient::default()),
+            script,
+        )));
+
+        let _ = context
+            .exchange
+            .reduce_order(&context.order, dec!(2))
+            .await
+            .expect_err("in test");
+
+        assert_eq!(context.order.amount(), dec!(5));
+        assert_eq!(context.available_balance(), Some(dec!(0)));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn amount_not_less_than_current_is_rejected() {
+        let context = TestContext::new(Box::new(TestClient::default()));
+
+        let error = context
+            .exchange
+            .reduce_order(&context.order, dec!(5))
+            .await
+            .expect_err("in test");
+
+        assert!(error
+            .to_string()
+            .contains("should be less than current amount"));
+        assert_eq!(context.order.amount(), dec!(5));
+        assert_eq!(context.available_balance(), Some(dec!(0)));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn order_which_is_not_created_is_rejected() {
+        let context = TestContext::new(Box::new(TestClient::default()));
+        context
+            .order
+            .fn_mut(|x| x.set_status(OrderStatus::Canceled, Utc::now()));
+
+        let _ = context
+            .exchange
+            .reduce_order(&context.order, dec!(2))
+            .await
+            .expect_err("in test");
+
+        assert_eq!(context.order.amount(), dec!(5));
+    }
+}
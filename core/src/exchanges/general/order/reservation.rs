@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+
+use crate::balance::manager::balance_manager::BalanceManager;
+use crate::exchanges::common::{Amount, CurrencyPair, Price};
+use crate::exchanges::general::exchange::Exchange;
+use crate::misc::reserve_parameters::ReserveParameters;
+use crate::orders::order::{OrderHeader, OrderSide, ReservationId};
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+
+impl Exchange {
+    pub(crate) fn get_balance_manager(&self) -> Option<Arc<Mutex<BalanceManager>>> {
+        self.balance_manager
+            .lock()
+            .as_ref()
+            .and_then(|x| x.upgrade())
+    }
+
+    /// Reserves balance for order created by engine services which don't work through
+    /// `DispositionExecutor`. Reservation is approved when order is created and should be
+    /// released by `release_order_reservation` when order is finished or isn't created
+    pub(crate) fn reserve_order_balance(
+        &self,
+        configuration_descriptor: ConfigurationDescriptor,
+        currency_pair: CurrencyPair,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+    ) -> Result<ReservationId> {
+        let symbol = self.get_symbol(currency_pair)?;
+        let balance_manager = self
+            .get_balance_manager()
+            .context("BalanceManager isn't available to reserve balance")?;
+
+        let reserve_parameters = ReserveParameters::new(
+            configuration_descriptor,
+            self.exchange_account_id,
+            symbol,
+            side,
+            price,
+            amount,
+        );
+        let reservation_id = balance_manager
+            .lock()
+            .try_reserve(&reserve_parameters, &mut None);
+        reservation_id.with_context(|| {
+            format!(
+                "Not enough balance to reserve {side:?} {amount} {currency_pair} on {}",
+                self.exchange_account_id
+            )
+        })
+    }
+
+    /// Reserves balance for three orders at once, so either all of them or none are reserved.
+    /// Orders are specified by currency pair, side, price and amount
+    pub(crate) fn reserve_three_orders_balance(
+        &self,
+        configuration_descriptor: ConfigurationDescriptor,
+        orders: [(CurrencyPair, OrderSide, Price, Amount); 3],
+    ) -> Result<[ReservationId; 3]> {
+        let balance_manager = self
+            .get_balance_manager()
+            .context("BalanceManager isn't available to reserve balance")?;
+
+        let [first, second, third] = orders.map(|(currency_pair, side, price, amount)| {
+            Ok::<_, anyhow::Error>(ReserveParameters::new(
+                configuration_descriptor,
+                self.exchange_account_id,
+                self.get_symbol(currency_pair)?,
+                side,
+                price,
+                amount,
+            ))
+        });
+        let (first, second, third) = balance_manager
+            .lock()
+            .try_reserve_three(first?, second?, third?)
+            .with_context(|| {
+                format!(
+                    "Not enough balance to reserve orders {orders:?} on {}",
+                    self.exchange_account_id
+                )
+            })?;
+
+        Ok([first, second, third])
+    }
+
+    /// Releases reservation which order wasn't created for
+    pub(crate) fn release_unused_reservation(&self, reservation_id: ReservationId) {
+        let balance_manager = match self.get_balance_manager() {
+            Some(balance_manager) => balance_manager,
+            None => {
+                log::warn!(
+                    "BalanceManager isn't available to release unused reservation {reservation_id}"
+                );
+                return;
+            }
+        };
+
+        let mut balance_manager = balance_manager.lock();
+        if balance_manager.get_reservation(reservation_id).is_some() {
+            balance_manager
+                .unreserve_rest(reservation_id)
+                .unwrap_or_else(|err| {
+                    log::error!("Failed to unreserve rest of {reservation_id}: {err:?}")
+                });
+        }
+    }
+
+    /// Releases the whole reservation of finished order including its approved part
+    pub(crate) fn release_order_reservation(&self, header: &OrderHeader) {
+        let reservation_id = match header.reservation_id {
+            Some(reservation_id) => reservation_id,
+            None => return,
+        };
+
+        let balance_manager = match self.get_balance_manager() {
+            Some(balance_manager) => balance_manager,
+            None => {
+                log::warn!(
+                    "BalanceManager isn't available to release reservation {reservation_id} of order {}",
+                    header.client_order_id
+                );
+                return;
+            }
+        };
+
+        let mut balance_manager = balance_manager.lock();
+        let is_approved = match balance_manager.get_reservation(reservation_id) {
+            Some(reservation) => reservation
+                .approved_parts
+                .contains_key(&header.client_order_id),
+            None => return,
+        };
+
+        if is_approved {
+            balance_manager
+                .unreserve_by_client_order_id(
+                    reservation_id,
+                    header.client_order_id.clone(),
+                    header.amount,
+                )
+                .unwrap_or_else(|err| {
+                    log::error!(
+                        "Failed to unreserve order {} from {reservation_id}: {err:?}",
+                        header.client_order_id
+                    )
+                });
+        }
+
+        if balance_manager.get_reservation(reservation_id).is_some() {
+            balance_manager
+                .unreserve_rest(reservation_id)
+                .unwrap_or_else(|err| {
+                    log::error!("Failed to unreserve rest of {reservation_id}: {err:?}")
+                });
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::request_type::RequestType;
+use crate::infrastructure::spawn_future_ok;
+use crate::misc::time::time_manager;
+use crate::orders::order::OrderStatus;
+
+impl Exchange {
+    /// Restores state of orders after websocket events could be missed, e.g. while websockets
+    /// were reconnected or connector process was restarted. Open orders unknown to engine are
+    /// added, missed fills and cancellations of not finished orders are applied by their state
+    /// on exchange. Orders which aren't open anymore are reconciled by order history if exchange
+    /// provides it, otherwise they are requested one by one. Returns count of updated orders
+    pub async fn resync_orders(&self) -> Result<usize> {
+        if self.is_market_data_only() {
+            return Ok(0);
+        }
+
+        let open_orders = self.get_open_orders(true).await?;
+
+        let mut resynced_count = 0;
+        let mut closed_orders = vec![];
+        for order in self.orders.not_finished.all() {
+            // creation of order is resolved by its own fallback
+            if order.status() == OrderStatus::Creating {
+                continue;
+            }
+            let exchange_order_id = match order.exchange_order_id() {
+                Some(exchange_order_id) => exchange_order_id,
+                None => continue,
+            };
+
+            match open_orders
+                .iter()
+                .find(|x| x.exchange_order_id == exchange_order_id)
+            {
+                Some(order_info) => {
+                    if self.reconcile_order(order_info)? {
+                        resynced_count += 1;
+                    }
+                }
+                None => closed_orders.push(order),
+            }
+        }
+
+        let history_from = closed_orders
+            .iter()
+            .map(|x| x.fn_ref(|x| x.header.init_time))
+            .min();
+        if let Some(history_from) = history_from {
+            match self
+                .reconcile_order_history(history_from, time_manager::now())
+                .await
+            {
+                Ok(reconciled_count) => resynced_count += reconciled_count,
+                Err(error) => log::warn!(
+                    "Unable to reconcile order history on {}: {error:?}",
+                    self.exchange_account_id
+                ),
+            }
+        }
+
+        for order in closed_orders.iter().filter(|x| !x.is_finished()) {
+            self.timeout_manager
+                .reserve_when_available(
+                    self.exchange_account_id,
+                    RequestType::GetOrderInfo,
+                    None,
+                    CancellationToken::default(),
+                )?
+                .await
+                .into_result()?;
+
+            let order_info = match self.get_order_info(order).await {
+                Ok(order_info) => order_info,
+                Err(error) => {
+                    log::warn!(
+                        "Unable to resync order {} on {}: {error:?}",
+                        order.client_order_id(),
+                        self.exchange_account_id
+                    );
+                    continue;
+                }
+            };
+
+            if self.reconcile_order(&order_info)? {
+                resynced_count += 1;
+            }
+        }
+
+        if resynced_count > 0 {
+            log::warn!(
+                "Resynced {resynced_count} orders after reconnection on {}",
+                self.exchange_account_id
+            );
+        }
+
+        Ok(resynced_count)
+    }
+
+    pub(crate) fn spawn_orders_resync(self: &Arc<Self>) {
+        let exchange = self.clone();
+        let _ = spawn_future_ok(
+            &format!("Orders resync of {}", self.exchange_account_id),
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            async move {
+                if let Err(error) = exchange.resync_orders().await {
+                    log::error!(
+                        "Failed to resync orders of {}: {error:?}",
+                        exchange.exchange_account_id
+                    );
+                }
+            },
+        );
+    }
+}
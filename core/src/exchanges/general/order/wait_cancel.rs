@@ -14,7 +14,8 @@ use tokio::time::{sleep, timeout};
 use super::cancel::CancelOrderResult;
 use crate::exchanges::common::ToStdExpected;
 use crate::exchanges::{
-    general::request_type::RequestType, timeouts::requests_timeout_manager::RequestGroupId,
+    general::request_type::RequestType, timeouts::request_priority_queue::RequestPriority,
+    timeouts::requests_timeout_manager::RequestGroupId,
 };
 use crate::misc::time::time_manager;
 use crate::{
@@ -146,14 +147,14 @@ impl Exchange {
             log!(log_event_level, "Cancellation iteration is {attempt_number} on {client_order_id} {exchange_order_id:?} {}", self.exchange_account_id);
 
             self.timeout_manager
-                .reserve_when_available(
+                .reserve_by_priority(
                     self.exchange_account_id,
                     RequestType::CancelOrder,
+                    RequestPriority::Cancel,
                     pre_reservation_group_id,
                     order_is_finished_token.clone(),
-                )?
-                .await
-                .into_result()?;
+                )
+                .await?;
 
             let cancel_order_fut = self.start_cancel_order(order, cancellation_token.clone());
             pin_mut!(cancel_order_fut);
@@ -416,33 +416,7 @@ impl Exchange {
             RequestType::GetOrderInfo => {
                 let order_info = match self.get_order_info(order).await {
                     Ok(order_info) => {
-                        let exchange_order_id = order.exchange_order_id().with_context(|| {
-                        "No exchange_order_id in order while handle_order_filled_for_restfallback"
-                    })?;
-
-                        let commission_currency_code = order_info
-                            .commission_currency_code
-                            .clone()
-                            .map(|currency_code| CurrencyCode::new(&currency_code));
-
-                        let mut fill_event = FillEvent {
-                            source_type: EventSourceType::RestFallback,
-                            trade_id: None,
-                            client_order_id: Some(order.client_order_id()),
-                            exchange_order_id,
-                            fill_price: order_info.average_fill_price,
-                            fill_amount: FillAmount::Total {
-                                total_filled_amount: order_info.filled_amount,
-                            },
-                            order_role: None,
-                            commission_currency_code,
-                            commission_rate: order_info.commission_rate,
-                            commission_amount: order_info.commission_amount,
-                            fill_type: OrderFillType::UserTrade,
-                            special_order_data: None,
-                            fill_date: None,
-                        };
-                        self.handle_order_filled(&mut fill_event);
+                        self.handle_order_filled_by_order_info(order, &order_info)?;
 
                         RequestResult::Success(order_info)
                     }
@@ -458,6 +432,43 @@ impl Exchange {
         }
     }
 
+    /// Handles total filled amount of order received by REST request
+    pub(crate) fn handle_order_filled_by_order_info(
+        &self,
+        order: &OrderRef,
+        order_info: &OrderInfo,
+    ) -> Result<()> {
+        let exchange_order_id = order.exchange_order_id().with_context(|| {
+            "No exchange_order_id in order while handle_order_filled_by_order_info"
+        })?;
+
+        let commission_currency_code = order_info
+            .commission_currency_code
+            .clone()
+            .map(|currency_code| CurrencyCode::new(&currency_code));
+
+        let mut fill_event = FillEvent {
+            source_type: EventSourceType::RestFallback,
+            trade_id: None,
+            client_order_id: Some(order.client_order_id()),
+            exchange_order_id,
+            fill_price: order_info.average_fill_price,
+            fill_amount: FillAmount::Total {
+                total_filled_amount: order_info.filled_amount,
+            },
+            order_role: None,
+            commission_currency_code,
+            commission_rate: order_info.commission_rate,
+            commission_amount: order_info.commission_amount,
+            fill_type: OrderFillType::UserTrade,
+            special_order_data: None,
+            fill_date: None,
+        };
+        self.handle_order_filled(&mut fill_event);
+
+        Ok(())
+    }
+
     pub(crate) fn handle_order_filled_for_rest_fallback(
         &self,
         order: &OrderRef,
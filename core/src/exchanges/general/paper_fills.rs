@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use rust_decimal_macros::dec;
+
+use crate::exchanges::common::{Amount, Price};
+use crate::exchanges::events::Trade;
+use crate::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+use crate::orders::order::{ClientOrderId, OrderSide};
+
+/// Resting limit order of paper trading
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct SimulatedOrder {
+    side: OrderSide,
+    price: Price,
+    amount: Amount,
+    filled_amount: Amount,
+    /// Estimated amount of orders on the same price level placed before the order
+    queue_ahead: Amount,
+}
+
+impl SimulatedOrder {
+    fn rest_amount(&self) -> Amount {
+        self.amount - self.filled_amount
+    }
+
+    /// Trade or book level with price better than order price for counterparty
+    fn is_price_through(&self, price: Price) -> bool {
+        match self.side {
+            OrderSide::Buy => price < self.price,
+            OrderSide::Sell => price > self.price,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SimulatedFill {
+    pub client_order_id: ClientOrderId,
+    pub price: Price,
+    pub amount: Amount,
+    pub is_completed: bool,
+}
+
+/// Simulates fills of resting limit orders of one market in paper trading by public trades.
+/// Order is filled when trades print through its price. Trades on order price fill it only
+/// after estimated queue ahead of the order is consumed
+#[derive(Debug, Default)]
+pub struct PaperFillSimulator {
+    orders: HashMap<ClientOrderId, SimulatedOrder>,
+}
+
+impl PaperFillSimulator {
+    /// Order is placed to the end of queue on its price level of current order book
+    pub fn add_order(
+        &mut self,
+        client_order_id: ClientOrderId,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        snapshot: &LocalOrderBookSnapshot,
+    ) {
+        let queue_ahead = snapshot
+            .get_price_levels_to_match(side.change_side())
+            .find(|(&level_price, _)| level_price == price)
+            .map(|(_, &level_amount)| level_amount)
+            .unwrap_or(dec!(0));
+
+        let _ = self.orders.insert(
+            client_order_id,
+            SimulatedOrder {
+                side,
+                price,
+                amount,
+                filled_amount: dec!(0),
+                queue_ahead,
+            },
+        );
+    }
+
+    pub fn cancel_order(&mut self, client_order_id: &ClientOrderId) -> bool {
+        self.orders.remove(client_order_id).is_some()
+    }
+
+    pub fn handle_trade(&mut self, trade: &Trade) -> Vec<SimulatedFill> {
+        let mut fills = vec![];
+        for (client_order_id, order) in &mut self.orders {
+            // resting order can be matched only with taker of opposite side
+            if order.side == trade.side {
+                continue;
+            }
+
+            let fill_amount = if order.is_price_through(trade.price) {
+                order.rest_amount()
+            } else if order.price == trade.price {
+                let consumed_queue = order.queue_ahead.min(trade.quantity);
+                order.queue_ahead -= consumed_queue;
+                (trade.quantity - consumed_queue).min(order.rest_amount())
+            } else {
+                continue;
+            };
+
+            if fill_amount.is_zero() {
+                continue;
+            }
+
+            order.filled_amount += fill_amount;
+            fills.push(SimulatedFill {
+                client_order_id: client_order_id.clone(),
+                price: order.price,
+                amount: fill_amount,
+                is_completed: order.rest_amount().is_zero(),
+            });
+        }
+
+        self.remove_completed(&fills);
+        fills
+    }
+
+    /// Orders crossed by the opposite side of order book are filled fully
+    pub fn handle_order_book(&mut self, snapshot: &LocalOrderBookSnapshot) -> Vec<SimulatedFill> {
+        let mut fills = vec![];
+        for (client_order_id, order) in &mut self.orders {
+            let top_price = match order.side {
+                OrderSide::Buy => snapshot.get_top_ask(),
+                OrderSide::Sell => snapshot.get_top_bid(),
+            };
+            let is_crossed = top_price
+                .map(|(price, _)| order.is_price_through(price) || price == order.price)
+                .unwrap_or(false);
+            if !is_crossed {
+                continue;
+            }
+
+            let fill_amount = order.rest_amount();
+            order.filled_amount += fill_amount;
+            fills.push(SimulatedFill {
+                client_order_id: client_order_id.clone(),
+                price: order.price,
+                amount: fill_amount,
+                is_completed: true,
+            });
+        }
+
+        self.remove_completed(&fills);
+        fills
+    }
+
+    fn remove_completed(&mut self, fills: &[SimulatedFill]) {
+        for fill in fills.iter().filter(|x| x.is_completed) {
+            let _ = self.orders.remove(&fill.client_order_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::events::{TickDirection, TradeId};
+    use crate::order_book::order_book_data::OrderBookData;
+    use crate::order_book_data;
+    use chrono::Utc;
+
+    fn snapshot() -> LocalOrderBookSnapshot {
+        let order_book_data: OrderBookData = order_book_data![
+            dec!(101) => dec!(1),
+            ;
+            dec!(100) => dec!(3),
+        ];
+        order_book_data.to_local_order_book_snapshot()
+    }
+
+    fn trade(side: OrderSide, price: Price, quantity: Amount) -> Trade {
+        Trade {
+            trade_id: TradeId::Number(1),
+            price,
+            quantity,
+            side,
+            transaction_time: Utc::now(),
+            tick_direction: TickDirection::None,
+        }
+    }
+
+    fn filled_amounts(fills: &[SimulatedFill]) -> Vec<Amount> {
+        fills.iter().map(|x| x.amount).collect()
+    }
+
+    #[test]
+    pub fn fill_after_queue_is_consumed() {
+        let mut simulator = PaperFillSimulator::default();
+        let client_order_id = ClientOrderId::unique_id();
+        simulator.add_order(
+            client_order_id,
+            OrderSide::Buy,
+            dec!(100),
+            dec!(2),
+            &snapshot(),
+        );
+
+        // buy taker can't fill resting buy order
+        assert!(simulator
+            .handle_trade(&trade(OrderSide::Buy, dec!(100), dec!(10)))
+            .is_empty());
+
+        // 3 in queue ahead
+        assert!(simulator
+            .handle_trade(&trade(OrderSide::Sell, dec!(100), dec!(2)))
+            .is_empty());
+        let fills = simulator.handle_trade(&trade(OrderSide::Sell, dec!(100), dec!(2)));
+        assert_eq!(filled_amounts(&fills), vec![dec!(1)]);
+        assert!(!fills[0].is_completed);
+
+        let fills = simulator.handle_trade(&trade(OrderSide::Sell, dec!(100), dec!(5)));
+        assert_eq!(filled_amounts(&fills), vec![dec!(1)]);
+        assert!(fills[0].is_completed);
+        assert!(simulator.orders.is_empty());
+    }
+
+    #[test]
+    pub fn trade_through_price_fills_regardless_of_queue() {
+        let mut simulator = PaperFillSimulator::default();
+        simulator.add_order(
+            ClientOrderId::unique_id(),
+            OrderSide::Sell,
+            dec!(101),
+            dec!(2),
+            &snapshot(),
+        );
+
+        assert!(simulator
+            .handle_trade(&trade(OrderSide::Buy, dec!(100.5), dec!(10)))
+            .is_empty());
+        let fills = simulator.handle_trade(&trade(OrderSide::Buy, dec!(101.5), dec!(0.1)));
+        assert_eq!(filled_amounts(&fills), vec![dec!(2)]);
+    }
+
+    #[test]
+    pub fn fill_by_book_crossing() {
+        let mut simulator = PaperFillSimulator::default();
+        simulator.add_order(
+            ClientOrderId::unique_id(),
+            OrderSide::Buy,
+            dec!(100.5),
+            dec!(1),
+            &snapshot(),
+        );
+        assert!(simulator.handle_order_book(&snapshot()).is_empty());
+
+        let crossed: OrderBookData = order_book_data![
+            dec!(100.5) => dec!(1),
+            ;
+            dec!(100) => dec!(3),
+        ];
+        let fills = simulator.handle_order_book(&crossed.to_local_order_book_snapshot());
+        assert_eq!(filled_amounts(&fills), vec![dec!(1)]);
+    }
+}
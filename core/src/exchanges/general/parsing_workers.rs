@@ -0,0 +1,219 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_isolated_future;
+
+/// Processing time of websocket messages of exchange account
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ParsingStats {
+    /// Messages are parsed inline by socket reader if there are no workers
+    pub workers_count: usize,
+    pub received_count: u64,
+    pub parsed_count: u64,
+    /// Time of socket reader spent per message: parsing for inline mode, dispatching to worker otherwise
+    pub avg_reader_time_us: u64,
+    pub max_reader_time_us: u64,
+    /// Time between reading message from socket and start of its parsing. It's 0 for inline mode
+    pub avg_queue_time_us: u64,
+    pub max_queue_time_us: u64,
+    pub avg_parse_time_us: u64,
+    pub max_parse_time_us: u64,
+}
+
+#[derive(Debug, Default)]
+struct Timings {
+    total: Duration,
+    max: Duration,
+}
+
+impl Timings {
+    fn register(&mut self, time: Duration) {
+        self.total += time;
+        self.max = self.max.max(time);
+    }
+
+    fn avg_us(&self, count: u64) -> u64 {
+        match count {
+            0 => 0,
+            _ => self.total.as_micros() as u64 / count,
+        }
+    }
+
+    fn max_us(&self) -> u64 {
+        self.max.as_micros() as u64
+    }
+}
+
+#[derive(Debug, Default)]
+struct ParsingMetricsState {
+    workers_count: usize,
+    received_count: u64,
+    parsed_count: u64,
+    reader: Timings,
+    queue: Timings,
+    parse: Timings,
+}
+
+/// Collects timings of websocket messages processing, so inline parsing and parsing by
+/// workers can be compared
+#[derive(Debug, Default)]
+pub struct ParsingMetrics {
+    state: Mutex<ParsingMetricsState>,
+}
+
+impl ParsingMetrics {
+    pub fn set_workers_count(&self, workers_count: usize) {
+        self.state.lock().workers_count = workers_count;
+    }
+
+    pub fn register_received(&self, reader_time: Duration) {
+        let mut state = self.state.lock();
+        state.received_count += 1;
+        state.reader.register(reader_time);
+    }
+
+    pub fn register_parsed(&self, queue_time: Duration, parse_time: Duration) {
+        let mut state = self.state.lock();
+        state.parsed_count += 1;
+        state.queue.register(queue_time);
+        state.parse.register(parse_time);
+    }
+
+    pub fn snapshot(&self) -> ParsingStats {
+        let state = self.state.lock();
+        ParsingStats {
+            workers_count: state.workers_count,
+            received_count: state.received_count,
+            parsed_count: state.parsed_count,
+            avg_reader_time_us: state.reader.avg_us(state.received_count),
+            max_reader_time_us: state.reader.max_us(),
+            avg_queue_time_us: state.queue.avg_us(state.parsed_count),
+            max_queue_time_us: state.queue.max_us(),
+            avg_parse_time_us: state.parse.avg_us(state.parsed_count),
+            max_parse_time_us: state.parse.max_us(),
+        }
+    }
+}
+
+struct QueuedMessage {
+    msg: String,
+    enqueue_time: Instant,
+}
+
+/// Pool of workers parsing websocket messages of connection instead of socket reader, so reader
+/// isn't delayed by CPU-heavy messages like large depth snapshots. Messages with the same
+/// partition key (usually market) are parsed by the same worker, so they are delivered in order.
+/// Workers are stopped after all queued messages are parsed when connection is closed
+pub(super) struct ParsingWorkers {
+    senders: Vec<mpsc::UnboundedSender<QueuedMessage>>,
+}
+
+impl ParsingWorkers {
+    pub(super) fn start(exchange: &Arc<Exchange>, workers_count: usize) -> Self {
+        let senders = (0..workers_count)
+            .map(|index| {
+                let (tx, rx) = mpsc::unbounded_channel();
+                let _ = spawn_isolated_future(
+                    &format!(
+                        "Exchange account id {} parsing worker {index}",
+                        exchange.exchange_account_id
+                    ),
+                    SpawnFutureFlags::STOP_BY_TOKEN,
+                    Self::worker_future(Arc::downgrade(exchange), rx),
+                    exchange.account_failure_handler(),
+                );
+                tx
+            })
+            .collect();
+
+        ParsingWorkers { senders }
+    }
+
+    async fn worker_future(
+        exchange: Weak<Exchange>,
+        mut receiver: mpsc::UnboundedReceiver<QueuedMessage>,
+    ) -> Result<()> {
+        while let Some(QueuedMessage { msg, enqueue_time }) = receiver.recv().await {
+            match exchange.upgrade() {
+                Some(exchange) => exchange.parse_websocket_message(&msg, enqueue_time.elapsed()),
+                None => return Ok(()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Messages without partition key, e.g. updates of account, are parsed by the first worker
+    pub(super) fn worker_index(&self, partition_key: Option<&str>) -> usize {
+        match partition_key {
+            None => 0,
+            Some(partition_key) => {
+                let mut hasher = DefaultHasher::new();
+                partition_key.hash(&mut hasher);
+                (hasher.finish() % self.senders.len() as u64) as usize
+            }
+        }
+    }
+
+    /// Returns `false` if worker is already stopped
+    pub(super) fn dispatch(&self, worker_index: usize, msg: String) -> bool {
+        let message = QueuedMessage {
+            msg,
+            enqueue_time: Instant::now(),
+        };
+        self.senders[worker_index].send(message).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_of_the_same_market_go_to_the_same_worker() {
+        let senders = (0..4).map(|_| mpsc::unbounded_channel().0).collect();
+        let workers = ParsingWorkers { senders };
+
+        let index = workers.worker_index(Some("btcusdt"));
+        for _ in 0..10 {
+            assert_eq!(workers.worker_index(Some("btcusdt")), index);
+        }
+        assert_eq!(workers.worker_index(None), 0);
+        assert!((0..100)
+            .map(|x| workers.worker_index(Some(&format!("market{x}"))))
+            .all(|x| x < 4));
+    }
+
+    #[test]
+    fn parsing_stats_average_by_their_counts() {
+        let metrics = ParsingMetrics::default();
+        metrics.set_workers_count(2);
+        metrics.register_received(Duration::from_micros(10));
+        metrics.register_received(Duration::from_micros(30));
+        metrics.register_parsed(Duration::from_micros(100), Duration::from_micros(400));
+
+        assert_eq!(
+            metrics.snapshot(),
+            ParsingStats {
+                workers_count: 2,
+                received_count: 2,
+                parsed_count: 1,
+                avg_reader_time_us: 20,
+                max_reader_time_us: 30,
+                avg_queue_time_us: 100,
+                max_queue_time_us: 100,
+                avg_parse_time_us: 400,
+                max_parse_time_us: 400,
+            }
+        );
+    }
+}
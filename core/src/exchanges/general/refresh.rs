@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::send_expected::SendExpectedByRef;
+use mmb_utils::DateTime;
+
+use crate::exchanges::common::CurrencyPair;
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::request_type::RequestType;
+use crate::misc::time::time_manager;
+use crate::order_book::event::{EventType, OrderBookEvent};
+use crate::orders::fill::EventSourceType;
+use crate::orders::order::{OrderInfo, OrderStatus};
+
+/// Min interval between on-demand refreshes of the same target, so strategies can't exhaust request limits
+const MIN_REFRESH_INTERVAL_MS: i64 = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RefreshTarget {
+    OrderBook(CurrencyPair),
+    OpenOrders,
+}
+
+/// Times of the last on-demand refreshes requested by strategies
+#[derive(Default)]
+pub(super) struct OnDemandRefreshes {
+    last_refresh_times: DashMap<RefreshTarget, DateTime>,
+}
+
+impl OnDemandRefreshes {
+    /// Returns `false` if target was refreshed less than `MIN_REFRESH_INTERVAL_MS` ago
+    fn try_start(&self, target: RefreshTarget, now: DateTime) -> bool {
+        match self.last_refresh_times.entry(target) {
+            Entry::Occupied(mut entry) => {
+                let min_interval = chrono::Duration::milliseconds(MIN_REFRESH_INTERVAL_MS);
+                if now - *entry.get() < min_interval {
+                    return false;
+                }
+                let _ = entry.insert(now);
+            }
+            Entry::Vacant(entry) => {
+                let _ = entry.insert(now);
+            }
+        }
+        true
+    }
+}
+
+impl Exchange {
+    fn start_on_demand_refresh(&self, target: RefreshTarget) -> Result<()> {
+        if !self
+            .on_demand_refreshes
+            .try_start(target, time_manager::now())
+        {
+            bail!(
+                "On-demand refresh of {target:?} on {} is requested too often",
+                self.exchange_account_id
+            );
+        }
+        Ok(())
+    }
+
+    /// Requests order book snapshot by REST and sends it as usual snapshot event,
+    /// so local order book is replaced with authoritative data
+    pub async fn refresh_order_book(&self, currency_pair: CurrencyPair) -> Result<()> {
+        self.start_on_demand_refresh(RefreshTarget::OrderBook(currency_pair))?;
+
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::GetOrderBook,
+                None,
+                CancellationToken::default(),
+            )?
+            .await
+            .into_result()?;
+
+        let order_book_data = self
+            .exchange_client
+            .get_order_book(currency_pair)
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to get order book {currency_pair} on {}",
+                    self.exchange_account_id
+                )
+            })?;
+
+        let order_book_data = match order_book_data {
+            Some(order_book_data) => order_book_data,
+            None => bail!(
+                "Exchange {} doesn't provide order book by REST",
+                self.exchange_account_id
+            ),
+        };
+
+        log::info!(
+            "Order book {currency_pair} on {} is refreshed on demand",
+            self.exchange_account_id
+        );
+
+        let order_book_event = OrderBookEvent::new(
+            time_manager::now(),
+            self.exchange_account_id,
+            currency_pair,
+            String::new(),
+            EventType::Snapshot,
+            Arc::new(order_book_data),
+        );
+        self.events_channel
+            .send_expected(ExchangeEvent::OrderBookEvent(order_book_event));
+
+        Ok(())
+    }
+
+    /// Requests open orders by REST and reconciles local orders with them.
+    /// Missing open orders are added, fills and cancellations missed by websocket are applied
+    pub async fn refresh_open_orders(&self) -> Result<Vec<OrderInfo>> {
+        self.start_on_demand_refresh(RefreshTarget::OpenOrders)?;
+
+        let open_orders = self.get_open_orders(true).await?;
+
+        for order_info in &open_orders {
+            if let Some(order) = self
+                .orders
+                .get_by_exchange_id(&order_info.exchange_order_id)
+            {
+                if order_info.filled_amount > order.filled_amount() {
+                    self.handle_order_filled_by_order_info(&order, order_info)?;
+                }
+            }
+        }
+
+        let missing_orders = self
+            .orders
+            .not_finished
+            .all()
+            .into_iter()
+            .filter(|order| {
+                order.exchange_account_id() == self.exchange_account_id
+                    && order.exchange_order_id().is_some_and(|exchange_order_id| {
+                        !open_orders
+                            .iter()
+                            .any(|x| x.exchange_order_id == exchange_order_id)
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        // Orders are finished on exchange, but websocket events about it are lost
+        for order in missing_orders {
+            let order_info = match self.get_order_info(&order).await {
+                Ok(order_info) => order_info,
+                Err(error) => {
+                    log::warn!(
+                        "Unable to get info of order {} missing in open orders on {}: {error:?}",
+                        order.client_order_id(),
+                        self.exchange_account_id
+                    );
+                    continue;
+                }
+            };
+
+            if order_info.filled_amount > order.filled_amount() {
+                self.handle_order_filled_by_order_info(&order, &order_info)?;
+            }
+
+            if order_info.order_status == OrderStatus::Canceled {
+                self.handle_cancel_order_succeeded(
+                    Some(&order.client_order_id()),
+                    &order_info.exchange_order_id,
+                    Some(order_info.filled_amount),
+                    EventSourceType::RestFallback,
+                );
+            }
+        }
+
+        log::info!(
+            "Open orders on {} are refreshed on demand",
+            self.exchange_account_id
+        );
+
+        Ok(open_orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn on_demand_refreshes_are_rate_limited_by_target() {
+        let refreshes = OnDemandRefreshes::default();
+        let time = Utc.ymd(2022, 1, 1).and_hms_milli(12, 0, 0, 0);
+        let order_book =
+            RefreshTarget::OrderBook(CurrencyPair::from_codes("btc".into(), "usdt".into()));
+
+        assert!(refreshes.try_start(order_book, time));
+        assert!(!refreshes.try_start(order_book, time + chrono::Duration::milliseconds(500)));
+        assert!(refreshes.try_start(RefreshTarget::OpenOrders, time));
+        assert!(refreshes.try_start(order_book, time + chrono::Duration::seconds(1)));
+    }
+}
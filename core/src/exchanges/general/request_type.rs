@@ -21,5 +21,9 @@ pub enum RequestType {
     GetLastPrints,
     GetProfileId,
     GetMyTrades,
+    GetOrderHistory,
     SetLeverage,
+    ReduceOrder,
+    /// Exchange-specific request of connector extension, e.g. conversion of currencies
+    Extension,
 }
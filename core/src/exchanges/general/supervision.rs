@@ -0,0 +1,143 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::send_expected::SendExpectedByRef;
+
+use crate::exchanges::block_reasons::ACCOUNT_FAILURE;
+use crate::exchanges::events::{AccountAvailabilityEvent, ExchangeEvent};
+use crate::exchanges::exchange_blocker::BlockType;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+
+/// Delay before the first restart of failed connector
+const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+fn next_restart_delay(delay: Duration) -> Duration {
+    (delay * 2).min(MAX_RESTART_DELAY)
+}
+
+impl Exchange {
+    /// Connector of exchange account is failed and is being restarted
+    pub fn is_account_failed(&self) -> bool {
+        self.is_account_failed.load(Ordering::SeqCst)
+    }
+
+    pub(super) fn account_failure_handler(self: &Arc<Self>) -> impl Fn(&str) + Clone + Send {
+        let exchange = Arc::downgrade(self);
+        move |reason| {
+            if let Some(exchange) = exchange.upgrade() {
+                exchange.on_account_failure(reason);
+            }
+        }
+    }
+
+    /// Handles panic or fatal error in connector of exchange account. Only failed account is stopped:
+    /// trading on it is blocked and strategies are notified by `ExchangeEvent::AccountAvailability`,
+    /// while other accounts keep trading. Connector is restarted with growing delay until it's connected again
+    pub(crate) fn on_account_failure(self: &Arc<Self>, reason: &str) {
+        if self
+            .lifetime_manager
+            .stop_token()
+            .is_cancellation_requested()
+        {
+            return;
+        }
+
+        if self.is_account_failed.swap(true, Ordering::SeqCst) {
+            log::warn!(
+                "Failure of exchange account {} is already handled: {reason}",
+                self.exchange_account_id
+            );
+            return;
+        }
+
+        log::error!(
+            "Exchange account {} failed and will be restarted: {reason}",
+            self.exchange_account_id
+        );
+
+        // connection of failed connector is closed, so it's restarted from scratch
+        self.auto_reconnect.store(false, Ordering::SeqCst);
+        let _ = self.ws_sender.lock().take();
+
+        if let Some(exchange_blocker) = self.exchange_blocker.upgrade() {
+            exchange_blocker.block(self.exchange_account_id, ACCOUNT_FAILURE, BlockType::Manual);
+        }
+        self.send_account_availability_event(false, Some(reason.to_owned()));
+
+        let _ = spawn_future(
+            &format!("Exchange account id {} restart", self.exchange_account_id),
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            self.clone().restart_account(),
+        );
+    }
+
+    async fn restart_account(self: Arc<Self>) -> Result<()> {
+        let stop_token = self.lifetime_manager.stop_token();
+        let mut delay = INITIAL_RESTART_DELAY;
+        let mut attempt = 1;
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = stop_token.when_cancelled() => return Ok(()),
+            }
+
+            match self.connect().await {
+                Ok(()) => break,
+                Err(error) => log::error!(
+                    "Restart attempt {attempt} of exchange account {} failed: {error:?}",
+                    self.exchange_account_id
+                ),
+            }
+
+            attempt += 1;
+            delay = next_restart_delay(delay);
+        }
+
+        log::info!(
+            "Exchange account {} is restarted after {attempt} attempts",
+            self.exchange_account_id
+        );
+        self.is_account_failed.store(false, Ordering::SeqCst);
+        if let Some(exchange_blocker) = self.exchange_blocker.upgrade() {
+            exchange_blocker.unblock(self.exchange_account_id, ACCOUNT_FAILURE);
+        }
+        self.send_account_availability_event(true, None);
+
+        Ok(())
+    }
+
+    fn send_account_availability_event(&self, is_available: bool, reason: Option<String>) {
+        self.events_channel
+            .send_expected(ExchangeEvent::AccountAvailability(
+                AccountAvailabilityEvent {
+                    exchange_account_id: self.exchange_account_id,
+                    is_available,
+                    reason,
+                },
+            ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn restart_delay_grows_up_to_limit() {
+        let mut delay = INITIAL_RESTART_DELAY;
+        for _ in 0..10 {
+            delay = next_restart_delay(delay);
+        }
+
+        assert_eq!(
+            next_restart_delay(INITIAL_RESTART_DELAY),
+            Duration::from_secs(2)
+        );
+        assert_eq!(delay, MAX_RESTART_DELAY);
+    }
+}
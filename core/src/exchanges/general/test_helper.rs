@@ -1,4 +1,5 @@
 #![cfg(test)]
+use std::any::Any;
 use std::sync::Arc;
 
 use crate::{
@@ -11,7 +12,7 @@ use crate::{
         events::{AllowedEventSourceType, ExchangeBalancesAndPositions, ExchangeEvent},
         general::{
             commission::{Commission, CommissionForType},
-            exchange::Exchange,
+            exchange::{BoxExchangeClient, Exchange},
             features::{
                 ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption,
                 RestFillsFeatures, WebSocketOptions,
@@ -27,8 +28,8 @@ use crate::{
     lifecycle::app_lifetime_manager::AppLifetimeManager,
     orders::{
         order::{
-            ClientOrderId, OrderCancelling, OrderInfo, OrderRole, OrderSide, OrderSnapshot,
-            OrderType,
+            ClientOrderId, OrderCancelling, OrderHeader, OrderInfo, OrderRole, OrderSide,
+            OrderSnapshot, OrderType,
         },
         pool::{OrderRef, OrdersPool},
     },
@@ -38,7 +39,8 @@ use anyhow::Result;
 use async_trait::async_trait;
 use chrono::Duration;
 use dashmap::DashMap;
-use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
 use rust_decimal_macros::dec;
 use tokio::sync::broadcast;
 use url::Url;
@@ -53,7 +55,15 @@ use mmb_utils::{cancellation_token::CancellationToken, hashmap, DateTime};
 
 use super::{order::get_order_trades::OrderTrade, symbol::BeforeAfter};
 
-pub struct TestClient;
+/// Client which returns configured open orders and order history
+#[derive(Default)]
+pub struct TestClient {
+    pub open_orders: Vec<OrderInfo>,
+    pub order_history: Vec<OrderInfo>,
+    /// Time ranges of requested order history
+    pub order_history_requests: Mutex<Vec<(DateTime, DateTime)>>,
+    pub canceled_by_client_order_id: Mutex<Vec<ClientOrderId>>,
+}
 
 #[async_trait]
 impl ExchangeClient for TestClient {
@@ -70,7 +80,27 @@ impl ExchangeClient for TestClient {
     }
 
     async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
-        unimplemented!("doesn't need in UT")
+        Ok(self.open_orders.clone())
+    }
+
+    async fn get_order_history(
+        &self,
+        _currency_pair: CurrencyPair,
+        from: DateTime,
+        to: DateTime,
+    ) -> Result<Option<Vec<OrderInfo>>> {
+        self.order_history_requests.lock().push((from, to));
+        Ok(Some(self.order_history.clone()))
+    }
+
+    async fn cancel_order_by_client_order_id(
+        &self,
+        header: &OrderHeader,
+    ) -> Result<(), ExchangeError> {
+        self.canceled_by_client_order_id
+            .lock()
+            .push(header.client_order_id.clone());
+        Ok(())
     }
 
     async fn get_open_orders_by_currency_pair(
@@ -111,6 +141,14 @@ impl ExchangeClient for TestClient {
     async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>> {
         unimplemented!("doesn't need in UT")
     }
+
+    async fn reduce_order(
+        &self,
+        _order: &OrderRef,
+        _new_amount: Amount,
+    ) -> Result<(), ExchangeError> {
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -167,7 +205,12 @@ impl Support for TestClient {
     }
 
     fn get_settings(&self) -> &ExchangeSettings {
-        unimplemented!("doesn't need in UT")
+        static SETTINGS: Lazy<ExchangeSettings> = Lazy::new(ExchangeSettings::default);
+        &SETTINGS
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
     }
 }
 
@@ -233,11 +276,18 @@ pub(crate) fn get_test_exchange_with_symbol(
 pub(crate) fn get_test_exchange_with_symbol_and_id(
     symbol: Arc<Symbol>,
     exchange_account_id: ExchangeAccountId,
+) -> (Arc<Exchange>, broadcast::Receiver<ExchangeEvent>) {
+    get_test_exchange_with_client(symbol, exchange_account_id, Box::new(TestClient::default()))
+}
+
+pub(crate) fn get_test_exchange_with_client(
+    symbol: Arc<Symbol>,
+    exchange_account_id: ExchangeAccountId,
+    exchange_client: BoxExchangeClient,
 ) -> (Arc<Exchange>, broadcast::Receiver<ExchangeEvent>) {
     let lifetime_manager = AppLifetimeManager::new(CancellationToken::new());
     let (tx, rx) = broadcast::channel(10);
 
-    let exchange_client = Box::new(TestClient);
     let referral_reward = dec!(40);
     let commission = Commission::new(
         CommissionForType::new(dec!(0.1), referral_reward),
@@ -0,0 +1,69 @@
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::time::Duration;
+
+/// Weight of last request in exponential moving averages
+const SMOOTHING_FACTOR: Decimal = dec!(0.2);
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct VenueMetricsSnapshot {
+    /// Smoothed order creation roundtrip in milliseconds. None if there were no successful requests yet
+    pub roundtrip_ms: Option<Decimal>,
+    /// Smoothed rate of rejected order creation requests in range [0, 1]
+    pub reject_rate: Decimal,
+    pub requests_count: u64,
+}
+
+/// Recent order creation roundtrip latency and reject rate of exchange account
+#[derive(Debug, Default)]
+pub struct VenueMetrics {
+    state: Mutex<VenueMetricsSnapshot>,
+}
+
+impl VenueMetrics {
+    pub fn register_order_created(&self, roundtrip: Duration) {
+        let roundtrip_ms = Decimal::from(roundtrip.as_millis() as u64);
+
+        let mut state = self.state.lock();
+        state.roundtrip_ms = Some(match state.roundtrip_ms {
+            None => roundtrip_ms,
+            Some(prev) => smooth(prev, roundtrip_ms),
+        });
+        state.reject_rate = smooth(state.reject_rate, dec!(0));
+        state.requests_count += 1;
+    }
+
+    pub fn register_order_rejected(&self) {
+        let mut state = self.state.lock();
+        state.reject_rate = smooth(state.reject_rate, dec!(1));
+        state.requests_count += 1;
+    }
+
+    pub fn snapshot(&self) -> VenueMetricsSnapshot {
+        *self.state.lock()
+    }
+}
+
+fn smooth(prev: Decimal, value: Decimal) -> Decimal {
+    prev + (value - prev) * SMOOTHING_FACTOR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn roundtrip_and_reject_rate_smoothing() {
+        let metrics = VenueMetrics::default();
+
+        metrics.register_order_created(Duration::from_millis(100));
+        metrics.register_order_created(Duration::from_millis(200));
+        metrics.register_order_rejected();
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.roundtrip_ms, Some(dec!(120)));
+        assert_eq!(snapshot.reject_rate, dec!(0.2));
+        assert_eq!(snapshot.requests_count, 3);
+    }
+}
@@ -0,0 +1,172 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{ExchangeAccountId, Price};
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::venue_metrics::VenueMetricsSnapshot;
+use crate::math::ConvertPercentToRate;
+use crate::orders::order::OrderSide;
+
+/// Venue where taker order can be sent
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VenueCandidate {
+    pub exchange_account_id: ExchangeAccountId,
+    /// Expected execution price of taker order on the venue
+    pub price: Price,
+    pub metrics: VenueMetricsSnapshot,
+}
+
+/// Scoring of venues with similar prices. Less score is better
+pub trait VenueScorer {
+    fn score(&self, candidate: &VenueCandidate, best_price: Price, side: OrderSide) -> Decimal;
+}
+
+/// Linear combination of price worsening (in percents), roundtrip latency (in milliseconds)
+/// and reject rate (in percents)
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WeightedVenueScorer {
+    pub price_weight: Decimal,
+    pub latency_weight: Decimal,
+    pub reject_rate_weight: Decimal,
+    /// Roundtrip used for venues without successful requests yet
+    pub unknown_roundtrip_ms: Decimal,
+}
+
+impl Default for WeightedVenueScorer {
+    fn default() -> Self {
+        WeightedVenueScorer {
+            price_weight: dec!(100),
+            latency_weight: dec!(0.1),
+            reject_rate_weight: dec!(1),
+            unknown_roundtrip_ms: dec!(1000),
+        }
+    }
+}
+
+impl VenueScorer for WeightedVenueScorer {
+    fn score(&self, candidate: &VenueCandidate, best_price: Price, side: OrderSide) -> Decimal {
+        let price_worsening = match side {
+            OrderSide::Buy => candidate.price - best_price,
+            OrderSide::Sell => best_price - candidate.price,
+        };
+        let price_worsening_pct = if best_price.is_zero() {
+            dec!(0)
+        } else {
+            price_worsening / best_price * dec!(100)
+        };
+
+        let roundtrip_ms = candidate
+            .metrics
+            .roundtrip_ms
+            .unwrap_or(self.unknown_roundtrip_ms);
+
+        self.price_weight * price_worsening_pct
+            + self.latency_weight * roundtrip_ms
+            + self.reject_rate_weight * candidate.metrics.reject_rate * dec!(100)
+    }
+}
+
+/// Select venue for taker order. Venues with price worse than best price more than
+/// `similar_price_tolerance` are ignored, the rest are compared by `scorer`
+pub fn select_venue<'a>(
+    candidates: &'a [VenueCandidate],
+    side: OrderSide,
+    similar_price_tolerance: Percent,
+    scorer: &dyn VenueScorer,
+) -> Option<&'a VenueCandidate> {
+    let best_price = match side {
+        OrderSide::Buy => candidates.iter().map(|x| x.price).min()?,
+        OrderSide::Sell => candidates.iter().map(|x| x.price).max()?,
+    };
+
+    let tolerance = best_price * similar_price_tolerance.percent_to_rate();
+    let is_similar_price = |candidate: &VenueCandidate| match side {
+        OrderSide::Buy => candidate.price <= best_price + tolerance,
+        OrderSide::Sell => candidate.price >= best_price - tolerance,
+    };
+
+    candidates
+        .iter()
+        .filter(|x| is_similar_price(x))
+        .min_by_key(|x| scorer.score(x, best_price, side))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(
+        exchange_account_id: &str,
+        price: Price,
+        roundtrip_ms: Option<Decimal>,
+        reject_rate: Decimal,
+    ) -> VenueCandidate {
+        VenueCandidate {
+            exchange_account_id: exchange_account_id.parse().expect("in test"),
+            price,
+            metrics: VenueMetricsSnapshot {
+                roundtrip_ms,
+                reject_rate,
+                requests_count: 10,
+            },
+        }
+    }
+
+    fn selected(candidates: &[VenueCandidate], side: OrderSide) -> ExchangeAccountId {
+        select_venue(candidates, side, dec!(0.1), &WeightedVenueScorer::default())
+            .expect("in test")
+            .exchange_account_id
+    }
+
+    #[test]
+    pub fn no_candidates() {
+        let selected = select_venue(
+            &[],
+            OrderSide::Buy,
+            dec!(0.1),
+            &WeightedVenueScorer::default(),
+        );
+        assert_eq!(selected, None);
+    }
+
+    #[test]
+    pub fn prefer_faster_venue_with_similar_price() {
+        let candidates = [
+            candidate("Binance_0", dec!(1000), Some(dec!(300)), dec!(0)),
+            candidate("Binance_1", dec!(1000.1), Some(dec!(50)), dec!(0)),
+        ];
+
+        assert_eq!(
+            selected(&candidates, OrderSide::Buy),
+            candidates[1].exchange_account_id
+        );
+    }
+
+    #[test]
+    pub fn prefer_venue_with_less_rejects() {
+        let candidates = [
+            candidate("Binance_0", dec!(1000), Some(dec!(50)), dec!(0.5)),
+            candidate("Binance_1", dec!(1000), Some(dec!(60)), dec!(0)),
+        ];
+
+        assert_eq!(
+            selected(&candidates, OrderSide::Sell),
+            candidates[1].exchange_account_id
+        );
+    }
+
+    #[test]
+    pub fn ignore_venue_with_not_similar_price() {
+        let candidates = [
+            candidate("Binance_0", dec!(1000), Some(dec!(300)), dec!(0)),
+            candidate("Binance_1", dec!(1005), Some(dec!(1)), dec!(0)),
+        ];
+
+        assert_eq!(
+            selected(&candidates, OrderSide::Buy),
+            candidates[0].exchange_account_id
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use hyper::Uri;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use tokio::time::{sleep, timeout};
+
+use crate::exchanges::common::ExchangeAccountId;
+use crate::exchanges::connection_pool::ConnectionPool;
+use crate::exchanges::rest_client;
+use crate::infrastructure::spawn_future;
+use crate::settings::{EgressSettings, HostSelectionSettings, RestClientSettings};
+
+/// Probe without response during this time means that host is unavailable
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Latency and errors of host of exchange API
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostStats {
+    pub host: String,
+    /// Latency of the last probe. None if host isn't probed yet or it's unavailable
+    pub latency_ms: Option<u64>,
+    /// Requests sent to host since the last round of probes
+    pub requests_count: u64,
+    pub errors_count: u64,
+    pub is_selected: bool,
+}
+
+#[derive(Debug)]
+struct HostState {
+    host: String,
+    latency: Option<Duration>,
+    requests_count: u64,
+    errors_count: u64,
+}
+
+impl HostState {
+    fn reset_counters(&mut self) {
+        self.requests_count = 0;
+        self.errors_count = 0;
+    }
+}
+
+#[derive(Debug)]
+struct HostSelectorState {
+    hosts: Vec<HostState>,
+    selected: usize,
+}
+
+impl HostSelectorState {
+    fn fastest_available(&self, excluded: Option<usize>) -> Option<usize> {
+        self.hosts
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| Some(*index) != excluded)
+            .filter_map(|(index, host)| host.latency.map(|latency| (index, latency)))
+            .min_by_key(|(_, latency)| *latency)
+            .map(|(index, _)| index)
+    }
+}
+
+/// Selects the fastest host among equivalent hosts of exchange API by latency probes and switches
+/// to another host when selected one has elevated error rate
+pub struct HostSelector {
+    exchange_account_id: ExchangeAccountId,
+    settings: HostSelectionSettings,
+    state: Mutex<HostSelectorState>,
+}
+
+impl HostSelector {
+    /// The first host is selected until the first round of probes
+    pub fn new(
+        exchange_account_id: ExchangeAccountId,
+        hosts: Vec<String>,
+        settings: HostSelectionSettings,
+    ) -> Arc<Self> {
+        assert!(!hosts.is_empty(), "Hosts for selection should be specified");
+
+        let hosts = hosts
+            .into_iter()
+            .map(|host| HostState {
+                host,
+                latency: None,
+                requests_count: 0,
+                errors_count: 0,
+            })
+            .collect();
+
+        Arc::new(HostSelector {
+            exchange_account_id,
+            settings,
+            state: Mutex::new(HostSelectorState { hosts, selected: 0 }),
+        })
+    }
+
+    pub fn selected_host(&self) -> String {
+        let state = self.state.lock();
+        state.hosts[state.selected].host.clone()
+    }
+
+    /// Registers latencies of probes of all hosts (None for unavailable host) and selects
+    /// the fastest available host. Selected host is kept if all hosts are unavailable
+    pub fn register_probes(&self, latencies: &[(String, Option<Duration>)]) {
+        let mut state = self.state.lock();
+        for host_state in &mut state.hosts {
+            if let Some((_, latency)) = latencies.iter().find(|(x, _)| *x == host_state.host) {
+                host_state.latency = *latency;
+            }
+            host_state.reset_counters();
+        }
+
+        if let Some(fastest) = state.fastest_available(None) {
+            if fastest != state.selected {
+                log::info!(
+                    "Host {} is selected on {} by latency {:?}",
+                    state.hosts[fastest].host,
+                    self.exchange_account_id,
+                    state.hosts[fastest].latency
+                );
+                state.selected = fastest;
+            }
+        }
+    }
+
+    /// Registers result of request to host. Selected host is switched to the fastest other
+    /// available host when its error rate exceeds max one
+    pub fn register_request_result(&self, host: &str, is_error: bool) {
+        let mut state = self.state.lock();
+        let index = match state.hosts.iter().position(|x| x.host == host) {
+            Some(index) => index,
+            None => return,
+        };
+
+        let is_selected = index == state.selected;
+        let host_state = &mut state.hosts[index];
+        host_state.requests_count += 1;
+        if is_error {
+            host_state.errors_count += 1;
+        }
+
+        if !is_selected || host_state.requests_count < self.settings.min_requests {
+            return;
+        }
+
+        let error_rate = Decimal::from(host_state.errors_count) * dec!(100)
+            / Decimal::from(host_state.requests_count);
+        if error_rate <= self.settings.max_error_rate {
+            return;
+        }
+
+        // failed host isn't selected again until the next round of probes
+        host_state.latency = None;
+        host_state.reset_counters();
+
+        let next = state
+            .fastest_available(Some(index))
+            .unwrap_or((index + 1) % state.hosts.len());
+        log::warn!(
+            "Host {host} has error rate {error_rate}% on {}, switched to host {}",
+            self.exchange_account_id,
+            state.hosts[next].host
+        );
+        state.selected = next;
+    }
+
+    pub fn stats(&self) -> Vec<HostStats> {
+        let state = self.state.lock();
+        state
+            .hosts
+            .iter()
+            .enumerate()
+            .map(|(index, host)| HostStats {
+                host: host.host.clone(),
+                latency_ms: host.latency.map(|x| x.as_millis() as u64),
+                requests_count: host.requests_count,
+                errors_count: host.errors_count,
+                is_selected: index == state.selected,
+            })
+            .collect()
+    }
+
+    fn hosts(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .hosts
+            .iter()
+            .map(|x| x.host.clone())
+            .collect()
+    }
+}
+
+/// Host of request url in the form used by `HostSelector`, e.g. `https://api.binance.com`
+pub fn host_of_uri(uri: &Uri) -> Option<String> {
+    match (uri.scheme_str(), uri.authority()) {
+        (Some(scheme), Some(authority)) => Some(format!("{scheme}://{authority}")),
+        _ => None,
+    }
+}
+
+/// Probes latency of all hosts of selector by GET requests of `probe_path` at startup and then
+/// periodically with interval from settings
+pub fn start_host_probing(
+    selector: Arc<HostSelector>,
+    probe_path: &'static str,
+    egress: Option<EgressSettings>,
+) {
+    let action_name = format!("Host latency probing for {}", selector.exchange_account_id);
+    let _ = spawn_future(
+        &action_name,
+        SpawnFutureFlags::STOP_BY_TOKEN,
+        probe_hosts(selector, probe_path, egress),
+    );
+}
+
+async fn probe_hosts(
+    selector: Arc<HostSelector>,
+    probe_path: &'static str,
+    egress: Option<EgressSettings>,
+) -> Result<()> {
+    // probes go the same route as requests of exchange account
+    let connection_pool = ConnectionPool::new(&RestClientSettings::default(), egress);
+    let client = connection_pool.client();
+    let probe_interval = Duration::from_millis(selector.settings.probe_interval_ms.max(1));
+
+    loop {
+        let mut latencies = Vec::new();
+        for host in selector.hosts() {
+            let uri = rest_client::build_uri(&host, probe_path, &vec![]);
+            let started_at = Instant::now();
+            let latency = match timeout(PROBE_TIMEOUT, client.get(uri)).await {
+                Ok(Ok(response)) if response.status().is_success() => Some(started_at.elapsed()),
+                Ok(Ok(response)) => {
+                    log::warn!("Probe of host {host} failed with {}", response.status());
+                    None
+                }
+                Ok(Err(err)) => {
+                    log::warn!("Probe of host {host} failed: {err}");
+                    None
+                }
+                Err(_) => {
+                    log::warn!("Probe of host {host} is timed out");
+                    None
+                }
+            };
+            latencies.push((host, latency));
+        }
+
+        selector.register_probes(&latencies);
+        sleep(probe_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selector() -> Arc<HostSelector> {
+        HostSelector::new(
+            ExchangeAccountId::new("Binance", 0),
+            vec![
+                "https://a".to_owned(),
+                "https://b".to_owned(),
+                "https://c".to_owned(),
+            ],
+            HostSelectionSettings {
+                probe_interval_ms: 60_000,
+                max_error_rate: dec!(50),
+                min_requests: 4,
+            },
+        )
+    }
+
+    fn ms(millis: u64) -> Option<Duration> {
+        Some(Duration::from_millis(millis))
+    }
+
+    #[test]
+    pub fn fastest_available_host_is_selected_by_probes() {
+        let selector = selector();
+        assert_eq!(selector.selected_host(), "https://a");
+
+        selector.register_probes(&[
+            ("https://a".to_owned(), ms(30)),
+            ("https://b".to_owned(), None),
+            ("https://c".to_owned(), ms(10)),
+        ]);
+        assert_eq!(selector.selected_host(), "https://c");
+
+        // all hosts are unavailable
+        selector.register_probes(&[
+            ("https://a".to_owned(), None),
+            ("https://b".to_owned(), None),
+            ("https://c".to_owned(), None),
+        ]);
+        assert_eq!(selector.selected_host(), "https://c");
+    }
+
+    #[test]
+    pub fn failover_on_elevated_error_rate() {
+        let selector = selector();
+        selector.register_probes(&[
+            ("https://a".to_owned(), ms(10)),
+            ("https://b".to_owned(), ms(30)),
+            ("https://c".to_owned(), ms(20)),
+        ]);
+
+        for is_error in [true, true, false] {
+            selector.register_request_result("https://a", is_error);
+        }
+        // error rate isn't considered before min requests count
+        assert_eq!(selector.selected_host(), "https://a");
+
+        selector.register_request_result("https://a", true);
+        assert_eq!(selector.selected_host(), "https://c");
+
+        let stats = selector.stats();
+        assert_eq!(stats[0].latency_ms, None);
+        assert!(stats[2].is_selected);
+    }
+}
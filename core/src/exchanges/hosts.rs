@@ -1,7 +1,22 @@
+use crate::settings::HostsSettings;
+
 #[derive(Clone)]
 pub struct Hosts {
-    pub web_socket_host: &'static str,
+    pub web_socket_host: String,
     // Some exchanges have two websockets, for public and private data
-    pub web_socket2_host: &'static str,
-    pub rest_host: &'static str,
+    pub web_socket2_host: String,
+    pub rest_host: String,
+}
+
+impl From<&HostsSettings> for Hosts {
+    fn from(settings: &HostsSettings) -> Self {
+        Hosts {
+            web_socket_host: settings.web_socket_host.clone(),
+            web_socket2_host: settings
+                .web_socket2_host
+                .clone()
+                .unwrap_or_else(|| settings.web_socket_host.clone()),
+            rest_host: settings.rest_host.clone(),
+        }
+    }
 }
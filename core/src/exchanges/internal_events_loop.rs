@@ -32,9 +32,9 @@ impl InternalEventsLoop {
         self: Arc<Self>,
         mut events_receiver: broadcast::Receiver<ExchangeEvent>,
         exchanges_map: HashMap<ExchangeAccountId, Arc<Exchange>>,
+        local_snapshots_service: Arc<Mutex<LocalSnapshotsService>>,
         cancellation_token: CancellationToken,
     ) -> Result<()> {
-        let mut local_snapshots_service = LocalSnapshotsService::default();
         let (work_finished_sender, receiver) = oneshot::channel();
         *self.work_finished_receiver.lock() = Some(receiver);
 
@@ -51,7 +51,7 @@ impl InternalEventsLoop {
                 ExchangeEvent::OrderBookEvent(order_book_event) => {
                     update_order_book_top_for_exchange(
                         order_book_event,
-                        &mut local_snapshots_service,
+                        &mut local_snapshots_service.lock(),
                         &exchanges_map,
                     )
                 }
@@ -105,6 +105,10 @@ impl InternalEventsLoop {
                 }
                 ExchangeEvent::LiquidationPrice(_) => {}
                 ExchangeEvent::Trades(_) => {}
+                ExchangeEvent::MarketDataStatus(_) => {}
+                ExchangeEvent::MarketPaused(_) => {}
+                ExchangeEvent::AccountAvailability(_) => {}
+                ExchangeEvent::QueuePosition(_) => {}
             }
         }
     }
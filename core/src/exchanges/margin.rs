@@ -0,0 +1,352 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::exchanges::common::{Amount, ExchangeAccountId};
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::math::ConvertPercentToRate;
+use crate::misc::time::time_manager;
+use crate::orders::reduce_only::ReduceOnlyReason;
+use crate::settings::MarginRiskSettings;
+
+/// Account margin state of derivatives account reported by exchange
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MarginInfo {
+    /// Wallet balance with unrealized profit
+    pub margin_balance: Amount,
+    /// Margin required for open positions and active orders
+    pub initial_margin: Amount,
+    /// Margin below which positions are liquidated
+    pub maintenance_margin: Amount,
+}
+
+impl MarginInfo {
+    /// Share of margin balance used as initial margin
+    pub fn margin_usage(&self) -> Decimal {
+        Self::ratio(self.initial_margin, self.margin_balance)
+    }
+
+    /// Share of margin balance used as maintenance margin. Positions are liquidated when it reaches 1
+    pub fn maintenance_margin_ratio(&self) -> Decimal {
+        Self::ratio(self.maintenance_margin, self.margin_balance)
+    }
+
+    fn ratio(margin: Amount, margin_balance: Amount) -> Decimal {
+        match margin_balance > dec!(0) {
+            true => margin / margin_balance,
+            false if margin > dec!(0) => Decimal::MAX,
+            false => dec!(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
+pub enum LiquidationRisk {
+    Low,
+    Elevated,
+    High,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum MarginRiskError {
+    #[error("margin state isn't received from exchange yet")]
+    MarginInfoUnavailable,
+    #[error("margin state was received {age_ms}ms ago, max age is {max_age_ms}ms")]
+    MarginInfoStale { age_ms: i64, max_age_ms: u64 },
+    #[error("margin usage {margin_usage} would exceed max margin usage {max_margin_usage}")]
+    MaxMarginUsageExceeded {
+        margin_usage: Decimal,
+        max_margin_usage: Decimal,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+enum MarginState {
+    #[default]
+    NotReceivedYet,
+    /// Exchange doesn't provide margin state, so orders aren't checked
+    Unsupported,
+    Received {
+        margin_info: MarginInfo,
+        receive_time: DateTime,
+    },
+}
+
+/// Risk limits of derivatives account by margin state which is polled from exchange
+#[derive(Debug, Default, Clone)]
+pub struct MarginRisk {
+    settings: Option<MarginRiskSettings>,
+    state: MarginState,
+}
+
+impl MarginRisk {
+    pub fn new(settings: &MarginRiskSettings) -> Self {
+        MarginRisk {
+            settings: Some(settings.clone()),
+            state: MarginState::NotReceivedYet,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.is_some()
+    }
+
+    pub fn margin_info(&self) -> Option<MarginInfo> {
+        match self.state {
+            MarginState::Received { margin_info, .. } => Some(margin_info),
+            _ => None,
+        }
+    }
+
+    /// Saves received margin state and returns liquidation risk level
+    pub fn update(&mut self, margin_info: MarginInfo) -> LiquidationRisk {
+        self.state = MarginState::Received {
+            margin_info,
+            receive_time: time_manager::now(),
+        };
+        self.liquidation_risk()
+    }
+
+    /// Exchange doesn't provide margin state, so margin usage of orders can't be checked
+    pub fn set_unsupported(&mut self) {
+        self.state = MarginState::Unsupported;
+    }
+
+    pub fn liquidation_risk(&self) -> LiquidationRisk {
+        let (settings, margin_info) = match (&self.settings, self.margin_info()) {
+            (Some(settings), Some(margin_info)) => (settings, margin_info),
+            _ => return LiquidationRisk::Low,
+        };
+
+        let ratio = margin_info.maintenance_margin_ratio();
+        if ratio >= settings.high_risk_maintenance_ratio.percent_to_rate() {
+            LiquidationRisk::High
+        } else if ratio >= settings.elevated_risk_maintenance_ratio.percent_to_rate() {
+            LiquidationRisk::Elevated
+        } else {
+            LiquidationRisk::Low
+        }
+    }
+
+    /// Checks that margin usage doesn't exceed max margin usage after order requiring
+    /// `order_margin` is created. Orders aren't checked if margin risk isn't configured or
+    /// exchange doesn't provide margin state. Orders are rejected until the first margin state
+    /// is received and while the last one is older than max age
+    pub fn check(&self, order_margin: Amount) -> Result<(), MarginRiskError> {
+        let settings = match &self.settings {
+            Some(settings) => settings,
+            None => return Ok(()),
+        };
+        let margin_info = match self.state {
+            MarginState::NotReceivedYet => return Err(MarginRiskError::MarginInfoUnavailable),
+            MarginState::Unsupported => return Ok(()),
+            MarginState::Received {
+                margin_info,
+                receive_time,
+            } => {
+                let age_ms = (time_manager::now() - receive_time).num_milliseconds();
+                if age_ms > settings.max_margin_info_age_ms as i64 {
+                    return Err(MarginRiskError::MarginInfoStale {
+                        age_ms,
+                        max_age_ms: settings.max_margin_info_age_ms,
+                    });
+                }
+                margin_info
+            }
+        };
+
+        let margin_usage = MarginInfo {
+            initial_margin: margin_info.initial_margin + order_margin,
+            ..margin_info
+        }
+        .margin_usage();
+        let max_margin_usage = settings.max_margin_usage.percent_to_rate();
+        if margin_usage > max_margin_usage {
+            return Err(MarginRiskError::MaxMarginUsageExceeded {
+                margin_usage,
+                max_margin_usage,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Polls margin state of margin trading exchanges and alerts when liquidation risk rises
+pub(crate) fn start_margin_monitoring(
+    settings: &MarginRiskSettings,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    cancellation_token: CancellationToken,
+) {
+    for exchange in exchanges.iter().filter(|x| x.is_margin_risk_enabled()) {
+        let _ = spawn_future(
+            "Margin state polling",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            poll_margin_info(
+                exchange.clone(),
+                settings.clone(),
+                cancellation_token.clone(),
+            ),
+        );
+    }
+}
+
+async fn poll_margin_info(
+    exchange: Arc<Exchange>,
+    settings: MarginRiskSettings,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    let exchange_account_id = exchange.exchange_account_id;
+    let reduce_only_reason = ReduceOnlyReason::MarginRisk(exchange_account_id);
+    let mut poll_interval =
+        tokio::time::interval(Duration::from_millis(settings.poll_interval_ms.max(1)));
+    let mut last_risk = LiquidationRisk::Low;
+
+    loop {
+        tokio::select! {
+            _ = poll_interval.tick() => {}
+            _ = cancellation_token.when_cancelled() => return Ok(()),
+        }
+
+        let margin_info = match exchange.exchange_client.get_margin_info().await {
+            Ok(Some(margin_info)) => margin_info,
+            Ok(None) => {
+                log::warn!("Exchange {exchange_account_id} doesn't provide margin state, margin usage of orders isn't checked");
+                exchange.set_margin_info_unsupported();
+                return Ok(());
+            }
+            Err(error) => {
+                log::error!("Failed to get margin state of {exchange_account_id}: {error:?}");
+                continue;
+            }
+        };
+
+        let risk = exchange.update_margin_info(margin_info);
+        if risk > last_risk {
+            let ratio = margin_info.maintenance_margin_ratio();
+            match risk {
+                LiquidationRisk::High => log::error!(
+                    "Liquidation risk of {exchange_account_id} is high: maintenance margin ratio is {ratio}, {margin_info:?}"
+                ),
+                _ => log::warn!(
+                    "Liquidation risk of {exchange_account_id} is rising: maintenance margin ratio is {ratio}, {margin_info:?}"
+                ),
+            }
+        } else if risk < last_risk {
+            log::info!("Liquidation risk of {exchange_account_id} decreased to {risk:?}");
+        }
+        last_risk = risk;
+
+        if settings.reduce_only_on_high_risk {
+            match risk {
+                LiquidationRisk::High => exchange.reduce_only_mode().enable(reduce_only_reason),
+                _ => exchange.reduce_only_mode().disable(reduce_only_reason),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use mmb_utils::time::set_mock_now;
+    use rstest::rstest;
+
+    fn margin_risk() -> MarginRisk {
+        MarginRisk::new(&MarginRiskSettings {
+            max_margin_usage: dec!(50),
+            elevated_risk_maintenance_ratio: dec!(50),
+            high_risk_maintenance_ratio: dec!(80),
+            poll_interval_ms: 1_000,
+            max_margin_info_age_ms: 10_000,
+            reduce_only_on_high_risk: false,
+        })
+    }
+
+    fn margin_info(initial_margin: Amount, maintenance_margin: Amount) -> MarginInfo {
+        MarginInfo {
+            margin_balance: dec!(1000),
+            initial_margin,
+            maintenance_margin,
+        }
+    }
+
+    #[rstest]
+    #[case(dec!(100), dec!(400), true)]
+    #[case(dec!(400), dec!(100), true)]
+    #[case(dec!(400), dec!(101), false)]
+    #[case(dec!(600), dec!(0), false)]
+    pub fn order_margin_is_limited(
+        #[case] initial_margin: Amount,
+        #[case] order_margin: Amount,
+        #[case] is_allowed: bool,
+    ) {
+        let mut margin_risk = margin_risk();
+        let _ = margin_risk.update(margin_info(initial_margin, dec!(0)));
+
+        assert_eq!(margin_risk.check(order_margin).is_ok(), is_allowed);
+    }
+
+    #[test]
+    pub fn orders_are_blocked_without_margin_info() {
+        assert_eq!(
+            margin_risk().check(dec!(1)),
+            Err(MarginRiskError::MarginInfoUnavailable)
+        );
+        assert_eq!(MarginRisk::default().check(dec!(1)), Ok(()));
+    }
+
+    #[test]
+    pub fn orders_are_not_checked_if_margin_info_is_unsupported() {
+        let mut margin_risk = margin_risk();
+        margin_risk.set_unsupported();
+
+        assert_eq!(margin_risk.check(dec!(1)), Ok(()));
+    }
+
+    #[test]
+    pub fn orders_are_blocked_by_stale_margin_info() {
+        let time = Utc.ymd(2021, 9, 20).and_hms(0, 0, 0);
+        let _guard = set_mock_now(time);
+        let mut margin_risk = margin_risk();
+        margin_risk.state = MarginState::Received {
+            margin_info: margin_info(dec!(0), dec!(0)),
+            receive_time: time - chrono::Duration::seconds(11),
+        };
+
+        assert!(matches!(
+            margin_risk.check(dec!(1)),
+            Err(MarginRiskError::MarginInfoStale { .. })
+        ));
+
+        let _ = margin_risk.update(margin_info(dec!(0), dec!(0)));
+        assert_eq!(margin_risk.check(dec!(1)), Ok(()));
+    }
+
+    #[rstest]
+    #[case(dec!(100), LiquidationRisk::Low)]
+    #[case(dec!(500), LiquidationRisk::Elevated)]
+    #[case(dec!(900), LiquidationRisk::High)]
+    pub fn liquidation_risk_by_maintenance_margin(
+        #[case] maintenance_margin: Amount,
+        #[case] expected: LiquidationRisk,
+    ) {
+        let mut margin_risk = margin_risk();
+
+        assert_eq!(
+            margin_risk.update(margin_info(dec!(0), maintenance_margin)),
+            expected
+        );
+    }
+}
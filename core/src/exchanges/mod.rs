@@ -1,10 +1,21 @@
+pub mod account_groups;
+pub mod api_key_permissions;
 pub mod block_reasons;
 pub mod common;
+pub mod connection_pool;
+#[cfg(unix)]
+pub mod connector_process;
 pub mod events;
 pub mod exchange_blocker;
+pub mod fault_injection;
 pub mod general;
+pub mod host_selection;
 pub mod hosts;
 pub(crate) mod internal_events_loop;
+pub mod margin;
+pub mod payload_anomalies;
 pub mod rest_client;
+pub mod rest_pagination;
+pub(crate) mod stale_market_data;
 pub mod timeouts;
 pub mod traits;
@@ -0,0 +1,87 @@
+use dashmap::DashMap;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::exchanges::common::ExchangeAccountId;
+
+/// Unexpected value in exchange payload which is tolerated by connector instead of failing parsing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PayloadAnomaly {
+    pub kind: String,
+    pub value: String,
+    pub count: u64,
+}
+
+/// Counts of anomalies tolerated by connector, e.g. unknown enum values or missing fields, so
+/// exchanges extending their API are noticed without breaking parsing in production
+#[derive(Debug)]
+pub struct PayloadAnomalies {
+    exchange_account_id: ExchangeAccountId,
+    counts: DashMap<(String, String), u64>,
+}
+
+impl PayloadAnomalies {
+    pub fn new(exchange_account_id: ExchangeAccountId) -> Self {
+        PayloadAnomalies {
+            exchange_account_id,
+            counts: Default::default(),
+        }
+    }
+
+    /// Anomaly is logged only the first time, so log isn't flooded by every message with it
+    pub fn register(&self, kind: &str, value: &str) {
+        let mut count = self
+            .counts
+            .entry((kind.to_owned(), value.to_owned()))
+            .or_default();
+        *count += 1;
+
+        if *count == 1 {
+            log::warn!(
+                "Unexpected {kind} '{value}' in payload from {} is tolerated",
+                self.exchange_account_id
+            );
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<PayloadAnomaly> {
+        self.counts
+            .iter()
+            .map(|x| PayloadAnomaly {
+                kind: x.key().0.clone(),
+                value: x.key().1.clone(),
+                count: *x.value(),
+            })
+            .sorted_by(|a, b| (&a.kind, &a.value).cmp(&(&b.kind, &b.value)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anomalies_are_counted_by_kind_and_value() {
+        let anomalies = PayloadAnomalies::new(ExchangeAccountId::new("Binance", 0));
+        anomalies.register("order status", "NEW_STATUS");
+        anomalies.register("order status", "NEW_STATUS");
+        anomalies.register("execution type", "NEW_STATUS");
+
+        assert_eq!(
+            anomalies.snapshot(),
+            vec![
+                PayloadAnomaly {
+                    kind: "execution type".to_owned(),
+                    value: "NEW_STATUS".to_owned(),
+                    count: 1,
+                },
+                PayloadAnomaly {
+                    kind: "order status".to_owned(),
+                    value: "NEW_STATUS".to_owned(),
+                    count: 2,
+                },
+            ]
+        );
+    }
+}
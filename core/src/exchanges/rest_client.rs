@@ -1,4 +1,7 @@
 use super::common::*;
+use super::connection_pool::{ConnectionPool, ConnectionPoolStats};
+use super::host_selection::{self, HostSelector};
+use crate::settings::{EgressSettings, RestClientSettings};
 use anyhow::Result;
 use hyper::client::HttpConnector;
 use hyper::{Body, Client, Error, Request, Response, StatusCode, Uri};
@@ -7,6 +10,7 @@ use log::log;
 use mmb_utils::infrastructure::WithExpect;
 use std::convert::TryInto;
 use std::fmt::Write;
+use std::sync::Arc;
 use uuid::Uuid;
 
 pub type HttpParams = Vec<(String, String)>;
@@ -141,8 +145,9 @@ fn check_content(content: &str) -> CheckContent {
 }
 
 pub struct RestClient<ErrHandler: ErrorHandler + Send + Sync + 'static> {
-    client: Client<HttpsConnector<HttpConnector>>,
+    connection_pool: ConnectionPool,
     error_handler: ErrorHandlerData<ErrHandler>,
+    host_selector: Option<Arc<HostSelector>>,
 }
 
 const KEEP_ALIVE: &str = "keep-alive";
@@ -151,12 +156,27 @@ type ResponseType = Result<Response<Body>, Error>;
 
 impl<ErrHandler: ErrorHandler + Send + Sync + 'static> RestClient<ErrHandler> {
     pub fn new(error_handler: ErrorHandlerData<ErrHandler>) -> Self {
+        Self::new_with_settings(error_handler, &RestClientSettings::default(), None)
+    }
+
+    pub fn new_with_settings(
+        error_handler: ErrorHandlerData<ErrHandler>,
+        settings: &RestClientSettings,
+        egress: Option<EgressSettings>,
+    ) -> Self {
         Self {
-            client: create_client(),
+            connection_pool: ConnectionPool::new(settings, egress),
             error_handler,
+            host_selector: None,
         }
     }
 
+    /// Results of requests are reported to selector for failover of hosts with elevated error rate
+    pub fn with_host_selector(mut self, host_selector: Option<Arc<HostSelector>>) -> Self {
+        self.host_selector = host_selector;
+        self
+    }
+
     pub async fn get(
         &self,
         url: Uri,
@@ -167,7 +187,8 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static> RestClient<ErrHandler> {
         let request_id = Uuid::new_v4();
         self.error_handler.request_log(action_name, &request_id);
 
-        let req = Request::get(url)
+        let _active_request = self.connection_pool.start_request().await;
+        let req = Request::get(url.clone())
             .header(hyper::header::CONNECTION, KEEP_ALIVE)
             .header("X-MBX-APIKEY", api_key)
             .body(Body::empty())
@@ -175,7 +196,8 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static> RestClient<ErrHandler> {
                 format!("Error during creation of http GET request, request_id: {request_id}")
             });
 
-        let response = self.client.request(req).await;
+        let response = self.connection_pool.client().request(req).await;
+        self.register_request_result(&url, &response);
 
         self.handle_response(response, "GET", action_name, log_args, request_id)
             .await
@@ -196,7 +218,8 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static> RestClient<ErrHandler> {
             .extend_pairs(http_params)
             .finish();
 
-        let req = Request::post(url)
+        let _active_request = self.connection_pool.start_request().await;
+        let req = Request::post(url.clone())
             .header(hyper::header::CONNECTION, KEEP_ALIVE)
             .header("X-MBX-APIKEY", api_key)
             .body(Body::from(form_encoded))
@@ -204,7 +227,8 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static> RestClient<ErrHandler> {
                 format!("Error during creation of http POST request, request_id: {request_id}")
             });
 
-        let response = self.client.request(req).await;
+        let response = self.connection_pool.client().request(req).await;
+        self.register_request_result(&url, &response);
 
         self.handle_response(response, "POST", action_name, log_args, request_id)
             .await
@@ -220,7 +244,8 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static> RestClient<ErrHandler> {
         let request_id = Uuid::new_v4();
         self.error_handler.request_log(action_name, &request_id);
 
-        let req = Request::delete(url)
+        let _active_request = self.connection_pool.start_request().await;
+        let req = Request::delete(url.clone())
             .header(hyper::header::CONNECTION, KEEP_ALIVE)
             .header("X-MBX-APIKEY", api_key)
             .body(Body::empty())
@@ -228,12 +253,58 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static> RestClient<ErrHandler> {
                 format!("Error during creation of http DELETE request, request_id: {request_id}",)
             });
 
-        let response = self.client.request(req).await;
+        let response = self.connection_pool.client().request(req).await;
+        self.register_request_result(&url, &response);
 
         self.handle_response(response, "DELETE", action_name, log_args, request_id)
             .await
     }
 
+    pub async fn put(
+        &self,
+        url: Uri,
+        api_key: &str,
+        action_name: &'static str,
+        log_args: String,
+    ) -> Result<RestRequestOutcome, ExchangeError> {
+        let request_id = Uuid::new_v4();
+        self.error_handler.request_log(action_name, &request_id);
+
+        let _active_request = self.connection_pool.start_request().await;
+        let req = Request::put(url.clone())
+            .header(hyper::header::CONNECTION, KEEP_ALIVE)
+            .header("X-MBX-APIKEY", api_key)
+            .body(Body::empty())
+            .with_expect(|| {
+                format!("Error during creation of http PUT request, request_id: {request_id}")
+            });
+
+        let response = self.connection_pool.client().request(req).await;
+        self.register_request_result(&url, &response);
+
+        self.handle_response(response, "PUT", action_name, log_args, request_id)
+            .await
+    }
+
+    pub fn connection_pool_stats(&self) -> ConnectionPoolStats {
+        self.connection_pool.stats()
+    }
+
+    fn register_request_result(&self, url: &Uri, response: &ResponseType) {
+        let host_selector = match &self.host_selector {
+            Some(host_selector) => host_selector,
+            None => return,
+        };
+
+        if let Some(host) = host_selection::host_of_uri(url) {
+            let is_error = match response {
+                Ok(response) => response.status().is_server_error(),
+                Err(_) => true,
+            };
+            host_selector.register_request_result(&host, is_error);
+        }
+    }
+
     async fn handle_response(
         &self,
         response: ResponseType,
@@ -266,7 +337,7 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static> RestClient<ErrHandler> {
     }
 }
 
-fn create_client() -> Client<HttpsConnector<HttpConnector>> {
+pub(crate) fn create_client() -> Client<HttpsConnector<HttpConnector>> {
     let https = HttpsConnectorBuilder::new()
         .with_native_roots()
         .https_only()
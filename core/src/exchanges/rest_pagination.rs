@@ -0,0 +1,380 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::hash::Hash;
+use std::time::Duration;
+
+use mmb_utils::DateTime;
+use tokio::time::sleep;
+
+use crate::exchanges::common::{ExchangeError, ExchangeErrorType};
+use crate::exchanges::events::TradeId;
+use crate::exchanges::general::order::get_order_trades::OrderTrade;
+use crate::misc::time::time_manager;
+use crate::orders::order::{ExchangeOrderId, OrderInfo};
+
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// How pages of endpoint follow each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaginationKind {
+    /// Next page is requested by cursor returned with previous page
+    Cursor,
+    /// Next page starts after max id of items of previous page (e.g. `fromId` parameter)
+    AfterId,
+    /// Pages are consecutive time windows of specified length up to now. Full page is continued
+    /// from time of its latest item within the same window
+    TimeWindows { window: chrono::Duration },
+}
+
+/// Request of page which connector converts to parameters of endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageRequest {
+    /// First page of items since `from_time` if it's specified
+    First {
+        from_time: Option<DateTime>,
+    },
+    Cursor(String),
+    /// Items with ids greater than specified one
+    AfterId(u64),
+    /// Items with time in range [from, to)
+    TimeWindow {
+        from: DateTime,
+        to: DateTime,
+    },
+}
+
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor of next page for `PaginationKind::Cursor`
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>) -> Self {
+        Page {
+            items,
+            next_cursor: None,
+        }
+    }
+}
+
+/// Item of paginated endpoint. Items with the same key received in several pages are
+/// de-duplicated
+pub trait PaginatedItem {
+    type Key: Eq + Hash;
+
+    fn key(&self) -> Self::Key;
+
+    /// Numeric id for `PaginationKind::AfterId`
+    fn id(&self) -> Option<u64> {
+        None
+    }
+
+    /// Time for `PaginationKind::TimeWindows`
+    fn time(&self) -> Option<DateTime> {
+        None
+    }
+}
+
+impl PaginatedItem for OrderTrade {
+    type Key = (ExchangeOrderId, String);
+
+    fn key(&self) -> Self::Key {
+        (self.exchange_order_id.clone(), self.trade_id.to_string())
+    }
+
+    fn id(&self) -> Option<u64> {
+        match self.trade_id {
+            TradeId::Number(id) => Some(id),
+            TradeId::String(_) => None,
+        }
+    }
+
+    fn time(&self) -> Option<DateTime> {
+        Some(self.datetime)
+    }
+}
+
+impl PaginatedItem for OrderInfo {
+    type Key = ExchangeOrderId;
+
+    fn key(&self) -> Self::Key {
+        self.exchange_order_id.clone()
+    }
+
+    fn id(&self) -> Option<u64> {
+        self.exchange_order_id.as_str().parse().ok()
+    }
+}
+
+/// Declarative pagination of REST endpoint. Pages are fetched sequentially with pause between
+/// them and retried with backoff on rate limit errors
+#[derive(Debug, Clone)]
+pub struct Paginator {
+    pub kind: PaginationKind,
+    /// Max count of items in page. Shorter page is the last one
+    pub page_limit: usize,
+    /// Protection from endless pagination
+    pub max_pages: usize,
+    pub delay_between_pages: Duration,
+}
+
+impl Paginator {
+    /// Fetches all pages of items since `from_time`. It's required for `PaginationKind::TimeWindows`
+    pub async fn fetch_all<T, F, Fut>(
+        &self,
+        from_time: Option<DateTime>,
+        mut fetch_page: F,
+    ) -> Result<Vec<T>, ExchangeError>
+    where
+        T: PaginatedItem,
+        F: FnMut(PageRequest) -> Fut,
+        Fut: Future<Output = Result<Page<T>, ExchangeError>>,
+    {
+        let mut request = match self.kind {
+            PaginationKind::TimeWindows { window } => {
+                let from = from_time.ok_or_else(|| {
+                    ExchangeError::unknown("Start time is required for time windows pagination")
+                })?;
+                PageRequest::TimeWindow {
+                    from,
+                    to: from + window,
+                }
+            }
+            _ => PageRequest::First { from_time },
+        };
+
+        let mut keys = HashSet::new();
+        let mut items = Vec::new();
+        for page_number in 0..self.max_pages {
+            if page_number > 0 {
+                sleep(self.delay_between_pages).await;
+            }
+
+            let page = fetch_page_with_retries(&mut fetch_page, &request).await?;
+            let next_request = self.next_request(&request, &page, time_manager::now());
+
+            let items_count = items.len();
+            for item in page.items {
+                if keys.insert(item.key()) {
+                    items.push(item);
+                }
+            }
+
+            let has_new_items = items.len() > items_count;
+            let is_time_windows = matches!(self.kind, PaginationKind::TimeWindows { .. });
+            match next_request {
+                // page without new items means that endpoint ignores pagination parameters
+                Some(_) if !has_new_items && !is_time_windows => return Ok(items),
+                Some(next_request) => request = next_request,
+                None => return Ok(items),
+            }
+        }
+
+        log::warn!(
+            "Pagination is stopped after {} pages with {} items",
+            self.max_pages,
+            items.len()
+        );
+        Ok(items)
+    }
+
+    fn next_request<T: PaginatedItem>(
+        &self,
+        request: &PageRequest,
+        page: &Page<T>,
+        now: DateTime,
+    ) -> Option<PageRequest> {
+        let is_full_page = page.items.len() >= self.page_limit;
+        match &self.kind {
+            PaginationKind::Cursor => page.next_cursor.clone().map(PageRequest::Cursor),
+            PaginationKind::AfterId => match is_full_page {
+                true => page
+                    .items
+                    .iter()
+                    .filter_map(|x| x.id())
+                    .max()
+                    .map(PageRequest::AfterId),
+                false => None,
+            },
+            PaginationKind::TimeWindows { window } => {
+                let (from, to) = match *request {
+                    PageRequest::TimeWindow { from, to } => (from, to),
+                    _ => return None,
+                };
+
+                let last_time = page.items.iter().filter_map(|x| x.time()).max();
+                match last_time {
+                    Some(last_time) if is_full_page && last_time > from => {
+                        Some(PageRequest::TimeWindow {
+                            from: last_time,
+                            to,
+                        })
+                    }
+                    _ if to >= now => None,
+                    _ => Some(PageRequest::TimeWindow {
+                        from: to,
+                        to: to + *window,
+                    }),
+                }
+            }
+        }
+    }
+}
+
+async fn fetch_page_with_retries<T, F, Fut>(
+    fetch_page: &mut F,
+    request: &PageRequest,
+) -> Result<Page<T>, ExchangeError>
+where
+    F: FnMut(PageRequest) -> Fut,
+    Fut: Future<Output = Result<Page<T>, ExchangeError>>,
+{
+    let mut retry = 0;
+    loop {
+        match fetch_page(request.clone()).await {
+            Err(error)
+                if error.error_type == ExchangeErrorType::RateLimit
+                    && retry < MAX_RATE_LIMIT_RETRIES =>
+            {
+                retry += 1;
+                log::warn!("Rate limit is reached while fetching page {request:?}, retry {retry}");
+                sleep(RATE_LIMIT_BACKOFF * retry).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Item {
+        id: u64,
+        time: DateTime,
+    }
+
+    impl PaginatedItem for Item {
+        type Key = u64;
+
+        fn key(&self) -> Self::Key {
+            self.id
+        }
+
+        fn id(&self) -> Option<u64> {
+            Some(self.id)
+        }
+
+        fn time(&self) -> Option<DateTime> {
+            Some(self.time)
+        }
+    }
+
+    fn item(id: u64) -> Item {
+        Item {
+            id,
+            time: Utc.timestamp_millis(id as i64 * 1000),
+        }
+    }
+
+    fn paginator(kind: PaginationKind) -> Paginator {
+        Paginator {
+            kind,
+            page_limit: 3,
+            max_pages: 10,
+            delay_between_pages: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    pub async fn fetch_pages_after_id_with_deduplication() {
+        let mut requests = Vec::new();
+        let items = paginator(PaginationKind::AfterId)
+            .fetch_all(None, |request| {
+                requests.push(request.clone());
+                let ids = match request {
+                    PageRequest::First { .. } => vec![1, 2, 3],
+                    // endpoint returns the last item of previous page again
+                    PageRequest::AfterId(3) => vec![3, 4, 5],
+                    PageRequest::AfterId(5) => vec![6],
+                    _ => vec![],
+                };
+                async move { Ok(Page::new(ids.into_iter().map(item).collect())) }
+            })
+            .await
+            .expect("in test");
+
+        assert_eq!(items, (1..=6).map(item).collect::<Vec<_>>());
+        assert_eq!(
+            requests,
+            vec![
+                PageRequest::First { from_time: None },
+                PageRequest::AfterId(3),
+                PageRequest::AfterId(5),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    pub async fn fetch_pages_by_cursor_and_retry_on_rate_limit() {
+        let mut is_rate_limited = true;
+        let items = paginator(PaginationKind::Cursor)
+            .fetch_all(None, |request| {
+                let result = match request {
+                    PageRequest::First { .. } => Ok(Page {
+                        items: vec![item(1), item(2)],
+                        next_cursor: Some("next".to_owned()),
+                    }),
+                    PageRequest::Cursor(_) if is_rate_limited => {
+                        is_rate_limited = false;
+                        Err(ExchangeError::new(
+                            ExchangeErrorType::RateLimit,
+                            "Too many requests".to_owned(),
+                            None,
+                        ))
+                    }
+                    _ => Ok(Page::new(vec![item(3)])),
+                };
+                async move { result }
+            })
+            .await
+            .expect("in test");
+
+        assert_eq!(items, (1..=3).map(item).collect::<Vec<_>>());
+    }
+
+    #[test]
+    pub fn time_windows_continue_full_page_and_stop_at_now() {
+        let paginator = paginator(PaginationKind::TimeWindows {
+            window: chrono::Duration::seconds(10),
+        });
+        let time = |secs: i64| Utc.timestamp_millis(secs * 1000);
+        let window = PageRequest::TimeWindow {
+            from: time(0),
+            to: time(10),
+        };
+
+        let full_page = Page::new(vec![item(1), item(2), item(3)]);
+        assert_eq!(
+            paginator.next_request(&window, &full_page, time(100)),
+            Some(PageRequest::TimeWindow {
+                from: time(3),
+                to: time(10),
+            })
+        );
+
+        let last_page = Page::new(vec![item(4)]);
+        assert_eq!(
+            paginator.next_request(&window, &last_page, time(100)),
+            Some(PageRequest::TimeWindow {
+                from: time(10),
+                to: time(20),
+            })
+        );
+        assert_eq!(paginator.next_request(&window, &last_page, time(5)), None);
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::Duration;
+use dashmap::DashMap;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::block_reasons;
+use crate::exchanges::common::{ExchangeAccountId, MarketAccountId};
+use crate::exchanges::events::{ExchangeEvent, MarketDataStatusEvent};
+use crate::exchanges::exchange_blocker::ExchangeBlocker;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager::now;
+use crate::settings::StaleMarketDataSettings;
+
+#[derive(Debug, Clone, Copy)]
+struct MarketState {
+    last_update_time: DateTime,
+    is_stale: bool,
+}
+
+/// Tracks last order book update times of markets and finds markets which order books
+/// stopped updating while connection is healthy
+#[derive(Debug)]
+pub(crate) struct StaleMarketDataTracker {
+    max_update_interval: Duration,
+    markets: HashMap<MarketAccountId, MarketState>,
+}
+
+impl StaleMarketDataTracker {
+    pub fn new(settings: &StaleMarketDataSettings) -> Self {
+        StaleMarketDataTracker {
+            max_update_interval: Duration::milliseconds(settings.max_update_interval_ms as i64),
+            markets: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if market was stale before update
+    pub fn order_book_updated(
+        &mut self,
+        market_account_id: MarketAccountId,
+        time: DateTime,
+    ) -> bool {
+        let previous = self.markets.insert(
+            market_account_id,
+            MarketState {
+                last_update_time: time,
+                is_stale: false,
+            },
+        );
+
+        previous.is_some_and(|x| x.is_stale)
+    }
+
+    /// Marks markets without order book updates during max update interval as stale and returns
+    /// newly stale markets. Markets of disconnected exchanges are skipped because their staleness
+    /// is already reported by exchange blocking
+    pub fn find_new_stale_markets(
+        &mut self,
+        now: DateTime,
+        is_connected: impl Fn(ExchangeAccountId) -> bool,
+    ) -> Vec<(MarketAccountId, DateTime)> {
+        let min_time = now - self.max_update_interval;
+
+        let mut stale_markets = vec![];
+        for (market_account_id, state) in self.markets.iter_mut() {
+            if state.is_stale
+                || state.last_update_time >= min_time
+                || !is_connected(market_account_id.exchange_account_id)
+            {
+                continue;
+            }
+
+            state.is_stale = true;
+            stale_markets.push((*market_account_id, state.last_update_time));
+        }
+
+        stale_markets
+    }
+}
+
+/// Watches order book updates of all markets, marks markets on exchanges as stale when data
+/// stops arriving and notifies strategies by `ExchangeEvent::MarketDataStatus`.
+/// New orders aren't created on stale markets until data resumes
+pub(crate) fn start_stale_market_data_detection(
+    settings: &StaleMarketDataSettings,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    exchange_blocker: Arc<ExchangeBlocker>,
+    events_sender: broadcast::Sender<ExchangeEvent>,
+) {
+    let tracker = StaleMarketDataTracker::new(settings);
+    let check_period =
+        std::time::Duration::from_millis((settings.max_update_interval_ms / 4).max(1));
+
+    let _ = spawn_future(
+        "Stale market data detection",
+        SpawnFutureFlags::STOP_BY_TOKEN,
+        detect_stale_market_data(
+            tracker,
+            check_period,
+            exchanges,
+            exchange_blocker,
+            events_sender,
+        ),
+    );
+}
+
+async fn detect_stale_market_data(
+    mut tracker: StaleMarketDataTracker,
+    check_period: std::time::Duration,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    exchange_blocker: Arc<ExchangeBlocker>,
+    events_sender: broadcast::Sender<ExchangeEvent>,
+) -> Result<()> {
+    let mut events_receiver = events_sender.subscribe();
+    let mut check_interval = tokio::time::interval(check_period);
+
+    let set_status = |market_account_id: MarketAccountId, is_stale: bool, last_update_time| {
+        if let Some(exchange) = exchanges.get(&market_account_id.exchange_account_id) {
+            exchange.set_market_stale(market_account_id.currency_pair, is_stale);
+        }
+
+        let _ = events_sender.send(ExchangeEvent::MarketDataStatus(MarketDataStatusEvent {
+            exchange_account_id: market_account_id.exchange_account_id,
+            currency_pair: market_account_id.currency_pair,
+            is_stale,
+            last_update_time,
+        }));
+    };
+
+    loop {
+        tokio::select! {
+            event = events_receiver.recv() => {
+                let order_book_event = match event {
+                    Ok(ExchangeEvent::OrderBookEvent(order_book_event)) => order_book_event,
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Stale market data detection skipped {skipped} exchange events");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                };
+
+                let market_account_id = MarketAccountId::new(
+                    order_book_event.exchange_account_id,
+                    order_book_event.currency_pair,
+                );
+                let time = now();
+                if tracker.order_book_updated(market_account_id, time) {
+                    log::info!("Market data of {market_account_id:?} is resumed");
+                    set_status(market_account_id, false, time);
+                }
+            }
+            _ = check_interval.tick() => {
+                let is_connected = |exchange_account_id| {
+                    !exchange_blocker.is_blocked_by_reason(
+                        exchange_account_id,
+                        block_reasons::WEBSOCKET_DISCONNECTED,
+                    )
+                };
+                for (market_account_id, last_update_time) in
+                    tracker.find_new_stale_markets(now(), is_connected)
+                {
+                    log::warn!("Market data of {market_account_id:?} is stale since {last_update_time}");
+                    set_status(market_account_id, true, last_update_time);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use chrono::Utc;
+
+    fn market_account_id(exchange_account_id: ExchangeAccountId) -> MarketAccountId {
+        MarketAccountId::new(
+            exchange_account_id,
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    fn tracker() -> StaleMarketDataTracker {
+        StaleMarketDataTracker::new(&StaleMarketDataSettings {
+            max_update_interval_ms: 1_000,
+        })
+    }
+
+    #[test]
+    pub fn market_becomes_stale_and_resumes() {
+        let mut tracker = tracker();
+        let market = market_account_id(ExchangeAccountId::new("Binance", 0));
+        let start = Utc::now();
+
+        assert!(!tracker.order_book_updated(market, start));
+        assert!(tracker
+            .find_new_stale_markets(start + Duration::milliseconds(500), |_| true)
+            .is_empty());
+
+        let stale_markets =
+            tracker.find_new_stale_markets(start + Duration::milliseconds(1500), |_| true);
+        assert_eq!(stale_markets, vec![(market, start)]);
+
+        // already reported market isn't reported again
+        assert!(tracker
+            .find_new_stale_markets(start + Duration::milliseconds(2000), |_| true)
+            .is_empty());
+
+        assert!(tracker.order_book_updated(market, start + Duration::milliseconds(2500)));
+        assert!(!tracker.order_book_updated(market, start + Duration::milliseconds(2600)));
+    }
+
+    #[test]
+    pub fn disconnected_exchange_is_skipped() {
+        let mut tracker = tracker();
+        let connected = ExchangeAccountId::new("Binance", 0);
+        let disconnected = ExchangeAccountId::new("Binance", 1);
+        let start = Utc::now();
+        let _ = tracker.order_book_updated(market_account_id(connected), start);
+        let _ = tracker.order_book_updated(market_account_id(disconnected), start);
+
+        let stale_markets =
+            tracker.find_new_stale_markets(start + Duration::seconds(2), |x| x == connected);
+
+        assert_eq!(stale_markets, vec![(market_account_id(connected), start)]);
+    }
+}
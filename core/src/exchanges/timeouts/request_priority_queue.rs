@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::OPERATION_CANCELED_MSG;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+/// Waiting request is served before requests of higher priority after this time
+pub const DEFAULT_MAX_QUEUE_WAIT: Duration = Duration::from_secs(3);
+
+/// Interval of checking whether request can be reserved by rate limits
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Priority of outgoing order action when rate limits constrain throughput
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum RequestPriority {
+    /// New quotes placement
+    Quote,
+    /// Orders which reduce position
+    RiskReducing,
+    Cancel,
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct QueueWaitMetrics {
+    pub requests_count: u64,
+    pub total_wait_ms: u64,
+    pub max_wait_ms: u64,
+}
+
+impl QueueWaitMetrics {
+    pub fn average_wait_ms(&self) -> Option<u64> {
+        (self.requests_count > 0).then(|| self.total_wait_ms / self.requests_count)
+    }
+
+    fn register(&mut self, wait: Duration) {
+        let wait_ms = wait.as_millis() as u64;
+        self.requests_count += 1;
+        self.total_wait_ms += wait_ms;
+        self.max_wait_ms = self.max_wait_ms.max(wait_ms);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Waiter {
+    id: u64,
+    priority: RequestPriority,
+    enqueue_time: Instant,
+}
+
+#[derive(Default)]
+struct QueueState {
+    waiters: Vec<Waiter>,
+    next_id: u64,
+}
+
+/// Returns waiter which should be served next: requests waiting longer than `max_wait` go first in
+/// order of arrival, otherwise request with the highest priority is selected
+fn next_waiter(waiters: &[Waiter], now: Instant, max_wait: Duration) -> Option<u64> {
+    waiters
+        .iter()
+        .min_by_key(|x| {
+            let is_starving = now.duration_since(x.enqueue_time) >= max_wait;
+            (!is_starving, std::cmp::Reverse(x.priority), x.id)
+        })
+        .map(|x| x.id)
+}
+
+/// Queue of order actions which wait for available requests by rate limits of exchange account.
+/// Cancels and risk reducing orders are served ahead of new quotes
+pub struct RequestPriorityQueue {
+    max_wait: Duration,
+    state: Mutex<QueueState>,
+    metrics: Mutex<HashMap<RequestPriority, QueueWaitMetrics>>,
+}
+
+impl RequestPriorityQueue {
+    pub fn new(max_wait: Duration) -> Self {
+        RequestPriorityQueue {
+            max_wait,
+            state: Default::default(),
+            metrics: Default::default(),
+        }
+    }
+
+    /// Waits for the turn of request and reserves it by `try_reserve`
+    pub async fn reserve(
+        &self,
+        priority: RequestPriority,
+        mut try_reserve: impl FnMut() -> Result<bool>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let enqueue_time = Instant::now();
+        let id = {
+            let mut state = self.state.lock();
+            if state.waiters.is_empty() && try_reserve()? {
+                self.register_wait(priority, Duration::ZERO);
+                return Ok(());
+            }
+
+            let id = state.next_id;
+            state.next_id += 1;
+            state.waiters.push(Waiter {
+                id,
+                priority,
+                enqueue_time,
+            });
+            id
+        };
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                _ = cancellation_token.when_cancelled() => {
+                    self.state.lock().waiters.retain(|x| x.id != id);
+                    bail!(OPERATION_CANCELED_MSG)
+                }
+            }
+
+            let mut state = self.state.lock();
+            if next_waiter(&state.waiters, Instant::now(), self.max_wait) != Some(id) {
+                continue;
+            }
+
+            match try_reserve() {
+                Ok(false) => continue,
+                result => {
+                    state.waiters.retain(|x| x.id != id);
+                    if result? {
+                        self.register_wait(priority, enqueue_time.elapsed());
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if requests with higher priority than `priority` are waiting
+    pub fn has_higher_priority_waiters(&self, priority: RequestPriority) -> bool {
+        self.state
+            .lock()
+            .waiters
+            .iter()
+            .any(|x| x.priority > priority)
+    }
+
+    pub fn wait_metrics(&self) -> HashMap<RequestPriority, QueueWaitMetrics> {
+        self.metrics.lock().clone()
+    }
+
+    fn register_wait(&self, priority: RequestPriority, wait: Duration) {
+        self.metrics
+            .lock()
+            .entry(priority)
+            .or_default()
+            .register(wait);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn waiter(id: u64, priority: RequestPriority, enqueue_time: Instant) -> Waiter {
+        Waiter {
+            id,
+            priority,
+            enqueue_time,
+        }
+    }
+
+    #[test]
+    pub fn higher_priority_is_served_first() {
+        let now = Instant::now();
+        let waiters = [
+            waiter(0, RequestPriority::Quote, now),
+            waiter(1, RequestPriority::Cancel, now),
+            waiter(2, RequestPriority::Cancel, now),
+        ];
+
+        assert_eq!(next_waiter(&waiters, now, Duration::from_secs(1)), Some(1));
+    }
+
+    #[test]
+    pub fn starving_request_is_served_first() {
+        let now = Instant::now();
+        let waiters = [
+            waiter(0, RequestPriority::Cancel, now),
+            waiter(1, RequestPriority::Quote, now - Duration::from_secs(2)),
+        ];
+
+        assert_eq!(next_waiter(&waiters, now, Duration::from_secs(1)), Some(1));
+    }
+
+    #[tokio::test]
+    pub async fn request_waits_for_its_turn() {
+        let queue = RequestPriorityQueue::new(DEFAULT_MAX_QUEUE_WAIT);
+        let mut available_requests = 0;
+        let try_reserve = || {
+            let is_reserved = available_requests > 0;
+            available_requests = 1;
+            Ok(is_reserved)
+        };
+
+        queue
+            .reserve(
+                RequestPriority::Cancel,
+                try_reserve,
+                CancellationToken::new(),
+            )
+            .await
+            .expect("in test");
+
+        let metrics = queue.wait_metrics()[&RequestPriority::Cancel];
+        assert_eq!(metrics.requests_count, 1);
+        assert!(!queue.has_higher_priority_waiters(RequestPriority::Quote));
+    }
+}
@@ -231,6 +231,13 @@ impl RequestsTimeoutManager {
         }
     }
 
+    /// Requests count that can be sent instantly
+    pub fn get_available_requests_count(&self, current_time: DateTime) -> usize {
+        self.inner
+            .lock()
+            .get_available_requests_count_at_present(current_time)
+    }
+
     pub fn try_reserve_request_instant(
         &self,
         request_type: RequestType,
@@ -16,6 +16,9 @@ use chrono::Utc;
 
 use crate::exchanges::common::ExchangeAccountId;
 use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::timeouts::request_priority_queue::{
+    QueueWaitMetrics, RequestPriority, RequestPriorityQueue, DEFAULT_MAX_QUEUE_WAIT,
+};
 use crate::exchanges::timeouts::requests_timeout_manager::{
     RequestGroupId, RequestsTimeoutManager,
 };
@@ -24,14 +27,21 @@ pub type BoxFuture = Box<dyn Future<Output = Result<()>> + Sync + Send>;
 
 pub struct TimeoutManager {
     inner: HashMap<ExchangeAccountId, Arc<RequestsTimeoutManager>>,
+    priority_queues: HashMap<ExchangeAccountId, RequestPriorityQueue>,
 }
 
 impl TimeoutManager {
     pub fn new(
         timeout_managers: HashMap<ExchangeAccountId, Arc<RequestsTimeoutManager>>,
     ) -> Arc<Self> {
+        let priority_queues = timeout_managers
+            .keys()
+            .map(|&x| (x, RequestPriorityQueue::new(DEFAULT_MAX_QUEUE_WAIT)))
+            .collect();
+
         Arc::new(TimeoutManager {
             inner: timeout_managers,
+            priority_queues,
         })
     }
 
@@ -115,6 +125,48 @@ impl TimeoutManager {
         Ok(Either::Left(convert(result.0)))
     }
 
+    /// Waits until request can be reserved by rate limits. Requests with higher priority are
+    /// reserved first, but request isn't delayed by them longer than `DEFAULT_MAX_QUEUE_WAIT`
+    pub async fn reserve_by_priority(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        request_type: RequestType,
+        priority: RequestPriority,
+        pre_reservation_group_id: Option<RequestGroupId>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let inner = &self.inner[&exchange_account_id];
+        let try_reserve =
+            || inner.try_reserve_instant(request_type, now(), pre_reservation_group_id);
+
+        self.priority_queues[&exchange_account_id]
+            .reserve(priority, try_reserve, cancellation_token)
+            .await
+    }
+
+    /// New requests of `priority` should yield to waiting requests of higher priority
+    pub fn has_higher_priority_requests(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        priority: RequestPriority,
+    ) -> bool {
+        self.priority_queues[&exchange_account_id].has_higher_priority_waiters(priority)
+    }
+
+    pub fn queue_wait_metrics(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> HashMap<RequestPriority, QueueWaitMetrics> {
+        self.priority_queues[&exchange_account_id].wait_metrics()
+    }
+
+    pub fn get_available_requests_count(&self, exchange_account_id: ExchangeAccountId) -> usize {
+        self.inner
+            .get(&exchange_account_id)
+            .with_expect(|| format!("Can't find timeout manger for {exchange_account_id}"))
+            .get_available_requests_count(now())
+    }
+
     pub fn get_period_duration(&self, exchange_account_id: ExchangeAccountId) -> Duration {
         self.inner
             .get(&exchange_account_id)
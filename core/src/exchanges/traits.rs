@@ -1,36 +1,45 @@
 use super::{
     common::CurrencyCode,
     common::{
-        ActivePosition, CurrencyPair, ExchangeAccountId, ExchangeError, ExchangeId,
-        SpecificCurrencyPair,
+        ActivePosition, CurrencyPair, ExchangeAccountId, ExchangeError, ExchangeErrorType,
+        ExchangeId, SpecificCurrencyPair,
     },
     common::{Amount, ClosedPosition, CurrencyId, Price},
-    events::{ExchangeBalancesAndPositions, TradeId},
+    events::{ExchangeBalancesAndPositions, Trade, TradeId},
     general::handlers::handle_order_filled::FillEvent,
     general::symbol::BeforeAfter,
     general::{order::get_order_trades::OrderTrade, symbol::Symbol},
     timeouts::requests_timeout_manager_factory::RequestTimeoutArguments,
 };
+use crate::exchanges::api_key_permissions::ApiKeyPermissions;
+use crate::exchanges::connection_pool::ConnectionPoolStats;
 use crate::exchanges::events::ExchangeEvent;
 use crate::exchanges::general::exchange::RequestResult;
 use crate::exchanges::general::features::ExchangeFeatures;
 use crate::exchanges::general::order::cancel::CancelOrderResult;
 use crate::exchanges::general::order::create::CreateOrderResult;
+use crate::exchanges::host_selection::HostStats;
+use crate::exchanges::margin::MarginInfo;
+use crate::exchanges::payload_anomalies::PayloadAnomaly;
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::order_book::order_book_data::OrderBookData;
 use crate::orders::fill::EventSourceType;
 use crate::orders::order::{
-    ClientOrderId, ExchangeOrderId, OrderCancelling, OrderInfo, OrderInfoExtensionData,
+    ClientOrderId, ExchangeOrderId, OrderCancelling, OrderHeader, OrderInfo, OrderInfoExtensionData,
 };
 use crate::orders::pool::OrdersPool;
-use crate::settings::ExchangeSettings;
+use crate::services::funding_rates::FundingRate;
+use crate::settings::{Environment, ExchangeSettings};
 use crate::{connectivity::WebSocketRole, orders::order::OrderSide};
 use crate::{exchanges::general::exchange::BoxExchangeClient, orders::pool::OrderRef};
 use anyhow::Result;
 use async_trait::async_trait;
 use dashmap::DashMap;
 use mmb_utils::DateTime;
+use std::any::Any;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
 use url::Url;
 
 // Implementation of rest API client
@@ -68,6 +77,82 @@ pub trait ExchangeClient: Support {
     ) -> Result<RequestResult<Vec<OrderTrade>>>;
 
     async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>>;
+
+    /// Returns `None` if exchange doesn't provide permissions of API key
+    async fn get_api_key_permissions(&self) -> Result<Option<ApiKeyPermissions>> {
+        Ok(None)
+    }
+
+    /// Returns `None` if exchange doesn't provide margin state of account
+    async fn get_margin_info(&self) -> Result<Option<MarginInfo>> {
+        Ok(None)
+    }
+
+    /// Volumes traded during last 24 hours in quote currency by currency pairs.
+    /// Returns `None` if exchange doesn't provide them
+    async fn get_daily_volumes(&self) -> Result<Option<HashMap<CurrencyPair, Amount>>> {
+        Ok(None)
+    }
+
+    /// History of orders created in time range [from, to] including completed ones.
+    /// Returns `None` if exchange doesn't provide history of orders
+    async fn get_order_history(
+        &self,
+        _currency_pair: CurrencyPair,
+        _from: DateTime,
+        _to: DateTime,
+    ) -> Result<Option<Vec<OrderInfo>>> {
+        Ok(None)
+    }
+
+    /// Page of trades with ids following `last_trade_id` in order of ids.
+    /// Returns `None` if exchange doesn't provide history of trades
+    async fn get_trades_after(
+        &self,
+        _currency_pair: CurrencyPair,
+        _last_trade_id: u64,
+    ) -> Result<Option<Vec<Trade>>> {
+        Ok(None)
+    }
+
+    /// Returns `None` if exchange doesn't provide funding rate of currency pair (e.g. spot market)
+    async fn get_funding_rate(&self, _currency_pair: CurrencyPair) -> Result<Option<FundingRate>> {
+        Ok(None)
+    }
+
+    /// Order book of currency pair requested by REST.
+    /// Returns `None` if exchange doesn't provide order book by REST
+    async fn get_order_book(&self, _currency_pair: CurrencyPair) -> Result<Option<OrderBookData>> {
+        Ok(None)
+    }
+
+    /// Reduces amount of order to `new_amount` keeping its priority in queue.
+    /// Exchanges which don't provide such operation return `ExchangeErrorType::Unsupported`
+    async fn reduce_order(
+        &self,
+        _order: &OrderRef,
+        _new_amount: Amount,
+    ) -> Result<(), ExchangeError> {
+        Err(ExchangeError::new(
+            ExchangeErrorType::Unsupported,
+            "Reducing of order amount isn't supported by exchange".to_owned(),
+            None,
+        ))
+    }
+
+    /// Cancels order by its client order id, so order can be canceled before its creation is
+    /// acknowledged. Exchanges which don't provide such operation return
+    /// `ExchangeErrorType::Unsupported`
+    async fn cancel_order_by_client_order_id(
+        &self,
+        _header: &OrderHeader,
+    ) -> Result<(), ExchangeError> {
+        Err(ExchangeError::new(
+            ExchangeErrorType::Unsupported,
+            "Cancellation of order by client order id isn't supported by exchange".to_owned(),
+            None,
+        ))
+    }
 }
 
 pub type OrderCreatedCb =
@@ -103,12 +188,28 @@ pub trait Support: Send + Sync {
 
     async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url>;
 
+    /// Opens websockets which are kept by connector itself instead of engine, e.g. by connector
+    /// process. Their messages aren't passed to engine, returned channel is closed when the
+    /// websockets are disconnected. `None` if websockets of connector are kept by engine
+    async fn connect_own_websockets(
+        &self,
+        _is_market_data_only: bool,
+    ) -> Option<Result<mpsc::UnboundedReceiver<String>>> {
+        None
+    }
+
     fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair;
 
     fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode>;
 
     fn should_log_message(&self, message: &str) -> bool;
 
+    /// Key of websocket message to keep order of messages with the same key, usually market,
+    /// when they are parsed by parsing workers. It should be cheap, so full parsing isn't allowed
+    fn get_message_partition_key<'a>(&self, _message: &'a str) -> Option<&'a str> {
+        None
+    }
+
     fn log_unknown_message(&self, exchange_account_id: ExchangeAccountId, message: &str) {
         log::info!("Unknown message for {}: {}", exchange_account_id, message);
     }
@@ -123,9 +224,27 @@ pub trait Support: Send + Sync {
 
     fn get_settings(&self) -> &ExchangeSettings;
 
+    /// Connector as `Any`, so exchange-specific features of connector which aren't part of
+    /// common traits can be accessed by downcasting (see `Exchange::client_as`)
+    fn as_any(&self) -> &dyn Any;
+
     fn get_initial_extension_data(&self) -> Option<Box<dyn OrderInfoExtensionData>> {
         None
     }
+
+    /// Latency and errors of hosts if connector selects one of several hosts of exchange API
+    fn get_host_stats(&self) -> Vec<HostStats> {
+        Vec::new()
+    }
+
+    fn get_connection_pool_stats(&self) -> Option<ConnectionPoolStats> {
+        None
+    }
+
+    /// Unexpected values in exchange payloads tolerated by connector
+    fn get_payload_anomalies(&self) -> Vec<PayloadAnomaly> {
+        Vec::new()
+    }
 }
 
 pub struct ExchangeClientBuilderResult {
@@ -145,4 +264,9 @@ pub trait ExchangeClientBuilder {
     fn get_timeout_arguments(&self) -> RequestTimeoutArguments;
 
     fn get_exchange_id(&self) -> ExchangeId;
+
+    /// Environment which hosts of exchange client belong to. `None` if it's unknown
+    fn get_hosts_environment(&self, _exchange_settings: &ExchangeSettings) -> Option<Environment> {
+        None
+    }
 }
@@ -3,6 +3,7 @@ use futures::Future;
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::infrastructure::FutureOutcome;
 use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::OPERATION_CANCELED_MSG;
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
 use std::panic;
@@ -11,10 +12,12 @@ use std::time::Duration;
 use tokio::task::JoinHandle;
 
 use super::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::errors::format_error_code;
 
 static LIFETIME_MANAGER: OnceCell<Mutex<Option<Arc<AppLifetimeManager>>>> = OnceCell::new();
 
 pub fn init_lifetime_manager() -> Arc<AppLifetimeManager> {
+    mmb_utils::infrastructure::set_error_code_formatter(format_error_code);
     let manger = AppLifetimeManager::new(CancellationToken::new());
     keep_lifetime_manager(manger.clone());
 
@@ -99,6 +102,35 @@ pub fn spawn_future(
     )
 }
 
+/// Spawn future of isolated subsystem, e.g. connector of exchange account.
+/// Panic or error inside the future doesn't stop the engine, `on_failure` is called instead,
+/// so the subsystem can be restarted independently. Other nuances are the same as spawn_future()
+pub fn spawn_isolated_future(
+    action_name: &str,
+    flags: SpawnFutureFlags,
+    action: impl Future<Output = Result<()>> + Send + 'static,
+    on_failure: impl Fn(&str) + Clone + Send + 'static,
+) -> JoinHandle<FutureOutcome> {
+    let on_error = on_failure.clone();
+    let action = async move {
+        let result = action.await;
+        if let Err(error) = &result {
+            if error.to_string() != OPERATION_CANCELED_MSG {
+                on_error(&format!("{error:?}"));
+            }
+        }
+        result
+    };
+
+    mmb_utils::infrastructure::spawn_future(
+        action_name,
+        flags,
+        action,
+        move |_, error_message| on_failure(error_message),
+        get_futures_cancellation_token(),
+    )
+}
+
 fn spawn_graceful_shutdown(log_template: String, error_message: &str) {
     match LIFETIME_MANAGER.get() {
         Some(lifetime_manager) => {
@@ -16,6 +16,7 @@
     clippy::unwrap_used
 )]
 
+pub mod backtesting;
 pub mod balance;
 pub mod connectivity;
 pub mod exchanges;
@@ -28,15 +29,20 @@ pub mod statistic_service;
 pub mod strategies;
 
 pub mod config;
+pub mod config_wizard;
+pub mod data_bridge;
 pub mod database;
 pub mod disposition_execution;
+pub mod errors;
 pub mod explanation;
 pub mod lifecycle;
 pub mod math;
 pub mod order_book;
-pub(crate) mod services;
+pub mod services;
 pub mod settings;
+pub mod settings_values;
 pub mod text;
+pub mod treasury;
 
 #[cfg(test)]
 use parking_lot::ReentrantMutex;
@@ -1,22 +1,39 @@
+use crate::balance::balance_anomaly::start_balance_anomaly_detection;
 use crate::balance::manager::balance_manager::BalanceManager;
-use crate::config::{load_pretty_settings, try_load_settings};
-use crate::database::events::recorder::{DbSettings, EventRecorder};
-use crate::exchanges::common::{ExchangeAccountId, ExchangeId};
+use crate::config::{effective_settings, load_pretty_settings, try_load_settings};
+use crate::data_bridge::DataBridge;
+use crate::database::events::recorder::{DbSettings, EventRecorder, EventSink};
+use crate::database::kv_store::KeyValueStore;
+use crate::database::retention::EventRetention;
+use crate::exchanges::api_key_permissions::{
+    check_api_key_permissions, start_api_key_health_checks,
+};
+use crate::exchanges::common::{ExchangeAccountId, ExchangeId, MarketAccountId};
 use crate::exchanges::events::{ExchangeEvent, ExchangeEvents, CHANNEL_MAX_EVENTS_COUNT};
 use crate::exchanges::exchange_blocker::ExchangeBlocker;
 use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
 use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::exchange_creation::check_environments;
 use crate::exchanges::general::exchange_creation::create_exchange;
 use crate::exchanges::general::exchange_creation::create_timeout_manager;
 use crate::exchanges::internal_events_loop::InternalEventsLoop;
+use crate::exchanges::margin::start_margin_monitoring;
+use crate::exchanges::stale_market_data::start_stale_market_data_detection;
 use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
 use crate::exchanges::traits::ExchangeClientBuilder;
 use crate::infrastructure::{init_lifetime_manager, spawn_future_ok};
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::lifecycle::leader_election::start_leader_election;
 use crate::lifecycle::trading_engine::{EngineContext, TradingEngine};
+use crate::lifecycle::warm_up::start_warm_up;
+use crate::order_book::delta_recorder::OrderBookDeltaRecorder;
 use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::queue_position::start_queue_position_tracking;
+use crate::orders::risk_engine::RiskEngine;
 use crate::rpc::config_waiter::ConfigWaiter;
 use crate::rpc::core_api::CoreApi;
+use crate::services::metrics_sink::{start_metrics_reporting, MetricsSink};
+use crate::services::news_restrictions::SignalSource;
 use crate::settings::{AppSettings, BaseStrategySettings, CoreSettings};
 use crate::statistic_service::StatisticEventHandler;
 use crate::statistic_service::StatisticService;
@@ -65,6 +82,7 @@ impl EngineBuildConfig {
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum InitSettings<StrategySettings>
 where
     StrategySettings: BaseStrategySettings + Clone,
@@ -118,9 +136,19 @@ where
     }
 }
 
+/// Custom parts of engine replacing built-in ones
+#[derive(Default)]
+struct EngineOverrides {
+    risk_engine: Option<Arc<dyn RiskEngine>>,
+    event_sink: Option<Arc<dyn EventSink>>,
+    metrics_sink: Option<(Arc<dyn MetricsSink>, Duration)>,
+    signal_sources: Vec<Arc<dyn SignalSource>>,
+}
+
 async fn before_engine_context_init<StrategySettings>(
     build_settings: &EngineBuildConfig,
     init_user_settings: InitSettings<StrategySettings>,
+    overrides: &EngineOverrides,
 ) -> Result<(
     broadcast::Sender<ExchangeEvent>,
     broadcast::Receiver<ExchangeEvent>,
@@ -154,6 +182,8 @@ where
 
     let (events_sender, events_receiver) = broadcast::channel(CHANNEL_MAX_EVENTS_COUNT);
 
+    check_environments(&settings.core, build_settings).context("Refusing to start")?;
+
     let timeout_manager = create_timeout_manager(&settings.core, build_settings);
 
     let exchange_account_ids = settings
@@ -180,6 +210,10 @@ where
         .map(|exchange| (exchange.exchange_account_id, exchange))
         .collect();
 
+    check_api_key_permissions(&settings.core, &exchanges_map)
+        .await
+        .context("Refusing to start")?;
+
     let exchange_events = ExchangeEvents::new(events_sender.clone());
 
     let exchanges_hashmap: HashMap<ExchangeAccountId, Arc<Exchange>> =
@@ -188,17 +222,44 @@ where
     let currency_pair_to_symbol_converter = CurrencyPairToSymbolConverter::new(exchanges_hashmap);
 
     let balance_manager = BalanceManager::new(currency_pair_to_symbol_converter);
+    balance_manager.lock().setup_spending_limits(&settings.core);
+    balance_manager
+        .lock()
+        .setup_capital_allocations(&settings.core);
 
-    BalanceManager::update_balances_for_exchanges(
-        balance_manager.clone(),
-        lifetime_manager.stop_token(),
-    )
-    .await;
+    if settings.core.market_data_only {
+        log::info!("TradingEngine runs in market data only mode, trading is disabled");
+    } else {
+        BalanceManager::update_balances_for_exchanges(
+            balance_manager.clone(),
+            lifetime_manager.stop_token(),
+        )
+        .await;
+    }
 
     for exchange in &exchanges_map {
         exchange
             .value()
-            .setup_balance_manager(balance_manager.clone())
+            .setup_balance_manager(balance_manager.clone());
+        exchange
+            .value()
+            .setup_currency_restrictions(&settings.core.currency_restrictions);
+        if let Some(risk_engine) = &overrides.risk_engine {
+            exchange.value().setup_risk_engine(risk_engine.clone());
+        }
+    }
+
+    if let Some(margin_risk_settings) = &settings.core.margin_risk {
+        for exchange_settings in settings
+            .core
+            .exchanges
+            .iter()
+            .filter(|x| x.is_margin_trading)
+        {
+            if let Some(exchange) = exchanges_map.get(&exchange_settings.exchange_account_id) {
+                exchange.setup_margin_risk(margin_risk_settings);
+            }
+        }
     }
 
     let (finish_graceful_shutdown_tx, finish_graceful_shutdown_rx) = oneshot::channel();
@@ -216,9 +277,28 @@ where
         None
     };
 
-    let event_recorder = EventRecorder::start(database)
+    let kv_store = KeyValueStore::start(database.as_ref().map(|x| x.database_url.as_str()))
         .await
-        .context("can't start EventRecorder")?;
+        .context("can't start KeyValueStore")?;
+
+    let event_retention = match &settings.core.database {
+        Some(db) => match &db.retention {
+            Some(retention) => Some(
+                EventRetention::start(&db.url, retention.clone())
+                    .await
+                    .context("can't start EventRetention")?,
+            ),
+            None => None,
+        },
+        None => None,
+    };
+
+    let event_recorder = match &overrides.event_sink {
+        Some(event_sink) => EventRecorder::start_with_sink(event_sink.clone()),
+        None => EventRecorder::start(database)
+            .await
+            .context("can't start EventRecorder")?,
+    };
 
     let engine_context = EngineContext::new(
         settings.core.clone(),
@@ -230,7 +310,9 @@ where
         lifetime_manager.clone(),
         balance_manager,
         event_recorder,
-    );
+        kv_store,
+        event_retention,
+    )?;
 
     Ok((
         events_sender,
@@ -264,14 +346,77 @@ where
         .shutdown_service
         .register_core_service(internal_events_loop.clone());
 
+    if let Some(margin_risk_settings) = &engine_context.core_settings.margin_risk {
+        start_margin_monitoring(
+            margin_risk_settings,
+            exchanges_map.clone(),
+            engine_context.lifetime_manager.stop_token(),
+        );
+    }
+
+    if let Some(balance_anomaly_settings) = &engine_context.core_settings.balance_anomaly {
+        start_balance_anomaly_detection(
+            balance_anomaly_settings,
+            exchanges_map.clone(),
+            engine_context.audit_log.clone(),
+            events_sender.subscribe(),
+        );
+    }
+
+    if let Some(api_key_health_settings) = &engine_context.core_settings.api_key_health {
+        start_api_key_health_checks(
+            api_key_health_settings,
+            &engine_context.core_settings,
+            &exchanges_map,
+            engine_context.account_groups.clone(),
+            engine_context.lifetime_manager.stop_token(),
+        );
+    }
+
+    if let Some(failover_settings) = &engine_context.core_settings.failover {
+        start_leader_election(failover_settings, engine_context.clone());
+    }
+
+    if let Some(stale_market_data_settings) = &engine_context.core_settings.stale_market_data {
+        start_stale_market_data_detection(
+            stale_market_data_settings,
+            exchanges_map.clone(),
+            engine_context.exchange_blocker.clone(),
+            events_sender.clone(),
+        );
+    }
+
+    if let Some(queue_position_settings) = &engine_context.core_settings.queue_position {
+        start_queue_position_tracking(
+            queue_position_settings,
+            exchanges_map.clone(),
+            events_sender.clone(),
+        );
+    }
+
     let exchange_events = ExchangeEvents::new(events_sender);
-    let statistic_service = StatisticService::new();
+    let statistic_service = engine_context.statistics.clone();
     let statistic_event_handler =
         create_statistic_event_handler(exchange_events, statistic_service.clone());
+    if let Some(data_bridge_settings) = &engine_context.core_settings.data_bridge {
+        let _ = DataBridge::start(data_bridge_settings, engine_context.get_events_channel());
+    }
+    if let Some(recording_settings) = &engine_context.core_settings.order_book_recording {
+        if let Err(err) =
+            OrderBookDeltaRecorder::start(recording_settings, engine_context.get_events_channel())
+        {
+            log::error!("Unable to start order book recording: {err:?}");
+        }
+    }
     let control_panel = CoreApi::create_and_start(
         engine_context.lifetime_manager.clone(),
         load_pretty_settings(init_user_settings),
+        effective_settings(&settings).unwrap_or_else(|err| {
+            log::error!("Unable to get effective settings: {err:?}");
+            format!("Effective settings are unavailable: {err}")
+        }),
         statistic_service,
+        Arc::downgrade(&engine_context),
     )
     .expect("Unable to start control panel");
     engine_context
@@ -285,21 +430,43 @@ where
             internal_events_loop.start(
                 events_receiver,
                 exchanges_map.into_iter().collect(),
+                engine_context.local_snapshots.clone(),
                 engine_context.lifetime_manager.stop_token(),
             ),
         );
     }
 
-    let disposition_strategy = build_strategy(&settings, engine_context.clone());
-    let disposition_executor_service = create_disposition_executor_service(
-        &settings.strategy,
-        &engine_context,
-        disposition_strategy,
-        &statistic_event_handler.stats,
-    );
-    engine_context
-        .shutdown_service
-        .register_user_service(disposition_executor_service);
+    if engine_context.core_settings.market_data_only {
+        log::info!("Strategy isn't started in market data only mode");
+    } else {
+        if let Some(warm_up_settings) = &engine_context.core_settings.warm_up {
+            let market_account_id = MarketAccountId::new(
+                settings.strategy.exchange_account_id(),
+                settings.strategy.currency_pair(),
+            );
+            start_warm_up(
+                warm_up_settings,
+                engine_context.clone(),
+                vec![market_account_id],
+                engine_context.get_events_channel(),
+            );
+        }
+
+        if let Some(hedging) = &engine_context.hedging {
+            hedging.clone().start();
+        }
+
+        let disposition_strategy = build_strategy(&settings, engine_context.clone());
+        let disposition_executor_service = create_disposition_executor_service(
+            &settings.strategy,
+            &engine_context,
+            disposition_strategy,
+            &statistic_event_handler.stats,
+        );
+        engine_context
+            .shutdown_service
+            .register_user_service(disposition_executor_service);
+    }
 
     log::info!("TradingEngine started");
     TradingEngine::new(engine_context, finish_graceful_shutdown_rx)
@@ -371,61 +538,154 @@ pub async fn launch_trading_engine<StrategySettings>(
 where
     StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
 {
-    print_info("The TradingEngine is going to start...");
-    let action_outcome = AssertUnwindSafe(before_engine_context_init(
-        build_settings,
-        init_user_settings.clone(),
-    ))
-    .catch_unwind()
-    .await;
+    EngineBuilder::new(build_settings, init_user_settings)
+        .launch(build_strategy)
+        .await
+}
 
-    let message_template = "Panic happened during EngineContext initialization";
-    let (
-        events_sender,
-        events_receiver,
-        settings,
-        exchanges_map,
-        engine_context,
-        finish_graceful_shutdown_rx,
-    ) = unwrap_or_handle_panic(action_outcome, message_template, None)??;
+/// Builder of trading engine for applications which embed engine into their own binaries.
+/// Built-in parts of engine can be replaced by custom ones, other parts are created by settings
+/// as in `launch_trading_engine`
+pub struct EngineBuilder<'a, StrategySettings>
+where
+    StrategySettings: BaseStrategySettings + Clone,
+{
+    build_settings: &'a EngineBuildConfig,
+    init_user_settings: InitSettings<StrategySettings>,
+    overrides: EngineOverrides,
+}
 
-    let cloned_lifetime_manager = engine_context.lifetime_manager.clone();
-    let action = async move {
-        signal::ctrl_c().await.expect("failed to listen for event");
+impl<'a, StrategySettings> EngineBuilder<'a, StrategySettings>
+where
+    StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
+{
+    pub fn new(
+        build_settings: &'a EngineBuildConfig,
+        init_user_settings: InitSettings<StrategySettings>,
+    ) -> Self {
+        EngineBuilder {
+            build_settings,
+            init_user_settings,
+            overrides: Default::default(),
+        }
+    }
 
-        print_info("Ctrl-C signal was received so graceful_shutdown will be started");
-        cloned_lifetime_manager.spawn_graceful_shutdown("Ctrl-C signal was received");
-    };
+    /// Engine is started with settings created in process instead of loading them from files
+    pub fn with_settings(
+        build_settings: &'a EngineBuildConfig,
+        settings: AppSettings<StrategySettings>,
+    ) -> Self {
+        Self::new(build_settings, InitSettings::Directly(settings))
+    }
 
-    let _ = spawn_future_ok(
-        "Start Ctrl-C handler",
-        SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
-        action,
-    );
+    /// Orders of all exchanges are checked by `risk_engine` in addition to built-in checks
+    pub fn risk_engine(mut self, risk_engine: Arc<dyn RiskEngine>) -> Self {
+        self.overrides.risk_engine = Some(risk_engine);
+        self
+    }
 
-    let action_outcome = panic::catch_unwind(AssertUnwindSafe(|| {
-        run_services(
-            engine_context.clone(),
+    /// Recorded events are saved to `event_sink` instead of database
+    pub fn event_sink(mut self, event_sink: Arc<dyn EventSink>) -> Self {
+        self.overrides.event_sink = Some(event_sink);
+        self
+    }
+
+    /// Metrics of exchange accounts are pushed to `metrics_sink` every `interval`
+    pub fn metrics_sink(mut self, metrics_sink: Arc<dyn MetricsSink>, interval: Duration) -> Self {
+        self.overrides.metrics_sink = Some((metrics_sink, interval));
+        self
+    }
+
+    /// Signals of `signal_source` restrict assets in addition to feeds from news restrictions settings
+    pub fn signal_source(mut self, signal_source: Arc<dyn SignalSource>) -> Self {
+        self.overrides.signal_sources.push(signal_source);
+        self
+    }
+
+    pub async fn launch(
+        self,
+        build_strategy: impl Fn(
+            &AppSettings<StrategySettings>,
+            Arc<EngineContext>,
+        ) -> Box<dyn DispositionStrategy + 'static>,
+    ) -> Result<TradingEngine> {
+        let EngineBuilder {
+            build_settings,
+            init_user_settings,
+            overrides,
+        } = self;
+
+        print_info("The TradingEngine is going to start...");
+        let action_outcome = AssertUnwindSafe(before_engine_context_init(
+            build_settings,
+            init_user_settings.clone(),
+            &overrides,
+        ))
+        .catch_unwind()
+        .await;
+
+        let message_template = "Panic happened during EngineContext initialization";
+        let (
             events_sender,
             events_receiver,
             settings,
             exchanges_map,
-            init_user_settings,
-            build_strategy,
+            engine_context,
             finish_graceful_shutdown_rx,
-        )
-    }));
+        ) = unwrap_or_handle_panic(action_outcome, message_template, None)??;
+
+        let cloned_lifetime_manager = engine_context.lifetime_manager.clone();
+        let action = async move {
+            signal::ctrl_c().await.expect("failed to listen for event");
 
-    let message_template = "Panic happened during TradingEngine creation";
-    let result = unwrap_or_handle_panic(
-        action_outcome,
-        message_template,
-        Some(engine_context.lifetime_manager.clone()),
-    );
+            print_info("Ctrl-C signal was received so graceful_shutdown will be started");
+            cloned_lifetime_manager.spawn_graceful_shutdown("Ctrl-C signal was received");
+        };
 
-    print_info("The TradingEngine has been successfully launched");
+        let _ = spawn_future_ok(
+            "Start Ctrl-C handler",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            action,
+        );
 
-    result
+        if let Some((metrics_sink, interval)) = overrides.metrics_sink {
+            start_metrics_reporting(metrics_sink, interval, exchanges_map.clone());
+        }
+
+        for signal_source in overrides.signal_sources {
+            match &engine_context.news_restrictions {
+                Some(news_restrictions) => news_restrictions.start_source(signal_source),
+                None => log::error!(
+                    "Signal source {} isn't started because news restrictions aren't configured",
+                    signal_source.name()
+                ),
+            }
+        }
+
+        let action_outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_services(
+                engine_context.clone(),
+                events_sender,
+                events_receiver,
+                settings,
+                exchanges_map,
+                init_user_settings,
+                build_strategy,
+                finish_graceful_shutdown_rx,
+            )
+        }));
+
+        let message_template = "Panic happened during TradingEngine creation";
+        let result = unwrap_or_handle_panic(
+            action_outcome,
+            message_template,
+            Some(engine_context.lifetime_manager.clone()),
+        );
+
+        print_info("The TradingEngine has been successfully launched");
+
+        result
+    }
 }
 
 fn create_disposition_executor_service(
@@ -469,6 +729,7 @@ pub async fn create_exchanges(
             lifetime_manager.clone(),
             timeout_manager.clone(),
             exchange_blocker.clone(),
+            core_settings.market_data_only,
         )
     }))
     .await
@@ -0,0 +1,497 @@
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{ensure, Context, Result};
+use fs2::FileExt;
+use futures::future::join_all;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::block_reasons::STANDBY;
+use crate::exchanges::exchange_blocker::BlockType;
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::misc::time::time_manager;
+use crate::settings::FailoverSettings;
+
+/// Lease of leadership which is stored in file shared by engine instances
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Lease {
+    pub instance_id: String,
+    /// Fencing token which is incremented on every change of leader
+    pub epoch: u64,
+    pub expire_time: DateTime,
+}
+
+impl Lease {
+    fn is_expired(&self, now: DateTime) -> bool {
+        self.expire_time <= now
+    }
+}
+
+#[derive(Debug)]
+struct LeaderState {
+    epoch: u64,
+    trading_allowed_until: DateTime,
+}
+
+/// Leadership of engine instance. Only leader is allowed to create orders.
+/// Without failover settings instance is always leader
+#[derive(Debug)]
+pub struct Leadership {
+    is_failover_enabled: bool,
+    state: Mutex<Option<LeaderState>>,
+}
+
+impl Default for Leadership {
+    fn default() -> Self {
+        Leadership {
+            is_failover_enabled: false,
+            state: Mutex::new(None),
+        }
+    }
+}
+
+impl Leadership {
+    pub fn new(is_failover_enabled: bool) -> Arc<Self> {
+        Arc::new(Leadership {
+            is_failover_enabled,
+            state: Mutex::new(None),
+        })
+    }
+
+    /// Leader stops trading by itself when its lease isn't renewed in time, so it never trades
+    /// simultaneously with standby instance which took over expired lease
+    pub fn is_leader(&self) -> bool {
+        if !self.is_failover_enabled {
+            return true;
+        }
+
+        self.state
+            .lock()
+            .as_ref()
+            .is_some_and(|x| time_manager::now() < x.trading_allowed_until)
+    }
+
+    /// Epoch of lease held by this instance
+    pub fn fencing_token(&self) -> Option<u64> {
+        self.state.lock().as_ref().map(|x| x.epoch)
+    }
+
+    /// Action authorized by fencing token is allowed only while this instance is still leader
+    /// with the same lease, so actions started before leadership was lost or acquired again
+    /// with new epoch are rejected
+    pub fn is_fencing_token_valid(&self, fencing_token: Option<u64>) -> bool {
+        if !self.is_failover_enabled {
+            return true;
+        }
+
+        self.is_leader() && fencing_token.is_some() && self.fencing_token() == fencing_token
+    }
+
+    fn set_leader(&self, epoch: u64, trading_allowed_until: DateTime) {
+        *self.state.lock() = Some(LeaderState {
+            epoch,
+            trading_allowed_until,
+        });
+    }
+
+    fn reset(&self) {
+        *self.state.lock() = None;
+    }
+}
+
+/// Next lease for instance or `None` if lease is held by another instance
+fn next_lease(
+    current: Option<&Lease>,
+    instance_id: &str,
+    now: DateTime,
+    lease_duration: chrono::Duration,
+) -> Option<Lease> {
+    let epoch = match current {
+        None => 1,
+        Some(lease) if lease.instance_id == instance_id && !lease.is_expired(now) => lease.epoch,
+        Some(lease) if lease.is_expired(now) => lease.epoch + 1,
+        Some(_) => return None,
+    };
+
+    Some(Lease {
+        instance_id: instance_id.to_owned(),
+        epoch,
+        expire_time: now + lease_duration,
+    })
+}
+
+fn read_lease(path: &Path) -> Result<Option<Lease>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Unable to read lease from {}", path.display()))?;
+    let lease = serde_json::from_str(&content).context("Unable to parse lease")?;
+    Ok(Some(lease))
+}
+
+/// Lease is written to temporary file and renamed, so other instances never read partial lease
+fn write_lease(path: &Path, lease: &Lease) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let content = serde_json::to_string(lease).context("Unable to serialize lease")?;
+    fs::write(&tmp_path, content)
+        .with_context(|| format!("Unable to write lease to {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("Unable to replace lease file {}", path.display()))
+}
+
+/// Exclusive lock of lease held while lease is read and replaced, so instances never overwrite
+/// leases of each other. Lock is released when returned file is dropped or instance exits
+fn lock_lease(path: &Path) -> Result<File> {
+    let lock_path = path.with_extension("lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&lock_path)
+        .with_context(|| format!("Unable to open lease lock {}", lock_path.display()))?;
+    file.lock_exclusive()
+        .with_context(|| format!("Unable to lock lease lock {}", lock_path.display()))?;
+    Ok(file)
+}
+
+/// Acquires or renews lease of instance under exclusive lock. Returns `None` if lease is held by
+/// another instance
+fn update_lease(
+    path: &Path,
+    instance_id: &str,
+    now: DateTime,
+    lease_duration: chrono::Duration,
+) -> Result<Option<Lease>> {
+    let _lock = lock_lease(path)?;
+
+    let current = read_lease(path)?;
+    let lease = next_lease(current.as_ref(), instance_id, now, lease_duration);
+    if let Some(lease) = &lease {
+        write_lease(path, lease)?;
+    }
+
+    Ok(lease)
+}
+
+/// Expires lease if it's still held by instance with specified epoch
+fn expire_lease(path: &Path, instance_id: &str, epoch: u64, now: DateTime) -> Result<()> {
+    let _lock = lock_lease(path)?;
+
+    match read_lease(path)? {
+        Some(current) if current.instance_id == instance_id && current.epoch == epoch => {
+            let lease = Lease {
+                instance_id: instance_id.to_owned(),
+                epoch,
+                expire_time: now,
+            };
+            write_lease(path, &lease)
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn update_lease_blocking(
+    path: PathBuf,
+    instance_id: String,
+    now: DateTime,
+    lease_duration: chrono::Duration,
+) -> Result<Option<Lease>> {
+    // waiting for lock of lease shouldn't block async runtime
+    tokio::task::spawn_blocking(move || update_lease(&path, &instance_id, now, lease_duration))
+        .await
+        .context("Lease update is aborted")?
+}
+
+fn check_settings(settings: &FailoverSettings) -> Result<()> {
+    // trading is allowed only until `expire - renew_interval`, so shorter lease makes leader
+    // stop trading before the next renewal every cycle
+    ensure!(
+        settings.lease_duration_ms >= 2 * settings.renew_interval_ms,
+        "Failover lease_duration_ms ({}) should be at least twice as long as renew_interval_ms ({})",
+        settings.lease_duration_ms,
+        settings.renew_interval_ms
+    );
+    Ok(())
+}
+
+/// Starts engine instance in standby mode: trading is blocked until instance acquires lease.
+/// When standby instance takes over, it adopts open orders of previous leader by reconciliation
+pub(crate) fn start_leader_election(
+    settings: &FailoverSettings,
+    engine_context: Arc<EngineContext>,
+) {
+    for exchange in engine_context.exchanges.iter() {
+        engine_context.exchange_blocker.block(
+            exchange.exchange_account_id,
+            STANDBY,
+            BlockType::Manual,
+        );
+    }
+
+    let cancellation_token = engine_context.lifetime_manager.stop_token();
+    let _ = spawn_future(
+        "Leader election",
+        SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+        run_leader_election(settings.clone(), engine_context, cancellation_token),
+    );
+}
+
+async fn run_leader_election(
+    settings: FailoverSettings,
+    engine_context: Arc<EngineContext>,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    check_settings(&settings)?;
+
+    let leadership = engine_context.leadership.clone();
+    let lease_duration = chrono::Duration::milliseconds(settings.lease_duration_ms as i64);
+    let renew_interval = Duration::from_millis(settings.renew_interval_ms);
+    // leader stops trading one renew interval before its lease expires
+    let trading_margin = chrono::Duration::milliseconds(settings.renew_interval_ms as i64);
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(renew_interval) => {}
+            _ = cancellation_token.when_cancelled() => {
+                release_lease(&settings, &leadership).await;
+                return Ok(());
+            }
+        }
+
+        let previous_epoch = leadership.fencing_token();
+        let lease = update_lease_blocking(
+            settings.lease_file.clone(),
+            settings.instance_id.clone(),
+            time_manager::now(),
+            lease_duration,
+        )
+        .await
+        .unwrap_or_else(|error| {
+            log::error!("Failed to update leadership lease: {error:?}");
+            None
+        });
+
+        let lease = match lease {
+            Some(lease) => lease,
+            None => {
+                if previous_epoch.is_some() {
+                    log::error!(
+                        "Instance {} lost leadership, trading is stopped",
+                        settings.instance_id
+                    );
+                    leadership.reset();
+                    block_trading(&engine_context);
+                }
+                continue;
+            }
+        };
+
+        if previous_epoch == Some(lease.epoch) {
+            leadership.set_leader(lease.epoch, lease.expire_time - trading_margin);
+            continue;
+        }
+        // own lease expired before renewal, so it's acquired again with new fencing token and
+        // orders started under previous token are rejected
+        leadership.reset();
+
+        log::warn!(
+            "Instance {} became leader with fencing token {}",
+            settings.instance_id,
+            lease.epoch
+        );
+        leadership.set_leader(lease.epoch, lease.expire_time - trading_margin);
+        adopt_open_orders(&engine_context).await;
+        for exchange in engine_context.exchanges.iter() {
+            engine_context
+                .exchange_blocker
+                .unblock(exchange.exchange_account_id, STANDBY);
+        }
+    }
+}
+
+fn block_trading(engine_context: &EngineContext) {
+    for exchange in engine_context.exchanges.iter() {
+        engine_context.exchange_blocker.block(
+            exchange.exchange_account_id,
+            STANDBY,
+            BlockType::Manual,
+        );
+    }
+}
+
+/// Orders created by previous leader are added to orders pool to be managed by this instance
+async fn adopt_open_orders(engine_context: &EngineContext) {
+    let exchanges = engine_context
+        .exchanges
+        .iter()
+        .map(|x| x.clone())
+        .collect::<Vec<_>>();
+
+    let results = join_all(exchanges.iter().map(|x| x.get_open_orders(true))).await;
+    for (exchange, result) in exchanges.iter().zip(results) {
+        match result {
+            Ok(orders) => log::info!(
+                "Adopted {} open orders on {}",
+                orders.len(),
+                exchange.exchange_account_id
+            ),
+            Err(error) => log::error!(
+                "Failed to adopt open orders on {}: {error:?}",
+                exchange.exchange_account_id
+            ),
+        }
+    }
+}
+
+/// Expires lease of stopping leader, so standby instance takes over without waiting for lease expiration
+async fn release_lease(settings: &FailoverSettings, leadership: &Leadership) {
+    let epoch = match leadership.fencing_token() {
+        Some(epoch) => epoch,
+        None => return,
+    };
+    leadership.reset();
+
+    let path = settings.lease_file.clone();
+    let instance_id = settings.instance_id.clone();
+    let now = time_manager::now();
+    let released =
+        tokio::task::spawn_blocking(move || expire_lease(&path, &instance_id, epoch, now)).await;
+    match released {
+        Ok(Ok(())) => {}
+        Ok(Err(error)) => log::error!("Failed to release leadership lease: {error:?}"),
+        Err(error) => log::error!("Release of leadership lease is aborted: {error:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use mmb_utils::time::{advance_mock_now, set_mock_now};
+
+    fn lease(instance_id: &str, epoch: u64, expire_time: DateTime) -> Lease {
+        Lease {
+            instance_id: instance_id.to_owned(),
+            epoch,
+            expire_time,
+        }
+    }
+
+    #[test]
+    pub fn lease_is_taken_over_only_after_expiration() {
+        let now = Utc.ymd(2022, 10, 20).and_hms(12, 0, 0);
+        let duration = chrono::Duration::seconds(10);
+        let primary = lease("primary", 3, now + chrono::Duration::seconds(1));
+
+        assert_eq!(next_lease(Some(&primary), "standby", now, duration), None);
+
+        let renewed = next_lease(Some(&primary), "primary", now, duration).expect("in test");
+        assert_eq!(renewed.epoch, 3);
+        assert_eq!(renewed.expire_time, now + duration);
+
+        let expired = lease("primary", 3, now);
+        let taken = next_lease(Some(&expired), "standby", now, duration).expect("in test");
+        assert_eq!(taken.instance_id, "standby");
+        assert_eq!(taken.epoch, 4);
+    }
+
+    #[test]
+    pub fn lease_shorter_than_two_renew_intervals_is_rejected() {
+        let mut settings = FailoverSettings {
+            instance_id: "primary".to_owned(),
+            lease_file: PathBuf::from("lease.json"),
+            lease_duration_ms: 10_000,
+            renew_interval_ms: 5_000,
+        };
+        assert!(check_settings(&settings).is_ok());
+
+        settings.renew_interval_ms = 5_001;
+        assert!(check_settings(&settings).is_err());
+    }
+
+    #[test]
+    pub fn expired_own_lease_gets_new_epoch() {
+        let now = Utc.ymd(2022, 10, 20).and_hms(12, 0, 0);
+        let duration = chrono::Duration::seconds(10);
+
+        let own = lease("primary", 3, now - chrono::Duration::seconds(1));
+        let lease = next_lease(Some(&own), "primary", now, duration).expect("in test");
+        assert_eq!(lease.epoch, 4);
+        assert_eq!(
+            next_lease(None, "primary", now, duration).map(|x| x.epoch),
+            Some(1)
+        );
+    }
+
+    #[test]
+    pub fn instance_without_failover_is_always_leader() {
+        assert!(Leadership::default().is_leader());
+        assert!(Leadership::default().is_fencing_token_valid(None));
+        assert!(!Leadership::new(true).is_leader());
+    }
+
+    #[test]
+    pub fn stale_fencing_token_is_rejected() {
+        let time = Utc.ymd(2021, 9, 20).and_hms(0, 0, 0);
+        let _guard = set_mock_now(time);
+        let leadership = Leadership::new(true);
+        assert!(!leadership.is_fencing_token_valid(None));
+
+        let trading_allowed_until = time + chrono::Duration::seconds(10);
+        leadership.set_leader(3, trading_allowed_until);
+        let fencing_token = leadership.fencing_token();
+        assert!(leadership.is_fencing_token_valid(fencing_token));
+
+        // leadership is lost and acquired again with new epoch
+        leadership.reset();
+        assert!(!leadership.is_fencing_token_valid(fencing_token));
+        leadership.set_leader(4, trading_allowed_until);
+        assert!(!leadership.is_fencing_token_valid(fencing_token));
+        assert!(leadership.is_fencing_token_valid(Some(4)));
+
+        // lease isn't renewed in time
+        advance_mock_now(chrono::Duration::seconds(10));
+        assert!(!leadership.is_fencing_token_valid(Some(4)));
+    }
+
+    #[test]
+    pub fn lease_is_acquired_by_one_of_concurrent_instances() {
+        let path = std::env::temp_dir().join(format!("lease_{}.json", uuid::Uuid::new_v4()));
+        let now = Utc::now();
+        let duration = chrono::Duration::seconds(10);
+
+        let handles = (0..8)
+            .map(|index| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    update_lease(&path, &format!("instance_{index}"), now, duration)
+                        .expect("in test")
+                })
+            })
+            .collect::<Vec<_>>();
+        let acquired = handles
+            .into_iter()
+            .filter_map(|x| x.join().expect("in test"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(acquired.len(), 1);
+        let stored = read_lease(&path).expect("in test");
+        assert_eq!(stored.as_ref(), acquired.first());
+
+        expire_lease(&path, &acquired[0].instance_id, 1, now).expect("in test");
+        let taken = update_lease(&path, "standby", now, duration).expect("in test");
+        assert_eq!(taken.map(|x| x.epoch), Some(2));
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(path.with_extension("lock"));
+    }
+}
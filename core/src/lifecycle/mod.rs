@@ -1,4 +1,8 @@
 pub mod app_lifetime_manager;
 pub mod launcher;
+pub mod leader_election;
+pub mod session_summary;
 pub mod shutdown;
+pub mod state_transfer;
 pub mod trading_engine;
+pub mod warm_up;
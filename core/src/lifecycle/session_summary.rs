@@ -0,0 +1,317 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+use mmb_database::impl_event;
+use mmb_database::postgres_db::events::TableName;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::disposition_execution::rejection_analytics::RejectionReason;
+use crate::exchanges::common::{Amount, ExchangeAccountId, MarketAccountId};
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::connection_uptime::ConnectionUptimeSnapshot;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::misc::time::time_manager;
+use crate::statistic_service::MarketAccountIdStatistic;
+
+/// Count of the most frequent errors included in session summary
+const TOP_ERRORS_COUNT: usize = 5;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct MarketSessionSummary {
+    pub market_account_id: MarketAccountId,
+    pub placed_orders_count: u64,
+    pub canceled_orders_count: u64,
+    pub filled_orders_count: u64,
+    pub rejected_orders_count: u64,
+    /// Filled amount of completely filled orders
+    pub volume: Amount,
+    pub fees: Amount,
+    /// `None` if performance attribution isn't configured
+    pub pnl: Option<Amount>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct ConnectorUptime {
+    pub exchange_account_id: ExchangeAccountId,
+    pub connected_secs: u64,
+    /// Part of session when websocket connection was established
+    pub uptime: Percent,
+    pub disconnections_count: u64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct SessionError {
+    pub market_account_id: MarketAccountId,
+    pub reason: RejectionReason,
+    pub count: u64,
+}
+
+/// Statistics of trading session from engine start up to graceful shutdown
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct SessionSummary {
+    pub session_start: DateTime,
+    pub session_end: DateTime,
+    pub markets: Vec<MarketSessionSummary>,
+    pub connectors: Vec<ConnectorUptime>,
+    /// The most frequent reasons of rejected orders
+    pub top_errors: Vec<SessionError>,
+}
+
+impl_event!(&SessionSummary, "session_summaries");
+
+impl SessionSummary {
+    pub fn new(
+        session_start: DateTime,
+        session_end: DateTime,
+        statistics: &HashMap<MarketAccountId, MarketAccountIdStatistic>,
+        pnl: Option<&HashMap<MarketAccountId, Amount>>,
+        uptimes: &[(ExchangeAccountId, ConnectionUptimeSnapshot)],
+    ) -> Self {
+        let markets = statistics
+            .keys()
+            .chain(pnl.into_iter().flat_map(|x| x.keys()))
+            .unique()
+            .sorted_by_key(|x| format!("{x:?}"))
+            .map(|&market_account_id| {
+                let stats = statistics
+                    .get(&market_account_id)
+                    .cloned()
+                    .unwrap_or_default();
+
+                MarketSessionSummary {
+                    market_account_id,
+                    placed_orders_count: stats.opened_orders_count,
+                    canceled_orders_count: stats.canceled_orders_count,
+                    filled_orders_count: stats.fully_filled_orders_count,
+                    rejected_orders_count: stats.rejected_orders_count.values().sum(),
+                    volume: stats.summary_filled_amount,
+                    fees: stats.summary_commission,
+                    pnl: pnl.map(|x| x.get(&market_account_id).copied().unwrap_or_default()),
+                }
+            })
+            .collect();
+
+        let session_ms = (session_end - session_start).num_milliseconds().max(1);
+        let connectors = uptimes
+            .iter()
+            .map(|(exchange_account_id, uptime)| {
+                let connected_ms = Decimal::from(uptime.connected_duration.as_millis() as u64);
+                let uptime_percent = connected_ms / Decimal::from(session_ms) * dec!(100);
+
+                ConnectorUptime {
+                    exchange_account_id: *exchange_account_id,
+                    connected_secs: uptime.connected_duration.as_secs(),
+                    uptime: uptime_percent.min(dec!(100)).round_dp(2),
+                    disconnections_count: uptime.disconnections_count,
+                }
+            })
+            .collect();
+
+        let top_errors = statistics
+            .iter()
+            .flat_map(|(market_account_id, stats)| {
+                stats
+                    .rejected_orders_count
+                    .iter()
+                    .map(|(&reason, &count)| SessionError {
+                        market_account_id: *market_account_id,
+                        reason,
+                        count,
+                    })
+            })
+            .sorted_by_key(|x| {
+                (
+                    Reverse(x.count),
+                    format!("{:?} {:?}", x.market_account_id, x.reason),
+                )
+            })
+            .take(TOP_ERRORS_COUNT)
+            .collect();
+
+        SessionSummary {
+            session_start,
+            session_end,
+            markets,
+            connectors,
+            top_errors,
+        }
+    }
+
+    fn collect(engine_context: &EngineContext) -> Self {
+        let statistics = engine_context
+            .statistics
+            .statistic_service_state
+            .market_account_id_stats();
+
+        let pnl = engine_context.performance_attribution.as_ref().map(|x| {
+            let reports = x.reports();
+            let mut pnl = HashMap::<MarketAccountId, Amount>::new();
+            for report in reports.completed.iter().chain(&reports.current) {
+                *pnl.entry(report.market_account_id).or_default() += report.total;
+            }
+            pnl
+        });
+
+        let uptimes = engine_context
+            .exchanges
+            .iter()
+            .map(|x| (x.exchange_account_id, x.get_connection_uptime()))
+            .sorted_by_key(|(exchange_account_id, _)| exchange_account_id.to_string())
+            .collect_vec();
+
+        SessionSummary::new(
+            engine_context.session_start,
+            time_manager::now(),
+            &statistics,
+            pnl.as_ref(),
+            &uptimes,
+        )
+    }
+}
+
+impl Display for SessionSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Session from {} to {}",
+            self.session_start, self.session_end
+        )?;
+
+        for market in &self.markets {
+            let pnl = market
+                .pnl
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| "n/a".to_owned());
+            writeln!(
+                f,
+                "{:?}: placed {}, canceled {}, filled {}, rejected {} orders, volume {}, fees {}, PnL {pnl}",
+                market.market_account_id,
+                market.placed_orders_count,
+                market.canceled_orders_count,
+                market.filled_orders_count,
+                market.rejected_orders_count,
+                market.volume,
+                market.fees,
+            )?;
+        }
+
+        for connector in &self.connectors {
+            writeln!(
+                f,
+                "{}: uptime {}% ({} secs), disconnections {}",
+                connector.exchange_account_id,
+                connector.uptime,
+                connector.connected_secs,
+                connector.disconnections_count,
+            )?;
+        }
+
+        for error in &self.top_errors {
+            writeln!(
+                f,
+                "{:?}: {} orders rejected by {:?}",
+                error.market_account_id, error.count, error.reason
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Prints summary of trading session to log and saves it to database for history view
+pub(crate) fn report_session_summary(engine_context: &EngineContext) {
+    let session_summary = SessionSummary::collect(engine_context);
+    log::info!("Session summary:\n{session_summary}");
+
+    if let Err(error) = engine_context.event_recorder.save(&session_summary) {
+        log::error!("Failed to save session summary: {error:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+
+    #[test]
+    pub fn session_summary_by_statistics_and_uptime() {
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        let market_account_id = MarketAccountId::new(
+            exchange_account_id,
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        );
+        let stats = MarketAccountIdStatistic {
+            opened_orders_count: 10,
+            canceled_orders_count: 6,
+            fully_filled_orders_count: 3,
+            summary_filled_amount: dec!(1.5),
+            summary_commission: dec!(0.3),
+            rejected_orders_count: HashMap::from([
+                (RejectionReason::PriceFilter, 2),
+                (RejectionReason::RateLimit, 5),
+            ]),
+            ..Default::default()
+        };
+        let statistics = HashMap::from([(market_account_id, stats)]);
+        let pnl = HashMap::from([(market_account_id, dec!(12.5))]);
+        let uptime = ConnectionUptimeSnapshot {
+            connected_duration: Duration::from_secs(3240),
+            disconnections_count: 2,
+        };
+        let session_start = Utc.ymd(2022, 10, 25).and_hms(12, 0, 0);
+        let session_end = Utc.ymd(2022, 10, 25).and_hms(13, 0, 0);
+
+        let summary = SessionSummary::new(
+            session_start,
+            session_end,
+            &statistics,
+            Some(&pnl),
+            &[(exchange_account_id, uptime)],
+        );
+
+        assert_eq!(
+            summary.markets,
+            vec![MarketSessionSummary {
+                market_account_id,
+                placed_orders_count: 10,
+                canceled_orders_count: 6,
+                filled_orders_count: 3,
+                rejected_orders_count: 7,
+                volume: dec!(1.5),
+                fees: dec!(0.3),
+                pnl: Some(dec!(12.5)),
+            }]
+        );
+        assert_eq!(
+            summary.connectors,
+            vec![ConnectorUptime {
+                exchange_account_id,
+                connected_secs: 3240,
+                uptime: dec!(90),
+                disconnections_count: 2,
+            }]
+        );
+        assert_eq!(
+            summary.top_errors,
+            vec![
+                SessionError {
+                    market_account_id,
+                    reason: RejectionReason::RateLimit,
+                    count: 5,
+                },
+                SessionError {
+                    market_account_id,
+                    reason: RejectionReason::PriceFilter,
+                    count: 2,
+                },
+            ]
+        );
+    }
+}
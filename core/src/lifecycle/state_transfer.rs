@@ -0,0 +1,498 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use mmb_utils::DateTime;
+use parking_lot::RwLock;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::balance::capital_allocation::CapitalSubBalances;
+use crate::balance::manager::approved_part::ApprovedPart;
+use crate::balance::manager::balance_position_by_fill_amount::BalancePositionByFillAmount;
+use crate::balance::manager::balance_request::BalanceRequest;
+use crate::balance::manager::balance_reservation::BalanceReservation;
+use crate::balance::manager::balances::Balances;
+use crate::database::kv_store::KeyValueEntry;
+use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price};
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::misc::service_value_tree::ServiceValueTree;
+use crate::misc::time::time_manager;
+use crate::orders::conditional::ConditionalOrder;
+use crate::orders::good_till_date::ExpiringOrder;
+use crate::orders::order::{ClientOrderId, OrderSide, OrderSnapshot, ReservationId};
+use crate::service_configuration::configuration_descriptor::{
+    ConfigurationDescriptor, ServiceName,
+};
+
+/// Version of state format. State exported by engine with other version can't be imported
+const STATE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExchangeBalanceState {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    pub balance: Amount,
+}
+
+/// Value of balance tree for strategy configuration on market
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceValueState {
+    pub configuration_descriptor: ConfigurationDescriptor,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub currency_code: CurrencyCode,
+    pub value: Amount,
+}
+
+impl BalanceValueState {
+    fn from_tree(tree: &ServiceValueTree) -> Vec<Self> {
+        tree.get_as_balances()
+            .into_iter()
+            .map(|(request, value)| BalanceValueState {
+                configuration_descriptor: request.configuration_descriptor,
+                exchange_account_id: request.exchange_account_id,
+                currency_pair: request.currency_pair,
+                currency_code: request.currency_code,
+                value,
+            })
+            .collect()
+    }
+
+    fn to_tree(values: &[Self]) -> ServiceValueTree {
+        let mut tree = ServiceValueTree::default();
+        for x in values {
+            let request = BalanceRequest::new(
+                x.configuration_descriptor,
+                x.exchange_account_id,
+                x.currency_pair,
+                x.currency_code,
+            );
+            tree.set_by_balance_request(&request, x.value);
+        }
+        tree
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovedPartState {
+    pub client_order_id: ClientOrderId,
+    pub approve_time: DateTime,
+    pub amount: Amount,
+    pub is_canceled: bool,
+    pub unreserved_amount: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReservationState {
+    pub reservation_id: ReservationId,
+    pub configuration_descriptor: ConfigurationDescriptor,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub order_side: OrderSide,
+    pub price: Price,
+    pub amount: Amount,
+    pub taken_free_amount: Amount,
+    pub cost: Decimal,
+    pub reservation_currency_code: CurrencyCode,
+    pub unreserved_amount: Amount,
+    pub not_approved_amount: Amount,
+    pub approved_parts: Vec<ApprovedPartState>,
+}
+
+impl ReservationState {
+    fn new(reservation_id: ReservationId, reservation: &BalanceReservation) -> Self {
+        let approved_parts = reservation
+            .approved_parts
+            .values()
+            .map(|x| ApprovedPartState {
+                client_order_id: x.client_order_id().clone(),
+                approve_time: x.approve_time(),
+                amount: x.amount,
+                is_canceled: x.is_canceled,
+                unreserved_amount: x.unreserved_amount,
+            })
+            .collect();
+
+        ReservationState {
+            reservation_id,
+            configuration_descriptor: reservation.configuration_descriptor,
+            exchange_account_id: reservation.exchange_account_id,
+            currency_pair: reservation.symbol.currency_pair(),
+            order_side: reservation.order_side,
+            price: reservation.price,
+            amount: reservation.amount,
+            taken_free_amount: reservation.taken_free_amount,
+            cost: reservation.cost,
+            reservation_currency_code: reservation.reservation_currency_code,
+            unreserved_amount: reservation.unreserved_amount,
+            not_approved_amount: reservation.not_approved_amount,
+            approved_parts,
+        }
+    }
+
+    fn to_reservation(&self, engine_context: &EngineContext) -> Result<BalanceReservation> {
+        let symbol = engine_context
+            .exchanges
+            .get(&self.exchange_account_id)
+            .with_context(|| format!("Exchange {} isn't found", self.exchange_account_id))?
+            .symbols
+            .get(&self.currency_pair)
+            .map(|x| x.clone())
+            .with_context(|| {
+                format!(
+                    "Symbol {} isn't found on {}",
+                    self.currency_pair, self.exchange_account_id
+                )
+            })?;
+
+        let approved_parts = self
+            .approved_parts
+            .iter()
+            .map(|x| {
+                let mut approved_part =
+                    ApprovedPart::new(x.approve_time, x.client_order_id.clone(), x.amount);
+                approved_part.is_canceled = x.is_canceled;
+                approved_part.unreserved_amount = x.unreserved_amount;
+                (x.client_order_id.clone(), approved_part)
+            })
+            .collect();
+
+        Ok(BalanceReservation {
+            configuration_descriptor: self.configuration_descriptor,
+            exchange_account_id: self.exchange_account_id,
+            symbol,
+            order_side: self.order_side,
+            price: self.price,
+            amount: self.amount,
+            taken_free_amount: self.taken_free_amount,
+            cost: self.cost,
+            reservation_currency_code: self.reservation_currency_code,
+            unreserved_amount: self.unreserved_amount,
+            not_approved_amount: self.not_approved_amount,
+            approved_parts,
+        })
+    }
+}
+
+/// Position by filled amount in amount currency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionState {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub position: Decimal,
+}
+
+/// Sub-balance of strategy with allocated capital
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapitalSubBalanceState {
+    pub service_name: ServiceName,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    pub amount: Amount,
+}
+
+/// Portable state of engine which allows to move engine to another host without losing context:
+/// orders, balances, reservations and persistent data of strategies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineState {
+    pub version: u32,
+    pub export_time: DateTime,
+    pub orders: Vec<OrderSnapshot>,
+    pub exchange_balances: Vec<ExchangeBalanceState>,
+    pub virtual_balance_diffs: Vec<BalanceValueState>,
+    pub amount_limits: Vec<BalanceValueState>,
+    pub reservations: Vec<ReservationState>,
+    pub positions: Vec<PositionState>,
+    #[serde(default)]
+    pub capital_sub_balances: Vec<CapitalSubBalanceState>,
+    pub conditional_orders: Vec<ConditionalOrder>,
+    pub expiring_orders: Vec<ExpiringOrder>,
+    /// Values of strategies key-value store
+    #[serde(default)]
+    pub strategy_values: Vec<KeyValueEntry>,
+}
+
+pub async fn export_state(engine_context: &EngineContext) -> Result<EngineState> {
+    let strategy_values = engine_context
+        .kv_store
+        .export()
+        .await
+        .context("Unable to export strategies key-value store")?;
+
+    let orders = engine_context
+        .exchanges
+        .iter()
+        .flat_map(|exchange| {
+            exchange
+                .orders
+                .cache_by_client_id
+                .iter()
+                .map(|x| x.deep_clone())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let balances = engine_context.balance_manager.lock().get_balances();
+
+    let exchange_balances = balances
+        .balances_by_exchange_id
+        .iter()
+        .flatten()
+        .flat_map(|(&exchange_account_id, balances)| {
+            balances
+                .iter()
+                .map(move |(&currency_code, &balance)| ExchangeBalanceState {
+                    exchange_account_id,
+                    currency_code,
+                    balance,
+                })
+        })
+        .collect();
+
+    let reservations = balances
+        .balance_reservations_by_reservation_id
+        .iter()
+        .flatten()
+        .map(|(&reservation_id, reservation)| ReservationState::new(reservation_id, reservation))
+        .collect();
+
+    let positions = balances
+        .position_by_fill_amount
+        .iter()
+        .flat_map(|x| x.get_all())
+        .map(|(market_account_id, &position)| PositionState {
+            exchange_account_id: market_account_id.exchange_account_id,
+            currency_pair: market_account_id.currency_pair,
+            position,
+        })
+        .collect();
+
+    let capital_sub_balances = balances
+        .capital_sub_balances
+        .iter()
+        .flatten()
+        .flat_map(|(&(service_name, exchange_account_id), balances)| {
+            balances
+                .iter()
+                .map(move |(&currency_code, &amount)| CapitalSubBalanceState {
+                    service_name,
+                    exchange_account_id,
+                    currency_code,
+                    amount,
+                })
+        })
+        .collect();
+
+    Ok(EngineState {
+        version: STATE_VERSION,
+        export_time: time_manager::now(),
+        orders,
+        exchange_balances,
+        virtual_balance_diffs: balances
+            .virtual_diff_balances
+            .as_ref()
+            .map(BalanceValueState::from_tree)
+            .unwrap_or_default(),
+        amount_limits: balances
+            .amount_limits
+            .as_ref()
+            .map(BalanceValueState::from_tree)
+            .unwrap_or_default(),
+        reservations,
+        positions,
+        capital_sub_balances,
+        conditional_orders: engine_context.conditional_orders.get_all(),
+        expiring_orders: engine_context.good_till_date.get_all(),
+        strategy_values,
+    })
+}
+
+/// Restores state exported on another host. State should be imported before trading starts,
+/// so it's rejected when engine already has orders or balance reservations or trading isn't blocked
+/// on any exchange (e.g. engine isn't in standby)
+pub async fn import_state(engine_context: &EngineContext, state: EngineState) -> Result<()> {
+    if state.version != STATE_VERSION {
+        bail!(
+            "Unsupported state version {}, expected version is {STATE_VERSION}",
+            state.version
+        );
+    }
+
+    for exchange in engine_context.exchanges.iter() {
+        let exchange_account_id = *exchange.key();
+        if !engine_context
+            .exchange_blocker
+            .is_blocked(exchange_account_id)
+        {
+            bail!("State can't be imported because trading isn't blocked on {exchange_account_id}");
+        }
+        if !exchange.orders.cache_by_client_id.is_empty() {
+            bail!("State can't be imported because engine already has orders on {exchange_account_id}");
+        }
+    }
+    if !engine_context
+        .balance_manager
+        .lock()
+        .get_reservation_ids()
+        .is_empty()
+    {
+        bail!("State can't be imported because engine already has balance reservations");
+    }
+
+    // all parts of state are checked before applying, so invalid state doesn't change engine
+    for order in &state.orders {
+        let exchange_account_id = order.header.exchange_account_id;
+        if !engine_context.exchanges.contains_key(&exchange_account_id) {
+            bail!(
+                "Exchange {exchange_account_id} of order {} isn't found",
+                order.header.client_order_id
+            );
+        }
+    }
+
+    let reservations = state
+        .reservations
+        .iter()
+        .map(|x| Ok((x.reservation_id, x.to_reservation(engine_context)?)))
+        .collect::<Result<HashMap<_, _>>>()?;
+
+    // the only fallible step of applying, so it goes first
+    engine_context
+        .kv_store
+        .import(state.strategy_values)
+        .await
+        .context("Unable to import strategies key-value store")?;
+
+    let mut balances_by_exchange_id = HashMap::<_, HashMap<_, _>>::new();
+    for x in &state.exchange_balances {
+        let _ = balances_by_exchange_id
+            .entry(x.exchange_account_id)
+            .or_default()
+            .insert(x.currency_code, x.balance);
+    }
+
+    let now = time_manager::now();
+    let mut position_by_fill_amount = BalancePositionByFillAmount::default();
+    for x in &state.positions {
+        position_by_fill_amount.set(
+            x.exchange_account_id,
+            x.currency_pair,
+            None,
+            x.position,
+            None,
+            now,
+        );
+    }
+
+    let mut capital_sub_balances = CapitalSubBalances::new();
+    for x in &state.capital_sub_balances {
+        let _ = capital_sub_balances
+            .entry((x.service_name, x.exchange_account_id))
+            .or_default()
+            .insert(x.currency_code, x.amount);
+    }
+
+    let mut balances = Balances::new(
+        balances_by_exchange_id,
+        state.export_time,
+        BalanceValueState::to_tree(&state.virtual_balance_diffs),
+        ServiceValueTree::default(),
+        position_by_fill_amount,
+        BalanceValueState::to_tree(&state.amount_limits),
+        reservations,
+    );
+    balances.capital_sub_balances = Some(capital_sub_balances);
+    // reservations created after import shouldn't reuse imported ids
+    if let Some(max_reservation_id) = state.reservations.iter().map(|x| x.reservation_id).max() {
+        ReservationId::advance_past(max_reservation_id);
+    }
+    // exchange balances are refreshed by the next balance update from exchange
+    engine_context
+        .balance_manager
+        .lock()
+        .restore_balance_state(&balances, true);
+
+    let mut imported_orders_count = 0;
+    for order in state.orders {
+        let exchange = match engine_context
+            .exchanges
+            .get(&order.header.exchange_account_id)
+        {
+            Some(exchange) => exchange.clone(),
+            None => continue,
+        };
+        let exchange_order_id = order.exchange_order_id();
+        let is_finished = order.is_finished();
+        let order_ref = exchange
+            .orders
+            .add_snapshot_initial(Arc::new(RwLock::new(order)));
+        if is_finished {
+            let _ = exchange.orders.not_finished.remove(&order_ref);
+        }
+        if let Some(exchange_order_id) = exchange_order_id {
+            let _ = exchange
+                .orders
+                .cache_by_exchange_id
+                .insert(exchange_order_id, order_ref);
+        }
+        imported_orders_count += 1;
+    }
+
+    for order in state.conditional_orders {
+        let id = order.id;
+        if let Err(err) = engine_context.conditional_orders.submit(order) {
+            log::error!("Failed to import conditional order {id}: {err:?}");
+        }
+    }
+
+    for order in state.expiring_orders {
+        engine_context.good_till_date.schedule(order);
+    }
+
+    log::info!(
+        "Engine state exported at {} is imported: {imported_orders_count} orders, {} reservations",
+        state.export_time,
+        state.reservations.len()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service_configuration::configuration_descriptor::{
+        ServiceConfigurationKey, ServiceName,
+    };
+    use rust_decimal_macros::dec;
+
+    #[test]
+    pub fn balance_tree_survives_export() {
+        let value = BalanceValueState {
+            configuration_descriptor: ConfigurationDescriptor::new(
+                ServiceName::new("test_strategy"),
+                ServiceConfigurationKey::new("BTC/USDT"),
+            ),
+            exchange_account_id: ExchangeAccountId::new("Binance", 0),
+            currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            currency_code: "btc".into(),
+            value: dec!(1.5),
+        };
+
+        let json = serde_json::to_string(std::slice::from_ref(&value)).expect("in test");
+        let values: Vec<BalanceValueState> = serde_json::from_str(&json).expect("in test");
+        let tree = BalanceValueState::to_tree(&values);
+
+        assert_eq!(BalanceValueState::from_tree(&tree), vec![value]);
+    }
+
+    #[test]
+    pub fn reservation_ids_are_generated_after_imported_ids() {
+        let imported_id = ReservationId::from_u64(ReservationId::generate().as_u64() + 1_000_000);
+
+        ReservationId::advance_past(imported_id);
+
+        assert!(ReservationId::generate() > imported_id);
+    }
+}
@@ -6,7 +6,7 @@ use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use dashmap::DashMap;
 use futures::future::join_all;
 use mmb_utils::cancellation_token::CancellationToken;
@@ -14,7 +14,12 @@ use tokio::sync::{broadcast, oneshot};
 use tokio::time::{timeout, Duration};
 
 use crate::balance::manager::balance_manager::BalanceManager;
+use crate::database::audit_log::AuditLog;
 use crate::database::events::recorder::EventRecorder;
+use crate::database::events::transaction::TransactionsService;
+use crate::database::kv_store::KeyValueStore;
+use crate::database::retention::EventRetention;
+use crate::exchanges::account_groups::AccountGroups;
 use crate::exchanges::block_reasons;
 use crate::exchanges::common::ExchangeAccountId;
 use crate::exchanges::events::{ExchangeEvent, ExchangeEvents};
@@ -22,12 +27,37 @@ use crate::exchanges::exchange_blocker::BlockType;
 use crate::exchanges::exchange_blocker::ExchangeBlocker;
 use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
+use crate::lifecycle::leader_election::Leadership;
+use crate::lifecycle::session_summary::report_session_summary;
 use crate::lifecycle::shutdown::ShutdownService;
+use crate::lifecycle::warm_up::WarmUp;
+use crate::misc::time::time_manager;
+use crate::order_book::event::BestPriceChangedEvent;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::conditional::ConditionalOrdersManager;
+use crate::orders::good_till_date::GoodTillDateScheduler;
+use crate::orders::internalization::InternalCrossingEngine;
+use crate::orders::market_rollout::MarketRollout;
+use crate::orders::reduce_only::ReduceOnlyMode;
+use crate::orders::trailing_stop::TrailingStopManager;
+use crate::services::candles::CandlesService;
+use crate::services::funding_rates::FundingRatesService;
+use crate::services::hedging::InventoryHedger;
+use crate::services::index_price::IndexPriceService;
+use crate::services::news_restrictions::NewsRestrictionsService;
+use crate::services::performance_attribution::PerformanceAttributionService;
+use crate::services::spread_execution::SpreadExecutor;
+use crate::services::triangular_arbitrage::TriangularArbitrageService;
+use crate::services::value_at_risk::ValueAtRiskService;
 use crate::settings::CoreSettings;
+use crate::statistic_service::StatisticService;
+use crate::strategies::desired_amounts::DesiredAmounts;
+use crate::treasury::rebalancing::RebalancingPlanner;
+use crate::treasury::withdrawals::WithdrawalsService;
 use crate::{
     infrastructure::unset_lifetime_manager, lifecycle::app_lifetime_manager::AppLifetimeManager,
 };
-use mmb_utils::nothing_to_do;
+use mmb_utils::{nothing_to_do, DateTime};
 use parking_lot::Mutex;
 
 use super::app_lifetime_manager::ActionAfterGracefulShutdown;
@@ -45,12 +75,41 @@ pub trait Service: Send + Sync + 'static {
 pub struct EngineContext {
     pub core_settings: CoreSettings,
     pub exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    /// Order books of all markets maintained by internal events loop
+    pub local_snapshots: Arc<Mutex<LocalSnapshotsService>>,
     pub shutdown_service: Arc<ShutdownService>,
     pub exchange_blocker: Arc<ExchangeBlocker>,
     pub lifetime_manager: Arc<AppLifetimeManager>,
     pub timeout_manager: Arc<TimeoutManager>,
     pub balance_manager: Arc<Mutex<BalanceManager>>,
     pub event_recorder: Arc<EventRecorder>,
+    pub audit_log: Option<Arc<AuditLog>>,
+    pub kv_store: Arc<KeyValueStore>,
+    pub event_retention: Option<Arc<EventRetention>>,
+    pub transactions: Arc<TransactionsService>,
+    pub withdrawals: Arc<WithdrawalsService>,
+    pub rebalancing: Option<Arc<RebalancingPlanner>>,
+    pub account_groups: Arc<AccountGroups>,
+    pub triangular_arbitrage: Option<Arc<TriangularArbitrageService>>,
+    pub index_prices: Option<Arc<IndexPriceService>>,
+    pub candles: Option<Arc<CandlesService>>,
+    pub value_at_risk: Option<Arc<ValueAtRiskService>>,
+    pub news_restrictions: Option<Arc<NewsRestrictionsService>>,
+    pub funding_rates: Option<Arc<FundingRatesService>>,
+    pub hedging: Option<Arc<InventoryHedger>>,
+    pub good_till_date: Arc<GoodTillDateScheduler>,
+    pub trailing_stops: Arc<TrailingStopManager>,
+    pub conditional_orders: Arc<ConditionalOrdersManager>,
+    pub reduce_only: Arc<ReduceOnlyMode>,
+    pub market_rollout: Arc<MarketRollout>,
+    pub desired_amounts: Arc<DesiredAmounts>,
+    pub spread_executor: Arc<SpreadExecutor>,
+    pub performance_attribution: Option<Arc<PerformanceAttributionService>>,
+    pub internal_crossing: Option<Arc<InternalCrossingEngine>>,
+    pub leadership: Arc<Leadership>,
+    pub warm_up: Arc<WarmUp>,
+    pub statistics: Arc<StatisticService>,
+    pub session_start: DateTime,
     is_graceful_shutdown_started: AtomicBool,
     exchange_events: ExchangeEvents,
     finish_graceful_shutdown_sender: Mutex<Option<oneshot::Sender<ActionAfterGracefulShutdown>>>,
@@ -68,16 +127,223 @@ impl EngineContext {
         lifetime_manager: Arc<AppLifetimeManager>,
         balance_manager: Arc<Mutex<BalanceManager>>,
         event_recorder: Arc<EventRecorder>,
-    ) -> Arc<Self> {
+        kv_store: Arc<KeyValueStore>,
+        event_retention: Option<Arc<EventRetention>>,
+    ) -> Result<Arc<Self>> {
+        let withdrawal_settings = core_settings
+            .exchanges
+            .iter()
+            .filter_map(|x| Some((x.exchange_account_id, x.withdrawals.clone()?)))
+            .collect();
+        let withdrawals = WithdrawalsService::new(
+            withdrawal_settings,
+            core_settings.withdrawal_approvals.as_ref(),
+            event_recorder.clone(),
+        )
+        .context("Unable to start withdrawals service")?;
+        let rebalancing = match core_settings.rebalancing.clone() {
+            Some(settings) => Some(
+                RebalancingPlanner::new(settings, withdrawals.clone(), event_recorder.clone())
+                    .context("Invalid rebalancing settings")?,
+            ),
+            None => None,
+        };
+
+        let account_groups = AccountGroups::new(
+            &core_settings.account_groups,
+            exchanges.clone(),
+            timeout_manager.clone(),
+        )
+        .context("Invalid account groups settings")?;
+
+        let triangular_arbitrage = match &core_settings.triangular_arbitrage {
+            Some(settings) => Some(
+                TriangularArbitrageService::start(
+                    settings,
+                    &exchanges,
+                    exchange_blocker.clone(),
+                    exchange_events.get_events_channel(),
+                    lifetime_manager.stop_token(),
+                )
+                .context("Invalid triangular arbitrage settings")?,
+            ),
+            None => None,
+        };
+
+        let index_prices = core_settings.index_prices.clone().map(|settings| {
+            IndexPriceService::start(
+                settings,
+                exchange_events.get_events_channel(),
+                lifetime_manager.stop_token(),
+            )
+        });
+
+        let candles = match &core_settings.candles {
+            Some(settings) => Some(
+                CandlesService::start(
+                    settings,
+                    exchange_events.get_events_channel(),
+                    lifetime_manager.stop_token(),
+                )
+                .context("Invalid candles settings")?,
+            ),
+            None => None,
+        };
+
+        let value_at_risk = match &core_settings.value_at_risk {
+            Some(settings) => {
+                let index_prices = index_prices
+                    .clone()
+                    .context("Value at risk requires index prices settings")?;
+                let value_at_risk = ValueAtRiskService::start(
+                    settings,
+                    index_prices,
+                    balance_manager.clone(),
+                    exchanges.clone(),
+                    &kv_store,
+                    lifetime_manager.stop_token(),
+                )
+                .context("Invalid value at risk settings")?;
+                Some(value_at_risk)
+            }
+            None => None,
+        };
+
+        let news_restrictions = core_settings.news_restrictions.as_ref().map(|settings| {
+            NewsRestrictionsService::start(
+                settings,
+                exchanges.clone(),
+                lifetime_manager.stop_token(),
+            )
+        });
+
+        let funding_rates = core_settings.funding_rates.as_ref().map(|settings| {
+            FundingRatesService::start(settings, exchanges.clone(), lifetime_manager.stop_token())
+        });
+
+        let hedging = core_settings.hedging.as_ref().map(|settings| {
+            InventoryHedger::new(settings, exchanges.clone(), lifetime_manager.stop_token())
+        });
+
+        let good_till_date = GoodTillDateScheduler::start(
+            core_settings.good_till_date.as_ref(),
+            exchanges.clone(),
+            exchange_events.get_events_channel(),
+            lifetime_manager.stop_token(),
+        )
+        .context("Unable to start good-till-date scheduler")?;
+
+        let trailing_stops = TrailingStopManager::start(
+            exchanges.clone(),
+            exchange_events.get_events_channel(),
+            lifetime_manager.stop_token(),
+        );
+
+        let conditional_orders = ConditionalOrdersManager::start(
+            core_settings.conditional_orders.as_ref(),
+            exchanges.clone(),
+            exchange_events.get_events_channel(),
+            lifetime_manager.stop_token(),
+        )
+        .context("Unable to start conditional orders manager")?;
+
+        let spread_executor = SpreadExecutor::new(exchanges.clone(), lifetime_manager.stop_token());
+
+        let performance_attribution =
+            core_settings
+                .performance_attribution
+                .as_ref()
+                .map(|settings| {
+                    PerformanceAttributionService::start(
+                        settings,
+                        exchanges.clone(),
+                        event_recorder.clone(),
+                        exchange_events.get_events_channel(),
+                        lifetime_manager.stop_token(),
+                    )
+                });
+
+        let internal_crossing = core_settings
+            .internalization
+            .as_ref()
+            .map(|settings| InternalCrossingEngine::new(settings, exchanges.clone()));
+
+        let audit_log = match &core_settings.audit_log {
+            Some(settings) => {
+                let audit_log = AuditLog::open(settings, event_recorder.clone())
+                    .context("Unable to open audit log")?;
+                audit_log.start(exchange_events.get_events_channel());
+                balance_manager.lock().set_audit_log(audit_log.clone());
+                Some(audit_log)
+            }
+            None => None,
+        };
+
+        let market_rollout = MarketRollout::new(
+            &core_settings.market_rollouts,
+            performance_attribution.clone(),
+        )
+        .context("Invalid market rollout settings")?;
+        market_rollout.clone().start(
+            exchange_events.get_events_channel(),
+            lifetime_manager.stop_token(),
+        );
+
+        let local_snapshots = Arc::new(Mutex::new(LocalSnapshotsService::default()));
+        let reduce_only = Arc::new(ReduceOnlyMode::default());
+        let leadership = Leadership::new(core_settings.failover.is_some());
+        let warm_up = WarmUp::new(core_settings.warm_up.is_some());
+        for exchange in exchanges.iter() {
+            exchange.setup_local_snapshots(local_snapshots.clone());
+            exchange.setup_good_till_date(good_till_date.clone());
+            exchange.setup_reduce_only_mode(reduce_only.clone());
+            exchange.setup_market_rollout(market_rollout.clone());
+            exchange.setup_leadership(leadership.clone());
+            if let Some(index_prices) = &index_prices {
+                exchange.setup_index_prices(index_prices.clone());
+            }
+            if let Some(value_at_risk) = value_at_risk.as_ref().filter(|x| x.is_limit_enabled()) {
+                exchange.setup_value_at_risk(value_at_risk.clone());
+            }
+        }
+
         let engine_context = Arc::new(EngineContext {
             core_settings,
             exchanges,
+            local_snapshots,
             shutdown_service: Default::default(),
             exchange_blocker,
             lifetime_manager: lifetime_manager.clone(),
             timeout_manager,
             balance_manager,
+            transactions: TransactionsService::new(event_recorder.clone()),
+            withdrawals,
+            rebalancing,
+            account_groups,
+            triangular_arbitrage,
+            index_prices,
+            candles,
+            value_at_risk,
+            news_restrictions,
+            funding_rates,
+            hedging,
+            good_till_date,
+            trailing_stops,
+            conditional_orders,
+            reduce_only,
+            market_rollout,
+            desired_amounts: Default::default(),
+            spread_executor,
+            performance_attribution,
+            internal_crossing,
+            leadership,
+            warm_up,
+            statistics: StatisticService::new(),
+            session_start: time_manager::now(),
             event_recorder,
+            audit_log,
+            kv_store,
+            event_retention,
             is_graceful_shutdown_started: Default::default(),
             exchange_events,
             finish_graceful_shutdown_sender: Mutex::new(Some(finish_graceful_shutdown_sender)),
@@ -85,7 +351,7 @@ impl EngineContext {
 
         lifetime_manager.setup_engine_context(engine_context.clone());
 
-        engine_context
+        Ok(engine_context)
     }
 
     pub(crate) async fn graceful_shutdown(
@@ -137,6 +403,8 @@ impl EngineContext {
 
         self.shutdown_service.core_lvl_shutdown().await;
 
+        report_session_summary(&self);
+
         match timeout(Duration::from_secs(5), self.event_recorder.flush_and_stop()).await {
             Err(_) => log::error!("In graceful shutdown EventRecorder::flush_and_stop() was not finished during 5 seconds"),
             Ok(Err(err)) => log::error!("In graceful shutdown error from EventRecorder::flush_and_stop(): {err:?}"),
@@ -167,6 +435,67 @@ impl EngineContext {
     pub fn get_events_channel(&self) -> broadcast::Receiver<ExchangeEvent> {
         self.exchange_events.get_events_channel()
     }
+
+    /// Best price changes of all markets by order books maintained by internal events loop.
+    /// All subscribers share one `LocalSnapshotsService`, so order books aren't rebuilt per subscriber
+    pub fn subscribe_best_price_changes(&self) -> broadcast::Receiver<BestPriceChangedEvent> {
+        self.local_snapshots.lock().subscribe_best_price_changes()
+    }
+
+    /// Returns reasons why engine isn't ready to trade: some exchange isn't connected,
+    /// order books aren't received yet or balances aren't loaded. Empty list means engine is ready
+    pub fn get_readiness_problems(&self) -> Vec<String> {
+        if self.is_graceful_shutdown_started.load(Ordering::SeqCst) {
+            return vec!["Graceful shutdown is started".to_owned()];
+        }
+
+        let mut problems = vec![];
+        for exchange_settings in &self.core_settings.exchanges {
+            let exchange_account_id = exchange_settings.exchange_account_id;
+            let exchange = match self.exchanges.get(&exchange_account_id) {
+                None => {
+                    problems.push(format!("Exchange {exchange_account_id} is not created"));
+                    continue;
+                }
+                Some(v) => v.clone(),
+            };
+
+            if self
+                .exchange_blocker
+                .is_blocked_by_reason(exchange_account_id, block_reasons::WEBSOCKET_DISCONNECTED)
+            {
+                problems.push(format!("Exchange {exchange_account_id} is not connected"));
+            }
+
+            if exchange_settings.subscribe_to_market_data {
+                for symbol in exchange.symbols.iter() {
+                    let currency_pair = symbol.currency_pair();
+                    if !exchange.order_book_top.contains_key(&currency_pair) {
+                        problems.push(format!(
+                            "Order book {currency_pair} on {exchange_account_id} is not received yet"
+                        ));
+                    } else if exchange.is_market_stale(currency_pair) {
+                        problems.push(format!(
+                            "Order book {currency_pair} on {exchange_account_id} is stale"
+                        ));
+                    }
+                }
+            }
+
+            if !self.core_settings.market_data_only
+                && !self
+                    .balance_manager
+                    .lock()
+                    .balance_was_received(exchange_account_id)
+            {
+                problems.push(format!(
+                    "Balances for {exchange_account_id} are not loaded yet"
+                ));
+            }
+        }
+
+        problems
+    }
 }
 
 async fn cancel_opened_orders(
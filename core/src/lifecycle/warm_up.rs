@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use itertools::Itertools;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::common::{ExchangeAccountId, MarketAccountId};
+use crate::exchanges::events::ExchangeEvent;
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::settings::WarmUpSettings;
+
+const READINESS_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize)]
+pub enum WarmUpDependency {
+    /// Order book of market has both sides and was updated after start
+    OrderBook(MarketAccountId),
+    Balances(ExchangeAccountId),
+    /// Difference between local clock and exchange time of trades is within allowed skew
+    ClockSync(ExchangeAccountId),
+}
+
+impl Display for WarmUpDependency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WarmUpDependency::OrderBook(market_account_id) => {
+                write!(f, "order book {market_account_id:?}")
+            }
+            WarmUpDependency::Balances(exchange_account_id) => {
+                write!(f, "balances {exchange_account_id}")
+            }
+            WarmUpDependency::ClockSync(exchange_account_id) => {
+                write!(f, "clock sync {exchange_account_id}")
+            }
+        }
+    }
+}
+
+/// Warm-up phase of engine: strategies aren't activated until all their dependencies are ready,
+/// so they don't quote on empty order books right after start. Without warm-up settings engine
+/// is considered warmed up immediately
+#[derive(Debug)]
+pub struct WarmUp {
+    is_completed: AtomicBool,
+    readiness: Mutex<HashMap<WarmUpDependency, bool>>,
+}
+
+impl WarmUp {
+    pub fn new(is_enabled: bool) -> Arc<Self> {
+        Arc::new(WarmUp {
+            is_completed: AtomicBool::new(!is_enabled),
+            readiness: Default::default(),
+        })
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.is_completed.load(Ordering::SeqCst)
+    }
+
+    /// Readiness of every dependency of warm-up
+    pub fn readiness(&self) -> Vec<(WarmUpDependency, bool)> {
+        self.readiness
+            .lock()
+            .iter()
+            .map(|(dependency, is_ready)| (*dependency, *is_ready))
+            .sorted_by_key(|(dependency, _)| dependency.to_string())
+            .collect()
+    }
+
+    fn register(&self, dependency: WarmUpDependency) {
+        let _ = self.readiness.lock().insert(dependency, false);
+    }
+
+    /// Only registered dependencies are updated
+    fn set_ready(&self, dependency: WarmUpDependency, is_ready: bool) {
+        if let Some(readiness) = self.readiness.lock().get_mut(&dependency) {
+            *readiness = is_ready;
+        }
+    }
+
+    fn not_ready(&self) -> Vec<WarmUpDependency> {
+        self.readiness()
+            .into_iter()
+            .filter(|(_, is_ready)| !is_ready)
+            .map(|(dependency, _)| dependency)
+            .collect()
+    }
+
+    fn complete(&self) {
+        self.is_completed.store(true, Ordering::SeqCst);
+    }
+}
+
+pub(crate) fn start_warm_up(
+    settings: &WarmUpSettings,
+    engine_context: Arc<EngineContext>,
+    markets: Vec<MarketAccountId>,
+    events_receiver: broadcast::Receiver<ExchangeEvent>,
+) {
+    let warm_up = engine_context.warm_up.clone();
+    for market_account_id in &markets {
+        warm_up.register(WarmUpDependency::OrderBook(*market_account_id));
+    }
+    for exchange_account_id in markets.iter().map(|x| x.exchange_account_id).unique() {
+        warm_up.register(WarmUpDependency::Balances(exchange_account_id));
+        if settings.max_clock_skew_ms.is_some() {
+            warm_up.register(WarmUpDependency::ClockSync(exchange_account_id));
+        }
+    }
+
+    let _ = spawn_future(
+        "Warm-up",
+        SpawnFutureFlags::STOP_BY_TOKEN,
+        run_warm_up(settings.clone(), engine_context, events_receiver),
+    );
+}
+
+async fn run_warm_up(
+    settings: WarmUpSettings,
+    engine_context: Arc<EngineContext>,
+    mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+) -> Result<()> {
+    let warm_up = engine_context.warm_up.clone();
+    let stop_token = engine_context.lifetime_manager.stop_token();
+
+    let timeout = tokio::time::sleep(Duration::from_millis(settings.timeout_ms));
+    tokio::pin!(timeout);
+    let mut check_interval = tokio::time::interval(READINESS_CHECK_PERIOD);
+
+    loop {
+        tokio::select! {
+            event = events_receiver.recv() => {
+                match event {
+                    Ok(ExchangeEvent::OrderBookEvent(event)) => {
+                        let market_account_id =
+                            MarketAccountId::new(event.exchange_account_id, event.currency_pair);
+                        warm_up.set_ready(
+                            WarmUpDependency::OrderBook(market_account_id),
+                            has_both_sides(&engine_context, market_account_id),
+                        );
+                    }
+                    Ok(ExchangeEvent::Trades(event)) => {
+                        // exchange time of trade is compared with receipt time to detect clock skew
+                        if let (Some(max_clock_skew_ms), Some(trade)) =
+                            (settings.max_clock_skew_ms, event.trades.last())
+                        {
+                            let skew = event.receipt_time - trade.transaction_time;
+                            warm_up.set_ready(
+                                WarmUpDependency::ClockSync(event.exchange_account_id),
+                                skew.num_milliseconds().unsigned_abs() <= max_clock_skew_ms,
+                            );
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Warm-up skipped {skipped} exchange events")
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                }
+            }
+            _ = check_interval.tick() => {
+                for (dependency, _) in warm_up.readiness() {
+                    if let WarmUpDependency::Balances(exchange_account_id) = dependency {
+                        let is_received = engine_context
+                            .balance_manager
+                            .lock()
+                            .balance_was_received(exchange_account_id);
+                        warm_up.set_ready(dependency, is_received);
+                    }
+                }
+
+                if warm_up.not_ready().is_empty() {
+                    warm_up.complete();
+                    log::info!("Warm-up is completed, strategies are activated");
+                    return Ok(());
+                }
+            }
+            _ = &mut timeout => {
+                let not_ready = warm_up.not_ready().iter().join(", ");
+                if settings.activate_on_timeout {
+                    log::warn!(
+                        "Warm-up timed out, strategies are activated with not ready dependencies: {not_ready}"
+                    );
+                    warm_up.complete();
+                } else {
+                    let reason =
+                        format!("Warm-up timed out with not ready dependencies: {not_ready}");
+                    log::error!("{reason}");
+                    let _ = engine_context.lifetime_manager.spawn_graceful_shutdown(&reason);
+                }
+                return Ok(());
+            }
+            _ = stop_token.when_cancelled() => return Ok(()),
+        }
+    }
+}
+
+fn has_both_sides(engine_context: &EngineContext, market_account_id: MarketAccountId) -> bool {
+    engine_context
+        .exchanges
+        .get(&market_account_id.exchange_account_id)
+        .and_then(|exchange| {
+            exchange
+                .order_book_top
+                .get(&market_account_id.currency_pair)
+                .map(|top| top.ask.is_some() && top.bid.is_some())
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+
+    #[test]
+    pub fn warm_up_without_settings_is_completed() {
+        assert!(WarmUp::new(false).is_completed());
+        assert!(!WarmUp::new(true).is_completed());
+    }
+
+    #[test]
+    pub fn readiness_of_registered_dependencies() {
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        let market = MarketAccountId::new(
+            exchange_account_id,
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        );
+        let warm_up = WarmUp::new(true);
+        warm_up.register(WarmUpDependency::OrderBook(market));
+        warm_up.register(WarmUpDependency::Balances(exchange_account_id));
+
+        warm_up.set_ready(WarmUpDependency::OrderBook(market), true);
+        // not registered dependency is ignored
+        warm_up.set_ready(WarmUpDependency::ClockSync(exchange_account_id), true);
+
+        assert_eq!(
+            warm_up.not_ready(),
+            vec![WarmUpDependency::Balances(exchange_account_id)]
+        );
+
+        warm_up.set_ready(WarmUpDependency::Balances(exchange_account_id), true);
+        assert!(warm_up.not_ready().is_empty());
+        assert_eq!(warm_up.readiness().len(), 2);
+    }
+}
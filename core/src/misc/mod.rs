@@ -4,5 +4,6 @@ pub mod price_by_order_side;
 pub(crate) mod price_source_model;
 pub mod reserve_parameters;
 pub(crate) mod service_value_tree;
+pub(crate) mod state_file;
 pub mod time;
 pub mod traits;
@@ -8,6 +8,7 @@ use crate::service_configuration::configuration_descriptor::{
 
 use mmb_utils::hashmap;
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
 
 pub(crate) type ConfigurationKeyByServiceName =
     HashMap<ServiceName, ExchangeAccountIdByConfigurationKey>;
@@ -23,7 +24,9 @@ pub(crate) type ValueByCurrencyCode = HashMap<CurrencyCode, Amount>;
 ///     NOTE: there is storing all balances by ServiceNames(strategy name),
 ///     that will contain several configuration keys for strategies, next layer is one or more accounts for
 ///     selected ServiceName and here stored CurrencyCodes by CurrencyPairs and amount for every currency code.
-#[derive(Debug, Default, Clone)]
+/// Tree is serialized as nested json objects with the same layers.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct ServiceValueTree {
     tree: ConfigurationKeyByServiceName,
 }
@@ -237,6 +240,49 @@ impl ServiceValueTree {
             self.get_by_balance_request(request).unwrap_or(dec!(0)) + value,
         );
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.get_as_balances().is_empty()
+    }
+
+    /// Values summed over all services, configuration keys and currency pairs of exchange account
+    pub fn sum_by_exchange_account_id(&self) -> HashMap<ExchangeAccountId, ValueByCurrencyCode> {
+        let mut sums = HashMap::<ExchangeAccountId, ValueByCurrencyCode>::new();
+        for (request, value) in self.get_as_balances() {
+            *sums
+                .entry(request.exchange_account_id)
+                .or_default()
+                .entry(request.currency_code)
+                .or_default() += value;
+        }
+        sums
+    }
+
+    /// Values summed over the whole tree
+    pub fn sum_by_currency_code(&self) -> ValueByCurrencyCode {
+        let mut sums = ValueByCurrencyCode::new();
+        for (request, value) in self.get_as_balances() {
+            *sums.entry(request.currency_code).or_default() += value;
+        }
+        sums
+    }
+
+    /// Tree of changes from `self` to `other`. Missing values are treated as zero and unchanged
+    /// values are omitted, so diff of equal trees is empty
+    pub fn diff(&self, other: &ServiceValueTree) -> ServiceValueTree {
+        let mut changes = other.get_as_balances();
+        for (request, value) in self.get_as_balances() {
+            *changes.entry(request).or_default() -= value;
+        }
+
+        let mut diff = ServiceValueTree::default();
+        for (request, change) in changes {
+            if !change.is_zero() {
+                diff.set_by_balance_request(&request, change);
+            }
+        }
+        diff
+    }
 }
 
 #[cfg(test)]
@@ -499,6 +545,100 @@ mod test {
         assert_eq!(new_tree.get_as_balances(), test_data.1);
     }
 
+    fn request(
+        service_name: &str,
+        exchange_account_id: ExchangeAccountId,
+        currency_code: &str,
+    ) -> BalanceRequest {
+        BalanceRequest::new(
+            ConfigurationDescriptor::new(service_name.into(), "cc0".into()),
+            exchange_account_id,
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            currency_code.into(),
+        )
+    }
+
+    #[test]
+    pub fn sums() {
+        init_logger_file_named("log.txt");
+        let binance = ExchangeAccountId::new("Binance", 0);
+        let bitmex = ExchangeAccountId::new("Bitmex", 0);
+
+        let mut tree = ServiceValueTree::default();
+        tree.set_by_balance_request(&request("name0", binance, "btc"), dec!(1));
+        tree.set_by_balance_request(&request("name1", binance, "btc"), dec!(2));
+        tree.set_by_balance_request(&request("name1", binance, "usdt"), dec!(100));
+        tree.set_by_balance_request(&request("name0", bitmex, "btc"), dec!(0.5));
+
+        assert_eq!(
+            tree.sum_by_exchange_account_id(),
+            hashmap![
+                binance => hashmap!["btc".into() => dec!(3), "usdt".into() => dec!(100)],
+                bitmex => hashmap!["btc".into() => dec!(0.5)]
+            ]
+        );
+        assert_eq!(
+            tree.sum_by_currency_code(),
+            hashmap!["btc".into() => dec!(3.5), "usdt".into() => dec!(100)]
+        );
+    }
+
+    #[test]
+    pub fn diff() {
+        init_logger_file_named("log.txt");
+        let binance = ExchangeAccountId::new("Binance", 0);
+        let unchanged = request("name0", binance, "btc");
+        let changed = request("name0", binance, "usdt");
+        let removed = request("name1", binance, "btc");
+        let added = request("name2", binance, "btc");
+
+        let mut before = ServiceValueTree::default();
+        before.set_by_balance_request(&unchanged, dec!(1));
+        before.set_by_balance_request(&changed, dec!(100));
+        before.set_by_balance_request(&removed, dec!(2));
+
+        let mut after = ServiceValueTree::default();
+        after.set_by_balance_request(&unchanged, dec!(1));
+        after.set_by_balance_request(&changed, dec!(70));
+        after.set_by_balance_request(&added, dec!(0.5));
+
+        assert_eq!(
+            before.diff(&after).get_as_balances(),
+            hashmap![changed => dec!(-30), removed => dec!(-2), added => dec!(0.5)]
+        );
+        assert!(after.diff(&after).is_empty());
+
+        let mut restored = before.clone();
+        restored.add(&before.diff(&after));
+        assert!(restored.diff(&after).is_empty());
+    }
+
+    #[test]
+    pub fn serialization() {
+        init_logger_file_named("log.txt");
+        let mut tree = ServiceValueTree::default();
+        tree.set_by_balance_request(
+            &request("name0", ExchangeAccountId::new("Binance", 0), "btc"),
+            dec!(1.5),
+        );
+        tree.set_by_balance_request(
+            &request("name1", ExchangeAccountId::new("Bitmex", 1), "usdt"),
+            dec!(20),
+        );
+
+        let json = serde_json::to_string(&tree).expect("in test");
+        let deserialized: ServiceValueTree = serde_json::from_str(&json).expect("in test");
+        assert_eq!(deserialized, tree);
+
+        assert_eq!(
+            serde_json::to_value(&tree).expect("in test"),
+            serde_json::json!({
+                "name0": {"cc0": {"Binance_0": {"btc/usdt": {"btc": "1.5"}}}},
+                "name1": {"cc0": {"Bitmex_1": {"btc/usdt": {"usdt": "20"}}}}
+            })
+        );
+    }
+
     #[test]
     pub fn compare() {
         init_logger_file_named("log.txt");
@@ -0,0 +1,69 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// JSON file with state of engine service which survives engine restarts.
+/// State is serialized under lock of service with increasing version, but written
+/// outside of it, so state which is older than already written one is skipped
+pub(crate) struct StateFile {
+    path: PathBuf,
+    last_version: AtomicU64,
+    written_version: Mutex<u64>,
+}
+
+impl StateFile {
+    pub fn new(path: PathBuf) -> Self {
+        StateFile {
+            path,
+            last_version: AtomicU64::new(0),
+            written_version: Mutex::new(0),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns `None` if state isn't saved yet
+    pub fn load<T: DeserializeOwned>(&self) -> Result<Option<T>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Unable to read state from {}", self.path.display()))?;
+        let state = serde_json::from_str(&content)
+            .with_context(|| format!("Unable to parse state from {}", self.path.display()))?;
+        Ok(Some(state))
+    }
+
+    /// Serializes state which should be written by `write`. It should be called under the lock
+    /// which guards state, so versions are ordered as state changes
+    pub fn serialize<T: Serialize + ?Sized>(&self, state: &T) -> Result<(u64, String)> {
+        let content = serde_json::to_string(state).context("Unable to serialize state")?;
+        let version = self.last_version.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok((version, content))
+    }
+
+    /// State is written to temporary file and renamed, so partial state is never read after crash
+    pub fn write(&self, version: u64, content: &str) -> Result<()> {
+        let mut written_version = self.written_version.lock();
+        if version <= *written_version {
+            return Ok(());
+        }
+
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, content)
+            .with_context(|| format!("Unable to write state to {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Unable to replace state file {}", self.path.display()))?;
+        *written_version = version;
+
+        Ok(())
+    }
+}
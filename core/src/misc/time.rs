@@ -1,5 +1,60 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use mmb_utils::DateTime;
 #[cfg(test)]
 use mockall::automock;
+use parking_lot::Mutex;
+
+/// Clock of backtest which follows recorded time of replayed market data instead of wall clock.
+/// Time between replayed messages goes with replay speed
+struct ReplayClock {
+    time: DateTime,
+    instant: Instant,
+    speed: f64,
+}
+
+impl ReplayClock {
+    fn now(&self) -> DateTime {
+        let elapsed = self.instant.elapsed().mul_f64(self.speed);
+        self.time + chrono::Duration::from_std(elapsed).unwrap_or_else(|_| chrono::Duration::zero())
+    }
+}
+
+static REPLAY_CLOCK: Mutex<Option<ReplayClock>> = parking_lot::const_mutex(None);
+/// Live engine reads wall clock without locking replay clock
+static IS_REPLAY_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Moves engine time to recorded time of replayed message. Time never goes back, so messages
+/// of several replayed exchanges can't move it backwards
+pub(crate) fn advance_replay_clock(time: DateTime, speed: f64) {
+    let mut clock = REPLAY_CLOCK.lock();
+    let time = match clock.as_ref() {
+        Some(clock) => clock.now().max(time),
+        None => time,
+    };
+    *clock = Some(ReplayClock {
+        time,
+        instant: Instant::now(),
+        speed,
+    });
+    IS_REPLAY_ACTIVE.store(true, Ordering::Release);
+}
+
+/// Returns engine time to wall clock after backtest
+pub(crate) fn reset_replay_clock() {
+    let mut clock = REPLAY_CLOCK.lock();
+    IS_REPLAY_ACTIVE.store(false, Ordering::Release);
+    *clock = None;
+}
+
+fn replay_now() -> Option<DateTime> {
+    if !IS_REPLAY_ACTIVE.load(Ordering::Acquire) {
+        return None;
+    }
+
+    REPLAY_CLOCK.lock().as_ref().map(|x| x.now())
+}
 
 /// If you'll use this mod in some tests, mocks object should be created.
 /// Automock doesn't support default implementation.
@@ -10,9 +65,9 @@ pub mod time_manager {
 
     use mmb_utils::DateTime;
 
-    /// Return current date in UTC
+    /// Return current date in UTC. It's recorded time of replayed market data in backtest
     pub fn now() -> DateTime {
-        chrono::Utc::now()
+        super::replay_now().unwrap_or_else(mmb_utils::time::now)
     }
 }
 
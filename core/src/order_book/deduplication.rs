@@ -0,0 +1,130 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use chrono::Duration;
+use mmb_utils::DateTime;
+
+use crate::exchanges::common::MarketAccountId;
+use crate::order_book::event::{EventType, OrderBookEvent};
+
+/// Identical event which arrives within this window after previous event of market is treated as resent
+const DUPLICATE_WINDOW_MS: i64 = 5_000;
+
+#[derive(Debug, Clone, Copy)]
+struct LastEvent {
+    hash: u64,
+    creation_time: DateTime,
+}
+
+fn content_hash(event: &OrderBookEvent) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    matches!(event.event_type, EventType::Snapshot).hash(&mut hasher);
+    event.data.asks.hash(&mut hasher);
+    event.data.bids.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Detects order book events which repeat previous event of market, e.g. depth frames resent
+/// by exchange after reconnect. Only consecutive identical events are duplicates: identical event
+/// after different one changes order book again, so it isn't skipped
+#[derive(Debug, Default)]
+pub struct OrderBookEventDeduplicator {
+    last_events: HashMap<MarketAccountId, LastEvent>,
+}
+
+impl OrderBookEventDeduplicator {
+    pub fn is_duplicate(&self, event: &OrderBookEvent) -> bool {
+        self.last_events
+            .get(&event.market_account_id())
+            .is_some_and(|last| Self::is_repeated(last, content_hash(event), event.creation_time))
+    }
+
+    /// Returns `true` if event is duplicate, otherwise remembers it as last event of market
+    pub fn register(&mut self, event: &OrderBookEvent) -> bool {
+        let hash = content_hash(event);
+        let market_account_id = event.market_account_id();
+        if let Some(last) = self.last_events.get(&market_account_id) {
+            if Self::is_repeated(last, hash, event.creation_time) {
+                return true;
+            }
+        }
+
+        let _ = self.last_events.insert(
+            market_account_id,
+            LastEvent {
+                hash,
+                creation_time: event.creation_time,
+            },
+        );
+        false
+    }
+
+    fn is_repeated(last: &LastEvent, hash: u64, creation_time: DateTime) -> bool {
+        last.hash == hash
+            && creation_time - last.creation_time <= Duration::milliseconds(DUPLICATE_WINDOW_MS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+    use crate::order_book_data;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+    use std::sync::Arc;
+
+    fn event(
+        creation_time: DateTime,
+        event_type: EventType,
+        ask_amount: rust_decimal::Decimal,
+    ) -> OrderBookEvent {
+        OrderBookEvent::new(
+            creation_time,
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            "".to_owned(),
+            event_type,
+            Arc::new(order_book_data![
+                dec!(3.0) => ask_amount,
+                ;
+                dec!(2.0) => dec!(1.0),
+            ]),
+        )
+    }
+
+    #[test]
+    pub fn resent_event_is_duplicate() {
+        let now = Utc::now();
+        let mut deduplicator = OrderBookEventDeduplicator::default();
+
+        assert!(!deduplicator.register(&event(now, EventType::Snapshot, dec!(1.0))));
+        let resent = event(now + Duration::seconds(1), EventType::Snapshot, dec!(1.0));
+        assert!(deduplicator.is_duplicate(&resent));
+        assert!(deduplicator.register(&resent));
+
+        let update = event(now + Duration::seconds(1), EventType::Update, dec!(1.0));
+        assert!(!deduplicator.register(&update));
+    }
+
+    #[test]
+    pub fn identical_event_after_other_one_is_not_duplicate() {
+        let now = Utc::now();
+        let mut deduplicator = OrderBookEventDeduplicator::default();
+
+        assert!(!deduplicator.register(&event(now, EventType::Update, dec!(1.0))));
+        assert!(!deduplicator.register(&event(now, EventType::Update, dec!(2.0))));
+        assert!(!deduplicator.register(&event(now, EventType::Update, dec!(1.0))));
+    }
+
+    #[test]
+    pub fn identical_event_after_window_is_not_duplicate() {
+        let now = Utc::now();
+        let mut deduplicator = OrderBookEventDeduplicator::default();
+
+        assert!(!deduplicator.register(&event(now, EventType::Snapshot, dec!(1.0))));
+        let late = event(now + Duration::seconds(10), EventType::Snapshot, dec!(1.0));
+        assert!(!deduplicator.register(&late));
+    }
+}
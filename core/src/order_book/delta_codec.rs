@@ -0,0 +1,531 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use chrono::{TimeZone, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{CurrencyPair, ExchangeAccountId, SortedOrderData};
+use crate::order_book::event::{EventType, OrderBookEvent};
+use crate::order_book::order_book_data::OrderBookData;
+
+const KEYFRAME_TAG: u8 = 0;
+const DELTA_TAG: u8 = 1;
+
+/// Scales of prices and amounts of market. Every price and amount is stored as integer
+/// number of `10^-scale` units, so values with bigger precision can't be encoded
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DeltaCodecScales {
+    pub price_scale: u32,
+    pub amount_scale: u32,
+}
+
+/// Market and scales of order book delta file. It's written as the first JSON line of file
+/// followed by encoded records
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct OrderBookDeltaFileHeader {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub scales: DeltaCodecScales,
+}
+
+impl OrderBookDeltaFileHeader {
+    pub fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        serde_json::to_writer(&mut *out, self).context("Unable to serialize file header")?;
+        out.push(b'\n');
+        Ok(())
+    }
+}
+
+/// Compact binary encoding of order book events of one market.
+/// Every record is keyframe with full order book or delta with changed price levels.
+/// Prices are encoded as zigzag varint differences with previous level, amounts as varints.
+/// Keyframe contains absolute time and delta contains time difference with previous record, so
+/// reading can be started from any keyframe. Keyframe is written for snapshot events, periodically
+/// and after event which couldn't be encoded
+pub struct OrderBookDeltaEncoder {
+    scales: DeltaCodecScales,
+    keyframe_interval: u32,
+    events_since_keyframe: u32,
+    last_time_ms: i64,
+    book: OrderBookData,
+}
+
+impl OrderBookDeltaEncoder {
+    pub fn new(scales: DeltaCodecScales, keyframe_interval: u32) -> Self {
+        OrderBookDeltaEncoder {
+            scales,
+            keyframe_interval,
+            events_since_keyframe: 0,
+            last_time_ms: 0,
+            book: OrderBookData::new(Default::default(), Default::default()),
+        }
+    }
+
+    /// Record is appended to `out` only if the whole event is encoded successfully.
+    /// Order book is updated by event anyway, so the next record is keyframe if event isn't encoded
+    pub fn encode(&mut self, event: &OrderBookEvent, out: &mut Vec<u8>) -> Result<()> {
+        match event.event_type {
+            EventType::Snapshot => self.book = event.data.as_ref().clone(),
+            EventType::Update => {
+                OrderBookData::apply_update(&mut self.book.asks, &mut self.book.bids, &event.data)
+            }
+        }
+
+        let is_keyframe = matches!(event.event_type, EventType::Snapshot)
+            || self.events_since_keyframe >= self.keyframe_interval;
+
+        let time_ms = event.creation_time.timestamp_millis();
+        let (tag, time, data) = match is_keyframe {
+            true => (KEYFRAME_TAG, time_ms, &self.book),
+            false => (DELTA_TAG, time_ms - self.last_time_ms, event.data.as_ref()),
+        };
+
+        let mut record = vec![tag];
+        write_varint(&mut record, zigzag_encode(time));
+        let written = write_levels(&mut record, &data.asks, self.scales)
+            .and_then(|_| write_levels(&mut record, &data.bids, self.scales));
+        if let Err(error) = written {
+            self.events_since_keyframe = self.keyframe_interval;
+            return Err(error);
+        }
+
+        out.extend_from_slice(&record);
+        self.events_since_keyframe = match is_keyframe {
+            true => 0,
+            false => self.events_since_keyframe + 1,
+        };
+        self.last_time_ms = time_ms;
+        Ok(())
+    }
+}
+
+/// Reconstructs order book events from data written by `OrderBookDeltaEncoder`
+pub struct OrderBookDeltaReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    scales: DeltaCodecScales,
+    exchange_account_id: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    last_time_ms: i64,
+    events_count: u64,
+}
+
+impl<'a> OrderBookDeltaReader<'a> {
+    pub fn new(
+        data: &'a [u8],
+        scales: DeltaCodecScales,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+    ) -> Self {
+        OrderBookDeltaReader {
+            data,
+            position: 0,
+            scales,
+            exchange_account_id,
+            currency_pair,
+            last_time_ms: 0,
+            events_count: 0,
+        }
+    }
+
+    /// Reads file written as `OrderBookDeltaFileHeader` followed by encoded records
+    pub fn from_file(data: &'a [u8]) -> Result<Self> {
+        let header_end = data
+            .iter()
+            .position(|&x| x == b'\n')
+            .context("Order book delta file doesn't contain header")?;
+        let header: OrderBookDeltaFileHeader = serde_json::from_slice(&data[..header_end])
+            .context("Unable to parse header of order book delta file")?;
+
+        Ok(Self::new(
+            &data[header_end + 1..],
+            header.scales,
+            header.exchange_account_id,
+            header.currency_pair,
+        ))
+    }
+
+    fn read_event(&mut self) -> Result<OrderBookEvent> {
+        let event_type = match self.read_byte()? {
+            KEYFRAME_TAG => EventType::Snapshot,
+            DELTA_TAG => EventType::Update,
+            tag => bail!("Unknown record tag {tag} at position {}", self.position - 1),
+        };
+
+        let time = zigzag_decode(self.read_varint()?);
+        self.last_time_ms = match event_type {
+            EventType::Snapshot => time,
+            EventType::Update => self.last_time_ms + time,
+        };
+        let creation_time = Utc
+            .timestamp_millis_opt(self.last_time_ms)
+            .single()
+            .with_context(|| format!("Invalid event time {}", self.last_time_ms))?;
+
+        let asks = self.read_levels()?;
+        let bids = self.read_levels()?;
+
+        self.events_count += 1;
+        Ok(OrderBookEvent::new(
+            creation_time,
+            self.exchange_account_id,
+            self.currency_pair,
+            self.events_count.to_string(),
+            event_type,
+            Arc::new(OrderBookData::new(asks, bids)),
+        ))
+    }
+
+    fn read_levels(&mut self) -> Result<SortedOrderData> {
+        let count = self.read_varint()?;
+        let mut levels = SortedOrderData::new();
+        let mut price_units = 0i64;
+        for _ in 0..count {
+            price_units = price_units
+                .checked_add(zigzag_decode(self.read_varint()?))
+                .with_context(|| format!("Price overflow at position {}", self.position))?;
+            let amount_units = i64::try_from(self.read_varint()?)
+                .with_context(|| format!("Amount overflow at position {}", self.position))?;
+            let price = Decimal::try_new(price_units, self.scales.price_scale)
+                .with_context(|| format!("Invalid price scale {}", self.scales.price_scale))?;
+            let amount = Decimal::try_new(amount_units, self.scales.amount_scale)
+                .with_context(|| format!("Invalid amount scale {}", self.scales.amount_scale))?;
+            let _ = levels.insert(price, amount);
+        }
+        Ok(levels)
+    }
+
+    fn read_byte(&mut self) -> Result<u8> {
+        let byte = *self
+            .data
+            .get(self.position)
+            .context("Unexpected end of order book delta data")?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn read_varint(&mut self) -> Result<u64> {
+        let mut result = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_byte()?;
+            result |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        bail!("Varint is too long at position {}", self.position)
+    }
+}
+
+impl Iterator for OrderBookDeltaReader<'_> {
+    type Item = Result<OrderBookEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.data.len() {
+            return None;
+        }
+
+        Some(self.read_event())
+    }
+}
+
+fn write_levels(
+    out: &mut Vec<u8>,
+    levels: &SortedOrderData,
+    scales: DeltaCodecScales,
+) -> Result<()> {
+    write_varint(out, levels.len() as u64);
+
+    let mut prev_price_units = 0i64;
+    for (&price, &amount) in levels {
+        let price_units = to_units(price, scales.price_scale)?;
+        let amount_units = to_units(amount, scales.amount_scale)?;
+        if amount_units < 0 {
+            bail!("Negative amount {amount} can't be encoded");
+        }
+
+        write_varint(out, zigzag_encode(price_units - prev_price_units));
+        write_varint(out, amount_units as u64);
+        prev_price_units = price_units;
+    }
+
+    Ok(())
+}
+
+fn to_units(value: Decimal, scale: u32) -> Result<i64> {
+    let multiplier = 10i64
+        .checked_pow(scale)
+        .with_context(|| format!("Scale {scale} is too big"))?;
+    let units = value
+        .checked_mul(Decimal::from(multiplier))
+        .with_context(|| format!("Value {value} is too big to be encoded"))?;
+    if !units.fract().is_zero() {
+        bail!("Value {value} has precision bigger than scale {scale}");
+    }
+
+    units
+        .to_i64()
+        .with_context(|| format!("Value {value} is too big to be encoded"))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book_data;
+    use rstest::rstest;
+    use rust_decimal_macros::dec;
+
+    const SCALES: DeltaCodecScales = DeltaCodecScales {
+        price_scale: 2,
+        amount_scale: 4,
+    };
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", 0)
+    }
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    fn event(time_ms: i64, event_type: EventType, data: OrderBookData) -> OrderBookEvent {
+        OrderBookEvent::new(
+            Utc.timestamp_millis(time_ms),
+            exchange_account_id(),
+            currency_pair(),
+            String::new(),
+            event_type,
+            Arc::new(data),
+        )
+    }
+
+    #[test]
+    pub fn zigzag() {
+        for value in [0, 1, -1, 63, -64, i64::MAX, i64::MIN] {
+            assert_eq!(zigzag_decode(zigzag_encode(value)), value);
+        }
+    }
+
+    #[test]
+    pub fn encode_and_read_events() {
+        let events = vec![
+            event(
+                1_000,
+                EventType::Snapshot,
+                order_book_data![
+                    dec!(100.5) => dec!(1.5),
+                    dec!(101) => dec!(2),
+                    ;
+                    dec!(99.99) => dec!(0.0001),
+                ],
+            ),
+            event(
+                1_010,
+                EventType::Update,
+                order_book_data![
+                    dec!(100.5) => dec!(0),
+                    ;
+                    dec!(99.5) => dec!(3),
+                ],
+            ),
+            event(
+                1_005,
+                EventType::Update,
+                order_book_data![
+                    dec!(102) => dec!(1),
+                    ;
+                ],
+            ),
+        ];
+
+        let mut encoder = OrderBookDeltaEncoder::new(SCALES, 1);
+        let mut encoded = vec![];
+        for event in &events {
+            encoder.encode(event, &mut encoded).expect("in test");
+        }
+
+        let reader =
+            OrderBookDeltaReader::new(&encoded, SCALES, exchange_account_id(), currency_pair());
+        let decoded = reader.collect::<Result<Vec<_>>>().expect("in test");
+
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0].data, events[0].data);
+        assert_eq!(decoded[1].data, events[1].data);
+        assert!(matches!(decoded[1].event_type, EventType::Update));
+        // keyframe interval is reached, so full order book is written
+        assert!(matches!(decoded[2].event_type, EventType::Snapshot));
+        assert_eq!(
+            decoded[2].data.as_ref(),
+            &order_book_data![
+                dec!(101) => dec!(2),
+                dec!(102) => dec!(1),
+                ;
+                dec!(99.99) => dec!(0.0001),
+                dec!(99.5) => dec!(3),
+            ]
+        );
+        for (decoded, event) in decoded.iter().zip(&events) {
+            assert_eq!(decoded.creation_time, event.creation_time);
+        }
+    }
+
+    #[test]
+    pub fn value_with_too_big_precision_is_not_encoded() {
+        let mut encoder = OrderBookDeltaEncoder::new(SCALES, 100);
+        let event = event(
+            0,
+            EventType::Snapshot,
+            order_book_data![
+                dec!(100.001) => dec!(1),
+                ;
+            ],
+        );
+
+        let mut encoded = vec![];
+        assert!(encoder.encode(&event, &mut encoded).is_err());
+        assert!(encoded.is_empty());
+
+        // order book was updated by failed event, so the next record is keyframe
+        let update = self::event(
+            10,
+            EventType::Update,
+            order_book_data![
+                dec!(100.001) => dec!(0),
+                ;
+                dec!(99) => dec!(1),
+            ],
+        );
+        encoder.encode(&update, &mut encoded).expect("in test");
+        let decoded =
+            OrderBookDeltaReader::new(&encoded, SCALES, exchange_account_id(), currency_pair())
+                .collect::<Result<Vec<_>>>()
+                .expect("in test");
+        assert_eq!(decoded.len(), 1);
+        assert!(matches!(decoded[0].event_type, EventType::Snapshot));
+        assert_eq!(decoded[0].creation_time, update.creation_time);
+    }
+
+    #[test]
+    pub fn too_big_scale_is_not_encoded() {
+        let scales = DeltaCodecScales {
+            price_scale: 19,
+            amount_scale: 4,
+        };
+        let mut encoder = OrderBookDeltaEncoder::new(scales, 100);
+        let event = event(
+            0,
+            EventType::Snapshot,
+            order_book_data![
+                dec!(100) => dec!(1),
+                ;
+            ],
+        );
+
+        let error = encoder.encode(&event, &mut vec![]).expect_err("in test");
+        assert!(error.to_string().contains("Scale 19"));
+    }
+
+    #[rstest]
+    #[case(vec![zigzag_encode(i64::MAX), 1, zigzag_encode(1), 1])]
+    #[case(vec![zigzag_encode(100), u64::MAX])]
+    pub fn corrupt_levels_are_not_read(#[case] values: Vec<u64>) {
+        let mut data = vec![];
+        write_varint(&mut data, 2);
+        for value in values {
+            write_varint(&mut data, value);
+        }
+
+        let mut reader =
+            OrderBookDeltaReader::new(&data, SCALES, exchange_account_id(), currency_pair());
+        assert!(reader.read_levels().is_err());
+    }
+
+    #[test]
+    pub fn file_is_read_from_keyframe_in_the_middle() {
+        let header = OrderBookDeltaFileHeader {
+            exchange_account_id: exchange_account_id(),
+            currency_pair: currency_pair(),
+            scales: SCALES,
+        };
+        let mut file = vec![];
+        header.encode(&mut file).expect("in test");
+
+        let mut encoder = OrderBookDeltaEncoder::new(SCALES, 1);
+        let snapshot = event(
+            1_000,
+            EventType::Snapshot,
+            order_book_data![
+                dec!(101) => dec!(2),
+                ;
+                dec!(100) => dec!(1),
+            ],
+        );
+        let update = event(
+            1_500,
+            EventType::Update,
+            order_book_data![
+                ;
+                dec!(99) => dec!(3),
+            ],
+        );
+        let keyframe = event(
+            2_000,
+            EventType::Update,
+            order_book_data![
+                dec!(101) => dec!(0),
+                ;
+            ],
+        );
+        encoder.encode(&snapshot, &mut file).expect("in test");
+        encoder.encode(&update, &mut file).expect("in test");
+        let keyframe_position = file.len();
+        encoder.encode(&keyframe, &mut file).expect("in test");
+
+        let decoded = OrderBookDeltaReader::from_file(&file)
+            .expect("in test")
+            .collect::<Result<Vec<_>>>()
+            .expect("in test");
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0].exchange_account_id, exchange_account_id());
+        assert_eq!(decoded[0].currency_pair, currency_pair());
+
+        // keyframe holds absolute time, so reading can start from it
+        let decoded = OrderBookDeltaReader::new(
+            &file[keyframe_position..],
+            SCALES,
+            exchange_account_id(),
+            currency_pair(),
+        )
+        .collect::<Result<Vec<_>>>()
+        .expect("in test");
+        assert_eq!(decoded.len(), 1);
+        assert!(matches!(decoded[0].event_type, EventType::Snapshot));
+        assert_eq!(decoded[0].creation_time, keyframe.creation_time);
+        assert_eq!(
+            decoded[0].data.as_ref(),
+            &order_book_data![
+                ;
+                dec!(100) => dec!(1),
+                dec!(99) => dec!(3),
+            ]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::common::MarketAccountId;
+use crate::exchanges::events::ExchangeEvent;
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+use crate::order_book::delta_codec::{
+    DeltaCodecScales, OrderBookDeltaEncoder, OrderBookDeltaFileHeader,
+};
+use crate::order_book::event::OrderBookEvent;
+use crate::settings::OrderBookRecordingSettings;
+
+struct MarketRecording {
+    encoder: OrderBookDeltaEncoder,
+    file: BufWriter<File>,
+}
+
+/// Records order book events of every market to its own file in delta encoding. File of market is
+/// created when its first event is received, so files of different engine runs don't overlap
+pub(crate) struct OrderBookDeltaRecorder {
+    settings: OrderBookRecordingSettings,
+    markets: HashMap<MarketAccountId, MarketRecording>,
+    buffer: Vec<u8>,
+}
+
+impl OrderBookDeltaRecorder {
+    pub fn start(
+        settings: &OrderBookRecordingSettings,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Result<()> {
+        fs::create_dir_all(&settings.directory).with_context(|| {
+            format!(
+                "Unable to create order book recording directory {}",
+                settings.directory.display()
+            )
+        })?;
+
+        let recorder = OrderBookDeltaRecorder {
+            settings: settings.clone(),
+            markets: HashMap::new(),
+            buffer: vec![],
+        };
+        let _ = spawn_future(
+            "Order book delta recording",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            recorder.record_order_books(events_receiver),
+        );
+
+        Ok(())
+    }
+
+    async fn record_order_books(
+        mut self,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Result<()> {
+        loop {
+            match events_receiver.recv().await {
+                Ok(ExchangeEvent::OrderBookEvent(event)) => {
+                    if let Err(error) = self.record(&event) {
+                        log::error!(
+                            "Failed to record order book event of {} on {}: {error:?}",
+                            event.currency_pair,
+                            event.exchange_account_id
+                        );
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    // recorded order books are restored by the next snapshots of exchanges
+                    log::error!("Order book recording skipped {skipped} exchange events");
+                }
+                Err(RecvError::Closed) => return self.flush(),
+            }
+        }
+    }
+
+    fn record(&mut self, event: &OrderBookEvent) -> Result<()> {
+        let market_account_id =
+            MarketAccountId::new(event.exchange_account_id, event.currency_pair);
+        let market = match self.markets.get_mut(&market_account_id) {
+            Some(market) => market,
+            None => {
+                let market = self.create_market_recording(market_account_id)?;
+                self.markets.entry(market_account_id).or_insert(market)
+            }
+        };
+
+        self.buffer.clear();
+        market.encoder.encode(event, &mut self.buffer)?;
+        market
+            .file
+            .write_all(&self.buffer)
+            .context("Unable to write order book record")
+    }
+
+    fn create_market_recording(
+        &self,
+        market_account_id: MarketAccountId,
+    ) -> Result<MarketRecording> {
+        let scales = DeltaCodecScales {
+            price_scale: self.settings.price_scale,
+            amount_scale: self.settings.amount_scale,
+        };
+        let codes = market_account_id.currency_pair.to_codes();
+        let path: PathBuf = self.settings.directory.join(format!(
+            "{}_{}_{}_{}.obd",
+            market_account_id.exchange_account_id,
+            codes.base,
+            codes.quote,
+            time_manager::now().timestamp_millis()
+        ));
+
+        let mut header = vec![];
+        OrderBookDeltaFileHeader {
+            exchange_account_id: market_account_id.exchange_account_id,
+            currency_pair: market_account_id.currency_pair,
+            scales,
+        }
+        .encode(&mut header)?;
+
+        let mut file = BufWriter::new(
+            File::create(&path)
+                .with_context(|| format!("Unable to create file {}", path.display()))?,
+        );
+        file.write_all(&header)
+            .with_context(|| format!("Unable to write header to {}", path.display()))?;
+        log::info!(
+            "Order book of {market_account_id:?} is recorded to {}",
+            path.display()
+        );
+
+        Ok(MarketRecording {
+            encoder: OrderBookDeltaEncoder::new(scales, self.settings.keyframe_interval),
+            file,
+        })
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        for market in self.markets.values_mut() {
+            market
+                .file
+                .flush()
+                .context("Unable to flush order book recording")?;
+        }
+        Ok(())
+    }
+}
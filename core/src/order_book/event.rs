@@ -2,6 +2,7 @@ use mmb_utils::DateTime;
 
 use crate::exchanges::common::*;
 use crate::order_book::order_book_data::OrderBookData;
+use std::cmp::Ordering;
 use std::sync::Arc;
 
 /// Possible variants of OrderBookEvent
@@ -51,3 +52,57 @@ impl OrderBookEvent {
         MarketAccountId::new(self.exchange_account_id, self.currency_pair)
     }
 }
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PriceDirection {
+    Up,
+    Down,
+    Unchanged,
+}
+
+/// Event about change of best ask or best bid (price or size at best level) of local snapshot
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BestPriceChangedEvent {
+    pub creation_time: DateTime,
+    pub market_account_id: MarketAccountId,
+    /// Best ask price and size before change
+    pub old_ask: Option<(Price, Amount)>,
+    pub new_ask: Option<(Price, Amount)>,
+    /// Best bid price and size before change
+    pub old_bid: Option<(Price, Amount)>,
+    pub new_bid: Option<(Price, Amount)>,
+}
+
+impl BestPriceChangedEvent {
+    /// Returns `None` if there was no best ask before or after change
+    pub fn ask_direction(&self) -> Option<PriceDirection> {
+        Self::direction(self.old_ask, self.new_ask)
+    }
+
+    /// Returns `None` if there was no best bid before or after change
+    pub fn bid_direction(&self) -> Option<PriceDirection> {
+        Self::direction(self.old_bid, self.new_bid)
+    }
+
+    /// Difference between new and old best ask price
+    pub fn ask_change(&self) -> Option<Price> {
+        Some(self.new_ask?.0 - self.old_ask?.0)
+    }
+
+    /// Difference between new and old best bid price
+    pub fn bid_change(&self) -> Option<Price> {
+        Some(self.new_bid?.0 - self.old_bid?.0)
+    }
+
+    fn direction(
+        old: Option<(Price, Amount)>,
+        new: Option<(Price, Amount)>,
+    ) -> Option<PriceDirection> {
+        let direction = match new?.0.cmp(&old?.0) {
+            Ordering::Greater => PriceDirection::Up,
+            Ordering::Less => PriceDirection::Down,
+            Ordering::Equal => PriceDirection::Unchanged,
+        };
+        Some(direction)
+    }
+}
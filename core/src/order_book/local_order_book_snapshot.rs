@@ -90,6 +90,39 @@ impl LocalOrderBookSnapshot {
         self.bids.iter().rev()
     }
 
+    /// Return price levels which order with specified side would be matched with, starting from the best price
+    pub fn get_price_levels_to_match(
+        &self,
+        order_side: OrderSide,
+    ) -> Box<dyn Iterator<Item = (&Price, &Amount)> + '_> {
+        match order_side {
+            OrderSide::Buy => Box::new(self.get_asks_price_levels()),
+            OrderSide::Sell => Box::new(self.get_bids_price_levels()),
+        }
+    }
+
+    /// Base amount which can be bought or sold immediately for specified amount in quote currency.
+    /// Return None if order book depth isn't enough
+    pub fn calculate_amount_by_quote(
+        &self,
+        order_side: OrderSide,
+        quote_amount: Amount,
+    ) -> Option<Amount> {
+        let mut rest_quote_amount = quote_amount;
+        let mut amount = dec!(0);
+        for (&price, &level_amount) in self.get_price_levels_to_match(order_side) {
+            let level_quote_amount = price * level_amount;
+            if level_quote_amount >= rest_quote_amount {
+                return Some(amount + rest_quote_amount / price);
+            }
+
+            amount += level_amount;
+            rest_quote_amount -= level_quote_amount;
+        }
+
+        None
+    }
+
     fn try_remove_order(&mut self, order: DataToExcludeOrder) {
         let book_side = self.get_order_book_side(order.side);
 
@@ -182,6 +215,33 @@ mod tests {
         assert_eq!(iter.next().expect("in test"), (&dec!(3.0), &dec!(4.2)));
     }
 
+    #[test]
+    fn calculate_amount_by_quote() {
+        let mut asks = SortedOrderData::new();
+        asks.insert(dec!(10), dec!(1));
+        asks.insert(dec!(20), dec!(2));
+        let mut bids = SortedOrderData::new();
+        bids.insert(dec!(8), dec!(1));
+        bids.insert(dec!(5), dec!(10));
+
+        let order_book_snapshot = LocalOrderBookSnapshot::new(asks, bids, Utc::now());
+
+        // 10 * 1 + 20 * 1
+        assert_eq!(
+            order_book_snapshot.calculate_amount_by_quote(OrderSide::Buy, dec!(30)),
+            Some(dec!(2))
+        );
+        // 8 * 1 + 5 * 2
+        assert_eq!(
+            order_book_snapshot.calculate_amount_by_quote(OrderSide::Sell, dec!(18)),
+            Some(dec!(3))
+        );
+        assert_eq!(
+            order_book_snapshot.calculate_amount_by_quote(OrderSide::Buy, dec!(51)),
+            None
+        );
+    }
+
     #[test]
     fn get_top_bid() {
         let asks = SortedOrderData::new();
@@ -1,18 +1,125 @@
 use crate::exchanges::common::*;
+use crate::order_book::deduplication::OrderBookEventDeduplicator;
 use crate::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
 use crate::order_book::*;
-use std::collections::HashMap;
+use crate::orders::order::OrderSide;
+use crate::orders::price_protection::{estimate_market_impact, MarketImpact};
+use std::collections::{HashMap, VecDeque};
 
 use mmb_utils::infrastructure::WithExpect;
+use rust_decimal_macros::dec;
+use tokio::sync::broadcast;
+
+/// Max count of changed levels stored per market for reading changes since version
+const MAX_CHANGES_HISTORY: usize = 10_000;
+const BEST_PRICE_CHANNEL_CAPACITY: usize = 1_000;
+
+pub type SnapshotVersion = u64;
+
+/// Best ask and bid of order book
+type TopLevels = (Option<(Price, Amount)>, Option<(Price, Amount)>);
+
+/// Result of reading changes of snapshot since some version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotChanges {
+    /// Current amounts of levels changed since requested version. Zero amount means that level is removed
+    Levels {
+        version: SnapshotVersion,
+        asks: SortedOrderData,
+        bids: SortedOrderData,
+    },
+    /// Changes since requested version aren't available (snapshot was replaced or history is trimmed),
+    /// so whole snapshot should be reread
+    Full { version: SnapshotVersion },
+}
+
+/// Versions of snapshot with history of changed levels
+#[derive(Debug, Default)]
+struct SnapshotHistory {
+    version: SnapshotVersion,
+    /// Changes since versions older than this one aren't available
+    oldest_version: SnapshotVersion,
+    changes: VecDeque<(SnapshotVersion, OrderSide, Price)>,
+}
+
+impl SnapshotHistory {
+    fn reset(&mut self) {
+        self.version += 1;
+        self.oldest_version = self.version;
+        self.changes.clear();
+    }
+
+    fn add_changes(&mut self, update: &order_book_data::OrderBookData) {
+        self.version += 1;
+
+        let version = self.version;
+        let asks = update.asks.keys().map(|&x| (version, OrderSide::Sell, x));
+        let bids = update.bids.keys().map(|&x| (version, OrderSide::Buy, x));
+        self.changes.extend(asks.chain(bids));
+
+        while self.changes.len() > MAX_CHANGES_HISTORY {
+            if let Some((trimmed_version, _, _)) = self.changes.pop_front() {
+                self.oldest_version = trimmed_version;
+            }
+        }
+    }
+
+    fn get_changes_since(
+        &self,
+        version: SnapshotVersion,
+        snapshot: &LocalOrderBookSnapshot,
+    ) -> SnapshotChanges {
+        if version < self.oldest_version || version > self.version {
+            return SnapshotChanges::Full {
+                version: self.version,
+            };
+        }
+
+        let mut asks = SortedOrderData::new();
+        let mut bids = SortedOrderData::new();
+        let changes = self.changes.iter().rev().take_while(|x| x.0 > version);
+        for &(_, side, price) in changes {
+            let (levels, changed) = match side {
+                OrderSide::Sell => (&snapshot.asks, &mut asks),
+                OrderSide::Buy => (&snapshot.bids, &mut bids),
+            };
+            let amount = levels.get(&price).copied().unwrap_or(dec!(0));
+            let _ = changed.insert(price, amount);
+        }
+
+        SnapshotChanges::Levels {
+            version: self.version,
+            asks,
+            bids,
+        }
+    }
+}
 
 /// Produce and actualize current logical state of order book snapshot according to logical time of handled order book events
 pub struct LocalSnapshotsService {
     local_snapshots: HashMap<MarketId, LocalOrderBookSnapshot>,
+    histories: HashMap<MarketId, SnapshotHistory>,
+    best_price_sender: broadcast::Sender<event::BestPriceChangedEvent>,
+    deduplicator: OrderBookEventDeduplicator,
 }
 
 impl LocalSnapshotsService {
     pub fn new(local_snapshots: HashMap<MarketId, LocalOrderBookSnapshot>) -> Self {
-        Self { local_snapshots }
+        let (best_price_sender, _) = broadcast::channel(BEST_PRICE_CHANNEL_CAPACITY);
+        Self {
+            local_snapshots,
+            histories: HashMap::new(),
+            best_price_sender,
+            deduplicator: Default::default(),
+        }
+    }
+
+    /// Events are sent only when best ask or best bid is changed, so simple strategies
+    /// don't need to process every depth update
+    pub fn subscribe_best_price_changes(
+        &self,
+    ) -> broadcast::Receiver<event::BestPriceChangedEvent> {
+        self.best_price_sender.subscribe()
     }
 
     pub fn get_snapshot(&self, market_id: MarketId) -> Option<&LocalOrderBookSnapshot> {
@@ -25,10 +132,100 @@ impl LocalSnapshotsService {
             .with_expect(|| format!("Can't get snapshot for {:?}", market_id))
     }
 
+    /// Expected average price, worst price and impact in bps of taker order by order book of market
+    pub fn estimate_market_impact(
+        &self,
+        market_id: MarketId,
+        side: OrderSide,
+        amount: Amount,
+    ) -> Option<MarketImpact> {
+        estimate_market_impact(&[self.get_snapshot(market_id)?], side, amount)
+    }
+
+    /// Same as `estimate_market_impact` but by consolidated order book of markets on different exchanges.
+    /// Markets without snapshot are skipped
+    pub fn estimate_consolidated_market_impact(
+        &self,
+        markets: &[MarketId],
+        side: OrderSide,
+        amount: Amount,
+    ) -> Option<MarketImpact> {
+        let snapshots: Vec<_> = markets
+            .iter()
+            .filter_map(|&market_id| self.get_snapshot(market_id))
+            .collect();
+        estimate_market_impact(&snapshots, side, amount)
+    }
+
+    /// Current version of snapshot. Version is incremented on every snapshot change
+    pub fn get_version(&self, market_id: MarketId) -> Option<SnapshotVersion> {
+        if !self.local_snapshots.contains_key(&market_id) {
+            return None;
+        }
+        Some(self.histories.get(&market_id).map_or(0, |x| x.version))
+    }
+
+    /// Returns only levels changed since `version`, so strategies can maintain derived structures
+    /// without full scan of order book. Returns `None` if there is no snapshot for market
+    pub fn get_changes_since(
+        &self,
+        market_id: MarketId,
+        version: SnapshotVersion,
+    ) -> Option<SnapshotChanges> {
+        let snapshot = self.local_snapshots.get(&market_id)?;
+        let changes = match self.histories.get(&market_id) {
+            Some(history) => history.get_changes_since(version, snapshot),
+            None => SnapshotHistory::default().get_changes_since(version, snapshot),
+        };
+        Some(changes)
+    }
+
+    /// Event repeats previous event of market, e.g. depth frame is resent by exchange after reconnect
+    pub fn is_duplicate(&self, event: &event::OrderBookEvent) -> bool {
+        self.deduplicator.is_duplicate(event)
+    }
+
     /// Create snapshot if it does not exist
     /// Update snapshot if suitable data arrive
-    /// Returns `Some(MarketAccountId)` if snapshot update succeeded, otherwise `None`
+    /// Returns `Some(MarketAccountId)` if snapshot update succeeded, otherwise `None`.
+    /// Duplicate events are skipped and `None` is returned for them
     pub fn update(&mut self, event: event::OrderBookEvent) -> Option<MarketAccountId> {
+        let market_account_id = event.market_account_id();
+        if self.deduplicator.register(&event) {
+            log::trace!("Duplicate order book event for {market_account_id:?} is skipped");
+            return None;
+        }
+        let market_id = market_account_id.market_id();
+
+        let old_top = self.get_top(market_id);
+        let creation_time = event.creation_time;
+        let result = self.update_snapshot(event);
+
+        let new_top = self.get_top(market_id);
+        if result.is_some() && new_top != old_top {
+            let ((old_ask, old_bid), (new_ask, new_bid)) = (old_top, new_top);
+            // there may be no subscribers
+            let _ = self.best_price_sender.send(event::BestPriceChangedEvent {
+                creation_time,
+                market_account_id,
+                old_ask,
+                new_ask,
+                old_bid,
+                new_bid,
+            });
+        }
+
+        result
+    }
+
+    fn get_top(&self, market_id: MarketId) -> TopLevels {
+        match self.local_snapshots.get(&market_id) {
+            Some(snapshot) => (snapshot.get_top_ask(), snapshot.get_top_bid()),
+            None => (None, None),
+        }
+    }
+
+    fn update_snapshot(&mut self, event: event::OrderBookEvent) -> Option<MarketAccountId> {
         let market_account_id = event.market_account_id();
         let market_id = market_account_id.market_id();
 
@@ -36,15 +233,17 @@ impl LocalSnapshotsService {
             event::EventType::Snapshot => {
                 self.local_snapshots
                     .insert(market_id, event.data.to_local_order_book_snapshot());
+                self.histories.entry(market_id).or_default().reset();
                 Some(market_account_id)
             }
             event::EventType::Update => {
-                self.local_snapshots
-                    .get_mut(&market_id)
-                    .map(move |snapshot| {
-                        snapshot.apply_update(&event.data, event.creation_time);
-                        market_account_id
-                    })
+                let snapshot = self.local_snapshots.get_mut(&market_id)?;
+                snapshot.apply_update(&event.data, event.creation_time);
+                self.histories
+                    .entry(market_id)
+                    .or_default()
+                    .add_changes(&event.data);
+                Some(market_account_id)
             }
         }
     }
@@ -61,7 +260,6 @@ mod tests {
     use super::*;
     use crate::order_book_data;
     use chrono::Utc;
-    use rust_decimal_macros::*;
     use std::sync::Arc;
 
     fn create_order_book_event_for_tests(
@@ -80,6 +278,176 @@ mod tests {
         )
     }
 
+    fn market_id() -> MarketId {
+        MarketId::new(
+            "exchange_id".into(),
+            CurrencyPair::from_codes("base".into(), "quote".into()),
+        )
+    }
+
+    fn update_event(
+        event_type: event::EventType,
+        order_book_data: order_book_data::OrderBookData,
+    ) -> event::OrderBookEvent {
+        create_order_book_event_for_tests(
+            market_id().exchange_id,
+            market_id().currency_pair,
+            event_type,
+            order_book_data,
+        )
+    }
+
+    #[test]
+    fn changes_since_version() {
+        let mut snapshot_service = LocalSnapshotsService::default();
+        let _ = snapshot_service.update(update_event(
+            event::EventType::Snapshot,
+            order_book_data![
+                dec!(3.0) => dec!(1.0),
+                dec!(4.0) => dec!(1.0),
+                ;
+                dec!(2.0) => dec!(1.0),
+            ],
+        ));
+        let version = snapshot_service.get_version(market_id()).expect("in test");
+
+        let _ = snapshot_service.update(update_event(
+            event::EventType::Update,
+            order_book_data![
+                dec!(3.0) => dec!(2.0),
+                ;
+                dec!(2.0) => dec!(0),
+            ],
+        ));
+        let middle_version = snapshot_service.get_version(market_id()).expect("in test");
+        let _ = snapshot_service.update(update_event(
+            event::EventType::Update,
+            order_book_data![
+                dec!(5.0) => dec!(3.0),
+                ;
+            ],
+        ));
+
+        let changes = snapshot_service
+            .get_changes_since(market_id(), version)
+            .expect("in test");
+        assert_eq!(
+            changes,
+            SnapshotChanges::Levels {
+                version: middle_version + 1,
+                asks: SortedOrderData::from([(dec!(3.0), dec!(2.0)), (dec!(5.0), dec!(3.0))]),
+                bids: SortedOrderData::from([(dec!(2.0), dec!(0))]),
+            }
+        );
+
+        let changes = snapshot_service
+            .get_changes_since(market_id(), middle_version)
+            .expect("in test");
+        assert_eq!(
+            changes,
+            SnapshotChanges::Levels {
+                version: middle_version + 1,
+                asks: SortedOrderData::from([(dec!(5.0), dec!(3.0))]),
+                bids: SortedOrderData::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn duplicate_event_is_skipped() {
+        let mut snapshot_service = LocalSnapshotsService::default();
+        let event = update_event(
+            event::EventType::Snapshot,
+            order_book_data![
+                dec!(3.0) => dec!(1.0),
+                ;
+                dec!(2.0) => dec!(1.0),
+            ],
+        );
+
+        assert!(snapshot_service.update(event.clone()).is_some());
+        let version = snapshot_service.get_version(market_id());
+
+        assert!(snapshot_service.is_duplicate(&event));
+        assert!(snapshot_service.update(event).is_none());
+        assert_eq!(snapshot_service.get_version(market_id()), version);
+    }
+
+    #[test]
+    fn full_reread_is_required_after_new_snapshot() {
+        let mut snapshot_service = LocalSnapshotsService::default();
+        let _ = snapshot_service.update(update_event(
+            event::EventType::Snapshot,
+            order_book_data![
+                dec!(3.0) => dec!(1.0),
+                ;
+                dec!(2.0) => dec!(1.0),
+            ],
+        ));
+        let version = snapshot_service.get_version(market_id()).expect("in test");
+
+        let _ = snapshot_service.update(update_event(
+            event::EventType::Snapshot,
+            order_book_data![
+                dec!(3.5) => dec!(1.0),
+                ;
+                dec!(2.5) => dec!(1.0),
+            ],
+        ));
+
+        assert_eq!(
+            snapshot_service.get_changes_since(market_id(), version),
+            Some(SnapshotChanges::Full {
+                version: version + 1
+            })
+        );
+    }
+
+    #[test]
+    fn best_price_changed_events() {
+        let mut snapshot_service = LocalSnapshotsService::default();
+        let mut receiver = snapshot_service.subscribe_best_price_changes();
+
+        let _ = snapshot_service.update(update_event(
+            event::EventType::Snapshot,
+            order_book_data![
+                dec!(3.0) => dec!(1.0),
+                ;
+                dec!(2.0) => dec!(1.0),
+            ],
+        ));
+        let event = receiver.try_recv().expect("in test");
+        assert_eq!(event.old_ask, None);
+        assert_eq!(event.new_ask, Some((dec!(3.0), dec!(1.0))));
+        assert_eq!(event.ask_direction(), None);
+
+        // depth update doesn't change best prices
+        let _ = snapshot_service.update(update_event(
+            event::EventType::Update,
+            order_book_data![
+                dec!(4.0) => dec!(1.0),
+                ;
+            ],
+        ));
+        assert!(receiver.try_recv().is_err());
+
+        let _ = snapshot_service.update(update_event(
+            event::EventType::Update,
+            order_book_data![
+                ;
+                dec!(2.5) => dec!(2.0),
+            ],
+        ));
+        let event = receiver.try_recv().expect("in test");
+        assert_eq!(
+            event.ask_direction(),
+            Some(event::PriceDirection::Unchanged)
+        );
+        assert_eq!(event.bid_direction(), Some(event::PriceDirection::Up));
+        assert_eq!(event.bid_change(), Some(dec!(0.5)));
+        assert_eq!(event.new_bid, Some((dec!(2.5), dec!(2.0))));
+    }
+
     #[test]
     fn update_by_full_snapshot() {
         // Construct main object
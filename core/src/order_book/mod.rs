@@ -1,3 +1,6 @@
+pub mod deduplication;
+pub mod delta_codec;
+pub mod delta_recorder;
 pub mod event;
 pub mod local_order_book_snapshot;
 pub mod local_snapshot_service;
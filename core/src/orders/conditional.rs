@@ -0,0 +1,804 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use mmb_utils::{impl_u64_id, time::get_atomic_current_secs};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, MarketAccountId, Price};
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::misc::state_file::StateFile;
+use crate::misc::time::time_manager;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::event::OrderEventType;
+use crate::orders::order::{
+    ClientOrderId, OrderCreating, OrderExecutionType, OrderHeader, OrderSide, OrderSnapshot,
+    OrderType,
+};
+use crate::orders::trailing_stop::PriceReference;
+use crate::settings::ConditionalOrdersSettings;
+
+const TIME_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+impl_u64_id!(ConditionalOrderId);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TouchDirection {
+    AtOrAbove,
+    AtOrBelow,
+}
+
+/// Condition of order creation. Once condition is satisfied it stays satisfied
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum OrderCondition {
+    PriceTouched {
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        reference: PriceReference,
+        price: Price,
+        direction: TouchDirection,
+    },
+    TimeReached(DateTime),
+    /// Order is completely filled, so position is opened
+    OrderFilled(ClientOrderId),
+}
+
+/// Market state change which conditions are checked by
+#[derive(Debug, Clone)]
+enum ConditionUpdate {
+    Price {
+        market_account_id: MarketAccountId,
+        reference: PriceReference,
+        price: Price,
+    },
+    Time(DateTime),
+    Order {
+        client_order_id: ClientOrderId,
+        amount: Amount,
+        filled_amount: Amount,
+        is_finished: bool,
+    },
+}
+
+impl ConditionUpdate {
+    fn from_order(order: &OrderSnapshot) -> Self {
+        ConditionUpdate::Order {
+            client_order_id: order.header.client_order_id.clone(),
+            amount: order.amount(),
+            filled_amount: order.filled_amount(),
+            is_finished: order.is_finished(),
+        }
+    }
+}
+
+impl OrderCondition {
+    fn is_satisfied_by(&self, update: &ConditionUpdate) -> bool {
+        match (self, update) {
+            (
+                OrderCondition::PriceTouched {
+                    exchange_account_id,
+                    currency_pair,
+                    reference,
+                    price,
+                    direction,
+                },
+                ConditionUpdate::Price {
+                    market_account_id: updated_market,
+                    reference: updated_reference,
+                    price: updated_price,
+                },
+            ) => {
+                *exchange_account_id == updated_market.exchange_account_id
+                    && *currency_pair == updated_market.currency_pair
+                    && reference == updated_reference
+                    && match direction {
+                        TouchDirection::AtOrAbove => updated_price >= price,
+                        TouchDirection::AtOrBelow => updated_price <= price,
+                    }
+            }
+            (OrderCondition::TimeReached(time), ConditionUpdate::Time(now)) => now >= time,
+            (
+                OrderCondition::OrderFilled(client_order_id),
+                ConditionUpdate::Order {
+                    client_order_id: updated,
+                    amount,
+                    filled_amount,
+                    ..
+                },
+            ) => client_order_id == updated && filled_amount >= amount,
+            _ => false,
+        }
+    }
+}
+
+/// Order which is created when conditions are satisfied
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct OrderTemplate {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    /// Only `Limit` and `Market` orders are supported
+    pub order_type: OrderType,
+    pub side: OrderSide,
+    pub amount: Amount,
+    /// Price of limit order or expected price of market order
+    pub price: Price,
+    pub strategy_name: String,
+}
+
+impl OrderTemplate {
+    fn to_order_creating(&self, client_order_id: ClientOrderId) -> OrderCreating {
+        OrderCreating {
+            header: OrderHeader::new(
+                client_order_id,
+                time_manager::now(),
+                self.exchange_account_id,
+                self.currency_pair,
+                self.order_type,
+                self.side,
+                self.amount,
+                OrderExecutionType::None,
+                None,
+                None,
+                self.strategy_name.clone(),
+            ),
+            price: self.price,
+        }
+    }
+}
+
+/// Exit orders of position opened by entry order. They are armed for every fill of entry order with
+/// filled amount: take profit limit order is created at once, stop loss market order is created
+/// when price touches stop loss price. Stop loss is reduced by filled amount of its take profit,
+/// and triggered stop loss cancels its take profit
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BracketTemplate {
+    pub take_profit_price: Option<Price>,
+    pub stop_loss_price: Option<Price>,
+    pub reference: PriceReference,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ConditionalOrder {
+    pub id: ConditionalOrderId,
+    /// Conditions which aren't satisfied yet. They are satisfied one by one in order of the list,
+    /// e.g. stop loss price is watched only after position is opened.
+    /// Order is created when all of them are satisfied
+    pub conditions: Vec<OrderCondition>,
+    pub order: OrderTemplate,
+    /// Client order id which is assigned to order when it's created
+    pub client_order_id: ClientOrderId,
+    pub bracket: Option<BracketTemplate>,
+    /// Linked order of bracket: it's canceled when this order is created and
+    /// amount of this conditional order is reduced by filled amount of linked order
+    pub one_cancels_other: Option<ClientOrderId>,
+}
+
+/// Triggered entry order of bracket which exit orders are armed for its fills
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ArmedBracket {
+    pub entry: ConditionalOrder,
+    /// Filled amount of entry order which exit orders are already created for
+    pub armed_amount: Amount,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ConditionalOrdersState {
+    orders: Vec<ConditionalOrder>,
+    brackets: Vec<ArmedBracket>,
+}
+
+impl ConditionalOrder {
+    pub fn new(
+        conditions: Vec<OrderCondition>,
+        order: OrderTemplate,
+        bracket: Option<BracketTemplate>,
+    ) -> Self {
+        ConditionalOrder {
+            id: ConditionalOrderId::generate(),
+            conditions,
+            order,
+            client_order_id: ClientOrderId::unique_id(),
+            bracket,
+            one_cancels_other: None,
+        }
+    }
+
+    /// Exit orders of bracket for filled amount of this order
+    fn bracket_children(&self, filled_amount: Amount) -> Vec<ConditionalOrder> {
+        let bracket = match &self.bracket {
+            Some(bracket) => bracket,
+            None => return vec![],
+        };
+
+        let exit_template = |order_type, price| OrderTemplate {
+            order_type,
+            side: self.order.side.change_side(),
+            amount: filled_amount,
+            price,
+            ..self.order.clone()
+        };
+
+        let take_profit = bracket.take_profit_price.map(|price| {
+            ConditionalOrder::new(vec![], exit_template(OrderType::Limit, price), None)
+        });
+
+        let stop_loss = bracket.stop_loss_price.map(|price| {
+            let direction = match self.order.side {
+                OrderSide::Buy => TouchDirection::AtOrBelow,
+                OrderSide::Sell => TouchDirection::AtOrAbove,
+            };
+            let price_touched = OrderCondition::PriceTouched {
+                exchange_account_id: self.order.exchange_account_id,
+                currency_pair: self.order.currency_pair,
+                reference: bracket.reference,
+                price,
+                direction,
+            };
+
+            let mut stop_loss = ConditionalOrder::new(
+                vec![price_touched],
+                exit_template(OrderType::Market, price),
+                None,
+            );
+            stop_loss.one_cancels_other = take_profit.as_ref().map(|x| x.client_order_id.clone());
+            stop_loss
+        });
+
+        take_profit.into_iter().chain(stop_loss).collect()
+    }
+}
+
+/// Conditional orders managed by core: if-touched, time scheduled and bracket orders.
+/// Conditional orders are saved to state file if it's configured, so they survive engine restarts
+pub struct ConditionalOrdersManager {
+    state_file: Option<StateFile>,
+    orders: Mutex<HashMap<ConditionalOrderId, ConditionalOrder>>,
+    brackets: Mutex<HashMap<ClientOrderId, ArmedBracket>>,
+}
+
+impl ConditionalOrdersManager {
+    pub fn new(settings: Option<&ConditionalOrdersSettings>) -> Result<Self> {
+        let state_file = settings.map(|x| StateFile::new(x.state_file.clone()));
+        let state = match &state_file {
+            Some(state_file) => state_file
+                .load::<ConditionalOrdersState>()
+                .context("Unable to load conditional orders state")?
+                .unwrap_or_default(),
+            None => ConditionalOrdersState::default(),
+        };
+
+        Ok(ConditionalOrdersManager {
+            state_file,
+            orders: Mutex::new(state.orders.into_iter().map(|x| (x.id, x)).collect()),
+            brackets: Mutex::new(
+                state
+                    .brackets
+                    .into_iter()
+                    .map(|x| (x.entry.client_order_id.clone(), x))
+                    .collect(),
+            ),
+        })
+    }
+
+    pub fn start(
+        settings: Option<&ConditionalOrdersSettings>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<Self>> {
+        let manager = Arc::new(Self::new(settings)?);
+
+        let _ = spawn_future(
+            "Conditional orders events handling",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            manager
+                .clone()
+                .handle_events(exchanges, events_receiver, cancellation_token),
+        );
+
+        Ok(manager)
+    }
+
+    pub fn submit(&self, order: ConditionalOrder) -> Result<ConditionalOrderId> {
+        match order.order.order_type {
+            OrderType::Market | OrderType::Limit => {}
+            order_type => bail!("Conditional order can't create order of type {order_type:?}"),
+        }
+        if order.conditions.is_empty() {
+            bail!("Conditional order {} should have conditions", order.id);
+        }
+
+        let id = order.id;
+        log::info!("Conditional order {id} is submitted: {order:?}");
+        let mut orders = self.orders.lock();
+        let _ = orders.insert(id, order);
+        self.save(orders);
+
+        Ok(id)
+    }
+
+    pub fn cancel(&self, id: ConditionalOrderId) -> bool {
+        let mut orders = self.orders.lock();
+        let is_removed = orders.remove(&id).is_some();
+        if is_removed {
+            self.save(orders);
+        }
+
+        is_removed
+    }
+
+    pub fn get_all(&self) -> Vec<ConditionalOrder> {
+        self.orders.lock().values().cloned().collect()
+    }
+
+    pub fn get_armed_brackets(&self) -> Vec<ArmedBracket> {
+        self.brackets.lock().values().cloned().collect()
+    }
+
+    /// Removes satisfied conditions and returns triggered orders. Triggered bracket entries are
+    /// armed, so their exit orders are created for every fill of entry order
+    fn update(&self, update: &ConditionUpdate) -> Vec<ConditionalOrder> {
+        let mut orders = self.orders.lock();
+        let mut is_changed = false;
+        let mut triggered = vec![];
+
+        if let ConditionUpdate::Order {
+            client_order_id,
+            amount,
+            filled_amount,
+            is_finished,
+        } = update
+        {
+            let remaining_amount = amount - filled_amount;
+            let count = orders.len();
+            orders.retain(|_, x| {
+                x.one_cancels_other.as_ref() != Some(client_order_id) || !remaining_amount.is_zero()
+            });
+            is_changed = count != orders.len();
+            for order in orders
+                .values_mut()
+                .filter(|x| x.one_cancels_other.as_ref() == Some(client_order_id))
+                .filter(|x| x.order.amount != remaining_amount)
+            {
+                order.order.amount = remaining_amount;
+                is_changed = true;
+            }
+
+            let mut brackets = self.brackets.lock();
+            if let Some(bracket) = brackets.get_mut(client_order_id) {
+                let newly_filled = filled_amount - bracket.armed_amount;
+                if newly_filled > Amount::ZERO {
+                    bracket.armed_amount = *filled_amount;
+                    for child in bracket.entry.bracket_children(newly_filled) {
+                        match child.conditions.is_empty() {
+                            true => triggered.push(child),
+                            false => {
+                                let _ = orders.insert(child.id, child);
+                            }
+                        }
+                    }
+                    is_changed = true;
+                }
+                if *is_finished {
+                    let _ = brackets.remove(client_order_id);
+                    is_changed = true;
+                }
+            }
+        }
+
+        let mut satisfied = vec![];
+        for order in orders.values_mut() {
+            let count = order.conditions.len();
+            while order
+                .conditions
+                .first()
+                .is_some_and(|x| x.is_satisfied_by(update))
+            {
+                let _ = order.conditions.remove(0);
+            }
+            if count == order.conditions.len() {
+                continue;
+            }
+
+            is_changed = true;
+            if order.conditions.is_empty() {
+                satisfied.push(order.clone());
+            }
+        }
+
+        for order in &satisfied {
+            let _ = orders.remove(&order.id);
+            if order.bracket.is_some() {
+                let bracket = ArmedBracket {
+                    entry: order.clone(),
+                    armed_amount: Amount::ZERO,
+                };
+                let _ = self
+                    .brackets
+                    .lock()
+                    .insert(order.client_order_id.clone(), bracket);
+            }
+        }
+        triggered.extend(satisfied);
+
+        if is_changed {
+            self.save(orders);
+        }
+
+        triggered
+    }
+
+    /// Orders which fills conditional orders are waiting for
+    fn watched_orders(&self) -> Vec<ClientOrderId> {
+        let orders = self.orders.lock();
+        let conditions = orders.values().filter_map(|x| match x.conditions.first() {
+            Some(OrderCondition::OrderFilled(client_order_id)) => Some(client_order_id.clone()),
+            _ => None,
+        });
+        let linked = orders.values().filter_map(|x| x.one_cancels_other.clone());
+
+        conditions
+            .chain(linked)
+            .chain(self.brackets.lock().keys().cloned())
+            .collect()
+    }
+
+    /// Updates conditions by watched orders of order pools, so fills of orders which events
+    /// are skipped aren't lost
+    fn sync_with_order_pools(
+        &self,
+        exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+        cancellation_token: &CancellationToken,
+    ) {
+        for client_order_id in self.watched_orders() {
+            let order = exchanges.iter().find_map(|exchange| {
+                exchange
+                    .orders
+                    .cache_by_client_id
+                    .get(&client_order_id)
+                    .map(|x| x.clone())
+            });
+            if let Some(order) = order {
+                let update = order.fn_ref(ConditionUpdate::from_order);
+                self.execute(self.update(&update), exchanges, cancellation_token);
+            }
+        }
+    }
+
+    /// State is written after locks of conditional orders are released
+    fn save(&self, orders: parking_lot::MutexGuard<HashMap<ConditionalOrderId, ConditionalOrder>>) {
+        let state_file = match &self.state_file {
+            Some(state_file) => state_file,
+            None => return,
+        };
+
+        let state = ConditionalOrdersState {
+            orders: orders.values().cloned().collect(),
+            brackets: self.brackets.lock().values().cloned().collect(),
+        };
+        let serialized = state_file.serialize(&state);
+        drop(orders);
+
+        let result = serialized.and_then(|(version, content)| state_file.write(version, &content));
+        if let Err(error) = result {
+            log::error!(
+                "Failed to save conditional orders to {}: {error:?}",
+                state_file.path().display()
+            );
+        }
+    }
+
+    async fn handle_events(
+        self: Arc<Self>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let mut snapshots = LocalSnapshotsService::default();
+        let mut time_check_interval = tokio::time::interval(TIME_CHECK_PERIOD);
+
+        loop {
+            let event = tokio::select! {
+                event = events_receiver.recv() => match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Conditional orders manager skipped {skipped} exchange events");
+                        self.sync_with_order_pools(&exchanges, &cancellation_token);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                _ = time_check_interval.tick() => {
+                    let update = ConditionUpdate::Time(time_manager::now());
+                    self.execute(self.update(&update), &exchanges, &cancellation_token);
+                    // fills of orders are checked periodically too, because order events
+                    // can be skipped
+                    self.sync_with_order_pools(&exchanges, &cancellation_token);
+                    continue;
+                }
+            };
+
+            let update = match event {
+                ExchangeEvent::Trades(trades_event) => match trades_event.trades.last() {
+                    Some(trade) => ConditionUpdate::Price {
+                        market_account_id: MarketAccountId::new(
+                            trades_event.exchange_account_id,
+                            trades_event.currency_pair,
+                        ),
+                        reference: PriceReference::LastTrade,
+                        price: trade.price,
+                    },
+                    None => continue,
+                },
+                ExchangeEvent::OrderBookEvent(order_book_event) => {
+                    let market_account_id = match snapshots.update(order_book_event) {
+                        Some(market_account_id) => market_account_id,
+                        None => continue,
+                    };
+                    let market_id = market_account_id.market_id();
+                    match snapshots
+                        .get_snapshot(market_id)
+                        .and_then(|x| x.calculate_middle_price(market_id))
+                    {
+                        Some(price) => ConditionUpdate::Price {
+                            market_account_id,
+                            reference: PriceReference::MiddlePrice,
+                            price,
+                        },
+                        None => continue,
+                    }
+                }
+                ExchangeEvent::OrderEvent(order_event) => match order_event.event_type {
+                    OrderEventType::OrderFilled { cloned_order }
+                    | OrderEventType::OrderCompleted { cloned_order } => {
+                        ConditionUpdate::from_order(&cloned_order)
+                    }
+                    _ => continue,
+                },
+                _ => continue,
+            };
+
+            self.execute(self.update(&update), &exchanges, &cancellation_token);
+        }
+    }
+
+    fn execute(
+        &self,
+        triggered: Vec<ConditionalOrder>,
+        exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+        cancellation_token: &CancellationToken,
+    ) {
+        for order in triggered {
+            let exchange = match exchanges.get(&order.order.exchange_account_id) {
+                Some(exchange) => exchange.clone(),
+                None => {
+                    log::error!(
+                        "Conditional order {} is dropped because exchange {} isn't configured",
+                        order.id,
+                        order.order.exchange_account_id
+                    );
+                    continue;
+                }
+            };
+
+            log::info!("Conditional order {} is triggered", order.id);
+            let order_to_create = order.order.to_order_creating(order.client_order_id.clone());
+            let linked_order = order
+                .one_cancels_other
+                .and_then(|x| exchange.orders.get_by_client_id(&x));
+            let cancellation_token = cancellation_token.clone();
+            let action = async move {
+                if let Some(order_cancelling) = linked_order.and_then(|x| x.to_order_cancelling()) {
+                    let _ = exchange
+                        .cancel_order(order_cancelling, cancellation_token.clone())
+                        .await;
+                }
+
+                exchange
+                    .create_order(order_to_create, None, cancellation_token)
+                    .await
+                    .map(|_| ())
+            };
+            let _ = spawn_future(
+                "Conditional order creation",
+                SpawnFutureFlags::STOP_BY_TOKEN,
+                action,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn market_account_id() -> MarketAccountId {
+        MarketAccountId::new(
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    fn template() -> OrderTemplate {
+        OrderTemplate {
+            exchange_account_id: market_account_id().exchange_account_id,
+            currency_pair: market_account_id().currency_pair,
+            order_type: OrderType::Limit,
+            side: OrderSide::Buy,
+            amount: dec!(1),
+            price: dec!(100),
+            strategy_name: "test".to_owned(),
+        }
+    }
+
+    fn price_update(price: Price) -> ConditionUpdate {
+        ConditionUpdate::Price {
+            market_account_id: market_account_id(),
+            reference: PriceReference::MiddlePrice,
+            price,
+        }
+    }
+
+    #[test]
+    pub fn if_touched_order() {
+        let manager = ConditionalOrdersManager::new(None).expect("in test");
+        let condition = OrderCondition::PriceTouched {
+            exchange_account_id: market_account_id().exchange_account_id,
+            currency_pair: market_account_id().currency_pair,
+            reference: PriceReference::MiddlePrice,
+            price: dec!(100),
+            direction: TouchDirection::AtOrBelow,
+        };
+        let id = manager
+            .submit(ConditionalOrder::new(vec![condition], template(), None))
+            .expect("in test");
+
+        assert!(manager.update(&price_update(dec!(101))).is_empty());
+        let triggered = manager.update(&price_update(dec!(100)));
+
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].id, id);
+        assert!(manager.get_all().is_empty());
+    }
+
+    fn order_update(
+        client_order_id: &ClientOrderId,
+        amount: Amount,
+        filled_amount: Amount,
+    ) -> ConditionUpdate {
+        ConditionUpdate::Order {
+            client_order_id: client_order_id.clone(),
+            amount,
+            filled_amount,
+            is_finished: filled_amount == amount,
+        }
+    }
+
+    fn triggered_bracket_entry(manager: &ConditionalOrdersManager) -> ClientOrderId {
+        let bracket = BracketTemplate {
+            take_profit_price: Some(dec!(110)),
+            stop_loss_price: Some(dec!(95)),
+            reference: PriceReference::MiddlePrice,
+        };
+        let entry = ConditionalOrder::new(
+            vec![OrderCondition::TimeReached(time_manager::now())],
+            template(),
+            Some(bracket),
+        );
+        let entry_client_order_id = entry.client_order_id.clone();
+        let _ = manager.submit(entry).expect("in test");
+
+        let triggered = manager.update(&ConditionUpdate::Time(time_manager::now()));
+        assert_eq!(triggered.len(), 1);
+        assert!(manager.get_all().is_empty());
+        assert_eq!(manager.get_armed_brackets().len(), 1);
+
+        entry_client_order_id
+    }
+
+    #[test]
+    pub fn bracket_order() {
+        let manager = ConditionalOrdersManager::new(None).expect("in test");
+        let entry_client_order_id = triggered_bracket_entry(&manager);
+
+        // stop loss isn't active before position is opened
+        assert!(manager.update(&price_update(dec!(90))).is_empty());
+
+        let triggered = manager.update(&order_update(&entry_client_order_id, dec!(1), dec!(1)));
+        assert_eq!(triggered.len(), 1);
+        let take_profit = &triggered[0];
+        assert_eq!(take_profit.order.side, OrderSide::Sell);
+        assert_eq!(take_profit.order.price, dec!(110));
+        assert_eq!(take_profit.order.amount, dec!(1));
+        assert_eq!(manager.get_all().len(), 1);
+        assert!(manager.get_armed_brackets().is_empty());
+
+        // filled take profit drops stop loss
+        let _ = manager.update(&order_update(
+            &take_profit.client_order_id,
+            dec!(1),
+            dec!(1),
+        ));
+        assert!(manager.get_all().is_empty());
+    }
+
+    #[test]
+    pub fn bracket_exits_are_armed_for_every_fill() {
+        let manager = ConditionalOrdersManager::new(None).expect("in test");
+        let entry_client_order_id = triggered_bracket_entry(&manager);
+
+        let triggered = manager.update(&order_update(&entry_client_order_id, dec!(1), dec!(0.3)));
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].order.amount, dec!(0.3));
+        let first_take_profit = triggered[0].client_order_id.clone();
+
+        // repeated update of the same fill doesn't arm exits again
+        let repeated = manager.update(&order_update(&entry_client_order_id, dec!(1), dec!(0.3)));
+        assert!(repeated.is_empty());
+
+        let triggered = manager.update(&order_update(&entry_client_order_id, dec!(1), dec!(1)));
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].order.amount, dec!(0.7));
+        assert!(manager.get_armed_brackets().is_empty());
+
+        let stop_losses = manager.get_all();
+        assert_eq!(stop_losses.len(), 2);
+        assert_eq!(
+            stop_losses.iter().map(|x| x.order.amount).sum::<Amount>(),
+            dec!(1)
+        );
+
+        // stop loss is reduced by partially filled take profit
+        let _ = manager.update(&order_update(&first_take_profit, dec!(0.3), dec!(0.1)));
+        let stop_loss = manager
+            .get_all()
+            .into_iter()
+            .find(|x| x.one_cancels_other.as_ref() == Some(&first_take_profit))
+            .expect("in test");
+        assert_eq!(stop_loss.order.amount, dec!(0.2));
+
+        let triggered = manager.update(&price_update(dec!(95)));
+        assert_eq!(triggered.len(), 2);
+        assert!(triggered
+            .iter()
+            .all(|x| x.order.order_type == OrderType::Market));
+        assert!(manager.get_all().is_empty());
+    }
+
+    #[test]
+    pub fn state_is_restored_from_state_file() {
+        let state_file = std::env::temp_dir().join(format!(
+            "conditional_orders_{}.json",
+            ClientOrderId::unique_id()
+        ));
+        let settings = ConditionalOrdersSettings {
+            state_file: state_file.clone(),
+        };
+
+        let manager = ConditionalOrdersManager::new(Some(&settings)).expect("in test");
+        let entry_client_order_id = triggered_bracket_entry(&manager);
+        let _ = manager.update(&order_update(&entry_client_order_id, dec!(1), dec!(0.5)));
+
+        let restored = ConditionalOrdersManager::new(Some(&settings)).expect("in test");
+        assert_eq!(restored.get_all(), manager.get_all());
+        assert_eq!(restored.get_armed_brackets(), manager.get_armed_brackets());
+        assert_eq!(restored.get_armed_brackets()[0].armed_amount, dec!(0.5));
+
+        let _ = std::fs::remove_file(state_file);
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use crate::exchanges::common::{CurrencyCode, CurrencyPair};
+use crate::settings::CurrencyRestrictionsSettings;
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum CurrencyRestrictionError {
+    #[error("currency {0} is denied")]
+    CurrencyDenied(CurrencyCode),
+    #[error("currency {0} isn't in allowed list")]
+    CurrencyNotAllowed(CurrencyCode),
+    #[error("currency pair {0} is denied")]
+    CurrencyPairDenied(CurrencyPair),
+    #[error("currency pair {0} isn't in allowed list")]
+    CurrencyPairNotAllowed(CurrencyPair),
+}
+
+/// Engine level allow and deny lists of currencies and currency pairs for compliance.
+/// Deny lists have priority over allow lists
+#[derive(Debug, Default, Clone)]
+pub struct CurrencyRestrictions {
+    allowed_currencies: HashSet<CurrencyCode>,
+    denied_currencies: HashSet<CurrencyCode>,
+    allowed_currency_pairs: HashSet<CurrencyPair>,
+    denied_currency_pairs: HashSet<CurrencyPair>,
+}
+
+impl CurrencyRestrictions {
+    pub fn new(settings: &CurrencyRestrictionsSettings) -> Self {
+        CurrencyRestrictions {
+            allowed_currencies: settings.allowed_currencies.iter().copied().collect(),
+            denied_currencies: settings.denied_currencies.iter().copied().collect(),
+            allowed_currency_pairs: settings.allowed_currency_pairs.iter().copied().collect(),
+            denied_currency_pairs: settings.denied_currency_pairs.iter().copied().collect(),
+        }
+    }
+
+    pub fn check(&self, currency_pair: CurrencyPair) -> Result<(), CurrencyRestrictionError> {
+        use CurrencyRestrictionError::*;
+
+        if self.denied_currency_pairs.contains(&currency_pair) {
+            return Err(CurrencyPairDenied(currency_pair));
+        }
+
+        let codes = currency_pair.to_codes();
+        for currency_code in [codes.base, codes.quote] {
+            if self.denied_currencies.contains(&currency_code) {
+                return Err(CurrencyDenied(currency_code));
+            }
+        }
+
+        if !self.allowed_currency_pairs.is_empty()
+            && !self.allowed_currency_pairs.contains(&currency_pair)
+        {
+            return Err(CurrencyPairNotAllowed(currency_pair));
+        }
+
+        if !self.allowed_currencies.is_empty() {
+            for currency_code in [codes.base, codes.quote] {
+                if !self.allowed_currencies.contains(&currency_code) {
+                    return Err(CurrencyNotAllowed(currency_code));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn currency_pair(base: &str, quote: &str) -> CurrencyPair {
+        CurrencyPair::from_codes(base.into(), quote.into())
+    }
+
+    #[test]
+    pub fn everything_is_allowed_by_default() {
+        let restrictions = CurrencyRestrictions::default();
+
+        assert_eq!(restrictions.check(currency_pair("btc", "usdt")), Ok(()));
+    }
+
+    #[rstest]
+    #[case("btc", "usdt", None)]
+    #[case("xmr", "usdt", Some(CurrencyRestrictionError::CurrencyDenied("xmr".into())))]
+    #[case(
+        "eth",
+        "btc",
+        Some(CurrencyRestrictionError::CurrencyPairDenied(currency_pair("eth", "btc")))
+    )]
+    #[case("eth", "usdt", Some(CurrencyRestrictionError::CurrencyNotAllowed("eth".into())))]
+    pub fn check_restrictions(
+        #[case] base: &str,
+        #[case] quote: &str,
+        #[case] expected_error: Option<CurrencyRestrictionError>,
+    ) {
+        let restrictions = CurrencyRestrictions::new(&CurrencyRestrictionsSettings {
+            allowed_currencies: vec!["btc".into(), "usdt".into(), "xmr".into()],
+            denied_currencies: vec!["xmr".into()],
+            allowed_currency_pairs: vec![],
+            denied_currency_pairs: vec![currency_pair("eth", "btc")],
+        });
+
+        assert_eq!(
+            restrictions.check(currency_pair(base, quote)).err(),
+            expected_error
+        );
+    }
+
+    #[test]
+    pub fn allowed_currency_pairs() {
+        let restrictions = CurrencyRestrictions::new(&CurrencyRestrictionsSettings {
+            allowed_currency_pairs: vec![currency_pair("btc", "usdt")],
+            ..Default::default()
+        });
+
+        assert_eq!(restrictions.check(currency_pair("btc", "usdt")), Ok(()));
+        assert_eq!(
+            restrictions.check(currency_pair("eth", "usdt")),
+            Err(CurrencyRestrictionError::CurrencyPairNotAllowed(
+                currency_pair("eth", "usdt")
+            ))
+        );
+    }
+}
@@ -9,6 +9,7 @@ use crate::orders::pool::OrderRef;
 pub enum OrderEventType {
     CreateOrderSucceeded,
     CreateOrderFailed,
+    AckTimeout,
     OrderFilled { cloned_order: Arc<OrderSnapshot> },
     OrderCompleted { cloned_order: Arc<OrderSnapshot> },
     CancelOrderSucceeded,
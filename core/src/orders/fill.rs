@@ -15,6 +15,8 @@ pub enum OrderFillType {
     Liquidation = 2,
     Funding = 3,
     ClosePosition = 4,
+    /// Fill by order of another strategy matched by internal crossing engine
+    Internal = 5,
 }
 
 impl OrderFillType {
@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::common::{ExchangeAccountId, ExchangeErrorType};
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::exchange::{Exchange, RequestResult};
+use crate::infrastructure::spawn_future;
+use crate::misc::state_file::StateFile;
+use crate::misc::time::time_manager;
+use crate::orders::event::{OrderEvent, OrderEventType};
+use crate::orders::order::{
+    ClientOrderId, ExchangeOrderId, OrderCancelling, OrderHeader, OrderStatus,
+};
+use crate::orders::pool::OrderRef;
+use crate::settings::GoodTillDateSettings;
+
+const EXPIRATION_CHECK_PERIOD: Duration = Duration::from_secs(1);
+/// Orders which exchange expires itself are canceled by core only if they are still open
+/// after this delay in seconds
+const NATIVE_EXPIRATION_GRACE_SECS: i64 = 10;
+const MAX_RETRY_DELAY_SECS: i64 = 60;
+
+/// Created order with good-till-date time in force
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpiringOrder {
+    pub header: Arc<OrderHeader>,
+    pub exchange_order_id: ExchangeOrderId,
+    pub expire_time: DateTime,
+    /// Time of the next cancellation attempt. It's expire time for the first attempt
+    #[serde(default)]
+    pub cancel_time: Option<DateTime>,
+    #[serde(default)]
+    pub failed_attempts: u32,
+}
+
+impl ExpiringOrder {
+    pub fn new(
+        header: Arc<OrderHeader>,
+        exchange_order_id: ExchangeOrderId,
+        expire_time: DateTime,
+    ) -> Self {
+        ExpiringOrder {
+            header,
+            exchange_order_id,
+            expire_time,
+            cancel_time: None,
+            failed_attempts: 0,
+        }
+    }
+
+    fn cancel_time(&self) -> DateTime {
+        self.cancel_time.unwrap_or(self.expire_time)
+    }
+}
+
+/// Delay of the next cancellation attempt which is doubled after each failed attempt
+fn retry_delay(failed_attempts: u32) -> chrono::Duration {
+    let seconds = 2i64
+        .checked_pow(failed_attempts)
+        .map_or(MAX_RETRY_DELAY_SECS, |x| x.min(MAX_RETRY_DELAY_SECS));
+    chrono::Duration::seconds(seconds)
+}
+
+/// Good-till-date orders: orders are canceled by core when their expire time comes if exchange
+/// doesn't support good-till-date time in force, otherwise core cancels them only if exchange
+/// hasn't expired them within grace period. Orders are kept in schedule until they are canceled
+/// or finished, failed cancellations are retried with backoff. Scheduled expirations are saved
+/// to state file if it's configured, so they survive engine restarts
+pub struct GoodTillDateScheduler {
+    state_file: Option<StateFile>,
+    orders: Mutex<HashMap<ClientOrderId, ExpiringOrder>>,
+}
+
+impl GoodTillDateScheduler {
+    pub fn new(settings: Option<&GoodTillDateSettings>) -> Result<Self> {
+        let state_file = settings.map(|x| StateFile::new(x.state_file.clone()));
+        let orders = match &state_file {
+            Some(state_file) => state_file
+                .load::<Vec<ExpiringOrder>>()
+                .context("Unable to load good-till-date state")?
+                .unwrap_or_default()
+                .into_iter()
+                .map(|x| (x.header.client_order_id.clone(), x))
+                .collect(),
+            None => HashMap::new(),
+        };
+
+        Ok(GoodTillDateScheduler {
+            state_file,
+            orders: Mutex::new(orders),
+        })
+    }
+
+    pub fn start(
+        settings: Option<&GoodTillDateSettings>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<Self>> {
+        let scheduler = Arc::new(Self::new(settings)?);
+
+        let _ = spawn_future(
+            "Good-till-date orders expiration",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            scheduler
+                .clone()
+                .run(exchanges, events_receiver, cancellation_token),
+        );
+
+        Ok(scheduler)
+    }
+
+    /// Schedules cancellation of created order if it has expire time. It's called by
+    /// `Exchange::create_order`, so orders are scheduled even if order events are lagged
+    pub fn schedule_created_order(&self, order: &OrderRef, is_native: bool) {
+        let (header, exchange_order_id, status) = order.fn_ref(|x| {
+            (
+                x.header.clone(),
+                x.props.exchange_order_id.clone(),
+                x.status(),
+            )
+        });
+        let (expire_time, exchange_order_id) = match (header.expire_time, exchange_order_id) {
+            (Some(expire_time), Some(exchange_order_id)) if status == OrderStatus::Created => {
+                (expire_time, exchange_order_id)
+            }
+            _ => return,
+        };
+
+        let mut expiring_order = ExpiringOrder::new(header, exchange_order_id, expire_time);
+        if is_native {
+            expiring_order.cancel_time =
+                Some(expire_time + chrono::Duration::seconds(NATIVE_EXPIRATION_GRACE_SECS));
+        }
+        self.schedule(expiring_order);
+    }
+
+    pub fn schedule(&self, order: ExpiringOrder) {
+        log::info!(
+            "Order {} will be canceled at {} by good-till-date scheduler",
+            order.header.client_order_id,
+            order.cancel_time()
+        );
+
+        let mut orders = self.orders.lock();
+        let _ = orders.insert(order.header.client_order_id.clone(), order);
+        self.save(orders);
+    }
+
+    pub fn unschedule(&self, client_order_id: &ClientOrderId) {
+        let mut orders = self.orders.lock();
+        if orders.remove(client_order_id).is_some() {
+            self.save(orders);
+        }
+    }
+
+    pub fn get_all(&self) -> Vec<ExpiringOrder> {
+        self.orders.lock().values().cloned().collect()
+    }
+
+    /// Returns orders which cancellation is due. They stay in schedule with the next attempt
+    /// postponed by retry delay until they are unscheduled after successful cancellation
+    pub fn take_due(&self, now: DateTime) -> Vec<ExpiringOrder> {
+        let mut orders = self.orders.lock();
+        let mut due = vec![];
+        for order in orders.values_mut().filter(|x| x.cancel_time() <= now) {
+            due.push(order.clone());
+            order.cancel_time = Some(now + retry_delay(order.failed_attempts));
+            order.failed_attempts += 1;
+        }
+
+        if !due.is_empty() {
+            self.save(orders);
+        }
+
+        due
+    }
+
+    /// State is written after lock of orders is released
+    fn save(&self, orders: parking_lot::MutexGuard<HashMap<ClientOrderId, ExpiringOrder>>) {
+        let state_file = match &self.state_file {
+            Some(state_file) => state_file,
+            None => return,
+        };
+
+        let serialized = state_file.serialize(&orders.values().collect::<Vec<_>>());
+        drop(orders);
+
+        let result = serialized.and_then(|(version, content)| state_file.write(version, &content));
+        if let Err(error) = result {
+            log::error!(
+                "Failed to save good-till-date state to {}: {error:?}",
+                state_file.path().display()
+            );
+        }
+    }
+
+    fn handle_order_event(&self, event: &OrderEvent) {
+        match event.event_type {
+            OrderEventType::CancelOrderSucceeded | OrderEventType::OrderCompleted { .. } => {
+                self.unschedule(&event.order.client_order_id())
+            }
+            _ => {}
+        }
+    }
+
+    /// Cancels order which cancellation is due. Order is unscheduled if it's canceled or
+    /// already finished, otherwise it's retried later
+    async fn cancel_expired(
+        &self,
+        order: ExpiringOrder,
+        exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+        cancellation_token: CancellationToken,
+    ) {
+        let client_order_id = order.header.client_order_id.clone();
+        let exchange = match exchanges.get(&order.header.exchange_account_id) {
+            Some(exchange) => exchange.clone(),
+            None => {
+                log::error!(
+                    "Expired order {client_order_id} isn't canceled because exchange {} isn't found",
+                    order.header.exchange_account_id
+                );
+                return self.unschedule(&client_order_id);
+            }
+        };
+
+        let is_finished = exchange
+            .orders
+            .cache_by_client_id
+            .get(&client_order_id)
+            .is_some_and(|x| x.is_finished());
+        if is_finished {
+            return self.unschedule(&client_order_id);
+        }
+
+        log::info!(
+            "Canceling expired good-till-date order {client_order_id} (attempt {})",
+            order.failed_attempts + 1
+        );
+        let cancel_result = exchange
+            .cancel_order(
+                OrderCancelling {
+                    header: order.header,
+                    exchange_order_id: order.exchange_order_id,
+                    extension_data: None,
+                },
+                cancellation_token,
+            )
+            .await;
+
+        match cancel_result.map(|x| x.outcome) {
+            Some(RequestResult::Success(_)) => self.unschedule(&client_order_id),
+            Some(RequestResult::Error(error))
+                if matches!(
+                    error.error_type,
+                    ExchangeErrorType::OrderNotFound | ExchangeErrorType::OrderCompleted
+                ) =>
+            {
+                self.unschedule(&client_order_id)
+            }
+            Some(RequestResult::Error(error)) => log::warn!(
+                "Failed to cancel expired order {client_order_id}, it will be retried: {error:?}"
+            ),
+            None => log::warn!(
+                "Cancellation of expired order {client_order_id} is stopped, it will be retried"
+            ),
+        }
+    }
+
+    async fn run(
+        self: Arc<Self>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let mut check_interval = tokio::time::interval(EXPIRATION_CHECK_PERIOD);
+
+        loop {
+            tokio::select! {
+                event = events_receiver.recv() => match event {
+                    Ok(ExchangeEvent::OrderEvent(order_event)) => self.handle_order_event(&order_event),
+                    Ok(_) => {}
+                    // finished orders which events are skipped are unscheduled by order pool
+                    // when their cancellation is due
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Good-till-date scheduler skipped {skipped} exchange events")
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                _ = check_interval.tick() => {
+                    for order in self.take_due(time_manager::now()) {
+                        self.cancel_expired(order, &exchanges, cancellation_token.clone()).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use crate::orders::order::{OrderExecutionType, OrderSide, OrderType};
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    fn expiring_order(client_order_id: &str, expire_time: DateTime) -> ExpiringOrder {
+        let header = OrderHeader::new(
+            client_order_id.into(),
+            Utc::now(),
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            OrderType::Limit,
+            OrderSide::Buy,
+            dec!(1),
+            OrderExecutionType::None,
+            None,
+            None,
+            "test".to_owned(),
+        )
+        .with_expire_time(expire_time);
+
+        ExpiringOrder::new(
+            header,
+            format!("exchange_{client_order_id}").as_str().into(),
+            expire_time,
+        )
+    }
+
+    #[test]
+    pub fn expired_orders_are_retried_with_backoff_until_unscheduled() {
+        let scheduler = GoodTillDateScheduler::new(None).expect("in test");
+        let now = Utc::now();
+        scheduler.schedule(expiring_order("first", now - chrono::Duration::seconds(1)));
+        scheduler.schedule(expiring_order(
+            "second",
+            now + chrono::Duration::seconds(10),
+        ));
+
+        let due = scheduler.take_due(now);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].header.client_order_id, "first".into());
+        assert!(scheduler.take_due(now).is_empty());
+
+        // failed cancellation is retried after 1 second, then after 2 seconds
+        let retry_time = now + chrono::Duration::seconds(1);
+        let due = scheduler.take_due(retry_time);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].failed_attempts, 1);
+        assert!(scheduler
+            .take_due(retry_time + chrono::Duration::seconds(1))
+            .is_empty());
+
+        scheduler.unschedule(&"first".into());
+        scheduler.unschedule(&"second".into());
+        assert!(scheduler
+            .take_due(now + chrono::Duration::seconds(120))
+            .is_empty());
+    }
+
+    #[test]
+    pub fn retry_delay_is_capped() {
+        assert_eq!(retry_delay(0), chrono::Duration::seconds(1));
+        assert_eq!(retry_delay(3), chrono::Duration::seconds(8));
+        assert_eq!(
+            retry_delay(100),
+            chrono::Duration::seconds(MAX_RETRY_DELAY_SECS)
+        );
+    }
+
+    #[test]
+    pub fn schedule_survives_restart() {
+        let state_file =
+            std::env::temp_dir().join(format!("good_till_date_{}.json", uuid::Uuid::new_v4()));
+        let settings = GoodTillDateSettings {
+            state_file: state_file.clone(),
+        };
+        let expire_time = Utc::now();
+
+        let scheduler = GoodTillDateScheduler::new(Some(&settings)).expect("in test");
+        scheduler.schedule(expiring_order("first", expire_time));
+        drop(scheduler);
+
+        let restarted = GoodTillDateScheduler::new(Some(&settings)).expect("in test");
+        let expired = restarted.take_due(expire_time);
+        let _ = std::fs::remove_file(state_file);
+
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].header.client_order_id, "first".into());
+        assert_eq!(expired[0].expire_time, expire_time);
+    }
+}
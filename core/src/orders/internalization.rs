@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use mmb_utils::cancellation_token::CancellationToken;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::exchanges::common::{Amount, CurrencyCode, ExchangeAccountId, MarketAccountId, Price};
+use crate::exchanges::general::exchange::Exchange;
+use crate::misc::time::time_manager;
+use crate::orders::event::OrderEventType;
+use crate::orders::fill::{OrderFill, OrderFillType};
+use crate::orders::order::{
+    ClientOrderId, OrderCreating, OrderFillRole, OrderSide, OrderStatus, OrderType,
+};
+use crate::orders::pool::OrderRef;
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use crate::settings::InternalizationSettings;
+
+/// Order can be crossed at middle price if its limit price allows it
+fn is_crossable(order_type: OrderType, side: OrderSide, price: Price, middle_price: Price) -> bool {
+    match (order_type, side) {
+        (OrderType::Market, _) => true,
+        (_, OrderSide::Buy) => price >= middle_price,
+        (_, OrderSide::Sell) => price <= middle_price,
+    }
+}
+
+/// Order which waits for internal counterparty
+struct CrossingInterest {
+    order_type: OrderType,
+    side: OrderSide,
+    price: Price,
+    strategy_name: String,
+    configuration_descriptor: ConfigurationDescriptor,
+    remaining: Amount,
+    internal_order: OrderRef,
+    filled_tx: Option<oneshot::Sender<()>>,
+}
+
+impl CrossingInterest {
+    fn new(
+        order: &OrderCreating,
+        configuration_descriptor: ConfigurationDescriptor,
+        internal_order: OrderRef,
+    ) -> Self {
+        CrossingInterest {
+            order_type: order.header.order_type,
+            side: order.header.side,
+            price: order.price,
+            strategy_name: order.header.strategy_name.clone(),
+            configuration_descriptor,
+            remaining: order.header.amount,
+            internal_order,
+            filled_tx: None,
+        }
+    }
+
+    fn client_order_id(&self) -> ClientOrderId {
+        self.internal_order.client_order_id()
+    }
+}
+
+/// Matches `incoming` against waiting interests of other strategies in order of their arrival.
+/// Returns internal orders of counterparties with their strategies and matched amounts. Fully
+/// matched interests are removed and their waiters are notified
+fn match_interests(
+    interests: &mut Vec<CrossingInterest>,
+    incoming: &mut CrossingInterest,
+    middle_price: Price,
+) -> Vec<(OrderRef, ConfigurationDescriptor, Amount)> {
+    let mut matches = vec![];
+    for interest in interests.iter_mut() {
+        if incoming.remaining.is_zero() {
+            break;
+        }
+
+        if interest.side == incoming.side
+            || interest.strategy_name == incoming.strategy_name
+            || !is_crossable(
+                interest.order_type,
+                interest.side,
+                interest.price,
+                middle_price,
+            )
+        {
+            continue;
+        }
+
+        let amount = incoming.remaining.min(interest.remaining);
+        interest.remaining -= amount;
+        incoming.remaining -= amount;
+        matches.push((
+            interest.internal_order.clone(),
+            interest.configuration_descriptor,
+            amount,
+        ));
+    }
+
+    interests.retain_mut(|interest| {
+        if !interest.remaining.is_zero() {
+            return true;
+        }
+
+        if let Some(filled_tx) = interest.filled_tx.take() {
+            let _ = filled_tx.send(());
+        }
+        false
+    });
+
+    matches
+}
+
+pub struct CrossingResult {
+    /// Order with internal fills at middle price. It has new client order id and it's added to
+    /// orders pool as completed order
+    pub internal_order: Option<OrderRef>,
+    /// Order with amount which isn't matched internally. It's sent to exchange with client order
+    /// id of submitted order
+    pub exchange_order: Option<OrderRef>,
+}
+
+/// Crosses opposite orders of different strategies on the same market at middle price, so both of
+/// them don't pay exchange fees. Order waits for internal counterparty for `max_wait_ms` and then its
+/// rest is sent to exchange.
+/// Internal fills are applied to balances of strategies by `BalanceManager` and reported to
+/// strategies, statistics and PnL by order events. Summary balances of exchange account aren't
+/// changed by them, because both sides of trade are on the same account
+pub struct InternalCrossingEngine {
+    max_wait: Duration,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    interests: Mutex<HashMap<MarketAccountId, Vec<CrossingInterest>>>,
+}
+
+impl InternalCrossingEngine {
+    pub fn new(
+        settings: &InternalizationSettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    ) -> Arc<Self> {
+        Arc::new(InternalCrossingEngine {
+            max_wait: Duration::from_millis(settings.max_wait_ms),
+            exchanges,
+            interests: Default::default(),
+        })
+    }
+
+    /// Used instead of `Exchange::create_order` for orders of strategies. Reservation of order is
+    /// released for internally filled amount, the rest of it is used by exchange order
+    pub async fn submit(
+        &self,
+        order: OrderCreating,
+        configuration_descriptor: ConfigurationDescriptor,
+        cancellation_token: CancellationToken,
+    ) -> Result<CrossingResult> {
+        let market_account_id = order.header.market_account_id();
+        let exchange = self
+            .exchanges
+            .get(&market_account_id.exchange_account_id)
+            .map(|x| x.clone())
+            .with_context(|| {
+                format!(
+                    "Exchange {} isn't found",
+                    market_account_id.exchange_account_id
+                )
+            })?;
+        let symbol = exchange
+            .symbols
+            .get(&market_account_id.currency_pair)
+            .map(|x| x.clone())
+            .with_context(|| format!("Symbol for {market_account_id:?} isn't found"))?;
+
+        let middle_price = Self::middle_price(&exchange, market_account_id).filter(|&price| {
+            is_crossable(
+                order.header.order_type,
+                order.header.side,
+                order.price,
+                price,
+            )
+        });
+        let middle_price = match middle_price {
+            Some(middle_price) => middle_price,
+            None => {
+                let exchange_order =
+                    Self::create_exchange_order(&exchange, &order, cancellation_token).await?;
+                return Ok(CrossingResult {
+                    internal_order: None,
+                    exchange_order: Some(exchange_order),
+                });
+            }
+        };
+
+        let mut internal_header = (*order.header).clone();
+        internal_header.client_order_id = ClientOrderId::unique_id();
+        let internal_order =
+            exchange
+                .orders
+                .add_simple_initial(Arc::new(internal_header), Some(middle_price), None);
+
+        let (filled_tx, filled_rx) = oneshot::channel();
+        let is_waiting = {
+            let mut interests = self.interests.lock();
+            let market_interests = interests.entry(market_account_id).or_default();
+
+            let mut incoming =
+                CrossingInterest::new(&order, configuration_descriptor, internal_order.clone());
+            for (counterparty_order, counterparty_descriptor, amount) in
+                match_interests(market_interests, &mut incoming, middle_price)
+            {
+                for (order_ref, descriptor, role) in [
+                    (
+                        &counterparty_order,
+                        counterparty_descriptor,
+                        OrderFillRole::Maker,
+                    ),
+                    (
+                        &internal_order,
+                        configuration_descriptor,
+                        OrderFillRole::Taker,
+                    ),
+                ] {
+                    Self::add_internal_fill(
+                        &exchange,
+                        order_ref,
+                        descriptor,
+                        middle_price,
+                        amount,
+                        role,
+                        symbol.quote_currency_code(),
+                    );
+                }
+            }
+
+            let is_waiting = !incoming.remaining.is_zero();
+            if is_waiting {
+                incoming.filled_tx = Some(filled_tx);
+                market_interests.push(incoming);
+            }
+            is_waiting
+        };
+
+        if is_waiting {
+            tokio::select! {
+                _ = filled_rx => {}
+                _ = tokio::time::sleep(self.max_wait) => {}
+                _ = cancellation_token.when_cancelled() => {}
+            }
+        }
+
+        let client_order_id = order.header.client_order_id.clone();
+        let _ = self.remove_interest(market_account_id, &internal_order.client_order_id());
+
+        // interest can't be matched after its removal, so filled amount is final
+        let internal_filled_amount = internal_order.filled_amount();
+        let remaining = order.header.amount - internal_filled_amount;
+        let internal_order = match internal_filled_amount.is_zero() {
+            true => {
+                let _ = exchange
+                    .orders
+                    .cache_by_client_id
+                    .remove(&internal_order.client_order_id());
+                let _ = exchange.orders.not_finished.remove(&internal_order);
+                None
+            }
+            false => {
+                internal_order.fn_mut(|x| {
+                    Arc::make_mut(&mut x.header).amount = internal_filled_amount;
+                    x.set_status(OrderStatus::Completed, time_manager::now());
+                });
+                exchange.unreserve_reduced_amount(&order.header, internal_filled_amount, false);
+
+                let cloned_order = Arc::new(internal_order.deep_clone());
+                exchange.add_event_on_order_change(
+                    &internal_order,
+                    OrderEventType::OrderCompleted { cloned_order },
+                )?;
+                log::info!(
+                    "Order {client_order_id} on {market_account_id:?} is filled internally by {internal_filled_amount} in order {}",
+                    internal_order.client_order_id()
+                );
+                Some(internal_order)
+            }
+        };
+
+        // order could be added to pool by its creator already, so it's updated to the rest amount
+        let pool_order = exchange.orders.get_by_client_id(&client_order_id);
+        let exchange_order = match remaining.is_zero() {
+            true => {
+                if let Some(pool_order) = pool_order {
+                    Self::cancel_fully_crossed_order(&exchange, &pool_order)?;
+                }
+                None
+            }
+            false => {
+                let mut header = (*order.header).clone();
+                header.amount = remaining;
+                let header = Arc::new(header);
+                if let Some(pool_order) = pool_order {
+                    pool_order.fn_mut(|x| x.header = header.clone());
+                }
+
+                let order_to_create = OrderCreating {
+                    header,
+                    price: order.price,
+                };
+                Some(
+                    Self::create_exchange_order(&exchange, &order_to_create, cancellation_token)
+                        .await?,
+                )
+            }
+        };
+
+        Ok(CrossingResult {
+            internal_order,
+            exchange_order,
+        })
+    }
+
+    /// Order which is fully filled by internal order isn't sent to exchange, so its creator is
+    /// notified that it's finished without fills
+    fn cancel_fully_crossed_order(exchange: &Exchange, order: &OrderRef) -> Result<()> {
+        order.fn_mut(|x| {
+            Arc::make_mut(&mut x.header).amount = dec!(0);
+            x.set_status(OrderStatus::Canceled, time_manager::now());
+        });
+        exchange.add_event_on_order_change(order, OrderEventType::CancelOrderSucceeded)
+    }
+
+    fn middle_price(exchange: &Exchange, market_account_id: MarketAccountId) -> Option<Price> {
+        let top = exchange
+            .order_book_top
+            .get(&market_account_id.currency_pair)?;
+        let ask = top.ask.as_ref()?.price;
+        let bid = top.bid.as_ref()?.price;
+
+        Some((ask + bid) / dec!(2))
+    }
+
+    /// Returns not matched amount if order is still waiting for counterparty
+    fn remove_interest(
+        &self,
+        market_account_id: MarketAccountId,
+        client_order_id: &ClientOrderId,
+    ) -> Option<Amount> {
+        let mut interests = self.interests.lock();
+        let market_interests = interests.get_mut(&market_account_id)?;
+        let index = market_interests
+            .iter()
+            .position(|x| &x.client_order_id() == client_order_id)?;
+
+        Some(market_interests.remove(index).remaining)
+    }
+
+    fn add_internal_fill(
+        exchange: &Exchange,
+        order_ref: &OrderRef,
+        configuration_descriptor: ConfigurationDescriptor,
+        price: Price,
+        amount: Amount,
+        role: OrderFillRole,
+        commission_currency_code: CurrencyCode,
+    ) {
+        let fill = OrderFill::new(
+            Uuid::new_v4(),
+            None,
+            time_manager::now(),
+            OrderFillType::Internal,
+            None,
+            price,
+            amount,
+            price * amount,
+            role,
+            commission_currency_code,
+            dec!(0),
+            dec!(0),
+            commission_currency_code,
+            dec!(0),
+            dec!(0),
+            false,
+            None,
+            Some(order_ref.side()),
+        );
+        order_ref.fn_mut(|x| x.add_fill(fill));
+
+        let cloned_order = Arc::new(order_ref.deep_clone());
+        match exchange.get_balance_manager() {
+            Some(balance_manager) => balance_manager
+                .lock()
+                .order_was_filled(configuration_descriptor, &cloned_order),
+            None => log::warn!(
+                "BalanceManager isn't available to apply internal fill of order {}",
+                order_ref.client_order_id()
+            ),
+        }
+
+        if let Err(error) = exchange
+            .add_event_on_order_change(order_ref, OrderEventType::OrderFilled { cloned_order })
+        {
+            log::error!(
+                "Failed to send internal fill event of order {}: {error:?}",
+                order_ref.client_order_id()
+            );
+        }
+    }
+
+    async fn create_exchange_order(
+        exchange: &Exchange,
+        order: &OrderCreating,
+        cancellation_token: CancellationToken,
+    ) -> Result<OrderRef> {
+        exchange
+            .create_order(order.clone(), None, cancellation_token)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use crate::orders::order::{OrderExecutionType, OrderHeader, OrderSimpleProps, OrderSnapshot};
+    use crate::service_configuration::configuration_descriptor::{
+        ServiceConfigurationKey, ServiceName,
+    };
+    use parking_lot::RwLock;
+    use rstest::rstest;
+
+    fn order(side: OrderSide, price: Price, amount: Amount, strategy_name: &str) -> OrderCreating {
+        let header = OrderHeader::new(
+            ClientOrderId::unique_id(),
+            time_manager::now(),
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            OrderType::Limit,
+            side,
+            amount,
+            OrderExecutionType::None,
+            None,
+            None,
+            strategy_name.to_owned(),
+        );
+
+        OrderCreating { header, price }
+    }
+
+    fn interest(order: &OrderCreating) -> CrossingInterest {
+        let internal_order = OrderRef::new(Arc::new(RwLock::new(OrderSnapshot::new(
+            order.header.clone(),
+            OrderSimpleProps::from_price(Some(order.price)),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            None,
+        ))));
+
+        let configuration_descriptor = ConfigurationDescriptor::new(
+            ServiceName::new(&order.header.strategy_name),
+            ServiceConfigurationKey::new("Binance_0;btc/usdt"),
+        );
+        CrossingInterest::new(order, configuration_descriptor, internal_order)
+    }
+
+    #[rstest]
+    #[case(OrderSide::Buy, dec!(101), true)]
+    #[case(OrderSide::Buy, dec!(99), false)]
+    #[case(OrderSide::Sell, dec!(99), true)]
+    #[case(OrderSide::Sell, dec!(101), false)]
+    pub fn limit_price_should_allow_middle_price(
+        #[case] side: OrderSide,
+        #[case] price: Price,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(
+            is_crossable(OrderType::Limit, side, price, dec!(100)),
+            expected
+        );
+    }
+
+    #[test]
+    pub fn opposite_orders_of_other_strategies_are_matched() {
+        let mut interests = vec![
+            interest(&order(OrderSide::Sell, dec!(99), dec!(1), "first")),
+            interest(&order(OrderSide::Sell, dec!(99), dec!(2), "second")),
+            interest(&order(OrderSide::Buy, dec!(101), dec!(2), "second")),
+            interest(&order(OrderSide::Sell, dec!(99), dec!(2), "third")),
+        ];
+        let mut incoming = interest(&order(OrderSide::Buy, dec!(101), dec!(2), "first"));
+
+        let matches = match_interests(&mut interests, &mut incoming, dec!(100));
+
+        let matched_amounts = matches
+            .iter()
+            .map(|(_, _, amount)| *amount)
+            .collect::<Vec<_>>();
+        assert_eq!(matched_amounts, vec![dec!(2)]);
+        assert_eq!(incoming.remaining, dec!(0));
+        assert_eq!(interests.len(), 3);
+        assert_eq!(interests[0].strategy_name, "first");
+    }
+
+    #[test]
+    pub fn partially_matched_interest_keeps_waiting() {
+        let sell = order(OrderSide::Sell, dec!(99), dec!(3), "first");
+        let mut interests = vec![interest(&sell)];
+        let mut incoming = interest(&order(OrderSide::Buy, dec!(101), dec!(1), "second"));
+
+        let _ = match_interests(&mut interests, &mut incoming, dec!(100));
+
+        assert_eq!(interests[0].remaining, dec!(2));
+        assert_eq!(interests[0].client_order_id(), sell.header.client_order_id);
+    }
+}
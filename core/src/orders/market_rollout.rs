@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{ensure, Result};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::common::{Amount, MarketAccountId};
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::commission::Percent;
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+use crate::orders::event::OrderEventType;
+use crate::services::performance_attribution::PerformanceAttributionService;
+use crate::settings::{MarketMode, MarketRolloutSettings, RolloutPromotionSettings};
+
+const PROMOTION_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+impl MarketMode {
+    fn next(self) -> Option<MarketMode> {
+        match self {
+            MarketMode::Shadow => Some(MarketMode::Reduced),
+            MarketMode::Reduced => Some(MarketMode::Full),
+            MarketMode::Full => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum MarketRolloutError {
+    #[error("order amount {amount} is reduced to zero by rollout size {reduced_size}%")]
+    ReducedToZero {
+        amount: Amount,
+        reduced_size: Percent,
+    },
+}
+
+/// Amount of order on market in reduced mode rounded by `round` function. Zero amount can't be sent
+pub fn reduce_amount(
+    amount: Amount,
+    reduced_size: Percent,
+    round: impl Fn(Amount) -> Amount,
+) -> Result<Amount, MarketRolloutError> {
+    let reduced_amount = round(amount * reduced_size / dec!(100));
+    if reduced_amount <= dec!(0) {
+        return Err(MarketRolloutError::ReducedToZero {
+            amount,
+            reduced_size,
+        });
+    }
+
+    Ok(reduced_amount)
+}
+
+/// Rollout state of market with metrics observed since the last mode change
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketRolloutState {
+    pub market_account_id: MarketAccountId,
+    pub mode: MarketMode,
+    pub reduced_size: Percent,
+    pub mode_since: DateTime,
+    /// Orders created on exchange or suppressed in shadow mode
+    pub orders_count: u64,
+    pub rejected_orders_count: u64,
+    /// PnL since mode change if performance attribution is enabled
+    pub pnl: Option<Amount>,
+    #[serde(skip)]
+    pnl_at_mode_start: Option<Amount>,
+    #[serde(skip)]
+    promotion: Option<RolloutPromotionSettings>,
+}
+
+impl MarketRolloutState {
+    fn change_mode(&mut self, mode: MarketMode, pnl: Option<Amount>) {
+        self.mode = mode;
+        self.mode_since = time_manager::now();
+        self.orders_count = 0;
+        self.rejected_orders_count = 0;
+        self.pnl = pnl.map(|_| dec!(0));
+        self.pnl_at_mode_start = pnl;
+    }
+
+    fn update_pnl(&mut self, pnl: Option<Amount>) {
+        self.pnl = pnl.zip(self.pnl_at_mode_start).map(|(x, start)| x - start);
+    }
+
+    fn reject_rate(&self) -> Percent {
+        let attempts = self.orders_count + self.rejected_orders_count;
+        match attempts {
+            0 => dec!(0),
+            _ => dec!(100) * Amount::from(self.rejected_orders_count) / Amount::from(attempts),
+        }
+    }
+
+    /// Next mode if all promotion rules are met at `now`
+    fn promotion(&self, now: DateTime) -> Option<MarketMode> {
+        let rules = self.promotion.as_ref()?;
+        let next_mode = self.mode.next()?;
+
+        let min_duration = chrono::Duration::milliseconds(rules.min_duration_ms as i64);
+        if now - self.mode_since < min_duration
+            || self.orders_count < rules.min_orders
+            || self.reject_rate() > rules.max_reject_rate
+        {
+            return None;
+        }
+
+        if let (MarketMode::Reduced, Some(min_pnl)) = (self.mode, rules.min_pnl) {
+            if self.pnl.is_none_or(|pnl| pnl < min_pnl) {
+                return None;
+            }
+        }
+
+        Some(next_mode)
+    }
+}
+
+/// Modes of markets which are rolled out gradually. Markets without rollout settings are in full
+/// mode. Markets are promoted by their rules periodically and can be switched through control API
+#[derive(Default)]
+pub struct MarketRollout {
+    markets: Mutex<HashMap<MarketAccountId, MarketRolloutState>>,
+    performance_attribution: Option<Arc<PerformanceAttributionService>>,
+}
+
+impl MarketRollout {
+    pub fn new(
+        settings: &[MarketRolloutSettings],
+        performance_attribution: Option<Arc<PerformanceAttributionService>>,
+    ) -> Result<Arc<Self>> {
+        let mut markets = HashMap::new();
+        for market in settings {
+            let market_account_id =
+                MarketAccountId::new(market.exchange_account_id, market.currency_pair);
+            ensure!(
+                market.reduced_size > dec!(0) && market.reduced_size <= dec!(100),
+                "Reduced size of {market_account_id:?} should be in range (0, 100]"
+            );
+            let requires_pnl = market.promotion.as_ref().and_then(|x| x.min_pnl).is_some();
+            ensure!(
+                !requires_pnl || performance_attribution.is_some(),
+                "Min PnL promotion rule of {market_account_id:?} requires performance attribution"
+            );
+
+            let pnl = performance_attribution
+                .as_ref()
+                .map(|x| x.market_pnl(market_account_id));
+            let mut state = MarketRolloutState {
+                market_account_id,
+                mode: market.mode,
+                reduced_size: market.reduced_size,
+                mode_since: time_manager::now(),
+                orders_count: 0,
+                rejected_orders_count: 0,
+                pnl: None,
+                pnl_at_mode_start: None,
+                promotion: market.promotion.clone(),
+            };
+            state.change_mode(market.mode, pnl);
+            let _ = markets.insert(market_account_id, state);
+        }
+
+        Ok(Arc::new(MarketRollout {
+            markets: Mutex::new(markets),
+            performance_attribution,
+        }))
+    }
+
+    pub fn start(
+        self: Arc<Self>,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) {
+        let _ = spawn_future(
+            "Market rollout events handling",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            self.clone()
+                .handle_events(events_receiver, cancellation_token.clone()),
+        );
+
+        let _ = spawn_future(
+            "Market rollout promotions",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            self.check_promotions(cancellation_token),
+        );
+    }
+
+    pub fn mode(&self, market_account_id: MarketAccountId) -> MarketMode {
+        self.markets
+            .lock()
+            .get(&market_account_id)
+            .map_or(MarketMode::Full, |x| x.mode)
+    }
+
+    /// Size of orders in percents of normal ones if market is in reduced mode
+    pub fn reduced_size(&self, market_account_id: MarketAccountId) -> Option<Percent> {
+        self.markets
+            .lock()
+            .get(&market_account_id)
+            .filter(|x| x.mode == MarketMode::Reduced)
+            .map(|x| x.reduced_size)
+    }
+
+    pub fn states(&self) -> Vec<MarketRolloutState> {
+        let pnls = self.market_pnls();
+        let mut markets = self.markets.lock();
+        markets
+            .values_mut()
+            .map(|state| {
+                state.update_pnl(pnls.get(&state.market_account_id).copied());
+                state.clone()
+            })
+            .collect()
+    }
+
+    /// Changes mode of market and resets its metrics. Reduced size is kept from settings or
+    /// the previous change if it isn't specified
+    pub fn set_mode(
+        &self,
+        market_account_id: MarketAccountId,
+        mode: MarketMode,
+        reduced_size: Option<Percent>,
+    ) -> Result<MarketMode> {
+        if let Some(reduced_size) = reduced_size {
+            ensure!(
+                reduced_size > dec!(0) && reduced_size <= dec!(100),
+                "Reduced size should be in range (0, 100]"
+            );
+        }
+
+        let pnl = self
+            .performance_attribution
+            .as_ref()
+            .map(|x| x.market_pnl(market_account_id));
+        let mut markets = self.markets.lock();
+        let state = match markets.get_mut(&market_account_id) {
+            Some(state) => state,
+            None => {
+                ensure!(
+                    mode != MarketMode::Reduced || reduced_size.is_some(),
+                    "Reduced size should be specified for market without rollout settings"
+                );
+                markets
+                    .entry(market_account_id)
+                    .or_insert_with(|| MarketRolloutState {
+                        market_account_id,
+                        mode: MarketMode::Full,
+                        reduced_size: dec!(100),
+                        mode_since: time_manager::now(),
+                        orders_count: 0,
+                        rejected_orders_count: 0,
+                        pnl: None,
+                        pnl_at_mode_start: None,
+                        promotion: None,
+                    })
+            }
+        };
+
+        let previous_mode = state.mode;
+        if let Some(reduced_size) = reduced_size {
+            state.reduced_size = reduced_size;
+        }
+        state.change_mode(mode, pnl);
+        log::warn!(
+            "Market {market_account_id:?} is switched from {previous_mode:?} to {mode:?} mode"
+        );
+
+        Ok(previous_mode)
+    }
+
+    pub fn register_shadow_order(&self, market_account_id: MarketAccountId) {
+        if let Some(state) = self.markets.lock().get_mut(&market_account_id) {
+            state.orders_count += 1;
+        }
+    }
+
+    fn market_pnls(&self) -> HashMap<MarketAccountId, Amount> {
+        let performance_attribution = match &self.performance_attribution {
+            Some(performance_attribution) => performance_attribution,
+            None => return HashMap::new(),
+        };
+
+        let market_account_ids: Vec<_> = self.markets.lock().keys().copied().collect();
+        market_account_ids
+            .into_iter()
+            .map(|x| (x, performance_attribution.market_pnl(x)))
+            .collect()
+    }
+
+    fn promote_markets(&self) {
+        let pnls = self.market_pnls();
+        let now = time_manager::now();
+
+        for state in self.markets.lock().values_mut() {
+            let pnl = pnls.get(&state.market_account_id).copied();
+            state.update_pnl(pnl);
+            if let Some(next_mode) = state.promotion(now) {
+                log::warn!(
+                    "Market {:?} is promoted from {:?} to {next_mode:?} mode after {} orders with reject rate {}% and PnL {:?}",
+                    state.market_account_id,
+                    state.mode,
+                    state.orders_count,
+                    state.reject_rate().round_dp(2),
+                    state.pnl
+                );
+                state.change_mode(next_mode, pnl);
+            }
+        }
+    }
+
+    async fn check_promotions(
+        self: Arc<Self>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(PROMOTION_CHECK_INTERVAL) => {}
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            }
+
+            self.promote_markets();
+        }
+    }
+
+    async fn handle_events(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        loop {
+            let event = tokio::select! {
+                event = events_receiver.recv() => event,
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            };
+            let order_event = match event {
+                Ok(ExchangeEvent::OrderEvent(order_event)) => order_event,
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Market rollout skipped {skipped} exchange events");
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            let market_account_id = MarketAccountId::new(
+                order_event.order.exchange_account_id(),
+                order_event.order.currency_pair(),
+            );
+            let mut markets = self.markets.lock();
+            let state = match markets.get_mut(&market_account_id) {
+                Some(state) => state,
+                None => continue,
+            };
+            match order_event.event_type {
+                OrderEventType::CreateOrderSucceeded => state.orders_count += 1,
+                OrderEventType::CreateOrderFailed => state.rejected_orders_count += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+
+    fn state(mode: MarketMode, min_pnl: Option<Amount>) -> MarketRolloutState {
+        MarketRolloutState {
+            market_account_id: MarketAccountId::new(
+                ExchangeAccountId::new("Binance", 0),
+                CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            ),
+            mode,
+            reduced_size: dec!(10),
+            mode_since: time_manager::now(),
+            orders_count: 0,
+            rejected_orders_count: 0,
+            pnl: None,
+            pnl_at_mode_start: None,
+            promotion: Some(RolloutPromotionSettings {
+                min_duration_ms: 60_000,
+                min_orders: 10,
+                max_reject_rate: dec!(5),
+                min_pnl,
+            }),
+        }
+    }
+
+    #[test]
+    pub fn market_is_promoted_when_all_rules_are_met() {
+        let mut state = state(MarketMode::Shadow, Some(dec!(1)));
+        let later = state.mode_since + chrono::Duration::seconds(61);
+
+        state.orders_count = 10;
+        assert_eq!(state.promotion(state.mode_since), None);
+        // min PnL isn't checked in shadow mode
+        assert_eq!(state.promotion(later), Some(MarketMode::Reduced));
+
+        state.mode = MarketMode::Reduced;
+        state.orders_count = 20;
+        state.rejected_orders_count = 1;
+        state.pnl = Some(dec!(0.5));
+        assert_eq!(state.promotion(later), None);
+        state.pnl = Some(dec!(2));
+        assert_eq!(state.promotion(later), Some(MarketMode::Full));
+
+        state.rejected_orders_count = 2;
+        assert_eq!(state.promotion(later), None);
+
+        state.mode = MarketMode::Full;
+        state.rejected_orders_count = 0;
+        assert_eq!(state.promotion(later), None);
+    }
+
+    #[test]
+    pub fn reduce_order_amount() {
+        let round = |x: Amount| x.round_dp_with_strategy(2, rust_decimal::RoundingStrategy::ToZero);
+
+        assert_eq!(reduce_amount(dec!(1.5), dec!(10), round), Ok(dec!(0.15)));
+        assert_eq!(
+            reduce_amount(dec!(0.05), dec!(10), round),
+            Err(MarketRolloutError::ReducedToZero {
+                amount: dec!(0.05),
+                reduced_size: dec!(10),
+            })
+        );
+    }
+}
@@ -1,5 +1,15 @@
 pub mod buffered_fills;
+pub mod conditional;
+pub mod currency_restrictions;
 pub mod event;
 pub mod fill;
+pub mod good_till_date;
+pub mod internalization;
+pub mod market_rollout;
 pub mod order;
 pub mod pool;
+pub mod price_protection;
+pub mod queue_position;
+pub mod reduce_only;
+pub mod risk_engine;
+pub mod trailing_stop;
@@ -2,8 +2,9 @@ use std::any::Any;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
+use std::time::Duration;
 use std::vec::Vec;
 
 use chrono::Utc;
@@ -23,6 +24,7 @@ use crate::exchanges::common::{
     Amount, CurrencyPair, ExchangeAccountId, ExchangeErrorType, MarketAccountId, MarketId, Price,
 };
 use crate::orders::fill::{EventSourceType, OrderFill};
+use crate::orders::queue_position::QueuePosition;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash, Enum)]
 pub enum OrderSide {
@@ -172,6 +174,31 @@ pub struct OrderHeader {
 
     pub signal_id: Option<String>,
     pub strategy_name: String,
+
+    /// Amount in quote currency (cash quantity) for market orders sized by notional.
+    /// In this case `amount` is base amount estimated by order book. Exchanges which support
+    /// sizing by quote amount receive it natively, otherwise `amount` is recalculated by local
+    /// order book before order is sent
+    #[serde(default)]
+    pub quote_amount: Option<Amount>,
+
+    /// Good-till-date time in force: order should be canceled at this time.
+    /// It's emulated by core for exchanges without native support
+    #[serde(default)]
+    pub expire_time: Option<DateTime>,
+
+    /// Order should only reduce position. It's sent to exchange only if it supports the flag natively
+    #[serde(default)]
+    pub reduce_only: bool,
+
+    /// Max time to wait for exchange acknowledgment of order creation. If it's exceeded,
+    /// order is marked by `AckTimeout` event and core cancels it as soon as it's possible
+    #[serde(default)]
+    pub latency_budget: Option<Duration>,
+
+    /// Price of order is intentionally far from the last trade price, so sanity check is skipped
+    #[serde(default)]
+    pub skip_price_sanity_check: bool,
 }
 
 impl OrderHeader {
@@ -202,9 +229,70 @@ impl OrderHeader {
             reservation_id,
             signal_id,
             strategy_name,
+            quote_amount: None,
+            expire_time: None,
+            reduce_only: false,
+            latency_budget: None,
+            skip_price_sanity_check: false,
+        })
+    }
+
+    /// Market order sized by amount in quote currency. `amount` is estimated base amount which
+    /// is used for balance reservation and is sent to exchange if it doesn't support sizing by quote
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_by_quote_amount(
+        client_order_id: ClientOrderId,
+        init_time: DateTime,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        side: OrderSide,
+        quote_amount: Amount,
+        amount: Amount,
+        reservation_id: Option<ReservationId>,
+        signal_id: Option<String>,
+        strategy_name: String,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            version: CURRENT_ORDER_VERSION,
+            client_order_id,
+            init_time,
+            exchange_account_id,
+            currency_pair,
+            order_type: OrderType::Market,
+            side,
+            amount,
+            execution_type: OrderExecutionType::None,
+            reservation_id,
+            signal_id,
+            strategy_name,
+            quote_amount: Some(quote_amount),
+            expire_time: None,
+            reduce_only: false,
+            latency_budget: None,
+            skip_price_sanity_check: false,
         })
     }
 
+    pub fn with_expire_time(mut self: Arc<Self>, expire_time: DateTime) -> Arc<Self> {
+        Arc::make_mut(&mut self).expire_time = Some(expire_time);
+        self
+    }
+
+    pub fn with_reduce_only(mut self: Arc<Self>) -> Arc<Self> {
+        Arc::make_mut(&mut self).reduce_only = true;
+        self
+    }
+
+    pub fn with_latency_budget(mut self: Arc<Self>, latency_budget: Duration) -> Arc<Self> {
+        Arc::make_mut(&mut self).latency_budget = Some(latency_budget);
+        self
+    }
+
+    pub fn with_skip_price_sanity_check(mut self: Arc<Self>) -> Arc<Self> {
+        Arc::make_mut(&mut self).skip_price_sanity_check = true;
+        self
+    }
+
     pub fn version(&self) -> u32 {
         self.version
     }
@@ -326,6 +414,9 @@ pub struct SystemInternalOrderProps {
     pub last_order_cancellation_status_request_time: Option<DateTime>,
     pub last_cancellation_error: Option<ExchangeErrorType>,
 
+    #[serde(skip)]
+    pub queue_position: Option<QueuePosition>,
+
     #[serde(skip_serializing)]
     pub is_canceling_from_wait_cancel_order: bool,
 
@@ -339,6 +430,10 @@ pub struct SystemInternalOrderProps {
 
     pub handled_by_balance_recovery: bool,
     pub filled_amount_after_cancellation: Option<Amount>,
+
+    /// Creation acknowledgment wasn't received within latency budget of order
+    #[serde(default)]
+    pub is_ack_timeout: bool,
 }
 
 /// It may be necessary for an exchange to store specific information for an order.
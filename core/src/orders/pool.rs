@@ -14,6 +14,7 @@ use crate::orders::order::{
     ClientOrderId, ExchangeOrderId, OrderHeader, OrderInfoExtensionData, OrderSimpleProps,
     OrderSnapshot, OrderStatus,
 };
+use crate::orders::queue_position::QueuePosition;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -66,6 +67,10 @@ impl OrderRef {
     pub fn side(&self) -> OrderSide {
         self.fn_ref(|x| x.header.side)
     }
+    /// Latest estimate of position in queue of price level for resting limit order
+    pub fn queue_position(&self) -> Option<QueuePosition> {
+        self.fn_ref(|x| x.internal_props.queue_position)
+    }
 
     pub fn deep_clone(&self) -> OrderSnapshot {
         self.fn_ref(|order| order.clone())
@@ -92,18 +97,86 @@ impl OrderRef {
         })
     }
 
-    #[cfg(test)]
     pub fn new(snapshot: Arc<RwLock<OrderSnapshot>>) -> Self {
         Self(snapshot)
     }
 }
 
+/// Not finished orders sharded by market, so scanning orders of one market doesn't lock and
+/// iterate orders of other markets
+#[derive(Debug, Default)]
+pub struct NotFinishedOrders {
+    markets: DashMap<CurrencyPair, Arc<DashMap<ClientOrderId, OrderRef>>>,
+}
+
+impl NotFinishedOrders {
+    /// Shard of market is cloned out of map, so lock of the outer map is held only for lookup
+    fn market_orders(&self, currency_pair: CurrencyPair) -> Arc<DashMap<ClientOrderId, OrderRef>> {
+        if let Some(orders) = self.markets.get(&currency_pair) {
+            return orders.value().clone();
+        }
+
+        self.markets
+            .entry(currency_pair)
+            .or_default()
+            .value()
+            .clone()
+    }
+
+    pub fn insert(&self, order_ref: OrderRef) {
+        let (currency_pair, client_order_id) =
+            order_ref.fn_ref(|x| (x.header.currency_pair, x.header.client_order_id.clone()));
+        let _ = self
+            .market_orders(currency_pair)
+            .insert(client_order_id, order_ref);
+    }
+
+    pub fn remove(&self, order_ref: &OrderRef) -> Option<OrderRef> {
+        let (currency_pair, client_order_id) =
+            order_ref.fn_ref(|x| (x.header.currency_pair, x.header.client_order_id.clone()));
+        self.markets
+            .get(&currency_pair)?
+            .remove(&client_order_id)
+            .map(|(_, order_ref)| order_ref)
+    }
+
+    /// Not finished orders of market
+    pub fn by_market(&self, currency_pair: CurrencyPair) -> Vec<OrderRef> {
+        let orders = self.markets.get(&currency_pair).map(|x| x.value().clone());
+        orders
+            .map(|orders| orders.iter().map(|x| x.value().clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Not finished orders of all markets
+    pub fn all(&self) -> Vec<OrderRef> {
+        let markets = self
+            .markets
+            .iter()
+            .map(|x| x.value().clone())
+            .collect::<Vec<_>>();
+
+        markets
+            .iter()
+            .flat_map(|orders| orders.iter().map(|x| x.value().clone()).collect::<Vec<_>>())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.markets.iter().map(|x| x.value().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct OrdersPool {
     pub cache_by_client_id: DashMap<ClientOrderId, OrderRef>,
     pub cache_by_exchange_id: DashMap<ExchangeOrderId, OrderRef>,
-    pub not_finished: DashMap<ClientOrderId, OrderRef>,
+    pub not_finished: NotFinishedOrders,
 }
 
 impl OrdersPool {
@@ -113,18 +186,33 @@ impl OrdersPool {
         Arc::new(OrdersPool {
             cache_by_client_id: DashMap::with_capacity(ORDERS_INIT_CAPACITY),
             cache_by_exchange_id: DashMap::with_capacity(ORDERS_INIT_CAPACITY),
-            not_finished: DashMap::with_capacity(ORDERS_INIT_CAPACITY),
+            not_finished: NotFinishedOrders::default(),
         })
     }
 
+    /// Order is cloned out of map, so lock of map shard isn't held while caller works with order.
+    /// Should be used instead of `cache_by_client_id.get()` on hot paths
+    pub fn get_by_client_id(&self, client_order_id: &ClientOrderId) -> Option<OrderRef> {
+        self.cache_by_client_id
+            .get(client_order_id)
+            .map(|x| x.value().clone())
+    }
+
+    /// The same as `get_by_client_id` for lookup by exchange order id
+    pub fn get_by_exchange_id(&self, exchange_order_id: &ExchangeOrderId) -> Option<OrderRef> {
+        self.cache_by_exchange_id
+            .get(exchange_order_id)
+            .map(|x| x.value().clone())
+    }
+
     /// Insert specified `OrderSnapshot` in order pool.
     pub fn add_snapshot_initial(&self, snapshot: Arc<RwLock<OrderSnapshot>>) -> OrderRef {
         let client_order_id = snapshot.read().header.client_order_id.clone();
         let order_ref = OrderRef(snapshot);
         let _ = self
             .cache_by_client_id
-            .insert(client_order_id.clone(), order_ref.clone());
-        let _ = self.not_finished.insert(client_order_id, order_ref.clone());
+            .insert(client_order_id, order_ref.clone());
+        self.not_finished.insert(order_ref.clone());
 
         order_ref
     }
@@ -136,20 +224,73 @@ impl OrdersPool {
         price: Option<Decimal>,
         extension_data: Option<Box<dyn OrderInfoExtensionData>>,
     ) -> OrderRef {
-        match self.cache_by_client_id.get(&header.client_order_id) {
-            None => {
-                let snapshot = Arc::new(RwLock::new(OrderSnapshot {
-                    props: OrderSimpleProps::from_price(price),
-                    header,
-                    fills: Default::default(),
-                    status_history: Default::default(),
-                    internal_props: Default::default(),
-                    extension_data,
-                }));
-
-                self.add_snapshot_initial(snapshot)
-            }
-            Some(order_ref) => order_ref.clone(),
+        if let Some(order_ref) = self.get_by_client_id(&header.client_order_id) {
+            return order_ref;
         }
+
+        let snapshot = Arc::new(RwLock::new(OrderSnapshot {
+            props: OrderSimpleProps::from_price(price),
+            header,
+            fills: Default::default(),
+            status_history: Default::default(),
+            internal_props: Default::default(),
+            extension_data,
+        }));
+
+        self.add_snapshot_initial(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn add_order(pool: &OrdersPool, currency_pair: CurrencyPair) -> OrderRef {
+        let snapshot = OrderSnapshot::with_params(
+            ClientOrderId::unique_id(),
+            OrderType::Limit,
+            None,
+            ExchangeAccountId::new("Binance", 0),
+            currency_pair,
+            dec!(1000),
+            dec!(1),
+            OrderSide::Buy,
+            None,
+            "test",
+        );
+        pool.add_snapshot_initial(Arc::new(RwLock::new(snapshot)))
+    }
+
+    #[test]
+    pub fn not_finished_orders_are_sharded_by_market() {
+        let btc_usdt = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let eth_usdt = CurrencyPair::from_codes("eth".into(), "usdt".into());
+        let pool = OrdersPool::new();
+
+        let btc_order = add_order(&pool, btc_usdt);
+        let eth_order = add_order(&pool, eth_usdt);
+        let _ = add_order(&pool, eth_usdt);
+
+        assert_eq!(pool.not_finished.len(), 3);
+        assert_eq!(pool.not_finished.all().len(), 3);
+        assert_eq!(pool.not_finished.by_market(eth_usdt).len(), 2);
+        assert_eq!(
+            pool.not_finished
+                .by_market(btc_usdt)
+                .iter()
+                .map(|x| x.client_order_id())
+                .collect::<Vec<_>>(),
+            vec![btc_order.client_order_id()]
+        );
+
+        assert!(pool.not_finished.remove(&eth_order).is_some());
+        assert!(pool.not_finished.remove(&eth_order).is_none());
+        assert_eq!(pool.not_finished.by_market(eth_usdt).len(), 1);
+
+        // finished orders are still available by id
+        assert!(pool
+            .get_by_client_id(&eth_order.client_order_id())
+            .is_some());
     }
 }
@@ -0,0 +1,390 @@
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::exchanges::common::{Amount, MarketId, Price, SortedOrderData};
+use crate::exchanges::general::commission::Percent;
+use crate::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+use crate::orders::order::OrderSide;
+
+const BPS_IN_ONE: Decimal = dec!(10_000);
+const BPS_IN_PERCENT: Decimal = dec!(100);
+
+/// Expected execution of taker order by local order book
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct SlippageAnalysis {
+    pub middle_price: Price,
+    /// Average price of order book levels which order would be matched with
+    pub expected_average_price: Price,
+    /// Worst price level which order would be matched with
+    pub worst_price: Price,
+    /// Part of order amount that can be matched by order book depth
+    pub available_amount: Amount,
+    /// Price worsening of expected average price from middle price
+    pub slippage: Percent,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum PriceProtectionError {
+    #[error("can't analyze slippage for {0:?} because order book is empty")]
+    EmptyOrderBook(MarketId),
+    #[error("order book depth isn't enough to fill order: {analysis:?}")]
+    NotEnoughDepth { analysis: SlippageAnalysis },
+    #[error("expected slippage exceeds max slippage {max_slippage}%: {analysis:?}")]
+    SlippageExceeded {
+        analysis: SlippageAnalysis,
+        max_slippage: Percent,
+    },
+    #[error("price {price} deviates from index price {index_price} more than {max_deviation}%")]
+    OutsideCollar {
+        price: Price,
+        index_price: Price,
+        max_deviation: Percent,
+    },
+    #[error("price {price} deviates from the last trade price {last_trade_price} more than {max_deviation}%")]
+    FarFromLastTrade {
+        price: Price,
+        last_trade_price: Price,
+        max_deviation: Percent,
+    },
+}
+
+/// Expected execution of liquidity taking order of specified size by current order book depth
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct MarketImpact {
+    pub middle_price: Price,
+    /// Average price of order book levels which order would be matched with
+    pub expected_average_price: Price,
+    /// Worst price level which order would be matched with
+    pub worst_price: Price,
+    /// Part of order amount that can be matched by order book depth
+    pub available_amount: Amount,
+    /// Price worsening of expected average price from middle price in basis points
+    pub impact_bps: Decimal,
+}
+
+/// Walk levels of single-venue order book or consolidated levels of several order books of the same
+/// currency pair to estimate execution of taker order. Middle price of consolidated book is
+/// calculated by the best bid and the best ask among all venues
+pub fn estimate_market_impact(
+    snapshots: &[&LocalOrderBookSnapshot],
+    side: OrderSide,
+    amount: Amount,
+) -> Option<MarketImpact> {
+    let top_ask = snapshots.iter().filter_map(|x| x.get_top_ask()).min()?.0;
+    let top_bid = snapshots.iter().filter_map(|x| x.get_top_bid()).max()?.0;
+    let middle_price = (top_ask + top_bid) / dec!(2);
+
+    let mut levels = SortedOrderData::new();
+    for snapshot in snapshots {
+        for (&price, &level_amount) in snapshot.get_price_levels_to_match(side) {
+            *levels.entry(price).or_default() += level_amount;
+        }
+    }
+    let levels_to_match: Box<dyn Iterator<Item = (&Price, &Amount)>> = match side {
+        OrderSide::Buy => Box::new(levels.iter()),
+        OrderSide::Sell => Box::new(levels.iter().rev()),
+    };
+
+    let mut available_amount = dec!(0);
+    let mut cost = dec!(0);
+    let mut worst_price = None;
+    for (&price, &level_amount) in levels_to_match {
+        if available_amount >= amount {
+            break;
+        }
+
+        let matched_amount = level_amount.min(amount - available_amount);
+        available_amount += matched_amount;
+        cost += matched_amount * price;
+        worst_price = Some(price);
+    }
+
+    let worst_price = worst_price?;
+    let expected_average_price = cost / available_amount;
+    let price_worsening = match side {
+        OrderSide::Buy => expected_average_price - middle_price,
+        OrderSide::Sell => middle_price - expected_average_price,
+    };
+
+    Some(MarketImpact {
+        middle_price,
+        expected_average_price,
+        worst_price,
+        available_amount,
+        impact_bps: price_worsening / middle_price * BPS_IN_ONE,
+    })
+}
+
+/// Walk order book levels to calculate expected average price of taker order and its slippage from middle price
+pub fn analyze_slippage(
+    snapshot: &LocalOrderBookSnapshot,
+    market_id: MarketId,
+    side: OrderSide,
+    amount: Amount,
+) -> Option<SlippageAnalysis> {
+    let impact = estimate_market_impact(&[snapshot], side, amount);
+    if impact.is_none() {
+        log::warn!("Can't analyze slippage for {market_id:?} because order book side is empty");
+    }
+
+    impact.map(|impact| SlippageAnalysis {
+        middle_price: impact.middle_price,
+        expected_average_price: impact.expected_average_price,
+        worst_price: impact.worst_price,
+        available_amount: impact.available_amount,
+        slippage: impact.impact_bps / BPS_IN_PERCENT,
+    })
+}
+
+/// Pre-trade check for market and taker orders. Rejection contains calculated analysis
+pub fn check_price_protection(
+    snapshot: &LocalOrderBookSnapshot,
+    market_id: MarketId,
+    side: OrderSide,
+    amount: Amount,
+    max_slippage: Percent,
+) -> Result<SlippageAnalysis, PriceProtectionError> {
+    let analysis = analyze_slippage(snapshot, market_id, side, amount)
+        .ok_or(PriceProtectionError::EmptyOrderBook(market_id))?;
+
+    if analysis.available_amount < amount {
+        return Err(PriceProtectionError::NotEnoughDepth { analysis });
+    }
+
+    if analysis.slippage > max_slippage {
+        return Err(PriceProtectionError::SlippageExceeded {
+            analysis,
+            max_slippage,
+        });
+    }
+
+    Ok(analysis)
+}
+
+/// Pre-trade check that order price is within collar around reference index price
+pub fn check_price_collar(
+    price: Price,
+    index_price: Price,
+    max_deviation: Percent,
+) -> Result<(), PriceProtectionError> {
+    if deviation_percent(price, index_price) > max_deviation {
+        return Err(PriceProtectionError::OutsideCollar {
+            price,
+            index_price,
+            max_deviation,
+        });
+    }
+
+    Ok(())
+}
+
+/// Last-line guard against unit errors like quoting in wrong currency: order price should be
+/// close to the most recent trade price
+pub fn check_last_trade_deviation(
+    price: Price,
+    last_trade_price: Price,
+    max_deviation: Percent,
+) -> Result<(), PriceProtectionError> {
+    if deviation_percent(price, last_trade_price) > max_deviation {
+        return Err(PriceProtectionError::FarFromLastTrade {
+            price,
+            last_trade_price,
+            max_deviation,
+        });
+    }
+
+    Ok(())
+}
+
+/// Price of the last trade if it isn't older than max age, because outdated price can't be
+/// reference of price sanity check
+pub fn fresh_last_trade_price(
+    last_trade: Option<(Price, DateTime)>,
+    now: DateTime,
+    max_age_ms: u64,
+) -> Option<Price> {
+    let (price, receipt_time) = last_trade?;
+    let age_ms = (now - receipt_time).num_milliseconds();
+    (age_ms <= max_age_ms as i64).then_some(price)
+}
+
+/// Trade print is erroneous if its price deviates from the nearest side of order book top more
+/// than max deviation. Prints inside the spread or without order book aren't considered bad
+pub fn is_bad_print(
+    price: Price,
+    bid: Option<Price>,
+    ask: Option<Price>,
+    max_deviation: Percent,
+) -> bool {
+    let deviation = match (bid, ask) {
+        (_, Some(ask)) if price > ask => deviation_percent(price, ask),
+        (Some(bid), _) if price < bid => deviation_percent(price, bid),
+        _ => return false,
+    };
+
+    deviation > max_deviation
+}
+
+fn deviation_percent(price: Price, reference_price: Price) -> Percent {
+    (price - reference_price).abs() / reference_price * dec!(100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use crate::order_book::order_book_data::OrderBookData;
+    use crate::order_book_data;
+    use rstest::rstest;
+
+    fn market_id() -> MarketId {
+        MarketId::new(
+            "Binance".into(),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    fn snapshot() -> LocalOrderBookSnapshot {
+        let order_book_data: OrderBookData = order_book_data![
+            dec!(101) => dec!(1),
+            dec!(103) => dec!(1),
+            ;
+            dec!(99) => dec!(2),
+            dec!(95) => dec!(2),
+        ];
+        order_book_data.to_local_order_book_snapshot()
+    }
+
+    #[test]
+    pub fn analysis_by_order_book_levels() {
+        let analysis =
+            analyze_slippage(&snapshot(), market_id(), OrderSide::Buy, dec!(2)).expect("in test");
+
+        assert_eq!(
+            analysis,
+            SlippageAnalysis {
+                middle_price: dec!(100),
+                expected_average_price: dec!(102),
+                worst_price: dec!(103),
+                available_amount: dec!(2),
+                slippage: dec!(2),
+            }
+        );
+    }
+
+    #[rstest]
+    #[case(OrderSide::Buy, dec!(2), dec!(100.75), dec!(101), dec!(75))]
+    #[case(OrderSide::Sell, dec!(4), dec!(98.875), dec!(98), dec!(112.5))]
+    pub fn market_impact_by_consolidated_book(
+        #[case] side: OrderSide,
+        #[case] amount: Amount,
+        #[case] expected_average_price: Price,
+        #[case] worst_price: Price,
+        #[case] impact_bps: Decimal,
+    ) {
+        let order_book_data: OrderBookData = order_book_data![
+            dec!(100.5) => dec!(1),
+            dec!(102) => dec!(1),
+            ;
+            dec!(99.5) => dec!(1),
+            dec!(98) => dec!(1),
+        ];
+        let other_snapshot = order_book_data.to_local_order_book_snapshot();
+
+        let impact =
+            estimate_market_impact(&[&snapshot(), &other_snapshot], side, amount).expect("in test");
+
+        assert_eq!(impact.middle_price, dec!(100));
+        assert_eq!(impact.expected_average_price, expected_average_price);
+        assert_eq!(impact.worst_price, worst_price);
+        assert_eq!(impact.available_amount, amount);
+        assert_eq!(impact.impact_bps, impact_bps);
+    }
+
+    #[rstest]
+    #[case(OrderSide::Buy, dec!(1), true)]
+    #[case(OrderSide::Buy, dec!(2), false)]
+    #[case(OrderSide::Sell, dec!(2), true)]
+    #[case(OrderSide::Sell, dec!(3), false)]
+    pub fn check_max_slippage(
+        #[case] side: OrderSide,
+        #[case] amount: Amount,
+        #[case] is_allowed: bool,
+    ) {
+        let result = check_price_protection(&snapshot(), market_id(), side, amount, dec!(1.5));
+
+        match result {
+            Ok(analysis) => assert!(is_allowed, "{analysis:?}"),
+            Err(err) => {
+                assert!(!is_allowed, "{err}");
+                assert!(matches!(err, PriceProtectionError::SlippageExceeded { .. }));
+            }
+        }
+    }
+
+    #[test]
+    pub fn not_enough_depth() {
+        let result =
+            check_price_protection(&snapshot(), market_id(), OrderSide::Buy, dec!(3), dec!(100));
+
+        assert!(matches!(
+            result,
+            Err(PriceProtectionError::NotEnoughDepth { .. })
+        ));
+    }
+
+    #[rstest]
+    #[case(dec!(101), true)]
+    #[case(dec!(98), true)]
+    #[case(dec!(102.5), false)]
+    #[case(dec!(97), false)]
+    pub fn price_collar(#[case] price: Price, #[case] is_allowed: bool) {
+        let result = check_price_collar(price, dec!(100), dec!(2));
+
+        assert_eq!(result.is_ok(), is_allowed, "{result:?}");
+    }
+
+    #[rstest]
+    #[case(dec!(104), true)]
+    #[case(dec!(100_000), false)]
+    #[case(dec!(0.1), false)]
+    pub fn last_trade_deviation(#[case] price: Price, #[case] is_allowed: bool) {
+        let result = check_last_trade_deviation(price, dec!(100), dec!(5));
+
+        assert_eq!(result.is_ok(), is_allowed, "{result:?}");
+    }
+
+    #[rstest]
+    #[case(0, Some(dec!(100)))]
+    #[case(60, Some(dec!(100)))]
+    #[case(61, None)]
+    pub fn outdated_last_trade_price_is_ignored(
+        #[case] age_secs: i64,
+        #[case] expected: Option<Price>,
+    ) {
+        let now = mmb_utils::time::now();
+        let last_trade = Some((dec!(100), now - chrono::Duration::seconds(age_secs)));
+
+        assert_eq!(fresh_last_trade_price(last_trade, now, 60_000), expected);
+        assert_eq!(fresh_last_trade_price(None, now, 60_000), None);
+    }
+
+    #[rstest]
+    #[case(dec!(100.5), Some(dec!(100)), Some(dec!(101)), false)]
+    #[case(dec!(105), Some(dec!(100)), Some(dec!(101)), false)]
+    #[case(dec!(120), Some(dec!(100)), Some(dec!(101)), true)]
+    #[case(dec!(80), Some(dec!(100)), Some(dec!(101)), true)]
+    #[case(dec!(80), None, Some(dec!(101)), false)]
+    #[case(dec!(80), None, None, false)]
+    pub fn bad_print(
+        #[case] price: Price,
+        #[case] bid: Option<Price>,
+        #[case] ask: Option<Price>,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(is_bad_print(price, bid, ask, dec!(10)), expected);
+    }
+}
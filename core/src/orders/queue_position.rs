@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::{nothing_to_do, DateTime};
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::common::{Amount, ExchangeAccountId, MarketAccountId, Price};
+use crate::exchanges::events::{ExchangeEvent, QueuePositionEvent, TradesEvent};
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::math::ConvertPercentToRate;
+use crate::misc::time::time_manager::now;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::event::OrderEventType;
+use crate::orders::order::{ClientOrderId, OrderSide, OrderStatus, OrderType};
+use crate::orders::pool::OrderRef;
+use crate::settings::QueuePositionSettings;
+
+/// Estimated position of resting maker order in queue of its price level
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct QueuePosition {
+    /// Amount of other orders ahead of order at its price level
+    pub amount_ahead: Amount,
+    /// Amount of the whole price level in order book
+    pub level_amount: Amount,
+    pub update_time: DateTime,
+}
+
+#[derive(Debug)]
+struct QueueState {
+    side: OrderSide,
+    price: Price,
+    remaining_amount: Amount,
+    position: QueuePosition,
+    /// Amount traded at price level since last order book update, so it isn't treated as cancellations
+    traded_since_update: Amount,
+    notified_amount_ahead: Amount,
+}
+
+impl QueueState {
+    fn new(
+        side: OrderSide,
+        price: Price,
+        remaining_amount: Amount,
+        level_amount: Amount,
+        time: DateTime,
+    ) -> Self {
+        // new order is placed at the end of queue
+        let amount_ahead = (level_amount - remaining_amount).max(dec!(0));
+        QueueState {
+            side,
+            price,
+            remaining_amount,
+            position: QueuePosition {
+                amount_ahead,
+                level_amount,
+                update_time: time,
+            },
+            traded_since_update: dec!(0),
+            notified_amount_ahead: amount_ahead,
+        }
+    }
+
+    /// Decrease of price level which isn't explained by trades is cancellations. Cancellations are
+    /// spread across the queue proportionally, while new orders are added behind our order
+    fn update_level(&mut self, level_amount: Amount, time: DateTime) {
+        let previous = self.position;
+        let cancelled_amount = previous.level_amount - level_amount - self.traded_since_update;
+        let others_amount = previous.level_amount - self.remaining_amount;
+
+        let mut amount_ahead = previous.amount_ahead;
+        if cancelled_amount > dec!(0) && others_amount > dec!(0) {
+            amount_ahead -= cancelled_amount * amount_ahead / others_amount;
+        }
+
+        self.position = QueuePosition {
+            amount_ahead: amount_ahead
+                .min(level_amount - self.remaining_amount)
+                .max(dec!(0)),
+            level_amount,
+            update_time: time,
+        };
+        self.traded_since_update = dec!(0);
+    }
+
+    /// Trades at order price consume queue from its front, trades at worse price mean that
+    /// the whole queue ahead of order was consumed
+    fn trade(&mut self, price: Price, amount: Amount, time: DateTime) -> bool {
+        let is_through = match self.side {
+            OrderSide::Buy => price < self.price,
+            OrderSide::Sell => price > self.price,
+        };
+
+        let amount_ahead = if is_through {
+            dec!(0)
+        } else if price == self.price {
+            self.traded_since_update += amount;
+            (self.position.amount_ahead - amount).max(dec!(0))
+        } else {
+            return false;
+        };
+
+        self.position.amount_ahead = amount_ahead;
+        self.position.update_time = time;
+        true
+    }
+
+    /// Returns `true` if change of position since last notification is material
+    fn take_material_change(&mut self, min_change: Percent) -> bool {
+        let amount_ahead = self.position.amount_ahead;
+        let notified = self.notified_amount_ahead;
+        let reached_front = amount_ahead.is_zero();
+        let is_material = amount_ahead != notified
+            && (reached_front
+                || (amount_ahead - notified).abs() >= notified * min_change.percent_to_rate());
+
+        if is_material {
+            self.notified_amount_ahead = amount_ahead;
+        }
+        is_material
+    }
+}
+
+/// Estimates queue positions of resting limit orders by order book updates and trade prints
+#[derive(Debug)]
+pub(crate) struct QueuePositionTracker {
+    min_change: Percent,
+    markets: HashMap<MarketAccountId, HashMap<ClientOrderId, QueueState>>,
+}
+
+impl QueuePositionTracker {
+    pub fn new(settings: &QueuePositionSettings) -> Self {
+        QueuePositionTracker {
+            min_change: settings.min_change,
+            markets: HashMap::new(),
+        }
+    }
+
+    /// Returns new position and whether its change is material
+    pub fn level_updated(
+        &mut self,
+        market_account_id: MarketAccountId,
+        client_order_id: &ClientOrderId,
+        (side, price, remaining_amount): (OrderSide, Price, Amount),
+        level_amount: Amount,
+        time: DateTime,
+    ) -> (QueuePosition, bool) {
+        let orders = self.markets.entry(market_account_id).or_default();
+        match orders.get_mut(client_order_id) {
+            Some(state) => {
+                state.remaining_amount = remaining_amount;
+                state.update_level(level_amount, time);
+                let is_material = state.take_material_change(self.min_change);
+                (state.position, is_material)
+            }
+            None => {
+                let state = QueueState::new(side, price, remaining_amount, level_amount, time);
+                let position = state.position;
+                let _ = orders.insert(client_order_id.clone(), state);
+                (position, true)
+            }
+        }
+    }
+
+    /// Returns orders which positions are changed by trade and whether changes are material
+    pub fn trade(
+        &mut self,
+        market_account_id: MarketAccountId,
+        price: Price,
+        amount: Amount,
+        time: DateTime,
+    ) -> Vec<(ClientOrderId, QueuePosition, bool)> {
+        let orders = match self.markets.get_mut(&market_account_id) {
+            Some(orders) => orders,
+            None => return vec![],
+        };
+
+        let min_change = self.min_change;
+        orders
+            .iter_mut()
+            .filter_map(|(client_order_id, state)| {
+                state.trade(price, amount, time).then(|| {
+                    let is_material = state.take_material_change(min_change);
+                    (client_order_id.clone(), state.position, is_material)
+                })
+            })
+            .collect()
+    }
+
+    pub fn remove(&mut self, market_account_id: MarketAccountId, client_order_id: &ClientOrderId) {
+        if let Some(orders) = self.markets.get_mut(&market_account_id) {
+            let _ = orders.remove(client_order_id);
+        }
+    }
+}
+
+/// Tracks queue positions of resting maker orders, stores them to orders and notifies strategies
+/// by `ExchangeEvent::QueuePosition` when priority of order changes materially
+pub(crate) fn start_queue_position_tracking(
+    settings: &QueuePositionSettings,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    events_sender: broadcast::Sender<ExchangeEvent>,
+) {
+    let tracker = QueuePositionTracker::new(settings);
+    let _ = spawn_future(
+        "Queue position tracking",
+        SpawnFutureFlags::STOP_BY_TOKEN,
+        track_queue_positions(tracker, exchanges, events_sender),
+    );
+}
+
+async fn track_queue_positions(
+    mut tracker: QueuePositionTracker,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    events_sender: broadcast::Sender<ExchangeEvent>,
+) -> Result<()> {
+    let mut events_receiver = events_sender.subscribe();
+    let mut local_snapshots_service = LocalSnapshotsService::default();
+
+    let set_position = |order: &OrderRef, position: QueuePosition, is_material: bool| {
+        order.fn_mut(|x| x.internal_props.queue_position = Some(position));
+        if is_material {
+            let _ = events_sender.send(ExchangeEvent::QueuePosition(QueuePositionEvent {
+                order: order.clone(),
+                queue_position: position,
+            }));
+        }
+    };
+
+    loop {
+        let event = match events_receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                log::warn!("Queue position tracking skipped {skipped} exchange events");
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+
+        match event {
+            ExchangeEvent::OrderBookEvent(order_book_event) => {
+                let market_account_id = match local_snapshots_service.update(order_book_event) {
+                    Some(market_account_id) => market_account_id,
+                    None => continue,
+                };
+                let exchange = match exchanges.get(&market_account_id.exchange_account_id) {
+                    Some(exchange) => exchange.clone(),
+                    None => continue,
+                };
+                let snapshot =
+                    local_snapshots_service.get_snapshot_expected(market_account_id.market_id());
+
+                for order in resting_orders(&exchange, market_account_id) {
+                    let (client_order_id, side, price, remaining_amount) = order.fn_ref(|x| {
+                        (
+                            x.header.client_order_id.clone(),
+                            x.header.side,
+                            x.price(),
+                            x.header.amount - x.fills.filled_amount,
+                        )
+                    });
+                    let book_side = match side {
+                        OrderSide::Buy => &snapshot.bids,
+                        OrderSide::Sell => &snapshot.asks,
+                    };
+                    let level_amount = book_side.get(&price).copied().unwrap_or_default();
+
+                    let (position, is_material) = tracker.level_updated(
+                        market_account_id,
+                        &client_order_id,
+                        (side, price, remaining_amount),
+                        level_amount,
+                        now(),
+                    );
+                    set_position(&order, position, is_material);
+                }
+            }
+            ExchangeEvent::Trades(trades_event) => {
+                handle_trades(&mut tracker, &exchanges, &trades_event, &set_position);
+            }
+            ExchangeEvent::OrderEvent(order_event) => {
+                let order = &order_event.order;
+                let market_account_id =
+                    MarketAccountId::new(order.exchange_account_id(), order.currency_pair());
+                match order_event.event_type {
+                    OrderEventType::CreateOrderFailed
+                    | OrderEventType::CancelOrderSucceeded
+                    | OrderEventType::OrderCompleted { .. } => {
+                        tracker.remove(market_account_id, &order.client_order_id())
+                    }
+                    _ => nothing_to_do(),
+                }
+            }
+            _ => nothing_to_do(),
+        }
+    }
+}
+
+fn handle_trades(
+    tracker: &mut QueuePositionTracker,
+    exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+    trades_event: &TradesEvent,
+    set_position: &impl Fn(&OrderRef, QueuePosition, bool),
+) {
+    let exchange = match exchanges.get(&trades_event.exchange_account_id) {
+        Some(exchange) => exchange.clone(),
+        None => return,
+    };
+    let market_account_id =
+        MarketAccountId::new(trades_event.exchange_account_id, trades_event.currency_pair);
+
+    for trade in &trades_event.trades {
+        for (client_order_id, position, is_material) in
+            tracker.trade(market_account_id, trade.price, trade.quantity, now())
+        {
+            if let Some(order) = exchange.orders.get_by_client_id(&client_order_id) {
+                set_position(&order, position, is_material);
+            }
+        }
+    }
+}
+
+fn resting_orders(exchange: &Exchange, market_account_id: MarketAccountId) -> Vec<OrderRef> {
+    exchange
+        .orders
+        .not_finished
+        .by_market(market_account_id.currency_pair)
+        .into_iter()
+        .filter(|x| {
+            x.fn_ref(|order| {
+                order.header.order_type == OrderType::Limit
+                    && order.status() == OrderStatus::Created
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use chrono::Utc;
+
+    fn market_account_id() -> MarketAccountId {
+        MarketAccountId::new(
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    fn tracker() -> QueuePositionTracker {
+        QueuePositionTracker::new(&QueuePositionSettings {
+            min_change: dec!(20),
+        })
+    }
+
+    #[test]
+    pub fn order_moves_ahead_by_trades_and_cancellations() {
+        let now = Utc::now();
+        let mut tracker = tracker();
+        let market = market_account_id();
+        let client_order_id = ClientOrderId::unique_id();
+        let order = (OrderSide::Buy, dec!(100), dec!(1));
+
+        // order is placed behind 9
+        let (position, is_material) =
+            tracker.level_updated(market, &client_order_id, order, dec!(10), now);
+        assert_eq!(position.amount_ahead, dec!(9));
+        assert!(is_material);
+
+        // new orders are added behind
+        let (position, is_material) =
+            tracker.level_updated(market, &client_order_id, order, dec!(15), now);
+        assert_eq!(position.amount_ahead, dec!(9));
+        assert!(!is_material);
+
+        // trade consumes front of queue
+        let changes = tracker.trade(market, dec!(100), dec!(3), now);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].1.amount_ahead, dec!(6));
+        assert!(changes[0].2);
+
+        // decrease by traded amount isn't treated as cancellation
+        let (position, _) = tracker.level_updated(market, &client_order_id, order, dec!(12), now);
+        assert_eq!(position.amount_ahead, dec!(6));
+
+        // cancellation of 5.5 out of 11 others is spread across queue
+        let (position, is_material) =
+            tracker.level_updated(market, &client_order_id, order, dec!(6.5), now);
+        assert_eq!(position.amount_ahead, dec!(3));
+        assert!(is_material);
+    }
+
+    #[test]
+    pub fn trade_through_price_moves_order_to_front() {
+        let now = Utc::now();
+        let mut tracker = tracker();
+        let market = market_account_id();
+        let client_order_id = ClientOrderId::unique_id();
+
+        let _ = tracker.level_updated(
+            market,
+            &client_order_id,
+            (OrderSide::Sell, dec!(100), dec!(1)),
+            dec!(10),
+            now,
+        );
+
+        assert!(tracker.trade(market, dec!(99), dec!(1), now).is_empty());
+
+        let changes = tracker.trade(market, dec!(101), dec!(1), now);
+        assert_eq!(changes[0].1.amount_ahead, dec!(0));
+        assert!(changes[0].2);
+
+        tracker.remove(market, &client_order_id);
+        assert!(tracker.trade(market, dec!(101), dec!(1), now).is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashSet;
+
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::exchanges::common::{Amount, ExchangeAccountId, MarketAccountId};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum ReduceOnlyReason {
+    /// Enabled by operator through control API
+    Manual,
+    /// Liquidation risk of exchange account is high
+    MarginRisk(ExchangeAccountId),
+    /// Balance of market currency on exchange differs from balance expected by fills.
+    /// Only this market is in reduce-only mode
+    BalanceAnomaly(MarketAccountId),
+}
+
+impl ReduceOnlyReason {
+    /// Market which reason is limited to. Reasons without market are applied to all markets
+    pub fn market_account_id(&self) -> Option<MarketAccountId> {
+        match self {
+            ReduceOnlyReason::BalanceAnomaly(market_account_id) => Some(*market_account_id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum ReduceOnlyError {
+    #[error("order amount {amount} exceeds closable position {closable_position}")]
+    PositionIncreasing {
+        amount: Amount,
+        closable_position: Amount,
+    },
+}
+
+/// Order is position reducing if its amount fits into position which is left to close after
+/// active orders of the same side are filled
+pub fn check_reduce_only(
+    amount: Amount,
+    position: Amount,
+    active_orders_amount: Amount,
+) -> Result<(), ReduceOnlyError> {
+    let closable_position = (position - active_orders_amount).max(dec!(0));
+    if amount > closable_position {
+        return Err(ReduceOnlyError::PositionIncreasing {
+            amount,
+            closable_position,
+        });
+    }
+
+    Ok(())
+}
+
+/// Engine state in which only position reducing orders are allowed for all strategies.
+/// Mode is enabled while at least one reason is active. Reasons limited to market enable mode
+/// only for this market
+#[derive(Debug, Default)]
+pub struct ReduceOnlyMode {
+    reasons: Mutex<HashSet<ReduceOnlyReason>>,
+}
+
+impl ReduceOnlyMode {
+    /// Reduce-only mode is enabled for all markets
+    pub fn is_enabled(&self) -> bool {
+        self.reasons
+            .lock()
+            .iter()
+            .any(|x| x.market_account_id().is_none())
+    }
+
+    pub fn is_enabled_for(&self, market_account_id: MarketAccountId) -> bool {
+        self.reasons
+            .lock()
+            .iter()
+            .any(|x| x.market_account_id().is_none_or(|x| x == market_account_id))
+    }
+
+    pub fn reasons(&self) -> Vec<ReduceOnlyReason> {
+        self.reasons.lock().iter().copied().collect()
+    }
+
+    pub fn enable(&self, reason: ReduceOnlyReason) {
+        let mut reasons = self.reasons.lock();
+        if reasons.insert(reason) && reasons.len() == 1 {
+            log::warn!("Reduce-only mode is enabled by {reason:?}");
+        }
+    }
+
+    pub fn disable(&self, reason: ReduceOnlyReason) {
+        let mut reasons = self.reasons.lock();
+        if reasons.remove(&reason) && reasons.is_empty() {
+            log::warn!("Reduce-only mode is disabled by {reason:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(dec!(1), dec!(2), dec!(0), true)]
+    #[case(dec!(2), dec!(2), dec!(0), true)]
+    #[case(dec!(2), dec!(2), dec!(1), false)]
+    #[case(dec!(1), dec!(0), dec!(0), false)]
+    pub fn only_position_reducing_orders_are_allowed(
+        #[case] amount: Amount,
+        #[case] position: Amount,
+        #[case] active_orders_amount: Amount,
+        #[case] is_allowed: bool,
+    ) {
+        assert_eq!(
+            check_reduce_only(amount, position, active_orders_amount).is_ok(),
+            is_allowed
+        );
+    }
+
+    #[test]
+    pub fn mode_is_enabled_while_any_reason_is_active() {
+        let mode = ReduceOnlyMode::default();
+        let margin_risk = ReduceOnlyReason::MarginRisk(ExchangeAccountId::new("Binance", 0));
+
+        mode.enable(ReduceOnlyReason::Manual);
+        mode.enable(margin_risk);
+        mode.disable(ReduceOnlyReason::Manual);
+        assert!(mode.is_enabled());
+
+        mode.disable(margin_risk);
+        assert!(!mode.is_enabled());
+    }
+
+    #[test]
+    pub fn market_reason_enables_mode_only_for_market() {
+        let mode = ReduceOnlyMode::default();
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        let market = |currency_pair: &str| {
+            MarketAccountId::new(
+                exchange_account_id,
+                CurrencyPair::from_codes(currency_pair.into(), "usdt".into()),
+            )
+        };
+
+        mode.enable(ReduceOnlyReason::BalanceAnomaly(market("btc")));
+        assert!(!mode.is_enabled());
+        assert!(mode.is_enabled_for(market("btc")));
+        assert!(!mode.is_enabled_for(market("eth")));
+    }
+}
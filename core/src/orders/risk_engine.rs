@@ -0,0 +1,10 @@
+use anyhow::Result;
+
+use crate::orders::order::OrderCreating;
+
+/// Custom pre-trade checks of application which embeds engine. Orders are checked after
+/// built-in checks of engine right before submitting them to exchange
+pub trait RiskEngine: Send + Sync + 'static {
+    /// Order is rejected if error is returned
+    fn check_order(&self, order: &OrderCreating) -> Result<()>;
+}
@@ -0,0 +1,450 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::{impl_u64_id, time::get_atomic_current_secs};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::common::{ExchangeAccountId, MarketAccountId, Price};
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::infrastructure::spawn_future;
+use crate::math::ConvertPercentToRate;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::order::{
+    ClientOrderId, OrderCreating, OrderHeader, OrderSide, OrderStatus, OrderType,
+};
+
+impl_u64_id!(TrailingStopId);
+
+/// Reference price of market which core managed orders follow
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PriceReference {
+    LastTrade,
+    MiddlePrice,
+}
+
+/// Trailing stop request. Order is created by `header` when reference price moves back from its
+/// best value by `trail` percents. Header side defines direction: sell stop follows rising price
+/// and buy stop follows falling price
+#[derive(Debug, Clone)]
+pub struct TrailingStopRequest {
+    /// Market or limit order which is created when stop is triggered
+    pub header: Arc<OrderHeader>,
+    pub reference: PriceReference,
+    pub trail: Percent,
+    /// Limit price offset from trigger price in unfavorable direction for limit orders
+    pub limit_offset: Percent,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrailingStop {
+    pub id: TrailingStopId,
+    pub request: TrailingStopRequest,
+    /// The most favorable reference price since stop was submitted
+    pub best_price: Option<Price>,
+    /// Exit order is being created. Stop isn't updated until creation fails and stop is re-armed
+    pub is_triggered: bool,
+}
+
+impl TrailingStop {
+    fn new(request: TrailingStopRequest) -> Self {
+        TrailingStop {
+            id: TrailingStopId::generate(),
+            request,
+            best_price: None,
+            is_triggered: false,
+        }
+    }
+
+    pub fn trigger_price(&self) -> Option<Price> {
+        let trail_rate = self.request.trail.percent_to_rate();
+        self.best_price
+            .map(|best_price| match self.request.header.side {
+                OrderSide::Sell => best_price * (dec!(1) - trail_rate),
+                OrderSide::Buy => best_price * (dec!(1) + trail_rate),
+            })
+    }
+
+    /// Moves trigger if price moved favorably. Returns trigger price if stop is triggered
+    pub fn update(&mut self, price: Price) -> Option<Price> {
+        let is_favorable = |best_price| match self.request.header.side {
+            OrderSide::Sell => price > best_price,
+            OrderSide::Buy => price < best_price,
+        };
+        if self.best_price.is_none_or(is_favorable) {
+            self.best_price = Some(price);
+            return None;
+        }
+
+        let trigger_price = self.trigger_price()?;
+        let is_triggered = match self.request.header.side {
+            OrderSide::Sell => price <= trigger_price,
+            OrderSide::Buy => price >= trigger_price,
+        };
+
+        is_triggered.then_some(trigger_price)
+    }
+
+    /// Price of exit order rounded to tick size in unfavorable direction, so limit offset
+    /// isn't reduced by rounding
+    fn order_price(&self, trigger_price: Price, symbol: &Symbol) -> Price {
+        let offset_rate = self.request.limit_offset.percent_to_rate();
+        match self.request.header.side {
+            OrderSide::Sell => {
+                symbol.price_round(trigger_price * (dec!(1) - offset_rate), Round::Floor)
+            }
+            OrderSide::Buy => {
+                symbol.price_round(trigger_price * (dec!(1) + offset_rate), Round::Ceiling)
+            }
+        }
+    }
+}
+
+/// Trailing stops for exchanges without native support. Core follows reference price and creates
+/// order when it's triggered
+pub struct TrailingStopManager {
+    stops: Mutex<HashMap<TrailingStopId, TrailingStop>>,
+}
+
+impl TrailingStopManager {
+    pub fn start(
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Arc<Self> {
+        let manager = Arc::new(TrailingStopManager {
+            stops: Default::default(),
+        });
+
+        let _ = spawn_future(
+            "Trailing stops events handling",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            manager
+                .clone()
+                .handle_events(exchanges, events_receiver, cancellation_token),
+        );
+
+        manager
+    }
+
+    pub fn submit(&self, request: TrailingStopRequest) -> Result<TrailingStopId> {
+        match request.header.order_type {
+            OrderType::Market | OrderType::Limit => {}
+            order_type => bail!("Trailing stop can't create order of type {order_type:?}"),
+        }
+
+        let stop = TrailingStop::new(request);
+        let id = stop.id;
+        log::info!("Trailing stop {id} is submitted: {stop:?}");
+        let _ = self.stops.lock().insert(id, stop);
+
+        Ok(id)
+    }
+
+    pub fn cancel(&self, id: TrailingStopId) -> bool {
+        self.stops.lock().remove(&id).is_some()
+    }
+
+    pub fn get(&self, id: TrailingStopId) -> Option<TrailingStop> {
+        self.stops.lock().get(&id).cloned()
+    }
+
+    /// Updates stops of market by reference price and marks triggered stops. Triggered stops
+    /// are kept until their exit orders are accepted by exchange.
+    /// Returns triggered stops with their trigger prices
+    fn update(
+        &self,
+        market_account_id: MarketAccountId,
+        reference: PriceReference,
+        price: Price,
+    ) -> Vec<(TrailingStop, Price)> {
+        let mut stops = self.stops.lock();
+
+        let mut triggered = vec![];
+        for stop in stops.values_mut() {
+            if stop.is_triggered
+                || stop.request.reference != reference
+                || stop.request.header.market_account_id() != market_account_id
+            {
+                continue;
+            }
+
+            if let Some(trigger_price) = stop.update(price) {
+                stop.is_triggered = true;
+                triggered.push((stop.clone(), trigger_price));
+            }
+        }
+
+        triggered
+    }
+
+    /// Removes stop after its exit order is accepted by exchange
+    fn complete(&self, id: TrailingStopId) {
+        let _ = self.stops.lock().remove(&id);
+    }
+
+    /// Re-arms stop which exit order wasn't created, so it's triggered again by the next price.
+    /// Exit order gets new client order id because failed one stays in orders pool
+    fn rearm(&self, id: TrailingStopId) {
+        if let Some(stop) = self.stops.lock().get_mut(&id) {
+            stop.is_triggered = false;
+            Arc::make_mut(&mut stop.request.header).client_order_id = ClientOrderId::unique_id();
+        }
+    }
+
+    async fn create_exit_order(
+        self: Arc<Self>,
+        exchange: Arc<Exchange>,
+        stop: TrailingStop,
+        trigger_price: Price,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let result = async {
+            let symbol = exchange.get_symbol(stop.request.header.currency_pair)?;
+            let order_to_create = OrderCreating {
+                price: stop.order_price(trigger_price, &symbol),
+                header: stop.request.header.clone(),
+            };
+            let order = exchange
+                .create_order(order_to_create, None, cancellation_token)
+                .await?;
+            if order.status() == OrderStatus::FailedToCreate {
+                bail!("Order {} isn't created", order.client_order_id());
+            }
+            Ok(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => {
+                self.complete(stop.id);
+                Ok(())
+            }
+            Err(error) => {
+                self.rearm(stop.id);
+                Err(error).with_context(|| {
+                    format!(
+                        "Failed to create exit order of trailing stop {}, stop is re-armed",
+                        stop.id
+                    )
+                })
+            }
+        }
+    }
+
+    async fn handle_events(
+        self: Arc<Self>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let mut snapshots = LocalSnapshotsService::default();
+
+        loop {
+            let event = match events_receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Trailing stops manager skipped {skipped} exchange events");
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            let (market_account_id, reference, price) = match event {
+                ExchangeEvent::Trades(trades_event) => {
+                    let price = match trades_event.trades.last() {
+                        Some(trade) => trade.price,
+                        None => continue,
+                    };
+                    let market_account_id = MarketAccountId::new(
+                        trades_event.exchange_account_id,
+                        trades_event.currency_pair,
+                    );
+                    (market_account_id, PriceReference::LastTrade, price)
+                }
+                ExchangeEvent::OrderBookEvent(order_book_event) => {
+                    let market_account_id = match snapshots.update(order_book_event) {
+                        Some(market_account_id) => market_account_id,
+                        None => continue,
+                    };
+                    let market_id = market_account_id.market_id();
+                    let price = match snapshots
+                        .get_snapshot(market_id)
+                        .and_then(|x| x.calculate_middle_price(market_id))
+                    {
+                        Some(price) => price,
+                        None => continue,
+                    };
+                    (market_account_id, PriceReference::MiddlePrice, price)
+                }
+                _ => continue,
+            };
+
+            for (stop, trigger_price) in self.update(market_account_id, reference, price) {
+                let exchange = match exchanges.get(&market_account_id.exchange_account_id) {
+                    Some(exchange) => exchange.clone(),
+                    None => {
+                        log::error!(
+                            "Trailing stop {} is triggered but exchange {} isn't configured",
+                            stop.id,
+                            market_account_id.exchange_account_id
+                        );
+                        self.rearm(stop.id);
+                        continue;
+                    }
+                };
+
+                log::info!(
+                    "Trailing stop {} is triggered at {trigger_price} by reference price {price}",
+                    stop.id
+                );
+                let action = self.clone().create_exit_order(
+                    exchange,
+                    stop,
+                    trigger_price,
+                    cancellation_token.clone(),
+                );
+                let _ = spawn_future(
+                    "Trailing stop order creation",
+                    SpawnFutureFlags::STOP_BY_TOKEN,
+                    action,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use crate::exchanges::general::symbol::Precision;
+    use crate::orders::order::OrderExecutionType;
+    use chrono::Utc;
+    use rstest::rstest;
+
+    fn trailing_stop(side: OrderSide) -> TrailingStop {
+        let header = OrderHeader::new(
+            ClientOrderId::unique_id(),
+            Utc::now(),
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            OrderType::Limit,
+            side,
+            dec!(1),
+            OrderExecutionType::None,
+            None,
+            None,
+            "test".to_owned(),
+        );
+
+        TrailingStop::new(TrailingStopRequest {
+            header,
+            reference: PriceReference::LastTrade,
+            trail: dec!(10),
+            limit_offset: dec!(1),
+        })
+    }
+
+    #[rstest]
+    #[case(OrderSide::Sell, &[dec!(100), dec!(120), dec!(110), dec!(108)], Some(dec!(108)))]
+    #[case(OrderSide::Sell, &[dec!(100), dec!(120), dec!(110), dec!(109)], None)]
+    #[case(OrderSide::Buy, &[dec!(100), dec!(80), dec!(87), dec!(88)], Some(dec!(88)))]
+    #[case(OrderSide::Buy, &[dec!(100), dec!(80), dec!(87)], None)]
+    pub fn trigger_follows_favorable_moves(
+        #[case] side: OrderSide,
+        #[case] prices: &[Price],
+        #[case] expected_trigger: Option<Price>,
+    ) {
+        let mut stop = trailing_stop(side);
+
+        let trigger_price = prices.iter().find_map(|&price| stop.update(price));
+
+        assert_eq!(trigger_price, expected_trigger);
+    }
+
+    fn symbol() -> Symbol {
+        Symbol::new(
+            false,
+            false,
+            "btc".into(),
+            "btc".into(),
+            "usdt".into(),
+            "usdt".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "btc".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.5) },
+            Precision::ByTick { tick: dec!(0.01) },
+        )
+    }
+
+    #[rstest]
+    #[case(OrderSide::Sell, dec!(100), dec!(90), dec!(89))]
+    #[case(OrderSide::Buy, dec!(100), dec!(110), dec!(111.5))]
+    pub fn limit_price_is_offset_from_trigger_and_rounded_to_tick(
+        #[case] side: OrderSide,
+        #[case] price: Price,
+        #[case] expected_trigger: Price,
+        #[case] expected_order_price: Price,
+    ) {
+        let mut stop = trailing_stop(side);
+        let _ = stop.update(price);
+
+        assert_eq!(stop.trigger_price(), Some(expected_trigger));
+        assert_eq!(
+            stop.order_price(expected_trigger, &symbol()),
+            expected_order_price
+        );
+    }
+
+    #[test]
+    pub fn triggered_stop_is_kept_until_rearmed() {
+        let manager = TrailingStopManager {
+            stops: Default::default(),
+        };
+        let stop = trailing_stop(OrderSide::Sell);
+        let market_account_id = stop.request.header.market_account_id();
+        let client_order_id = stop.request.header.client_order_id.clone();
+        let id = manager.submit(stop.request).expect("in test");
+
+        let _ = manager.update(market_account_id, PriceReference::LastTrade, dec!(100));
+        let triggered = manager.update(market_account_id, PriceReference::LastTrade, dec!(80));
+        assert_eq!(triggered.len(), 1);
+        assert!(manager
+            .update(market_account_id, PriceReference::LastTrade, dec!(70))
+            .is_empty());
+
+        manager.rearm(id);
+        let stop = manager.get(id).expect("in test");
+        assert!(!stop.is_triggered);
+        assert_ne!(stop.request.header.client_order_id, client_order_id);
+        assert_eq!(
+            manager
+                .update(market_account_id, PriceReference::LastTrade, dec!(70))
+                .len(),
+            1
+        );
+
+        manager.complete(id);
+        assert!(manager.get(id).is_none());
+    }
+}
@@ -10,6 +10,7 @@ use tokio::sync::{mpsc, oneshot};
 
 use crate::{
     config::{save_settings, CONFIG_PATH, CREDENTIALS_PATH},
+    errors::error_codes_catalog,
     infrastructure::spawn_future_ok,
     lifecycle::app_lifetime_manager::{ActionAfterGracefulShutdown, AppLifetimeManager},
     rpc::core_api::FAILED_TO_SEND_STOP_NOTIFICATION,
@@ -27,6 +28,13 @@ pub(super) fn set_config(settings: String) -> Result<()> {
     Ok(())
 }
 
+pub(super) fn serialize_error_codes() -> Result<String> {
+    serde_json::to_string(&error_codes_catalog()).map_err(|err| {
+        log::warn!("Failed to serialize error codes: {err}");
+        server_side_error(ErrorCode::FailedToSaveNewConfig)
+    })
+}
+
 /// Send signal to stop TradingEngine
 pub(super) fn send_stop(
     stopper: Arc<Mutex<Option<mpsc::Sender<ActionAfterGracefulShutdown>>>>,
@@ -2,12 +2,12 @@ use anyhow::Result;
 use parking_lot::Mutex;
 use tokio::sync::{mpsc, oneshot};
 
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
 use crate::{
     lifecycle::{
         app_lifetime_manager::{ActionAfterGracefulShutdown, AppLifetimeManager},
-        trading_engine::Service,
+        trading_engine::{EngineContext, Service},
     },
     statistic_service::StatisticService,
 };
@@ -31,7 +31,9 @@ impl CoreApi {
     pub(crate) fn create_and_start(
         lifetime_manager: Arc<AppLifetimeManager>,
         engine_settings: String,
+        effective_settings: String,
         statistics: Arc<StatisticService>,
+        engine_context: Weak<EngineContext>,
     ) -> Result<Arc<Self>> {
         let (server_stopper_tx, server_stopper_rx) =
             mpsc::channel::<ActionAfterGracefulShutdown>(10);
@@ -44,6 +46,8 @@ impl CoreApi {
             server_stopper_tx.clone(),
             statistics,
             engine_settings,
+            effective_settings,
+            engine_context,
         ));
 
         spawn_server_stopping_action(
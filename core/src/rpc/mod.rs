@@ -1,5 +1,6 @@
 pub mod common;
 pub mod config_waiter;
 pub mod core_api;
+pub(crate) mod operators;
 pub mod rpc_impl;
 pub mod rpc_impl_no_config;
@@ -0,0 +1,66 @@
+use crate::settings::ControlApiOperatorSettings;
+
+/// Resolves name of operator by token of control API. Tokens are compared in constant time
+pub(crate) fn authenticate_operator(
+    operators: &[ControlApiOperatorSettings],
+    token: &str,
+) -> Option<String> {
+    if token.is_empty() {
+        return None;
+    }
+
+    operators
+        .iter()
+        .filter(|x| !x.token.is_empty())
+        .find(|x| is_equal_in_constant_time(x.token.as_bytes(), token.as_bytes()))
+        .map(|x| x.name.clone())
+}
+
+fn is_equal_in_constant_time(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+
+    left.iter()
+        .zip(right)
+        .fold(0u8, |diff, (l, r)| diff | (l ^ r))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operators() -> Vec<ControlApiOperatorSettings> {
+        vec![
+            ControlApiOperatorSettings {
+                name: "alice".to_owned(),
+                token: "token_a".to_owned(),
+            },
+            ControlApiOperatorSettings {
+                name: "bob".to_owned(),
+                token: "token_b".to_owned(),
+            },
+            ControlApiOperatorSettings {
+                name: "disabled".to_owned(),
+                token: String::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn operator_is_resolved_by_token() {
+        assert_eq!(
+            authenticate_operator(&operators(), "token_b"),
+            Some("bob".to_owned())
+        );
+    }
+
+    #[test]
+    fn unknown_or_empty_token_is_rejected() {
+        assert_eq!(authenticate_operator(&operators(), "token_c"), None);
+        assert_eq!(authenticate_operator(&operators(), "token"), None);
+        assert_eq!(authenticate_operator(&operators(), ""), None);
+        assert_eq!(authenticate_operator(&operators(), "alice"), None);
+    }
+}
@@ -1,23 +1,56 @@
-use jsonrpc_core::Result;
+use futures::FutureExt;
+use itertools::Itertools;
+use jsonrpc_core::{BoxFuture, Result};
+use mmb_rpc::rest_api::compaction_error;
+use mmb_rpc::rest_api::engine_is_not_ready_error;
+use mmb_rpc::rest_api::export_error;
+use mmb_rpc::rest_api::market_request_error;
+use mmb_rpc::rest_api::rebalancing_error;
+use mmb_rpc::rest_api::risk_request_error;
 use mmb_rpc::rest_api::server_side_error;
+use mmb_rpc::rest_api::state_transfer_error;
+use mmb_rpc::rest_api::unauthorized_error;
+use mmb_rpc::rest_api::with_error_code;
+use mmb_rpc::rest_api::withdrawal_request_error;
 use mmb_rpc::rest_api::MmbRpc;
 use parking_lot::Mutex;
 use tokio::sync::mpsc;
 
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Weak};
 
+use crate::balance::manager::balances::BalanceTreesReport;
+use crate::database::retention::EventRetention;
+use crate::database::tax_export::{self, TaxExportFormat};
+use crate::errors::WithErrorCode;
+use crate::exchanges::common::{CurrencyPair, ExchangeAccountId, MarketAccountId};
+use crate::exchanges::general::exchange::Exchange;
 use crate::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
+use crate::lifecycle::state_transfer::{self, EngineState};
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::orders::reduce_only::{ReduceOnlyMode, ReduceOnlyReason};
+use crate::services::news_restrictions::{ExternalSignal, NewsRestrictionsService};
+use crate::services::value_at_risk::ValueAtRiskService;
+use crate::settings::{MarketMode, PriceShockSettings};
+use crate::settings_values::parse_decimal;
 use crate::statistic_service::StatisticService;
+use crate::treasury::rebalancing::{RebalancingPlanId, RebalancingPlanner};
+use crate::treasury::withdrawals::{WithdrawalId, WithdrawalsService};
 use mmb_rpc::rest_api::ErrorCode;
 
 use super::common::send_restart;
 use super::common::send_stop;
+use super::common::serialize_error_codes;
 use super::common::set_config;
+use super::operators::authenticate_operator;
 
 pub struct RpcImpl {
     server_stopper_tx: Arc<Mutex<Option<mpsc::Sender<ActionAfterGracefulShutdown>>>>,
     statistics: Arc<StatisticService>,
     engine_settings: String,
+    /// Resolved settings with redacted credentials
+    effective_settings: String,
+    engine_context: Weak<EngineContext>,
 }
 
 impl RpcImpl {
@@ -25,30 +58,194 @@ impl RpcImpl {
         server_stopper_tx: Arc<Mutex<Option<mpsc::Sender<ActionAfterGracefulShutdown>>>>,
         statistics: Arc<StatisticService>,
         engine_settings: String,
+        effective_settings: String,
+        engine_context: Weak<EngineContext>,
     ) -> Self {
         Self {
             server_stopper_tx,
             statistics,
             engine_settings,
+            effective_settings,
+            engine_context,
         }
     }
 }
 
+impl RpcImpl {
+    fn get_exchange(&self, exchange_account_id: ExchangeAccountId) -> Result<Arc<Exchange>> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| market_request_error("Engine context is dropped".to_owned()))?;
+        let exchange = engine_context
+            .exchanges
+            .get(&exchange_account_id)
+            .map(|x| x.clone())
+            .ok_or_else(|| {
+                market_request_error(format!("Exchange {exchange_account_id} isn't found"))
+            })?;
+
+        Ok(exchange)
+    }
+
+    fn withdrawals_service(&self) -> Result<Arc<WithdrawalsService>> {
+        match self.engine_context.upgrade() {
+            None => Err(withdrawal_request_error(
+                "Engine context is dropped".to_owned(),
+            )),
+            Some(engine_context) => Ok(engine_context.withdrawals.clone()),
+        }
+    }
+
+    /// Name of operator who owns token of control API
+    fn operator(&self, operator_token: &str) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+        authenticate_operator(
+            &engine_context.core_settings.control_api_operators,
+            operator_token,
+        )
+        .ok_or_else(|| unauthorized_error("Operator token isn't valid".to_owned()))
+    }
+
+    /// Records action of operator to audit log if it's configured
+    fn audit(&self, action: &str, details: String) {
+        if let Some(audit_log) = self
+            .engine_context
+            .upgrade()
+            .and_then(|x| x.audit_log.clone())
+        {
+            audit_log.record_manual_intervention(action, details);
+        }
+    }
+
+    fn reduce_only_mode(&self) -> Result<Arc<ReduceOnlyMode>> {
+        match self.engine_context.upgrade() {
+            None => Err(engine_is_not_ready_error(
+                "Engine context is dropped".to_owned(),
+            )),
+            Some(engine_context) => Ok(engine_context.reduce_only.clone()),
+        }
+    }
+
+    fn event_retention(&self) -> Result<Arc<EventRetention>> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+        engine_context
+            .event_retention
+            .clone()
+            .ok_or_else(|| compaction_error("Retention of events isn't configured".to_owned()))
+    }
+
+    fn rebalancing(&self) -> Result<Arc<RebalancingPlanner>> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+        engine_context
+            .rebalancing
+            .clone()
+            .ok_or_else(|| rebalancing_error("Rebalancing isn't configured".to_owned()))
+    }
+
+    fn value_at_risk_service(&self) -> Result<Arc<ValueAtRiskService>> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+        engine_context
+            .value_at_risk
+            .clone()
+            .ok_or_else(|| risk_request_error("Value at risk isn't configured".to_owned()))
+    }
+
+    fn news_restrictions_service(&self) -> Result<Arc<NewsRestrictionsService>> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+        engine_context
+            .news_restrictions
+            .clone()
+            .ok_or_else(|| risk_request_error("News restrictions aren't configured".to_owned()))
+    }
+}
+
+fn parse_market(
+    exchange_account_id: &str,
+    currency_pair: &str,
+) -> Result<(ExchangeAccountId, CurrencyPair)> {
+    let exchange_account_id = exchange_account_id.parse().map_err(|err| {
+        market_request_error(format!(
+            "Invalid exchange account id {exchange_account_id}: {err:?}"
+        ))
+    })?;
+    let currency_pair = match currency_pair.split_once('/') {
+        Some((base, quote)) if !base.is_empty() && !quote.is_empty() => {
+            CurrencyPair::from_codes(base.into(), quote.into())
+        }
+        _ => {
+            return Err(market_request_error(format!(
+                "Invalid currency pair {currency_pair}, expected format is base/quote"
+            )))
+        }
+    };
+
+    Ok((exchange_account_id, currency_pair))
+}
+
+fn parse_rebalancing_plan_id(plan_id: &str) -> Result<RebalancingPlanId> {
+    plan_id
+        .parse()
+        .map_err(|err| rebalancing_error(format!("Invalid rebalancing plan id {plan_id}: {err}")))
+}
+
+fn parse_withdrawal_id(withdrawal_id: &str) -> Result<WithdrawalId> {
+    withdrawal_id.parse().map_err(|err| {
+        withdrawal_request_error(format!("Invalid withdrawal id {withdrawal_id}: {err}"))
+    })
+}
+
 impl MmbRpc for RpcImpl {
     fn health(&self) -> Result<String> {
         Ok("Engine is working".into())
     }
 
+    fn ready(&self) -> Result<String> {
+        let problems = match self.engine_context.upgrade() {
+            None => vec!["Engine context is dropped".to_owned()],
+            Some(engine_context) => engine_context.get_readiness_problems(),
+        };
+
+        if problems.is_empty() {
+            return Ok("Engine is ready".into());
+        }
+
+        Err(engine_is_not_ready_error(problems.join("; ")))
+    }
+
     fn stop(&self) -> Result<String> {
-        send_stop(self.server_stopper_tx.clone())
+        let result = send_stop(self.server_stopper_tx.clone())?;
+        self.audit("stop", "Engine is stopped".to_owned());
+        Ok(result)
     }
 
     fn get_config(&self) -> Result<String> {
         Ok(self.engine_settings.clone())
     }
 
-    fn set_config(&self, settings: String) -> Result<String> {
+    fn get_effective_config(&self) -> Result<String> {
+        Ok(self.effective_settings.clone())
+    }
+
+    fn set_config(&self, settings: String, operator_token: String) -> Result<String> {
+        let operator = self.operator(&operator_token)?;
         set_config(settings)?;
+        self.audit("set_config", format!("Config is updated by {operator}"));
         send_restart(self.server_stopper_tx.clone())?;
         Ok("Config was successfully updated. Trading engine will be restarted".into())
     }
@@ -66,4 +263,603 @@ impl MmbRpc for RpcImpl {
 
         Ok(json_statistic)
     }
+
+    fn withdrawals(&self) -> Result<String> {
+        let waiting_approval = self.withdrawals_service()?.waiting_approval();
+        serde_json::to_string(&waiting_approval).map_err(|err| {
+            withdrawal_request_error(format!("Failed to serialize withdrawals: {err}"))
+        })
+    }
+
+    fn approve_withdrawal(&self, withdrawal_id: String, operator_token: String) -> Result<String> {
+        let operator = self.operator(&operator_token)?;
+        let withdrawal_id = parse_withdrawal_id(&withdrawal_id)?;
+        self.withdrawals_service()?
+            .approve(withdrawal_id, operator.clone())
+            .map_err(|err| {
+                with_error_code(
+                    withdrawal_request_error(format!("{err:?}")),
+                    err.error_code(),
+                )
+            })?;
+        self.audit(
+            "approve_withdrawal",
+            format!("Withdrawal {withdrawal_id} is approved by {operator}"),
+        );
+
+        Ok(format!("Withdrawal {withdrawal_id} was approved"))
+    }
+
+    fn reject_withdrawal(&self, withdrawal_id: String, operator_token: String) -> Result<String> {
+        let operator = self.operator(&operator_token)?;
+        let withdrawal_id = parse_withdrawal_id(&withdrawal_id)?;
+        self.withdrawals_service()?
+            .reject(withdrawal_id, operator.clone())
+            .map_err(|err| {
+                with_error_code(
+                    withdrawal_request_error(format!("{err:?}")),
+                    err.error_code(),
+                )
+            })?;
+        self.audit(
+            "reject_withdrawal",
+            format!("Withdrawal {withdrawal_id} is rejected by {operator}"),
+        );
+
+        Ok(format!("Withdrawal {withdrawal_id} was rejected"))
+    }
+
+    fn reduce_only(&self) -> Result<String> {
+        let reasons = self.reduce_only_mode()?.reasons();
+        serde_json::to_string(&reasons).map_err(|err| {
+            log::warn!("Failed to serialize reduce-only reasons {reasons:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn set_reduce_only(&self, enabled: bool) -> Result<String> {
+        let reduce_only_mode = self.reduce_only_mode()?;
+        match enabled {
+            true => reduce_only_mode.enable(ReduceOnlyReason::Manual),
+            false => reduce_only_mode.disable(ReduceOnlyReason::Manual),
+        }
+        self.audit(
+            "set_reduce_only",
+            format!("Manual reduce-only mode is set to {enabled}"),
+        );
+
+        Ok(format!(
+            "Manual reduce-only mode is {}, engine is in reduce-only mode: {}",
+            if enabled { "enabled" } else { "disabled" },
+            reduce_only_mode.is_enabled()
+        ))
+    }
+
+    fn performance_attribution(&self) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+        let performance_attribution =
+            engine_context
+                .performance_attribution
+                .as_ref()
+                .ok_or_else(|| {
+                    engine_is_not_ready_error("Performance attribution isn't configured".to_owned())
+                })?;
+
+        let reports = performance_attribution.reports();
+        serde_json::to_string(&reports).map_err(|err| {
+            log::warn!("Failed to serialize performance attribution reports: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn balance_trees(&self) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let balances = engine_context.balance_manager.lock().get_balances();
+        serde_json::to_string(&BalanceTreesReport::new(&balances)).map_err(|err| {
+            log::warn!("Failed to serialize balance trees: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn pause_market(&self, exchange_account_id: String, currency_pair: String) -> Result<String> {
+        let (exchange_account_id, currency_pair) =
+            parse_market(&exchange_account_id, &currency_pair)?;
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| market_request_error("Engine context is dropped".to_owned()))?;
+        let exchange = self.get_exchange(exchange_account_id)?;
+        let cancellation_token = engine_context.lifetime_manager.stop_token();
+
+        match exchange.pause_market(currency_pair, cancellation_token) {
+            true => {
+                let message =
+                    format!("Quoting on {currency_pair} on {exchange_account_id} is paused");
+                self.audit("pause_market", message.clone());
+                Ok(message)
+            }
+            false => Ok(format!(
+                "Quoting on {currency_pair} on {exchange_account_id} is already paused"
+            )),
+        }
+    }
+
+    fn resume_market(&self, exchange_account_id: String, currency_pair: String) -> Result<String> {
+        let (exchange_account_id, currency_pair) =
+            parse_market(&exchange_account_id, &currency_pair)?;
+
+        match self
+            .get_exchange(exchange_account_id)?
+            .resume_market(currency_pair)
+        {
+            true => {
+                let message =
+                    format!("Quoting on {currency_pair} on {exchange_account_id} is resumed");
+                self.audit("resume_market", message.clone());
+                Ok(message)
+            }
+            false => Ok(format!(
+                "Quoting on {currency_pair} on {exchange_account_id} isn't paused"
+            )),
+        }
+    }
+
+    fn desired_amounts(&self) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let desired_amounts = engine_context.desired_amounts.get_all();
+        serde_json::to_string(&desired_amounts).map_err(|err| {
+            log::warn!("Failed to serialize desired amounts {desired_amounts:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn set_desired_amount(
+        &self,
+        exchange_account_id: String,
+        currency_pair: String,
+        amount: String,
+    ) -> Result<String> {
+        let (exchange_account_id, currency_pair) =
+            parse_market(&exchange_account_id, &currency_pair)?;
+        let amount = match parse_decimal(&amount) {
+            Ok(amount) if amount.is_sign_positive() => amount,
+            Ok(amount) => {
+                return Err(market_request_error(format!(
+                    "Desired amount {amount} is negative"
+                )))
+            }
+            Err(err) => return Err(market_request_error(format!("{err:?}"))),
+        };
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| market_request_error("Engine context is dropped".to_owned()))?;
+        if !self
+            .get_exchange(exchange_account_id)?
+            .symbols
+            .contains_key(&currency_pair)
+        {
+            return Err(market_request_error(format!(
+                "Currency pair {currency_pair} isn't found on {exchange_account_id}"
+            )));
+        }
+
+        let market_account_id = MarketAccountId::new(exchange_account_id, currency_pair);
+        let previous = engine_context
+            .desired_amounts
+            .set(market_account_id, amount)
+            .map(|x| x.to_string())
+            .unwrap_or_else(|| "max amount from settings".to_owned());
+        let message = format!(
+            "Desired amount of {currency_pair} on {exchange_account_id} is changed from {previous} to {amount}"
+        );
+        self.audit("set_desired_amount", message.clone());
+
+        Ok(message)
+    }
+
+    fn market_modes(&self) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let states = engine_context.market_rollout.states();
+        serde_json::to_string(&states).map_err(|err| {
+            log::warn!("Failed to serialize market modes {states:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn set_market_mode(
+        &self,
+        exchange_account_id: String,
+        currency_pair: String,
+        mode: String,
+        reduced_size: String,
+    ) -> Result<String> {
+        let (exchange_account_id, currency_pair) =
+            parse_market(&exchange_account_id, &currency_pair)?;
+        let mode = match mode.as_str() {
+            "shadow" => MarketMode::Shadow,
+            "reduced" => MarketMode::Reduced,
+            "full" => MarketMode::Full,
+            _ => {
+                return Err(market_request_error(format!(
+                    "Unknown market mode {mode}, expected shadow, reduced or full"
+                )))
+            }
+        };
+        let reduced_size = match reduced_size.trim() {
+            "" => None,
+            reduced_size => Some(
+                parse_decimal(reduced_size)
+                    .map_err(|err| market_request_error(format!("{err:?}")))?,
+            ),
+        };
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| market_request_error("Engine context is dropped".to_owned()))?;
+        if !self
+            .get_exchange(exchange_account_id)?
+            .symbols
+            .contains_key(&currency_pair)
+        {
+            return Err(market_request_error(format!(
+                "Currency pair {currency_pair} isn't found on {exchange_account_id}"
+            )));
+        }
+
+        let market_account_id = MarketAccountId::new(exchange_account_id, currency_pair);
+        let previous_mode = engine_context
+            .market_rollout
+            .set_mode(market_account_id, mode, reduced_size)
+            .map_err(|err| market_request_error(format!("{err:?}")))?;
+        let message = format!(
+            "Mode of {currency_pair} on {exchange_account_id} is changed from {previous_mode:?} to {mode:?}"
+        );
+        self.audit("set_market_mode", message.clone());
+
+        Ok(message)
+    }
+
+    fn export_state(&self) -> BoxFuture<Result<String>> {
+        let engine_context = self.engine_context.clone();
+        async move {
+            let engine_context = engine_context
+                .upgrade()
+                .ok_or_else(|| state_transfer_error("Engine context is dropped".to_owned()))?;
+
+            let state = state_transfer::export_state(&engine_context)
+                .await
+                .map_err(|err| state_transfer_error(format!("{err:?}")))?;
+            serde_json::to_string(&state).map_err(|err| {
+                state_transfer_error(format!("Failed to serialize engine state: {err}"))
+            })
+        }
+        .boxed()
+    }
+
+    fn import_state(&self, state: String) -> BoxFuture<Result<String>> {
+        let engine_context = self.engine_context.clone();
+        async move {
+            let engine_context = engine_context
+                .upgrade()
+                .ok_or_else(|| state_transfer_error("Engine context is dropped".to_owned()))?;
+
+            let state: EngineState = serde_json::from_str(&state).map_err(|err| {
+                state_transfer_error(format!("Failed to parse engine state: {err}"))
+            })?;
+            let export_time = state.export_time;
+            state_transfer::import_state(&engine_context, state)
+                .await
+                .map_err(|err| state_transfer_error(format!("{err:?}")))?;
+            if let Some(audit_log) = &engine_context.audit_log {
+                audit_log.record_manual_intervention(
+                    "import_state",
+                    format!("Engine state exported at {export_time} is imported"),
+                );
+            }
+
+            Ok(format!(
+                "Engine state exported at {export_time} was imported"
+            ))
+        }
+        .boxed()
+    }
+
+    fn host_stats(&self) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let host_stats: BTreeMap<_, _> = engine_context
+            .exchanges
+            .iter()
+            .map(|x| (x.key().to_string(), x.get_host_stats()))
+            .filter(|(_, stats)| !stats.is_empty())
+            .collect();
+        serde_json::to_string(&host_stats).map_err(|err| {
+            log::warn!("Failed to serialize host stats {host_stats:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn connection_pool_stats(&self) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let pool_stats: BTreeMap<_, _> = engine_context
+            .exchanges
+            .iter()
+            .filter_map(|x| Some((x.key().to_string(), x.get_connection_pool_stats()?)))
+            .collect();
+        serde_json::to_string(&pool_stats).map_err(|err| {
+            log::warn!("Failed to serialize connection pool stats {pool_stats:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn parsing_stats(&self) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let parsing_stats: BTreeMap<_, _> = engine_context
+            .exchanges
+            .iter()
+            .map(|x| (x.key().to_string(), x.get_parsing_stats()))
+            .collect();
+        serde_json::to_string(&parsing_stats).map_err(|err| {
+            log::warn!("Failed to serialize parsing stats {parsing_stats:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn request_queue_stats(&self) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let queue_stats: BTreeMap<_, BTreeMap<_, _>> = engine_context
+            .exchanges
+            .iter()
+            .map(|x| {
+                let wait_metrics = engine_context.timeout_manager.queue_wait_metrics(*x.key());
+                (x.key().to_string(), wait_metrics.into_iter().collect())
+            })
+            .collect();
+        serde_json::to_string(&queue_stats).map_err(|err| {
+            log::warn!("Failed to serialize request queue stats {queue_stats:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn payload_anomalies(&self) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let anomalies: BTreeMap<_, _> = engine_context
+            .exchanges
+            .iter()
+            .map(|x| (x.key().to_string(), x.get_payload_anomalies()))
+            .collect();
+        serde_json::to_string(&anomalies).map_err(|err| {
+            log::warn!("Failed to serialize payload anomalies {anomalies:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn error_codes(&self) -> Result<String> {
+        serialize_error_codes()
+    }
+
+    fn tax_export(&self, format: String) -> Result<String> {
+        let format =
+            TaxExportFormat::parse(&format).map_err(|err| export_error(err.to_string()))?;
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let orders: Vec<_> = engine_context
+            .exchanges
+            .iter()
+            .flat_map(|exchange| {
+                exchange
+                    .orders
+                    .cache_by_client_id
+                    .iter()
+                    .map(|x| x.deep_clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Ok(tax_export::export_csv(&orders, format))
+    }
+
+    fn fee_tiers(&self) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let fee_tiers: BTreeMap<_, _> = engine_context
+            .exchanges
+            .iter()
+            .map(|x| (x.key().to_string(), x.get_fee_tier_report()))
+            .collect();
+        serde_json::to_string(&fee_tiers).map_err(|err| {
+            log::warn!("Failed to serialize fee tiers {fee_tiers:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn compact_events(&self) -> Result<String> {
+        if !self.event_retention()?.trigger_compaction() {
+            return Err(compaction_error(
+                "Compaction of events is already running".to_owned(),
+            ));
+        }
+        self.audit(
+            "compact_events",
+            "Compaction of events is started".to_owned(),
+        );
+
+        Ok("Compaction of events is started".to_owned())
+    }
+
+    fn event_compaction(&self) -> Result<String> {
+        let status = self.event_retention()?.status();
+        serde_json::to_string(&status).map_err(|err| {
+            log::warn!("Failed to serialize compaction status {status:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn rebalancing_plans(&self) -> Result<String> {
+        let plans = self.rebalancing()?.plans();
+        serde_json::to_string(&plans).map_err(|err| {
+            log::warn!("Failed to serialize rebalancing plans: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn create_rebalancing_plan(&self) -> Result<String> {
+        let rebalancing = self.rebalancing()?;
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let balances = engine_context
+            .balance_manager
+            .lock()
+            .get_balances()
+            .balances_by_exchange_id
+            .unwrap_or_default();
+        let plan = rebalancing.create_plan(&balances);
+
+        serde_json::to_string(&plan).map_err(|err| {
+            log::warn!("Failed to serialize rebalancing plan {plan:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn approve_rebalancing_plan(&self, plan_id: String, operator_token: String) -> Result<String> {
+        let operator = self.operator(&operator_token)?;
+        let plan_id = parse_rebalancing_plan_id(&plan_id)?;
+        let plan = self
+            .rebalancing()?
+            .approve(plan_id, operator.clone())
+            .map_err(|err| {
+                with_error_code(rebalancing_error(format!("{err:?}")), err.error_code())
+            })?;
+        self.audit(
+            "approve_rebalancing_plan",
+            format!("Rebalancing plan {plan_id} is approved by {operator}"),
+        );
+
+        serde_json::to_string(&plan).map_err(|err| {
+            log::warn!("Failed to serialize rebalancing plan {plan:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn reject_rebalancing_plan(&self, plan_id: String, operator_token: String) -> Result<String> {
+        let operator = self.operator(&operator_token)?;
+        let plan_id = parse_rebalancing_plan_id(&plan_id)?;
+        self.rebalancing()?
+            .reject(plan_id, operator.clone())
+            .map_err(|err| rebalancing_error(format!("{err:?}")))?;
+        self.audit(
+            "reject_rebalancing_plan",
+            format!("Rebalancing plan {plan_id} is rejected by {operator}"),
+        );
+
+        Ok(format!("Rebalancing plan {plan_id} was rejected"))
+    }
+
+    fn hedging(&self) -> Result<String> {
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .ok_or_else(|| engine_is_not_ready_error("Engine context is dropped".to_owned()))?;
+
+        let reports = engine_context
+            .hedging
+            .as_ref()
+            .map(|x| x.reports())
+            .unwrap_or_default();
+        serde_json::to_string(&reports).map_err(|err| {
+            log::warn!("Failed to serialize hedge reports {reports:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn value_at_risk(&self) -> Result<String> {
+        let report = self.value_at_risk_service()?.report();
+        serde_json::to_string(&report).map_err(|err| {
+            log::warn!("Failed to serialize risk report {report:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn stress_test(&self, currency_code: String, change: String) -> Result<String> {
+        let change = parse_decimal(&change)
+            .map_err(|err| risk_request_error(format!("Invalid price change {change}: {err:?}")))?;
+        let shock = PriceShockSettings {
+            currency_code: currency_code.as_str().into(),
+            change,
+        };
+
+        let pnl = self
+            .value_at_risk_service()?
+            .stress_test(&[shock])
+            .map_err(|err| {
+                with_error_code(risk_request_error(err.to_string()), ErrorCode::ValueAtRisk)
+            })?;
+        Ok(pnl.to_string())
+    }
+
+    fn news_restrictions(&self) -> Result<String> {
+        let restrictions = self.news_restrictions_service()?.restrictions();
+        serde_json::to_string(&restrictions).map_err(|err| {
+            log::warn!("Failed to serialize news restrictions {restrictions:?}: {err}");
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+
+    fn news_signal(&self, signal: String) -> Result<String> {
+        let signal: ExternalSignal = serde_json::from_str(&signal)
+            .map_err(|err| risk_request_error(format!("Failed to parse signal: {err}")))?;
+        let changed = self
+            .news_restrictions_service()?
+            .handle_signal("control_api", signal.clone());
+        self.audit("news_signal", format!("{signal:?}"));
+
+        Ok(format!(
+            "Restrictions of assets [{}] are changed",
+            changed.iter().join(", ")
+        ))
+    }
 }
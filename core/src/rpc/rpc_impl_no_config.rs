@@ -1,5 +1,9 @@
-use jsonrpc_core::Result;
-use mmb_rpc::rest_api::MmbRpc;
+use futures::FutureExt;
+use jsonrpc_core::{BoxFuture, Result};
+use mmb_rpc::rest_api::{
+    engine_is_not_ready_error, market_request_error, state_transfer_error,
+    withdrawal_request_error, MmbRpc,
+};
 use mmb_utils::send_expected::SendExpectedByRef;
 use parking_lot::Mutex;
 use tokio::sync::mpsc;
@@ -9,6 +13,7 @@ use std::sync::Arc;
 use crate::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
 
 use super::common::send_stop;
+use super::common::serialize_error_codes;
 use super::common::set_config;
 
 static CONFIG_IS_NOT_SET: &str = "Config isn't set";
@@ -35,6 +40,10 @@ impl MmbRpc for RpcImplNoConfig {
         Ok(CONFIG_IS_NOT_SET.into())
     }
 
+    fn ready(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
     fn stop(&self) -> Result<String> {
         send_stop(self.server_stopper_tx.clone())
     }
@@ -43,13 +52,177 @@ impl MmbRpc for RpcImplNoConfig {
         Ok(CONFIG_IS_NOT_SET.into())
     }
 
-    fn set_config(&self, settings: String) -> Result<String> {
+    // operators aren't configured until the first config is set
+    fn set_config(&self, settings: String, _operator_token: String) -> Result<String> {
         set_config(settings)?;
         self.wait_config_tx.send_expected(());
         Ok("Config was successfully set. Trading engine will be launched".into())
     }
 
+    fn get_effective_config(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
     fn stats(&self) -> Result<String> {
         Ok(CONFIG_IS_NOT_SET.into())
     }
+
+    fn withdrawals(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn approve_withdrawal(
+        &self,
+        _withdrawal_id: String,
+        _operator_token: String,
+    ) -> Result<String> {
+        Err(withdrawal_request_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn reject_withdrawal(&self, _withdrawal_id: String, _operator_token: String) -> Result<String> {
+        Err(withdrawal_request_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn reduce_only(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn set_reduce_only(&self, _enabled: bool) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn performance_attribution(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn balance_trees(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn pause_market(&self, _exchange_account_id: String, _currency_pair: String) -> Result<String> {
+        Err(market_request_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn resume_market(
+        &self,
+        _exchange_account_id: String,
+        _currency_pair: String,
+    ) -> Result<String> {
+        Err(market_request_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn desired_amounts(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn set_desired_amount(
+        &self,
+        _exchange_account_id: String,
+        _currency_pair: String,
+        _amount: String,
+    ) -> Result<String> {
+        Err(market_request_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn market_modes(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn set_market_mode(
+        &self,
+        _exchange_account_id: String,
+        _currency_pair: String,
+        _mode: String,
+        _reduced_size: String,
+    ) -> Result<String> {
+        Err(market_request_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn export_state(&self) -> BoxFuture<Result<String>> {
+        futures::future::ready(Err(state_transfer_error(CONFIG_IS_NOT_SET.into()))).boxed()
+    }
+
+    fn import_state(&self, _state: String) -> BoxFuture<Result<String>> {
+        futures::future::ready(Err(state_transfer_error(CONFIG_IS_NOT_SET.into()))).boxed()
+    }
+
+    fn host_stats(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn connection_pool_stats(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn parsing_stats(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn request_queue_stats(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn payload_anomalies(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn error_codes(&self) -> Result<String> {
+        serialize_error_codes()
+    }
+
+    fn tax_export(&self, _format: String) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn fee_tiers(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn compact_events(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn event_compaction(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn rebalancing_plans(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn create_rebalancing_plan(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn approve_rebalancing_plan(
+        &self,
+        _plan_id: String,
+        _operator_token: String,
+    ) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn reject_rebalancing_plan(&self, _plan_id: String, _operator_token: String) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn hedging(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn value_at_risk(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn stress_test(&self, _currency_code: String, _change: String) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn news_restrictions(&self) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn news_signal(&self, _signal: String) -> Result<String> {
+        Err(engine_is_not_ready_error(CONFIG_IS_NOT_SET.into()))
+    }
 }
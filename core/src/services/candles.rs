@@ -0,0 +1,392 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+
+use anyhow::{ensure, Result};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::{Decimal, MathematicalOps};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::common::{MarketAccountId, Price};
+use crate::exchanges::events::{ExchangeEvent, TradesEvent};
+use crate::infrastructure::spawn_future;
+use crate::settings::CandlesSettings;
+
+/// OHLC candle of prices
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candle {
+    /// Start of candle period in milliseconds since unix epoch
+    pub open_time_ms: i64,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+}
+
+/// Aggregates prices to candles of fixed period and keeps the latest `max_candles` completed
+/// candles for indicators
+pub struct CandlesBuilder {
+    period_ms: i64,
+    max_candles: usize,
+    completed: VecDeque<Candle>,
+    current: Option<Candle>,
+}
+
+impl CandlesBuilder {
+    pub fn new(period_ms: i64, max_candles: usize) -> Self {
+        CandlesBuilder {
+            period_ms: period_ms.max(1),
+            max_candles,
+            completed: VecDeque::with_capacity(max_candles + 1),
+            current: None,
+        }
+    }
+
+    pub fn period_ms(&self) -> i64 {
+        self.period_ms
+    }
+
+    /// Prices have to be added in order of time. Returns candle completed by the price if any
+    pub fn add_price(&mut self, time: DateTime, price: Price) -> Option<Candle> {
+        self.add_candle(Candle {
+            open_time_ms: time.timestamp_millis(),
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+        })
+    }
+
+    /// Merges candle of lower timeframe into candle of builder period which contains its open
+    /// time. Candles have to be added in order of time, late candles of already completed
+    /// periods are skipped. Returns completed candle if any
+    pub fn add_candle(&mut self, candle: Candle) -> Option<Candle> {
+        let open_time_ms = candle.open_time_ms.div_euclid(self.period_ms) * self.period_ms;
+        match &mut self.current {
+            Some(current) if open_time_ms < current.open_time_ms => None,
+            Some(current) if current.open_time_ms == open_time_ms => {
+                current.high = current.high.max(candle.high);
+                current.low = current.low.min(candle.low);
+                current.close = candle.close;
+                None
+            }
+            current => {
+                let completed = current.replace(Candle {
+                    open_time_ms,
+                    ..candle
+                });
+                if let Some(completed) = completed {
+                    self.completed.push_back(completed);
+                    if self.completed.len() > self.max_candles {
+                        self.completed.pop_front();
+                    }
+                }
+
+                completed
+            }
+        }
+    }
+
+    /// Candle of the current period which isn't completed yet
+    pub fn current(&self) -> Option<&Candle> {
+        self.current.as_ref()
+    }
+
+    /// Completed candles ordered by time, candle which is in progress isn't included
+    pub fn completed(&self) -> &VecDeque<Candle> {
+        &self.completed
+    }
+
+    /// Indicators are calculated when `max_candles` candles are completed
+    pub fn is_ready(&self) -> bool {
+        self.completed.len() == self.max_candles
+    }
+}
+
+/// Candles of several timeframes (e.g. 5m, 1h, 1d) derived from the stream of base timeframe
+/// (e.g. 1m). Higher timeframes are aggregated from completed base candles only, so their
+/// boundaries always match boundaries of base candles and the latest base candle which is
+/// in progress isn't included into candles of higher timeframes
+pub struct MultiTimeframeCandles {
+    base_period_ms: i64,
+    timeframes: BTreeMap<i64, CandlesBuilder>,
+}
+
+impl MultiTimeframeCandles {
+    pub fn new(base_period_ms: i64, max_candles: usize) -> Self {
+        let base = CandlesBuilder::new(base_period_ms, max_candles);
+        MultiTimeframeCandles {
+            base_period_ms: base.period_ms(),
+            timeframes: BTreeMap::from([(base.period_ms(), base)]),
+        }
+    }
+
+    /// Period of timeframe has to be a multiple of base period, so that every base candle
+    /// belongs to exactly one candle of the timeframe
+    pub fn register_timeframe(&mut self, period_ms: i64, max_candles: usize) -> Result<()> {
+        ensure!(
+            period_ms > 0 && period_ms % self.base_period_ms == 0,
+            "Candle period {period_ms}ms isn't a multiple of base period {}ms",
+            self.base_period_ms
+        );
+
+        self.timeframes
+            .entry(period_ms)
+            .or_insert_with(|| CandlesBuilder::new(period_ms, max_candles));
+
+        Ok(())
+    }
+
+    /// Prices have to be added in order of time
+    pub fn add_price(&mut self, time: DateTime, price: Price) {
+        let base = self
+            .timeframes
+            .get_mut(&self.base_period_ms)
+            .expect("Base timeframe is always registered");
+        let completed = match base.add_price(time, price) {
+            Some(completed) => completed,
+            None => return,
+        };
+
+        for (_, builder) in self.timeframes.range_mut(self.base_period_ms + 1..) {
+            builder.add_candle(completed);
+        }
+    }
+
+    /// Candles of registered timeframe, None if timeframe isn't registered
+    pub fn timeframe(&self, period_ms: i64) -> Option<&CandlesBuilder> {
+        self.timeframes.get(&period_ms)
+    }
+}
+
+/// Candles of last trade prices of every market derived to configured timeframes. Bad prints
+/// are excluded from trades before they get here, so they don't affect candles
+pub struct CandlesService {
+    settings: CandlesSettings,
+    markets: Mutex<HashMap<MarketAccountId, MultiTimeframeCandles>>,
+}
+
+impl CandlesService {
+    pub fn start(
+        settings: &CandlesSettings,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<Self>> {
+        let _ = Self::create_candles(settings)?;
+
+        let service = Arc::new(CandlesService {
+            settings: settings.clone(),
+            markets: Default::default(),
+        });
+
+        let _ = spawn_future(
+            "Candles events handling",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            service
+                .clone()
+                .handle_events(events_receiver, cancellation_token),
+        );
+
+        Ok(service)
+    }
+
+    fn create_candles(settings: &CandlesSettings) -> Result<MultiTimeframeCandles> {
+        let mut candles =
+            MultiTimeframeCandles::new(settings.base_period_ms as i64, settings.max_candles);
+        for &period_ms in &settings.timeframes_ms {
+            candles.register_timeframe(period_ms as i64, settings.max_candles)?;
+        }
+
+        Ok(candles)
+    }
+
+    /// Completed candles of market ordered by time. Returns `None` if timeframe isn't
+    /// registered or there were no trades of market yet
+    pub fn completed(
+        &self,
+        market_account_id: MarketAccountId,
+        period_ms: u64,
+    ) -> Option<Vec<Candle>> {
+        let markets = self.markets.lock();
+        let builder = markets
+            .get(&market_account_id)?
+            .timeframe(period_ms as i64)?;
+
+        Some(builder.completed().iter().copied().collect())
+    }
+
+    /// Candle of market which period isn't completed yet
+    pub fn current(&self, market_account_id: MarketAccountId, period_ms: u64) -> Option<Candle> {
+        self.markets
+            .lock()
+            .get(&market_account_id)?
+            .timeframe(period_ms as i64)?
+            .current()
+            .copied()
+    }
+
+    fn add_trades(&self, trades_event: &TradesEvent) {
+        if trades_event.trades.is_empty() {
+            return;
+        }
+
+        let market_account_id =
+            MarketAccountId::new(trades_event.exchange_account_id, trades_event.currency_pair);
+        let mut markets = self.markets.lock();
+        let candles = markets.entry(market_account_id).or_insert_with(|| {
+            Self::create_candles(&self.settings).expect("Candles settings are checked on start")
+        });
+        for trade in &trades_event.trades {
+            candles.add_price(trade.transaction_time, trade.price);
+        }
+    }
+
+    async fn handle_events(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        loop {
+            let event = tokio::select! {
+                event = events_receiver.recv() => event,
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            };
+            match event {
+                Ok(ExchangeEvent::Trades(trades_event)) => self.add_trades(&trades_event),
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Candles service skipped {skipped} exchange events");
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Simple moving average of close prices
+pub fn sma(candles: &VecDeque<Candle>) -> Option<Price> {
+    if candles.is_empty() {
+        return None;
+    }
+
+    Some(candles.iter().map(|x| x.close).sum::<Decimal>() / Decimal::from(candles.len()))
+}
+
+/// Standard deviation of close prices
+pub fn std_dev(candles: &VecDeque<Candle>) -> Option<Decimal> {
+    let mean = sma(candles)?;
+    let variance = candles
+        .iter()
+        .map(|x| (x.close - mean) * (x.close - mean))
+        .sum::<Decimal>()
+        / Decimal::from(candles.len());
+
+    variance.sqrt()
+}
+
+pub fn highest_high(candles: &VecDeque<Candle>) -> Option<Price> {
+    candles.iter().map(|x| x.high).max()
+}
+
+pub fn lowest_low(candles: &VecDeque<Candle>) -> Option<Price> {
+    candles.iter().map(|x| x.low).min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const START_SECS: u64 = 1_667_260_800;
+
+    fn time(secs: u64) -> DateTime {
+        (UNIX_EPOCH + Duration::from_secs(START_SECS + secs)).into()
+    }
+
+    #[test]
+    pub fn aggregate_prices_to_candles() {
+        let mut builder = CandlesBuilder::new(60_000, 2);
+        let prices = [
+            (0, dec!(10)),
+            (20, dec!(12)),
+            (40, dec!(9)),
+            (61, dec!(11)),
+            (130, dec!(14)),
+            (185, dec!(13)),
+            // late price of completed period is skipped
+            (100, dec!(100)),
+        ];
+        for (secs, price) in prices {
+            builder.add_price(time(secs), price);
+        }
+
+        assert!(builder.is_ready());
+        let candles = builder.completed();
+        assert_eq!(
+            candles.iter().copied().collect::<Vec<_>>(),
+            vec![
+                Candle {
+                    open_time_ms: (START_SECS as i64 + 60) * 1000,
+                    open: dec!(11),
+                    high: dec!(11),
+                    low: dec!(11),
+                    close: dec!(11),
+                },
+                Candle {
+                    open_time_ms: (START_SECS as i64 + 120) * 1000,
+                    open: dec!(14),
+                    high: dec!(14),
+                    low: dec!(14),
+                    close: dec!(14),
+                },
+            ]
+        );
+
+        assert_eq!(sma(candles), Some(dec!(12.5)));
+        assert_eq!(std_dev(candles), Some(dec!(1.5)));
+        assert_eq!(highest_high(candles), Some(dec!(14)));
+        assert_eq!(lowest_low(candles), Some(dec!(11)));
+    }
+
+    #[test]
+    pub fn derive_higher_timeframes_from_base_candles() {
+        let mut candles = MultiTimeframeCandles::new(60_000, 10);
+        candles.register_timeframe(180_000, 10).expect("in test");
+        assert!(candles.register_timeframe(90_000, 10).is_err());
+        let prices = [
+            (0, dec!(10)),
+            (70, dec!(15)),
+            (130, dec!(8)),
+            (150, dec!(9)),
+            (190, dec!(12)),
+            (250, dec!(11)),
+        ];
+        for (secs, price) in prices {
+            candles.add_price(time(secs), price);
+        }
+
+        let base = candles.timeframe(60_000).expect("in test");
+        assert_eq!(base.completed().len(), 4);
+
+        // the first candle of 3m contains base candles of minutes 0-2 only
+        let higher = candles.timeframe(180_000).expect("in test");
+        let expected_open_time_ms = (START_SECS as i64).div_euclid(180) * 180_000;
+        let completed = higher.completed().iter().copied().collect::<Vec<_>>();
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].open_time_ms, expected_open_time_ms);
+        assert_eq!(
+            (completed[0].high, completed[0].low, completed[0].close),
+            (dec!(15), dec!(8), dec!(9))
+        );
+
+        // in-progress 3m candle includes completed base candle of minute 3 only
+        let current = higher.current().expect("in test");
+        assert_eq!((current.open, current.close), (dec!(12), dec!(12)));
+
+        assert!(candles.timeframe(300_000).is_none());
+    }
+}
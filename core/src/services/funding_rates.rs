@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{ExchangeAccountId, MarketAccountId};
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::settings::FundingRatesSettings;
+
+/// Funding rate of perpetual market per funding period. Positive rate means that longs pay shorts
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FundingRate {
+    /// Rate of the next funding payment
+    pub current_rate: Decimal,
+    /// Estimated rate of the funding period after the next one, if exchange provides it
+    pub predicted_rate: Option<Decimal>,
+    pub next_funding_time: Option<DateTime>,
+}
+
+/// Funding rates of configured perpetual markets polled from exchanges
+#[derive(Default)]
+pub struct FundingRatesService {
+    rates: Mutex<HashMap<MarketAccountId, FundingRate>>,
+}
+
+impl FundingRatesService {
+    pub fn start(
+        settings: &FundingRatesSettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        cancellation_token: CancellationToken,
+    ) -> Arc<Self> {
+        let service = Arc::new(FundingRatesService::default());
+
+        let markets = settings
+            .markets
+            .iter()
+            .map(|x| MarketAccountId::new(x.exchange_account_id, x.currency_pair))
+            .collect();
+        let _ = spawn_future(
+            "Funding rates polling",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            service.clone().poll_funding_rates(
+                markets,
+                exchanges,
+                Duration::from_millis(settings.poll_interval_ms.max(1)),
+                cancellation_token,
+            ),
+        );
+
+        service
+    }
+
+    /// Returns `None` if funding rate of market isn't received yet
+    pub fn get_funding_rate(&self, market_account_id: MarketAccountId) -> Option<FundingRate> {
+        self.rates.lock().get(&market_account_id).copied()
+    }
+
+    fn update(&self, market_account_id: MarketAccountId, funding_rate: FundingRate) {
+        let previous = self.rates.lock().insert(market_account_id, funding_rate);
+        if previous.map(|x| x.current_rate) != Some(funding_rate.current_rate) {
+            log::info!("Funding rate of {market_account_id:?} is updated: {funding_rate:?}");
+        }
+    }
+
+    async fn poll_funding_rates(
+        self: Arc<Self>,
+        mut markets: Vec<MarketAccountId>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        poll_interval: Duration,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let mut poll_interval = tokio::time::interval(poll_interval);
+
+        while !markets.is_empty() {
+            tokio::select! {
+                _ = poll_interval.tick() => {}
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            }
+
+            let mut unsupported = vec![];
+            for &market_account_id in &markets {
+                let exchange = match exchanges.get(&market_account_id.exchange_account_id) {
+                    Some(exchange) => exchange.clone(),
+                    None => {
+                        log::error!(
+                            "Exchange for funding rate of {market_account_id:?} isn't found"
+                        );
+                        unsupported.push(market_account_id);
+                        continue;
+                    }
+                };
+
+                match exchange
+                    .exchange_client
+                    .get_funding_rate(market_account_id.currency_pair)
+                    .await
+                {
+                    Ok(Some(funding_rate)) => self.update(market_account_id, funding_rate),
+                    Ok(None) => {
+                        log::warn!(
+                            "Exchange doesn't provide funding rate of {market_account_id:?}"
+                        );
+                        unsupported.push(market_account_id);
+                    }
+                    Err(error) => log::error!(
+                        "Failed to get funding rate of {market_account_id:?}: {error:?}"
+                    ),
+                }
+            }
+
+            markets.retain(|x| !unsupported.contains(x));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,522 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::exchanges::common::{Amount, ExchangeAccountId, MarketAccountId, Price};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::Round;
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+use crate::orders::order::{
+    ClientOrderId, OrderCreating, OrderExecutionType, OrderHeader, OrderSide, OrderType,
+};
+use crate::orders::pool::OrderRef;
+use crate::service_configuration::configuration_descriptor::{
+    ConfigurationDescriptor, ServiceConfigurationKey, ServiceName,
+};
+use crate::settings::{HedgeSettings, HedgingSettings};
+
+const HEDGER_STRATEGY_NAME: &str = "InventoryHedger";
+/// How long position received by REST may not reflect fills of the last hedge order
+const POSITION_LAG_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// PnL of hedged inventory caused by change of basis (perpetual price minus spot price).
+/// Positive hedged amount is short perpetual position against long spot inventory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct BasisPnl {
+    pub hedged_amount: Amount,
+    /// Average basis at which current hedge is opened
+    pub entry_basis: Price,
+    pub realized: Amount,
+}
+
+impl BasisPnl {
+    /// Registers change of hedged amount by `change` at `basis`
+    pub fn register_hedge_change(&mut self, change: Amount, basis: Price) {
+        if change.is_zero() {
+            return;
+        }
+
+        let is_increasing = self.hedged_amount.is_zero()
+            || self.hedged_amount.is_sign_positive() == change.is_sign_positive();
+        if is_increasing {
+            let hedged_amount = self.hedged_amount + change;
+            self.entry_basis =
+                (self.entry_basis * self.hedged_amount + basis * change) / hedged_amount;
+            self.hedged_amount = hedged_amount;
+            return;
+        }
+
+        let closed = match change.abs() < self.hedged_amount.abs() {
+            true => -change,
+            false => self.hedged_amount,
+        };
+        self.realized += closed * (self.entry_basis - basis);
+        self.hedged_amount -= closed;
+
+        let reversed = change + closed;
+        if self.hedged_amount.is_zero() {
+            self.entry_basis = dec!(0);
+            self.register_hedge_change(reversed, basis);
+        }
+    }
+
+    pub fn unrealized(&self, basis: Price) -> Amount {
+        self.hedged_amount * (self.entry_basis - basis)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HedgeReport {
+    pub spot_market: MarketAccountId,
+    pub perpetual_market: MarketAccountId,
+    pub spot_inventory: Amount,
+    /// Signed perpetual position, negative is short
+    pub perpetual_position: Amount,
+    pub target_position: Amount,
+    pub basis: Option<Price>,
+    pub basis_pnl: BasisPnl,
+    pub unrealized_basis_pnl: Option<Amount>,
+    pub last_check_time: Option<DateTime>,
+    pub last_error: Option<String>,
+}
+
+/// The last hedge order which isn't reflected in perpetual position received by REST yet
+struct PendingHedgeOrder {
+    order: OrderRef,
+    spot_price: Price,
+    position_before: Amount,
+    position_change: Amount,
+    settled_at: Option<Instant>,
+}
+
+struct Hedge {
+    settings: HedgeSettings,
+    spot_market: MarketAccountId,
+    perpetual_market: MarketAccountId,
+    report: Mutex<HedgeReport>,
+    is_initialized: Mutex<bool>,
+    pending_order: Mutex<Option<PendingHedgeOrder>>,
+}
+
+/// Perpetual position which offsets spot inventory by hedge ratio
+pub fn target_perpetual_position(spot_inventory: Amount, hedge_ratio: Decimal) -> Amount {
+    -spot_inventory * hedge_ratio
+}
+
+/// Offsets spot inventory with perpetual futures positions on the same or another exchange.
+/// Perpetual position is rebalanced by market orders when it deviates from target more than
+/// threshold. PnL caused by basis changes is tracked separately from PnL of trading
+pub struct InventoryHedger {
+    hedges: Vec<Hedge>,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    check_interval: Duration,
+    cancellation_token: CancellationToken,
+}
+
+impl InventoryHedger {
+    pub fn new(
+        settings: &HedgingSettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        cancellation_token: CancellationToken,
+    ) -> Arc<Self> {
+        let hedges = settings
+            .hedges
+            .iter()
+            .map(|x| {
+                let spot_market =
+                    MarketAccountId::new(x.spot_exchange_account_id, x.spot_currency_pair);
+                let perpetual_market = MarketAccountId::new(
+                    x.perpetual_exchange_account_id,
+                    x.perpetual_currency_pair,
+                );
+                Hedge {
+                    settings: x.clone(),
+                    spot_market,
+                    perpetual_market,
+                    report: Mutex::new(HedgeReport {
+                        spot_market,
+                        perpetual_market,
+                        spot_inventory: dec!(0),
+                        perpetual_position: dec!(0),
+                        target_position: dec!(0),
+                        basis: None,
+                        basis_pnl: BasisPnl::default(),
+                        unrealized_basis_pnl: None,
+                        last_check_time: None,
+                        last_error: None,
+                    }),
+                    is_initialized: Mutex::new(false),
+                    pending_order: Mutex::new(None),
+                }
+            })
+            .collect();
+
+        Arc::new(InventoryHedger {
+            hedges,
+            exchanges,
+            check_interval: Duration::from_millis(settings.check_interval_ms.max(1)),
+            cancellation_token,
+        })
+    }
+
+    /// Starts periodical hedging. Should be called after exchanges are connected
+    pub fn start(self: Arc<Self>) {
+        let _ = spawn_future(
+            "Inventory hedging",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            self.clone().hedge_periodically(self.check_interval),
+        );
+    }
+
+    pub fn reports(&self) -> Vec<HedgeReport> {
+        self.hedges
+            .iter()
+            .map(|x| x.report.lock().clone())
+            .collect()
+    }
+
+    async fn hedge_periodically(self: Arc<Self>, check_interval: Duration) -> Result<()> {
+        let mut interval = tokio::time::interval(check_interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = self.cancellation_token.when_cancelled() => return Ok(()),
+            }
+
+            for hedge in &self.hedges {
+                let result = self.check_hedge(hedge).await;
+                let mut report = hedge.report.lock();
+                report.last_check_time = Some(time_manager::now());
+                report.last_error = result.err().map(|err| {
+                    log::error!(
+                        "Failed to hedge {:?} by {:?}: {err:?}",
+                        hedge.spot_market,
+                        hedge.perpetual_market
+                    );
+                    format!("{err:?}")
+                });
+            }
+        }
+    }
+
+    async fn check_hedge(&self, hedge: &Hedge) -> Result<()> {
+        let spot_exchange = self.get_exchange(hedge.spot_market.exchange_account_id)?;
+        let perpetual_exchange = self.get_exchange(hedge.perpetual_market.exchange_account_id)?;
+
+        let spot_mid = mid_price(&spot_exchange, hedge.spot_market)
+            .context("Spot order book isn't received yet")?;
+        let perpetual_mid = mid_price(&perpetual_exchange, hedge.perpetual_market)
+            .context("Perpetual order book isn't received yet")?;
+        let basis = perpetual_mid - spot_mid;
+
+        let spot_inventory = self.get_spot_inventory(hedge, &spot_exchange).await?;
+        let perpetual_position = self
+            .get_perpetual_position(hedge, &perpetual_exchange)
+            .await
+            .context("Perpetual position is unknown, hedging is skipped")?;
+        if !self.is_pending_order_settled(hedge, &perpetual_exchange, perpetual_position) {
+            return Ok(());
+        }
+
+        let target_position = target_perpetual_position(spot_inventory, hedge.settings.hedge_ratio);
+
+        {
+            let mut is_initialized = hedge.is_initialized.lock();
+            if !*is_initialized {
+                // position opened before start is tracked from the current basis
+                hedge
+                    .report
+                    .lock()
+                    .basis_pnl
+                    .register_hedge_change(-perpetual_position, basis);
+                *is_initialized = true;
+            }
+        }
+
+        {
+            let mut report = hedge.report.lock();
+            report.spot_inventory = spot_inventory;
+            report.perpetual_position = perpetual_position;
+            report.target_position = target_position;
+            report.basis = Some(basis);
+            report.unrealized_basis_pnl = Some(report.basis_pnl.unrealized(basis));
+        }
+
+        let symbol = perpetual_exchange.get_symbol(hedge.perpetual_market.currency_pair)?;
+        let deviation = target_position - perpetual_position;
+        let amount = symbol.amount_round(deviation.abs(), Round::Floor);
+        if deviation.abs() < hedge.settings.rebalance_threshold || amount <= dec!(0) {
+            return Ok(());
+        }
+
+        let side = match deviation > dec!(0) {
+            true => OrderSide::Buy,
+            false => OrderSide::Sell,
+        };
+        log::info!(
+            "Perpetual position {perpetual_position} of {:?} deviates from target {target_position}, {side:?} {amount}",
+            hedge.perpetual_market
+        );
+
+        self.execute_market_order(
+            &perpetual_exchange,
+            hedge,
+            side,
+            amount,
+            perpetual_mid,
+            spot_mid,
+            perpetual_position,
+        )
+        .await
+    }
+
+    /// Returns `true` if there is no hedge order in flight and perpetual position received by
+    /// REST already reflects fills of the last one, so the next hedge order can be calculated
+    fn is_pending_order_settled(
+        &self,
+        hedge: &Hedge,
+        exchange: &Exchange,
+        perpetual_position: Amount,
+    ) -> bool {
+        let mut pending_order = hedge.pending_order.lock();
+        let pending = match pending_order.as_mut() {
+            Some(pending) => pending,
+            None => return true,
+        };
+
+        if !pending.order.is_finished() {
+            log::info!(
+                "Hedge order {} of {:?} isn't finished yet",
+                pending.order.client_order_id(),
+                hedge.perpetual_market
+            );
+            return false;
+        }
+
+        let settled_at = match pending.settled_at {
+            Some(settled_at) => settled_at,
+            None => settle_hedge_order(hedge, exchange, pending),
+        };
+
+        let is_position_updated = pending.position_change.is_zero()
+            || perpetual_position != pending.position_before
+            || settled_at.elapsed() >= POSITION_LAG_TIMEOUT;
+        if is_position_updated {
+            *pending_order = None;
+        }
+
+        is_position_updated
+    }
+
+    async fn get_spot_inventory(&self, hedge: &Hedge, exchange: &Exchange) -> Result<Amount> {
+        let base_currency_code = exchange
+            .get_symbol(hedge.spot_market.currency_pair)?
+            .base_currency_code();
+        let balances = exchange
+            .get_balance(self.cancellation_token.clone())
+            .await
+            .context("Unable to get spot balances")?;
+
+        Ok(balances
+            .balances
+            .iter()
+            .find(|x| x.currency_code == base_currency_code)
+            .map(|x| x.balance)
+            .unwrap_or(dec!(0)))
+    }
+
+    async fn get_perpetual_position(&self, hedge: &Hedge, exchange: &Exchange) -> Result<Amount> {
+        Ok(exchange
+            .get_active_positions(self.cancellation_token.clone())
+            .await?
+            .iter()
+            .map(|x| &x.derivative)
+            .filter(|x| x.currency_pair == hedge.perpetual_market.currency_pair)
+            .map(|x| match x.side {
+                Some(OrderSide::Sell) => -x.position.abs(),
+                _ => x.position.abs(),
+            })
+            .sum())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_market_order(
+        &self,
+        exchange: &Arc<Exchange>,
+        hedge: &Hedge,
+        side: OrderSide,
+        amount: Amount,
+        price: Price,
+        spot_price: Price,
+        position_before: Amount,
+    ) -> Result<()> {
+        let configuration_descriptor = ConfigurationDescriptor::new(
+            ServiceName::new(HEDGER_STRATEGY_NAME),
+            ServiceConfigurationKey::new(
+                format!(
+                    "{};{}",
+                    hedge.perpetual_market.exchange_account_id,
+                    hedge.perpetual_market.currency_pair
+                )
+                .as_str(),
+            ),
+        );
+        let reservation_id = exchange.reserve_order_balance(
+            configuration_descriptor,
+            hedge.perpetual_market.currency_pair,
+            side,
+            price,
+            amount,
+        )?;
+
+        let header = OrderHeader::new(
+            ClientOrderId::unique_id(),
+            time_manager::now(),
+            hedge.perpetual_market.exchange_account_id,
+            hedge.perpetual_market.currency_pair,
+            OrderType::Market,
+            side,
+            amount,
+            OrderExecutionType::None,
+            Some(reservation_id),
+            None,
+            HEDGER_STRATEGY_NAME.to_owned(),
+        );
+
+        let order = match exchange
+            .create_order(
+                OrderCreating {
+                    header: header.clone(),
+                    price,
+                },
+                None,
+                self.cancellation_token.clone(),
+            )
+            .await
+        {
+            Ok(order) => order,
+            Err(err) => {
+                exchange.release_order_reservation(&header);
+                return Err(err);
+            }
+        };
+
+        // order is tracked until its fills are reflected in position received by REST, so
+        // the next checks don't send one more order for the same deviation
+        *hedge.pending_order.lock() = Some(PendingHedgeOrder {
+            order: order.clone(),
+            spot_price,
+            position_before,
+            position_change: dec!(0),
+            settled_at: None,
+        });
+
+        let _ = exchange
+            .clone()
+            .wait_order_finish(&order, None, self.cancellation_token.clone())
+            .await?;
+
+        if let Some(pending) = hedge.pending_order.lock().as_mut() {
+            if pending.settled_at.is_none() {
+                let _ = settle_hedge_order(hedge, exchange, pending);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_exchange(&self, exchange_account_id: ExchangeAccountId) -> Result<Arc<Exchange>> {
+        self.exchanges
+            .get(&exchange_account_id)
+            .map(|x| x.clone())
+            .with_context(|| format!("Exchange {exchange_account_id} isn't found"))
+    }
+}
+
+/// Releases reservation of finished hedge order and registers its fills in basis PnL
+fn settle_hedge_order(
+    hedge: &Hedge,
+    exchange: &Exchange,
+    pending: &mut PendingHedgeOrder,
+) -> Instant {
+    let (header, filled_amount, average_price) = pending.order.fn_ref(|x| {
+        let filled_amount = x.fills.filled_amount;
+        let cost: Amount = x.fills.fills.iter().map(|f| f.price() * f.amount()).sum();
+        let average_price = match filled_amount > dec!(0) {
+            true => cost / filled_amount,
+            false => dec!(0),
+        };
+        (x.header.clone(), filled_amount, average_price)
+    });
+
+    exchange.release_order_reservation(&header);
+
+    if filled_amount > dec!(0) {
+        let position_change = match header.side {
+            OrderSide::Buy => filled_amount,
+            OrderSide::Sell => -filled_amount,
+        };
+        let mut report = hedge.report.lock();
+        report
+            .basis_pnl
+            .register_hedge_change(-position_change, average_price - pending.spot_price);
+        report.perpetual_position += position_change;
+        pending.position_change = position_change;
+    }
+
+    let settled_at = Instant::now();
+    pending.settled_at = Some(settled_at);
+    settled_at
+}
+
+fn mid_price(exchange: &Exchange, market_account_id: MarketAccountId) -> Option<Price> {
+    let top = exchange
+        .order_book_top
+        .get(&market_account_id.currency_pair)?;
+    let ask = top.ask.as_ref()?.price;
+    let bid = top.bid.as_ref()?.price;
+    Some((ask + bid) / dec!(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_position_offsets_inventory_by_ratio() {
+        assert_eq!(target_perpetual_position(dec!(2), dec!(0.5)), dec!(-1));
+        assert_eq!(target_perpetual_position(dec!(-2), dec!(1)), dec!(2));
+    }
+
+    #[test]
+    fn basis_pnl_of_hedge_changes() {
+        let mut pnl = BasisPnl::default();
+        pnl.register_hedge_change(dec!(1), dec!(10));
+        pnl.register_hedge_change(dec!(1), dec!(20));
+        assert_eq!(pnl.hedged_amount, dec!(2));
+        assert_eq!(pnl.entry_basis, dec!(15));
+        // basis converged, so short perpetual gained against spot
+        assert_eq!(pnl.unrealized(dec!(5)), dec!(20));
+
+        pnl.register_hedge_change(dec!(-1), dec!(5));
+        assert_eq!(pnl.realized, dec!(10));
+        assert_eq!(pnl.hedged_amount, dec!(1));
+
+        // hedge is reversed to long perpetual position
+        pnl.register_hedge_change(dec!(-2), dec!(0));
+        assert_eq!(pnl.realized, dec!(25));
+        assert_eq!(pnl.hedged_amount, dec!(-1));
+        assert_eq!(pnl.entry_basis, dec!(0));
+        assert_eq!(pnl.unrealized(dec!(3)), dec!(3));
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Duration;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::common::{CurrencyPair, MarketId, Price};
+use crate::exchanges::events::ExchangeEvent;
+use crate::infrastructure::spawn_future;
+use crate::math::ConvertPercentToRate;
+use crate::misc::time::time_manager;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::settings::{IndexPriceSettings, IndexSettings};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct ComponentPrice {
+    middle_price: Price,
+    time: DateTime,
+}
+
+/// Reference price of currency pair constructed from middle prices on several exchanges
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct IndexPrice {
+    pub price: Price,
+    /// Count of components which price is constructed from after excluding stale prices and outliers
+    pub components_count: usize,
+    /// Time of the oldest component price which index is constructed from
+    pub time: DateTime,
+}
+
+/// Weighted median of `(price, weight)` items. If cumulative weight is exactly half of total weight
+/// at some price, the average of this price and the next one is returned
+pub fn weighted_median(items: &[(Price, Decimal)]) -> Option<Price> {
+    let mut items = items
+        .iter()
+        .filter(|(_, weight)| *weight > dec!(0))
+        .copied()
+        .collect::<Vec<_>>();
+    items.sort_by_key(|(price, _)| *price);
+
+    let half_weight = items.iter().map(|(_, weight)| weight).sum::<Decimal>() / dec!(2);
+    let mut cumulative_weight = dec!(0);
+    for (index, &(price, weight)) in items.iter().enumerate() {
+        cumulative_weight += weight;
+        if cumulative_weight == half_weight {
+            return items
+                .get(index + 1)
+                .map(|(next, _)| (price + next) / dec!(2));
+        }
+        if cumulative_weight > half_weight {
+            return Some(price);
+        }
+    }
+
+    None
+}
+
+/// Weighted median of component prices after excluding prices deviating from median of all
+/// components more than `max_deviation_rate`
+pub fn calculate_index_price(
+    components: &[(Price, Decimal)],
+    max_deviation_rate: Decimal,
+) -> Option<(Price, usize)> {
+    let median = weighted_median(components)?;
+    let trimmed = components
+        .iter()
+        .filter(|(price, _)| ((price - median) / median).abs() <= max_deviation_rate)
+        .copied()
+        .collect::<Vec<_>>();
+
+    Some((weighted_median(&trimmed)?, trimmed.len()))
+}
+
+/// Calculates index prices by middle prices of configured markets on several exchanges.
+/// Index prices are reference for price collars, mark-to-market and circuit breakers
+pub struct IndexPriceService {
+    settings: IndexPriceSettings,
+    component_prices: Mutex<HashMap<MarketId, ComponentPrice>>,
+}
+
+impl IndexPriceService {
+    pub fn start(
+        settings: IndexPriceSettings,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Arc<Self> {
+        let service = Arc::new(IndexPriceService {
+            settings,
+            component_prices: Default::default(),
+        });
+
+        let _ = spawn_future(
+            "Index price events handling",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            service
+                .clone()
+                .handle_events(events_receiver, cancellation_token),
+        );
+
+        service
+    }
+
+    /// Returns `None` if index isn't configured for currency pair or there are not enough
+    /// fresh component prices
+    pub fn get_index_price(&self, currency_pair: CurrencyPair) -> Option<IndexPrice> {
+        let index = self
+            .settings
+            .indices
+            .iter()
+            .find(|x| x.currency_pair == currency_pair)?;
+
+        self.calculate(index, time_manager::now())
+    }
+
+    fn calculate(&self, index: &IndexSettings, now: DateTime) -> Option<IndexPrice> {
+        let min_time = now - Duration::milliseconds(self.settings.max_price_age_ms as i64);
+        let component_prices = self.component_prices.lock();
+
+        let fresh_components = index
+            .components
+            .iter()
+            .filter_map(|component| {
+                let market_id = MarketId::new(component.exchange_id, component.currency_pair);
+                let component_price = component_prices.get(&market_id)?;
+                (component_price.time >= min_time).then_some((*component_price, component.weight))
+            })
+            .collect::<Vec<_>>();
+
+        let prices = fresh_components
+            .iter()
+            .map(|(component_price, weight)| (component_price.middle_price, *weight))
+            .collect::<Vec<_>>();
+        let (price, components_count) =
+            calculate_index_price(&prices, self.settings.max_deviation.percent_to_rate())?;
+        if components_count < index.min_components {
+            log::warn!(
+                "Index price for {} isn't available: {components_count} components are used, required {}",
+                index.currency_pair,
+                index.min_components
+            );
+            return None;
+        }
+
+        Some(IndexPrice {
+            price,
+            components_count,
+            time: fresh_components.iter().map(|(x, _)| x.time).min()?,
+        })
+    }
+
+    fn is_component(&self, market_id: MarketId) -> bool {
+        self.settings.indices.iter().any(|index| {
+            index.components.iter().any(|x| {
+                x.exchange_id == market_id.exchange_id && x.currency_pair == market_id.currency_pair
+            })
+        })
+    }
+
+    async fn handle_events(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let mut snapshots = LocalSnapshotsService::default();
+
+        loop {
+            let event = tokio::select! {
+                event = events_receiver.recv() => event,
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            };
+            let order_book_event = match event {
+                Ok(ExchangeEvent::OrderBookEvent(order_book_event)) => order_book_event,
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Index price service skipped {skipped} exchange events");
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            let market_id = MarketId::new(
+                order_book_event.exchange_account_id.exchange_id,
+                order_book_event.currency_pair,
+            );
+            if !self.is_component(market_id) {
+                continue;
+            }
+
+            let time = order_book_event.creation_time;
+            if snapshots.update(order_book_event).is_none() {
+                continue;
+            }
+
+            let middle_price = snapshots
+                .get_snapshot(market_id)
+                .and_then(|x| x.calculate_middle_price(market_id));
+            let mut component_prices = self.component_prices.lock();
+            match middle_price {
+                Some(middle_price) => {
+                    let _ =
+                        component_prices.insert(market_id, ComponentPrice { middle_price, time });
+                }
+                None => {
+                    let _ = component_prices.remove(&market_id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::IndexComponentSettings;
+    use chrono::Utc;
+    use rstest::rstest;
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    fn service() -> IndexPriceService {
+        let components = ["Binance", "Bitmex", "Serum"]
+            .into_iter()
+            .map(|exchange_id| IndexComponentSettings {
+                exchange_id: exchange_id.into(),
+                currency_pair: currency_pair(),
+                weight: dec!(1),
+            })
+            .collect();
+
+        IndexPriceService {
+            settings: IndexPriceSettings {
+                indices: vec![IndexSettings {
+                    currency_pair: currency_pair(),
+                    components,
+                    min_components: 2,
+                }],
+                max_price_age_ms: 5_000,
+                max_deviation: dec!(5),
+            },
+            component_prices: Default::default(),
+        }
+    }
+
+    fn set_price(service: &IndexPriceService, exchange_id: &str, price: Price, time: DateTime) {
+        let _ = service.component_prices.lock().insert(
+            MarketId::new(exchange_id.into(), currency_pair()),
+            ComponentPrice {
+                middle_price: price,
+                time,
+            },
+        );
+    }
+
+    #[rstest]
+    #[case(vec![(dec!(1), dec!(1)), (dec!(3), dec!(1)), (dec!(2), dec!(1))], Some(dec!(2)))]
+    #[case(vec![(dec!(1), dec!(1)), (dec!(3), dec!(1))], Some(dec!(2)))]
+    #[case(vec![(dec!(1), dec!(1)), (dec!(2), dec!(1)), (dec!(3), dec!(5))], Some(dec!(3)))]
+    #[case(vec![(dec!(1), dec!(0))], None)]
+    #[case(vec![], None)]
+    pub fn weighted_median_of_prices(
+        #[case] items: Vec<(Price, Decimal)>,
+        #[case] expected: Option<Price>,
+    ) {
+        assert_eq!(weighted_median(&items), expected);
+    }
+
+    #[test]
+    pub fn outliers_are_trimmed() {
+        let components = [
+            (dec!(100), dec!(1)),
+            (dec!(101), dec!(1)),
+            (dec!(102), dec!(1)),
+            (dec!(150), dec!(1)),
+        ];
+
+        assert_eq!(
+            calculate_index_price(&components, dec!(0.05)),
+            Some((dec!(101), 3))
+        );
+    }
+
+    #[test]
+    pub fn stale_prices_are_excluded() {
+        let service = service();
+        let now = Utc::now();
+        set_price(&service, "Binance", dec!(100), now);
+        set_price(&service, "Bitmex", dec!(102), now - Duration::seconds(1));
+        set_price(&service, "Serum", dec!(90), now - Duration::seconds(10));
+
+        let index = &service.settings.indices[0];
+        assert_eq!(
+            service.calculate(index, now),
+            Some(IndexPrice {
+                price: dec!(101),
+                components_count: 2,
+                time: now - Duration::seconds(1),
+            })
+        );
+
+        set_price(&service, "Bitmex", dec!(102), now - Duration::seconds(10));
+        assert_eq!(service.calculate(index, now), None);
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+
+use crate::exchanges::common::ExchangeAccountId;
+use crate::exchanges::general::connection_uptime::ConnectionUptimeSnapshot;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::parsing_workers::ParsingStats;
+use crate::exchanges::general::venue_metrics::VenueMetricsSnapshot;
+use crate::infrastructure::spawn_future;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExchangeMetrics {
+    pub venue: VenueMetricsSnapshot,
+    pub parsing: ParsingStats,
+    pub connection_uptime: ConnectionUptimeSnapshot,
+}
+
+/// Custom destination of engine metrics, e.g. metrics system of application which embeds engine
+pub trait MetricsSink: Send + Sync + 'static {
+    fn record_exchange_metrics(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        metrics: &ExchangeMetrics,
+    );
+}
+
+/// Pushes metrics of exchange accounts to `sink` every `interval`
+pub fn start_metrics_reporting(
+    sink: Arc<dyn MetricsSink>,
+    interval: Duration,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+) {
+    let _ = spawn_future(
+        "Metrics reporting",
+        SpawnFutureFlags::STOP_BY_TOKEN,
+        report_metrics(sink, interval, exchanges),
+    );
+}
+
+async fn report_metrics(
+    sink: Arc<dyn MetricsSink>,
+    interval: Duration,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+) -> Result<()> {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        let _ = interval.tick().await;
+        for exchange in exchanges.iter() {
+            let metrics = ExchangeMetrics {
+                venue: exchange.get_venue_metrics(),
+                parsing: exchange.get_parsing_stats(),
+                connection_uptime: exchange.get_connection_uptime(),
+            };
+            sink.record_exchange_metrics(*exchange.key(), &metrics);
+        }
+    }
+}
@@ -1,2 +1,13 @@
+pub mod candles;
+pub mod funding_rates;
+pub mod hedging;
+pub mod index_price;
 pub(crate) mod market_prices;
+pub mod metrics_sink;
+pub mod news_restrictions;
+pub mod performance_attribution;
+pub mod spread_execution;
+pub mod triangular_arbitrage;
 pub mod usd_convertion;
+pub mod value_at_risk;
+pub mod volatility;
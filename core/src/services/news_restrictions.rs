@@ -0,0 +1,629 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use dashmap::DashMap;
+use futures::StreamExt;
+use hyper::Uri;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::time::now;
+use mmb_utils::DateTime;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::exchanges::common::{CurrencyCode, ExchangeAccountId, MarketAccountId};
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::Symbol;
+use crate::exchanges::rest_client::create_client;
+use crate::infrastructure::spawn_future;
+use crate::settings::{NewsFeedSettings, NewsRestrictionAction, NewsRestrictionsSettings};
+
+const EXPIRATION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const FEED_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Signal of external news or compliance system
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExternalSignal {
+    /// Headline restricts assets it mentions if it contains any of configured keywords
+    Headline {
+        text: String,
+        published_at: Option<DateTime>,
+    },
+    /// Assets are restricted regardless of keywords, e.g. by compliance system
+    Restrict {
+        currency_codes: Vec<CurrencyCode>,
+        reason: String,
+    },
+    Lift {
+        currency_codes: Vec<CurrencyCode>,
+    },
+}
+
+/// Pluggable source of external signals, e.g. adapter of news provider API. Built-in sources are
+/// RSS and websocket feeds from settings, custom ones are added by `EngineBuilder::signal_source`
+#[async_trait]
+pub trait SignalSource: Send + Sync + 'static {
+    fn name(&self) -> String;
+
+    /// Receives signals and passes them to `news_restrictions.handle_signal` until cancellation
+    async fn run(
+        &self,
+        news_restrictions: Arc<NewsRestrictionsService>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NewsRestriction {
+    pub currency_code: CurrencyCode,
+    /// Name of signal source which restricted asset
+    pub source: String,
+    pub reason: String,
+    pub restricted_at: DateTime,
+    pub expires_at: DateTime,
+}
+
+/// Assets restricted by external signals. Depending on settings markets of restricted assets are
+/// quoted wider by disposition executors or paused on all exchanges until restriction is lifted
+pub struct NewsRestrictionsService {
+    settings: NewsRestrictionsSettings,
+    keywords: Vec<String>,
+    restrictions: Mutex<HashMap<CurrencyCode, NewsRestriction>>,
+    /// Markets paused by restrictions. Markets paused manually aren't resumed on lifting of restrictions
+    paused_markets: Mutex<HashSet<MarketAccountId>>,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    cancellation_token: CancellationToken,
+}
+
+impl NewsRestrictionsService {
+    pub fn start(
+        settings: &NewsRestrictionsSettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        cancellation_token: CancellationToken,
+    ) -> Arc<Self> {
+        let service = Arc::new(NewsRestrictionsService::new(
+            settings,
+            exchanges,
+            cancellation_token.clone(),
+        ));
+
+        let _ = spawn_future(
+            "News restrictions expiration",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            service.clone().expire_restrictions(cancellation_token),
+        );
+
+        for feed in &settings.feeds {
+            let source: Arc<dyn SignalSource> = match feed {
+                NewsFeedSettings::Rss {
+                    url,
+                    poll_interval_ms,
+                } => Arc::new(RssFeed {
+                    url: url.clone(),
+                    poll_interval: Duration::from_millis((*poll_interval_ms).max(1)),
+                }),
+                NewsFeedSettings::Websocket { url } => Arc::new(WebsocketFeed { url: url.clone() }),
+            };
+            service.start_source(source);
+        }
+
+        service
+    }
+
+    fn new(
+        settings: &NewsRestrictionsSettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        NewsRestrictionsService {
+            settings: settings.clone(),
+            keywords: settings.keywords.iter().map(|x| x.to_lowercase()).collect(),
+            restrictions: Default::default(),
+            paused_markets: Default::default(),
+            exchanges,
+            cancellation_token,
+        }
+    }
+
+    /// Runs signal source until engine is stopped
+    pub fn start_source(self: &Arc<Self>, source: Arc<dyn SignalSource>) {
+        log::info!("Signal source {} is started", source.name());
+        let service = self.clone();
+        let cancellation_token = self.cancellation_token.clone();
+        let action = async move { source.run(service, cancellation_token).await };
+        let _ = spawn_future(
+            "News signal source",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action,
+        );
+    }
+
+    /// Applies signal and returns assets which restrictions are changed by it
+    pub fn handle_signal(&self, source: &str, signal: ExternalSignal) -> Vec<CurrencyCode> {
+        let now = now();
+        match signal {
+            ExternalSignal::Headline { text, published_at } => {
+                let expiration = self.restriction_duration();
+                if published_at.is_some_and(|x| x + expiration < now) {
+                    log::trace!("Headline from {source} is outdated: {text}");
+                    return vec![];
+                }
+
+                let currency_codes = self.matched_assets(&text);
+                for &currency_code in &currency_codes {
+                    self.restrict(currency_code, source, &text, now);
+                }
+                currency_codes
+            }
+            ExternalSignal::Restrict {
+                currency_codes,
+                reason,
+            } => {
+                for &currency_code in &currency_codes {
+                    self.restrict(currency_code, source, &reason, now);
+                }
+                currency_codes
+            }
+            ExternalSignal::Lift { currency_codes } => currency_codes
+                .into_iter()
+                .filter(|&currency_code| self.lift(currency_code))
+                .collect(),
+        }
+    }
+
+    pub fn restrictions(&self) -> Vec<NewsRestriction> {
+        self.restrictions
+            .lock()
+            .values()
+            .cloned()
+            .sorted_by_key(|x| x.restricted_at)
+            .collect()
+    }
+
+    pub fn is_restricted(&self, currency_code: CurrencyCode) -> bool {
+        self.restrictions.lock().contains_key(&currency_code)
+    }
+
+    /// Price collar and cause of widening of quotes if market of symbol has restricted asset
+    pub fn quote_widening(&self, symbol: &Symbol) -> Option<(Percent, String)> {
+        if self.settings.action != NewsRestrictionAction::WidenQuotes {
+            return None;
+        }
+
+        let restrictions = self.restrictions.lock();
+        let restriction = restrictions
+            .get(&symbol.base_currency_code())
+            .or_else(|| restrictions.get(&symbol.quote_currency_code()))?;
+
+        Some((
+            self.settings.price_collar,
+            format!(
+                "news restriction of {} by {}: {}",
+                restriction.currency_code, restriction.source, restriction.reason
+            ),
+        ))
+    }
+
+    /// Restricted assets mentioned by headline which contains any of keywords. Assets are
+    /// matched by whole words, so `eth` isn't found in `method`
+    fn matched_assets(&self, text: &str) -> Vec<CurrencyCode> {
+        let text = text.to_lowercase();
+        if !self.keywords.iter().any(|x| text.contains(x.as_str())) {
+            return vec![];
+        }
+
+        let words: HashSet<&str> = text
+            .split(|x: char| !x.is_alphanumeric())
+            .filter(|x| !x.is_empty())
+            .collect();
+
+        self.settings
+            .assets
+            .iter()
+            .filter(|asset| {
+                words.contains(asset.currency_code.as_str())
+                    || asset
+                        .aliases
+                        .iter()
+                        .any(|alias| words.contains(alias.to_lowercase().as_str()))
+            })
+            .map(|x| x.currency_code)
+            .collect()
+    }
+
+    fn restriction_duration(&self) -> chrono::Duration {
+        chrono::Duration::milliseconds(self.settings.restriction_duration_ms as i64)
+    }
+
+    fn restrict(&self, currency_code: CurrencyCode, source: &str, reason: &str, now: DateTime) {
+        let restriction = NewsRestriction {
+            currency_code,
+            source: source.to_owned(),
+            reason: reason.to_owned(),
+            restricted_at: now,
+            expires_at: now + self.restriction_duration(),
+        };
+        let previous = self.restrictions.lock().insert(currency_code, restriction);
+        if previous.is_some() {
+            log::info!("News restriction of {currency_code} is prolonged by {source}: {reason}");
+            return;
+        }
+
+        log::warn!("{currency_code} is restricted by {source}: {reason}");
+        if self.settings.action == NewsRestrictionAction::PauseMarkets {
+            self.pause_markets(currency_code);
+        }
+    }
+
+    /// Returns `false` if asset isn't restricted
+    fn lift(&self, currency_code: CurrencyCode) -> bool {
+        if self.restrictions.lock().remove(&currency_code).is_none() {
+            return false;
+        }
+
+        log::warn!("News restriction of {currency_code} is lifted");
+        self.resume_markets();
+        true
+    }
+
+    fn pause_markets(&self, currency_code: CurrencyCode) {
+        for exchange in self.exchanges.iter() {
+            for symbol in exchange.symbols.iter() {
+                if symbol.base_currency_code() != currency_code
+                    && symbol.quote_currency_code() != currency_code
+                {
+                    continue;
+                }
+
+                let currency_pair = symbol.currency_pair();
+                if exchange.pause_market(currency_pair, self.cancellation_token.clone()) {
+                    let _ = self
+                        .paused_markets
+                        .lock()
+                        .insert(MarketAccountId::new(*exchange.key(), currency_pair));
+                }
+            }
+        }
+    }
+
+    /// Resumes markets paused by restrictions which don't have restricted assets anymore
+    fn resume_markets(&self) {
+        let mut paused_markets = self.paused_markets.lock();
+        paused_markets.retain(|market_account_id| {
+            let exchange = match self.exchanges.get(&market_account_id.exchange_account_id) {
+                Some(exchange) => exchange.clone(),
+                None => return false,
+            };
+            let symbol = match exchange.symbols.get(&market_account_id.currency_pair) {
+                Some(symbol) => symbol.clone(),
+                None => return false,
+            };
+
+            if self.is_restricted(symbol.base_currency_code())
+                || self.is_restricted(symbol.quote_currency_code())
+            {
+                return true;
+            }
+
+            let _ = exchange.resume_market(market_account_id.currency_pair);
+            false
+        });
+    }
+
+    async fn expire_restrictions(
+        self: Arc<Self>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let mut interval = tokio::time::interval(EXPIRATION_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            }
+
+            let now = now();
+            let expired = self
+                .restrictions
+                .lock()
+                .values()
+                .filter(|x| x.expires_at <= now)
+                .map(|x| x.currency_code)
+                .collect_vec();
+            for currency_code in expired {
+                log::info!("News restriction of {currency_code} is expired");
+                let _ = self.lift(currency_code);
+            }
+        }
+    }
+}
+
+/// Titles of RSS items are handled as headlines. Items seen on previous poll are skipped
+struct RssFeed {
+    url: String,
+    poll_interval: Duration,
+}
+
+#[async_trait]
+impl SignalSource for RssFeed {
+    fn name(&self) -> String {
+        format!("RSS feed {}", self.url)
+    }
+
+    async fn run(
+        &self,
+        news_restrictions: Arc<NewsRestrictionsService>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let uri: Uri = self.url.parse().context("Invalid url of RSS feed")?;
+        let name = self.name();
+        let mut seen_titles = HashSet::new();
+        let mut interval = tokio::time::interval(self.poll_interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            }
+
+            let content = match fetch_content(uri.clone()).await {
+                Ok(content) => content,
+                Err(err) => {
+                    log::error!("Failed to poll {name}: {err:?}");
+                    continue;
+                }
+            };
+
+            let items = parse_rss_items(&content);
+            for (title, published_at) in &items {
+                if !seen_titles.contains(title) {
+                    let signal = ExternalSignal::Headline {
+                        text: title.clone(),
+                        published_at: *published_at,
+                    };
+                    let _ = news_restrictions.handle_signal(&name, signal);
+                }
+            }
+            seen_titles = items.into_iter().map(|(title, _)| title).collect();
+        }
+    }
+}
+
+async fn fetch_content(uri: Uri) -> Result<String> {
+    let response = create_client().get(uri).await.context("Request failed")?;
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .context("Unable to read response body")?;
+    anyhow::ensure!(status.is_success(), "Unexpected response status {status}");
+
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+static RSS_ITEM: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<item[^>]*>(.*?)</item>").expect("Invalid regex"));
+static RSS_TITLE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<title[^>]*>(.*?)</title>").expect("Invalid regex"));
+static RSS_PUB_DATE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<pubDate>(.*?)</pubDate>").expect("Invalid regex"));
+
+/// Titles and publication times of RSS items
+fn parse_rss_items(content: &str) -> Vec<(String, Option<DateTime>)> {
+    RSS_ITEM
+        .captures_iter(content)
+        .filter_map(|item| {
+            let item = &item[1];
+            let title = RSS_TITLE.captures(item)?[1].trim().to_owned();
+            let title = title
+                .strip_prefix("<![CDATA[")
+                .and_then(|x| x.strip_suffix("]]>"))
+                .map(|x| x.to_owned())
+                .unwrap_or(title);
+
+            let published_at = RSS_PUB_DATE.captures(item).and_then(|x| {
+                chrono::DateTime::parse_from_rfc2822(x[1].trim())
+                    .ok()
+                    .map(|x| x.with_timezone(&Utc))
+            });
+
+            Some((title, published_at))
+        })
+        .collect()
+}
+
+/// Every text message is either JSON of `ExternalSignal` or headline text
+struct WebsocketFeed {
+    url: String,
+}
+
+#[async_trait]
+impl SignalSource for WebsocketFeed {
+    fn name(&self) -> String {
+        format!("Websocket feed {}", self.url)
+    }
+
+    async fn run(
+        &self,
+        news_restrictions: Arc<NewsRestrictionsService>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let name = self.name();
+        loop {
+            tokio::select! {
+                result = read_websocket_feed(&self.url, &name, &news_restrictions) => {
+                    if let Err(err) = result {
+                        log::error!("{name} is disconnected: {err:?}");
+                    }
+                }
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(FEED_RECONNECT_DELAY) => {}
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            }
+        }
+    }
+}
+
+async fn read_websocket_feed(
+    url: &str,
+    name: &str,
+    news_restrictions: &NewsRestrictionsService,
+) -> Result<()> {
+    let (mut websocket, _) = tokio_tungstenite::connect_async(url)
+        .await
+        .context("Unable to connect")?;
+    log::info!("{name} is connected");
+
+    while let Some(message) = websocket.next().await {
+        match message.context("Websocket error")? {
+            Message::Text(text) => {
+                let _ = news_restrictions.handle_signal(name, parse_feed_message(text));
+            }
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_feed_message(text: String) -> ExternalSignal {
+    serde_json::from_str(&text).unwrap_or(ExternalSignal::Headline {
+        text,
+        published_at: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::NewsAssetSettings;
+    use chrono::TimeZone;
+    use mmb_utils::time::{advance_mock_now, set_mock_now};
+
+    fn service(action: NewsRestrictionAction) -> NewsRestrictionsService {
+        let settings = NewsRestrictionsSettings {
+            action,
+            price_collar: rust_decimal_macros::dec!(1),
+            restriction_duration_ms: 600_000,
+            keywords: vec!["halt".to_owned(), "delisting".to_owned()],
+            assets: vec![
+                NewsAssetSettings {
+                    currency_code: "btc".into(),
+                    aliases: vec!["Bitcoin".to_owned()],
+                },
+                NewsAssetSettings {
+                    currency_code: "eth".into(),
+                    aliases: vec![],
+                },
+            ],
+            feeds: vec![],
+        };
+
+        NewsRestrictionsService::new(&settings, DashMap::new(), CancellationToken::default())
+    }
+
+    fn headline(text: &str) -> ExternalSignal {
+        ExternalSignal::Headline {
+            text: text.to_owned(),
+            published_at: None,
+        }
+    }
+
+    #[test]
+    fn headlines_restrict_mentioned_assets_with_keywords() {
+        let time = Utc.ymd(2021, 9, 20).and_hms(0, 0, 0);
+        let _guard = set_mock_now(time);
+        let service = service(NewsRestrictionAction::WidenQuotes);
+
+        assert!(service
+            .handle_signal("test", headline("Delisting method is changed"))
+            .is_empty());
+        assert!(service
+            .handle_signal("test", headline("Bitcoin price goes up"))
+            .is_empty());
+
+        let restricted =
+            service.handle_signal("test", headline("Exchange halts BITCOIN withdrawals"));
+        assert_eq!(restricted, vec![CurrencyCode::from("btc")]);
+        assert!(service.is_restricted("btc".into()));
+        assert!(!service.is_restricted("eth".into()));
+        let restrictions = service.restrictions();
+        assert_eq!(restrictions[0].restricted_at, time);
+        assert_eq!(
+            restrictions[0].expires_at,
+            Utc.ymd(2021, 9, 20).and_hms(0, 10, 0)
+        );
+
+        advance_mock_now(chrono::Duration::hours(1));
+        let outdated = ExternalSignal::Headline {
+            text: "ETH delisting".to_owned(),
+            published_at: Some(time),
+        };
+        assert!(service.handle_signal("test", outdated).is_empty());
+
+        let lifted = service.handle_signal(
+            "test",
+            ExternalSignal::Lift {
+                currency_codes: vec!["btc".into(), "eth".into()],
+            },
+        );
+        assert_eq!(lifted, vec![CurrencyCode::from("btc")]);
+        assert!(service.restrictions().is_empty());
+    }
+
+    #[test]
+    fn feed_messages_are_parsed_as_signals_or_headlines() {
+        assert_eq!(
+            parse_feed_message(
+                r#"{"type":"restrict","currency_codes":["btc"],"reason":"compliance"}"#.to_owned()
+            ),
+            ExternalSignal::Restrict {
+                currency_codes: vec!["btc".into()],
+                reason: "compliance".to_owned(),
+            }
+        );
+        assert_eq!(
+            parse_feed_message("BTC halt".to_owned()),
+            headline("BTC halt")
+        );
+    }
+
+    #[test]
+    fn rss_items_are_parsed() {
+        let content = r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel>
+                <title>Exchange news</title>
+                <item>
+                    <title><![CDATA[Trading halt of BTC]]></title>
+                    <pubDate>Wed, 02 Oct 2002 13:00:00 GMT</pubDate>
+                </item>
+                <item><title>Maintenance</title></item>
+            </channel></rss>"#;
+
+        let items = parse_rss_items(content);
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].0, "Trading halt of BTC");
+        assert_eq!(
+            items[0].1,
+            Some(
+                chrono::DateTime::parse_from_rfc2822("Wed, 02 Oct 2002 13:00:00 GMT")
+                    .expect("in test")
+                    .with_timezone(&Utc)
+            )
+        );
+        assert_eq!(items[1], ("Maintenance".to_owned(), None));
+    }
+}
@@ -0,0 +1,477 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::DurationRound;
+use dashmap::DashMap;
+use mmb_database::impl_event;
+use mmb_database::postgres_db::events::TableName;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::database::events::recorder::EventRecorder;
+use crate::exchanges::common::{Amount, ExchangeAccountId, MarketAccountId, Price};
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::event::OrderEventType;
+use crate::orders::fill::OrderFillType;
+use crate::orders::order::OrderSide;
+use crate::settings::{PerformanceAttributionSettings, ReportPeriod};
+
+/// Count of completed reports kept in memory for control API
+const MAX_KEPT_REPORTS: usize = 10_000;
+
+impl ReportPeriod {
+    fn duration(&self) -> chrono::Duration {
+        match self {
+            ReportPeriod::Hourly => chrono::Duration::hours(1),
+            ReportPeriod::Daily => chrono::Duration::days(1),
+        }
+    }
+
+    /// Start of period which contains `time`
+    pub fn period_start(&self, time: DateTime) -> DateTime {
+        time.duration_trunc(self.duration())
+            .expect("Period duration should fit into timestamp")
+    }
+}
+
+/// PnL in quote currency decomposed by sources
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize)]
+pub struct PnlAttribution {
+    /// Difference between fill price and middle price at the moment of fill
+    pub spread_capture: Amount,
+    /// Revaluation of position by middle price moves
+    pub inventory: Amount,
+    /// Paid commissions are negative and received rebates are positive
+    pub fees: Amount,
+    pub funding: Amount,
+}
+
+impl PnlAttribution {
+    pub fn total(&self) -> Amount {
+        self.spread_capture + self.inventory + self.fees + self.funding
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributionReport {
+    pub strategy_name: String,
+    pub market_account_id: MarketAccountId,
+    pub period_start: DateTime,
+    pub period_end: DateTime,
+    pub attribution: PnlAttribution,
+    pub total: Amount,
+    /// Position in base currency at the end of period
+    pub position: Amount,
+    pub traded_amount: Amount,
+}
+
+impl_event!(&AttributionReport, "performance_attribution");
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributionReports {
+    pub completed: Vec<AttributionReport>,
+    /// Attribution of the current period up to now
+    pub current: Vec<AttributionReport>,
+}
+
+/// PnL attribution of strategy on market. Position is marked to middle price, so PnL of fill
+/// is split into spread capture relative to middle price and following inventory revaluation
+#[derive(Debug, Default, Clone)]
+pub struct MarketAttribution {
+    position: Amount,
+    mark_price: Option<Price>,
+    attribution: PnlAttribution,
+    traded_amount: Amount,
+}
+
+impl MarketAttribution {
+    pub fn mark_to_market(&mut self, price: Price) {
+        if let Some(mark_price) = self.mark_price {
+            self.attribution.inventory += self.position * (price - mark_price);
+        }
+        self.mark_price = Some(price);
+    }
+
+    /// `middle_price` is middle price at the moment of fill, fill price is used if it's unknown
+    pub fn register_fill(
+        &mut self,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        fee: Amount,
+        middle_price: Option<Price>,
+    ) {
+        let reference_price = middle_price.unwrap_or(price);
+        self.mark_to_market(reference_price);
+
+        let (spread, position_change) = match side {
+            OrderSide::Buy => (reference_price - price, amount),
+            OrderSide::Sell => (price - reference_price, -amount),
+        };
+        self.attribution.spread_capture += spread * amount;
+        self.attribution.fees -= fee;
+        self.position += position_change;
+        self.traded_amount += amount;
+    }
+
+    pub fn register_funding(&mut self, funding: Amount) {
+        self.attribution.funding += funding;
+    }
+
+    pub fn position(&self) -> Amount {
+        self.position
+    }
+
+    pub fn attribution(&self) -> PnlAttribution {
+        self.attribution
+    }
+
+    /// Returns attribution of finished period and starts new one. Position is carried over
+    fn take_period(&mut self) -> (PnlAttribution, Amount) {
+        let traded_amount = std::mem::take(&mut self.traded_amount);
+        (std::mem::take(&mut self.attribution), traded_amount)
+    }
+}
+
+type AttributionKey = (String, MarketAccountId);
+
+struct AttributionState {
+    period_start: DateTime,
+    markets: HashMap<AttributionKey, MarketAttribution>,
+    middle_prices: HashMap<MarketAccountId, Price>,
+    completed: VecDeque<AttributionReport>,
+}
+
+/// Periodic reports decomposing PnL of strategies per market into spread capture, inventory moves,
+/// fees and funding. PnL is calculated in quote currency for linear markets.
+/// Completed reports are saved to database and available via control API
+pub struct PerformanceAttributionService {
+    report_period: ReportPeriod,
+    event_recorder: Arc<EventRecorder>,
+    state: Mutex<AttributionState>,
+}
+
+impl PerformanceAttributionService {
+    pub fn start(
+        settings: &PerformanceAttributionSettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        event_recorder: Arc<EventRecorder>,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Arc<Self> {
+        let service = Arc::new(PerformanceAttributionService {
+            report_period: settings.report_period,
+            event_recorder,
+            state: Mutex::new(AttributionState {
+                period_start: settings.report_period.period_start(time_manager::now()),
+                markets: Default::default(),
+                middle_prices: Default::default(),
+                completed: Default::default(),
+            }),
+        });
+
+        let _ = spawn_future(
+            "Performance attribution events handling",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            service
+                .clone()
+                .handle_events(exchanges, events_receiver, cancellation_token.clone()),
+        );
+
+        let _ = spawn_future(
+            "Performance attribution reports",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            service.clone().make_reports(cancellation_token),
+        );
+
+        service
+    }
+
+    pub fn reports(&self) -> AttributionReports {
+        let mut state = self.state.lock();
+        let period_start = state.period_start;
+        let now = time_manager::now();
+
+        let current = Self::mark_to_market(&mut state)
+            .map(
+                |((strategy_name, market_account_id), market)| AttributionReport {
+                    strategy_name: strategy_name.clone(),
+                    market_account_id: *market_account_id,
+                    period_start,
+                    period_end: now,
+                    attribution: market.attribution,
+                    total: market.attribution.total(),
+                    position: market.position,
+                    traded_amount: market.traded_amount,
+                },
+            )
+            .collect();
+
+        AttributionReports {
+            completed: state.completed.iter().cloned().collect(),
+            current,
+        }
+    }
+
+    /// Total PnL of all strategies on market in kept completed reports and the current period
+    pub fn market_pnl(&self, market_account_id: MarketAccountId) -> Amount {
+        let mut state = self.state.lock();
+        let completed: Amount = state
+            .completed
+            .iter()
+            .filter(|x| x.market_account_id == market_account_id)
+            .map(|x| x.total)
+            .sum();
+        let current: Amount = Self::mark_to_market(&mut state)
+            .filter(|((_, x), _)| *x == market_account_id)
+            .map(|(_, market)| market.attribution.total())
+            .sum();
+
+        completed + current
+    }
+
+    fn mark_to_market(
+        state: &mut AttributionState,
+    ) -> impl Iterator<Item = (&AttributionKey, &mut MarketAttribution)> {
+        let middle_prices = &state.middle_prices;
+        state.markets.iter_mut().map(move |(key, market)| {
+            if let Some(&price) = middle_prices.get(&key.1) {
+                market.mark_to_market(price);
+            }
+            (key, market)
+        })
+    }
+
+    /// Closes current period and saves its reports
+    fn complete_period(&self, period_end: DateTime) {
+        let mut state = self.state.lock();
+        let period_start = std::mem::replace(&mut state.period_start, period_end);
+
+        let reports = Self::mark_to_market(&mut state)
+            .filter_map(|((strategy_name, market_account_id), market)| {
+                let (attribution, traded_amount) = market.take_period();
+                let is_empty = attribution == PnlAttribution::default()
+                    && traded_amount.is_zero()
+                    && market.position.is_zero();
+
+                (!is_empty).then(|| AttributionReport {
+                    strategy_name: strategy_name.clone(),
+                    market_account_id: *market_account_id,
+                    period_start,
+                    period_end,
+                    attribution,
+                    total: attribution.total(),
+                    position: market.position,
+                    traded_amount,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for report in reports {
+            log::info!(
+                "Performance attribution of {} on {:?} for period from {period_start} to {period_end}: {:?}",
+                report.strategy_name,
+                report.market_account_id,
+                report.attribution
+            );
+
+            if let Err(error) = self.event_recorder.save(&report) {
+                log::error!("Failed to save performance attribution report: {error:?}");
+            }
+
+            if state.completed.len() == MAX_KEPT_REPORTS {
+                let _ = state.completed.pop_front();
+            }
+            state.completed.push_back(report);
+        }
+    }
+
+    async fn make_reports(self: Arc<Self>, cancellation_token: CancellationToken) -> Result<()> {
+        loop {
+            let period_end = self.state.lock().period_start + self.report_period.duration();
+            let delay = (period_end - time_manager::now())
+                .to_std()
+                .unwrap_or_default();
+
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            }
+
+            self.complete_period(period_end);
+        }
+    }
+
+    async fn handle_events(
+        self: Arc<Self>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let mut snapshots = LocalSnapshotsService::default();
+
+        loop {
+            let event = tokio::select! {
+                event = events_receiver.recv() => event,
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            };
+            let event = match event {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Performance attribution skipped {skipped} exchange events");
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            match event {
+                ExchangeEvent::OrderBookEvent(order_book_event) => {
+                    let market_account_id = match snapshots.update(order_book_event) {
+                        Some(market_account_id) => market_account_id,
+                        None => continue,
+                    };
+                    let market_id = market_account_id.market_id();
+                    if let Some(price) = snapshots
+                        .get_snapshot(market_id)
+                        .and_then(|x| x.calculate_middle_price(market_id))
+                    {
+                        let _ = self
+                            .state
+                            .lock()
+                            .middle_prices
+                            .insert(market_account_id, price);
+                    }
+                }
+                ExchangeEvent::OrderEvent(order_event) => {
+                    let cloned_order = match order_event.event_type {
+                        OrderEventType::OrderFilled { cloned_order } => cloned_order,
+                        _ => continue,
+                    };
+                    let fill = match cloned_order.fills.fills.last() {
+                        Some(fill) => fill,
+                        None => continue,
+                    };
+                    let market_account_id = cloned_order.header.market_account_id();
+                    let symbol = match exchanges
+                        .get(&market_account_id.exchange_account_id)
+                        .and_then(|x| {
+                            x.symbols
+                                .get(&market_account_id.currency_pair)
+                                .map(|x| x.clone())
+                        }) {
+                        Some(symbol) => symbol,
+                        None => continue,
+                    };
+                    let fee = match fill.converted_commission_currency_code()
+                        == symbol.quote_currency_code()
+                    {
+                        true => fill.converted_commission_amount(),
+                        false => fill.converted_commission_amount() * fill.price(),
+                    };
+                    let side = fill.side().unwrap_or(cloned_order.header.side);
+
+                    let mut state = self.state.lock();
+                    let middle_price = state.middle_prices.get(&market_account_id).copied();
+                    let market = state
+                        .markets
+                        .entry((cloned_order.header.strategy_name.clone(), market_account_id))
+                        .or_default();
+                    match fill.fill_type() {
+                        // funding payment is received by sell fill and paid by buy fill
+                        OrderFillType::Funding => market.register_funding(match side {
+                            OrderSide::Sell => fill.cost(),
+                            OrderSide::Buy => -fill.cost(),
+                        }),
+                        _ => market.register_fill(
+                            side,
+                            fill.price(),
+                            fill.amount(),
+                            fee,
+                            middle_price,
+                        ),
+                    }
+                }
+                _ => continue,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    pub fn fill_pnl_is_split_into_spread_capture_and_inventory() {
+        let mut market = MarketAttribution::default();
+
+        market.register_fill(
+            OrderSide::Buy,
+            dec!(99),
+            dec!(2),
+            dec!(0.1),
+            Some(dec!(100)),
+        );
+        market.mark_to_market(dec!(103));
+        market.register_fill(
+            OrderSide::Sell,
+            dec!(104),
+            dec!(1),
+            dec!(0.1),
+            Some(dec!(103)),
+        );
+        market.register_funding(dec!(-0.5));
+
+        let attribution = market.attribution();
+        assert_eq!(attribution.spread_capture, dec!(3));
+        assert_eq!(attribution.inventory, dec!(6));
+        assert_eq!(attribution.fees, dec!(-0.2));
+        assert_eq!(attribution.funding, dec!(-0.5));
+        assert_eq!(market.position(), dec!(1));
+        // cash flow -198 + 104 and remaining position valued by last mark 103
+        assert_eq!(
+            attribution.total(),
+            dec!(-94) + dec!(103) - dec!(0.2) - dec!(0.5)
+        );
+    }
+
+    #[test]
+    pub fn position_is_carried_over_to_next_period() {
+        let mut market = MarketAttribution::default();
+        market.register_fill(OrderSide::Sell, dec!(10), dec!(1), dec!(0), None);
+
+        let (attribution, traded_amount) = market.take_period();
+        assert_eq!(attribution.spread_capture, dec!(0));
+        assert_eq!(traded_amount, dec!(1));
+
+        market.mark_to_market(dec!(8));
+        assert_eq!(market.attribution().inventory, dec!(2));
+        assert_eq!(market.position(), dec!(-1));
+    }
+
+    #[test]
+    pub fn period_start() {
+        let time = Utc.ymd(2022, 10, 17).and_hms(13, 45, 10);
+
+        assert_eq!(
+            ReportPeriod::Hourly.period_start(time),
+            Utc.ymd(2022, 10, 17).and_hms(13, 0, 0)
+        );
+        assert_eq!(
+            ReportPeriod::Daily.period_start(time),
+            Utc.ymd(2022, 10, 17).and_hms(0, 0, 0)
+        );
+    }
+}
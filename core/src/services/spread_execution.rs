@@ -0,0 +1,557 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::{impl_u64_id, time::get_atomic_current_secs};
+use once_cell::sync::Lazy;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::exchanges::common::{Amount, ExchangeAccountId, MarketAccountId, Price};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::Round;
+use crate::misc::time::time_manager::now;
+use crate::orders::order::{
+    ClientOrderId, OrderCreating, OrderExecutionType, OrderHeader, OrderSide, OrderType,
+    ReservationId,
+};
+use crate::service_configuration::configuration_descriptor::{
+    ConfigurationDescriptor, ServiceConfigurationKey, ServiceName,
+};
+
+const SPREAD_FILLS_CHANNEL_CAPACITY: usize = 1_000;
+
+impl_u64_id!(SpreadId);
+
+/// Controls of risk when one leg of spread is filled and other one isn't yet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LegRiskLimits {
+    /// Max notional of long leg which may be unhedged at once. Spread is worked by slices of this size
+    pub max_unhedged_notional: Amount,
+    /// Time for filling of leg order. Unhedged long leg amount is flattened when hedge isn't filled in time
+    pub leg_timeout: Duration,
+}
+
+/// Two-leg spread: buy `amount` on long market and sell `amount * ratio` on short market.
+/// Long leg is worked by limit orders at `long_price`, its fills are hedged by market orders
+/// on short leg
+#[derive(Debug, Clone)]
+pub struct SpreadRequest {
+    pub long_market: MarketAccountId,
+    pub short_market: MarketAccountId,
+    /// Amount of long leg
+    pub amount: Amount,
+    /// Amount of short leg per unit of long leg
+    pub ratio: Decimal,
+    pub long_price: Price,
+    /// Reference price of short leg used for notional estimation
+    pub short_price: Price,
+    pub limits: LegRiskLimits,
+    pub strategy_name: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LegFill {
+    pub amount: Amount,
+    /// Sum of price * amount of fills
+    pub cost: Amount,
+}
+
+impl LegFill {
+    pub fn average_price(&self) -> Option<Price> {
+        (self.amount > dec!(0)).then(|| self.cost / self.amount)
+    }
+
+    fn add(&mut self, other: LegFill) {
+        self.amount += other.amount;
+        self.cost += other.cost;
+    }
+
+    fn subtract(&mut self, other: LegFill) {
+        self.amount -= other.amount;
+        self.cost -= other.cost;
+    }
+}
+
+/// Fill of spread reported as a unit: hedged amounts of both legs and achieved spread price
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SpreadFill {
+    pub spread_id: SpreadId,
+    pub long_market: MarketAccountId,
+    pub short_market: MarketAccountId,
+    pub long: LegFill,
+    pub short: LegFill,
+    /// Average long price minus average short price per unit of long leg
+    pub spread_price: Option<Price>,
+}
+
+/// Fills of both legs of executing spread
+#[derive(Debug, Clone)]
+pub struct SpreadExecution {
+    pub id: SpreadId,
+    pub request: SpreadRequest,
+    pub long: LegFill,
+    pub short: LegFill,
+}
+
+impl SpreadExecution {
+    pub fn new(request: SpreadRequest) -> Self {
+        SpreadExecution {
+            id: SpreadId::generate(),
+            request,
+            long: LegFill::default(),
+            short: LegFill::default(),
+        }
+    }
+
+    /// Long leg amount of the next slice limited by max unhedged notional
+    pub fn next_slice_amount(&self) -> Amount {
+        let remaining = (self.request.amount - self.long.amount).max(dec!(0));
+        match self.request.long_price > dec!(0) {
+            true => {
+                remaining.min(self.request.limits.max_unhedged_notional / self.request.long_price)
+            }
+            false => remaining,
+        }
+    }
+
+    /// Short leg amount which should be sold to hedge filled long amount
+    pub fn hedge_amount(&self) -> Amount {
+        (self.long.amount * self.request.ratio - self.short.amount).max(dec!(0))
+    }
+
+    /// Long leg amount which isn't covered by short leg fills
+    pub fn unhedged_amount(&self) -> Amount {
+        match self.request.ratio > dec!(0) {
+            true => (self.long.amount - self.short.amount / self.request.ratio).max(dec!(0)),
+            false => self.long.amount,
+        }
+    }
+
+    pub fn unhedged_notional(&self) -> Amount {
+        self.unhedged_amount() * self.request.long_price
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.long.amount >= self.request.amount
+    }
+
+    pub fn spread_fill(&self) -> SpreadFill {
+        let spread_price = match (self.long.average_price(), self.short.average_price()) {
+            (Some(long_price), Some(short_price)) => {
+                Some(long_price - short_price * self.request.ratio)
+            }
+            _ => None,
+        };
+
+        SpreadFill {
+            spread_id: self.id,
+            long_market: self.request.long_market,
+            short_market: self.request.short_market,
+            long: self.long,
+            short: self.short,
+            spread_price,
+        }
+    }
+}
+
+/// Executor of two-leg spreads for pairs trading strategies. Spread is worked by slices: limit
+/// order on long leg is hedged by market order on short leg, so unhedged notional doesn't exceed
+/// the limit. If hedge isn't filled in time, unhedged long amount is flattened and execution is stopped
+pub struct SpreadExecutor {
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    fills_sender: broadcast::Sender<SpreadFill>,
+    cancellation_token: CancellationToken,
+}
+
+impl SpreadExecutor {
+    pub fn new(
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        cancellation_token: CancellationToken,
+    ) -> Arc<Self> {
+        let (fills_sender, _) = broadcast::channel(SPREAD_FILLS_CHANNEL_CAPACITY);
+
+        Arc::new(SpreadExecutor {
+            exchanges,
+            fills_sender,
+            cancellation_token,
+        })
+    }
+
+    /// Receiver of spread fills which are sent after each hedged slice
+    pub fn subscribe(&self) -> broadcast::Receiver<SpreadFill> {
+        self.fills_sender.subscribe()
+    }
+
+    /// Works spread until long leg amount is filled. Returns total spread fill
+    pub async fn execute(&self, request: SpreadRequest) -> Result<SpreadFill> {
+        if request.amount <= dec!(0) || request.ratio <= dec!(0) {
+            bail!("Spread amount and ratio should be positive: {request:?}");
+        }
+
+        let mut execution = SpreadExecution::new(request);
+        log::info!("Executing spread {}: {:?}", execution.id, execution.request);
+
+        while !execution.is_completed() {
+            let long_exchange = self.get_exchange(execution.request.long_market)?;
+            let short_exchange = self.get_exchange(execution.request.short_market)?;
+            let slice_amount = long_exchange
+                .get_symbol(execution.request.long_market.currency_pair)?
+                .amount_round(execution.next_slice_amount(), Round::Floor);
+            if slice_amount <= dec!(0) {
+                break;
+            }
+
+            let (long_reservation_id, short_reservation_id) = self
+                .reserve_slice(&execution, slice_amount)
+                .with_context(|| {
+                    format!(
+                        "Unable to reserve balance for slice of spread {}",
+                        execution.id
+                    )
+                })?;
+
+            let long_fill = match self
+                .execute_order(
+                    &execution,
+                    execution.request.long_market,
+                    OrderSide::Buy,
+                    OrderType::Limit,
+                    slice_amount,
+                    execution.request.long_price,
+                    long_reservation_id,
+                )
+                .await
+            {
+                Ok(long_fill) => long_fill,
+                Err(err) => {
+                    short_exchange.release_unused_reservation(short_reservation_id);
+                    return Err(err.context(format!("Long leg of spread {} failed", execution.id)));
+                }
+            };
+            if long_fill.amount <= dec!(0) {
+                short_exchange.release_unused_reservation(short_reservation_id);
+                log::info!(
+                    "Long leg of spread {} isn't filled in time, execution is stopped",
+                    execution.id
+                );
+                break;
+            }
+            execution.long.add(long_fill);
+
+            self.hedge(&mut execution, short_reservation_id).await?;
+
+            let _ = self.fills_sender.send(execution.spread_fill());
+        }
+
+        let spread_fill = execution.spread_fill();
+        log::info!("Spread {} is executed: {spread_fill:?}", execution.id);
+
+        Ok(spread_fill)
+    }
+
+    /// Balances of both legs of slice are reserved before long leg order is sent, so fills of
+    /// long leg can be hedged
+    fn reserve_slice(
+        &self,
+        execution: &SpreadExecution,
+        slice_amount: Amount,
+    ) -> Result<(ReservationId, ReservationId)> {
+        let request = &execution.request;
+        let long_exchange = self.get_exchange(request.long_market)?;
+        let short_exchange = self.get_exchange(request.short_market)?;
+        let short_amount = short_exchange
+            .get_symbol(request.short_market.currency_pair)?
+            .amount_round(slice_amount * request.ratio, Round::Ceiling);
+
+        let long_reservation_id = long_exchange.reserve_order_balance(
+            configuration_descriptor(request),
+            request.long_market.currency_pair,
+            OrderSide::Buy,
+            request.long_price,
+            slice_amount,
+        )?;
+        match short_exchange.reserve_order_balance(
+            configuration_descriptor(request),
+            request.short_market.currency_pair,
+            OrderSide::Sell,
+            request.short_price,
+            short_amount,
+        ) {
+            Ok(short_reservation_id) => Ok((long_reservation_id, short_reservation_id)),
+            Err(err) => {
+                long_exchange.release_unused_reservation(long_reservation_id);
+                Err(err)
+            }
+        }
+    }
+
+    /// Hedges filled long amount by short leg. If hedge fails or isn't fully filled within leg
+    /// timeout, unhedged long amount is flattened and execution is stopped
+    async fn hedge(
+        &self,
+        execution: &mut SpreadExecution,
+        reservation_id: ReservationId,
+    ) -> Result<()> {
+        let short_exchange = self.get_exchange(execution.request.short_market)?;
+        let short_symbol =
+            short_exchange.get_symbol(execution.request.short_market.currency_pair)?;
+        let hedge_amount = short_symbol.amount_round(execution.hedge_amount(), Round::Floor);
+
+        let hedge_result = match hedge_amount > dec!(0) {
+            true => {
+                self.execute_order(
+                    execution,
+                    execution.request.short_market,
+                    OrderSide::Sell,
+                    OrderType::Market,
+                    hedge_amount,
+                    execution.request.short_price,
+                    reservation_id,
+                )
+                .await
+            }
+            false => {
+                short_exchange.release_unused_reservation(reservation_id);
+                Ok(LegFill::default())
+            }
+        };
+
+        match hedge_result {
+            Ok(short_fill) => {
+                execution.short.add(short_fill);
+                let remaining = short_symbol.amount_round(execution.hedge_amount(), Round::Floor);
+                if remaining <= dec!(0) {
+                    return Ok(());
+                }
+                log::warn!(
+                    "Short leg of spread {} isn't fully filled in {:?}, {remaining} remains unhedged",
+                    execution.id,
+                    execution.request.limits.leg_timeout
+                );
+            }
+            Err(error) => log::error!("Short leg of spread {} failed: {error:?}", execution.id),
+        }
+
+        let long_exchange = self.get_exchange(execution.request.long_market)?;
+        let flatten_amount = long_exchange
+            .get_symbol(execution.request.long_market.currency_pair)?
+            .amount_round(execution.unhedged_amount(), Round::Floor);
+        log::warn!(
+            "Flattening {flatten_amount} of long leg of spread {} with unhedged notional {}",
+            execution.id,
+            execution.unhedged_notional()
+        );
+        if flatten_amount > dec!(0) {
+            let reservation_id = long_exchange.reserve_order_balance(
+                configuration_descriptor(&execution.request),
+                execution.request.long_market.currency_pair,
+                OrderSide::Sell,
+                execution.request.long_price,
+                flatten_amount,
+            )?;
+            let flatten_fill = self
+                .execute_order(
+                    execution,
+                    execution.request.long_market,
+                    OrderSide::Sell,
+                    OrderType::Market,
+                    flatten_amount,
+                    execution.request.long_price,
+                    reservation_id,
+                )
+                .await
+                .with_context(|| {
+                    format!("Failed to flatten long leg of spread {}", execution.id)
+                })?;
+            execution.long.subtract(flatten_fill);
+        }
+
+        bail!(
+            "Spread {} is stopped because hedge isn't filled",
+            execution.id
+        )
+    }
+
+    /// Creates order and waits its finish during leg timeout. Order is canceled on timeout.
+    /// Reservation of order is released when order is finished or isn't created
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_order(
+        &self,
+        execution: &SpreadExecution,
+        market_account_id: MarketAccountId,
+        side: OrderSide,
+        order_type: OrderType,
+        amount: Amount,
+        price: Price,
+        reservation_id: ReservationId,
+    ) -> Result<LegFill> {
+        let exchange = self.get_exchange(market_account_id)?;
+        let header = OrderHeader::new(
+            ClientOrderId::unique_id(),
+            now(),
+            market_account_id.exchange_account_id,
+            market_account_id.currency_pair,
+            order_type,
+            side,
+            amount,
+            OrderExecutionType::None,
+            Some(reservation_id),
+            Some(execution.id.to_string()),
+            execution.request.strategy_name.clone(),
+        );
+
+        let order = match exchange
+            .create_order(
+                OrderCreating {
+                    header: header.clone(),
+                    price,
+                },
+                None,
+                self.cancellation_token.clone(),
+            )
+            .await
+        {
+            Ok(order) => order,
+            Err(err) => {
+                exchange.release_order_reservation(&header);
+                return Err(err);
+            }
+        };
+
+        let wait_finish =
+            exchange
+                .clone()
+                .wait_order_finish(&order, None, self.cancellation_token.clone());
+        let finish_result =
+            match tokio::time::timeout(execution.request.limits.leg_timeout, wait_finish).await {
+                Ok(result) => result,
+                Err(_) => {
+                    if let Some(order_cancelling) = order.to_order_cancelling() {
+                        let _ = exchange
+                            .cancel_order(order_cancelling, self.cancellation_token.clone())
+                            .await;
+                    }
+                    exchange
+                        .clone()
+                        .wait_order_finish(&order, None, self.cancellation_token.clone())
+                        .await
+                }
+            };
+        exchange.release_order_reservation(&header);
+        let _ = finish_result?;
+
+        Ok(order.fn_ref(|x| LegFill {
+            amount: x.fills.filled_amount,
+            cost: x.fills.fills.iter().map(|f| f.price() * f.amount()).sum(),
+        }))
+    }
+
+    fn get_exchange(&self, market_account_id: MarketAccountId) -> Result<Arc<Exchange>> {
+        self.exchanges
+            .get(&market_account_id.exchange_account_id)
+            .map(|x| x.clone())
+            .with_context(|| {
+                format!(
+                    "Exchange {} isn't found",
+                    market_account_id.exchange_account_id
+                )
+            })
+    }
+}
+
+/// Balances of spread orders are reserved by strategy of spread and its long market
+fn configuration_descriptor(request: &SpreadRequest) -> ConfigurationDescriptor {
+    ConfigurationDescriptor::new(
+        ServiceName::new(&request.strategy_name),
+        ServiceConfigurationKey::new(
+            format!(
+                "{};{}",
+                request.long_market.exchange_account_id, request.long_market.currency_pair
+            )
+            .as_str(),
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+
+    fn execution() -> SpreadExecution {
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        SpreadExecution::new(SpreadRequest {
+            long_market: MarketAccountId::new(
+                exchange_account_id,
+                CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            ),
+            short_market: MarketAccountId::new(
+                exchange_account_id,
+                CurrencyPair::from_codes("eth".into(), "usdt".into()),
+            ),
+            amount: dec!(1),
+            ratio: dec!(10),
+            long_price: dec!(1000),
+            short_price: dec!(100),
+            limits: LegRiskLimits {
+                max_unhedged_notional: dec!(400),
+                leg_timeout: Duration::from_secs(1),
+            },
+            strategy_name: "test".to_owned(),
+        })
+    }
+
+    #[test]
+    pub fn slices_are_limited_by_unhedged_notional() {
+        let mut execution = execution();
+        assert_eq!(execution.next_slice_amount(), dec!(0.4));
+
+        execution.long.add(LegFill {
+            amount: dec!(0.8),
+            cost: dec!(800),
+        });
+        assert_eq!(execution.next_slice_amount(), dec!(0.2));
+        assert!(!execution.is_completed());
+    }
+
+    #[test]
+    pub fn unhedged_amount_by_leg_fills() {
+        let mut execution = execution();
+        execution.long.add(LegFill {
+            amount: dec!(0.4),
+            cost: dec!(400),
+        });
+        assert_eq!(execution.hedge_amount(), dec!(4));
+        assert_eq!(execution.unhedged_notional(), dec!(400));
+
+        execution.short.add(LegFill {
+            amount: dec!(3),
+            cost: dec!(297),
+        });
+        assert_eq!(execution.hedge_amount(), dec!(1));
+        assert_eq!(execution.unhedged_amount(), dec!(0.1));
+    }
+
+    #[test]
+    pub fn spread_fill_price() {
+        let mut execution = execution();
+        execution.long.add(LegFill {
+            amount: dec!(0.5),
+            cost: dec!(505),
+        });
+        execution.short.add(LegFill {
+            amount: dec!(5),
+            cost: dec!(495),
+        });
+
+        assert_eq!(execution.spread_fill().spread_price, Some(dec!(20)));
+    }
+}
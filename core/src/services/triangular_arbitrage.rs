@@ -0,0 +1,736 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::common::{
+    Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, MarketId, Price,
+};
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::exchange_blocker::ExchangeBlocker;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::Round;
+use crate::infrastructure::spawn_future;
+use crate::math::ConvertPercentToRate;
+use crate::misc::time::time_manager::now;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::order::{
+    ClientOrderId, OrderCreating, OrderExecutionType, OrderHeader, OrderSide, OrderType,
+    ReservationId,
+};
+use crate::service_configuration::configuration_descriptor::{
+    ConfigurationDescriptor, ServiceConfigurationKey, ServiceName,
+};
+use crate::settings::{
+    TriangleSettings, TriangularArbitrageExecutorSettings, TriangularArbitrageSettings,
+};
+
+const OPPORTUNITIES_CHANNEL_CAPACITY: usize = 1_000;
+const STRATEGY_NAME: &str = "TriangularArbitrage";
+
+/// Conversion of one currency of triangle to another one
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TriangleLeg {
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+}
+
+impl TriangleLeg {
+    /// Returns leg converting `from` currency by currency pair and received currency
+    fn convert(currency_pair: CurrencyPair, from: CurrencyCode) -> (TriangleLeg, CurrencyCode) {
+        let codes = currency_pair.to_codes();
+        let (side, to) = match codes.base == from {
+            true => (OrderSide::Sell, codes.quote),
+            false => (OrderSide::Buy, codes.base),
+        };
+        (
+            TriangleLeg {
+                currency_pair,
+                side,
+            },
+            to,
+        )
+    }
+}
+
+/// Round trip through 3 currency pairs which starts and ends with the same currency
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TriangleRoute {
+    pub start_currency_code: CurrencyCode,
+    pub legs: [TriangleLeg; 3],
+}
+
+impl TriangleRoute {
+    /// Both directions of round trip for configured triangle
+    pub fn from_settings(settings: &TriangleSettings) -> Result<[TriangleRoute; 2]> {
+        let pairs = match settings.currency_pairs[..] {
+            [first, second, third] => [first, second, third],
+            _ => bail!(
+                "Triangle should contain exactly 3 currency pairs: {:?}",
+                settings.currency_pairs
+            ),
+        };
+
+        let currency_counts = pairs
+            .iter()
+            .flat_map(|x| {
+                let codes = x.to_codes();
+                [codes.base, codes.quote]
+            })
+            .counts();
+        if currency_counts.len() != 3 || currency_counts.values().any(|&x| x != 2) {
+            bail!("Currency pairs {pairs:?} don't form triangle");
+        }
+
+        let start = settings.start_currency_code;
+        if !currency_counts.contains_key(&start) {
+            bail!("Start currency {start} isn't in triangle {pairs:?}");
+        }
+
+        let (starting, middle): (Vec<_>, Vec<_>) = pairs.iter().copied().partition(|x| {
+            let codes = x.to_codes();
+            codes.base == start || codes.quote == start
+        });
+
+        let route = |first: CurrencyPair, last: CurrencyPair| {
+            let (first_leg, second_currency) = TriangleLeg::convert(first, start);
+            let (second_leg, third_currency) = TriangleLeg::convert(middle[0], second_currency);
+            let (third_leg, _) = TriangleLeg::convert(last, third_currency);
+
+            TriangleRoute {
+                start_currency_code: start,
+                legs: [first_leg, second_leg, third_leg],
+            }
+        };
+
+        Ok([
+            route(starting[0], starting[1]),
+            route(starting[1], starting[0]),
+        ])
+    }
+
+    fn contains(&self, currency_pair: CurrencyPair) -> bool {
+        self.legs.iter().any(|x| x.currency_pair == currency_pair)
+    }
+
+    /// Profitability of round trip by best prices of order books after taker fees.
+    /// Returns `None` if some order book isn't received yet or is empty
+    pub fn evaluate(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        snapshots: &LocalSnapshotsService,
+        taker_fee_rate: Decimal,
+        now: DateTime,
+    ) -> Option<TriangularOpportunity> {
+        let mut rate = dec!(1);
+        let mut max_start_amount: Option<Amount> = None;
+        let mut legs = Vec::with_capacity(self.legs.len());
+
+        for leg in &self.legs {
+            let market_id = MarketId::new(exchange_account_id.exchange_id, leg.currency_pair);
+            let snapshot = snapshots.get_snapshot(market_id)?;
+            let (price, amount) = match leg.side {
+                OrderSide::Sell => snapshot.get_top_bid()?,
+                OrderSide::Buy => snapshot.get_top_ask()?,
+            };
+            if price <= dec!(0) {
+                return None;
+            }
+
+            // amount available at best price in currency which is converted by leg
+            let (input_capacity, leg_rate) = match leg.side {
+                OrderSide::Sell => (amount, price),
+                OrderSide::Buy => (amount * price, dec!(1) / price),
+            };
+            let start_capacity = input_capacity / rate;
+            max_start_amount =
+                Some(max_start_amount.map_or(start_capacity, |x| x.min(start_capacity)));
+            rate *= leg_rate * (dec!(1) - taker_fee_rate);
+
+            legs.push(OpportunityLeg {
+                currency_pair: leg.currency_pair,
+                side: leg.side,
+                price,
+            });
+        }
+
+        Some(TriangularOpportunity {
+            exchange_account_id,
+            start_currency_code: self.start_currency_code,
+            legs,
+            profit_rate: rate - dec!(1),
+            max_start_amount: max_start_amount?,
+            time: now,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OpportunityLeg {
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    /// Best price of order book side which leg is executed against
+    pub price: Price,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TriangularOpportunity {
+    pub exchange_account_id: ExchangeAccountId,
+    pub start_currency_code: CurrencyCode,
+    pub legs: Vec<OpportunityLeg>,
+    /// Round trip profit after taker fees, e.g. 0.001 means 0.1%
+    pub profit_rate: Decimal,
+    /// Amount of start currency which can be converted by best levels of all legs
+    pub max_start_amount: Amount,
+    pub time: DateTime,
+}
+
+/// Continuously computes round trip profitability of configured triangles and emits profitable opportunities
+pub struct TriangularArbitrageService {
+    exchange: Arc<Exchange>,
+    routes: Vec<TriangleRoute>,
+    min_profit_rate: Decimal,
+    opportunities_sender: broadcast::Sender<TriangularOpportunity>,
+}
+
+impl TriangularArbitrageService {
+    pub fn start(
+        settings: &TriangularArbitrageSettings,
+        exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+        exchange_blocker: Arc<ExchangeBlocker>,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<Self>> {
+        let exchange_account_id = settings.exchange_account_id;
+        let exchange = exchanges
+            .get(&exchange_account_id)
+            .with_context(|| format!("Exchange {exchange_account_id} isn't configured"))?
+            .clone();
+
+        let mut routes = vec![];
+        for triangle in &settings.triangles {
+            routes.extend(TriangleRoute::from_settings(triangle)?);
+        }
+
+        let (opportunities_sender, _) = broadcast::channel(OPPORTUNITIES_CHANNEL_CAPACITY);
+        let service = Arc::new(TriangularArbitrageService {
+            exchange: exchange.clone(),
+            routes,
+            min_profit_rate: settings.min_profit.percent_to_rate(),
+            opportunities_sender,
+        });
+
+        if let Some(executor_settings) = &settings.executor {
+            if exchange.is_market_data_only() {
+                bail!("Triangular arbitrage executor can't be started in market data only mode");
+            }
+
+            let executor = TriangularArbitrageExecutor {
+                exchange,
+                exchange_blocker,
+                settings: executor_settings.clone(),
+                cancellation_token,
+            };
+            let _ = spawn_future(
+                "Triangular arbitrage executor",
+                SpawnFutureFlags::STOP_BY_TOKEN,
+                executor.run(service.subscribe()),
+            );
+        }
+
+        let _ = spawn_future(
+            "Triangular arbitrage events handling",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            service.clone().handle_events(events_receiver),
+        );
+
+        Ok(service)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TriangularOpportunity> {
+        self.opportunities_sender.subscribe()
+    }
+
+    async fn handle_events(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Result<()> {
+        let exchange_account_id = self.exchange.exchange_account_id;
+        let mut snapshots = LocalSnapshotsService::default();
+
+        loop {
+            let event = match events_receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Triangular arbitrage service skipped {skipped} exchange events");
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            let order_book_event = match event {
+                ExchangeEvent::OrderBookEvent(order_book_event) => order_book_event,
+                _ => continue,
+            };
+            if order_book_event.exchange_account_id != exchange_account_id {
+                continue;
+            }
+
+            let currency_pair = order_book_event.currency_pair;
+            if snapshots.update(order_book_event).is_none()
+                || self.opportunities_sender.receiver_count() == 0
+            {
+                continue;
+            }
+
+            let taker_fee_rate = self.exchange.get_commission().taker.fee.percent_to_rate();
+            let now = now();
+            for route in self.routes.iter().filter(|x| x.contains(currency_pair)) {
+                let opportunity =
+                    match route.evaluate(exchange_account_id, &snapshots, taker_fee_rate, now) {
+                        Some(opportunity) if opportunity.profit_rate >= self.min_profit_rate => {
+                            opportunity
+                        }
+                        _ => continue,
+                    };
+
+                let _ = self.opportunities_sender.send(opportunity);
+            }
+        }
+    }
+}
+
+/// Executes round trips of opportunities by market orders one at a time
+struct TriangularArbitrageExecutor {
+    exchange: Arc<Exchange>,
+    exchange_blocker: Arc<ExchangeBlocker>,
+    settings: TriangularArbitrageExecutorSettings,
+    cancellation_token: CancellationToken,
+}
+
+impl TriangularArbitrageExecutor {
+    async fn run(
+        self,
+        mut opportunities_receiver: broadcast::Receiver<TriangularOpportunity>,
+    ) -> Result<()> {
+        let mut last_finish_time = now();
+
+        loop {
+            let opportunity = match opportunities_receiver.recv().await {
+                Ok(opportunity) => opportunity,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            // opportunities received during previous round trip are outdated
+            if opportunity.time < last_finish_time
+                || self
+                    .exchange_blocker
+                    .is_blocked(opportunity.exchange_account_id)
+            {
+                continue;
+            }
+
+            if let Err(error) = self.execute(&opportunity).await {
+                log::error!("Triangular arbitrage round trip failed: {error:?}");
+            }
+
+            tokio::time::sleep(Duration::from_millis(self.settings.cooldown_ms)).await;
+            last_finish_time = now();
+        }
+    }
+
+    /// Balances of all legs are reserved before the first order is sent, so round trip honours
+    /// balance reservations, spending limits and capital allocations. If some leg fails after
+    /// the first one, received intermediate currency is converted back to start currency
+    async fn execute(&self, opportunity: &TriangularOpportunity) -> Result<()> {
+        let taker_fee_rate = self.exchange.get_commission().taker.fee.percent_to_rate();
+        let start_amount = opportunity
+            .max_start_amount
+            .min(self.settings.max_start_amount);
+        log::info!(
+            "Executing triangular arbitrage round trip with {start_amount} {}: {opportunity:?}",
+            opportunity.start_currency_code
+        );
+
+        let leg_amounts = self.estimate_leg_amounts(opportunity, start_amount, taker_fee_rate)?;
+        let legs = &opportunity.legs;
+        let reservation_ids = self.exchange.reserve_three_orders_balance(
+            self.configuration_descriptor(),
+            [0, 1, 2].map(|i| {
+                (
+                    legs[i].currency_pair,
+                    legs[i].side,
+                    legs[i].price,
+                    leg_amounts[i],
+                )
+            }),
+        )?;
+
+        let mut input_amount = start_amount;
+        for (index, leg) in legs.iter().enumerate() {
+            let leg_result = self
+                .execute_leg(
+                    leg,
+                    input_amount,
+                    leg_amounts[index],
+                    reservation_ids[index],
+                    taker_fee_rate,
+                )
+                .await;
+
+            match leg_result {
+                Ok(output_amount) => input_amount = output_amount,
+                Err(err) => {
+                    for &reservation_id in &reservation_ids[index + 1..] {
+                        self.exchange.release_unused_reservation(reservation_id);
+                    }
+                    if index > 0 {
+                        self.flatten(opportunity, index, input_amount)
+                            .await
+                            .unwrap_or_else(|flatten_err| {
+                                log::error!(
+                                    "Unable to flatten intermediate currency of triangular arbitrage round trip: {flatten_err:?}"
+                                )
+                            });
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        log::info!(
+            "Triangular arbitrage round trip finished with {input_amount} {}",
+            opportunity.start_currency_code
+        );
+
+        Ok(())
+    }
+
+    /// Amounts of leg orders by prices of opportunity, which are reserved before round trip
+    fn estimate_leg_amounts(
+        &self,
+        opportunity: &TriangularOpportunity,
+        start_amount: Amount,
+        taker_fee_rate: Decimal,
+    ) -> Result<[Amount; 3]> {
+        let mut input_amount = start_amount;
+        let mut leg_amounts = [dec!(0); 3];
+        for (index, leg) in opportunity.legs.iter().enumerate() {
+            let amount = self.leg_amount(leg, input_amount)?;
+            leg_amounts[index] = amount;
+            input_amount = match leg.side {
+                OrderSide::Sell => amount * leg.price,
+                OrderSide::Buy => amount,
+            } * (dec!(1) - taker_fee_rate);
+        }
+
+        Ok(leg_amounts)
+    }
+
+    fn leg_amount(&self, leg: &OpportunityLeg, input_amount: Amount) -> Result<Amount> {
+        let symbol = self.exchange.get_symbol(leg.currency_pair)?;
+        let amount = match leg.side {
+            OrderSide::Sell => input_amount,
+            OrderSide::Buy => input_amount / leg.price,
+        };
+        let amount = symbol.amount_round(amount, Round::Floor);
+        if amount <= dec!(0) {
+            bail!("Amount for {} is too small", leg.currency_pair);
+        }
+
+        Ok(amount)
+    }
+
+    /// Executes leg by market order within reserved amount and returns received amount of
+    /// the next currency after taker fee. Reservation is released when order is finished
+    async fn execute_leg(
+        &self,
+        leg: &OpportunityLeg,
+        input_amount: Amount,
+        reserved_amount: Amount,
+        reservation_id: ReservationId,
+        taker_fee_rate: Decimal,
+    ) -> Result<Amount> {
+        let amount = match self.leg_amount(leg, input_amount) {
+            Ok(amount) => amount.min(reserved_amount),
+            Err(err) => {
+                self.exchange.release_unused_reservation(reservation_id);
+                return Err(err);
+            }
+        };
+
+        let header = OrderHeader::new(
+            ClientOrderId::unique_id(),
+            now(),
+            self.exchange.exchange_account_id,
+            leg.currency_pair,
+            OrderType::Market,
+            leg.side,
+            amount,
+            OrderExecutionType::None,
+            Some(reservation_id),
+            None,
+            STRATEGY_NAME.to_owned(),
+        );
+        let order = match self
+            .exchange
+            .create_order(
+                OrderCreating {
+                    header: header.clone(),
+                    price: leg.price,
+                },
+                None,
+                self.cancellation_token.clone(),
+            )
+            .await
+        {
+            Ok(order) => order,
+            Err(err) => {
+                self.exchange.release_order_reservation(&header);
+                return Err(
+                    err.context(format!("Failed to create order for {}", leg.currency_pair))
+                );
+            }
+        };
+
+        let order = self
+            .exchange
+            .clone()
+            .wait_order_finish(&order, None, self.cancellation_token.clone())
+            .await;
+        self.exchange.release_order_reservation(&header);
+        let order = order?;
+
+        let (filled_amount, filled_cost) = order.fn_ref(|x| {
+            let filled_cost: Decimal = x.fills.fills.iter().map(|f| f.price() * f.amount()).sum();
+            (x.fills.filled_amount, filled_cost)
+        });
+        if filled_amount <= dec!(0) {
+            bail!(
+                "Order {} for {} isn't filled",
+                order.client_order_id(),
+                leg.currency_pair
+            );
+        }
+
+        Ok(match leg.side {
+            OrderSide::Sell => filled_cost,
+            OrderSide::Buy => filled_amount,
+        } * (dec!(1) - taker_fee_rate))
+    }
+
+    /// Converts `held_amount` of currency received before leg `failed_index` back to start currency
+    /// by the currency pair of triangle which connects them
+    async fn flatten(
+        &self,
+        opportunity: &TriangularOpportunity,
+        failed_index: usize,
+        held_amount: Amount,
+    ) -> Result<()> {
+        let received_by = &opportunity.legs[failed_index - 1];
+        let codes = received_by.currency_pair.to_codes();
+        let held_currency_code = match received_by.side {
+            OrderSide::Buy => codes.base,
+            OrderSide::Sell => codes.quote,
+        };
+
+        // the first leg connects the second currency with start one, the last leg connects the third one
+        let connecting = match failed_index {
+            1 => &opportunity.legs[0],
+            _ => &opportunity.legs[2],
+        };
+        let (unwind_leg, _) = TriangleLeg::convert(connecting.currency_pair, held_currency_code);
+        let unwind_leg = OpportunityLeg {
+            currency_pair: unwind_leg.currency_pair,
+            side: unwind_leg.side,
+            price: connecting.price,
+        };
+        log::warn!(
+            "Flattening {held_amount} {held_currency_code} after failed leg {failed_index} of triangular arbitrage by {unwind_leg:?}"
+        );
+
+        let amount = self.leg_amount(&unwind_leg, held_amount)?;
+        let reservation_id = self.exchange.reserve_order_balance(
+            self.configuration_descriptor(),
+            unwind_leg.currency_pair,
+            unwind_leg.side,
+            unwind_leg.price,
+            amount,
+        )?;
+        let taker_fee_rate = self.exchange.get_commission().taker.fee.percent_to_rate();
+        let start_amount = self
+            .execute_leg(
+                &unwind_leg,
+                held_amount,
+                amount,
+                reservation_id,
+                taker_fee_rate,
+            )
+            .await?;
+
+        log::info!(
+            "Intermediate currency is flattened to {start_amount} {}",
+            opportunity.start_currency_code
+        );
+
+        Ok(())
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new(
+            ServiceName::new(STRATEGY_NAME),
+            ServiceConfigurationKey::new(self.exchange.exchange_account_id.to_string().as_str()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book::event::{EventType, OrderBookEvent};
+    use crate::order_book::order_book_data::OrderBookData;
+    use crate::order_book_data;
+    use chrono::Utc;
+
+    fn currency_pair(base: &str, quote: &str) -> CurrencyPair {
+        CurrencyPair::from_codes(base.into(), quote.into())
+    }
+
+    fn triangle() -> TriangleSettings {
+        TriangleSettings {
+            start_currency_code: "usdt".into(),
+            currency_pairs: vec![
+                currency_pair("btc", "usdt"),
+                currency_pair("eth", "btc"),
+                currency_pair("eth", "usdt"),
+            ],
+        }
+    }
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", 0)
+    }
+
+    fn add_snapshot(
+        snapshots: &mut LocalSnapshotsService,
+        currency_pair: CurrencyPair,
+        data: OrderBookData,
+    ) {
+        let _ = snapshots.update(OrderBookEvent::new(
+            Utc::now(),
+            exchange_account_id(),
+            currency_pair,
+            String::new(),
+            EventType::Snapshot,
+            Arc::new(data),
+        ));
+    }
+
+    #[test]
+    pub fn routes_from_settings() {
+        let routes = TriangleRoute::from_settings(&triangle()).expect("in test");
+
+        assert_eq!(
+            routes[0].legs,
+            [
+                TriangleLeg {
+                    currency_pair: currency_pair("btc", "usdt"),
+                    side: OrderSide::Buy
+                },
+                TriangleLeg {
+                    currency_pair: currency_pair("eth", "btc"),
+                    side: OrderSide::Buy
+                },
+                TriangleLeg {
+                    currency_pair: currency_pair("eth", "usdt"),
+                    side: OrderSide::Sell
+                },
+            ]
+        );
+        assert_eq!(
+            routes[1].legs,
+            [
+                TriangleLeg {
+                    currency_pair: currency_pair("eth", "usdt"),
+                    side: OrderSide::Buy
+                },
+                TriangleLeg {
+                    currency_pair: currency_pair("eth", "btc"),
+                    side: OrderSide::Sell
+                },
+                TriangleLeg {
+                    currency_pair: currency_pair("btc", "usdt"),
+                    side: OrderSide::Sell
+                },
+            ]
+        );
+    }
+
+    #[test]
+    pub fn invalid_triangle() {
+        let mut settings = triangle();
+        settings.currency_pairs[2] = currency_pair("xrp", "usdt");
+        assert!(TriangleRoute::from_settings(&settings).is_err());
+
+        let mut settings = triangle();
+        settings.start_currency_code = "xrp".into();
+        assert!(TriangleRoute::from_settings(&settings).is_err());
+    }
+
+    #[test]
+    pub fn evaluate_round_trip() {
+        let mut snapshots = LocalSnapshotsService::default();
+        add_snapshot(
+            &mut snapshots,
+            currency_pair("btc", "usdt"),
+            order_book_data![
+                dec!(20000) => dec!(1),
+                ;
+                dec!(19990) => dec!(1),
+            ],
+        );
+        add_snapshot(
+            &mut snapshots,
+            currency_pair("eth", "btc"),
+            order_book_data![
+                dec!(0.05) => dec!(10),
+                ;
+                dec!(0.049) => dec!(10),
+            ],
+        );
+        add_snapshot(
+            &mut snapshots,
+            currency_pair("eth", "usdt"),
+            order_book_data![
+                dec!(1010) => dec!(2),
+                ;
+                dec!(1005) => dec!(2),
+            ],
+        );
+
+        let routes = TriangleRoute::from_settings(&triangle()).expect("in test");
+        let opportunity = routes[0]
+            .evaluate(exchange_account_id(), &snapshots, dec!(0), Utc::now())
+            .expect("in test");
+
+        // 1 usdt -> 1/20000 btc -> 1/1000 eth -> 1.005 usdt
+        assert_eq!(opportunity.profit_rate, dec!(0.005));
+        // limited by 2 eth on the last leg: 2 * 1000 usdt
+        assert_eq!(opportunity.max_start_amount, dec!(2000));
+
+        let with_fees = routes[0]
+            .evaluate(exchange_account_id(), &snapshots, dec!(0.001), Utc::now())
+            .expect("in test");
+        assert!(with_fees.profit_rate < dec!(0.003));
+    }
+}
@@ -0,0 +1,687 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::balance::manager::balance_manager::BalanceManager;
+use crate::database::kv_store::{KeyValueStore, NamespacedStore};
+use crate::exchanges::common::{Amount, CurrencyCode, ExchangeAccountId, Price};
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::Symbol;
+use crate::infrastructure::spawn_future;
+use crate::math::ConvertPercentToRate;
+use crate::misc::time::time_manager;
+use crate::orders::order::{OrderCreating, OrderSide};
+use crate::services::index_price::IndexPriceService;
+use crate::services::volatility::{VolatilityEstimator, VolatilitySnapshot};
+use crate::settings::{PriceShockSettings, RiskWarmUpPolicy, ValueAtRiskSettings};
+
+const SAMPLES_NAMESPACE: &str = "value_at_risk";
+const SAMPLES_KEY: &str = "samples";
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum ValueAtRiskError {
+    #[error("value at risk isn't available: {0}")]
+    Unavailable(String),
+    #[error("value at risk isn't available: {samples_count} of {min_samples} returns are sampled")]
+    WarmingUp {
+        samples_count: usize,
+        min_samples: usize,
+    },
+    #[error(
+        "marginal value at risk {marginal_var} of order would exceed limit {max_marginal_var}"
+    )]
+    MaxMarginalVarExceeded {
+        marginal_var: Amount,
+        max_marginal_var: Amount,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetRisk {
+    pub currency_code: CurrencyCode,
+    /// Balances and derivative positions of currency on all exchange accounts
+    pub exposure: Option<Amount>,
+    pub price: Option<Price>,
+    /// Volatility of price over horizon of value at risk
+    pub volatility: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StressResult {
+    pub name: String,
+    /// Profit or loss of current exposures if prices change by scenario
+    pub pnl: Option<Amount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskReport {
+    /// Quote currency of index prices which risk is valued in
+    pub valuation_currency: CurrencyCode,
+    pub confidence: Percent,
+    pub horizon_ms: u64,
+    pub samples_count: usize,
+    /// Loss over horizon which isn't exceeded with confidence level
+    pub value_at_risk: Option<Amount>,
+    pub unavailability_reason: Option<String>,
+    pub assets: Vec<AssetRisk>,
+    pub stress: Vec<StressResult>,
+}
+
+/// Estimation of sampled returns which is persisted to continue sampling after restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PersistedSamples {
+    assets: Vec<CurrencyCode>,
+    saved_at: DateTime,
+    estimation: VolatilitySnapshot,
+}
+
+/// Exposures valued at the same time and value at risk of them
+struct RiskSnapshot {
+    created_at: Instant,
+    prices: Vec<Option<Price>>,
+    exposures: Vec<Option<Amount>>,
+    value_at_risk: Result<Amount, ValueAtRiskError>,
+}
+
+/// Quantile of standard normal distribution by rational approximation of P. J. Acklam
+/// with relative error less than 1.15e-9
+pub fn normal_quantile(probability: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    if probability < LOW {
+        tail((-2.0 * probability.ln()).sqrt())
+    } else if probability > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - probability).ln()).sqrt())
+    } else {
+        let q = probability - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// Variance-covariance value at risk of exposures valued in valuation currency. `covariance` is
+/// covariance of returns per sampling interval, `horizon_scale` scales it to horizon of value at risk
+pub fn parametric_var(
+    values: &[f64],
+    covariance: &[Vec<f64>],
+    z_score: f64,
+    horizon_scale: f64,
+) -> f64 {
+    let variance = values
+        .iter()
+        .enumerate()
+        .map(|(i, value_i)| {
+            values
+                .iter()
+                .enumerate()
+                .map(|(j, value_j)| value_i * value_j * covariance[i][j])
+                .sum::<f64>()
+        })
+        .sum::<f64>();
+
+    z_score * variance.max(0.0).sqrt() * horizon_scale
+}
+
+/// Profit or loss of exposures valued in valuation currency if prices change by `shocks`
+pub fn stress_pnl(values: &[(CurrencyCode, Amount)], shocks: &[PriceShockSettings]) -> Amount {
+    shocks
+        .iter()
+        .map(|shock| {
+            values
+                .iter()
+                .filter(|(currency_code, _)| *currency_code == shock.currency_code)
+                .map(|(_, value)| value * shock.change.percent_to_rate())
+                .sum::<Amount>()
+        })
+        .sum()
+}
+
+/// Parametric value at risk and stress scenarios over exposures of configured currencies on all
+/// exchange accounts. Exposures are valued by index prices, volatilities and correlations are
+/// estimated by index prices sampled periodically and persisted across restarts. Optionally,
+/// orders increasing value at risk more than limit are rejected before submitting
+pub struct ValueAtRiskService {
+    settings: ValueAtRiskSettings,
+    valuation_currency: CurrencyCode,
+    z_score: f64,
+    horizon_scale: f64,
+    index_prices: Arc<IndexPriceService>,
+    balance_manager: Arc<Mutex<BalanceManager>>,
+    /// Derivative symbols which positions are exposures of asset with the same index
+    derivatives: Vec<Vec<(ExchangeAccountId, Arc<Symbol>)>>,
+    estimator: Mutex<VolatilityEstimator>,
+    snapshot: Mutex<Option<Arc<RiskSnapshot>>>,
+    samples_store: NamespacedStore,
+}
+
+impl ValueAtRiskService {
+    pub fn start(
+        settings: &ValueAtRiskSettings,
+        index_prices: Arc<IndexPriceService>,
+        balance_manager: Arc<Mutex<BalanceManager>>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        kv_store: &Arc<KeyValueStore>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<Self>> {
+        let valuation_currency = match settings
+            .assets
+            .iter()
+            .map(|x| x.index_currency_pair.to_codes().quote)
+            .unique()
+            .collect_vec()[..]
+        {
+            [valuation_currency] => valuation_currency,
+            [] => bail!("Assets of value at risk aren't set"),
+            _ => bail!("Index prices of value at risk assets should have the same quote currency"),
+        };
+        if settings.confidence <= dec!(50) || settings.confidence >= dec!(100) {
+            bail!(
+                "Confidence {} of value at risk should be between 50% and 100%",
+                settings.confidence
+            );
+        }
+        let decay = match settings.decay.to_f64() {
+            Some(decay) if decay > 0.0 && decay < 1.0 => decay,
+            _ => bail!("Decay {} should be between 0 and 1", settings.decay),
+        };
+        if settings.sampling_interval_ms == 0 {
+            bail!("Sampling interval of value at risk should be positive");
+        }
+        for shock in settings.scenarios.iter().flat_map(|x| &x.shocks) {
+            if !settings
+                .assets
+                .iter()
+                .any(|x| x.currency_code == shock.currency_code)
+            {
+                bail!(
+                    "Stress scenario shock of {} isn't value at risk asset",
+                    shock.currency_code
+                );
+            }
+        }
+
+        let derivatives = settings
+            .assets
+            .iter()
+            .map(|asset| {
+                exchanges
+                    .iter()
+                    .flat_map(|exchange| {
+                        exchange
+                            .symbols
+                            .iter()
+                            .filter(|x| {
+                                x.is_derivative() && x.base_currency_code() == asset.currency_code
+                            })
+                            .map(|x| (exchange.exchange_account_id, x.value().clone()))
+                            .collect_vec()
+                    })
+                    .collect_vec()
+            })
+            .collect();
+
+        let confidence = settings
+            .confidence
+            .percent_to_rate()
+            .to_f64()
+            .unwrap_or(0.99);
+        let service = Arc::new(ValueAtRiskService {
+            settings: settings.clone(),
+            valuation_currency,
+            z_score: normal_quantile(confidence),
+            horizon_scale: (settings.horizon_ms as f64 / settings.sampling_interval_ms as f64)
+                .sqrt(),
+            index_prices,
+            balance_manager,
+            derivatives,
+            estimator: Mutex::new(VolatilityEstimator::new(settings.assets.len(), decay)),
+            snapshot: Mutex::new(None),
+            samples_store: kv_store.namespace(SAMPLES_NAMESPACE),
+        });
+
+        let _ = spawn_future(
+            "Value at risk prices sampling",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            service.clone().sample_periodically(cancellation_token),
+        );
+
+        Ok(service)
+    }
+
+    pub fn is_limit_enabled(&self) -> bool {
+        self.settings.max_marginal_var.is_some()
+    }
+
+    async fn sample_periodically(
+        self: Arc<Self>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        self.restore_samples().await;
+
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(self.settings.sampling_interval_ms));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            }
+
+            let prices = self.prices();
+            let estimation = {
+                let mut estimator = self.estimator.lock();
+                estimator.register_prices(&prices);
+                estimator.snapshot()
+            };
+            *self.snapshot.lock() = None;
+            self.persist_samples(estimation).await;
+        }
+    }
+
+    fn asset_currencies(&self) -> Vec<CurrencyCode> {
+        self.settings
+            .assets
+            .iter()
+            .map(|x| x.currency_code)
+            .collect()
+    }
+
+    /// Continues estimation persisted before restart unless it's outdated or for other assets.
+    /// Otherwise value at risk is warming up until `min_samples` returns are sampled
+    async fn restore_samples(&self) {
+        let persisted = match self
+            .samples_store
+            .get::<PersistedSamples>(SAMPLES_KEY)
+            .await
+        {
+            Ok(Some(persisted)) => persisted,
+            Ok(None) => return,
+            Err(err) => {
+                log::warn!("Unable to load persisted samples of value at risk: {err:?}");
+                return;
+            }
+        };
+
+        if persisted.assets != self.asset_currencies() {
+            log::info!("Persisted samples of value at risk are for other assets and are ignored");
+            return;
+        }
+
+        let age_ms = (time_manager::now() - persisted.saved_at).num_milliseconds();
+        if age_ms > self.settings.max_restored_samples_age_ms as i64 {
+            log::info!(
+                "Persisted samples of value at risk are outdated by {age_ms}ms and are ignored"
+            );
+            return;
+        }
+
+        let samples_count = persisted.estimation.samples_count;
+        if self.estimator.lock().restore(persisted.estimation) {
+            *self.snapshot.lock() = None;
+            log::info!("{samples_count} sampled returns of value at risk are restored");
+        }
+    }
+
+    async fn persist_samples(&self, estimation: VolatilitySnapshot) {
+        let persisted = PersistedSamples {
+            assets: self.asset_currencies(),
+            saved_at: time_manager::now(),
+            estimation,
+        };
+        if let Err(err) = self.samples_store.set(SAMPLES_KEY, &persisted).await {
+            log::warn!("Unable to persist samples of value at risk: {err:?}");
+        }
+    }
+
+    fn prices(&self) -> Vec<Option<Price>> {
+        self.settings
+            .assets
+            .iter()
+            .map(|x| {
+                self.index_prices
+                    .get_index_price(x.index_currency_pair)
+                    .map(|x| x.price)
+            })
+            .collect()
+    }
+
+    fn exposures(&self, prices: &[Option<Price>]) -> Vec<Option<Amount>> {
+        let balance_manager = self.balance_manager.lock();
+        let balances = balance_manager
+            .get_balances()
+            .balances_by_exchange_id
+            .unwrap_or_default();
+
+        self.settings
+            .assets
+            .iter()
+            .zip(&self.derivatives)
+            .zip(prices)
+            .map(|((asset, derivatives), price)| {
+                let mut exposure = balances
+                    .values()
+                    .filter_map(|x| x.get(&asset.currency_code))
+                    .sum::<Amount>();
+
+                for (exchange_account_id, symbol) in derivatives {
+                    let position = balance_manager.get_position(
+                        *exchange_account_id,
+                        symbol.currency_pair(),
+                        OrderSide::Buy,
+                    );
+                    exposure += base_amount(symbol, position, *price)?;
+                }
+
+                Some(exposure)
+            })
+            .collect()
+    }
+
+    /// Current exposures with value at risk of them. They are cached during `exposures_cache_ms`
+    /// and until the next sample, so order checks don't lock balances on every order
+    fn risk_snapshot(&self) -> Arc<RiskSnapshot> {
+        let cache_duration = Duration::from_millis(self.settings.exposures_cache_ms);
+        let mut snapshot = self.snapshot.lock();
+        if let Some(snapshot) = snapshot
+            .as_ref()
+            .filter(|x| x.created_at.elapsed() < cache_duration)
+        {
+            return snapshot.clone();
+        }
+
+        let prices = self.prices();
+        let exposures = self.exposures(&prices);
+        let value_at_risk = self.value_at_risk(&exposures, &prices);
+        let new_snapshot = Arc::new(RiskSnapshot {
+            created_at: Instant::now(),
+            prices,
+            exposures,
+            value_at_risk,
+        });
+        *snapshot = Some(new_snapshot.clone());
+        new_snapshot
+    }
+
+    fn value_at_risk(
+        &self,
+        exposures: &[Option<Amount>],
+        prices: &[Option<Price>],
+    ) -> Result<Amount, ValueAtRiskError> {
+        let estimator = self.estimator.lock();
+        if estimator.samples_count() < self.settings.min_samples {
+            return Err(ValueAtRiskError::WarmingUp {
+                samples_count: estimator.samples_count(),
+                min_samples: self.settings.min_samples,
+            });
+        }
+
+        let values = self.values(exposures, prices)?;
+        let values = values
+            .iter()
+            .map(|(_, value)| value.to_f64().unwrap_or_default())
+            .collect_vec();
+        let value_at_risk = parametric_var(
+            &values,
+            estimator.covariance(),
+            self.z_score,
+            self.horizon_scale,
+        );
+
+        Decimal::from_f64(value_at_risk).ok_or_else(|| {
+            ValueAtRiskError::Unavailable(format!("value at risk {value_at_risk} is invalid"))
+        })
+    }
+
+    /// Exposures valued in valuation currency
+    fn values(
+        &self,
+        exposures: &[Option<Amount>],
+        prices: &[Option<Price>],
+    ) -> Result<Vec<(CurrencyCode, Amount)>, ValueAtRiskError> {
+        self.settings
+            .assets
+            .iter()
+            .zip(exposures.iter().zip(prices))
+            .map(|(asset, (exposure, price))| match (exposure, price) {
+                (Some(exposure), Some(price)) => Ok((asset.currency_code, exposure * price)),
+                _ => Err(ValueAtRiskError::Unavailable(format!(
+                    "index price of {} isn't available",
+                    asset.currency_code
+                ))),
+            })
+            .collect()
+    }
+
+    pub fn report(&self) -> RiskReport {
+        let snapshot = self.risk_snapshot();
+        let values = self.values(&snapshot.exposures, &snapshot.prices).ok();
+
+        let estimator = self.estimator.lock();
+        let assets = self
+            .settings
+            .assets
+            .iter()
+            .enumerate()
+            .map(|(index, asset)| AssetRisk {
+                currency_code: asset.currency_code,
+                exposure: snapshot.exposures[index],
+                price: snapshot.prices[index],
+                volatility: (estimator.samples_count() > 0)
+                    .then(|| Decimal::from_f64(estimator.volatility(index) * self.horizon_scale))
+                    .flatten(),
+            })
+            .collect();
+        let stress = self
+            .settings
+            .scenarios
+            .iter()
+            .map(|scenario| StressResult {
+                name: scenario.name.clone(),
+                pnl: values.as_ref().map(|x| stress_pnl(x, &scenario.shocks)),
+            })
+            .collect();
+
+        RiskReport {
+            valuation_currency: self.valuation_currency,
+            confidence: self.settings.confidence,
+            horizon_ms: self.settings.horizon_ms,
+            samples_count: estimator.samples_count(),
+            value_at_risk: snapshot.value_at_risk.as_ref().ok().copied(),
+            unavailability_reason: snapshot.value_at_risk.as_ref().err().map(|x| x.to_string()),
+            assets,
+            stress,
+        }
+    }
+
+    /// Profit or loss of current exposures if prices change by custom scenario
+    pub fn stress_test(&self, shocks: &[PriceShockSettings]) -> Result<Amount, ValueAtRiskError> {
+        let snapshot = self.risk_snapshot();
+        let values = self.values(&snapshot.exposures, &snapshot.prices)?;
+        Ok(stress_pnl(&values, shocks))
+    }
+
+    /// Checks that value at risk wouldn't increase more than limit if order is filled.
+    /// Orders are rejected while value at risk isn't available, except reduce-only orders.
+    /// While returns are being sampled orders are checked according to `warm_up_policy`
+    pub fn check_order(
+        &self,
+        order: &OrderCreating,
+        symbol: &Symbol,
+    ) -> Result<(), ValueAtRiskError> {
+        let max_marginal_var = match self.settings.max_marginal_var {
+            Some(max_marginal_var) => max_marginal_var,
+            None => return Ok(()),
+        };
+        if order.header.reduce_only {
+            return Ok(());
+        }
+
+        let asset_index = |currency_code| {
+            self.settings
+                .assets
+                .iter()
+                .position(|x| x.currency_code == currency_code)
+        };
+        let base_index = asset_index(symbol.base_currency_code());
+        let quote_index = match symbol.is_derivative() {
+            true => None,
+            false => asset_index(symbol.quote_currency_code()),
+        };
+        if base_index.is_none() && quote_index.is_none() {
+            return Ok(());
+        }
+
+        let order_price = (order.price > dec!(0)).then_some(order.price);
+        let base_amount =
+            base_amount(symbol, order.header.amount, order_price).ok_or_else(|| {
+                ValueAtRiskError::Unavailable(format!(
+                    "price of order {} isn't set",
+                    order.header.client_order_id
+                ))
+            })?;
+        let base_change = match order.header.side {
+            OrderSide::Buy => base_amount,
+            OrderSide::Sell => -base_amount,
+        };
+
+        let snapshot = self.risk_snapshot();
+        let value_at_risk = match &snapshot.value_at_risk {
+            Ok(value_at_risk) => *value_at_risk,
+            Err(ValueAtRiskError::WarmingUp { .. })
+                if self.settings.warm_up_policy == RiskWarmUpPolicy::Allow =>
+            {
+                return Ok(())
+            }
+            Err(err) => return Err(err.clone()),
+        };
+
+        let mut changed_exposures = snapshot.exposures.clone();
+        if let Some(index) = base_index {
+            changed_exposures[index] = changed_exposures[index].map(|x| x + base_change);
+        }
+        if let Some(index) = quote_index {
+            let quote_change = order_price.map(|price| -base_change * price);
+            changed_exposures[index] = changed_exposures[index]
+                .zip(quote_change)
+                .map(|(x, y)| x + y);
+        }
+
+        let marginal_var =
+            self.value_at_risk(&changed_exposures, &snapshot.prices)? - value_at_risk;
+        if marginal_var > max_marginal_var {
+            return Err(ValueAtRiskError::MaxMarginalVarExceeded {
+                marginal_var,
+                max_marginal_var,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Amount in base currency of symbol. Price is required for symbols with amount in quote currency
+fn base_amount(symbol: &Symbol, amount: Amount, price: Option<Price>) -> Option<Amount> {
+    if symbol.amount_currency_code == symbol.base_currency_code() {
+        return Some(amount);
+    }
+
+    let price = price.filter(|x| *x > dec!(0))?;
+    Some(symbol.convert_amount_from_amount_currency_code(
+        symbol.base_currency_code(),
+        amount,
+        price,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_quantiles() {
+        assert!(normal_quantile(0.5).abs() < 1e-9);
+        assert!((normal_quantile(0.95) - 1.644853627).abs() < 1e-6);
+        assert!((normal_quantile(0.99) - 2.326347874).abs() < 1e-6);
+        assert!((normal_quantile(0.01) + 2.326347874).abs() < 1e-6);
+    }
+
+    #[test]
+    fn value_at_risk_depends_on_correlation() {
+        let values = [100.0, -100.0];
+        let correlated = vec![vec![0.01, 0.01], vec![0.01, 0.01]];
+        let uncorrelated = vec![vec![0.01, 0.0], vec![0.0, 0.01]];
+
+        // long and short positions in perfectly correlated assets are hedged
+        assert!(parametric_var(&values, &correlated, 2.0, 1.0).abs() < 1e-9);
+        let expected = 2.0 * (2.0 * 100.0 * 100.0 * 0.01f64).sqrt() * 3.0;
+        assert!((parametric_var(&values, &uncorrelated, 2.0, 3.0) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stress_pnl_of_shocked_currencies() {
+        let values = [
+            ("btc".into(), dec!(50000)),
+            ("eth".into(), dec!(-10000)),
+            ("sol".into(), dec!(1000)),
+        ];
+        let shocks = [
+            PriceShockSettings {
+                currency_code: "btc".into(),
+                change: dec!(-20),
+            },
+            PriceShockSettings {
+                currency_code: "eth".into(),
+                change: dec!(-30),
+            },
+        ];
+
+        assert_eq!(stress_pnl(&values, &shocks), dec!(-7000));
+    }
+}
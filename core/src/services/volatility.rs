@@ -0,0 +1,150 @@
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::Price;
+
+/// Exponentially weighted estimation of volatilities and correlations of log returns of several
+/// assets which prices are sampled at the same fixed interval
+#[derive(Debug, Clone)]
+pub struct VolatilityEstimator {
+    decay: f64,
+    last_prices: Option<Vec<f64>>,
+    covariance: Vec<Vec<f64>>,
+    samples_count: usize,
+}
+
+/// Persistable state of estimation. Last prices aren't kept because return over restart gap
+/// wouldn't be sampled at the same interval
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VolatilitySnapshot {
+    pub covariance: Vec<Vec<f64>>,
+    pub samples_count: usize,
+}
+
+impl VolatilityEstimator {
+    pub fn new(assets_count: usize, decay: f64) -> Self {
+        VolatilityEstimator {
+            decay,
+            last_prices: None,
+            covariance: vec![vec![0.0; assets_count]; assets_count],
+            samples_count: 0,
+        }
+    }
+
+    /// Registers prices of all assets sampled at the same time. Sample is skipped if price of any
+    /// asset is unknown, so the next return is calculated over longer period
+    pub fn register_prices(&mut self, prices: &[Option<Price>]) {
+        let prices = match prices
+            .iter()
+            .map(|x| x.and_then(|x| x.to_f64()).filter(|x| *x > 0.0))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(prices) if prices.len() == self.covariance.len() => prices,
+            _ => return,
+        };
+
+        if let Some(last_prices) = &self.last_prices {
+            let returns = prices
+                .iter()
+                .zip(last_prices)
+                .map(|(price, last_price)| (price / last_price).ln())
+                .collect::<Vec<_>>();
+
+            let weight = match self.samples_count {
+                0 => 1.0,
+                _ => 1.0 - self.decay,
+            };
+            for (i, row) in self.covariance.iter_mut().enumerate() {
+                for (j, value) in row.iter_mut().enumerate() {
+                    *value = (1.0 - weight) * *value + weight * returns[i] * returns[j];
+                }
+            }
+            self.samples_count += 1;
+        }
+
+        self.last_prices = Some(prices);
+    }
+
+    pub fn snapshot(&self) -> VolatilitySnapshot {
+        VolatilitySnapshot {
+            covariance: self.covariance.clone(),
+            samples_count: self.samples_count,
+        }
+    }
+
+    /// Continues estimation from snapshot. Returns `false` if snapshot is for other count of assets
+    pub fn restore(&mut self, snapshot: VolatilitySnapshot) -> bool {
+        let assets_count = self.covariance.len();
+        if snapshot.covariance.len() != assets_count
+            || snapshot.covariance.iter().any(|x| x.len() != assets_count)
+        {
+            return false;
+        }
+
+        self.covariance = snapshot.covariance;
+        self.samples_count = snapshot.samples_count;
+        self.last_prices = None;
+        true
+    }
+
+    /// Count of registered returns
+    pub fn samples_count(&self) -> usize {
+        self.samples_count
+    }
+
+    /// Covariance of log returns per sampling interval
+    pub fn covariance(&self) -> &[Vec<f64>] {
+        &self.covariance
+    }
+
+    /// Standard deviation of log returns of asset per sampling interval
+    pub fn volatility(&self, asset_index: usize) -> f64 {
+        self.covariance[asset_index][asset_index].sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn covariance_is_estimated_by_returns() {
+        let mut estimator = VolatilityEstimator::new(2, 0.5);
+        estimator.register_prices(&[Some(dec!(100)), Some(dec!(10))]);
+        assert_eq!(estimator.samples_count(), 0);
+
+        // sample with unknown price is skipped
+        estimator.register_prices(&[Some(dec!(200)), None]);
+        assert_eq!(estimator.samples_count(), 0);
+
+        estimator.register_prices(&[Some(dec!(110)), Some(dec!(9))]);
+        let first_returns = [(1.1f64).ln(), (0.9f64).ln()];
+        assert_eq!(estimator.samples_count(), 1);
+        assert!((estimator.volatility(0) - first_returns[0].abs()).abs() < 1e-12);
+        assert!((estimator.covariance()[0][1] - first_returns[0] * first_returns[1]).abs() < 1e-12);
+
+        estimator.register_prices(&[Some(dec!(110)), Some(dec!(9))]);
+        assert_eq!(estimator.samples_count(), 2);
+        let expected_variance = 0.5 * first_returns[0] * first_returns[0];
+        assert!((estimator.covariance()[0][0] - expected_variance).abs() < 1e-12);
+    }
+
+    #[test]
+    fn estimation_is_continued_from_snapshot() {
+        let mut estimator = VolatilityEstimator::new(2, 0.5);
+        estimator.register_prices(&[Some(dec!(100)), Some(dec!(10))]);
+        estimator.register_prices(&[Some(dec!(110)), Some(dec!(9))]);
+
+        let mut restored = VolatilityEstimator::new(2, 0.5);
+        assert!(restored.restore(estimator.snapshot()));
+        assert_eq!(restored.samples_count(), 1);
+        assert_eq!(restored.covariance(), estimator.covariance());
+
+        // return over restart gap isn't registered
+        restored.register_prices(&[Some(dec!(200)), Some(dec!(20))]);
+        assert_eq!(restored.samples_count(), 1);
+
+        assert!(!VolatilityEstimator::new(3, 0.5).restore(estimator.snapshot()));
+    }
+}
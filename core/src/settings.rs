@@ -1,5 +1,14 @@
-use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId};
+use crate::exchanges::common::{
+    Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, ExchangeId, SpecificCurrencyPair,
+};
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::venue_selection::WeightedVenueScorer;
+use crate::service_configuration::configuration_descriptor::ServiceName;
+use crate::settings_values::{deserialize_decimal, deserialize_optional_decimal};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
 use std::path::PathBuf;
 
 pub trait BaseStrategySettings {
@@ -24,6 +33,478 @@ where
 pub struct CoreSettings {
     pub database: Option<DbSettings>,
     pub exchanges: Vec<ExchangeSettings>,
+    #[serde(default)]
+    pub strategy_spending_limits: Vec<StrategySpendingLimitSettings>,
+    #[serde(default)]
+    pub strategy_capital_allocations: Vec<StrategyCapitalAllocationSettings>,
+    #[serde(default)]
+    pub strategy_order_throttles: Vec<StrategyOrderThrottleSettings>,
+    #[serde(default)]
+    pub strategy_tick_budgets: Vec<StrategyTickBudgetSettings>,
+    #[serde(default)]
+    pub market_rollouts: Vec<MarketRolloutSettings>,
+    pub data_bridge: Option<DataBridgeSettings>,
+    pub order_book_recording: Option<OrderBookRecordingSettings>,
+    #[serde(default)]
+    pub currency_restrictions: CurrencyRestrictionsSettings,
+    #[serde(default)]
+    pub account_groups: Vec<AccountGroupSettings>,
+    #[serde(default)]
+    pub control_api_operators: Vec<ControlApiOperatorSettings>,
+    pub triangular_arbitrage: Option<TriangularArbitrageSettings>,
+    pub index_prices: Option<IndexPriceSettings>,
+    pub candles: Option<CandlesSettings>,
+    pub stale_market_data: Option<StaleMarketDataSettings>,
+    pub good_till_date: Option<GoodTillDateSettings>,
+    pub conditional_orders: Option<ConditionalOrdersSettings>,
+    pub margin_risk: Option<MarginRiskSettings>,
+    pub performance_attribution: Option<PerformanceAttributionSettings>,
+    pub internalization: Option<InternalizationSettings>,
+    pub failover: Option<FailoverSettings>,
+    pub rejection_analytics: Option<RejectionAnalyticsSettings>,
+    pub queue_position: Option<QueuePositionSettings>,
+    pub warm_up: Option<WarmUpSettings>,
+    pub funding_rates: Option<FundingRatesSettings>,
+    pub hedging: Option<HedgingSettings>,
+    pub api_key_health: Option<ApiKeyHealthSettings>,
+    pub audit_log: Option<AuditLogSettings>,
+    pub balance_anomaly: Option<BalanceAnomalySettings>,
+    pub rebalancing: Option<RebalancingSettings>,
+    pub withdrawal_approvals: Option<WithdrawalApprovalsSettings>,
+    pub value_at_risk: Option<ValueAtRiskSettings>,
+    pub news_restrictions: Option<NewsRestrictionsSettings>,
+    /// Connectors receive public market data only: credentials aren't required
+    /// and all trading capabilities are disabled
+    #[serde(default)]
+    pub market_data_only: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AccountRouting {
+    #[default]
+    RoundRobin,
+    /// Account with the most requests available by rate limits is selected
+    RateBudget,
+    /// Account with the best recent order creation roundtrip and reject rate scored by `venue_scorer`
+    VenueScore,
+}
+
+/// Accounts on the same exchange which orders are routed across, e.g. to spread rate limits
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AccountGroupSettings {
+    pub name: String,
+    pub exchange_account_ids: Vec<ExchangeAccountId>,
+    #[serde(default)]
+    pub routing: AccountRouting,
+    /// Weights of `VenueScore` routing
+    #[serde(default)]
+    pub venue_scorer: WeightedVenueScorer,
+}
+
+/// Global allow and deny lists of currencies and currency pairs enforced on every order creation.
+/// Empty allow list means that everything not denied is allowed
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CurrencyRestrictionsSettings {
+    #[serde(default)]
+    pub allowed_currencies: Vec<CurrencyCode>,
+    #[serde(default)]
+    pub denied_currencies: Vec<CurrencyCode>,
+    #[serde(default)]
+    pub allowed_currency_pairs: Vec<CurrencyPair>,
+    #[serde(default)]
+    pub denied_currency_pairs: Vec<CurrencyPair>,
+}
+
+/// Detection of cross rate arbitrage on triangles of currency pairs of one exchange account
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TriangularArbitrageSettings {
+    pub exchange_account_id: ExchangeAccountId,
+    pub triangles: Vec<TriangleSettings>,
+    /// Minimal round trip profit after taker fees for emitting opportunity
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub min_profit: Percent,
+    /// Opportunities are executed by built-in executor if it is set
+    pub executor: Option<TriangularArbitrageExecutorSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TriangleSettings {
+    /// Currency which round trip starts and ends with
+    pub start_currency_code: CurrencyCode,
+    /// Exactly 3 currency pairs forming cycle of 3 currencies, e.g. BTC/USDT, ETH/BTC, ETH/USDT
+    pub currency_pairs: Vec<CurrencyPair>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TriangularArbitrageExecutorSettings {
+    /// Cap of start currency amount spent on one round trip
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_start_amount: Amount,
+    /// Minimal pause between round trips
+    pub cooldown_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GoodTillDateSettings {
+    /// File where emulated expirations of good-till-date orders are saved to survive engine restarts
+    pub state_file: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ConditionalOrdersSettings {
+    /// File where conditional orders are saved to survive engine restarts
+    pub state_file: PathBuf,
+}
+
+/// Detection of balance changes reported by exchange which don't match balance changes by fills
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BalanceAnomalySettings {
+    /// Max difference between balance changes reported by exchange and expected by fills
+    /// in percents of balance
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub tolerance: Percent,
+    /// Count of consecutive balance updates with discrepancy before anomaly is reported,
+    /// so fills received after balance update don't lead to false alerts
+    pub confirmation_updates: u32,
+    /// Markets of anomalous currency are in reduce-only mode until anomaly is resolved
+    #[serde(default)]
+    pub reduce_only_on_anomaly: bool,
+}
+
+/// Risk checks of margin trading accounts by margin state polled from exchange
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MarginRiskSettings {
+    /// Orders which would push initial margin usage above this share of margin balance are rejected
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_margin_usage: Percent,
+    /// Maintenance margin ratio at which rising liquidation risk is alerted
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub elevated_risk_maintenance_ratio: Percent,
+    /// Maintenance margin ratio at which liquidation risk is alerted as high
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub high_risk_maintenance_ratio: Percent,
+    pub poll_interval_ms: u64,
+    /// Orders increasing margin are rejected if margin state is older than this
+    #[serde(default = "default_max_margin_info_age_ms")]
+    pub max_margin_info_age_ms: u64,
+    /// Engine switches to reduce-only mode while liquidation risk of any account is high
+    #[serde(default)]
+    pub reduce_only_on_high_risk: bool,
+}
+
+fn default_max_margin_info_age_ms() -> u64 {
+    60_000
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ReportPeriod {
+    Hourly,
+    Daily,
+}
+
+/// Periodic reports decomposing PnL of strategies per market into spread capture, inventory moves,
+/// fees and funding
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PerformanceAttributionSettings {
+    pub report_period: ReportPeriod,
+}
+
+/// Crossing of opposite orders of different strategies on the same market at middle price
+/// instead of sending both of them to exchange
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct InternalizationSettings {
+    /// How long order waits for internal counterparty before its rest is sent to exchange
+    pub max_wait_ms: u64,
+}
+
+/// Warm standby: instances with the same config compete for leadership lease stored in shared file.
+/// Only leader trades, standby takes over when lease of leader expires
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FailoverSettings {
+    /// Unique id of engine instance
+    pub instance_id: String,
+    pub lease_file: PathBuf,
+    pub lease_duration_ms: u64,
+    /// Should be several times less than `lease_duration_ms`
+    pub renew_interval_ms: u64,
+}
+
+/// Automatic adjustment of quoting when orders of market are rejected by exchange too often.
+/// Adjustment is relaxed step by step when there are no rejections during window
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RejectionAnalyticsSettings {
+    /// Rejections older than window aren't counted
+    pub window_ms: u64,
+    /// Count of rejections with the same reason within window which triggers adjustment step
+    pub threshold: usize,
+    /// Rejections are only collected to statistics if not set
+    #[serde(default)]
+    pub auto_adjust: bool,
+    /// Quotes are moved away from market by this distance on every step caused by price filter rejections
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub price_collar_step: Percent,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_price_collar: Percent,
+    /// Max count of outer levels removed by rate limit and insufficient funds rejections
+    pub max_removed_levels: usize,
+}
+
+/// Strategies are activated only after order books of their markets are received, balances are loaded
+/// and clocks are synced
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WarmUpSettings {
+    pub timeout_ms: u64,
+    /// Max difference between local clock and exchange time of trades. Clock sync isn't checked if not set
+    pub max_clock_skew_ms: Option<u64>,
+    /// Strategies are activated on timeout even if some dependencies aren't ready, otherwise engine is stopped
+    #[serde(default)]
+    pub activate_on_timeout: bool,
+}
+
+/// Estimation of queue positions of resting maker orders
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct QueuePositionSettings {
+    /// Strategies are notified when amount ahead of order changes by this part of previously notified amount
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub min_change: Percent,
+}
+
+/// Polling of funding rates of perpetual markets which quoting models can lean on
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FundingRatesSettings {
+    pub markets: Vec<FundingMarketSettings>,
+    pub poll_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FundingMarketSettings {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+}
+
+/// Offsetting of spot inventory by perpetual futures positions
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HedgingSettings {
+    pub hedges: Vec<HedgeSettings>,
+    pub check_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HedgeSettings {
+    /// Market which base currency balance is hedged
+    pub spot_exchange_account_id: ExchangeAccountId,
+    pub spot_currency_pair: CurrencyPair,
+    pub perpetual_exchange_account_id: ExchangeAccountId,
+    pub perpetual_currency_pair: CurrencyPair,
+    /// Share of spot inventory offset by perpetual position, 1 is delta neutral
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub hedge_ratio: Decimal,
+    /// Min deviation of perpetual position from target in base currency which is rebalanced
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub rebalance_threshold: Amount,
+}
+
+/// Periodic validation of API keys of exchanges
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ApiKeyHealthSettings {
+    pub check_interval_ms: u64,
+    /// Expiration of API key is alerted when less than this period is left
+    pub expiry_warning_ms: u64,
+}
+
+/// Append-only log of order actions and manual interventions chained by hashes
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AuditLogSettings {
+    /// File which records are appended to. Chain is continued from the last record of existing file
+    pub path: PathBuf,
+}
+
+/// Detection of markets which order books stopped updating while connection is healthy
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct StaleMarketDataSettings {
+    /// Market is stale if its order book wasn't updated during this period
+    pub max_update_interval_ms: u64,
+}
+
+/// Reference prices constructed as weighted median of middle prices on several exchanges
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct IndexPriceSettings {
+    pub indices: Vec<IndexSettings>,
+    /// Middle prices which weren't updated longer than this period are excluded from index
+    pub max_price_age_ms: u64,
+    /// Middle prices deviating from median of all components more than this are excluded from index
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_deviation: Percent,
+}
+
+/// Candles of last trade prices built by core. Higher timeframes are derived from base candles
+/// instead of being built from trades separately
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CandlesSettings {
+    pub base_period_ms: u64,
+    /// Periods of higher timeframes, each has to be a multiple of `base_period_ms`
+    #[serde(default)]
+    pub timeframes_ms: Vec<u64>,
+    /// Count of completed candles kept for every timeframe
+    pub max_candles: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct IndexSettings {
+    /// Currency pair which index price is requested by
+    pub currency_pair: CurrencyPair,
+    pub components: Vec<IndexComponentSettings>,
+    /// Index price isn't available if fewer components are left after excluding stale prices and outliers
+    #[serde(default = "default_min_index_components")]
+    pub min_components: usize,
+}
+
+fn default_min_index_components() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct IndexComponentSettings {
+    pub exchange_id: ExchangeId,
+    /// Currency pair on exchange, it can differ from index currency pair, e.g. BTC/USD for BTC/USDT index
+    pub currency_pair: CurrencyPair,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub weight: Decimal,
+}
+
+/// Read-only streaming of market data and fills for research clients
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DataBridgeSettings {
+    /// Address for websocket listening, e.g. "127.0.0.1:9100"
+    pub address: String,
+}
+
+/// Order books of all markets are recorded to files in compact delta encoding, one file per market.
+/// Recorded files are replayed by backtesting (see `BacktestSettings::order_book_deltas_dir`)
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct OrderBookRecordingSettings {
+    pub directory: PathBuf,
+    /// Prices and amounts are recorded as integer number of `10^-scale` units, so levels with
+    /// bigger precision aren't recorded
+    #[serde(default = "default_order_book_recording_scale")]
+    pub price_scale: u32,
+    #[serde(default = "default_order_book_recording_scale")]
+    pub amount_scale: u32,
+    /// Full order book is recorded after this number of updates
+    #[serde(default = "default_order_book_keyframe_interval")]
+    pub keyframe_interval: u32,
+}
+
+fn default_order_book_recording_scale() -> u32 {
+    8
+}
+
+fn default_order_book_keyframe_interval() -> u32 {
+    1_000
+}
+
+/// Caps of notional traded by strategy during last 24 hours plus notional of its open and in
+/// flight orders
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct StrategySpendingLimitSettings {
+    pub service_name: ServiceName,
+    pub daily_notional_caps: Vec<NotionalCapSettings>,
+}
+
+/// Cap of notional (price * amount) of orders with specified quote currency. Notionals in
+/// different quote currencies aren't comparable, so each quote currency has its own cap and
+/// orders with quote currency without cap aren't limited
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NotionalCapSettings {
+    pub quote_currency_code: CurrencyCode,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_daily_notional: Amount,
+}
+
+/// Virtual capital which strategy may use on exchange account independently of shared exchange
+/// balance, e.g. 2 BTC and 30000 USDT. Currencies which aren't listed start from zero
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct StrategyCapitalAllocationSettings {
+    pub service_name: ServiceName,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currencies: Vec<CurrencyAllocationSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CurrencyAllocationSettings {
+    pub currency_code: CurrencyCode,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub amount: Amount,
+}
+
+/// What to do with orders and cancels exceeding throttle budget
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ThrottlePolicy {
+    #[default]
+    Drop,
+    /// Actions are postponed to next ticks and issued in original order when budget allows
+    Queue,
+}
+
+/// Budget of new orders and cancels issued by strategy. Tick is handling of one event by strategy
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct StrategyOrderThrottleSettings {
+    pub service_name: ServiceName,
+    pub max_orders_per_second: Option<usize>,
+    pub max_orders_per_tick: Option<usize>,
+    pub max_cancels_per_second: Option<usize>,
+    pub max_cancels_per_tick: Option<usize>,
+    #[serde(default)]
+    pub policy: ThrottlePolicy,
+    /// Actions exceeding queue size are dropped
+    pub max_queue_size: Option<usize>,
+}
+
+/// Max time of one strategy callback. Strategy exceeding budget in `max_overruns` ticks in a row
+/// is alerted and suspended if `suspend` is set, while engine and other strategies keep running
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct StrategyTickBudgetSettings {
+    pub service_name: ServiceName,
+    pub budget_ms: u64,
+    pub max_overruns: usize,
+    #[serde(default)]
+    pub suspend: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketMode {
+    /// Orders are calculated and logged, but aren't sent to exchange
+    Shadow,
+    /// Order amounts are reduced to `reduced_size` percents of normal ones
+    Reduced,
+    Full,
+}
+
+/// Gradual rollout of new market. Market is promoted from shadow to reduced and then to full mode
+/// by `promotion` rules or manually through control API
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MarketRolloutSettings {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub mode: MarketMode,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub reduced_size: Percent,
+    pub promotion: Option<RolloutPromotionSettings>,
+}
+
+/// Market is promoted to next mode when all rules are met by metrics observed since the last
+/// mode change. Orders suppressed in shadow mode are counted as orders
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RolloutPromotionSettings {
+    pub min_duration_ms: u64,
+    pub min_orders: u64,
+    /// Max share of orders rejected by exchange
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_reject_rate: Percent,
+    /// Min PnL by performance attribution in quote currency. It's checked in reduced mode only
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub min_pnl: Option<Amount>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
@@ -33,6 +514,24 @@ pub struct DbSettings {
     /// Path to directory for creating temporary directory for save events that was not saved to
     /// database by any reason and will be resaved to db late
     pub postponed_events_dir: Option<PathBuf>,
+    pub retention: Option<EventRetentionSettings>,
+}
+
+/// Events beyond retention limits of their tables are removed from database by periodic
+/// compaction or by control API. Removed events are archived to zip files if `archive_dir` is set
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct EventRetentionSettings {
+    /// Compaction is triggered only by control API if it isn't set
+    pub compaction_interval_ms: Option<u64>,
+    pub archive_dir: Option<PathBuf>,
+    pub tables: Vec<TableRetentionSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TableRetentionSettings {
+    pub table_name: String,
+    pub max_age_ms: Option<u64>,
+    pub max_rows: Option<u64>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -45,6 +544,31 @@ pub enum CurrencyPairSetting {
     Specific(String),
 }
 
+/// Filters of currency pairs expanded from wildcard patterns of `currency_pairs`
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CurrencyPairsFilterSettings {
+    /// Only pairs with any of these currencies are expanded if list isn't empty
+    #[serde(default)]
+    pub include_currencies: Vec<CurrencyCode>,
+    #[serde(default)]
+    pub exclude_currencies: Vec<CurrencyCode>,
+    /// Min volume traded during last 24 hours in quote currency
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub min_daily_volume: Option<Amount>,
+}
+
+/// Minimal allowed spread between own buy and sell quotes on a market.
+/// Floor is calculated as maker fee for both sides plus `min_edge` in percents of middle price
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SpreadFloorSettings {
+    pub currency_pair: CurrencyPair,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub min_edge: Percent,
+    /// Quotes tighter than floor are allowed, only warning will be logged
+    #[serde(default)]
+    pub allow_below_floor: bool,
+}
+
 // Field order are matter for serialization:
 // Simple values must be emitted before struct with custom serialization
 // https://github.com/alexcrichton/toml-rs/issues/142#issuecomment-278970591
@@ -55,14 +579,397 @@ pub struct ExchangeSettings {
     pub api_key: String,
     pub secret_key: String,
     pub is_margin_trading: bool,
+    /// Expected environment of exchange account. Engine refuses to start if hosts of exchange
+    /// belong to another environment
+    pub environment: Option<Environment>,
     pub request_trades: bool,
     pub is_reducing_market_data: Option<bool>,
     pub subscribe_to_market_data: bool,
     pub websocket_channels: Vec<String>,
+    /// Orders with price deviating from the last trade price more than this percent are rejected
+    /// unless they are flagged by `OrderHeader::skip_price_sanity_check`
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub max_price_deviation_from_last_trade: Option<Percent>,
+    /// Trades older than this aren't used as reference price of price sanity check
+    #[serde(default = "default_max_last_trade_age_ms")]
+    pub max_last_trade_age_ms: u64,
+    /// Orders with price outside of collar around index price of currency pair are rejected
+    /// unless they are flagged by `OrderHeader::skip_price_sanity_check`. Index prices should be configured
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub max_price_deviation_from_index: Option<Percent>,
+    /// Trades with price deviating from the nearest side of order book top more than this
+    /// percent are bad prints which are excluded from trades used by trading logic
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub max_trade_deviation_from_book: Option<Percent>,
+    /// Caps of notional traded during last 24 hours plus notional of open and in flight orders
+    /// on exchange account
+    #[serde(default)]
+    pub daily_notional_caps: Vec<NotionalCapSettings>,
+    /// Explicit currency pairs or wildcard patterns like `*-USDT` expanded against exchange symbols at startup
     pub currency_pairs: Option<Vec<CurrencyPairSetting>>,
+    /// Filters of currency pairs expanded from wildcard patterns
+    pub currency_pairs_filter: Option<CurrencyPairsFilterSettings>,
+    pub spread_floors: Option<Vec<SpreadFloorSettings>>,
+    /// Symbols of exchange for currency pairs which differ from the ones derived by connector
+    pub currency_pair_overrides: Option<Vec<CurrencyPairOverrideSettings>>,
+    pub withdrawals: Option<WithdrawalSettings>,
+    /// Hosts of exchange API instead of default production ones, e.g. hosts of testnet
+    pub hosts: Option<HostsSettings>,
+    /// Selection of the fastest REST host among alternative production hosts of exchange
+    pub host_selection: Option<HostSelectionSettings>,
+    /// Keep-alive connection pool of REST client. Defaults of HTTP client are used if it isn't set
+    pub rest_client: Option<RestClientSettings>,
+    /// Proxy and local address of REST and websocket connections
+    pub egress: Option<EgressSettings>,
+    /// Fee schedule of exchange by volume traded during last 30 days
+    pub fee_tiers: Option<Vec<FeeTierSettings>>,
+    /// Websocket messages are parsed by socket reader if it isn't set
+    pub parsing_workers: Option<ParsingWorkersSettings>,
+    /// Connector runs in engine process if it isn't set
+    pub connector_process: Option<ConnectorProcessSettings>,
+}
+
+fn default_max_last_trade_age_ms() -> u64 {
+    60_000
+}
+
+/// Pool of workers parsing websocket messages of exchange account, so socket reader isn't
+/// delayed by CPU-heavy messages. Order of messages is kept per market
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ParsingWorkersSettings {
+    pub workers_count: usize,
+}
+
+/// Connector of exchange account runs in separate process supervised by engine, so crash or
+/// hang of connector doesn't take engine down. Connector process is restarted when it exits.
+/// Supported on unix only, because engine and connector communicate over unix socket
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ConnectorProcessSettings {
+    /// Executable of engine is started if it isn't set. Executable should call
+    /// `run_connector_process` when it's started with `MMB_CONNECTOR_SOCKET` environment variable
+    pub executable: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Fee tier of exchange. Negative fee is rebate
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FeeTierSettings {
+    pub name: String,
+    /// Min volume traded during last 30 days in quote currency of markets
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub min_volume: Amount,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub maker_fee: Percent,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub taker_fee: Percent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    /// Real funds are traded
+    Production,
+    /// Testnet of exchange
+    Sandbox,
+}
+
+impl Display for Environment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Environment::Production => write!(f, "production"),
+            Environment::Sandbox => write!(f, "sandbox"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HostsSettings {
+    pub rest_host: String,
+    pub web_socket_host: String,
+    /// Main websocket host is used if it isn't set
+    pub web_socket2_host: Option<String>,
+}
+
+/// Connection pool of REST client of exchange account. Default of HTTP client is used for
+/// each unset value
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RestClientSettings {
+    /// Idle keep-alive connections are closed after this timeout
+    pub pool_idle_timeout_sec: Option<u64>,
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Interval of TCP keep-alive probes of connections
+    pub tcp_keepalive_sec: Option<u64>,
+    /// Requests over this count wait for completion of active ones, so count of connections
+    /// opened by pool is limited too
+    pub max_concurrent_requests: Option<usize>,
+    /// HTTP version is negotiated with host if it isn't set. Enabled HTTP/2 multiplexes
+    /// concurrent requests over single connection, otherwise only HTTP/1.1 is used
+    pub http2: Option<bool>,
+    /// Interval of HTTP/2 pings which keep multiplexed connection alive while it's idle
+    pub http2_keep_alive_interval_sec: Option<u64>,
+}
+
+/// Route of REST and websocket connections of exchange account, e.g. to satisfy IP whitelist
+/// of exchange or to spread accounts across different egress IPs
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EgressSettings {
+    pub proxy: Option<ProxySettings>,
+    /// Connections are bound to this local address, so they go out through its interface
+    pub local_address: Option<IpAddr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyKind {
+    /// Tunnel is opened by HTTP CONNECT request
+    Http,
+    Socks5,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProxySettings {
+    pub kind: ProxyKind,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// Latency probing of alternative hosts of exchange with failover on errors
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HostSelectionSettings {
+    /// Interval between latency probes of all hosts. The fastest available host is selected
+    /// after each round of probes
+    pub probe_interval_ms: u64,
+    /// Selected host is switched when its share of failed requests exceeds this value
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_error_rate: Percent,
+    /// Min count of requests to selected host before its error rate is taken into account
+    pub min_requests: u64,
+}
+
+/// Mapping between currency pair and symbol of exchange, e.g. `BTC-USDT` or numeric id of market
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CurrencyPairOverrideSettings {
+    pub currency_pair: CurrencyPair,
+    pub specific_currency_pair: SpecificCurrencyPair,
+}
+
+/// Address where withdrawals of currency are allowed to
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WhitelistedAddress {
+    pub currency_code: CurrencyCode,
+    pub address: String,
+}
+
+/// Operator of control API. Operator is identified by token passed in `Authorization: Bearer`
+/// header, so approvals requiring another operator can't be made by passing someone else's name
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ControlApiOperatorSettings {
+    pub name: String,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WithdrawalSettings {
+    pub whitelist: Vec<WhitelistedAddress>,
+    /// Withdrawal should be confirmed by second operator before sending
+    #[serde(default)]
+    pub require_approval: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WithdrawalApprovalsSettings {
+    /// File where withdrawals waiting approval and approved ones are saved to survive engine restarts
+    pub state_file: PathBuf,
+}
+
+/// Target distribution of currency balance across exchange accounts
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RebalancingTargetSettings {
+    pub currency_code: CurrencyCode,
+    /// Shares of total balance should sum up to 100%
+    pub shares: Vec<RebalancingShareSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RebalancingShareSettings {
+    pub exchange_account_id: ExchangeAccountId,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub share: Percent,
+}
+
+/// Way of transferring currency between exchange accounts
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TransferRouteSettings {
+    pub currency_code: CurrencyCode,
+    pub from: ExchangeAccountId,
+    pub to: ExchangeAccountId,
+    /// Deposit address of destination account. It should be whitelisted in withdrawal settings of source account
+    pub address: String,
+    /// Withdrawal fee of source exchange in transferred currency
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub fee: Amount,
+    /// Min withdrawal amount of source exchange
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub min_amount: Amount,
+    /// Expected time from withdrawal till crediting of deposit
+    pub transfer_time_mins: u64,
+}
+
+/// Parametric value at risk and stress scenarios over exposures of currencies valued by index prices
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ValueAtRiskSettings {
+    pub assets: Vec<RiskAssetSettings>,
+    /// Confidence level of value at risk, e.g. 99%
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub confidence: Percent,
+    pub horizon_ms: u64,
+    /// Index prices are sampled with this interval for estimation of volatilities and correlations
+    pub sampling_interval_ms: u64,
+    /// Decay factor of exponentially weighted estimation, 0.94 is common for daily returns
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub decay: Decimal,
+    /// Value at risk isn't available until this count of returns is sampled
+    #[serde(default = "default_min_risk_samples")]
+    pub min_samples: usize,
+    /// How orders are checked while less than `min_samples` returns are sampled
+    #[serde(default)]
+    pub warm_up_policy: RiskWarmUpPolicy,
+    /// Estimation of sampled returns is persisted and restored on restart if it isn't older than this
+    #[serde(default = "default_max_restored_risk_samples_age_ms")]
+    pub max_restored_samples_age_ms: u64,
+    /// Exposures and their value at risk are reused by order checks during this time
+    #[serde(default = "default_risk_exposures_cache_ms")]
+    pub exposures_cache_ms: u64,
+    #[serde(default)]
+    pub scenarios: Vec<StressScenarioSettings>,
+    /// Orders increasing value at risk more than this are rejected. It's in quote currency of indices
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub max_marginal_var: Option<Amount>,
+}
+
+fn default_min_risk_samples() -> usize {
+    30
+}
+
+fn default_max_restored_risk_samples_age_ms() -> u64 {
+    24 * 60 * 60 * 1000
+}
+
+fn default_risk_exposures_cache_ms() -> u64 {
+    1000
+}
+
+/// How orders are checked by value at risk limit while returns are being sampled after start
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RiskWarmUpPolicy {
+    /// Orders are rejected except reduce-only ones
+    #[default]
+    Reject,
+    /// Orders are allowed without value at risk limit
+    Allow,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RiskAssetSettings {
+    pub currency_code: CurrencyCode,
+    /// Index price which exposure of currency is valued by. All indices should have the same quote currency
+    pub index_currency_pair: CurrencyPair,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct StressScenarioSettings {
+    pub name: String,
+    pub shocks: Vec<PriceShockSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PriceShockSettings {
+    pub currency_code: CurrencyCode,
+    /// Change of price, e.g. -20% for crash of 20%
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub change: Percent,
+}
+
+/// Assets are restricted by external news or compliance signals received from feeds or control API.
+/// Markets of restricted asset are quoted wider or paused until restriction is lifted or expired
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NewsRestrictionsSettings {
+    pub action: NewsRestrictionAction,
+    /// Quotes on markets of restricted assets are moved away from market by this distance
+    #[serde(default, deserialize_with = "deserialize_decimal")]
+    pub price_collar: Percent,
+    /// Restriction is lifted automatically after this time if it isn't lifted earlier
+    pub restriction_duration_ms: u64,
+    /// Headline restricts asset if it mentions the asset and contains any of keywords, e.g. halt, delisting, hack
+    pub keywords: Vec<String>,
+    pub assets: Vec<NewsAssetSettings>,
+    #[serde(default)]
+    pub feeds: Vec<NewsFeedSettings>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum NewsRestrictionAction {
+    WidenQuotes,
+    PauseMarkets,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NewsAssetSettings {
+    pub currency_code: CurrencyCode,
+    /// Other names of asset in headlines, e.g. bitcoin for btc. Currency code is always matched
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NewsFeedSettings {
+    /// Titles of items of RSS feed polled with interval
+    Rss { url: String, poll_interval_ms: u64 },
+    /// Every text message of websocket is a headline
+    Websocket { url: String },
+}
+
+/// Planning of transfers between exchange accounts to keep target distribution of balances
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RebalancingSettings {
+    pub targets: Vec<RebalancingTargetSettings>,
+    pub routes: Vec<TransferRouteSettings>,
+    /// Deviation from target balance in percents of total balance which isn't rebalanced
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub tolerance: Percent,
+}
+
+impl WithdrawalSettings {
+    pub fn is_whitelisted(&self, currency_code: CurrencyCode, address: &str) -> bool {
+        self.whitelist
+            .iter()
+            .any(|x| x.currency_code == currency_code && x.address == address)
+    }
 }
 
 impl ExchangeSettings {
+    pub fn get_spread_floor(&self, currency_pair: CurrencyPair) -> Option<&SpreadFloorSettings> {
+        self.spread_floors
+            .as_ref()?
+            .iter()
+            .find(|x| x.currency_pair == currency_pair)
+    }
+
+    pub fn get_overridden_currency_pair(
+        &self,
+        specific_currency_pair: SpecificCurrencyPair,
+    ) -> Option<CurrencyPair> {
+        self.currency_pair_overrides
+            .as_ref()?
+            .iter()
+            .find(|x| x.specific_currency_pair == specific_currency_pair)
+            .map(|x| x.currency_pair)
+    }
+
     // only for tests
     pub fn new_short(
         exchange_account_id: ExchangeAccountId,
@@ -75,11 +982,28 @@ impl ExchangeSettings {
             api_key,
             secret_key,
             is_margin_trading,
+            environment: None,
             request_trades: false,
             websocket_channels: vec![],
+            max_price_deviation_from_last_trade: None,
+            max_last_trade_age_ms: default_max_last_trade_age_ms(),
+            max_price_deviation_from_index: None,
+            max_trade_deviation_from_book: None,
+            daily_notional_caps: vec![],
             currency_pairs: None,
+            currency_pairs_filter: None,
             subscribe_to_market_data: true,
             is_reducing_market_data: None,
+            spread_floors: None,
+            currency_pair_overrides: None,
+            withdrawals: None,
+            hosts: None,
+            host_selection: None,
+            rest_client: None,
+            egress: None,
+            fee_tiers: None,
+            parsing_workers: None,
+            connector_process: None,
         }
     }
 }
@@ -91,11 +1015,28 @@ impl Default for ExchangeSettings {
             api_key: "".to_string(),
             secret_key: "".to_string(),
             is_margin_trading: false,
+            environment: None,
             request_trades: false,
             websocket_channels: vec![],
+            max_price_deviation_from_last_trade: None,
+            max_last_trade_age_ms: default_max_last_trade_age_ms(),
+            max_price_deviation_from_index: None,
+            max_trade_deviation_from_book: None,
+            daily_notional_caps: vec![],
             currency_pairs: None,
+            currency_pairs_filter: None,
             subscribe_to_market_data: true,
             is_reducing_market_data: None,
+            spread_floors: None,
+            currency_pair_overrides: None,
+            withdrawals: None,
+            hosts: None,
+            host_selection: None,
+            rest_client: None,
+            egress: None,
+            fee_tiers: None,
+            parsing_workers: None,
+            connector_process: None,
         }
     }
 }
@@ -0,0 +1,339 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::de::{Error, Unexpected, Visitor};
+use serde::Deserializer;
+use toml_edit::{Document, Item, Table, Value};
+
+/// Settings keys with this suffix are durations in milliseconds
+const DURATION_MS_SUFFIX: &str = "_ms";
+const DURATION_UNITS: &str = "'ms', 's', 'm', 'h', 'd'";
+
+/// Parses duration like "500ms", "2s", "1.5m", "1h" or "1d". Unit is required, so the same value
+/// can't be read differently by different settings
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let unit_start = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .with_context(|| {
+            format!("Duration '{value}' has no unit, expected one of {DURATION_UNITS}")
+        })?;
+    let (number, unit) = value.split_at(unit_start);
+
+    let unit_ms = match unit {
+        "ms" => dec!(1),
+        "s" => dec!(1000),
+        "m" => dec!(60000),
+        "h" => dec!(3600000),
+        "d" => dec!(86400000),
+        _ => {
+            bail!("Duration '{value}' has unknown unit '{unit}', expected one of {DURATION_UNITS}")
+        }
+    };
+    let number = parse_decimal(number).with_context(|| format!("Invalid duration '{value}'"))?;
+    if number.is_sign_negative() {
+        bail!("Duration '{value}' is negative");
+    }
+
+    let ms = number
+        .checked_mul(unit_ms)
+        .with_context(|| format!("Duration '{value}' is too long"))?;
+    if !ms.fract().is_zero() {
+        bail!("Duration '{value}' isn't a whole number of milliseconds");
+    }
+    let ms = ms
+        .to_u64()
+        .with_context(|| format!("Duration '{value}' is too long"))?;
+
+    Ok(Duration::from_millis(ms))
+}
+
+/// Parses decimal exactly as it is written, e.g. "0.0001", "1_000.5" or "1e-4"
+pub fn parse_decimal(value: &str) -> Result<Decimal> {
+    let value = value.trim().replace('_', "");
+    if value.is_empty() {
+        bail!("Decimal value is empty");
+    }
+
+    match value.contains(['e', 'E']) {
+        true => Decimal::from_scientific(&value),
+        false => Decimal::from_str(&value),
+    }
+    .with_context(|| format!("Invalid decimal '{value}'"))
+}
+
+/// Replaces human readable durations in keys with suffix `_ms` by count of milliseconds,
+/// e.g. `poll_interval_ms = "2s"` by `poll_interval_ms = 2000`. Errors point at the full key path
+pub(crate) fn normalize_durations(document: &mut Document) -> Result<()> {
+    normalize_table(document.as_table_mut(), "")
+}
+
+fn normalize_table(table: &mut Table, path: &str) -> Result<()> {
+    let keys = table
+        .iter()
+        .map(|(key, _)| key.to_owned())
+        .collect::<Vec<_>>();
+    for key in keys {
+        let item_path = join_path(path, &key);
+        match table.get_mut(&key) {
+            Some(Item::Value(value)) => normalize_value(value, &key, &item_path)?,
+            Some(Item::Table(table)) => normalize_table(table, &item_path)?,
+            Some(Item::ArrayOfTables(tables)) => {
+                for (index, table) in tables.iter_mut().enumerate() {
+                    normalize_table(table, &format!("{item_path}[{index}]"))?;
+                }
+            }
+            Some(Item::None) | None => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn normalize_value(value: &mut Value, key: &str, path: &str) -> Result<()> {
+    match value {
+        Value::String(duration) if key.ends_with(DURATION_MS_SUFFIX) => {
+            let duration = parse_duration(duration.value())
+                .with_context(|| format!("Invalid value of settings key '{path}'"))?;
+            let ms = i64::try_from(duration.as_millis())
+                .with_context(|| format!("Value of settings key '{path}' is too long"))?;
+            *value = Value::from(ms);
+        }
+        Value::Integer(ms) if key.ends_with(DURATION_MS_SUFFIX) && *ms.value() < 0 => {
+            bail!(
+                "Invalid value of settings key '{path}': duration {} is negative",
+                ms.value()
+            );
+        }
+        Value::InlineTable(table) => {
+            let keys = table
+                .iter()
+                .map(|(key, _)| key.to_owned())
+                .collect::<Vec<_>>();
+            for key in keys {
+                if let Some(value) = table.get_mut(&key) {
+                    normalize_value(value, &key, &join_path(path, &key))?;
+                }
+            }
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                normalize_value(value, key, &format!("{path}[{index}]"))?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_owned(),
+        false => format!("{path}.{key}"),
+    }
+}
+
+struct DecimalVisitor;
+
+impl<'de> Visitor<'de> for DecimalVisitor {
+    type Value = Decimal;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("decimal number or string with decimal number")
+    }
+
+    fn visit_i64<E: Error>(self, value: i64) -> Result<Decimal, E> {
+        Ok(Decimal::from(value))
+    }
+
+    fn visit_u64<E: Error>(self, value: u64) -> Result<Decimal, E> {
+        Ok(Decimal::from(value))
+    }
+
+    /// Float is converted by its shortest representation, so 0.1 is read as 0.1 rather than
+    /// 0.1000000000000000055511151231. Values with more than 15 significant digits should be
+    /// written as strings
+    fn visit_f64<E: Error>(self, value: f64) -> Result<Decimal, E> {
+        parse_decimal(&value.to_string())
+            .map_err(|_| E::invalid_value(Unexpected::Float(value), &self))
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<Decimal, E> {
+        parse_decimal(value).map_err(|error| E::custom(format!("{error:#}")))
+    }
+}
+
+/// Deserializes decimal settings from numbers and strings without float artifacts:
+/// `#[serde(deserialize_with = "deserialize_decimal")]`
+pub fn deserialize_decimal<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Decimal, D::Error> {
+    deserializer.deserialize_any(DecimalVisitor)
+}
+
+struct OptionalDecimalVisitor;
+
+impl<'de> Visitor<'de> for OptionalDecimalVisitor {
+    type Value = Option<Decimal>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("optional decimal number or string with decimal number")
+    }
+
+    fn visit_none<E: Error>(self) -> Result<Option<Decimal>, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: Error>(self) -> Result<Option<Decimal>, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Option<Decimal>, D::Error> {
+        deserialize_decimal(deserializer).map(Some)
+    }
+}
+
+/// The same as `deserialize_decimal` for optional settings, should be used with `#[serde(default)]`
+/// Null is read as `None`, so settings serialized to JSON are read back
+pub fn deserialize_optional_decimal<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Decimal>, D::Error> {
+    deserializer.deserialize_option(OptionalDecimalVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde::Deserialize;
+
+    #[rstest]
+    #[case("500ms", 500)]
+    #[case("2s", 2_000)]
+    #[case(" 1.5m ", 90_000)]
+    #[case("1h", 3_600_000)]
+    #[case("1d", 86_400_000)]
+    #[case("0s", 0)]
+    pub fn duration_parsing(#[case] value: &str, #[case] expected_ms: u64) {
+        assert_eq!(
+            parse_duration(value).expect("in test"),
+            Duration::from_millis(expected_ms)
+        );
+    }
+
+    #[rstest]
+    #[case("500", "has no unit")]
+    #[case("2sec", "unknown unit 'sec'")]
+    #[case("-1s", "is negative")]
+    #[case("0.5ms", "isn't a whole number of milliseconds")]
+    #[case("ms", "Invalid duration")]
+    pub fn invalid_duration(#[case] value: &str, #[case] expected_error: &str) {
+        let error = parse_duration(value).expect_err("in test");
+        assert!(
+            format!("{error:#}").contains(expected_error),
+            "unexpected error: {error:#}"
+        );
+    }
+
+    #[rstest]
+    #[case("0.1", dec!(0.1))]
+    #[case("1_000.5", dec!(1000.5))]
+    #[case("1e-4", dec!(0.0001))]
+    #[case("0.123456789012345678901", dec!(0.123456789012345678901))]
+    pub fn decimal_parsing(#[case] value: &str, #[case] expected: Decimal) {
+        assert_eq!(parse_decimal(value).expect("in test"), expected);
+    }
+
+    #[test]
+    pub fn durations_are_normalized_with_key_path_in_errors() {
+        let mut document: Document = r#"
+            [core.warm_up]
+            timeout_ms = "30s"
+            max_clock_skew_ms = 500
+
+            [core.index_prices]
+            rate_limit = { window_ms = "1.5s" }
+            "#
+        .parse()
+        .expect("in test");
+
+        normalize_durations(&mut document).expect("in test");
+        assert_eq!(
+            document["core"]["warm_up"]["timeout_ms"].as_integer(),
+            Some(30_000)
+        );
+        assert_eq!(
+            document["core"]["warm_up"]["max_clock_skew_ms"].as_integer(),
+            Some(500)
+        );
+        assert_eq!(
+            document["core"]["index_prices"]["rate_limit"]["window_ms"].as_integer(),
+            Some(1_500)
+        );
+
+        let mut document: Document = r#"
+            [[strategy.markets]]
+            cooldown_ms = "2s"
+
+            [[strategy.markets]]
+            cooldown_ms = "2 sec"
+            "#
+        .parse()
+        .expect("in test");
+
+        let error = normalize_durations(&mut document).expect_err("in test");
+        assert!(format!("{error:#}").contains("'strategy.markets[1].cooldown_ms'"));
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct DecimalSettings {
+        #[serde(deserialize_with = "deserialize_decimal")]
+        float: Decimal,
+        #[serde(deserialize_with = "deserialize_decimal")]
+        string: Decimal,
+        #[serde(deserialize_with = "deserialize_decimal")]
+        integer: Decimal,
+        #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+        optional: Option<Decimal>,
+    }
+
+    #[test]
+    pub fn decimal_settings_without_float_artifacts() {
+        let settings: DecimalSettings = toml_edit::de::from_str(
+            r#"
+            float = 0.07
+            string = "0.123456789012345678901"
+            integer = 3
+            "#,
+        )
+        .expect("in test");
+
+        assert_eq!(settings.float, dec!(0.07));
+        assert_eq!(settings.string, dec!(0.123456789012345678901));
+        assert_eq!(settings.integer, dec!(3));
+        assert_eq!(settings.optional, None);
+    }
+
+    #[test]
+    pub fn optional_decimal_is_read_from_json() {
+        let settings: DecimalSettings = serde_json::from_str(
+            r#"{"float": 0.5, "string": "1", "integer": 2, "optional": null}"#,
+        )
+        .expect("in test");
+        assert_eq!(settings.optional, None);
+
+        let settings: DecimalSettings = serde_json::from_str(
+            r#"{"float": 0.5, "string": "1", "integer": 2, "optional": "0.25"}"#,
+        )
+        .expect("in test");
+        assert_eq!(settings.optional, Some(dec!(0.25)));
+    }
+}
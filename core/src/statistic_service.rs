@@ -1,3 +1,4 @@
+use super::disposition_execution::rejection_analytics::{classify_rejection, RejectionReason};
 use super::orders::{event::OrderEventType, order::ClientOrderId};
 use anyhow::{Context, Result};
 use mmb_utils::infrastructure::SpawnFutureFlags;
@@ -17,16 +18,17 @@ use super::{
     infrastructure::spawn_future,
 };
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct MarketAccountIdStatistic {
-    opened_orders_count: u64,
-    canceled_orders_count: u64,
-    partially_filled_orders_count: u64,
-    fully_filled_orders_count: u64,
+    pub(crate) opened_orders_count: u64,
+    pub(crate) canceled_orders_count: u64,
+    pub(crate) partially_filled_orders_count: u64,
+    pub(crate) fully_filled_orders_count: u64,
     // Calculated only for completely filled orders
-    summary_filled_amount: Amount,
+    pub(crate) summary_filled_amount: Amount,
     // Calculated only for completely filled orders
-    summary_commission: Amount,
+    pub(crate) summary_commission: Amount,
+    pub(crate) rejected_orders_count: HashMap<RejectionReason, u64>,
 }
 
 impl MarketAccountIdStatistic {
@@ -38,6 +40,10 @@ impl MarketAccountIdStatistic {
         self.canceled_orders_count += 1;
     }
 
+    fn register_rejected_order(&mut self, reason: RejectionReason) {
+        *self.rejected_orders_count.entry(reason).or_default() += 1;
+    }
+
     fn increment_partially_filled_orders(&mut self) {
         self.partially_filled_orders_count += 1;
     }
@@ -66,6 +72,9 @@ impl MarketAccountIdStatistic {
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct DispositionExecutorStatistic {
     skipped_events_amount: u64,
+    throttled_queued_actions_count: u64,
+    throttled_dropped_actions_count: u64,
+    duplicate_order_book_events_count: u64,
 }
 
 #[derive(Default, Debug, Serialize, Deserialize)]
@@ -75,6 +84,12 @@ pub(crate) struct StatisticServiceState {
 }
 
 impl StatisticServiceState {
+    pub(crate) fn market_account_id_stats(
+        &self,
+    ) -> HashMap<MarketAccountId, MarketAccountIdStatistic> {
+        self.market_account_id_stats.read().clone()
+    }
+
     pub(crate) fn register_created_order(&self, market_account_id: MarketAccountId) {
         self.market_account_id_stats
             .write()
@@ -91,6 +106,18 @@ impl StatisticServiceState {
             .register_canceled_order();
     }
 
+    pub(crate) fn register_rejected_order(
+        &self,
+        market_account_id: MarketAccountId,
+        reason: RejectionReason,
+    ) {
+        self.market_account_id_stats
+            .write()
+            .entry(market_account_id)
+            .or_default()
+            .register_rejected_order(reason);
+    }
+
     pub(crate) fn register_partially_filled_order(&self, market_account_id: MarketAccountId) {
         self.market_account_id_stats
             .write()
@@ -142,6 +169,24 @@ impl StatisticServiceState {
     pub(crate) fn register_skipped_event(&self) {
         (*self.disposition_executor_stats.lock()).skipped_events_amount += 1;
     }
+
+    pub(crate) fn register_throttled_queued_action(&self) {
+        self.disposition_executor_stats
+            .lock()
+            .throttled_queued_actions_count += 1;
+    }
+
+    pub(crate) fn register_throttled_dropped_action(&self) {
+        self.disposition_executor_stats
+            .lock()
+            .throttled_dropped_actions_count += 1;
+    }
+
+    pub(crate) fn register_duplicate_order_book_event(&self) {
+        self.disposition_executor_stats
+            .lock()
+            .duplicate_order_book_events_count += 1;
+    }
 }
 
 #[derive(Default, Debug)]
@@ -174,6 +219,15 @@ impl StatisticService {
         self.remove_filled_order_if_exist(market_account_id, client_order_id);
     }
 
+    pub(crate) fn register_rejected_order(
+        &self,
+        market_account_id: MarketAccountId,
+        reason: RejectionReason,
+    ) {
+        self.statistic_service_state
+            .register_rejected_order(market_account_id, reason);
+    }
+
     pub(crate) fn register_partially_filled_order(
         &self,
         market_account_id: MarketAccountId,
@@ -224,6 +278,21 @@ impl StatisticService {
     pub(crate) fn register_skipped_event(&self) {
         self.statistic_service_state.register_skipped_event();
     }
+
+    pub(crate) fn register_throttled_queued_action(&self) {
+        self.statistic_service_state
+            .register_throttled_queued_action();
+    }
+
+    pub(crate) fn register_throttled_dropped_action(&self) {
+        self.statistic_service_state
+            .register_throttled_dropped_action();
+    }
+
+    pub(crate) fn register_duplicate_order_book_event(&self) {
+        self.statistic_service_state
+            .register_duplicate_order_book_event();
+    }
 }
 
 pub struct StatisticEventHandler {
@@ -274,6 +343,16 @@ impl StatisticEventHandler {
                     OrderEventType::CreateOrderSucceeded => {
                         self.stats.register_created_order(market_account_id);
                     }
+                    OrderEventType::CreateOrderFailed => {
+                        let reason = order_event.order.fn_ref(|x| {
+                            classify_rejection(
+                                x.internal_props.last_creation_error_type,
+                                &x.internal_props.last_creation_error_message,
+                            )
+                        });
+                        self.stats
+                            .register_rejected_order(market_account_id, reason);
+                    }
                     OrderEventType::CancelOrderSucceeded => {
                         let client_order_id = order_event.order.client_order_id();
                         self.stats
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+
+use crate::exchanges::common::{Amount, MarketAccountId};
+
+/// Desired amounts of markets set at runtime through control API. Desired amount overrides max
+/// amount from strategy settings, so liquidity targets are tuned without restart of engine
+#[derive(Debug, Default)]
+pub struct DesiredAmounts {
+    amounts: Mutex<HashMap<MarketAccountId, Amount>>,
+}
+
+impl DesiredAmounts {
+    /// Returns previous desired amount of market if it was set
+    pub fn set(&self, market_account_id: MarketAccountId, amount: Amount) -> Option<Amount> {
+        self.amounts.lock().insert(market_account_id, amount)
+    }
+
+    pub fn get(&self, market_account_id: MarketAccountId) -> Option<Amount> {
+        self.amounts.lock().get(&market_account_id).copied()
+    }
+
+    /// Desired amount set at runtime or `configured` amount from strategy settings
+    pub fn get_or(&self, market_account_id: MarketAccountId, configured: Amount) -> Amount {
+        self.get(market_account_id).unwrap_or(configured)
+    }
+
+    pub fn get_all(&self) -> HashMap<MarketAccountId, Amount> {
+        self.amounts.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    pub fn desired_amount_overrides_configured_one() {
+        let market_account_id = MarketAccountId::new(
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        );
+        let desired_amounts = DesiredAmounts::default();
+        assert_eq!(desired_amounts.get_or(market_account_id, dec!(1)), dec!(1));
+
+        assert_eq!(desired_amounts.set(market_account_id, dec!(2.5)), None);
+        assert_eq!(
+            desired_amounts.set(market_account_id, dec!(3)),
+            Some(dec!(2.5))
+        );
+        assert_eq!(desired_amounts.get_or(market_account_id, dec!(1)), dec!(3));
+    }
+}
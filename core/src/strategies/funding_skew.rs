@@ -0,0 +1,129 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::Price;
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::math::ConvertPercentToRate;
+use crate::orders::order::OrderSide;
+use crate::services::funding_rates::FundingRate;
+use crate::settings_values::deserialize_decimal;
+
+/// Skew of quotes around fair value by funding rate of perpetual market, so market maker leans
+/// to the side receiving funding
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FundingSkewSettings {
+    /// Weight of predicted rate in expected funding rate, the rest of weight is given to current rate
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub predicted_rate_weight: Decimal,
+    /// Skew of quotes in percents of price per 1% of expected funding rate
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub sensitivity: Decimal,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_skew: Percent,
+}
+
+/// Weighted average of current and predicted funding rates. Current rate is used if exchange
+/// doesn't provide predicted one
+pub fn expected_funding_rate(
+    funding_rate: &FundingRate,
+    predicted_rate_weight: Decimal,
+) -> Decimal {
+    let predicted_rate = funding_rate
+        .predicted_rate
+        .unwrap_or(funding_rate.current_rate);
+    let weight = predicted_rate_weight.clamp(dec!(0), dec!(1));
+
+    funding_rate.current_rate * (dec!(1) - weight) + predicted_rate * weight
+}
+
+/// Skew of quotes in percents. Positive funding rate gives negative skew: quotes are moved down
+/// to sell more and buy less, so position leans short and receives funding
+pub fn calculate_funding_skew(
+    funding_rate: &FundingRate,
+    settings: &FundingSkewSettings,
+) -> Percent {
+    let expected_rate = expected_funding_rate(funding_rate, settings.predicted_rate_weight);
+    let skew = -expected_rate * dec!(100) * settings.sensitivity;
+
+    skew.clamp(-settings.max_skew, settings.max_skew)
+}
+
+/// Moves quote price by skew. Price is rounded to symbol precision away from market
+pub fn skew_price(price: Price, side: OrderSide, skew: Percent, symbol: &Symbol) -> Price {
+    let round = match side {
+        OrderSide::Buy => Round::Floor,
+        OrderSide::Sell => Round::Ceiling,
+    };
+
+    symbol.price_round(price * (dec!(1) + skew.percent_to_rate()), round)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::symbol::Precision;
+    use rstest::rstest;
+
+    fn settings() -> FundingSkewSettings {
+        FundingSkewSettings {
+            predicted_rate_weight: dec!(0.5),
+            sensitivity: dec!(2),
+            max_skew: dec!(0.1),
+        }
+    }
+
+    fn funding_rate(current_rate: Decimal, predicted_rate: Option<Decimal>) -> FundingRate {
+        FundingRate {
+            current_rate,
+            predicted_rate,
+            next_funding_time: None,
+        }
+    }
+
+    #[rstest]
+    #[case(dec!(0.0001), Some(dec!(0.0003)), dec!(-0.04))]
+    #[case(dec!(0.0001), None, dec!(-0.02))]
+    #[case(dec!(-0.0002), Some(dec!(-0.0002)), dec!(0.04))]
+    #[case(dec!(0.001), Some(dec!(0.002)), dec!(-0.1))]
+    pub fn skew_by_funding_rate(
+        #[case] current_rate: Decimal,
+        #[case] predicted_rate: Option<Decimal>,
+        #[case] expected_skew: Percent,
+    ) {
+        let skew = calculate_funding_skew(&funding_rate(current_rate, predicted_rate), &settings());
+
+        assert_eq!(skew, expected_skew);
+    }
+
+    #[test]
+    pub fn skewed_prices_are_rounded_away_from_market() {
+        let symbol = Symbol::new(
+            false,
+            false,
+            "btc".into(),
+            "btc".into(),
+            "usdt".into(),
+            "usdt".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "btc".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(0.01) },
+        );
+
+        assert_eq!(
+            skew_price(dec!(1000), OrderSide::Buy, dec!(-0.04), &symbol),
+            dec!(999.6)
+        );
+        assert_eq!(
+            skew_price(dec!(1000.05), OrderSide::Sell, dec!(-0.04), &symbol),
+            dec!(999.7)
+        );
+    }
+}
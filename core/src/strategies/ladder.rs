@@ -0,0 +1,318 @@
+use std::cmp::Ordering;
+
+use itertools::{EitherOrBoth, Itertools};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{Amount, Price};
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::math::ConvertPercentToRate;
+use crate::misc::price_by_order_side::PriceByOrderSide;
+use crate::orders::order::{ClientOrderId, OrderSide};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SizeProfile {
+    /// All levels have amount of the first level
+    Flat,
+    /// Amount of every next level is `ratio` times amount of previous one
+    Geometric { ratio: Decimal },
+}
+
+/// Quote levels placed on each side of order book
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LadderSettings {
+    pub levels_count: usize,
+    /// Distance between neighbouring levels in percents of middle price
+    pub level_step: Percent,
+    pub first_level_amount: Amount,
+    pub size_profile: SizeProfile,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LadderOrder {
+    pub side: OrderSide,
+    pub level_index: usize,
+    pub price: Price,
+    pub amount: Amount,
+}
+
+/// Generates target orders of both sides. The first levels are placed at half of `spread`
+/// (e.g. output of spread model) from middle price, next levels are placed by `level_step` further.
+/// Prices and amounts are rounded to symbol precision away from market, levels which coincide
+/// after rounding or have amount less than min amount of symbol are skipped
+pub fn generate_ladder(
+    top: &PriceByOrderSide,
+    spread: Percent,
+    settings: &LadderSettings,
+    symbol: &Symbol,
+) -> Vec<LadderOrder> {
+    let (top_bid, top_ask) = match (top.top_bid, top.top_ask) {
+        (Some(top_bid), Some(top_ask)) => (top_bid, top_ask),
+        _ => return vec![],
+    };
+
+    let middle_price = (top_bid + top_ask) / dec!(2);
+    [OrderSide::Buy, OrderSide::Sell]
+        .into_iter()
+        .flat_map(|side| generate_side(side, middle_price, spread, settings, symbol))
+        .collect()
+}
+
+fn generate_side(
+    side: OrderSide,
+    middle_price: Price,
+    spread: Percent,
+    settings: &LadderSettings,
+    symbol: &Symbol,
+) -> Vec<LadderOrder> {
+    let half_spread_rate = spread.percent_to_rate() / dec!(2);
+    let step_rate = settings.level_step.percent_to_rate();
+
+    let mut orders: Vec<LadderOrder> = vec![];
+    let mut level_amount = settings.first_level_amount;
+    for level_index in 0..settings.levels_count {
+        let distance_rate = half_spread_rate + step_rate * Decimal::from(level_index);
+        let price = match side {
+            OrderSide::Buy => {
+                symbol.price_round(middle_price * (dec!(1) - distance_rate), Round::Floor)
+            }
+            OrderSide::Sell => {
+                symbol.price_round(middle_price * (dec!(1) + distance_rate), Round::Ceiling)
+            }
+        };
+        let amount = symbol.amount_round(level_amount, Round::Floor);
+
+        level_amount = match settings.size_profile {
+            SizeProfile::Flat => level_amount,
+            SizeProfile::Geometric { ratio } => level_amount * ratio,
+        };
+
+        let is_duplicate = orders.last().is_some_and(|x| x.price == price);
+        let is_too_small = symbol
+            .get_min_amount(price)
+            .map_or(amount <= dec!(0), |min_amount| amount < min_amount);
+        if price <= dec!(0) || is_duplicate || is_too_small {
+            continue;
+        }
+
+        orders.push(LadderOrder {
+            side,
+            level_index,
+            price,
+            amount,
+        });
+    }
+
+    orders
+}
+
+/// Resting order of ladder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LadderCurrentOrder {
+    pub client_order_id: ClientOrderId,
+    pub side: OrderSide,
+    pub price: Price,
+    /// Unfilled amount of order
+    pub amount: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LadderAction {
+    Cancel(ClientOrderId),
+    /// Exchanges without amendment of orders should execute it as cancel and create
+    Amend {
+        client_order_id: ClientOrderId,
+        target: LadderOrder,
+    },
+    Create(LadderOrder),
+}
+
+/// Converts current orders to target ladder with minimal count of actions: orders matching target
+/// exactly are kept, rest of orders are amended to rest of targets from the best price and
+/// left orders or targets are canceled or created. Cancels go first to release reserved balance
+pub fn diff_ladder(current: &[LadderCurrentOrder], target: &[LadderOrder]) -> Vec<LadderAction> {
+    let mut cancels = vec![];
+    let mut amends = vec![];
+    let mut creates = vec![];
+
+    for side in [OrderSide::Buy, OrderSide::Sell] {
+        let mut current_left = current.iter().filter(|x| x.side == side).collect_vec();
+        let mut target_left = vec![];
+        for target in target.iter().filter(|x| x.side == side) {
+            let kept = current_left
+                .iter()
+                .position(|x| x.price == target.price && x.amount == target.amount);
+            match kept {
+                Some(index) => {
+                    let _ = current_left.remove(index);
+                }
+                None => target_left.push(target),
+            }
+        }
+
+        current_left.sort_by(|a, b| compare_from_best(side, a.price, b.price));
+        target_left.sort_by(|a, b| compare_from_best(side, a.price, b.price));
+
+        for pair in current_left.into_iter().zip_longest(target_left) {
+            match pair {
+                EitherOrBoth::Both(order, target) => amends.push(LadderAction::Amend {
+                    client_order_id: order.client_order_id.clone(),
+                    target: *target,
+                }),
+                EitherOrBoth::Left(order) => {
+                    cancels.push(LadderAction::Cancel(order.client_order_id.clone()))
+                }
+                EitherOrBoth::Right(target) => creates.push(LadderAction::Create(*target)),
+            }
+        }
+    }
+
+    cancels.into_iter().chain(amends).chain(creates).collect()
+}
+
+fn compare_from_best(side: OrderSide, a: Price, b: Price) -> Ordering {
+    match side {
+        OrderSide::Buy => b.cmp(&a),
+        OrderSide::Sell => a.cmp(&b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::symbol::Precision;
+
+    fn symbol() -> Symbol {
+        Symbol::new(
+            false,
+            false,
+            "btc".into(),
+            "btc".into(),
+            "usdt".into(),
+            "usdt".into(),
+            None,
+            None,
+            Some(dec!(0.01)),
+            None,
+            None,
+            "btc".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(0.01) },
+        )
+    }
+
+    fn settings(levels_count: usize, size_profile: SizeProfile) -> LadderSettings {
+        LadderSettings {
+            levels_count,
+            level_step: dec!(0.1),
+            first_level_amount: dec!(1),
+            size_profile,
+        }
+    }
+
+    fn top() -> PriceByOrderSide {
+        PriceByOrderSide::new(Some(dec!(999)), Some(dec!(1001)))
+    }
+
+    fn prices_and_amounts(orders: &[LadderOrder], side: OrderSide) -> Vec<(Price, Amount)> {
+        orders
+            .iter()
+            .filter(|x| x.side == side)
+            .map(|x| (x.price, x.amount))
+            .collect()
+    }
+
+    #[test]
+    pub fn flat_ladder() {
+        let orders = generate_ladder(
+            &top(),
+            dec!(0.2),
+            &settings(3, SizeProfile::Flat),
+            &symbol(),
+        );
+
+        assert_eq!(
+            prices_and_amounts(&orders, OrderSide::Buy),
+            vec![
+                (dec!(999), dec!(1)),
+                (dec!(998), dec!(1)),
+                (dec!(997), dec!(1))
+            ]
+        );
+        assert_eq!(
+            prices_and_amounts(&orders, OrderSide::Sell),
+            vec![
+                (dec!(1001), dec!(1)),
+                (dec!(1002), dec!(1)),
+                (dec!(1003), dec!(1))
+            ]
+        );
+    }
+
+    #[test]
+    pub fn geometric_ladder_skips_too_small_levels() {
+        let settings = settings(4, SizeProfile::Geometric { ratio: dec!(0.1) });
+        let orders = generate_ladder(&top(), dec!(0.2), &settings, &symbol());
+
+        // amount of the last level 0.001 is less than min amount
+        assert_eq!(
+            prices_and_amounts(&orders, OrderSide::Buy),
+            vec![
+                (dec!(999), dec!(1)),
+                (dec!(998), dec!(0.1)),
+                (dec!(997), dec!(0.01))
+            ]
+        );
+    }
+
+    #[test]
+    pub fn ladder_without_top_is_empty() {
+        let top = PriceByOrderSide::new(Some(dec!(999)), None);
+        assert!(
+            generate_ladder(&top, dec!(0.2), &settings(3, SizeProfile::Flat), &symbol()).is_empty()
+        );
+    }
+
+    #[test]
+    pub fn ladder_diff_contains_minimal_actions() {
+        let target = generate_ladder(
+            &top(),
+            dec!(0.2),
+            &settings(2, SizeProfile::Flat),
+            &symbol(),
+        );
+        let order = |id: &str, side, price, amount| LadderCurrentOrder {
+            client_order_id: id.into(),
+            side,
+            price,
+            amount,
+        };
+        let current = vec![
+            order("kept", OrderSide::Buy, dec!(999), dec!(1)),
+            order("amended", OrderSide::Buy, dec!(990), dec!(1)),
+            order("canceled", OrderSide::Buy, dec!(980), dec!(1)),
+            order("sell", OrderSide::Sell, dec!(1001), dec!(0.5)),
+        ];
+
+        let actions = diff_ladder(&current, &target);
+
+        assert_eq!(
+            actions,
+            vec![
+                LadderAction::Cancel("canceled".into()),
+                LadderAction::Amend {
+                    client_order_id: "amended".into(),
+                    target: target[1],
+                },
+                LadderAction::Amend {
+                    client_order_id: "sell".into(),
+                    target: target[2],
+                },
+                LadderAction::Create(target[3]),
+            ]
+        );
+    }
+}
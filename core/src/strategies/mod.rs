@@ -1 +1,6 @@
+pub mod desired_amounts;
 pub mod disposition_strategy;
+pub mod funding_skew;
+pub mod ladder;
+pub mod obligations;
+pub mod shadow_pricing;
@@ -0,0 +1,310 @@
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{Amount, Price};
+use crate::exchanges::general::commission::Percent;
+use crate::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+use crate::settings_values::deserialize_decimal;
+
+/// Benchmark obligations of liquidity provision contract. Distances and spreads are in percents
+/// of middle price of market
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ObligationProfile {
+    /// Max distance between own best buy and sell quotes
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_spread: Percent,
+    /// Required share of time with two-sided quotes within `max_spread`
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub min_time_in_spread: Percent,
+    #[serde(default)]
+    pub levels: Vec<ObligationLevel>,
+}
+
+/// Required amount on each side within distance from middle price during share of time
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ObligationLevel {
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_distance: Percent,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub min_amount: Amount,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub min_time: Percent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ObligationLevelReport {
+    pub level: ObligationLevel,
+    /// Share of time with `min_amount` quoted on both sides within `max_distance`, percents
+    pub time_at_level: Percent,
+    /// Time-weighted average amounts quoted within `max_distance`
+    pub avg_buy_amount: Amount,
+    pub avg_sell_amount: Amount,
+    pub is_fulfilled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ObligationsReport {
+    pub measured_ms: i64,
+    /// Share of time with two-sided quotes within `max_spread`, percents
+    pub time_in_spread: Percent,
+    pub levels: Vec<ObligationLevelReport>,
+    pub is_fulfilled: bool,
+}
+
+/// State of own quotes which lasts until next sample
+struct QuotingState {
+    time: DateTime,
+    is_in_spread: bool,
+    /// Quoted buy and sell amounts for each level of profile
+    amounts_at_levels: Vec<(Amount, Amount)>,
+}
+
+/// Measures how hypothetical quotes of strategy fulfill obligation profile while replaying market
+/// history offline. Every sample lasts until the next one, time without market prices is counted
+/// as not fulfilling obligations
+pub struct ObligationsSimulator {
+    profile: ObligationProfile,
+    last_state: Option<QuotingState>,
+    measured_ms: i64,
+    in_spread_ms: i64,
+    at_levels_ms: Vec<i64>,
+    /// Sums of quoted amounts multiplied by their duration in ms for each level
+    amount_ms_at_levels: Vec<(Decimal, Decimal)>,
+}
+
+impl ObligationsSimulator {
+    pub fn new(profile: ObligationProfile) -> Self {
+        let levels_count = profile.levels.len();
+        ObligationsSimulator {
+            profile,
+            last_state: None,
+            measured_ms: 0,
+            in_spread_ms: 0,
+            at_levels_ms: vec![0; levels_count],
+            amount_ms_at_levels: vec![(dec!(0), dec!(0)); levels_count],
+        }
+    }
+
+    /// Registers market order book and own quotes of strategy at `time`. Samples have to be
+    /// ordered by time
+    pub fn add_sample(
+        &mut self,
+        time: DateTime,
+        market: &LocalOrderBookSnapshot,
+        quotes: &LocalOrderBookSnapshot,
+    ) {
+        self.close_last_state(time);
+
+        let middle_price = match (market.get_top_bid(), market.get_top_ask()) {
+            (Some((bid, _)), Some((ask, _))) => Some((bid + ask) / dec!(2)),
+            _ => None,
+        };
+
+        self.last_state = Some(match middle_price {
+            Some(middle_price) => self.quoting_state(time, middle_price, quotes),
+            None => QuotingState {
+                time,
+                is_in_spread: false,
+                amounts_at_levels: vec![(dec!(0), dec!(0)); self.profile.levels.len()],
+            },
+        });
+    }
+
+    /// Report of measurements up to `end_time`
+    pub fn finish(mut self, end_time: DateTime) -> ObligationsReport {
+        self.close_last_state(end_time);
+
+        let share = |ms: i64| match self.measured_ms {
+            0 => dec!(0),
+            measured_ms => Decimal::from(ms) / Decimal::from(measured_ms) * dec!(100),
+        };
+        let avg_amount = |amount_ms: Decimal| match self.measured_ms {
+            0 => dec!(0),
+            measured_ms => amount_ms / Decimal::from(measured_ms),
+        };
+
+        let levels: Vec<_> = self
+            .profile
+            .levels
+            .iter()
+            .zip(&self.at_levels_ms)
+            .zip(&self.amount_ms_at_levels)
+            .map(
+                |((level, &at_level_ms), &(buy_amount_ms, sell_amount_ms))| {
+                    let time_at_level = share(at_level_ms);
+                    ObligationLevelReport {
+                        level: level.clone(),
+                        time_at_level,
+                        avg_buy_amount: avg_amount(buy_amount_ms),
+                        avg_sell_amount: avg_amount(sell_amount_ms),
+                        is_fulfilled: time_at_level >= level.min_time,
+                    }
+                },
+            )
+            .collect();
+
+        let time_in_spread = share(self.in_spread_ms);
+        let is_fulfilled = self.measured_ms > 0
+            && time_in_spread >= self.profile.min_time_in_spread
+            && levels.iter().all(|x| x.is_fulfilled);
+
+        ObligationsReport {
+            measured_ms: self.measured_ms,
+            time_in_spread,
+            levels,
+            is_fulfilled,
+        }
+    }
+
+    fn quoting_state(
+        &self,
+        time: DateTime,
+        middle_price: Price,
+        quotes: &LocalOrderBookSnapshot,
+    ) -> QuotingState {
+        let max_spread = middle_price * self.profile.max_spread / dec!(100);
+        let is_in_spread = match (quotes.get_top_bid(), quotes.get_top_ask()) {
+            (Some((bid, _)), Some((ask, _))) => ask - bid <= max_spread,
+            _ => false,
+        };
+
+        let amounts_at_levels = self
+            .profile
+            .levels
+            .iter()
+            .map(|level| {
+                let distance = middle_price * level.max_distance / dec!(100);
+                let buy_amount = quotes
+                    .bids
+                    .range(middle_price - distance..)
+                    .map(|(_, amount)| amount)
+                    .sum::<Amount>();
+                let sell_amount = quotes
+                    .asks
+                    .range(..=middle_price + distance)
+                    .map(|(_, amount)| amount)
+                    .sum::<Amount>();
+                (buy_amount, sell_amount)
+            })
+            .collect();
+
+        QuotingState {
+            time,
+            is_in_spread,
+            amounts_at_levels,
+        }
+    }
+
+    fn close_last_state(&mut self, time: DateTime) {
+        let state = match self.last_state.take() {
+            Some(state) => state,
+            None => return,
+        };
+
+        let duration_ms = (time - state.time).num_milliseconds().max(0);
+        self.measured_ms += duration_ms;
+        if state.is_in_spread {
+            self.in_spread_ms += duration_ms;
+        }
+
+        let duration = Decimal::from(duration_ms);
+        for (i, (level, &(buy_amount, sell_amount))) in self
+            .profile
+            .levels
+            .iter()
+            .zip(&state.amounts_at_levels)
+            .enumerate()
+        {
+            if buy_amount >= level.min_amount && sell_amount >= level.min_amount {
+                self.at_levels_ms[i] += duration_ms;
+            }
+            let (buy_amount_ms, sell_amount_ms) = &mut self.amount_ms_at_levels[i];
+            *buy_amount_ms += buy_amount * duration;
+            *sell_amount_ms += sell_amount * duration;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::SortedOrderData;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn book(bids: &[(Price, Amount)], asks: &[(Price, Amount)]) -> LocalOrderBookSnapshot {
+        LocalOrderBookSnapshot::new(
+            asks.iter().copied().collect::<SortedOrderData>(),
+            bids.iter().copied().collect::<SortedOrderData>(),
+            Utc::now(),
+        )
+    }
+
+    fn profile() -> ObligationProfile {
+        ObligationProfile {
+            max_spread: dec!(1),
+            min_time_in_spread: dec!(70),
+            levels: vec![ObligationLevel {
+                max_distance: dec!(2),
+                min_amount: dec!(5),
+                min_time: dec!(50),
+            }],
+        }
+    }
+
+    #[test]
+    pub fn measure_time_in_spread_and_amounts_at_levels() {
+        let start = Utc.ymd(2022, 11, 1).and_hms(0, 0, 0);
+        let market = book(&[(dec!(99.9), dec!(1))], &[(dec!(100.1), dec!(1))]);
+        let mut simulator = ObligationsSimulator::new(profile());
+
+        // in spread and at level
+        let quotes = book(
+            &[(dec!(99.6), dec!(2)), (dec!(98.5), dec!(4))],
+            &[(dec!(100.4), dec!(6))],
+        );
+        simulator.add_sample(start, &market, &quotes);
+
+        // in spread only: far buy quote is out of level
+        let quotes = book(
+            &[(dec!(99.6), dec!(2)), (dec!(97), dec!(10))],
+            &[(dec!(100.4), dec!(6))],
+        );
+        simulator.add_sample(start + Duration::seconds(30), &market, &quotes);
+
+        // spread is too wide
+        let quotes = book(&[(dec!(99), dec!(10))], &[(dec!(101), dec!(10))]);
+        simulator.add_sample(start + Duration::seconds(70), &market, &quotes);
+
+        let report = simulator.finish(start + Duration::seconds(100));
+
+        assert_eq!(report.measured_ms, 100_000);
+        assert_eq!(report.time_in_spread, dec!(70));
+        let level = &report.levels[0];
+        assert_eq!(level.time_at_level, dec!(60));
+        assert_eq!(level.avg_buy_amount, dec!(5.6));
+        assert_eq!(level.avg_sell_amount, dec!(7.2));
+        assert!(level.is_fulfilled);
+        assert!(report.is_fulfilled);
+    }
+
+    #[test]
+    pub fn time_without_market_prices_isnt_fulfilling() {
+        let start = Utc.ymd(2022, 11, 1).and_hms(0, 0, 0);
+        let quotes = book(&[(dec!(99.6), dec!(10))], &[(dec!(100.4), dec!(10))]);
+        let mut simulator = ObligationsSimulator::new(profile());
+
+        simulator.add_sample(start, &book(&[], &[]), &quotes);
+        simulator.add_sample(
+            start + Duration::seconds(50),
+            &book(&[(dec!(99.9), dec!(1))], &[(dec!(100.1), dec!(1))]),
+            &quotes,
+        );
+        let report = simulator.finish(start + Duration::seconds(100));
+
+        assert_eq!(report.time_in_spread, dec!(50));
+        assert_eq!(report.levels[0].time_at_level, dec!(50));
+        assert!(!report.is_fulfilled);
+    }
+}
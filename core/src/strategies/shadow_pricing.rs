@@ -0,0 +1,304 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::Duration;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::disposition_execution::{PriceSlot, TradingContext};
+use crate::exchanges::common::{Amount, ExchangeAccountId, MarketId, Price};
+use crate::explanation::Explanation;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::order::{OrderSide, OrderSnapshot};
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use crate::strategies::disposition_strategy::DispositionStrategy;
+
+/// How often comparison of live and shadow models is written to log (in evaluated shadow quotes)
+const STATS_LOGGING_PERIOD: u64 = 100;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct HypotheticalQuote {
+    market_id: MarketId,
+    side: OrderSide,
+    price: Price,
+    amount: Amount,
+    creation_time: DateTime,
+    is_filled: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize)]
+pub struct QuotesEvaluationStats {
+    pub quotes_count: u64,
+    pub filled_count: u64,
+    /// Sum of profit of filled quotes measured by middle price after evaluation horizon
+    pub markout: Decimal,
+}
+
+/// Tracks quotes of one model and evaluates them against realized market moves:
+/// quote is considered filled if market crossed its price during evaluation horizon
+struct QuotesEvaluator {
+    evaluation_horizon: Duration,
+    pending: Vec<HypotheticalQuote>,
+    stats: QuotesEvaluationStats,
+}
+
+impl QuotesEvaluator {
+    fn new(evaluation_horizon: Duration) -> Self {
+        QuotesEvaluator {
+            evaluation_horizon,
+            pending: vec![],
+            stats: QuotesEvaluationStats::default(),
+        }
+    }
+
+    fn register(&mut self, model: &str, trading_context: &TradingContext, now: DateTime) {
+        for (side, trading_context_by_side) in trading_context.by_side.iter() {
+            for trade_cycle in trading_context_by_side
+                .estimating
+                .iter()
+                .filter_map(|x| x.value.as_ref())
+            {
+                let disposition = &trade_cycle.disposition;
+                let is_already_pending = self.pending.iter().any(|x| {
+                    !x.is_filled
+                        && x.side == side
+                        && x.price == disposition.price()
+                        && x.market_id == disposition.market_id()
+                });
+                if is_already_pending {
+                    continue;
+                }
+
+                log::trace!(
+                    "{model} quote {} {side} {} ({})",
+                    disposition.market_id().currency_pair,
+                    disposition.price(),
+                    disposition.amount()
+                );
+
+                self.pending.push(HypotheticalQuote {
+                    market_id: disposition.market_id(),
+                    side,
+                    price: disposition.price(),
+                    amount: disposition.amount(),
+                    creation_time: now,
+                    is_filled: false,
+                });
+            }
+        }
+    }
+
+    fn evaluate(&mut self, local_snapshots_service: &LocalSnapshotsService, now: DateTime) {
+        let evaluation_horizon = self.evaluation_horizon;
+        let stats = &mut self.stats;
+
+        self.pending.retain_mut(|quote| {
+            let snapshot = match local_snapshots_service.get_snapshot(quote.market_id) {
+                None => return true,
+                Some(v) => v,
+            };
+
+            if !quote.is_filled {
+                quote.is_filled = match quote.side {
+                    OrderSide::Buy => snapshot.get_top_ask().map(|x| x.0 <= quote.price),
+                    OrderSide::Sell => snapshot.get_top_bid().map(|x| x.0 >= quote.price),
+                }
+                .unwrap_or(false);
+            }
+
+            if quote.creation_time + evaluation_horizon > now {
+                return true;
+            }
+
+            stats.quotes_count += 1;
+            if quote.is_filled {
+                stats.filled_count += 1;
+
+                if let Some(middle_price) = snapshot.calculate_middle_price(quote.market_id) {
+                    let price_diff = match quote.side {
+                        OrderSide::Buy => middle_price - quote.price,
+                        OrderSide::Sell => quote.price - middle_price,
+                    };
+                    stats.markout += price_diff * quote.amount;
+                }
+            }
+
+            false
+        });
+    }
+}
+
+/// Runs alternative (shadow) quoting model in parallel with the live one.
+/// Trading context of live model is used for trading, shadow quotes are never executed:
+/// they are only logged and evaluated against realized market moves, so models can be compared
+pub struct ShadowPricingStrategy {
+    live: Box<dyn DispositionStrategy>,
+    shadow: Box<dyn DispositionStrategy>,
+    live_evaluator: QuotesEvaluator,
+    shadow_evaluator: QuotesEvaluator,
+    last_logged_quotes_count: u64,
+}
+
+impl ShadowPricingStrategy {
+    pub fn new(
+        live: Box<dyn DispositionStrategy>,
+        shadow: Box<dyn DispositionStrategy>,
+        evaluation_horizon: Duration,
+    ) -> Self {
+        ShadowPricingStrategy {
+            live,
+            shadow,
+            live_evaluator: QuotesEvaluator::new(evaluation_horizon),
+            shadow_evaluator: QuotesEvaluator::new(evaluation_horizon),
+            last_logged_quotes_count: 0,
+        }
+    }
+
+    /// Evaluation stats of live and shadow models
+    pub fn stats(&self) -> (QuotesEvaluationStats, QuotesEvaluationStats) {
+        (self.live_evaluator.stats, self.shadow_evaluator.stats)
+    }
+
+    fn log_stats_if_needed(&mut self) {
+        let (live, shadow) = self.stats();
+        if shadow.quotes_count < self.last_logged_quotes_count + STATS_LOGGING_PERIOD {
+            return;
+        }
+        self.last_logged_quotes_count = shadow.quotes_count;
+
+        log::info!("Shadow pricing stats: live {live:?}, shadow {shadow:?}");
+    }
+}
+
+impl DispositionStrategy for ShadowPricingStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        now: DateTime,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        self.live_evaluator.evaluate(local_snapshots_service, now);
+        self.shadow_evaluator.evaluate(local_snapshots_service, now);
+        self.log_stats_if_needed();
+
+        let mut shadow_explanation = explanation.clone();
+        if let Some(shadow_trading_context) = self.shadow.calculate_trading_context(
+            now,
+            local_snapshots_service,
+            &mut shadow_explanation,
+        ) {
+            self.shadow_evaluator
+                .register("Shadow", &shadow_trading_context, now);
+        }
+
+        let trading_context =
+            self.live
+                .calculate_trading_context(now, local_snapshots_service, explanation);
+        if let Some(trading_context) = &trading_context {
+            self.live_evaluator.register("Live", trading_context, now);
+        }
+
+        trading_context
+    }
+
+    fn handle_order_fill(
+        &self,
+        cloned_order: &Arc<OrderSnapshot>,
+        price_slot: &PriceSlot,
+        target_eai: ExchangeAccountId,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        self.live
+            .handle_order_fill(cloned_order, price_slot, target_eai, cancellation_token)
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        self.live.configuration_descriptor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disposition_execution::{TradeCycle, TradeDisposition, TradingContextBySide};
+    use crate::exchanges::common::{CurrencyPair, MarketAccountId};
+    use crate::explanation::WithExplanation;
+    use crate::order_book::order_book_data::OrderBookData;
+    use crate::order_book_data;
+    use crate::orders::order::OrderRole;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+    use std::collections::HashMap;
+
+    fn market_account_id() -> MarketAccountId {
+        MarketAccountId::new(
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    fn side_context(side: OrderSide, price: Price) -> TradingContextBySide {
+        TradingContextBySide {
+            max_amount: dec!(1),
+            estimating: vec![WithExplanation {
+                value: Some(TradeCycle {
+                    order_role: OrderRole::Maker,
+                    strategy_name: "test".to_string(),
+                    disposition: TradeDisposition::new(market_account_id(), side, price, dec!(1)),
+                }),
+                explanation: Explanation::default(),
+            }],
+        }
+    }
+
+    fn snapshots(order_book_data: OrderBookData) -> LocalSnapshotsService {
+        let mut local_snapshots = HashMap::new();
+        local_snapshots.insert(
+            market_account_id().market_id(),
+            order_book_data.to_local_order_book_snapshot(),
+        );
+        LocalSnapshotsService::new(local_snapshots)
+    }
+
+    #[test]
+    pub fn evaluate_quotes_by_realized_market_moves() {
+        let mut evaluator = QuotesEvaluator::new(Duration::seconds(10));
+        let now = Utc::now();
+
+        let trading_context = TradingContext::new(
+            side_context(OrderSide::Buy, dec!(99)),
+            side_context(OrderSide::Sell, dec!(101)),
+        );
+        evaluator.register("test", &trading_context, now);
+        // the same quotes should not be registered twice
+        evaluator.register("test", &trading_context, now);
+
+        // market moves down and crosses buy quote
+        let crossed = snapshots(order_book_data![
+            dec!(98.5) => dec!(1),
+            ;
+            dec!(98) => dec!(1),
+        ]);
+        evaluator.evaluate(&crossed, now + Duration::seconds(1));
+        assert_eq!(evaluator.stats, QuotesEvaluationStats::default());
+
+        let after_horizon = snapshots(order_book_data![
+            dec!(100.5) => dec!(1),
+            ;
+            dec!(99.5) => dec!(1),
+        ]);
+        evaluator.evaluate(&after_horizon, now + Duration::seconds(11));
+
+        assert_eq!(
+            evaluator.stats,
+            QuotesEvaluationStats {
+                quotes_count: 2,
+                filled_count: 1,
+                markout: dec!(1),
+            }
+        );
+        assert!(evaluator.pending.is_empty());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod rebalancing;
+pub mod withdrawals;
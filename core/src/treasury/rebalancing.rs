@@ -0,0 +1,488 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use itertools::Itertools;
+use mmb_database::impl_event;
+use mmb_database::postgres_db::events::TableName;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::database::events::recorder::EventRecorder;
+use crate::exchanges::common::{Amount, CurrencyCode, ExchangeAccountId};
+use crate::misc::time::time_manager;
+use crate::settings::{RebalancingSettings, RebalancingTargetSettings};
+use crate::treasury::withdrawals::{WithdrawalId, WithdrawalStatus, WithdrawalsService};
+
+pub type RebalancingPlanId = Uuid;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+pub enum RebalancingPlanStatus {
+    WaitingApproval,
+    /// Withdrawals of plan are being requested
+    Executing,
+    /// Withdrawals of plan are requested via withdrawals service
+    Executed,
+    /// Some withdrawals are requested, but requesting of the next one failed
+    PartiallyExecuted,
+    /// Requesting of the first withdrawal failed
+    Failed,
+    Rejected,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+pub enum TransferStatus {
+    Planned,
+    /// Withdrawal is being requested. Transfer stays in this status if engine stopped meanwhile
+    Requesting,
+    Requested,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedTransfer {
+    pub currency_code: CurrencyCode,
+    pub from: ExchangeAccountId,
+    pub to: ExchangeAccountId,
+    pub address: String,
+    /// Amount withdrawn from source account including fee
+    pub amount: Amount,
+    pub fee: Amount,
+    pub estimated_arrival_time: DateTime,
+    pub status: TransferStatus,
+    pub withdrawal_id: Option<WithdrawalId>,
+    pub withdrawal_status: Option<WithdrawalStatus>,
+    pub error: Option<String>,
+}
+
+/// Rebalancing plan. Every change of it is saved to database as new revision
+#[derive(Debug, Clone, Serialize)]
+pub struct RebalancingPlan {
+    revision: u64,
+    pub plan_id: RebalancingPlanId,
+    pub creation_time: DateTime,
+    pub status: RebalancingPlanStatus,
+    pub transfers: Vec<PlannedTransfer>,
+    /// Deviations from target distribution which can't be fixed by configured transfer routes
+    pub unresolved: Vec<String>,
+    pub approved_by: Option<String>,
+    pub update_time: DateTime,
+}
+
+impl_event!(&RebalancingPlan, "rebalancing_plans");
+
+pub type BalancesByExchange = HashMap<ExchangeAccountId, HashMap<CurrencyCode, Amount>>;
+
+/// Plans transfers of currencies between exchange accounts to reach target distribution of
+/// balances. Plan is executed only after approval of operator, its transfers are requested as
+/// withdrawals, so they pass whitelist and approval checks of withdrawals service
+pub struct RebalancingPlanner {
+    settings: RebalancingSettings,
+    withdrawals: Arc<WithdrawalsService>,
+    event_recorder: Arc<EventRecorder>,
+    plans: Mutex<HashMap<RebalancingPlanId, RebalancingPlan>>,
+}
+
+impl RebalancingPlanner {
+    pub fn new(
+        settings: RebalancingSettings,
+        withdrawals: Arc<WithdrawalsService>,
+        event_recorder: Arc<EventRecorder>,
+    ) -> Result<Arc<Self>> {
+        for target in &settings.targets {
+            let total_share: Amount = target.shares.iter().map(|x| x.share).sum();
+            if total_share != dec!(100) {
+                bail!(
+                    "Sum of target shares of {} should be 100%, but it's {total_share}%",
+                    target.currency_code
+                );
+            }
+        }
+
+        Ok(Arc::new(RebalancingPlanner {
+            settings,
+            withdrawals,
+            event_recorder,
+            plans: Default::default(),
+        }))
+    }
+
+    pub fn create_plan(&self, balances: &BalancesByExchange) -> RebalancingPlan {
+        let now = time_manager::now();
+        let mut transfers = vec![];
+        let mut unresolved = vec![];
+        for target in &self.settings.targets {
+            self.plan_currency(target, balances, now, &mut transfers, &mut unresolved);
+        }
+
+        let mut plan = RebalancingPlan {
+            revision: 0,
+            plan_id: Uuid::new_v4(),
+            creation_time: now,
+            status: RebalancingPlanStatus::WaitingApproval,
+            transfers,
+            unresolved,
+            approved_by: None,
+            update_time: now,
+        };
+        self.save(&mut plan);
+        log::info!(
+            "Rebalancing plan {} is created with {} transfers",
+            plan.plan_id,
+            plan.transfers.len()
+        );
+
+        let _ = self.plans.lock().insert(plan.plan_id, plan.clone());
+        plan
+    }
+
+    /// Deficits are covered by surpluses of other accounts starting from the largest ones
+    fn plan_currency(
+        &self,
+        target: &RebalancingTargetSettings,
+        balances: &BalancesByExchange,
+        now: DateTime,
+        transfers: &mut Vec<PlannedTransfer>,
+        unresolved: &mut Vec<String>,
+    ) {
+        let currency_code = target.currency_code;
+        let balance_of = |exchange_account_id| {
+            balances
+                .get(&exchange_account_id)
+                .and_then(|x| x.get(&currency_code))
+                .copied()
+                .unwrap_or(dec!(0))
+        };
+        let total: Amount = target
+            .shares
+            .iter()
+            .map(|x| balance_of(x.exchange_account_id))
+            .sum();
+        let tolerance = total * self.settings.tolerance / dec!(100);
+
+        let mut surpluses = HashMap::new();
+        let mut deficits = vec![];
+        for share in &target.shares {
+            let diff = balance_of(share.exchange_account_id) - total * share.share / dec!(100);
+            if diff > dec!(0) {
+                let _ = surpluses.insert(share.exchange_account_id, diff);
+            } else if -diff > tolerance {
+                deficits.push((share.exchange_account_id, -diff));
+            }
+        }
+        deficits.sort_by_key(|x| std::cmp::Reverse(x.1));
+
+        for (to, mut deficit) in deficits {
+            let routes = self
+                .settings
+                .routes
+                .iter()
+                .filter(|x| x.currency_code == currency_code && x.to == to)
+                .sorted_by(|a, b| {
+                    let surplus = |x: ExchangeAccountId| surpluses.get(&x).copied();
+                    surplus(b.from)
+                        .cmp(&surplus(a.from))
+                        .then(a.fee.cmp(&b.fee))
+                })
+                .collect_vec();
+
+            for route in routes {
+                let surplus = match surpluses.get_mut(&route.from) {
+                    Some(surplus) if *surplus > route.fee => surplus,
+                    _ => continue,
+                };
+
+                let amount = (deficit + route.fee).min(*surplus);
+                if amount < route.min_amount {
+                    continue;
+                }
+
+                *surplus -= amount;
+                deficit -= amount - route.fee;
+                transfers.push(PlannedTransfer {
+                    currency_code,
+                    from: route.from,
+                    to,
+                    address: route.address.clone(),
+                    amount,
+                    fee: route.fee,
+                    estimated_arrival_time: now
+                        + chrono::Duration::minutes(route.transfer_time_mins as i64),
+                    status: TransferStatus::Planned,
+                    withdrawal_id: None,
+                    withdrawal_status: None,
+                    error: None,
+                });
+
+                if deficit <= tolerance {
+                    break;
+                }
+            }
+
+            if deficit > tolerance {
+                unresolved.push(format!(
+                    "Deficit {deficit} {currency_code} on {to} can't be covered by transfer routes"
+                ));
+            }
+        }
+    }
+
+    /// Transfers of plan are requested as withdrawals on behalf of approving operator one by one.
+    /// Every transfer is saved before its withdrawal is requested. Requesting stops on the first
+    /// failed transfer, then plan is partially executed or failed and isn't approved again
+    pub fn approve(&self, plan_id: RebalancingPlanId, operator: String) -> Result<RebalancingPlan> {
+        let mut plans = self.plans.lock();
+        let plan = match plans.get_mut(&plan_id) {
+            Some(plan) if plan.status == RebalancingPlanStatus::WaitingApproval => plan,
+            _ => bail!("Rebalancing plan {plan_id} isn't waiting for approval"),
+        };
+
+        plan.status = RebalancingPlanStatus::Executing;
+        plan.approved_by = Some(operator.clone());
+
+        for index in 0..plan.transfers.len() {
+            plan.transfers[index].status = TransferStatus::Requesting;
+            let result = self.try_save(plan).and_then(|_| {
+                let transfer = &plan.transfers[index];
+                self.withdrawals.request(
+                    transfer.from,
+                    transfer.currency_code,
+                    transfer.amount,
+                    transfer.address.clone(),
+                    operator.clone(),
+                )
+            });
+
+            let transfer = &mut plan.transfers[index];
+            match result {
+                Ok(withdrawal) => {
+                    transfer.withdrawal_id = Some(withdrawal.withdrawal_id);
+                    transfer.withdrawal_status = Some(withdrawal.status);
+                    if withdrawal.status == WithdrawalStatus::Rejected {
+                        transfer.status = TransferStatus::Failed;
+                        transfer.error = withdrawal.reason;
+                        break;
+                    }
+                    transfer.status = TransferStatus::Requested;
+                }
+                Err(err) => {
+                    transfer.status = TransferStatus::Failed;
+                    transfer.error = Some(format!("{err:?}"));
+                    break;
+                }
+            }
+        }
+
+        let requested_count = plan
+            .transfers
+            .iter()
+            .filter(|x| x.status == TransferStatus::Requested)
+            .count();
+        plan.status = if requested_count == plan.transfers.len() {
+            RebalancingPlanStatus::Executed
+        } else if requested_count > 0 {
+            RebalancingPlanStatus::PartiallyExecuted
+        } else {
+            RebalancingPlanStatus::Failed
+        };
+        self.save(plan);
+
+        match plan.status {
+            RebalancingPlanStatus::Executed => log::info!("Rebalancing plan {plan_id} is executed"),
+            status => log::error!(
+                "Rebalancing plan {plan_id} is {status:?}: {requested_count} of {} transfers are requested",
+                plan.transfers.len()
+            ),
+        }
+
+        Ok(plan.clone())
+    }
+
+    pub fn reject(&self, plan_id: RebalancingPlanId, operator: String) -> Result<()> {
+        let mut plans = self.plans.lock();
+        match plans.get_mut(&plan_id) {
+            Some(plan) if plan.status == RebalancingPlanStatus::WaitingApproval => {
+                plan.status = RebalancingPlanStatus::Rejected;
+                self.save(plan);
+                log::info!("Rebalancing plan {plan_id} is rejected by {operator}");
+                Ok(())
+            }
+            _ => bail!("Rebalancing plan {plan_id} isn't waiting for approval"),
+        }
+    }
+
+    pub fn plans(&self) -> Vec<RebalancingPlan> {
+        self.plans
+            .lock()
+            .values()
+            .sorted_by_key(|x| x.creation_time)
+            .cloned()
+            .collect()
+    }
+
+    fn try_save(&self, plan: &mut RebalancingPlan) -> Result<()> {
+        plan.revision += 1;
+        plan.update_time = time_manager::now();
+
+        self.event_recorder
+            .save(&*plan)
+            .context("in RebalancingPlanner::save()")
+    }
+
+    fn save(&self, plan: &mut RebalancingPlan) {
+        if let Err(err) = self.try_save(plan) {
+            log::error!(
+                "Failed to save revision {} of rebalancing plan {}: {err:?}",
+                plan.revision,
+                plan.plan_id
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::events::recorder::EventRecorder;
+    use crate::settings::{
+        RebalancingShareSettings, TransferRouteSettings, WhitelistedAddress, WithdrawalSettings,
+    };
+
+    fn account(number: u8) -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", number)
+    }
+
+    fn route(from: u8, to: u8, fee: Amount, min_amount: Amount) -> TransferRouteSettings {
+        TransferRouteSettings {
+            currency_code: "usdt".into(),
+            from: account(from),
+            to: account(to),
+            address: format!("address{to}"),
+            fee,
+            min_amount,
+            transfer_time_mins: 30,
+        }
+    }
+
+    async fn planner(routes: Vec<TransferRouteSettings>) -> Arc<RebalancingPlanner> {
+        let event_recorder = EventRecorder::start(None).await.expect("in test");
+        let withdrawal_settings = (0..3)
+            .map(|from| {
+                let settings = WithdrawalSettings {
+                    whitelist: (0..3)
+                        .map(|to| WhitelistedAddress {
+                            currency_code: "usdt".into(),
+                            address: format!("address{to}"),
+                        })
+                        .collect(),
+                    require_approval: true,
+                };
+                (account(from), settings)
+            })
+            .collect();
+
+        let settings = RebalancingSettings {
+            targets: vec![RebalancingTargetSettings {
+                currency_code: "usdt".into(),
+                shares: (0..3)
+                    .map(|x| RebalancingShareSettings {
+                        exchange_account_id: account(x),
+                        share: [dec!(50), dec!(25), dec!(25)][x as usize],
+                    })
+                    .collect(),
+            }],
+            routes,
+            tolerance: dec!(1),
+        };
+
+        RebalancingPlanner::new(
+            settings,
+            WithdrawalsService::new(withdrawal_settings, None, event_recorder.clone())
+                .expect("in test"),
+            event_recorder,
+        )
+        .expect("in test")
+    }
+
+    fn balances(amounts: [Amount; 3]) -> BalancesByExchange {
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(i, &amount)| (account(i as u8), HashMap::from([("usdt".into(), amount)])))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn deficits_are_covered_by_surpluses_with_fees() {
+        let planner = planner(vec![
+            route(1, 0, dec!(1), dec!(10)),
+            route(2, 0, dec!(2), dec!(10)),
+        ])
+        .await;
+
+        // targets are 500, 250, 250
+        let plan = planner.create_plan(&balances([dec!(300), dec!(400), dec!(300)]));
+
+        assert_eq!(plan.transfers.len(), 2);
+        assert_eq!(plan.transfers[0].from, account(1));
+        assert_eq!(plan.transfers[0].amount, dec!(150));
+        // the rest of deficit is 51, but surplus is only 50
+        assert_eq!(plan.transfers[1].from, account(2));
+        assert_eq!(plan.transfers[1].amount, dec!(50));
+        assert!(plan.unresolved.is_empty());
+    }
+
+    #[tokio::test]
+    async fn deficit_without_route_is_unresolved() {
+        let planner = planner(vec![route(1, 0, dec!(1), dec!(1000))]).await;
+
+        let plan = planner.create_plan(&balances([dec!(300), dec!(400), dec!(300)]));
+
+        assert!(plan.transfers.is_empty());
+        assert_eq!(plan.unresolved.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn approved_plan_requests_withdrawals() {
+        let planner = planner(vec![route(1, 0, dec!(1), dec!(10))]).await;
+        let plan = planner.create_plan(&balances([dec!(400), dec!(350), dec!(250)]));
+
+        let executed = planner
+            .approve(plan.plan_id, "operator1".to_owned())
+            .expect("in test");
+
+        assert_eq!(executed.status, RebalancingPlanStatus::Executed);
+        assert_eq!(executed.transfers[0].status, TransferStatus::Requested);
+        assert_eq!(
+            executed.transfers[0].withdrawal_status,
+            Some(WithdrawalStatus::WaitingApproval)
+        );
+        assert!(planner
+            .approve(plan.plan_id, "operator1".to_owned())
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn plan_is_partially_executed_if_withdrawal_is_rejected() {
+        let mut not_whitelisted = route(2, 0, dec!(2), dec!(10));
+        not_whitelisted.address = "unknown_address".to_owned();
+        let planner = planner(vec![route(1, 0, dec!(1), dec!(10)), not_whitelisted]).await;
+        let plan = planner.create_plan(&balances([dec!(300), dec!(400), dec!(300)]));
+        assert_eq!(plan.transfers.len(), 2);
+
+        let executed = planner
+            .approve(plan.plan_id, "operator1".to_owned())
+            .expect("in test");
+
+        assert_eq!(executed.status, RebalancingPlanStatus::PartiallyExecuted);
+        assert_eq!(executed.transfers[0].status, TransferStatus::Requested);
+        assert_eq!(executed.transfers[1].status, TransferStatus::Failed);
+        assert!(executed.transfers[1].error.is_some());
+        assert!(planner
+            .approve(plan.plan_id, "operator1".to_owned())
+            .is_err());
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use mmb_database::impl_event;
+use mmb_database::postgres_db::events::TableName;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::database::events::recorder::EventRecorder;
+use crate::exchanges::common::{Amount, CurrencyCode, ExchangeAccountId};
+use crate::misc::state_file::StateFile;
+use crate::misc::time::time_manager;
+use crate::settings::{WithdrawalApprovalsSettings, WithdrawalSettings};
+
+pub type WithdrawalId = Uuid;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum WithdrawalStatus {
+    /// Waiting for confirmation of second operator
+    WaitingApproval,
+    /// Allowed to be sent to exchange
+    Approved,
+    Rejected,
+}
+
+/// Withdrawal request. Every change of it is saved to audit log as new revision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WithdrawalRequest {
+    revision: u64,
+    pub withdrawal_id: WithdrawalId,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    pub amount: Amount,
+    pub address: String,
+    pub requested_by: String,
+    pub approved_by: Option<String>,
+    pub status: WithdrawalStatus,
+    /// Reason of rejection
+    pub reason: Option<String>,
+    pub update_time: DateTime,
+}
+
+impl_event!(&WithdrawalRequest, "withdrawals_audit");
+
+/// Withdrawals which aren't sent to exchange yet
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct WithdrawalsState {
+    waiting_approval: HashMap<WithdrawalId, WithdrawalRequest>,
+    /// Withdrawals that aren't taken for sending yet
+    approved: Vec<WithdrawalRequest>,
+}
+
+/// Guards withdrawals: destination address should be whitelisted in exchange settings and,
+/// if it's required by settings, withdrawal should be approved by another operator via control API.
+/// Approved withdrawals are sent to exchange only after they are taken by `take_approved`.
+/// Every change is saved before it's applied, so withdrawals aren't lost or approved twice after restart
+pub struct WithdrawalsService {
+    settings: HashMap<ExchangeAccountId, WithdrawalSettings>,
+    event_recorder: Arc<EventRecorder>,
+    state_file: Option<StateFile>,
+    state: Mutex<WithdrawalsState>,
+}
+
+impl WithdrawalsService {
+    pub fn new(
+        settings: HashMap<ExchangeAccountId, WithdrawalSettings>,
+        approvals_settings: Option<&WithdrawalApprovalsSettings>,
+        event_recorder: Arc<EventRecorder>,
+    ) -> Result<Arc<Self>> {
+        let state_file = approvals_settings.map(|x| StateFile::new(x.state_file.clone()));
+        let state = match &state_file {
+            Some(state_file) => state_file
+                .load()
+                .context("Unable to load withdrawals state")?
+                .unwrap_or_default(),
+            None => WithdrawalsState::default(),
+        };
+
+        Ok(Arc::new(WithdrawalsService {
+            settings,
+            event_recorder,
+            state_file,
+            state: Mutex::new(state),
+        }))
+    }
+
+    /// Register withdrawal request. Request with status `Approved` is sent to exchange after it's
+    /// taken by `take_approved`
+    pub fn request(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        currency_code: CurrencyCode,
+        amount: Amount,
+        address: String,
+        operator: String,
+    ) -> Result<WithdrawalRequest> {
+        let mut request = WithdrawalRequest {
+            revision: 0,
+            withdrawal_id: Uuid::new_v4(),
+            exchange_account_id,
+            currency_code,
+            amount,
+            address,
+            requested_by: operator,
+            approved_by: None,
+            status: WithdrawalStatus::WaitingApproval,
+            reason: None,
+            update_time: time_manager::now(),
+        };
+
+        let settings = match self.settings.get(&exchange_account_id) {
+            Some(settings) => settings,
+            None => {
+                let reason = format!("Withdrawals aren't configured for {exchange_account_id}");
+                return self.reject_request(request, reason);
+            }
+        };
+
+        if !settings.is_whitelisted(currency_code, &request.address) {
+            let reason = format!(
+                "Address {} isn't whitelisted for {currency_code} on {exchange_account_id}",
+                request.address
+            );
+            return self.reject_request(request, reason);
+        }
+
+        let mut state = self.state.lock();
+        let mut new_state = state.clone();
+        if settings.require_approval {
+            self.save(&mut request)?;
+            let _ = new_state
+                .waiting_approval
+                .insert(request.withdrawal_id, request.clone());
+        } else {
+            request.status = WithdrawalStatus::Approved;
+            self.save(&mut request)?;
+            new_state.approved.push(request.clone());
+        }
+        self.write_state(&new_state)?;
+        *state = new_state;
+        drop(state);
+
+        if request.status == WithdrawalStatus::WaitingApproval {
+            log::warn!(
+                "Withdrawal {} of {amount} {currency_code} from {exchange_account_id} is waiting for approval",
+                request.withdrawal_id
+            );
+        }
+
+        Ok(request)
+    }
+
+    /// Withdrawal can be approved only by operator different from requester
+    pub fn approve(
+        &self,
+        withdrawal_id: WithdrawalId,
+        operator: String,
+    ) -> Result<WithdrawalRequest> {
+        let mut state = self.state.lock();
+        let mut request = match state.waiting_approval.get(&withdrawal_id) {
+            None => bail!("Withdrawal {withdrawal_id} isn't waiting for approval"),
+            Some(v) => v.clone(),
+        };
+
+        if request.requested_by == operator {
+            bail!("Withdrawal {withdrawal_id} can't be approved by the same operator {operator} who requested it");
+        }
+
+        request.status = WithdrawalStatus::Approved;
+        request.approved_by = Some(operator);
+        self.save(&mut request)?;
+
+        let mut new_state = state.clone();
+        let _ = new_state.waiting_approval.remove(&withdrawal_id);
+        new_state.approved.push(request.clone());
+        self.write_state(&new_state)?;
+        *state = new_state;
+
+        log::info!("Withdrawal {withdrawal_id} was approved");
+        Ok(request)
+    }
+
+    /// Take approved withdrawals for sending to exchange
+    pub fn take_approved(&self) -> Result<Vec<WithdrawalRequest>> {
+        let mut state = self.state.lock();
+        if state.approved.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut new_state = state.clone();
+        let approved = std::mem::take(&mut new_state.approved);
+        self.write_state(&new_state)?;
+        *state = new_state;
+
+        Ok(approved)
+    }
+
+    pub fn reject(&self, withdrawal_id: WithdrawalId, operator: String) -> Result<()> {
+        let mut state = self.state.lock();
+        let request = match state.waiting_approval.get(&withdrawal_id) {
+            None => bail!("Withdrawal {withdrawal_id} isn't waiting for approval"),
+            Some(v) => v.clone(),
+        };
+
+        let _ = self.reject_request(request, format!("Rejected by operator {operator}"))?;
+
+        let mut new_state = state.clone();
+        let _ = new_state.waiting_approval.remove(&withdrawal_id);
+        self.write_state(&new_state)?;
+        *state = new_state;
+
+        Ok(())
+    }
+
+    pub fn waiting_approval(&self) -> Vec<WithdrawalRequest> {
+        self.state
+            .lock()
+            .waiting_approval
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    fn reject_request(
+        &self,
+        mut request: WithdrawalRequest,
+        reason: String,
+    ) -> Result<WithdrawalRequest> {
+        log::error!("Withdrawal {} is rejected: {reason}", request.withdrawal_id);
+
+        request.status = WithdrawalStatus::Rejected;
+        request.reason = Some(reason);
+        self.save(&mut request)?;
+
+        Ok(request)
+    }
+
+    fn save(&self, request: &mut WithdrawalRequest) -> Result<()> {
+        request.revision += 1;
+        request.update_time = time_manager::now();
+
+        self.event_recorder
+            .save(&*request)
+            .context("in WithdrawalsService::save()")
+    }
+
+    /// State is written under lock of state, so it's applied only if it's saved
+    fn write_state(&self, state: &WithdrawalsState) -> Result<()> {
+        let state_file = match &self.state_file {
+            Some(state_file) => state_file,
+            None => return Ok(()),
+        };
+
+        let (version, content) = state_file.serialize(state)?;
+        state_file
+            .write(version, &content)
+            .context("Unable to save withdrawals state")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::WhitelistedAddress;
+    use rust_decimal_macros::dec;
+
+    const ADDRESS: &str = "whitelisted_address";
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", 0)
+    }
+
+    async fn withdrawals_service(require_approval: bool) -> Arc<WithdrawalsService> {
+        withdrawals_service_with_state(require_approval, None).await
+    }
+
+    async fn withdrawals_service_with_state(
+        require_approval: bool,
+        approvals_settings: Option<&WithdrawalApprovalsSettings>,
+    ) -> Arc<WithdrawalsService> {
+        let event_recorder = EventRecorder::start(None).await.expect("in test");
+        let settings = WithdrawalSettings {
+            whitelist: vec![WhitelistedAddress {
+                currency_code: "btc".into(),
+                address: ADDRESS.to_owned(),
+            }],
+            require_approval,
+        };
+
+        WithdrawalsService::new(
+            HashMap::from([(exchange_account_id(), settings)]),
+            approvals_settings,
+            event_recorder,
+        )
+        .expect("in test")
+    }
+
+    fn request(
+        service: &WithdrawalsService,
+        exchange_account_id: ExchangeAccountId,
+        address: &str,
+    ) -> WithdrawalRequest {
+        service
+            .request(
+                exchange_account_id,
+                "btc".into(),
+                dec!(1),
+                address.to_owned(),
+                "operator1".to_owned(),
+            )
+            .expect("in test")
+    }
+
+    #[tokio::test]
+    async fn not_whitelisted_address_is_rejected() {
+        let service = withdrawals_service(false).await;
+
+        let rejected = request(&service, exchange_account_id(), "unknown_address");
+        assert_eq!(rejected.status, WithdrawalStatus::Rejected);
+
+        let rejected = request(&service, ExchangeAccountId::new("Binance", 1), ADDRESS);
+        assert_eq!(rejected.status, WithdrawalStatus::Rejected);
+    }
+
+    #[tokio::test]
+    async fn approval_is_not_required() {
+        let service = withdrawals_service(false).await;
+
+        let approved = request(&service, exchange_account_id(), ADDRESS);
+
+        assert_eq!(approved.status, WithdrawalStatus::Approved);
+        assert!(service.waiting_approval().is_empty());
+
+        let taken = service.take_approved().expect("in test");
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].withdrawal_id, approved.withdrawal_id);
+    }
+
+    #[tokio::test]
+    async fn approval_by_second_operator() {
+        let service = withdrawals_service(true).await;
+
+        let withdrawal_id = request(&service, exchange_account_id(), ADDRESS).withdrawal_id;
+        assert_eq!(service.waiting_approval().len(), 1);
+
+        let same_operator = service.approve(withdrawal_id, "operator1".to_owned());
+        assert!(same_operator.is_err());
+
+        let approved = service
+            .approve(withdrawal_id, "operator2".to_owned())
+            .expect("in test");
+        assert_eq!(approved.status, WithdrawalStatus::Approved);
+        assert_eq!(approved.approved_by, Some("operator2".to_owned()));
+        assert!(service.waiting_approval().is_empty());
+
+        let taken = service.take_approved().expect("in test");
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].withdrawal_id, withdrawal_id);
+        assert!(service.take_approved().expect("in test").is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejection_by_operator() {
+        let service = withdrawals_service(true).await;
+
+        let withdrawal_id = request(&service, exchange_account_id(), ADDRESS).withdrawal_id;
+        service
+            .reject(withdrawal_id, "operator2".to_owned())
+            .expect("in test");
+
+        assert!(service.waiting_approval().is_empty());
+        assert!(service
+            .approve(withdrawal_id, "operator2".to_owned())
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn approvals_are_restored_from_state_file() {
+        let state_file = std::env::temp_dir().join(format!("withdrawals_{}.json", Uuid::new_v4()));
+        let approvals_settings = WithdrawalApprovalsSettings {
+            state_file: state_file.clone(),
+        };
+
+        let service = withdrawals_service_with_state(true, Some(&approvals_settings)).await;
+        let waiting_id = request(&service, exchange_account_id(), ADDRESS).withdrawal_id;
+        let approved_id = request(&service, exchange_account_id(), ADDRESS).withdrawal_id;
+        let _ = service
+            .approve(approved_id, "operator2".to_owned())
+            .expect("in test");
+
+        let restored = withdrawals_service_with_state(true, Some(&approvals_settings)).await;
+        let waiting_approval = restored.waiting_approval();
+        assert_eq!(waiting_approval.len(), 1);
+        assert_eq!(waiting_approval[0].withdrawal_id, waiting_id);
+        let taken = restored.take_approved().expect("in test");
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].withdrawal_id, approved_id);
+
+        // taken withdrawals aren't sent again after the next restart
+        let restored = withdrawals_service_with_state(true, Some(&approvals_settings)).await;
+        assert!(restored.take_approved().expect("in test").is_empty());
+
+        let _ = std::fs::remove_file(state_file);
+    }
+}
@@ -0,0 +1,559 @@
+use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use futures::FutureExt;
+use mmb_core::exchanges::common::{
+    Amount, CurrencyPair, ExchangeAccountId, ExchangeErrorType, Price,
+};
+use mmb_core::exchanges::events::ExchangeEvent;
+use mmb_core::exchanges::general::exchange::{Exchange, RequestResult};
+use mmb_core::exchanges::general::symbol::{Round, Symbol};
+use mmb_core::orders::order::{
+    ClientOrderId, ExchangeOrderId, OrderCancelling, OrderExecutionType, OrderHeader, OrderSide,
+    OrderStatus, OrderType,
+};
+use mmb_core::orders::pool::OrderRef;
+use mmb_utils::cancellation_token::CancellationToken;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Duration;
+
+use crate::order::OrderProxy;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CaseOutcome {
+    Passed,
+    Failed(String),
+    /// Case isn't applicable to exchange or its preconditions failed
+    Skipped(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ConformanceCase {
+    pub name: &'static str,
+    pub outcome: CaseOutcome,
+}
+
+/// Compliance report of exchange connector produced by `ConformanceSuite`
+#[derive(Debug, Clone)]
+pub struct ConformanceReport {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub cases: Vec<ConformanceCase>,
+}
+
+impl ConformanceReport {
+    pub fn is_compliant(&self) -> bool {
+        self.failed().next().is_none()
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &ConformanceCase> {
+        self.cases
+            .iter()
+            .filter(|x| matches!(x.outcome, CaseOutcome::Failed(_)))
+    }
+
+    fn count(&self, predicate: fn(&CaseOutcome) -> bool) -> usize {
+        self.cases.iter().filter(|x| predicate(&x.outcome)).count()
+    }
+}
+
+impl Display for ConformanceReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Conformance report for {} {}: {} passed, {} failed, {} skipped",
+            self.exchange_account_id,
+            self.currency_pair,
+            self.count(|x| matches!(x, CaseOutcome::Passed)),
+            self.count(|x| matches!(x, CaseOutcome::Failed(_))),
+            self.count(|x| matches!(x, CaseOutcome::Skipped(_))),
+        )?;
+
+        for case in &self.cases {
+            match &case.outcome {
+                CaseOutcome::Passed => writeln!(f, "[PASSED]  {}", case.name)?,
+                CaseOutcome::Failed(reason) => writeln!(f, "[FAILED]  {}: {reason}", case.name)?,
+                CaseOutcome::Skipped(reason) => writeln!(f, "[SKIPPED] {}: {reason}", case.name)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reusable conformance test harness for exchange connectors. It exercises every `ExchangeClient`
+/// method, websocket subscriptions, error mapping and precision handling against live or testnet venue.
+/// Exchange should be connected and have built symbols, credentials should belong to testnet
+/// or to account that is allowed to place orders.
+///
+/// ```no_run
+/// use core_tests::conformance::ConformanceSuite;
+/// use mmb_core::exchanges::common::{Amount, Price};
+/// use mmb_core::exchanges::events::ExchangeEvent;
+/// use mmb_core::exchanges::general::exchange::Exchange;
+/// use std::sync::Arc;
+/// use tokio::sync::broadcast;
+///
+/// async fn example(exchange: Arc<Exchange>, rx: broadcast::Receiver<ExchangeEvent>, price: Price, amount: Amount) {
+///     let report = ConformanceSuite::new(exchange, rx, price, amount).run().await;
+///     assert!(report.is_compliant(), "{report}");
+/// }
+/// ```
+pub struct ConformanceSuite {
+    exchange: Arc<Exchange>,
+    events_receiver: broadcast::Receiver<ExchangeEvent>,
+    currency_pair: CurrencyPair,
+    /// Price of limit orders that shouldn't be filled during the test
+    price: Price,
+    amount: Amount,
+    timeout: Duration,
+}
+
+impl ConformanceSuite {
+    pub fn new(
+        exchange: Arc<Exchange>,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        price: Price,
+        amount: Amount,
+    ) -> Self {
+        ConformanceSuite {
+            exchange,
+            events_receiver,
+            currency_pair: OrderProxy::default_currency_pair(),
+            price,
+            amount,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn currency_pair(mut self, currency_pair: CurrencyPair) -> Self {
+        self.currency_pair = currency_pair;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub async fn run(mut self) -> ConformanceReport {
+        let mut cases = vec![];
+
+        macro_rules! case {
+            ($name: literal, $check: expr) => {
+                let outcome = run_case(self.timeout, $check).await;
+                log::info!("Conformance case {} outcome: {:?}", $name, outcome);
+                cases.push(ConformanceCase {
+                    name: $name,
+                    outcome,
+                });
+            };
+        }
+
+        case!("websocket_order_book", self.check_order_book_subscription());
+        case!("websocket_trades", self.check_trades_subscription());
+        case!("build_all_symbols", self.check_build_all_symbols());
+        case!("precision", self.check_precision());
+        case!("get_balance", self.check_get_balance());
+        case!("get_open_orders", self.check_get_open_orders());
+
+        let mut created_order = None;
+        case!("create_order", async {
+            created_order = Some(self.create_order().await?);
+            Ok::<_, anyhow::Error>(CaseOutcome::Passed)
+        });
+        match &created_order {
+            Some(order) => {
+                case!(
+                    "get_order_info",
+                    self.check_order_status(order, OrderStatus::Created)
+                );
+                case!(
+                    "get_open_orders_by_currency_pair",
+                    self.check_open_orders_by_currency_pair(order)
+                );
+                case!("cancel_order", self.check_cancel_order(order));
+            }
+            None => {
+                for name in [
+                    "get_order_info",
+                    "get_open_orders_by_currency_pair",
+                    "cancel_order",
+                ] {
+                    cases.push(ConformanceCase {
+                        name,
+                        outcome: CaseOutcome::Skipped("order wasn't created".to_owned()),
+                    });
+                }
+            }
+        }
+
+        case!("cancel_all_orders", self.check_cancel_all_orders());
+        case!("get_my_trades", self.check_get_my_trades());
+        case!("get_active_positions", self.check_get_active_positions());
+        case!(
+            "error_mapping_order_not_found",
+            self.check_order_not_found_mapping()
+        );
+        case!(
+            "error_mapping_invalid_order",
+            self.check_invalid_order_mapping()
+        );
+
+        ConformanceReport {
+            exchange_account_id: self.exchange.exchange_account_id,
+            currency_pair: self.currency_pair,
+            cases,
+        }
+    }
+
+    fn symbol(&self) -> Result<Arc<Symbol>> {
+        Ok(self
+            .exchange
+            .symbols
+            .get(&self.currency_pair)
+            .with_context(|| format!("Symbol {} isn't built", self.currency_pair))?
+            .clone())
+    }
+
+    fn order_header(&self, amount: Amount) -> Arc<OrderHeader> {
+        OrderHeader::new(
+            ClientOrderId::unique_id(),
+            Utc::now(),
+            self.exchange.exchange_account_id,
+            self.currency_pair,
+            OrderType::Limit,
+            OrderSide::Buy,
+            amount,
+            OrderExecutionType::None,
+            None,
+            None,
+            "Conformance".to_owned(),
+        )
+    }
+
+    async fn check_order_book_subscription(&mut self) -> Result<CaseOutcome> {
+        let exchange_account_id = self.exchange.exchange_account_id;
+        let currency_pair = self.currency_pair;
+        self.wait_event(|event| match event {
+            ExchangeEvent::OrderBookEvent(x) => {
+                x.exchange_account_id == exchange_account_id && x.currency_pair == currency_pair
+            }
+            _ => false,
+        })
+        .await
+        .context("Order book event wasn't received")?;
+
+        Ok(CaseOutcome::Passed)
+    }
+
+    async fn check_trades_subscription(&mut self) -> Result<CaseOutcome> {
+        let exchange_account_id = self.exchange.exchange_account_id;
+        let currency_pair = self.currency_pair;
+        let timeout = self.timeout / 2;
+        let received = self.wait_event(|event| match event {
+            ExchangeEvent::Trades(x) => {
+                x.exchange_account_id == exchange_account_id && x.currency_pair == currency_pair
+            }
+            _ => false,
+        });
+
+        // public trades can be absent on testnet for a long time
+        match tokio::time::timeout(timeout, received).await {
+            Ok(result) => result.map(|_| CaseOutcome::Passed),
+            Err(_) => Ok(CaseOutcome::Skipped(
+                "there were no public trades during timeout".to_owned(),
+            )),
+        }
+    }
+
+    async fn wait_event(&mut self, predicate: impl Fn(&ExchangeEvent) -> bool) -> Result<()> {
+        loop {
+            match self.events_receiver.recv().await {
+                Ok(event) if predicate(&event) => return Ok(()),
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => bail!("Events channel is closed"),
+            }
+        }
+    }
+
+    async fn check_build_all_symbols(&mut self) -> Result<CaseOutcome> {
+        let symbols = self.exchange.exchange_client.build_all_symbols().await?;
+
+        if !symbols
+            .iter()
+            .any(|x| x.currency_pair() == self.currency_pair)
+        {
+            bail!(
+                "Symbol {} isn't found among {} symbols",
+                self.currency_pair,
+                symbols.len()
+            );
+        }
+
+        Ok(CaseOutcome::Passed)
+    }
+
+    async fn check_precision(&mut self) -> Result<CaseOutcome> {
+        let symbol = self.symbol()?;
+
+        let floor = symbol.price_round(self.price, Round::Floor);
+        let ceiling = symbol.price_round(self.price, Round::Ceiling);
+        if floor > self.price || ceiling < self.price {
+            bail!(
+                "Price {} is rounded incorrectly: floor {floor}, ceiling {ceiling}",
+                self.price
+            );
+        }
+        if symbol.price_round(floor, Round::ToNearest) != floor {
+            bail!("Rounded price {floor} isn't stable after rounding");
+        }
+
+        let amount = symbol.amount_round(self.amount, Round::Floor);
+        if amount != self.amount {
+            bail!(
+                "Test amount {} doesn't match amount precision {:?}",
+                self.amount,
+                symbol.amount_precision
+            );
+        }
+
+        if let Some(min_amount) = symbol.min_amount {
+            if self.amount < min_amount {
+                bail!(
+                    "Test amount {} is less than min amount {min_amount}",
+                    self.amount
+                );
+            }
+        }
+
+        Ok(CaseOutcome::Passed)
+    }
+
+    async fn check_get_balance(&mut self) -> Result<CaseOutcome> {
+        let is_spot = !self.symbol()?.is_derivative();
+        let balances = self.exchange.exchange_client.get_balance(is_spot).await?;
+
+        if balances.balances.is_empty() {
+            bail!("Balances are empty, test account should have some funds");
+        }
+
+        Ok(CaseOutcome::Passed)
+    }
+
+    async fn check_get_open_orders(&mut self) -> Result<CaseOutcome> {
+        let _ = self.exchange.exchange_client.get_open_orders().await?;
+        Ok(CaseOutcome::Passed)
+    }
+
+    async fn create_order(&self) -> Result<OrderRef> {
+        let mut order_proxy = OrderProxy::new(
+            self.exchange.exchange_account_id,
+            Some("Conformance".to_owned()),
+            CancellationToken::default(),
+            self.price,
+            self.amount,
+        );
+        order_proxy.currency_pair = self.currency_pair;
+        order_proxy.timeout = self.timeout;
+
+        let order = order_proxy.create_order(self.exchange.clone()).await?;
+        if order.exchange_order_id().is_none() {
+            bail!(
+                "Created order {} has no exchange order id",
+                order.client_order_id()
+            );
+        }
+
+        Ok(order)
+    }
+
+    async fn check_order_status(
+        &self,
+        order: &OrderRef,
+        expected_status: OrderStatus,
+    ) -> Result<CaseOutcome> {
+        let order_info = self.exchange.get_order_info(order).await?;
+
+        if Some(&order_info.exchange_order_id) != order.exchange_order_id().as_ref() {
+            bail!(
+                "Exchange order id {} doesn't match created order {:?}",
+                order_info.exchange_order_id,
+                order.exchange_order_id()
+            );
+        }
+        if order_info.order_status != expected_status {
+            bail!(
+                "Order status is {:?}, expected {expected_status:?}",
+                order_info.order_status
+            );
+        }
+        if order_info.price != self.price || order_info.amount != self.amount {
+            bail!(
+                "Order price {} and amount {} don't match requested {} and {}",
+                order_info.price,
+                order_info.amount,
+                self.price,
+                self.amount
+            );
+        }
+
+        Ok(CaseOutcome::Passed)
+    }
+
+    async fn check_open_orders_by_currency_pair(&self, order: &OrderRef) -> Result<CaseOutcome> {
+        let open_orders = self
+            .exchange
+            .exchange_client
+            .get_open_orders_by_currency_pair(self.currency_pair)
+            .await?;
+
+        if !open_orders
+            .iter()
+            .any(|x| Some(&x.exchange_order_id) == order.exchange_order_id().as_ref())
+        {
+            bail!("Created order isn't found among open orders");
+        }
+
+        Ok(CaseOutcome::Passed)
+    }
+
+    async fn check_cancel_order(&self, order: &OrderRef) -> Result<CaseOutcome> {
+        let order_to_cancel = OrderCancelling {
+            header: order.fn_ref(|x| x.header.clone()),
+            exchange_order_id: order
+                .exchange_order_id()
+                .context("Order has no exchange order id")?,
+            extension_data: order.fn_ref(|x| x.extension_data.clone()),
+        };
+        order.fn_mut(|x| x.set_status(OrderStatus::Canceling, Utc::now()));
+
+        let cancel_result = self
+            .exchange
+            .cancel_order(order_to_cancel, CancellationToken::default())
+            .await
+            .context("Cancel order result wasn't received")?;
+        if let RequestResult::Error(error) = cancel_result.outcome {
+            bail!("Failed to cancel order: {error}");
+        }
+
+        let order_info = self.exchange.get_order_info(order).await?;
+        if order_info.order_status != OrderStatus::Canceled {
+            bail!(
+                "Order status is {:?} after cancellation",
+                order_info.order_status
+            );
+        }
+
+        Ok(CaseOutcome::Passed)
+    }
+
+    async fn check_cancel_all_orders(&mut self) -> Result<CaseOutcome> {
+        self.exchange
+            .exchange_client
+            .cancel_all_orders(self.currency_pair)
+            .await?;
+
+        let open_orders = self
+            .exchange
+            .exchange_client
+            .get_open_orders_by_currency_pair(self.currency_pair)
+            .await?;
+        if !open_orders.is_empty() {
+            bail!("{} orders are still open", open_orders.len());
+        }
+
+        Ok(CaseOutcome::Passed)
+    }
+
+    async fn check_get_my_trades(&mut self) -> Result<CaseOutcome> {
+        let symbol = self.symbol()?;
+        match self
+            .exchange
+            .exchange_client
+            .get_my_trades(&symbol, None)
+            .await?
+        {
+            RequestResult::Success(_) => Ok(CaseOutcome::Passed),
+            RequestResult::Error(error) => bail!("Failed to get trades: {error}"),
+        }
+    }
+
+    async fn check_get_active_positions(&mut self) -> Result<CaseOutcome> {
+        if !self.symbol()?.is_derivative() {
+            return Ok(CaseOutcome::Skipped(
+                "positions are supported only for derivatives".to_owned(),
+            ));
+        }
+
+        let _ = self.exchange.exchange_client.get_active_positions().await?;
+        Ok(CaseOutcome::Passed)
+    }
+
+    async fn check_order_not_found_mapping(&mut self) -> Result<CaseOutcome> {
+        let order_to_cancel = OrderCancelling {
+            header: self.order_header(self.amount),
+            exchange_order_id: ExchangeOrderId::from("1"),
+            extension_data: None,
+        };
+
+        let cancel_result = self
+            .exchange
+            .exchange_client
+            .cancel_order(order_to_cancel)
+            .await;
+        expect_error_type(cancel_result.outcome, ExchangeErrorType::OrderNotFound)
+    }
+
+    async fn check_invalid_order_mapping(&mut self) -> Result<CaseOutcome> {
+        // amount is too small to be accepted by any exchange
+        let header = self.order_header(self.amount / Amount::from(1_000_000));
+        let order = self
+            .exchange
+            .orders
+            .add_simple_initial(header, Some(self.price), None);
+
+        let create_result = self.exchange.exchange_client.create_order(&order).await;
+        expect_error_type(create_result.outcome, ExchangeErrorType::InvalidOrder)
+    }
+}
+
+async fn run_case(
+    timeout: Duration,
+    check: impl Future<Output = Result<CaseOutcome>>,
+) -> CaseOutcome {
+    let result = tokio::time::timeout(timeout, AssertUnwindSafe(check).catch_unwind()).await;
+    match result {
+        Err(_) => CaseOutcome::Failed(format!("timeout {} ms is exceeded", timeout.as_millis())),
+        Ok(Err(panic)) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|x| x.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic without readable message".to_owned());
+            CaseOutcome::Failed(format!("panic: {message}"))
+        }
+        Ok(Ok(Err(error))) => CaseOutcome::Failed(format!("{error:?}")),
+        Ok(Ok(Ok(outcome))) => outcome,
+    }
+}
+
+fn expect_error_type<T: fmt::Debug>(
+    outcome: RequestResult<T>,
+    expected: ExchangeErrorType,
+) -> Result<CaseOutcome> {
+    match outcome {
+        RequestResult::Error(error) if error.error_type == expected => Ok(CaseOutcome::Passed),
+        RequestResult::Error(error) => bail!(
+            "Error is mapped to {:?} instead of {expected:?}: {}",
+            error.error_type,
+            error.message
+        ),
+        RequestResult::Success(value) => bail!("Request unexpectedly succeeded: {value:?}"),
+    }
+}
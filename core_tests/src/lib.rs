@@ -16,4 +16,5 @@
     clippy::unwrap_used
 )]
 
+pub mod conformance;
 pub mod order;
@@ -16,19 +16,41 @@
     clippy::unwrap_used
 )]
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use binance::binance::BinanceBuilder;
 use mmb_core::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
 
+use mmb_core::backtesting::backtester::{BacktestSettings, Backtester};
 use mmb_core::config::{CONFIG_PATH, CREDENTIALS_PATH};
+use mmb_core::config_wizard::run_config_wizard;
+#[cfg(unix)]
+use mmb_core::exchanges::connector_process::host::{
+    connector_socket_from_env, run_connector_process,
+};
 use mmb_core::lifecycle::launcher::{launch_trading_engine, EngineBuildConfig, InitSettings};
 use mmb_core::settings::BaseStrategySettings;
 
+use strategies::config_templates::strategy_templates;
 use strategies::example_strategy::{ExampleStrategy, ExampleStrategySettings};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let engine_config = EngineBuildConfig::new(vec![Box::new(BinanceBuilder)]);
+    let args = std::env::args().collect::<Vec<_>>();
+    let mut engine_config = EngineBuildConfig::new(vec![Box::new(BinanceBuilder)]);
+
+    // engine starts its own executable as connector process of accounts with `connector_process` settings
+    #[cfg(unix)]
+    if let Some(socket_path) = connector_socket_from_env() {
+        return run_connector_process(&engine_config, &socket_path).await;
+    }
+
+    match args.get(1).map(String::as_str) {
+        // `init` command generates config.toml and credentials.toml instead of launching engine
+        Some("init") => return run_config_wizard(&engine_config, &strategy_templates()).await,
+        // `backtest` command runs the same strategy on recorded market data
+        Some("backtest") => engine_config = backtest_engine_config(&args[2..])?,
+        _ => {}
+    }
 
     let init_settings = InitSettings::<ExampleStrategySettings>::Load {
         config_path: CONFIG_PATH.to_owned(),
@@ -37,13 +59,16 @@ async fn main() -> Result<()> {
     loop {
         let engine =
             launch_trading_engine(&engine_config, init_settings.clone(), |settings, ctx| {
-                Box::new(ExampleStrategy::new(
-                    settings.strategy.exchange_account_id(),
-                    settings.strategy.currency_pair(),
-                    settings.strategy.spread,
-                    settings.strategy.max_amount,
-                    ctx,
-                ))
+                Box::new(
+                    ExampleStrategy::new(
+                        settings.strategy.exchange_account_id(),
+                        settings.strategy.currency_pair(),
+                        settings.strategy.spread,
+                        settings.strategy.max_amount,
+                        ctx,
+                    )
+                    .with_funding_skew(settings.strategy.funding_skew.clone()),
+                )
             })
             .await?;
 
@@ -54,3 +79,26 @@ async fn main() -> Result<()> {
     }
     Ok(())
 }
+
+/// Arguments are path to recording and initial balances, e.g. `recording.jsonl btc=1 usdt=10000`
+fn backtest_engine_config(args: &[String]) -> Result<EngineBuildConfig> {
+    let (recording_path, balances) = match args {
+        [recording_path, balances @ ..] => (recording_path, balances),
+        [] => bail!("Usage: backtest <recording> [currency=balance ...]"),
+    };
+
+    let mut settings = BacktestSettings::new(recording_path);
+    for balance in balances {
+        let (currency_code, amount) = balance
+            .split_once('=')
+            .with_context(|| format!("Invalid balance {balance}, expected currency=balance"))?;
+        let amount = amount
+            .parse()
+            .with_context(|| format!("Invalid amount of balance {balance}"))?;
+        let _ = settings
+            .initial_balances
+            .insert(currency_code.into(), amount);
+    }
+
+    Ok(Backtester::load(settings)?.engine_build_config())
+}
@@ -0,0 +1 @@
+DROP TABLE withdrawals_audit;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE withdrawals_audit (
+    id bigint PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    insert_time timestamp WITH TIME ZONE NOT NULL DEFAULT now(),
+    version int,
+    json jsonb NOT NULL
+);
+
+CREATE INDEX withdrawals_audit__insert_time_idx ON withdrawals_audit USING btree (insert_time);
+CREATE INDEX withdrawals_audit__withdrawal_id_idx ON withdrawals_audit USING btree (((json ->> 'withdrawal_id')::text));
@@ -0,0 +1 @@
+DROP TABLE performance_attribution;
\ No newline at end of file
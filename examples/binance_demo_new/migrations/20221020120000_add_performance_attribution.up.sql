@@ -0,0 +1,9 @@
+CREATE TABLE performance_attribution (
+    id bigint PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    insert_time timestamp WITH TIME ZONE NOT NULL DEFAULT now(),
+    version int,
+    json jsonb NOT NULL
+);
+
+CREATE INDEX performance_attribution__insert_time_idx ON performance_attribution USING btree (insert_time);
+CREATE INDEX performance_attribution__strategy_name_idx ON performance_attribution USING btree (((json ->> 'strategy_name')::text));
@@ -0,0 +1 @@
+DROP TABLE session_summaries;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE audit_log (
+    id bigint PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    insert_time timestamp WITH TIME ZONE NOT NULL DEFAULT now(),
+    version int,
+    json jsonb NOT NULL
+);
+
+CREATE INDEX audit_log__insert_time_idx ON audit_log USING btree (insert_time);
+CREATE INDEX audit_log__sequence_idx ON audit_log USING btree (((json ->> 'sequence')::bigint));
@@ -0,0 +1 @@
+DROP TABLE liquidity_indicators;
\ No newline at end of file
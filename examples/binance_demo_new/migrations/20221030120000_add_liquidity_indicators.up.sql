@@ -0,0 +1,8 @@
+CREATE TABLE liquidity_indicators (
+    id bigint PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    insert_time timestamp WITH TIME ZONE NOT NULL DEFAULT now(),
+    version int,
+    json jsonb NOT NULL
+);
+
+CREATE INDEX liquidity_indicators__market_insert_time_idx ON liquidity_indicators USING btree ((json ->> 'exchange_id'), (json ->> 'currency_pair'), insert_time);
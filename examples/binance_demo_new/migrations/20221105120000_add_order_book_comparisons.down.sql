@@ -0,0 +1 @@
+DROP TABLE order_book_comparisons;
\ No newline at end of file
@@ -0,0 +1,8 @@
+CREATE TABLE order_book_comparisons (
+    id bigint PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    insert_time timestamp WITH TIME ZONE NOT NULL DEFAULT now(),
+    version int,
+    json jsonb NOT NULL
+);
+
+CREATE INDEX order_book_comparisons__market_insert_time_idx ON order_book_comparisons USING btree ((json ->> 'exchange_id'), (json ->> 'currency_pair'), insert_time);
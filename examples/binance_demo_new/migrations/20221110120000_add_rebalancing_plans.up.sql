@@ -0,0 +1,9 @@
+CREATE TABLE rebalancing_plans (
+    id bigint PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    insert_time timestamp WITH TIME ZONE NOT NULL DEFAULT now(),
+    version int,
+    json jsonb NOT NULL
+);
+
+CREATE INDEX rebalancing_plans__insert_time_idx ON rebalancing_plans USING btree (insert_time);
+CREATE INDEX rebalancing_plans__plan_id_idx ON rebalancing_plans USING btree (((json ->> 'plan_id')::text));
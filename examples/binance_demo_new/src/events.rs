@@ -1,6 +1,7 @@
 use itertools::Itertools;
 use mmb_core::exchanges::common::{Amount, CurrencyPair, ExchangeId, MarketId, Price};
 use mmb_core::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+use mmb_core::order_book::order_book_data::OrderBookData;
 use mmb_core::orders::order::{ClientOrderId, OrderSide, OrderStatus};
 use mmb_core::orders::pool::OrdersPool;
 use mmb_database::impl_event;
@@ -39,15 +40,54 @@ pub struct LiquidityOrderBook {
 
 impl_event!(LiquidityOrderBook, "liquidity_order_books");
 
+/// Order book claimed by exchange in snapshot event and local snapshot just before applying it,
+/// divergence of them means that updates are applied incorrectly by connector
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBookComparison {
+    exchange_id: ExchangeId,
+    currency_pair: CurrencyPair,
+    raw: LiquiditySnapshot,
+    local: LiquiditySnapshot,
+}
+
+impl_event!(OrderBookComparison, "order_book_comparisons");
+
+const PRICE_LEVELS_COUNT: usize = 20;
+
+fn price_levels<'a>(levels: impl Iterator<Item = (&'a Price, &'a Amount)>) -> Vec<PriceLevel> {
+    levels
+        .take(PRICE_LEVELS_COUNT)
+        .map(|(&price, &amount)| PriceLevel { price, amount })
+        .collect()
+}
+
+pub fn create_order_book_comparison(
+    raw: &OrderBookData,
+    local: &LocalOrderBookSnapshot,
+    market_id: MarketId,
+) -> OrderBookComparison {
+    OrderBookComparison {
+        exchange_id: market_id.exchange_id,
+        currency_pair: market_id.currency_pair,
+        raw: LiquiditySnapshot {
+            asks: price_levels(raw.asks.iter()),
+            bids: price_levels(raw.bids.iter().rev()),
+        },
+        local: LiquiditySnapshot {
+            asks: price_levels(local.get_asks_price_levels()),
+            bids: price_levels(local.get_bids_price_levels()),
+        },
+    }
+}
+
 pub fn create_liquidity_order_book_snapshot(
     order_book_snapshot: &LocalOrderBookSnapshot,
     market_id: MarketId,
     orders_pool: &Arc<OrdersPool>,
 ) -> LiquidityOrderBook {
-    const PRICE_LEVELS_COUNT: usize = 20;
-
     let orders = orders_pool
         .not_finished
+        .by_market(market_id.currency_pair)
         .iter()
         .filter_map(|x| {
             x.fn_ref(|os| match os.props.status {
@@ -67,16 +107,8 @@ pub fn create_liquidity_order_book_snapshot(
         exchange_id: market_id.exchange_id,
         currency_pair: market_id.currency_pair,
         snapshot: LiquiditySnapshot {
-            asks: order_book_snapshot
-                .get_asks_price_levels()
-                .take(PRICE_LEVELS_COUNT)
-                .map(|(&price, &amount)| PriceLevel { price, amount })
-                .collect(),
-            bids: order_book_snapshot
-                .get_bids_price_levels()
-                .take(PRICE_LEVELS_COUNT)
-                .map(|(&price, &amount)| PriceLevel { price, amount })
-                .collect(),
+            asks: price_levels(order_book_snapshot.get_asks_price_levels()),
+            bids: price_levels(order_book_snapshot.get_bids_price_levels()),
         },
         orders,
     }
@@ -24,24 +24,27 @@ use mmb_core::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
 use std::sync::Arc;
 
 use mmb_core::config::{CONFIG_PATH, CREDENTIALS_PATH};
-use mmb_core::database::events::transaction::{
-    transaction_service, TransactionSnapshot, TransactionStatus, TransactionTrade,
-};
-use mmb_core::exchanges::common::MarketAccountId;
+use mmb_core::database::events::transaction::TransactionStatus;
+use mmb_core::exchanges::common::{MarketAccountId, MarketId};
 use mmb_core::exchanges::events::ExchangeEvent;
 use mmb_core::infrastructure::spawn_future;
 use mmb_core::lifecycle::launcher::{launch_trading_engine, EngineBuildConfig, InitSettings};
 use mmb_core::lifecycle::trading_engine::EngineContext;
+use mmb_core::order_book::event::{EventType, OrderBookEvent};
 use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
 use mmb_core::orders::event::OrderEventType;
 use mmb_core::orders::order::OrderSnapshot;
 use mmb_core::settings::BaseStrategySettings;
 use mmb_utils::infrastructure::{SpawnFutureFlags, WithExpect};
+use mmb_utils::DateTime;
+use std::collections::HashMap;
 
-use crate::events::create_liquidity_order_book_snapshot;
+use crate::events::{create_liquidity_order_book_snapshot, create_order_book_comparison};
 use strategies::example_strategy::{ExampleStrategy, ExampleStrategySettings};
 
 const STRATEGY_NAME: &str = "binance_demo";
+/// Min interval between saved comparisons of exchange and local order books of market
+const ORDER_BOOK_COMPARISON_INTERVAL_MS: i64 = 1000;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -60,13 +63,16 @@ async fn main() -> Result<()> {
                     start_liquidity_order_book_saving(ctx.clone()),
                 );
 
-                Box::new(ExampleStrategy::new(
-                    settings.strategy.exchange_account_id(),
-                    settings.strategy.currency_pair(),
-                    settings.strategy.spread,
-                    settings.strategy.max_amount,
-                    ctx,
-                ))
+                Box::new(
+                    ExampleStrategy::new(
+                        settings.strategy.exchange_account_id(),
+                        settings.strategy.currency_pair(),
+                        settings.strategy.spread,
+                        settings.strategy.max_amount,
+                        ctx,
+                    )
+                    .with_funding_skew(settings.strategy.funding_skew.clone()),
+                )
             })
             .await?;
 
@@ -80,6 +86,7 @@ async fn main() -> Result<()> {
 
 async fn start_liquidity_order_book_saving(ctx: Arc<EngineContext>) -> Result<(), Error> {
     let mut snapshots_service = LocalSnapshotsService::default();
+    let mut last_comparison_times = HashMap::new();
     let mut events_rx = ctx.get_events_channel();
 
     let stop_token = ctx.lifetime_manager.stop_token();
@@ -89,21 +96,30 @@ async fn start_liquidity_order_book_saving(ctx: Arc<EngineContext>) -> Result<()
             Err(err) => eprintln!("Error occurred: {err:?}"),
             Ok(event) => {
                 let market_account_id = match event {
-                    ExchangeEvent::OrderBookEvent(ob_event) => snapshots_service.update(ob_event),
+                    ExchangeEvent::OrderBookEvent(ob_event) => {
+                        save_order_book_comparison_if_needed(
+                            &ctx,
+                            &snapshots_service,
+                            &ob_event,
+                            &mut last_comparison_times,
+                        )
+                        .context("in start_liquidity_order_book_saving")?;
+                        snapshots_service.update(ob_event)
+                    }
                     ExchangeEvent::OrderEvent(order_event) => match order_event.event_type {
                         OrderEventType::CreateOrderSucceeded
-                        | OrderEventType::OrderCompleted { .. }
                         | OrderEventType::CancelOrderSucceeded => {
                             Some(order_event.order.fn_ref(|o| o.market_account_id()))
                         }
                         OrderEventType::OrderFilled { cloned_order } => {
-                            save_transaction(
-                                &ctx,
-                                &cloned_order,
-                                TransactionStatus::Finished,
-                                STRATEGY_NAME.to_string(),
-                            )
-                            .context("in start_liquidity_order_book_saving")?;
+                            save_transaction_fill(&ctx, &cloned_order, STRATEGY_NAME.to_string())
+                                .context("in start_liquidity_order_book_saving")?;
+
+                            Some(cloned_order.market_account_id())
+                        }
+                        OrderEventType::OrderCompleted { cloned_order } => {
+                            finish_transaction(&ctx, &cloned_order)
+                                .context("in start_liquidity_order_book_saving")?;
 
                             Some(cloned_order.market_account_id())
                         }
@@ -121,43 +137,34 @@ async fn start_liquidity_order_book_saving(ctx: Arc<EngineContext>) -> Result<()
     Ok(())
 }
 
-fn save_transaction(
+fn save_transaction_fill(
     ctx: &EngineContext,
     order: &OrderSnapshot,
-    status: TransactionStatus,
     strategy_name: String,
 ) -> Result<()> {
-    let mut transaction = TransactionSnapshot::new(
-        order.market_id(),
-        order.side(),
-        order.props.raw_price,
-        order.amount(),
-        status,
-        strategy_name,
-    );
-
-    let exchange_order_id = order
-        .props
-        .exchange_order_id
-        .as_ref()
-        .expect("`exchange_order_id` must be set before saving transaction")
-        .clone();
-
-    let fill = order
-        .fills
-        .fills
-        .last()
-        .expect("must be existed at least 1 fill on saving transaction");
-
-    transaction.trades.push(TransactionTrade {
-        exchange_order_id,
-        exchange_id: order.header.exchange_account_id.exchange_id,
-        price: Some(fill.price()),
-        amount: fill.amount(),
-        side: fill.side(),
-    });
-
-    transaction_service::save(&mut transaction, status, &ctx.event_recorder)
+    let client_order_id = order.client_order_id();
+    if ctx.transactions.find_by_order(&client_order_id).is_none() {
+        let transaction_id = ctx.transactions.create(
+            order.market_id(),
+            order.side(),
+            order.props.raw_price,
+            order.amount(),
+            strategy_name,
+        )?;
+        ctx.transactions
+            .link_order(transaction_id, client_order_id)?;
+    }
+
+    ctx.transactions.add_fill(order)
+}
+
+fn finish_transaction(ctx: &EngineContext, order: &OrderSnapshot) -> Result<()> {
+    match ctx.transactions.find_by_order(&order.client_order_id()) {
+        Some(transaction_id) => ctx
+            .transactions
+            .set_status(transaction_id, TransactionStatus::Finished),
+        None => Ok(()),
+    }
 }
 
 fn save_liquidity_order_book_if_can(
@@ -184,3 +191,41 @@ fn save_liquidity_order_book_if_can(
 
     Ok(())
 }
+
+/// Saves snapshot claimed by exchange together with local snapshot before applying it
+/// for debugging of applying order book updates
+fn save_order_book_comparison_if_needed(
+    ctx: &EngineContext,
+    snapshots_service: &LocalSnapshotsService,
+    event: &OrderBookEvent,
+    last_comparison_times: &mut HashMap<MarketId, DateTime>,
+) -> Result<()> {
+    if !matches!(event.event_type, EventType::Snapshot) {
+        return Ok(());
+    }
+
+    let market_id = event.market_account_id().market_id();
+    let local_snapshot = match snapshots_service.get_snapshot(market_id) {
+        Some(local_snapshot) => local_snapshot,
+        None => return Ok(()),
+    };
+
+    let is_saved_recently = last_comparison_times
+        .get(&market_id)
+        .is_some_and(|last_time| {
+            (event.creation_time - *last_time).num_milliseconds()
+                < ORDER_BOOK_COMPARISON_INTERVAL_MS
+        });
+    if is_saved_recently {
+        return Ok(());
+    }
+    last_comparison_times.insert(market_id, event.creation_time);
+
+    ctx.event_recorder
+        .save(create_order_book_comparison(
+            &event.data,
+            local_snapshot,
+            market_id,
+        ))
+        .context("failed saving order_book_comparison")
+}
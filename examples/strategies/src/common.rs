@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use itertools::Itertools;
+use mmb_utils::infrastructure::WithExpect;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use mmb_core::balance::manager::balance_manager::BalanceManager;
+use mmb_core::disposition_execution::{TradeCycle, TradeDisposition, TradingContextBySide};
+use mmb_core::exchanges::common::{
+    Amount, CurrencyPair, ExchangeAccountId, MarketAccountId, Price,
+};
+use mmb_core::exchanges::general::symbol::{Round, Symbol};
+use mmb_core::explanation::{Explanation, WithExplanation};
+use mmb_core::lifecycle::trading_engine::EngineContext;
+use mmb_core::orders::order::{OrderRole, OrderSide};
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::settings::CurrencyPairSetting;
+
+pub(crate) fn ordinary_currency_pair(currency_pair: &CurrencyPairSetting) -> CurrencyPair {
+    if let CurrencyPairSetting::Ordinary { base, quote } = currency_pair {
+        CurrencyPair::from_codes(*base, *quote)
+    } else {
+        panic!("Incorrect currency pair setting enum type {currency_pair:?}");
+    }
+}
+
+pub(crate) fn configuration_descriptor(
+    strategy_name: &str,
+    target_eai: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+) -> ConfigurationDescriptor {
+    ConfigurationDescriptor::new(
+        strategy_name.into(),
+        (target_eai.to_string() + ";" + currency_pair.as_str())
+            .as_str()
+            .into(),
+    )
+}
+
+pub(crate) fn get_symbol(
+    engine_context: &EngineContext,
+    exchange_account_id: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+) -> Option<Arc<Symbol>> {
+    Some(
+        engine_context
+            .exchanges
+            .get(&exchange_account_id)?
+            .symbols
+            .get(&currency_pair)?
+            .clone(),
+    )
+}
+
+pub(crate) fn set_amount_limit(
+    engine_context: &EngineContext,
+    configuration_descriptor: ConfigurationDescriptor,
+    target_eai: ExchangeAccountId,
+    symbol: Arc<Symbol>,
+    max_amount: Decimal,
+) {
+    // amount_limit it's a limit for position changing for both sides
+    // it's equal to half of the max amount because an order that can change a position from
+    // a limit by sells to a limit by buys is possible
+    let amount_limit = max_amount * dec!(0.5);
+
+    engine_context
+        .balance_manager
+        .lock()
+        .set_target_amount_limit(configuration_descriptor, target_eai, symbol, amount_limit);
+}
+
+/// Amount available for order of strategy by balance, rounded by symbol precision
+pub(crate) fn calculate_balance_amount(
+    engine_context: &EngineContext,
+    configuration_descriptor: ConfigurationDescriptor,
+    side: OrderSide,
+    target_eai: ExchangeAccountId,
+    symbol: Arc<Symbol>,
+    price: Price,
+    explanation: Explanation,
+) -> (Amount, Explanation) {
+    let mut explanation = Some(explanation);
+
+    // TODO: delete deep_clone
+    let orders = engine_context
+        .exchanges
+        .iter()
+        .flat_map(|x| x.orders.not_finished.all())
+        .collect_vec();
+
+    let balance_manager = BalanceManager::clone_and_subtract_not_approved_data(
+        engine_context.balance_manager.clone(),
+        Some(orders),
+    )
+    .expect("calculate_balance_amount: failed to clone and subtract not approved data for BalanceManager");
+
+    let amount = balance_manager
+        .lock()
+        .get_leveraged_balance_in_amount_currency_code(
+            configuration_descriptor,
+            side,
+            target_eai,
+            symbol.clone(),
+            price,
+            &mut explanation,
+        )
+        .with_expect(|| format!("Failed to get balance for {}", target_eai));
+
+    // This expect can happened if get_leveraged_balance_in_amount_currency_code() sets the explanation to None
+    let explanation =
+        explanation.expect("calculate_balance_amount(): Explanation should be non None here");
+
+    (symbol.amount_round(amount, Round::Floor), explanation)
+}
+
+/// Trading context of side with single maker order
+pub(crate) fn maker_trading_context_by_side(
+    strategy_name: &str,
+    market_account_id: MarketAccountId,
+    side: OrderSide,
+    price: Price,
+    amount: Amount,
+    max_amount: Amount,
+    explanation: Explanation,
+) -> TradingContextBySide {
+    TradingContextBySide {
+        max_amount,
+        estimating: vec![WithExplanation {
+            value: Some(TradeCycle {
+                order_role: OrderRole::Maker,
+                strategy_name: strategy_name.to_string(),
+                disposition: TradeDisposition::new(market_account_id, side, price, amount),
+            }),
+            explanation,
+        }],
+    }
+}
+
+/// Trading context of side which isn't quoted now
+pub(crate) fn not_quoted_trading_context_by_side(
+    reason: String,
+    mut explanation: Explanation,
+) -> TradingContextBySide {
+    explanation.add_reason(reason);
+    TradingContextBySide::empty(1, explanation)
+}
@@ -0,0 +1,26 @@
+use mmb_core::config_wizard::{validate_settings, StrategyTemplate};
+
+use crate::example_strategy::ExampleStrategySettings;
+use crate::mean_reversion_strategy::MeanReversionStrategySettings;
+use crate::momentum_strategy::MomentumStrategySettings;
+
+/// Strategies offered by config wizard with placeholder values of their specific settings
+pub fn strategy_templates() -> Vec<StrategyTemplate> {
+    vec![
+        StrategyTemplate {
+            name: "example",
+            settings: "spread = 3\n",
+            validate: validate_settings::<ExampleStrategySettings>,
+        },
+        StrategyTemplate {
+            name: "mean_reversion",
+            settings: "candle_period_secs = 60\nband_candles = 20\nband_width = 2\n",
+            validate: validate_settings::<MeanReversionStrategySettings>,
+        },
+        StrategyTemplate {
+            name: "momentum",
+            settings: "candle_period_secs = 60\nlookback_candles = 20\nbreakout_threshold = 0.5\n",
+            validate: validate_settings::<MomentumStrategySettings>,
+        },
+    ]
+}
@@ -1,36 +1,44 @@
 use std::sync::Arc;
 
 use anyhow::Result;
-use itertools::Itertools;
 use mmb_utils::infrastructure::WithExpect;
 use mmb_utils::DateTime;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
-use mmb_core::balance::manager::balance_manager::BalanceManager;
-use mmb_core::disposition_execution::{
-    PriceSlot, TradeCycle, TradeDisposition, TradingContext, TradingContextBySide,
-};
+use mmb_core::disposition_execution::{PriceSlot, TradingContext, TradingContextBySide};
 use mmb_core::exchanges::common::{
     Amount, CurrencyPair, ExchangeAccountId, MarketAccountId, MarketId,
 };
 use mmb_core::exchanges::general::symbol::Round;
-use mmb_core::explanation::{Explanation, WithExplanation};
+use mmb_core::explanation::Explanation;
 use mmb_core::lifecycle::trading_engine::EngineContext;
 use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
-use mmb_core::orders::order::{OrderRole, OrderSide, OrderSnapshot};
+use mmb_core::orders::order::{OrderSide, OrderSnapshot};
 use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
 use mmb_core::settings::{BaseStrategySettings, CurrencyPairSetting};
+use mmb_core::settings_values::deserialize_decimal;
 use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_core::strategies::funding_skew::{calculate_funding_skew, skew_price, FundingSkewSettings};
 use mmb_utils::cancellation_token::CancellationToken;
 use serde::{Deserialize, Serialize};
 
+use crate::common::{
+    calculate_balance_amount, configuration_descriptor, get_symbol, maker_trading_context_by_side,
+    ordinary_currency_pair, set_amount_limit,
+};
+
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct ExampleStrategySettings {
+    #[serde(deserialize_with = "deserialize_decimal")]
     pub spread: Decimal,
     pub currency_pair: CurrencyPairSetting,
+    #[serde(deserialize_with = "deserialize_decimal")]
     pub max_amount: Decimal,
     pub exchange_account_id: ExchangeAccountId,
+    /// Quotes are skewed by funding rate on perpetual markets if set
+    #[serde(default)]
+    pub funding_skew: Option<FundingSkewSettings>,
 }
 
 impl BaseStrategySettings for ExampleStrategySettings {
@@ -39,14 +47,7 @@ impl BaseStrategySettings for ExampleStrategySettings {
     }
 
     fn currency_pair(&self) -> CurrencyPair {
-        if let CurrencyPairSetting::Ordinary { base, quote } = self.currency_pair {
-            CurrencyPair::from_codes(base, quote)
-        } else {
-            panic!(
-                "Incorrect currency pair setting enum type {:?}",
-                self.currency_pair
-            );
-        }
+        ordinary_currency_pair(&self.currency_pair)
     }
 
     // Max amount for orders that will be created
@@ -61,7 +62,10 @@ pub struct ExampleStrategy {
     spread: Decimal,
     engine_context: Arc<EngineContext>,
     configuration_descriptor: ConfigurationDescriptor,
+    /// Max amount from settings which is used until desired amount of market is set at runtime
+    configured_max_amount: Decimal,
     max_amount: Decimal,
+    funding_skew: Option<FundingSkewSettings>,
 }
 
 impl ExampleStrategy {
@@ -72,12 +76,8 @@ impl ExampleStrategy {
         max_amount: Decimal,
         engine_context: Arc<EngineContext>,
     ) -> Self {
-        let configuration_descriptor = ConfigurationDescriptor::new(
-            "ExampleStrategy".into(),
-            (target_eai.to_string() + ";" + currency_pair.as_str())
-                .as_str()
-                .into(),
-        );
+        let configuration_descriptor =
+            configuration_descriptor(Self::strategy_name(), target_eai, currency_pair);
 
         let exchanges = &engine_context.clone().exchanges;
         let exchange = exchanges.get(&target_eai).with_expect(|| {
@@ -87,21 +87,23 @@ impl ExampleStrategy {
             )
         });
 
-        // amount_limit it's a limit for position changing for both sides
-        // it's equal to half of the max amount because an order that can change a position from
-        // a limit by sells to a limit by buys is possible
-        let amount_limit = max_amount * dec!(0.5);
-
         let symbol = exchange
             .symbols
             .get(&currency_pair)
             .with_expect(|| format!("failed to get symbol from exchange for {}", currency_pair))
             .clone();
 
-        engine_context
-            .balance_manager
-            .lock()
-            .set_target_amount_limit(configuration_descriptor, target_eai, symbol, amount_limit);
+        let configured_max_amount = max_amount;
+        let max_amount = engine_context
+            .desired_amounts
+            .get_or(MarketAccountId::new(target_eai, currency_pair), max_amount);
+        set_amount_limit(
+            &engine_context,
+            configuration_descriptor,
+            target_eai,
+            symbol,
+            max_amount,
+        );
 
         ExampleStrategy {
             target_eai,
@@ -109,10 +111,43 @@ impl ExampleStrategy {
             spread,
             engine_context,
             configuration_descriptor,
+            configured_max_amount,
             max_amount,
+            funding_skew: None,
         }
     }
 
+    /// Desired amount of market can be changed through control API, so max amount and amount
+    /// limit of strategy follow it
+    fn apply_desired_amount(&mut self) -> Option<()> {
+        let desired_amount = self
+            .engine_context
+            .desired_amounts
+            .get_or(self.market_account_id(), self.configured_max_amount);
+        if desired_amount == self.max_amount {
+            return Some(());
+        }
+
+        let symbol = get_symbol(&self.engine_context, self.target_eai, self.currency_pair)?;
+        set_amount_limit(
+            &self.engine_context,
+            self.configuration_descriptor,
+            self.target_eai,
+            symbol,
+            desired_amount,
+        );
+        self.max_amount = desired_amount;
+
+        Some(())
+    }
+
+    /// Lean quotes with carry by funding rate. Funding rates of strategy market should be
+    /// configured in `funding_rates` settings of engine
+    pub fn with_funding_skew(mut self, funding_skew: Option<FundingSkewSettings>) -> Self {
+        self.funding_skew = funding_skew;
+        self
+    }
+
     fn strategy_name() -> &'static str {
         "ExampleStrategy"
     }
@@ -125,6 +160,17 @@ impl ExampleStrategy {
         self.market_account_id().market_id()
     }
 
+    fn calculate_funding_skew(&self) -> Option<Decimal> {
+        let settings = self.funding_skew.as_ref()?;
+        let funding_rate = self
+            .engine_context
+            .funding_rates
+            .as_ref()?
+            .get_funding_rate(self.market_account_id())?;
+
+        Some(calculate_funding_skew(&funding_rate, settings))
+    }
+
     fn calc_trading_context_by_side(
         &mut self,
         side: OrderSide,
@@ -138,13 +184,7 @@ impl ExampleStrategy {
 
         let current_spread = ask_min_price - bid_max_price;
 
-        let symbol = self
-            .engine_context
-            .exchanges
-            .get(&self.target_eai)?
-            .symbols
-            .get(&self.currency_pair)?
-            .clone();
+        let symbol = get_symbol(&self.engine_context, self.target_eai, self.currency_pair)?;
 
         let price = if current_spread < self.spread {
             let order_book_middle = (bid_max_price + ask_min_price) * dec!(0.5);
@@ -163,66 +203,36 @@ impl ExampleStrategy {
             snapshot.get_top(side)?.0
         };
 
-        let amount;
-        explanation = {
-            let mut explanation = Some(explanation);
-
-            // TODO: delete deep_clone
-            let orders = self
-                .engine_context
-                .exchanges
-                .iter()
-                .flat_map(|x| {
-                    x.orders
-                        .not_finished
-                        .iter()
-                        .map(|y| y.clone())
-                        .collect_vec()
-                })
-                .collect_vec();
-
-            let balance_manager = BalanceManager::clone_and_subtract_not_approved_data(
-                self.engine_context.balance_manager.clone(),
-                Some(orders),
-            )
-            .expect("ExampleStrategy::calc_trading_context_by_side: failed to clone and subtract not approved data for BalanceManager");
-
-            amount = balance_manager
-                .lock()
-                .get_leveraged_balance_in_amount_currency_code(
-                    self.configuration_descriptor,
-                    side,
-                    self.target_eai,
-                    symbol.clone(),
-                    price,
-                    &mut explanation,
-                )
-                .with_expect(|| format!("Failed to get balance for {}", self.target_eai));
-
-            // This expect can happened if get_leveraged_balance_in_amount_currency_code() sets the explanation to None
-            explanation.expect(
-                "ExampleStrategy::calc_trading_context_by_side(): Explanation should be non None here"
-            )
+        let price = match self.calculate_funding_skew() {
+            Some(skew) if !skew.is_zero() => {
+                let skewed_price = skew_price(price, side, skew, &symbol);
+                explanation.add_reason(format!(
+                    "Price {price} is skewed to {skewed_price} by funding rate skew {skew}%"
+                ));
+                skewed_price
+            }
+            _ => price,
         };
 
-        let amount = symbol.amount_round(amount, Round::Floor);
-
-        Some(TradingContextBySide {
-            max_amount: self.max_amount,
-            estimating: vec![WithExplanation {
-                value: Some(TradeCycle {
-                    order_role: OrderRole::Maker,
-                    strategy_name: Self::strategy_name().to_string(),
-                    disposition: TradeDisposition::new(
-                        self.market_account_id(),
-                        side,
-                        price,
-                        amount,
-                    ),
-                }),
-                explanation,
-            }],
-        })
+        let (amount, explanation) = calculate_balance_amount(
+            &self.engine_context,
+            self.configuration_descriptor,
+            side,
+            self.target_eai,
+            symbol,
+            price,
+            explanation,
+        );
+
+        Some(maker_trading_context_by_side(
+            Self::strategy_name(),
+            self.market_account_id(),
+            side,
+            price,
+            amount,
+            self.max_amount,
+            explanation,
+        ))
     }
 }
 
@@ -233,6 +243,8 @@ impl DispositionStrategy for ExampleStrategy {
         local_snapshots_service: &LocalSnapshotsService,
         explanation: &mut Explanation,
     ) -> Option<TradingContext> {
+        self.apply_desired_amount()?;
+
         let buy_trading_ctx = self.calc_trading_context_by_side(
             OrderSide::Buy,
             now,
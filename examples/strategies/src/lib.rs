@@ -16,4 +16,8 @@
     clippy::unwrap_used
 )]
 
+mod common;
+pub mod config_templates;
 pub mod example_strategy;
+pub mod mean_reversion_strategy;
+pub mod momentum_strategy;
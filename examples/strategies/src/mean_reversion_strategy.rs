@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use mmb_utils::infrastructure::WithExpect;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use mmb_core::disposition_execution::{PriceSlot, TradingContext, TradingContextBySide};
+use mmb_core::exchanges::common::{
+    Amount, CurrencyPair, ExchangeAccountId, MarketAccountId, Price,
+};
+use mmb_core::exchanges::general::symbol::Round;
+use mmb_core::explanation::Explanation;
+use mmb_core::lifecycle::trading_engine::EngineContext;
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::orders::order::{OrderSide, OrderSnapshot};
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::services::candles::{sma, std_dev, CandlesBuilder};
+use mmb_core::settings::{BaseStrategySettings, CurrencyPairSetting};
+use mmb_core::settings_values::deserialize_decimal;
+use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_utils::cancellation_token::CancellationToken;
+use serde::{Deserialize, Serialize};
+
+use crate::common::{
+    calculate_balance_amount, configuration_descriptor, get_symbol, maker_trading_context_by_side,
+    not_quoted_trading_context_by_side, ordinary_currency_pair, set_amount_limit,
+};
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct MeanReversionStrategySettings {
+    pub currency_pair: CurrencyPairSetting,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_amount: Decimal,
+    pub exchange_account_id: ExchangeAccountId,
+    pub candle_period_secs: u64,
+    /// Count of candles for moving average and standard deviation
+    pub band_candles: usize,
+    /// Distance of bands from moving average in standard deviations
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub band_width: Decimal,
+}
+
+impl BaseStrategySettings for MeanReversionStrategySettings {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        self.exchange_account_id
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        ordinary_currency_pair(&self.currency_pair)
+    }
+
+    // Max amount for orders that will be created
+    fn max_amount(&self) -> Amount {
+        self.max_amount
+    }
+}
+
+/// Bollinger bands strategy: buys at lower band and sells at upper band of moving average of
+/// middle price, expecting price to revert to average. Quotes never cross best prices of order book
+pub struct MeanReversionStrategy {
+    target_eai: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    engine_context: Arc<EngineContext>,
+    configuration_descriptor: ConfigurationDescriptor,
+    max_amount: Decimal,
+    band_width: Decimal,
+    candles: CandlesBuilder,
+}
+
+impl MeanReversionStrategy {
+    pub fn new(
+        settings: &MeanReversionStrategySettings,
+        engine_context: Arc<EngineContext>,
+    ) -> Self {
+        let target_eai = settings.exchange_account_id;
+        let currency_pair = settings.currency_pair();
+        let configuration_descriptor =
+            configuration_descriptor(Self::strategy_name(), target_eai, currency_pair);
+
+        let symbol = get_symbol(&engine_context, target_eai, currency_pair)
+            .with_expect(|| format!("failed to get symbol {currency_pair} for {target_eai}"));
+        set_amount_limit(
+            &engine_context,
+            configuration_descriptor,
+            target_eai,
+            symbol,
+            settings.max_amount,
+        );
+
+        MeanReversionStrategy {
+            target_eai,
+            currency_pair,
+            engine_context,
+            configuration_descriptor,
+            max_amount: settings.max_amount,
+            band_width: settings.band_width,
+            candles: CandlesBuilder::new(
+                settings.candle_period_secs as i64 * 1000,
+                settings.band_candles,
+            ),
+        }
+    }
+
+    fn strategy_name() -> &'static str {
+        "MeanReversionStrategy"
+    }
+
+    fn market_account_id(&self) -> MarketAccountId {
+        MarketAccountId::new(self.target_eai, self.currency_pair)
+    }
+
+    /// Lower and upper bands
+    fn bands(&self) -> Option<(Price, Price)> {
+        if !self.candles.is_ready() {
+            return None;
+        }
+
+        let average = sma(self.candles.completed())?;
+        let deviation = std_dev(self.candles.completed())? * self.band_width;
+        Some((average - deviation, average + deviation))
+    }
+
+    fn calc_trading_context_by_side(
+        &self,
+        side: OrderSide,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: Explanation,
+    ) -> Option<TradingContextBySide> {
+        let (lower_band, upper_band) = match self.bands() {
+            Some(bands) => bands,
+            None => {
+                return Some(not_quoted_trading_context_by_side(
+                    "Not enough candles for bands".to_owned(),
+                    explanation,
+                ))
+            }
+        };
+
+        let snapshot =
+            local_snapshots_service.get_snapshot(self.market_account_id().market_id())?;
+        let top_price = snapshot.get_top(side)?.0;
+        let symbol = get_symbol(&self.engine_context, self.target_eai, self.currency_pair)?;
+
+        let price = match side {
+            OrderSide::Buy => symbol.price_round(lower_band.min(top_price), Round::Floor),
+            OrderSide::Sell => symbol.price_round(upper_band.max(top_price), Round::Ceiling),
+        };
+        if price <= dec!(0) {
+            return Some(not_quoted_trading_context_by_side(
+                format!("Price {price} by bands isn't positive"),
+                explanation,
+            ));
+        }
+
+        let (amount, mut explanation) = calculate_balance_amount(
+            &self.engine_context,
+            self.configuration_descriptor,
+            side,
+            self.target_eai,
+            symbol,
+            price,
+            explanation,
+        );
+        explanation.add_reason(format!(
+            "Price {price} by bands {lower_band} - {upper_band}"
+        ));
+
+        Some(maker_trading_context_by_side(
+            Self::strategy_name(),
+            self.market_account_id(),
+            side,
+            price,
+            amount,
+            self.max_amount,
+            explanation,
+        ))
+    }
+}
+
+impl DispositionStrategy for MeanReversionStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        now: DateTime,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        let snapshot =
+            local_snapshots_service.get_snapshot(self.market_account_id().market_id())?;
+        let middle_price = (snapshot.get_top_bid()?.0 + snapshot.get_top_ask()?.0) * dec!(0.5);
+        self.candles.add_price(now, middle_price);
+
+        let buy_trading_ctx = self.calc_trading_context_by_side(
+            OrderSide::Buy,
+            local_snapshots_service,
+            explanation.clone(),
+        )?;
+
+        let sell_trading_ctx = self.calc_trading_context_by_side(
+            OrderSide::Sell,
+            local_snapshots_service,
+            explanation.clone(),
+        )?;
+
+        Some(TradingContext::new(buy_trading_ctx, sell_trading_ctx))
+    }
+
+    fn handle_order_fill(
+        &self,
+        _cloned_order: &Arc<OrderSnapshot>,
+        _price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        _cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        self.configuration_descriptor
+    }
+}
@@ -0,0 +1,252 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use mmb_utils::infrastructure::WithExpect;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use mmb_core::disposition_execution::{PriceSlot, TradingContext, TradingContextBySide};
+use mmb_core::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, MarketAccountId};
+use mmb_core::exchanges::general::commission::Percent;
+use mmb_core::explanation::Explanation;
+use mmb_core::lifecycle::trading_engine::EngineContext;
+use mmb_core::math::ConvertPercentToRate;
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::orders::order::{OrderSide, OrderSnapshot};
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::services::candles::{highest_high, lowest_low, sma, CandlesBuilder};
+use mmb_core::settings::{BaseStrategySettings, CurrencyPairSetting};
+use mmb_core::settings_values::deserialize_decimal;
+use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_utils::cancellation_token::CancellationToken;
+use serde::{Deserialize, Serialize};
+
+use crate::common::{
+    calculate_balance_amount, configuration_descriptor, get_symbol, maker_trading_context_by_side,
+    not_quoted_trading_context_by_side, ordinary_currency_pair, set_amount_limit,
+};
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct MomentumStrategySettings {
+    pub currency_pair: CurrencyPairSetting,
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub max_amount: Decimal,
+    pub exchange_account_id: ExchangeAccountId,
+    pub candle_period_secs: u64,
+    /// Count of candles which form channel of highs and lows
+    pub lookback_candles: usize,
+    /// Min distance of candle close beyond channel in percents to treat it as breakout
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub breakout_threshold: Percent,
+}
+
+impl BaseStrategySettings for MomentumStrategySettings {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        self.exchange_account_id
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        ordinary_currency_pair(&self.currency_pair)
+    }
+
+    // Max amount for orders that will be created
+    fn max_amount(&self) -> Amount {
+        self.max_amount
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trend {
+    Up,
+    Down,
+}
+
+/// Breakout strategy: when middle price closes beyond channel of highs and lows of previous
+/// candles, strategy joins best price on the side of breakout and follows trend until price
+/// returns back to average of channel
+pub struct MomentumStrategy {
+    target_eai: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    engine_context: Arc<EngineContext>,
+    configuration_descriptor: ConfigurationDescriptor,
+    max_amount: Decimal,
+    breakout_threshold: Percent,
+    /// Completed candles are channel of `lookback_candles` candles and the last candle which is
+    /// checked for breakout
+    candles: CandlesBuilder,
+    last_checked_candle_ms: Option<i64>,
+    trend: Option<Trend>,
+}
+
+impl MomentumStrategy {
+    pub fn new(settings: &MomentumStrategySettings, engine_context: Arc<EngineContext>) -> Self {
+        let target_eai = settings.exchange_account_id;
+        let currency_pair = settings.currency_pair();
+        let configuration_descriptor =
+            configuration_descriptor(Self::strategy_name(), target_eai, currency_pair);
+
+        let symbol = get_symbol(&engine_context, target_eai, currency_pair)
+            .with_expect(|| format!("failed to get symbol {currency_pair} for {target_eai}"));
+        set_amount_limit(
+            &engine_context,
+            configuration_descriptor,
+            target_eai,
+            symbol,
+            settings.max_amount,
+        );
+
+        MomentumStrategy {
+            target_eai,
+            currency_pair,
+            engine_context,
+            configuration_descriptor,
+            max_amount: settings.max_amount,
+            breakout_threshold: settings.breakout_threshold,
+            candles: CandlesBuilder::new(
+                settings.candle_period_secs as i64 * 1000,
+                settings.lookback_candles + 1,
+            ),
+            last_checked_candle_ms: None,
+            trend: None,
+        }
+    }
+
+    fn strategy_name() -> &'static str {
+        "MomentumStrategy"
+    }
+
+    fn market_account_id(&self) -> MarketAccountId {
+        MarketAccountId::new(self.target_eai, self.currency_pair)
+    }
+
+    /// Trend is changed only when new candle is completed
+    fn update_trend(&mut self) {
+        if !self.candles.is_ready() {
+            return;
+        }
+
+        let mut channel = self.candles.completed().clone();
+        let last_candle = match channel.pop_back() {
+            Some(last_candle) => last_candle,
+            None => return,
+        };
+        if self.last_checked_candle_ms == Some(last_candle.open_time_ms) {
+            return;
+        }
+        self.last_checked_candle_ms = Some(last_candle.open_time_ms);
+
+        let (high, low, average) =
+            match (highest_high(&channel), lowest_low(&channel), sma(&channel)) {
+                (Some(high), Some(low), Some(average)) => (high, low, average),
+                _ => return,
+            };
+
+        let threshold = self.breakout_threshold.percent_to_rate();
+        let close = last_candle.close;
+        self.trend = if close > high * (dec!(1) + threshold) {
+            Some(Trend::Up)
+        } else if close < low * (dec!(1) - threshold) {
+            Some(Trend::Down)
+        } else {
+            match self.trend {
+                Some(Trend::Up) if close < average => None,
+                Some(Trend::Down) if close > average => None,
+                trend => trend,
+            }
+        };
+    }
+
+    fn calc_trading_context_by_side(
+        &self,
+        side: OrderSide,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: Explanation,
+    ) -> Option<TradingContextBySide> {
+        let trend_side = match self.trend {
+            None => {
+                return Some(not_quoted_trading_context_by_side(
+                    "Price isn't trending".to_owned(),
+                    explanation,
+                ))
+            }
+            Some(Trend::Up) => OrderSide::Buy,
+            Some(Trend::Down) => OrderSide::Sell,
+        };
+        if side != trend_side {
+            return Some(not_quoted_trading_context_by_side(
+                format!("Only {trend_side:?} side is quoted in trend"),
+                explanation,
+            ));
+        }
+
+        let snapshot =
+            local_snapshots_service.get_snapshot(self.market_account_id().market_id())?;
+        let price = snapshot.get_top(side)?.0;
+        let symbol = get_symbol(&self.engine_context, self.target_eai, self.currency_pair)?;
+
+        let (amount, mut explanation) = calculate_balance_amount(
+            &self.engine_context,
+            self.configuration_descriptor,
+            side,
+            self.target_eai,
+            symbol,
+            price,
+            explanation,
+        );
+        explanation.add_reason(format!("Best price {price} is joined in trend"));
+
+        Some(maker_trading_context_by_side(
+            Self::strategy_name(),
+            self.market_account_id(),
+            side,
+            price,
+            amount,
+            self.max_amount,
+            explanation,
+        ))
+    }
+}
+
+impl DispositionStrategy for MomentumStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        now: DateTime,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        let snapshot =
+            local_snapshots_service.get_snapshot(self.market_account_id().market_id())?;
+        let middle_price = (snapshot.get_top_bid()?.0 + snapshot.get_top_ask()?.0) * dec!(0.5);
+        self.candles.add_price(now, middle_price);
+        self.update_trend();
+
+        let buy_trading_ctx = self.calc_trading_context_by_side(
+            OrderSide::Buy,
+            local_snapshots_service,
+            explanation.clone(),
+        )?;
+
+        let sell_trading_ctx = self.calc_trading_context_by_side(
+            OrderSide::Sell,
+            local_snapshots_service,
+            explanation.clone(),
+        )?;
+
+        Some(TradingContext::new(buy_trading_ctx, sell_trading_ctx))
+    }
+
+    fn handle_order_fill(
+        &self,
+        _cloned_order: &Arc<OrderSnapshot>,
+        _price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        _cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        self.configuration_descriptor
+    }
+}
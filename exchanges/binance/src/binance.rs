@@ -12,17 +12,23 @@ use mmb_utils::infrastructure::WithExpect;
 use mmb_utils::time::{get_current_milliseconds, u64_to_date_time};
 use mmb_utils::DateTime;
 use parking_lot::{Mutex, RwLock};
+use rust_decimal::Decimal;
 use serde_json::Value;
 use sha2::Sha256;
 use tokio::sync::broadcast;
 
-use super::support::{BinanceBalances, BinanceOrderInfo};
+use super::support::{
+    get_order_book_side, BinanceBalances, BinanceExecutionType, BinanceOrderInfo,
+    BinanceOrderStatus,
+};
 use crate::support::BinanceAccountInfo;
+use mmb_core::exchanges::api_key_permissions::ApiKeyPermissions;
 use mmb_core::exchanges::common::{
     ActivePosition, Amount, ExchangeError, ExchangeErrorType, ExchangeId, Price,
+    CURRENCY_CODE_REGISTRY,
 };
 use mmb_core::exchanges::events::{
-    ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent, TradeId,
+    ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent, TickDirection, Trade, TradeId,
 };
 use mmb_core::exchanges::general::features::{
     OrderFeatures, OrderTradeOption, RestFillsFeatures, RestFillsType, WebSocketOptions,
@@ -30,8 +36,12 @@ use mmb_core::exchanges::general::features::{
 use mmb_core::exchanges::general::handlers::handle_order_filled::FillAmount;
 use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
 use mmb_core::exchanges::general::symbol::{Precision, Symbol};
+use mmb_core::exchanges::host_selection::{self, HostSelector};
 use mmb_core::exchanges::hosts::Hosts;
+use mmb_core::exchanges::margin::MarginInfo;
+use mmb_core::exchanges::payload_anomalies::PayloadAnomalies;
 use mmb_core::exchanges::rest_client::{ErrorHandler, ErrorHandlerData, RestClient};
+use mmb_core::exchanges::rest_pagination::{PageRequest, PaginationKind, Paginator};
 use mmb_core::exchanges::traits::{
     ExchangeClientBuilderResult, HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb,
     OrderCreatedCb, Support,
@@ -48,10 +58,12 @@ use mmb_core::exchanges::{
 };
 use mmb_core::exchanges::{general::handlers::handle_order_filled::FillEvent, rest_client};
 use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::order_book::order_book_data::OrderBookData;
 use mmb_core::orders::fill::EventSourceType;
 use mmb_core::orders::order::*;
 use mmb_core::orders::pool::{OrderRef, OrdersPool};
-use mmb_core::settings::ExchangeSettings;
+use mmb_core::services::funding_rates::FundingRate;
+use mmb_core::settings::{Environment, ExchangeSettings};
 use mmb_core::{exchanges::traits::ExchangeClientBuilder, orders::fill::OrderFillType};
 use mmb_utils::value_to_decimal::GetOrErr;
 use serde::{Deserialize, Serialize};
@@ -104,12 +116,37 @@ impl ErrorHandler for ErrorHandlerBinance {
             | "Filter failure: PERCENT_PRICE"
             | "Quantity less than zero."
             | "Precision is over the maximum defined for this asset." => InvalidOrder,
+            "API-key format invalid."
+            | "Invalid API-key, IP, or permissions for action."
+            | "Signature for this request is not valid." => Authentication,
             msg if msg.contains("Too many requests;") => RateLimit,
             _ => Unknown,
         }
     }
 }
 
+/// Max count of aggregated trades in single REST response
+const AGG_TRADES_LIMIT: u64 = 1000;
+
+/// Depth of order book requested by REST
+const ORDER_BOOK_DEPTH_LIMIT: u64 = 1000;
+
+/// Max count of user trades or orders in single page of history REST response
+const HISTORY_PAGE_LIMIT: usize = 1000;
+const HISTORY_MAX_PAGES: usize = 100;
+const HISTORY_PAGES_DELAY: Duration = Duration::from_millis(100);
+
+/// Binance futures reject `goodTillDate` which is less than 600 seconds after order creation
+const MIN_GOOD_TILL_DATE_MS: u128 = 600_000;
+
+/// Hosts of Binance spot and futures testnets
+const SANDBOX_HOSTS: [&str; 4] = [
+    "https://testnet.binance.vision",
+    "wss://testnet.binance.vision",
+    "https://testnet.binancefuture.com",
+    "wss://stream.binancefuture.com",
+];
+
 pub struct Binance {
     pub settings: ExchangeSettings,
     pub hosts: Hosts,
@@ -125,6 +162,7 @@ pub struct Binance {
     // Currencies used for trading according to user settings
     pub traded_specific_currencies: Mutex<Vec<SpecificCurrencyPair>>,
     pub(super) last_trade_ids: DashMap<CurrencyPair, TradeId>,
+    pub(super) last_agg_trade_ids: DashMap<CurrencyPair, TradeId>,
 
     pub(super) lifetime_manager: Arc<AppLifetimeManager>,
 
@@ -134,6 +172,9 @@ pub struct Binance {
     pub(super) is_reducing_market_data: bool,
 
     pub(super) rest_client: RestClient<ErrorHandlerBinance>,
+    /// Selector of the fastest REST host if host selection is configured
+    pub(super) host_selector: Option<Arc<HostSelector>>,
+    pub(super) payload_anomalies: PayloadAnomalies,
 }
 
 impl Binance {
@@ -149,7 +190,10 @@ impl Binance {
             .is_reducing_market_data
             .unwrap_or(is_reducing_market_data);
 
-        let hosts = Self::make_hosts(settings.is_margin_trading);
+        let hosts = Self::make_hosts_by_settings(&settings);
+        let host_selector = Self::make_host_selector(&settings);
+        let rest_client_settings = settings.rest_client.clone().unwrap_or_default();
+        let egress = settings.egress.clone();
         let exchange_account_id = settings.exchange_account_id;
 
         Self {
@@ -163,39 +207,150 @@ impl Binance {
             supported_currencies: Default::default(),
             traded_specific_currencies: Default::default(),
             last_trade_ids: Default::default(),
+            last_agg_trade_ids: Default::default(),
             subscribe_to_market_data: settings.subscribe_to_market_data,
             is_reducing_market_data,
             settings,
             hosts,
             events_channel,
             lifetime_manager,
-            rest_client: RestClient::new(ErrorHandlerData::new(
-                empty_response_is_ok,
-                exchange_account_id,
-                ErrorHandlerBinance::default(),
-            )),
+            rest_client: RestClient::new_with_settings(
+                ErrorHandlerData::new(
+                    empty_response_is_ok,
+                    exchange_account_id,
+                    ErrorHandlerBinance::default(),
+                ),
+                &rest_client_settings,
+                egress,
+            )
+            .with_host_selector(host_selector.clone()),
+            host_selector,
+            payload_anomalies: PayloadAnomalies::new(exchange_account_id),
         }
     }
 
     pub fn make_hosts(is_margin_trading: bool) -> Hosts {
         if is_margin_trading {
             Hosts {
-                web_socket_host: "wss://fstream.binance.com",
-                web_socket2_host: "wss://fstream3.binance.com",
-                rest_host: "https://fapi.binance.com",
+                web_socket_host: "wss://fstream.binance.com".to_owned(),
+                web_socket2_host: "wss://fstream3.binance.com".to_owned(),
+                rest_host: "https://fapi.binance.com".to_owned(),
             }
         } else {
             Hosts {
-                web_socket_host: "wss://stream.binance.com:9443",
-                web_socket2_host: "wss://stream.binance.com:9443",
-                rest_host: "https://api.binance.com",
+                web_socket_host: "wss://stream.binance.com:9443".to_owned(),
+                web_socket2_host: "wss://stream.binance.com:9443".to_owned(),
+                rest_host: "https://api.binance.com".to_owned(),
+            }
+        }
+    }
+
+    /// Equivalent production REST hosts
+    pub fn make_rest_hosts(is_margin_trading: bool) -> Vec<String> {
+        match is_margin_trading {
+            true => vec![Self::make_hosts(true).rest_host],
+            false => vec![
+                "https://api.binance.com".to_owned(),
+                "https://api1.binance.com".to_owned(),
+                "https://api2.binance.com".to_owned(),
+                "https://api3.binance.com".to_owned(),
+                "https://api4.binance.com".to_owned(),
+            ],
+        }
+    }
+
+    /// Hosts from settings aren't replaced by other hosts
+    fn make_host_selector(settings: &ExchangeSettings) -> Option<Arc<HostSelector>> {
+        let host_selection = settings.host_selection.as_ref()?;
+        if settings.hosts.is_some() {
+            return None;
+        }
+
+        Some(HostSelector::new(
+            settings.exchange_account_id,
+            Self::make_rest_hosts(settings.is_margin_trading),
+            host_selection.clone(),
+        ))
+    }
+
+    /// Host for REST requests selected by latency if host selection is configured
+    pub(super) fn rest_host(&self) -> String {
+        match &self.host_selector {
+            Some(host_selector) => host_selector.selected_host(),
+            None => self.hosts.rest_host.clone(),
+        }
+    }
+
+    /// Host of spot REST API for endpoints which exist there only, also for futures accounts.
+    /// Futures accounts use production spot host only if their hosts are production ones, so
+    /// requests of sandbox accounts aren't sent to production
+    pub(super) fn spot_rest_host(&self) -> Result<String, ExchangeError> {
+        if !self.settings.is_margin_trading {
+            return Ok(self.rest_host());
+        }
+
+        match Self::get_hosts_environment(&self.hosts) {
+            Some(Environment::Production) => Ok(Self::make_hosts(false).rest_host),
+            environment => Err(ExchangeError::new(
+                ExchangeErrorType::Unsupported,
+                format!("Spot REST API isn't available for futures hosts of {environment:?} environment"),
+                None,
+            )),
+        }
+    }
+
+    pub(super) fn start_host_probing(&self) {
+        if let Some(host_selector) = &self.host_selector {
+            let ping_path = self.get_url_path("/fapi/v1/ping", "/api/v3/ping");
+            host_selection::start_host_probing(
+                host_selector.clone(),
+                ping_path,
+                self.settings.egress.clone(),
+            );
+        }
+    }
+
+    /// Hosts from settings if they are set, otherwise production hosts
+    pub fn make_hosts_by_settings(settings: &ExchangeSettings) -> Hosts {
+        match &settings.hosts {
+            Some(hosts) => hosts.into(),
+            None => Self::make_hosts(settings.is_margin_trading),
+        }
+    }
+
+    /// Returns `None` if hosts are unknown or belong to different environments
+    pub fn get_hosts_environment(hosts: &Hosts) -> Option<Environment> {
+        let production_hosts = [Self::make_hosts(false), Self::make_hosts(true)];
+        let host_environment = |host: &str| {
+            if production_hosts.iter().any(|x| {
+                x.rest_host == host || x.web_socket_host == host || x.web_socket2_host == host
+            }) {
+                Some(Environment::Production)
+            } else if SANDBOX_HOSTS.contains(&host) {
+                Some(Environment::Sandbox)
+            } else {
+                None
             }
+        };
+
+        let mut environments = [
+            &hosts.rest_host,
+            &hosts.web_socket_host,
+            &hosts.web_socket2_host,
+        ]
+        .into_iter()
+        .map(|host| host_environment(host.trim_end_matches('/')));
+        let environment = environments.next().flatten();
+
+        match environments.all(|x| x == environment) {
+            true => environment,
+            false => None,
         }
     }
 
     pub(super) async fn get_listen_key(&self) -> Result<RestRequestOutcome, ExchangeError> {
         let full_url = rest_client::build_uri(
-            self.hosts.rest_host,
+            &self.rest_host(),
             self.get_url_path("/sapi/v1/userDataStream", "/api/v3/userDataStream"),
             &vec![],
         );
@@ -228,6 +383,17 @@ impl Binance {
         todo!("is_websocket_reconnecting")
     }
 
+    /// Expire time in milliseconds for `GTD` time in force which is supported on futures only.
+    /// Orders which expire too soon for Binance are sent as `GTC` and canceled by core
+    pub(super) fn native_good_till_date(&self, header: &OrderHeader) -> Option<u128> {
+        if !self.settings.is_margin_trading {
+            return None;
+        }
+
+        let expire_time = u128::try_from(header.expire_time?.timestamp_millis()).ok()?;
+        (expire_time >= get_current_milliseconds() + MIN_GOOD_TILL_DATE_MS).then_some(expire_time)
+    }
+
     pub(super) fn get_server_order_side(side: OrderSide) -> String {
         match side {
             OrderSide::Buy => "BUY".to_owned(),
@@ -244,14 +410,20 @@ impl Binance {
         }
     }
 
-    fn get_local_order_status(status: &str) -> OrderStatus {
+    /// Order with unknown status is considered as not finished, so it's reconciled later
+    fn get_local_order_status(&self, status: &BinanceOrderStatus) -> OrderStatus {
         match status {
-            "NEW" | "PARTIALLY_FILLED" => OrderStatus::Created,
-            "FILLED" => OrderStatus::Completed,
-            "PENDING_CANCEL" => OrderStatus::Canceling,
-            "CANCELED" | "EXPIRED" | "REJECTED" => OrderStatus::Canceled,
-            // TODO just propagate and log there
-            _ => panic!("Unexpected order status"),
+            BinanceOrderStatus::New | BinanceOrderStatus::PartiallyFilled => OrderStatus::Created,
+            BinanceOrderStatus::Filled => OrderStatus::Completed,
+            BinanceOrderStatus::PendingCancel => OrderStatus::Canceling,
+            BinanceOrderStatus::Canceled
+            | BinanceOrderStatus::Expired
+            | BinanceOrderStatus::ExpiredInMatch
+            | BinanceOrderStatus::Rejected => OrderStatus::Canceled,
+            BinanceOrderStatus::Other(status) => {
+                self.payload_anomalies.register("order status", status);
+                OrderStatus::Created
+            }
         }
     }
 
@@ -309,7 +481,7 @@ impl Binance {
             specific.exchange_order_id.to_string().as_str().into(),
             specific.client_order_id.clone(),
             Self::get_local_order_side(&specific.side),
-            Self::get_local_order_status(&specific.status),
+            self.get_local_order_status(&specific.status),
             specific.price,
             specific.orig_quantity,
             specific.price,
@@ -345,8 +517,8 @@ impl Binance {
             .as_str()
             .ok_or_else(|| anyhow!("Unable to parse time in force"))?;
 
-        match execution_type {
-            "NEW" => match order_status {
+        match BinanceExecutionType::from(execution_type) {
+            BinanceExecutionType::New => match order_status {
                 "NEW" => {
                     (self.order_created_callback)(
                         client_order_id.into(),
@@ -360,7 +532,7 @@ impl Binance {
                     msg_to_log
                 ),
             },
-            "CANCELED" => match order_status {
+            BinanceExecutionType::Canceled => match order_status {
                 "CANCELED" => {
                     (self.order_cancelled_callback)(
                         client_order_id.into(),
@@ -374,11 +546,11 @@ impl Binance {
                     msg_to_log
                 ),
             },
-            "REJECTED" => {
+            BinanceExecutionType::Rejected => {
                 // TODO: May be not handle error in Rest but move it here to make it unified?
                 // We get notification of rejected orders from the rest responses
             }
-            "EXPIRED" => match time_in_force {
+            BinanceExecutionType::Expired => match time_in_force {
                 "GTX" => {
                     (self.order_cancelled_callback)(
                         client_order_id.into(),
@@ -392,7 +564,18 @@ impl Binance {
                     msg_to_log
                 ),
             },
-            "TRADE" | "CALCULATED" => {
+            BinanceExecutionType::Replaced | BinanceExecutionType::Amendment => {
+                // Order amount is reduced by REST request and local order is updated by its response
+            }
+            BinanceExecutionType::TradePrevention => {
+                // Order is expired by self-trade prevention
+                (self.order_cancelled_callback)(
+                    client_order_id.into(),
+                    exchange_order_id.into(),
+                    EventSourceType::WebSocket,
+                );
+            }
+            BinanceExecutionType::Trade | BinanceExecutionType::Calculated => {
                 let event_data = self.prepare_data_for_fill_handler(
                     &json_response,
                     execution_type,
@@ -402,7 +585,9 @@ impl Binance {
 
                 (self.handle_order_filled_callback)(event_data);
             }
-            _ => log::error!("Impossible execution type"),
+            BinanceExecutionType::Other(execution_type) => self
+                .payload_anomalies
+                .register("execution type", &execution_type),
         }
 
         Ok(())
@@ -440,15 +625,26 @@ impl Binance {
         let total_filled_amount = json_response["z"]
             .as_str()
             .ok_or_else(|| anyhow!("Unable to parse total filled amount"))?;
-        let commission_amount = json_response["n"]
-            .as_str()
-            .ok_or_else(|| anyhow!("Unable to parse last commission amount"))?;
-        let commission_currency = json_response["N"]
-            .as_str()
-            .ok_or_else(|| anyhow!("Unable to parse last commission currency"))?;
-        let commission_currency_code = self
-            .get_currency_code(&commission_currency.into())
-            .ok_or_else(|| anyhow!("There are no such supported currency code"))?;
+        // commission fields can be missing, e.g. for zero commission, then it's calculated by engine
+        let commission_amount = match json_response["n"].as_str() {
+            Some(commission_amount) => Some(commission_amount.parse()?),
+            None => {
+                self.payload_anomalies
+                    .register("missing field", "commission amount");
+                None
+            }
+        };
+        let commission_currency_code = match json_response["N"].as_str() {
+            Some(commission_currency) => Some(
+                self.get_currency_code(&commission_currency.into())
+                    .ok_or_else(|| anyhow!("There are no such supported currency code"))?,
+            ),
+            None => {
+                self.payload_anomalies
+                    .register("missing field", "commission currency");
+                None
+            }
+        };
         let is_maker = json_response["m"]
             .as_bool()
             .ok_or_else(|| anyhow!("Unable to parse trade side"))?;
@@ -479,9 +675,9 @@ impl Binance {
             fill_price: last_filled_price.parse()?,
             fill_amount,
             order_role: Some(order_role),
-            commission_currency_code: Some(commission_currency_code),
+            commission_currency_code,
             commission_rate: None,
-            commission_amount: Some(commission_amount.parse()?),
+            commission_amount,
             fill_type,
             special_order_data: None,
             fill_date: Some(fill_date),
@@ -551,7 +747,7 @@ impl Binance {
         http_params: Vec<(String, String)>,
     ) -> Result<RestRequestOutcome, ExchangeError> {
         let full_url = rest_client::build_uri(
-            self.hosts.rest_host,
+            &self.rest_host(),
             self.get_url_path("/fapi/v1/openOrders", "/api/v3/openOrders"),
             &http_params,
         );
@@ -586,7 +782,7 @@ impl Binance {
         self.add_authentification_headers(&mut http_params)?;
 
         let full_url = rest_client::build_uri(
-            self.hosts.rest_host,
+            &self.rest_host(),
             self.get_url_path("/fapi/v1/order", "/api/v3/order"),
             &http_params,
         );
@@ -677,7 +873,7 @@ impl Binance {
         self.add_authentification_headers(&mut http_params)?;
 
         let url_path = "/fapi/v1/order";
-        let full_url = rest_client::build_uri(self.hosts.rest_host, url_path, &http_params);
+        let full_url = rest_client::build_uri(&self.rest_host(), url_path, &http_params);
 
         let log_args =
             format_args!("Close position response for {:?} {:?}", position, price).to_string();
@@ -699,7 +895,7 @@ impl Binance {
         self.add_authentification_headers(&mut http_params)?;
 
         let url_path = "/fapi/v2/positionRisk";
-        let full_url = rest_client::build_uri(self.hosts.rest_host, url_path, &http_params);
+        let full_url = rest_client::build_uri(&self.rest_host(), url_path, &http_params);
 
         self.rest_client
             .get(
@@ -711,12 +907,184 @@ impl Binance {
             .await
     }
 
+    #[named]
+    pub(super) async fn request_funding_rate(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<RestRequestOutcome, ExchangeError> {
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let http_params = vec![(
+            "symbol".to_owned(),
+            specific_currency_pair.as_str().to_owned(),
+        )];
+        let full_url =
+            rest_client::build_uri(&self.rest_host(), "/fapi/v1/premiumIndex", &http_params);
+
+        self.rest_client
+            .get(
+                full_url,
+                &self.settings.api_key,
+                function_name!(),
+                format!("currency_pair: {currency_pair}"),
+            )
+            .await
+    }
+
+    #[named]
+    pub(super) async fn request_daily_tickers(&self) -> Result<RestRequestOutcome, ExchangeError> {
+        let full_url = rest_client::build_uri(
+            &self.rest_host(),
+            self.get_url_path("/fapi/v1/ticker/24hr", "/api/v3/ticker/24hr"),
+            &vec![],
+        );
+
+        self.rest_client
+            .get(
+                full_url,
+                &self.settings.api_key,
+                function_name!(),
+                "".to_string(),
+            )
+            .await
+    }
+
+    /// Tickers of symbols which aren't built are skipped
+    pub(super) fn parse_daily_volumes(
+        &self,
+        response: &RestRequestOutcome,
+    ) -> Result<HashMap<CurrencyPair, Amount>> {
+        let tickers: Vec<BinanceDailyTicker> = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for daily tickers request")?;
+
+        Ok(tickers
+            .into_iter()
+            .filter_map(|ticker| {
+                let currency_pair = self
+                    .get_unified_currency_pair(&ticker.symbol.as_str().into())
+                    .ok()?;
+                Some((currency_pair, ticker.quote_volume))
+            })
+            .collect())
+    }
+
+    #[named]
+    pub(super) async fn request_agg_trades(
+        &self,
+        currency_pair: CurrencyPair,
+        last_trade_id: u64,
+    ) -> Result<RestRequestOutcome, ExchangeError> {
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            ("fromId".to_owned(), (last_trade_id + 1).to_string()),
+            ("limit".to_owned(), AGG_TRADES_LIMIT.to_string()),
+        ];
+        let full_url = rest_client::build_uri(
+            &self.rest_host(),
+            self.get_url_path("/fapi/v1/aggTrades", "/api/v3/aggTrades"),
+            &http_params,
+        );
+
+        self.rest_client
+            .get(
+                full_url,
+                &self.settings.api_key,
+                function_name!(),
+                format!("currency_pair: {currency_pair}, last_trade_id: {last_trade_id}"),
+            )
+            .await
+    }
+
+    /// Ids of aggregated trades are the same as in `aggTrade` stream
+    pub(super) fn parse_agg_trades(response: &RestRequestOutcome) -> Result<Vec<Trade>> {
+        let agg_trades: Vec<BinanceAggTrade> = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for aggregated trades request")?;
+
+        Ok(agg_trades
+            .into_iter()
+            .map(|x| Trade {
+                trade_id: TradeId::Number(x.id),
+                price: x.price,
+                quantity: x.quantity,
+                side: match x.is_buyer_maker {
+                    true => OrderSide::Sell,
+                    false => OrderSide::Buy,
+                },
+                transaction_time: u64_to_date_time(x.time),
+                tick_direction: TickDirection::None,
+            })
+            .collect())
+    }
+
+    /// Binance doesn't provide predicted rate of the period after the next one
+    pub(super) fn parse_funding_rate(response: &RestRequestOutcome) -> Result<FundingRate> {
+        let premium_index: BinancePremiumIndex = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for funding rate request")?;
+
+        Ok(FundingRate {
+            current_rate: premium_index.last_funding_rate,
+            predicted_rate: None,
+            next_funding_time: match premium_index.next_funding_time {
+                0 => None,
+                time => Some(u64_to_date_time(time)),
+            },
+        })
+    }
+
+    #[named]
+    pub(super) async fn request_order_book(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<RestRequestOutcome, ExchangeError> {
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            ("limit".to_owned(), ORDER_BOOK_DEPTH_LIMIT.to_string()),
+        ];
+        let full_url = rest_client::build_uri(
+            &self.rest_host(),
+            self.get_url_path("/fapi/v1/depth", "/api/v3/depth"),
+            &http_params,
+        );
+
+        self.rest_client
+            .get(
+                full_url,
+                &self.settings.api_key,
+                function_name!(),
+                format!("currency_pair: {currency_pair}"),
+            )
+            .await
+    }
+
+    pub(super) fn parse_order_book(response: &RestRequestOutcome) -> Result<OrderBookData> {
+        let data: Value = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for order book request")?;
+        let raw_asks = data["asks"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Unable to parse 'asks' in Binance"))?;
+        let raw_bids = data["bids"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Unable to parse 'bids' in Binance"))?;
+
+        Ok(OrderBookData::new(
+            get_order_book_side(raw_asks)?,
+            get_order_book_side(raw_bids)?,
+        ))
+    }
+
     #[named]
     pub(super) async fn request_get_balance(&self) -> Result<RestRequestOutcome, ExchangeError> {
         let mut http_params = Vec::new();
         self.add_authentification_headers(&mut http_params)?;
         let full_url = rest_client::build_uri(
-            self.hosts.rest_host,
+            &self.rest_host(),
             self.get_url_path("/fapi/v2/account", "/api/v3/account"),
             &http_params,
         );
@@ -735,6 +1103,64 @@ impl Binance {
         panic!("not supported request")
     }
 
+    /// Key restrictions are available on production spot host only, also for futures keys
+    pub(super) fn is_api_restrictions_available(&self) -> bool {
+        Self::get_hosts_environment(&self.hosts) == Some(Environment::Production)
+            && self.spot_rest_host().is_ok()
+    }
+
+    #[named]
+    pub(super) async fn request_api_restrictions(
+        &self,
+    ) -> Result<RestRequestOutcome, ExchangeError> {
+        let mut http_params = Vec::new();
+        self.add_authentification_headers(&mut http_params)?;
+        let full_url = rest_client::build_uri(
+            &self.spot_rest_host()?,
+            "/sapi/v1/account/apiRestrictions",
+            &http_params,
+        );
+
+        self.rest_client
+            .get(
+                full_url,
+                &self.settings.api_key,
+                function_name!(),
+                "".to_string(),
+            )
+            .await
+    }
+
+    pub(super) fn parse_api_key_permissions(
+        response: &RestRequestOutcome,
+    ) -> Result<ApiKeyPermissions> {
+        let restrictions: BinanceApiRestrictions = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for api restrictions request")?;
+
+        Ok(ApiKeyPermissions {
+            can_read: restrictions.enable_reading,
+            can_trade: restrictions.enable_spot_and_margin_trading
+                || restrictions.enable_margin
+                || restrictions.enable_futures
+                || restrictions.enable_vanilla_options,
+            can_withdraw: restrictions.enable_withdrawals,
+            expiration_time: restrictions
+                .trading_authority_expiration_time
+                .map(u64_to_date_time),
+        })
+    }
+
+    pub(super) fn parse_margin_info(response: &RestRequestOutcome) -> Result<MarginInfo> {
+        let account: BinanceMarginAccountInfo = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for margin account request")?;
+
+        Ok(MarginInfo {
+            margin_balance: account.total_margin_balance,
+            initial_margin: account.total_initial_margin,
+            maintenance_margin: account.total_maint_margin,
+        })
+    }
+
     pub(super) fn parse_get_balance(
         &self,
         response: &RestRequestOutcome,
@@ -769,7 +1195,7 @@ impl Binance {
         self.add_authentification_headers(&mut http_params)?;
 
         let path = self.get_url_path("/fapi/v1/order", "/api/v3/order");
-        let full_url = rest_client::build_uri(self.hosts.rest_host, path, &http_params);
+        let full_url = rest_client::build_uri(&self.rest_host(), path, &http_params);
 
         let log_args = format!("Cancel order for {}", order.header.client_order_id);
         self.rest_client
@@ -777,21 +1203,128 @@ impl Binance {
             .await
     }
 
+    /// Order is canceled by `origClientOrderId`, so it's canceled before its creation is
+    /// acknowledged and exchange order id is known
+    #[named]
+    pub(super) async fn request_cancel_order_by_client_order_id(
+        &self,
+        header: &OrderHeader,
+    ) -> Result<RestRequestOutcome, ExchangeError> {
+        let specific_currency_pair = self.get_specific_currency_pair(header.currency_pair);
+
+        let mut http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            (
+                "origClientOrderId".to_owned(),
+                header.client_order_id.as_str().to_owned(),
+            ),
+        ];
+        self.add_authentification_headers(&mut http_params)?;
+
+        let path = self.get_url_path("/fapi/v1/order", "/api/v3/order");
+        let full_url = rest_client::build_uri(&self.rest_host(), path, &http_params);
+
+        let log_args = format!("Cancel order by client order id {}", header.client_order_id);
+        self.rest_client
+            .delete(full_url, &self.settings.api_key, function_name!(), log_args)
+            .await
+    }
+
+    /// Spot orders are amended with keeping priority, futures orders keep priority
+    /// if only quantity is decreased
+    #[named]
+    pub(super) async fn request_reduce_order(
+        &self,
+        order: &OrderRef,
+        new_amount: Amount,
+    ) -> Result<RestRequestOutcome, ExchangeError> {
+        let (header, price, exchange_order_id) = order.fn_ref(|order| {
+            (
+                order.header.clone(),
+                order.price(),
+                order.exchange_order_id(),
+            )
+        });
+        let exchange_order_id = exchange_order_id.ok_or_else(|| {
+            ExchangeError::unknown("Order without exchange_order_id can't be reduced")
+        })?;
+
+        let specific_currency_pair = self.get_specific_currency_pair(header.currency_pair);
+        let mut http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            ("orderId".to_owned(), exchange_order_id.as_str().to_owned()),
+        ];
+        if self.settings.is_margin_trading {
+            http_params.push(("side".to_owned(), Self::get_server_order_side(header.side)));
+            http_params.push(("quantity".to_owned(), new_amount.to_string()));
+            http_params.push(("price".to_owned(), price.to_string()));
+        } else {
+            http_params.push(("newQty".to_owned(), new_amount.to_string()));
+        }
+        self.add_authentification_headers(&mut http_params)?;
+
+        let path = self.get_url_path("/fapi/v1/order", "/api/v3/order/amend/keepPriority");
+        let full_url = rest_client::build_uri(&self.rest_host(), path, &http_params);
+
+        let log_args = format!("Reduce order {} to {new_amount}", header.client_order_id);
+        self.rest_client
+            .put(full_url, &self.settings.api_key, function_name!(), log_args)
+            .await
+    }
+
+    /// Trades and orders history of Binance is paginated by ids which are increasing in time
+    pub(super) fn history_paginator() -> Paginator {
+        Paginator {
+            kind: PaginationKind::AfterId,
+            page_limit: HISTORY_PAGE_LIMIT,
+            max_pages: HISTORY_MAX_PAGES,
+            delay_between_pages: HISTORY_PAGES_DELAY,
+        }
+    }
+
+    fn add_history_page_params(
+        http_params: &mut rest_client::HttpParams,
+        page_request: &PageRequest,
+        id_param_name: &str,
+    ) {
+        match page_request {
+            PageRequest::First {
+                from_time: Some(from_time),
+            } => http_params.push((
+                "startTime".to_owned(),
+                from_time.timestamp_millis().to_string(),
+            )),
+            // Binance returns items with ids starting from specified one inclusively
+            PageRequest::AfterId(id) => {
+                http_params.push((id_param_name.to_owned(), (id + 1).to_string()))
+            }
+            _ => {}
+        }
+        http_params.push(("limit".to_owned(), HISTORY_PAGE_LIMIT.to_string()));
+    }
+
     #[named]
     pub(super) async fn request_my_trades(
         &self,
         symbol: &Symbol,
-        _last_date_time: Option<DateTime>,
+        page_request: &PageRequest,
     ) -> Result<RestRequestOutcome, ExchangeError> {
         let specific_currency_pair = self.get_specific_currency_pair(symbol.currency_pair());
         let mut http_params = vec![(
             "symbol".to_owned(),
             specific_currency_pair.as_str().to_owned(),
         )];
+        Self::add_history_page_params(&mut http_params, page_request, "fromId");
 
         self.add_authentification_headers(&mut http_params)?;
         let full_url = rest_client::build_uri(
-            self.hosts.rest_host,
+            &self.rest_host(),
             self.get_url_path("/fapi/v1/userTrades", "/api/v3/myTrades"),
             &http_params,
         );
@@ -801,11 +1334,55 @@ impl Binance {
                 full_url,
                 &self.settings.api_key,
                 function_name!(),
-                "".to_string(),
+                format!("page_request: {page_request:?}"),
+            )
+            .await
+    }
+
+    #[named]
+    pub(super) async fn request_all_orders(
+        &self,
+        currency_pair: CurrencyPair,
+        page_request: &PageRequest,
+        to: DateTime,
+    ) -> Result<RestRequestOutcome, ExchangeError> {
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let mut http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            ("endTime".to_owned(), to.timestamp_millis().to_string()),
+        ];
+        Self::add_history_page_params(&mut http_params, page_request, "orderId");
+
+        self.add_authentification_headers(&mut http_params)?;
+        let full_url = rest_client::build_uri(
+            &self.rest_host(),
+            self.get_url_path("/fapi/v1/allOrders", "/api/v3/allOrders"),
+            &http_params,
+        );
+
+        self.rest_client
+            .get(
+                full_url,
+                &self.settings.api_key,
+                function_name!(),
+                format!("currency_pair: {currency_pair}, page_request: {page_request:?}, to: {to}"),
             )
             .await
     }
 
+    pub(super) fn parse_all_orders(&self, response: &RestRequestOutcome) -> Result<Vec<OrderInfo>> {
+        let binance_orders: Vec<BinanceOrderInfo> = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for all orders request")?;
+
+        Ok(binance_orders
+            .iter()
+            .map(|order| self.specific_order_info_to_unified(order))
+            .collect())
+    }
+
     pub(super) fn parse_get_my_trades(
         &self,
         response: &RestRequestOutcome,
@@ -854,8 +1431,8 @@ impl Binance {
             }
         }
 
-        let my_trades: Vec<BinanceMyTrade> =
-            serde_json::from_str(&response.content).expect("Unable to parse trades from response");
+        let my_trades: Vec<BinanceMyTrade> = serde_json::from_str(&response.content)
+            .context("Unable to parse trades from response")?;
 
         my_trades
             .into_iter()
@@ -886,24 +1463,44 @@ impl Binance {
                 "type".to_owned(),
                 Self::get_server_order_type(header.order_type),
             ),
-            ("quantity".to_owned(), header.amount.to_string()),
             (
                 "newClientOrderId".to_owned(),
                 header.client_order_id.as_str().to_owned(),
             ),
         ];
 
+        // `quoteOrderQty` is supported only for spot market orders, so `supports_quote_order_amount`
+        // feature is set for spot only and base amount is calculated by core for futures
+        match header.quote_amount {
+            Some(quote_amount)
+                if header.order_type == OrderType::Market && !self.settings.is_margin_trading =>
+            {
+                http_params.push(("quoteOrderQty".to_owned(), quote_amount.to_string()))
+            }
+            _ => http_params.push(("quantity".to_owned(), header.amount.to_string())),
+        }
+
         if header.order_type != OrderType::Market {
-            http_params.push(("timeInForce".to_owned(), "GTC".to_owned()));
+            match self.native_good_till_date(&header) {
+                Some(good_till_date) => {
+                    http_params.push(("timeInForce".to_owned(), "GTD".to_owned()));
+                    http_params.push(("goodTillDate".to_owned(), good_till_date.to_string()));
+                }
+                None => http_params.push(("timeInForce".to_owned(), "GTC".to_owned())),
+            }
             http_params.push(("price".to_owned(), price.to_string()));
         } else if header.execution_type == OrderExecutionType::MakerOnly {
             http_params.push(("timeInForce".to_owned(), "GTX".to_owned()));
         }
 
+        if header.reduce_only && self.settings.is_margin_trading {
+            http_params.push(("reduceOnly".to_owned(), "true".to_owned()));
+        }
+
         self.add_authentification_headers(&mut http_params)?;
 
         let full_url = rest_client::build_uri(
-            self.hosts.rest_host,
+            &self.rest_host(),
             self.get_url_path("/fapi/v1/order", "/api/v3/order"),
             &vec![],
         );
@@ -925,7 +1522,7 @@ impl Binance {
     pub(super) async fn request_all_symbols(&self) -> Result<RestRequestOutcome, ExchangeError> {
         // In current versions works only with Spot market
         let url_path = "/api/v3/exchangeInfo";
-        let full_url = rest_client::build_uri(self.hosts.rest_host, url_path, &vec![]);
+        let full_url = rest_client::build_uri(&self.rest_host(), url_path, &vec![]);
 
         self.rest_client
             .get(
@@ -960,13 +1557,24 @@ impl Binance {
             let quote_currency_id = &symbol
                 .get_as_str("quoteAsset")
                 .expect("Unable to get quote currency id from Binance");
-            let base = base_currency_id.as_str().into();
-            let quote = quote_currency_id.as_str().into();
-
             let specific_currency_pair_id = symbol
                 .get_as_str("symbol")
                 .expect("Unable to get specific currency pair");
             let specific_currency_pair = specific_currency_pair_id.as_str().into();
+
+            let (base, quote) = match self
+                .settings
+                .get_overridden_currency_pair(specific_currency_pair)
+            {
+                Some(currency_pair) => {
+                    let codes = currency_pair.to_codes();
+                    (codes.base, codes.quote)
+                }
+                None => (
+                    CURRENCY_CODE_REGISTRY.normalize(self.id.exchange_id, base_currency_id),
+                    CURRENCY_CODE_REGISTRY.normalize(self.id.exchange_id, quote_currency_id),
+                ),
+            };
             let unified_currency_pair = CurrencyPair::from_codes(base, quote);
             self.unified_to_specific
                 .write()
@@ -1066,24 +1674,45 @@ impl ExchangeClientBuilder for BinanceBuilder {
     ) -> ExchangeClientBuilderResult {
         let exchange_account_id = exchange_settings.exchange_account_id;
         let empty_response_is_ok = false;
+        // reduce-only orders are available on futures only
+        let supports_reduce_only = exchange_settings.is_margin_trading;
+        // market orders sized by `quoteOrderQty` are available on spot only
+        let supports_quote_order_amount = !exchange_settings.is_margin_trading;
+        // `GTD` time in force is available on futures only
+        let supports_good_till_date = exchange_settings.is_margin_trading;
+        // missed trades are requested by ids of aggregated trades, so only trades of
+        // `aggTrade` stream can be backfilled
+        let supports_trades_backfill = exchange_settings
+            .websocket_channels
+            .iter()
+            .any(|x| x == "aggTrade");
+
+        let binance = Binance::new(
+            exchange_account_id,
+            exchange_settings,
+            events_channel,
+            lifetime_manager,
+            false,
+            empty_response_is_ok,
+        );
+        binance.start_host_probing();
 
         ExchangeClientBuilderResult {
-            client: Box::new(Binance::new(
-                exchange_account_id,
-                exchange_settings,
-                events_channel,
-                lifetime_manager,
-                false,
-                empty_response_is_ok,
-            )) as BoxExchangeClient,
+            client: Box::new(binance) as BoxExchangeClient,
             features: ExchangeFeatures::new(
                 OpenOrdersType::AllCurrencyPair,
                 RestFillsFeatures::new(RestFillsType::None),
                 OrderFeatures {
                     supports_get_order_info_by_client_order_id: true,
+                    supports_reduce_only,
+                    supports_quote_order_amount,
+                    supports_good_till_date,
                     ..OrderFeatures::default()
                 },
-                OrderTradeOption::default(),
+                OrderTradeOption {
+                    supports_trades_backfill,
+                    ..OrderTradeOption::default()
+                },
                 WebSocketOptions::default(),
                 empty_response_is_ok,
                 AllowedEventSourceType::All,
@@ -1100,12 +1729,18 @@ impl ExchangeClientBuilder for BinanceBuilder {
     fn get_exchange_id(&self) -> ExchangeId {
         "Binance".into()
     }
+
+    fn get_hosts_environment(&self, exchange_settings: &ExchangeSettings) -> Option<Environment> {
+        Binance::get_hosts_environment(&Binance::make_hosts_by_settings(exchange_settings))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use mmb_core::settings::{CurrencyPairOverrideSettings, HostsSettings};
     use mmb_utils::cancellation_token::CancellationToken;
+    use rust_decimal_macros::dec;
 
     #[test]
     fn generate_signature() {
@@ -1135,6 +1770,110 @@ mod tests {
         assert_eq!(result, right_value);
     }
 
+    #[test]
+    fn parse_api_key_permissions() {
+        let response = RestRequestOutcome::new(
+            r#"{"ipRestrict":false,"createTime":1623840271000,"enableWithdrawals":false,"enableInternalTransfer":true,"permitsUniversalTransfer":true,"enableVanillaOptions":false,"enableReading":true,"enableFutures":false,"enableMargin":false,"enableSpotAndMarginTrading":true,"tradingAuthorityExpirationTime":1666224000000}"#.to_owned(),
+            hyper::StatusCode::OK,
+        );
+
+        let permissions = Binance::parse_api_key_permissions(&response).expect("in test");
+
+        assert_eq!(
+            permissions,
+            ApiKeyPermissions {
+                can_read: true,
+                can_trade: true,
+                can_withdraw: false,
+                expiration_time: Some(u64_to_date_time(1666224000000)),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_funding_rate() {
+        let response = RestRequestOutcome::new(
+            r#"{"symbol":"BTCUSDT","markPrice":"19160.10000000","indexPrice":"19168.52340426","estimatedSettlePrice":"19172.31425132","lastFundingRate":"0.00010000","interestRate":"0.00010000","nextFundingTime":1666252800000,"time":1666240000000}"#.to_owned(),
+            hyper::StatusCode::OK,
+        );
+
+        let funding_rate = Binance::parse_funding_rate(&response).expect("in test");
+
+        assert_eq!(
+            funding_rate,
+            FundingRate {
+                current_rate: dec!(0.0001),
+                predicted_rate: None,
+                next_funding_time: Some(u64_to_date_time(1666252800000)),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_agg_trades() {
+        let response = RestRequestOutcome::new(
+            r#"[{"a":26129,"p":"0.01633102","q":"4.70443515","f":27781,"l":27781,"T":1498793709153,"m":true,"M":true},{"a":26130,"p":"0.01633200","q":"1.5","f":27782,"l":27783,"T":1498793709160,"m":false,"M":true}]"#.to_owned(),
+            hyper::StatusCode::OK,
+        );
+
+        let trades = Binance::parse_agg_trades(&response).expect("in test");
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].trade_id, TradeId::Number(26129));
+        assert_eq!(trades[0].price, dec!(0.01633102));
+        assert_eq!(trades[0].quantity, dec!(4.70443515));
+        assert_eq!(trades[0].side, OrderSide::Sell);
+        assert_eq!(trades[0].transaction_time, u64_to_date_time(1498793709153));
+        assert_eq!(trades[1].trade_id, TradeId::Number(26130));
+        assert_eq!(trades[1].side, OrderSide::Buy);
+    }
+
+    #[test]
+    fn hosts_environment() {
+        assert_eq!(
+            Binance::get_hosts_environment(&Binance::make_hosts(true)),
+            Some(Environment::Production)
+        );
+
+        let testnet_settings = |web_socket2_host: Option<&str>| ExchangeSettings {
+            hosts: Some(HostsSettings {
+                rest_host: "https://testnet.binancefuture.com".to_owned(),
+                web_socket_host: "wss://stream.binancefuture.com".to_owned(),
+                web_socket2_host: web_socket2_host.map(|x| x.to_owned()),
+            }),
+            ..Default::default()
+        };
+        let hosts = Binance::make_hosts_by_settings(&testnet_settings(None));
+        assert_eq!(
+            Binance::get_hosts_environment(&hosts),
+            Some(Environment::Sandbox)
+        );
+
+        // mixed environments
+        let hosts =
+            Binance::make_hosts_by_settings(&testnet_settings(Some("wss://fstream.binance.com")));
+        assert_eq!(Binance::get_hosts_environment(&hosts), None);
+    }
+
+    #[test]
+    fn parse_margin_info() {
+        let response = RestRequestOutcome::new(
+            r#"{"feeTier":0,"canTrade":true,"totalInitialMargin":"120.50","totalMaintMargin":"12.05","totalWalletBalance":"990.00","totalUnrealizedProfit":"10.00","totalMarginBalance":"1000.00","availableBalance":"879.50"}"#.to_owned(),
+            hyper::StatusCode::OK,
+        );
+
+        let margin_info = Binance::parse_margin_info(&response).expect("in test");
+
+        assert_eq!(
+            margin_info,
+            MarginInfo {
+                margin_balance: dec!(1000),
+                initial_margin: dec!(120.5),
+                maintenance_margin: dec!(12.05),
+            }
+        );
+    }
+
     #[test]
     fn to_http_string() {
         let parameters: rest_client::HttpParams = vec![
@@ -1153,6 +1892,283 @@ mod tests {
         let right_value = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
         assert_eq!(http_string, right_value);
     }
+
+    #[test]
+    fn message_partition_key_is_symbol_of_stream() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let settings =
+            ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), false);
+        let (tx, _) = broadcast::channel(10);
+        let binance = Binance::new(
+            exchange_account_id,
+            settings,
+            tx,
+            AppLifetimeManager::new(CancellationToken::default()),
+            false,
+            false,
+        );
+
+        let depth = r#"{"stream":"btcusdt@depth20","data":{"lastUpdateId":1,"bids":[],"asks":[]}}"#;
+        let trade = r#"{"stream":"btcusdt@trade","data":{"t":1}}"#;
+        let account = r#"{"e":"executionReport","s":"BTCUSDT"}"#;
+
+        assert_eq!(binance.get_message_partition_key(depth), Some("btcusdt"));
+        assert_eq!(binance.get_message_partition_key(trade), Some("btcusdt"));
+        assert_eq!(binance.get_message_partition_key(account), None);
+    }
+
+    #[test]
+    fn unknown_order_status_is_tolerated() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let settings =
+            ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), false);
+        let (tx, _) = broadcast::channel(10);
+        let binance = Binance::new(
+            exchange_account_id,
+            settings,
+            tx,
+            AppLifetimeManager::new(CancellationToken::default()),
+            false,
+            false,
+        );
+
+        // unknown fields are ignored
+        let order_info: BinanceOrderInfo = serde_json::from_str(
+            r#"{"symbol":"BTCUSDT","orderId":1,"clientOrderId":"order1","price":"0.1","origQty":"1","executedQty":"0","status":"NEW_STATUS","side":"BUY","selfTradePreventionMode":"NONE"}"#,
+        )
+        .expect("in test");
+
+        assert_eq!(
+            order_info.status,
+            BinanceOrderStatus::Other("NEW_STATUS".to_owned())
+        );
+        assert_eq!(
+            binance.get_local_order_status(&order_info.status),
+            OrderStatus::Created
+        );
+        assert_eq!(binance.payload_anomalies.snapshot()[0].count, 1);
+    }
+
+    #[test]
+    fn overridden_currency_pair_is_used_for_symbol() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let currency_pair = CurrencyPair::from_codes("bsv".into(), "usdt".into());
+        let specific_currency_pair: SpecificCurrencyPair = "BCHSVUSDT".into();
+        let mut settings =
+            ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), false);
+        settings.currency_pair_overrides = Some(vec![CurrencyPairOverrideSettings {
+            currency_pair,
+            specific_currency_pair,
+        }]);
+        let (tx, _) = broadcast::channel(10);
+        let binance = Binance::new(
+            exchange_account_id,
+            settings,
+            tx,
+            AppLifetimeManager::new(CancellationToken::default()),
+            false,
+            false,
+        );
+        let response = RestRequestOutcome::new(
+            r#"{"symbols":[{"symbol":"BCHSVUSDT","status":"TRADING","baseAsset":"BCHSV","quoteAsset":"USDT","filters":[{"filterType":"PRICE_FILTER","minPrice":"0.01","maxPrice":"100000","tickSize":"0.01"},{"filterType":"LOT_SIZE","minQty":"0.001","maxQty":"9000","stepSize":"0.001"}]}]}"#.to_owned(),
+            hyper::StatusCode::OK,
+        );
+
+        let symbols = binance.parse_all_symbols(&response).expect("in test");
+
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].currency_pair(), currency_pair);
+        assert_eq!(
+            binance
+                .get_unified_currency_pair(&specific_currency_pair)
+                .expect("in test"),
+            currency_pair
+        );
+        assert_eq!(
+            binance.unified_to_specific.read().get(&currency_pair),
+            Some(&specific_currency_pair)
+        );
+    }
+
+    #[test]
+    fn spot_rest_host_of_futures_is_production_only_for_production_hosts() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let binance = |hosts: Option<HostsSettings>| {
+            let mut settings =
+                ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), true);
+            settings.hosts = hosts;
+            let (tx, _) = broadcast::channel(10);
+            Binance::new(
+                exchange_account_id,
+                settings,
+                tx,
+                AppLifetimeManager::new(CancellationToken::default()),
+                false,
+                false,
+            )
+        };
+
+        assert_eq!(
+            binance(None).spot_rest_host().expect("in test"),
+            "https://api.binance.com"
+        );
+
+        let testnet_hosts = HostsSettings {
+            rest_host: "https://testnet.binancefuture.com".to_owned(),
+            web_socket_host: "wss://stream.binancefuture.com".to_owned(),
+            web_socket2_host: None,
+        };
+        assert!(binance(Some(testnet_hosts)).spot_rest_host().is_err());
+    }
+
+    #[test]
+    fn api_restrictions_are_available_for_production_hosts_only() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let binance = |is_margin_trading: bool, hosts: Option<HostsSettings>| {
+            let mut settings = ExchangeSettings::new_short(
+                exchange_account_id,
+                "".into(),
+                "".into(),
+                is_margin_trading,
+            );
+            settings.hosts = hosts;
+            let (tx, _) = broadcast::channel(10);
+            Binance::new(
+                exchange_account_id,
+                settings,
+                tx,
+                AppLifetimeManager::new(CancellationToken::default()),
+                false,
+                false,
+            )
+        };
+
+        assert!(binance(false, None).is_api_restrictions_available());
+        assert!(binance(true, None).is_api_restrictions_available());
+
+        let spot_testnet_hosts = HostsSettings {
+            rest_host: "https://testnet.binance.vision".to_owned(),
+            web_socket_host: "wss://testnet.binance.vision".to_owned(),
+            web_socket2_host: None,
+        };
+        assert!(!binance(false, Some(spot_testnet_hosts)).is_api_restrictions_available());
+
+        let futures_testnet_hosts = HostsSettings {
+            rest_host: "https://testnet.binancefuture.com".to_owned(),
+            web_socket_host: "wss://stream.binancefuture.com".to_owned(),
+            web_socket2_host: None,
+        };
+        assert!(!binance(true, Some(futures_testnet_hosts)).is_api_restrictions_available());
+    }
+
+    #[test]
+    fn native_good_till_date_is_used_on_futures_only() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let binance = |is_margin_trading: bool| {
+            let settings = ExchangeSettings::new_short(
+                exchange_account_id,
+                "".into(),
+                "".into(),
+                is_margin_trading,
+            );
+            let (tx, _) = broadcast::channel(10);
+            Binance::new(
+                exchange_account_id,
+                settings,
+                tx,
+                AppLifetimeManager::new(CancellationToken::default()),
+                false,
+                false,
+            )
+        };
+        let header = |expire_time: DateTime| {
+            OrderHeader::new(
+                "test".into(),
+                chrono::Utc::now(),
+                exchange_account_id,
+                CurrencyPair::from_codes("btc".into(), "usdt".into()),
+                OrderType::Limit,
+                OrderSide::Buy,
+                dec!(1),
+                OrderExecutionType::None,
+                None,
+                None,
+                "test".to_owned(),
+            )
+            .with_expire_time(expire_time)
+        };
+
+        let expire_time = chrono::Utc::now() + chrono::Duration::hours(1);
+        assert_eq!(
+            binance(true).native_good_till_date(&header(expire_time)),
+            Some(expire_time.timestamp_millis() as u128)
+        );
+        assert_eq!(
+            binance(false).native_good_till_date(&header(expire_time)),
+            None
+        );
+
+        let soon_expire_time = chrono::Utc::now() + chrono::Duration::seconds(10);
+        assert_eq!(
+            binance(true).native_good_till_date(&header(soon_expire_time)),
+            None
+        );
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceMarginAccountInfo {
+    total_margin_balance: Decimal,
+    total_initial_margin: Decimal,
+    total_maint_margin: Decimal,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceDailyTicker {
+    symbol: String,
+    quote_volume: Decimal,
+}
+
+#[derive(Deserialize)]
+struct BinanceAggTrade {
+    #[serde(rename = "a")]
+    id: u64,
+    #[serde(rename = "p")]
+    price: Price,
+    #[serde(rename = "q")]
+    quantity: Amount,
+    #[serde(rename = "T")]
+    time: u64,
+    #[serde(rename = "m")]
+    is_buyer_maker: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinancePremiumIndex {
+    last_funding_rate: Decimal,
+    next_funding_time: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceApiRestrictions {
+    #[serde(default)]
+    enable_reading: bool,
+    #[serde(default)]
+    enable_spot_and_margin_trading: bool,
+    #[serde(default)]
+    enable_margin: bool,
+    #[serde(default)]
+    enable_futures: bool,
+    #[serde(default)]
+    enable_vanilla_options: bool,
+    #[serde(default)]
+    enable_withdrawals: bool,
+    /// Trading permission of keys without IP whitelist expires
+    trading_authority_expiration_time: Option<u64>,
 }
 
 #[derive(Deserialize)]
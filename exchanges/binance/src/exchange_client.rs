@@ -4,21 +4,27 @@ use anyhow::Result;
 use async_trait::async_trait;
 use function_name::named;
 use itertools::Itertools;
+use mmb_core::exchanges::api_key_permissions::ApiKeyPermissions;
 use mmb_core::exchanges::common::{
-    ActivePosition, ClosedPosition, CurrencyPair, ExchangeError, ExchangeErrorType, Price,
+    ActivePosition, Amount, ClosedPosition, CurrencyPair, ExchangeError, ExchangeErrorType, Price,
 };
-use mmb_core::exchanges::events::ExchangeBalancesAndPositions;
+use mmb_core::exchanges::events::{ExchangeBalancesAndPositions, Trade};
 use mmb_core::exchanges::general::exchange::RequestResult;
 use mmb_core::exchanges::general::order::cancel::CancelOrderResult;
 use mmb_core::exchanges::general::order::create::CreateOrderResult;
 use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
 use mmb_core::exchanges::general::symbol::Symbol;
+use mmb_core::exchanges::margin::MarginInfo;
 use mmb_core::exchanges::rest_client;
+use mmb_core::exchanges::rest_pagination::Page;
 use mmb_core::exchanges::traits::{ExchangeClient, Support};
+use mmb_core::order_book::order_book_data::OrderBookData;
 use mmb_core::orders::fill::EventSourceType;
 use mmb_core::orders::order::*;
 use mmb_core::orders::pool::OrderRef;
+use mmb_core::services::funding_rates::FundingRate;
 use mmb_utils::DateTime;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 #[async_trait]
@@ -50,7 +56,7 @@ impl ExchangeClient for Binance {
     async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
         let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
 
-        let host = &self.hosts.rest_host;
+        let host = &self.rest_host();
         let path_to_delete = "/api/v3/openOrders";
 
         let mut http_params = vec![(
@@ -142,18 +148,21 @@ impl ExchangeClient for Binance {
         last_date_time: Option<DateTime>,
     ) -> Result<RequestResult<Vec<OrderTrade>>> {
         // TODO Add metric UseTimeMetric(RequestType::GetMyTrades)
-        match self.request_my_trades(symbol, last_date_time).await {
-            Ok(response) => match self.parse_get_my_trades(&response, last_date_time) {
-                Ok(data) => Ok(RequestResult::Success(data)),
-                Err(_) => Ok(RequestResult::Error(ExchangeError::unknown(
-                    &response.content,
-                ))),
-            },
-            Err(error) => Ok(RequestResult::Error(ExchangeError::new(
-                ExchangeErrorType::ParsingError,
-                error.to_string(),
-                None,
-            ))),
+        let trades = Binance::history_paginator()
+            .fetch_all(last_date_time, |page_request| async move {
+                let response = self.request_my_trades(symbol, &page_request).await?;
+                let trades = self
+                    .parse_get_my_trades(&response, last_date_time)
+                    .map_err(|error| {
+                        ExchangeError::new(ExchangeErrorType::ParsingError, error.to_string(), None)
+                    })?;
+                Ok(Page::new(trades))
+            })
+            .await;
+
+        match trades {
+            Ok(trades) => Ok(RequestResult::Success(trades)),
+            Err(error) => Ok(RequestResult::Error(error)),
         }
     }
 
@@ -162,4 +171,99 @@ impl ExchangeClient for Binance {
 
         self.parse_all_symbols(response)
     }
+
+    async fn get_api_key_permissions(&self) -> Result<Option<ApiKeyPermissions>> {
+        // testnet keys have no restrictions endpoint, so they can't be checked
+        if !self.is_api_restrictions_available() {
+            return Ok(None);
+        }
+
+        let response = self.request_api_restrictions().await?;
+
+        Ok(Some(Binance::parse_api_key_permissions(&response)?))
+    }
+
+    async fn get_margin_info(&self) -> Result<Option<MarginInfo>> {
+        if !self.settings.is_margin_trading {
+            return Ok(None);
+        }
+
+        let response = self.request_get_balance().await?;
+
+        Ok(Some(Binance::parse_margin_info(&response)?))
+    }
+
+    async fn get_daily_volumes(&self) -> Result<Option<HashMap<CurrencyPair, Amount>>> {
+        let response = self.request_daily_tickers().await?;
+
+        Ok(Some(self.parse_daily_volumes(&response)?))
+    }
+
+    async fn get_order_history(
+        &self,
+        currency_pair: CurrencyPair,
+        from: DateTime,
+        to: DateTime,
+    ) -> Result<Option<Vec<OrderInfo>>> {
+        let orders = Binance::history_paginator()
+            .fetch_all(Some(from), |page_request| async move {
+                let response = self
+                    .request_all_orders(currency_pair, &page_request, to)
+                    .await?;
+                let orders = self.parse_all_orders(&response).map_err(|error| {
+                    ExchangeError::new(ExchangeErrorType::ParsingError, error.to_string(), None)
+                })?;
+                Ok(Page::new(orders))
+            })
+            .await?;
+
+        Ok(Some(orders))
+    }
+
+    async fn get_trades_after(
+        &self,
+        currency_pair: CurrencyPair,
+        last_trade_id: u64,
+    ) -> Result<Option<Vec<Trade>>> {
+        let response = self
+            .request_agg_trades(currency_pair, last_trade_id)
+            .await?;
+
+        Ok(Some(Binance::parse_agg_trades(&response)?))
+    }
+
+    async fn get_funding_rate(&self, currency_pair: CurrencyPair) -> Result<Option<FundingRate>> {
+        if !self.settings.is_margin_trading {
+            return Ok(None);
+        }
+
+        let response = self.request_funding_rate(currency_pair).await?;
+
+        Ok(Some(Binance::parse_funding_rate(&response)?))
+    }
+
+    async fn reduce_order(
+        &self,
+        order: &OrderRef,
+        new_amount: Amount,
+    ) -> Result<(), ExchangeError> {
+        let _ = self.request_reduce_order(order, new_amount).await?;
+
+        Ok(())
+    }
+
+    async fn cancel_order_by_client_order_id(
+        &self,
+        header: &OrderHeader,
+    ) -> Result<(), ExchangeError> {
+        let _ = self.request_cancel_order_by_client_order_id(header).await?;
+
+        Ok(())
+    }
+
+    async fn get_order_book(&self, currency_pair: CurrencyPair) -> Result<Option<OrderBookData>> {
+        let response = self.request_order_book(currency_pair).await?;
+
+        Ok(Some(Binance::parse_order_book(&response)?))
+    }
 }
@@ -0,0 +1,221 @@
+//! Binance-specific features which aren't part of common `ExchangeClient` trait. Strategies get
+//! them by downcasting connector of exchange and executing requests through engine, so rate limits
+//! of exchange account are respected:
+//!
+//! ```ignore
+//! let quote = exchange
+//!     .execute_extension_request(
+//!         move |binance: &Binance| binance.get_convert_quote(from, to, amount),
+//!         cancellation_token,
+//!     )
+//!     .await?;
+//! ```
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use function_name::named;
+use mmb_core::exchanges::common::{Amount, CurrencyCode, ExchangeError, RestRequestOutcome};
+use mmb_core::exchanges::rest_client;
+use mmb_utils::time::u64_to_date_time;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::binance::Binance;
+
+/// Offer of Binance Convert to exchange `from_amount` of one currency to `to_amount` of another
+/// one. It can be accepted until `valid_until`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConvertQuote {
+    pub quote_id: String,
+    pub ratio: Decimal,
+    pub inverse_ratio: Decimal,
+    pub from_amount: Amount,
+    pub to_amount: Amount,
+    pub valid_until: DateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConvertOrder {
+    pub order_id: String,
+    /// Status of conversion as it's reported by exchange, e.g. PROCESS, SUCCESS, FAIL
+    pub status: String,
+    pub create_time: DateTime,
+}
+
+/// Conversion of currencies by quotes of Binance without trading on order books (spot account only)
+#[async_trait]
+pub trait BinanceConvert {
+    async fn get_convert_quote(
+        &self,
+        from: CurrencyCode,
+        to: CurrencyCode,
+        from_amount: Amount,
+    ) -> Result<ConvertQuote>;
+
+    async fn accept_convert_quote(&self, quote_id: String) -> Result<ConvertOrder>;
+}
+
+#[async_trait]
+impl BinanceConvert for Binance {
+    async fn get_convert_quote(
+        &self,
+        from: CurrencyCode,
+        to: CurrencyCode,
+        from_amount: Amount,
+    ) -> Result<ConvertQuote> {
+        let response = self.request_convert_quote(from, to, from_amount).await?;
+        Binance::parse_convert_quote(&response)
+    }
+
+    async fn accept_convert_quote(&self, quote_id: String) -> Result<ConvertOrder> {
+        let response = self.request_accept_convert_quote(&quote_id).await?;
+        Binance::parse_convert_order(&response)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceConvertQuote {
+    quote_id: String,
+    ratio: Decimal,
+    inverse_ratio: Decimal,
+    valid_timestamp: u64,
+    to_amount: Amount,
+    from_amount: Amount,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceConvertOrder {
+    order_id: String,
+    create_time: u64,
+    order_status: String,
+}
+
+impl Binance {
+    #[named]
+    async fn request_convert_quote(
+        &self,
+        from: CurrencyCode,
+        to: CurrencyCode,
+        from_amount: Amount,
+    ) -> Result<RestRequestOutcome, ExchangeError> {
+        let mut http_params = vec![
+            ("fromAsset".to_owned(), from.as_str().to_uppercase()),
+            ("toAsset".to_owned(), to.as_str().to_uppercase()),
+            ("fromAmount".to_owned(), from_amount.to_string()),
+        ];
+        self.add_authentification_headers(&mut http_params)?;
+        // convert is available on spot host only
+        let full_url = rest_client::build_uri(
+            &self.spot_rest_host()?,
+            "/sapi/v1/convert/getQuote",
+            &http_params,
+        );
+
+        let log_args = format!("Convert quote {from_amount} {from} to {to}");
+        self.rest_client
+            .post(
+                full_url,
+                &self.settings.api_key,
+                &http_params,
+                function_name!(),
+                log_args,
+            )
+            .await
+    }
+
+    #[named]
+    async fn request_accept_convert_quote(
+        &self,
+        quote_id: &str,
+    ) -> Result<RestRequestOutcome, ExchangeError> {
+        let mut http_params = vec![("quoteId".to_owned(), quote_id.to_owned())];
+        self.add_authentification_headers(&mut http_params)?;
+        let full_url = rest_client::build_uri(
+            &self.spot_rest_host()?,
+            "/sapi/v1/convert/acceptQuote",
+            &http_params,
+        );
+
+        let log_args = format!("Accept convert quote {quote_id}");
+        self.rest_client
+            .post(
+                full_url,
+                &self.settings.api_key,
+                &http_params,
+                function_name!(),
+                log_args,
+            )
+            .await
+    }
+
+    fn parse_convert_quote(response: &RestRequestOutcome) -> Result<ConvertQuote> {
+        let quote: BinanceConvertQuote = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for convert quote request")?;
+
+        Ok(ConvertQuote {
+            quote_id: quote.quote_id,
+            ratio: quote.ratio,
+            inverse_ratio: quote.inverse_ratio,
+            from_amount: quote.from_amount,
+            to_amount: quote.to_amount,
+            valid_until: u64_to_date_time(quote.valid_timestamp),
+        })
+    }
+
+    fn parse_convert_order(response: &RestRequestOutcome) -> Result<ConvertOrder> {
+        let order: BinanceConvertOrder = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for accept convert quote request")?;
+
+        Ok(ConvertOrder {
+            order_id: order.order_id,
+            status: order.order_status,
+            create_time: u64_to_date_time(order.create_time),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parse_convert_quote() {
+        let response = RestRequestOutcome::new(
+            r#"{"quoteId":"12415572564","ratio":"38163.7","inverseRatio":"0.0000262","validTimestamp":1623319461670,"toAmount":"3816.37","fromAmount":"0.1"}"#.to_owned(),
+            hyper::StatusCode::OK,
+        );
+
+        let quote = Binance::parse_convert_quote(&response).expect("in test");
+
+        assert_eq!(
+            quote,
+            ConvertQuote {
+                quote_id: "12415572564".to_owned(),
+                ratio: dec!(38163.7),
+                inverse_ratio: dec!(0.0000262),
+                from_amount: dec!(0.1),
+                to_amount: dec!(3816.37),
+                valid_until: u64_to_date_time(1623319461670),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_convert_order() {
+        let response = RestRequestOutcome::new(
+            r#"{"orderId":"933256278426274426","createTime":1623381330472,"orderStatus":"PROCESS"}"#
+                .to_owned(),
+            hyper::StatusCode::OK,
+        );
+
+        let order = Binance::parse_convert_order(&response).expect("in test");
+
+        assert_eq!(order.order_id, "933256278426274426");
+        assert_eq!(order.status, "PROCESS");
+        assert_eq!(order.create_time, u64_to_date_time(1623381330472));
+    }
+}
@@ -18,5 +18,6 @@
 
 pub mod binance;
 pub mod exchange_client;
+pub mod extensions;
 
 mod support;
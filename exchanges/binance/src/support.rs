@@ -10,6 +10,7 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::any::Any;
 use std::sync::Arc;
 use url::Url;
 
@@ -17,7 +18,10 @@ use super::binance::Binance;
 use mmb_core::connectivity::WebSocketRole;
 use mmb_core::exchanges::common::{send_event, ActivePosition, SortedOrderData};
 use mmb_core::exchanges::common::{Amount, CurrencyPair, Price, SpecificCurrencyPair};
+use mmb_core::exchanges::connection_pool::ConnectionPoolStats;
 use mmb_core::exchanges::events::{ExchangeEvent, TradeId};
+use mmb_core::exchanges::host_selection::HostStats;
+use mmb_core::exchanges::payload_anomalies::PayloadAnomaly;
 use mmb_core::exchanges::traits::{
     HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb,
 };
@@ -40,10 +44,98 @@ pub struct BinanceOrderInfo {
     pub orig_quantity: Amount,
     #[serde(rename = "executedQty")]
     pub executed_quantity: Amount,
-    pub status: String,
+    pub status: BinanceOrderStatus,
     pub side: String,
 }
 
+/// Order status of Binance. Statuses added by exchange later are tolerated as `Other`
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum BinanceOrderStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    PendingCancel,
+    Rejected,
+    Expired,
+    /// Order is expired by self-trade prevention
+    ExpiredInMatch,
+    Other(String),
+}
+
+impl From<&str> for BinanceOrderStatus {
+    fn from(status: &str) -> Self {
+        match status {
+            "NEW" => BinanceOrderStatus::New,
+            "PARTIALLY_FILLED" => BinanceOrderStatus::PartiallyFilled,
+            "FILLED" => BinanceOrderStatus::Filled,
+            "CANCELED" => BinanceOrderStatus::Canceled,
+            "PENDING_CANCEL" => BinanceOrderStatus::PendingCancel,
+            "REJECTED" => BinanceOrderStatus::Rejected,
+            "EXPIRED" => BinanceOrderStatus::Expired,
+            "EXPIRED_IN_MATCH" => BinanceOrderStatus::ExpiredInMatch,
+            other => BinanceOrderStatus::Other(other.to_owned()),
+        }
+    }
+}
+
+impl From<String> for BinanceOrderStatus {
+    fn from(status: String) -> Self {
+        status.as_str().into()
+    }
+}
+
+impl From<BinanceOrderStatus> for String {
+    fn from(status: BinanceOrderStatus) -> Self {
+        match status {
+            BinanceOrderStatus::New => "NEW",
+            BinanceOrderStatus::PartiallyFilled => "PARTIALLY_FILLED",
+            BinanceOrderStatus::Filled => "FILLED",
+            BinanceOrderStatus::Canceled => "CANCELED",
+            BinanceOrderStatus::PendingCancel => "PENDING_CANCEL",
+            BinanceOrderStatus::Rejected => "REJECTED",
+            BinanceOrderStatus::Expired => "EXPIRED",
+            BinanceOrderStatus::ExpiredInMatch => "EXPIRED_IN_MATCH",
+            BinanceOrderStatus::Other(other) => return other,
+        }
+        .to_owned()
+    }
+}
+
+/// Execution type of order update. Types added by exchange later are tolerated as `Other`
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) enum BinanceExecutionType {
+    New,
+    Canceled,
+    Replaced,
+    Amendment,
+    Rejected,
+    Trade,
+    Expired,
+    /// Liquidation of futures position
+    Calculated,
+    TradePrevention,
+    Other(String),
+}
+
+impl From<&str> for BinanceExecutionType {
+    fn from(execution_type: &str) -> Self {
+        match execution_type {
+            "NEW" => BinanceExecutionType::New,
+            "CANCELED" => BinanceExecutionType::Canceled,
+            "REPLACED" => BinanceExecutionType::Replaced,
+            "AMENDMENT" => BinanceExecutionType::Amendment,
+            "REJECTED" => BinanceExecutionType::Rejected,
+            "TRADE" => BinanceExecutionType::Trade,
+            "EXPIRED" => BinanceExecutionType::Expired,
+            "CALCULATED" => BinanceExecutionType::Calculated,
+            "TRADE_PREVENTION" => BinanceExecutionType::TradePrevention,
+            other => BinanceExecutionType::Other(other.to_owned()),
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BinanceAccountInfo {
     pub balances: Vec<BinanceBalances>,
@@ -84,8 +176,15 @@ impl Support for Binance {
                 let currency_pair = self.currency_pair_from_web_socket(&stream[..byte_index])?;
                 let data = &data["data"];
 
-                if stream.ends_with("@trade") {
-                    self.handle_trade(currency_pair, data)?;
+                // stream names are lowercased in websocket path
+                let channel = &stream[byte_index + 1..];
+                if channel == "trade" {
+                    self.handle_trade(currency_pair, data, "t", &self.last_trade_ids)?;
+                    return Ok(());
+                }
+
+                if channel.eq_ignore_ascii_case("aggTrade") {
+                    self.handle_trade(currency_pair, data, "a", &self.last_agg_trade_ids)?;
                     return Ok(());
                 }
 
@@ -123,6 +222,9 @@ impl Support for Binance {
                 let _ = self
                     .last_trade_ids
                     .insert(*currency_pair, TradeId::Number(0));
+                let _ = self
+                    .last_agg_trade_ids
+                    .insert(*currency_pair, TradeId::Number(0));
             });
 
         Ok(())
@@ -187,6 +289,17 @@ impl Support for Binance {
         message.contains("executionReport")
     }
 
+    /// Public streams of the same symbol have the same key, e.g. `btcusdt` of `btcusdt@depth20`
+    fn get_message_partition_key<'a>(&self, message: &'a str) -> Option<&'a str> {
+        const STREAM_PREFIX: &str = "\"stream\":\"";
+
+        let stream_start = message.find(STREAM_PREFIX)? + STREAM_PREFIX.len();
+        let stream = &message[stream_start..];
+        stream
+            .find(['@', '"'])
+            .map(|symbol_end| &stream[..symbol_end])
+    }
+
     fn log_unknown_message(
         &self,
         exchange_account_id: mmb_core::exchanges::common::ExchangeAccountId,
@@ -198,19 +311,45 @@ impl Support for Binance {
     fn get_settings(&self) -> &ExchangeSettings {
         &self.settings
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_host_stats(&self) -> Vec<HostStats> {
+        self.host_selector
+            .as_ref()
+            .map(|x| x.stats())
+            .unwrap_or_default()
+    }
+
+    fn get_connection_pool_stats(&self) -> Option<ConnectionPoolStats> {
+        Some(self.rest_client.connection_pool_stats())
+    }
+
+    fn get_payload_anomalies(&self) -> Vec<PayloadAnomaly> {
+        self.payload_anomalies.snapshot()
+    }
 }
 
 impl Binance {
-    pub(crate) fn handle_trade(&self, currency_pair: CurrencyPair, data: &Value) -> Result<()> {
-        let trade_id = TradeId::from(data["t"].clone());
+    /// Trades of `trade` and `aggTrade` streams have the same format except field of trade id.
+    /// Their ids are tracked separately because ids of aggregated trades are different
+    pub(crate) fn handle_trade(
+        &self,
+        currency_pair: CurrencyPair,
+        data: &Value,
+        trade_id_field: &str,
+        last_trade_ids: &DashMap<CurrencyPair, TradeId>,
+    ) -> Result<()> {
+        let trade_id = TradeId::from(data[trade_id_field].clone());
 
-        let mut trade_id_from_lasts =
-            self.last_trade_ids.get_mut(&currency_pair).with_expect(|| {
-                format!(
-                    "There are no last_trade_id for given currency_pair {}",
-                    currency_pair
-                )
-            });
+        let mut trade_id_from_lasts = last_trade_ids.get_mut(&currency_pair).with_expect(|| {
+            format!(
+                "There are no last_trade_id for given currency_pair {}",
+                currency_pair
+            )
+        });
 
         if self.is_reducing_market_data && trade_id_from_lasts.get_number() >= trade_id.get_number()
         {
@@ -380,7 +519,7 @@ impl Binance {
     }
 }
 
-fn get_order_book_side(levels: &[Value]) -> Result<SortedOrderData> {
+pub(crate) fn get_order_book_side(levels: &[Value]) -> Result<SortedOrderData> {
     levels
         .iter()
         .map(|x| {
@@ -171,7 +171,9 @@ impl BinanceBuilder {
         exchange.connect().await.with_expect(move || {
             "Failed to connect to websockets on exchange {exchange_account_id}"
         });
-        exchange.build_symbols(&settings.currency_pairs).await;
+        exchange
+            .build_symbols(&settings.currency_pairs, &settings.currency_pairs_filter)
+            .await;
 
         let currency_pair_to_symbol_converter = CurrencyPairToSymbolConverter::new(
             hashmap![ exchange_account_id => exchange.clone()  ],
@@ -0,0 +1,31 @@
+use core_tests::conformance::ConformanceSuite;
+use mmb_utils::logger::init_logger_file_named;
+
+use crate::binance::binance_builder::BinanceBuilder;
+use crate::binance::common::get_binance_credentials;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn conformance() {
+    init_logger_file_named("log.txt");
+
+    // suite can't run without credentials, but any other failure of building exchange fails it
+    if let Err(error) = get_binance_credentials() {
+        println!("Conformance suite is skipped: {error}");
+        return;
+    }
+    let binance_builder = BinanceBuilder::build_account_0()
+        .await
+        .expect("Failed to build Binance exchange for conformance suite");
+
+    let report = ConformanceSuite::new(
+        binance_builder.exchange.clone(),
+        binance_builder.rx,
+        binance_builder.default_price,
+        binance_builder.min_amount,
+    )
+    .run()
+    .await;
+
+    println!("{report}");
+    assert!(report.is_compliant(), "{report}");
+}
@@ -1,4 +1,5 @@
 use crate::serum::{downcast_mut_to_serum_extension_data, Serum};
+use std::any::Any;
 use std::collections::HashSet;
 use std::sync::Arc;
 
@@ -113,6 +114,10 @@ impl Support for Serum {
     fn get_settings(&self) -> &ExchangeSettings {
         &self.settings
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
 
 impl Serum {
@@ -131,7 +131,9 @@ impl SerumBuilder {
             commission,
         );
         exchange.connect().await?;
-        exchange.build_symbols(&settings.currency_pairs).await;
+        exchange
+            .build_symbols(&settings.currency_pairs, &settings.currency_pairs_filter)
+            .await;
 
         Ok(Self {
             exchange,
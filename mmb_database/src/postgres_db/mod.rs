@@ -1,5 +1,7 @@
 pub mod events;
+pub mod kv_store;
 pub mod migrator;
+pub mod retention;
 
 use anyhow::{Context, Result};
 use bb8_postgres::bb8::{Pool, PooledConnection};
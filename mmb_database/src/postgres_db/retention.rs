@@ -0,0 +1,93 @@
+use crate::postgres_db::events::DbEvent;
+use crate::postgres_db::PgPool;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+/// Id of the newest event which is out of retention limits: inserted before `inserted_before`
+/// or beyond `max_rows` newest events. Returns `None` if all events are within limits
+pub async fn get_retention_boundary(
+    pool: &PgPool,
+    table_name: &str,
+    inserted_before: Option<DateTime<Utc>>,
+    max_rows: Option<i64>,
+) -> Result<Option<i64>> {
+    let connection = pool
+        .0
+        .get()
+        .await
+        .context("getting db connection from pool")?;
+
+    let mut boundary = None;
+    if let Some(inserted_before) = inserted_before {
+        let row = connection
+            .query_one(
+                &format!("SELECT max(id) AS id FROM {table_name} WHERE insert_time < $1"),
+                &[&inserted_before],
+            )
+            .await
+            .context("from `get_retention_boundary` on query by age")?;
+        boundary = row.get::<_, Option<i64>>("id");
+    }
+
+    if let Some(max_rows) = max_rows {
+        let row = connection
+            .query_opt(
+                &format!("SELECT id FROM {table_name} ORDER BY id DESC OFFSET $1 LIMIT 1"),
+                &[&max_rows],
+            )
+            .await
+            .context("from `get_retention_boundary` on query by size")?;
+        let by_size = row.map(|x| x.get::<_, i64>("id"));
+        boundary = boundary.max(by_size);
+    }
+
+    Ok(boundary)
+}
+
+/// Events with id in range `(after_id, up_to_id]` ordered by id
+pub async fn get_events_range(
+    pool: &PgPool,
+    table_name: &str,
+    after_id: i64,
+    up_to_id: i64,
+    limit: i64,
+) -> Result<Vec<DbEvent>> {
+    let rows = pool
+        .0
+        .get()
+        .await
+        .context("getting db connection from pool")?
+        .query(
+            &format!(
+                "SELECT id, insert_time, version, json FROM {table_name} \
+                 WHERE id > $1 AND id <= $2 ORDER BY id LIMIT $3"
+            ),
+            &[&after_id, &up_to_id, &limit],
+        )
+        .await
+        .context("from `get_events_range` on query")?;
+
+    Ok(rows
+        .into_iter()
+        .map(|x| DbEvent {
+            id: x.get::<_, i64>("id") as u64,
+            insert_time: x.get("insert_time"),
+            version: x.get::<_, Option<i32>>("version").unwrap_or_default(),
+            json: x.get("json"),
+        })
+        .collect())
+}
+
+/// Returns count of deleted events
+pub async fn delete_events_up_to(pool: &PgPool, table_name: &str, up_to_id: i64) -> Result<u64> {
+    pool.0
+        .get()
+        .await
+        .context("getting db connection from pool")?
+        .execute(
+            &format!("DELETE FROM {table_name} WHERE id <= $1"),
+            &[&up_to_id],
+        )
+        .await
+        .context("from `delete_events_up_to` on delete")
+}
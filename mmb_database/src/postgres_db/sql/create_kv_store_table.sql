@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS strategy_kv_store (
+    namespace text NOT NULL,
+    key text NOT NULL,
+    value jsonb NOT NULL,
+    update_time timestamp WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (namespace, key)
+);
@@ -0,0 +1,131 @@
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+use strum::{EnumIter, IntoEnumIterator};
+
+/// Class of failure which downstream automation can react to
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    Connectivity,
+    Exchange,
+    Risk,
+    Config,
+    Internal,
+    /// Failures of control API requests
+    Api,
+}
+
+/// Stable numeric codes of errors surfaced in logs and control API. Thousands of code are
+/// class of error. Codes are never renumbered, new codes are appended to their class
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, EnumIter)]
+#[repr(u32)]
+pub enum ErrorCode {
+    ConnectivityNotReady = 1001,
+    WebSocketConnectionFailed = 1002,
+    NotConnected = 1003,
+
+    ExchangeUnknown = 2000,
+    ExchangeSendError = 2001,
+    ExchangeRateLimit = 2002,
+    OrderNotFound = 2003,
+    OrderCompleted = 2004,
+    InsufficientFunds = 2005,
+    InvalidOrder = 2006,
+    ExchangeAuthentication = 2007,
+    ExchangeParsingError = 2008,
+    ExchangePending = 2009,
+    ExchangeServiceUnavailable = 2010,
+    ExchangeUnsupported = 2011,
+
+    PriceProtection = 3001,
+    CurrencyRestriction = 3002,
+    ReduceOnly = 3003,
+    MarginRisk = 3004,
+    MarketRollout = 3005,
+    ValueAtRisk = 3006,
+
+    InvalidConfig = 4001,
+
+    Internal = 5000,
+
+    StopperIsNone = 6001,
+    UnableToSendSignal = 6002,
+    FailedToSaveNewConfig = 6003,
+    EngineIsNotReady = 6004,
+    WithdrawalRequestFailed = 6005,
+    MarketRequestFailed = 6006,
+    StateTransferFailed = 6007,
+    ExportFailed = 6008,
+    CompactionFailed = 6009,
+    RebalancingFailed = 6010,
+    RiskRequestFailed = 6011,
+    Unauthorized = 6012,
+}
+
+impl ErrorCode {
+    pub fn all() -> impl Iterator<Item = ErrorCode> {
+        ErrorCode::iter()
+    }
+
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    pub fn class(self) -> ErrorClass {
+        match self.code() / 1000 {
+            1 => ErrorClass::Connectivity,
+            2 => ErrorClass::Exchange,
+            3 => ErrorClass::Risk,
+            4 => ErrorClass::Config,
+            6 => ErrorClass::Api,
+            _ => ErrorClass::Internal,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ErrorCode::ConnectivityNotReady => "Connectivity manager isn't ready",
+            ErrorCode::WebSocketConnectionFailed => "Failed to connect websocket",
+            ErrorCode::NotConnected => "Websocket isn't connected",
+            ErrorCode::ExchangeUnknown => "Unknown exchange error",
+            ErrorCode::ExchangeSendError => "Failed to send request to exchange",
+            ErrorCode::ExchangeRateLimit => "Rate limit of exchange is exceeded",
+            ErrorCode::OrderNotFound => "Order isn't found on exchange",
+            ErrorCode::OrderCompleted => "Order is already completed on exchange",
+            ErrorCode::InsufficientFunds => "Insufficient funds on exchange",
+            ErrorCode::InvalidOrder => "Order is rejected by exchange as invalid",
+            ErrorCode::ExchangeAuthentication => "Authentication on exchange failed",
+            ErrorCode::ExchangeParsingError => "Failed to parse response of exchange",
+            ErrorCode::ExchangePending => "Request is pending on exchange",
+            ErrorCode::ExchangeServiceUnavailable => "Exchange service is unavailable",
+            ErrorCode::ExchangeUnsupported => "Operation isn't supported by exchange",
+            ErrorCode::PriceProtection => "Order price is rejected by price protection",
+            ErrorCode::CurrencyRestriction => "Currency is restricted",
+            ErrorCode::ReduceOnly => "Order would increase position in reduce only mode",
+            ErrorCode::MarginRisk => "Margin risk limit is exceeded",
+            ErrorCode::MarketRollout => "Order is restricted by rollout mode of market",
+            ErrorCode::ValueAtRisk => "Value at risk limit is exceeded",
+            ErrorCode::InvalidConfig => "Invalid configuration",
+            ErrorCode::Internal => "Internal error",
+            ErrorCode::StopperIsNone => "Server stopper is none",
+            ErrorCode::UnableToSendSignal => "Unable to send signal",
+            ErrorCode::FailedToSaveNewConfig => "Failed to save new config",
+            ErrorCode::EngineIsNotReady => "Engine is not ready",
+            ErrorCode::WithdrawalRequestFailed => "Withdrawal request failed",
+            ErrorCode::MarketRequestFailed => "Market request failed",
+            ErrorCode::StateTransferFailed => "State transfer failed",
+            ErrorCode::ExportFailed => "Export failed",
+            ErrorCode::CompactionFailed => "Compaction failed",
+            ErrorCode::RebalancingFailed => "Rebalancing failed",
+            ErrorCode::RiskRequestFailed => "Risk request failed",
+            ErrorCode::Unauthorized => "Unauthorized",
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "E{}", self.code())
+    }
+}
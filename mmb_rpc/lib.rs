@@ -16,4 +16,5 @@
     clippy::unwrap_used
 )]
 
+pub mod error_codes;
 pub mod rest_api;
@@ -1,6 +1,8 @@
-use jsonrpc_core::{Error, Result};
+use jsonrpc_core::{BoxFuture, Error, Result, Value};
 use jsonrpc_derive::rpc;
 
+pub use crate::error_codes::ErrorCode;
+
 #[cfg(unix)]
 pub static IPC_ADDRESS: &str = "/tmp/mmb_core.ipc";
 #[cfg(windows)]
@@ -11,31 +13,263 @@ pub trait MmbRpc {
     #[rpc(name = "health")]
     fn health(&self) -> Result<String>;
 
+    /// All configured exchanges are connected, order books are received and balances are loaded
+    #[rpc(name = "ready")]
+    fn ready(&self) -> Result<String>;
+
     #[rpc(name = "stop")]
     fn stop(&self) -> Result<String>;
 
     #[rpc(name = "get_config")]
     fn get_config(&self) -> Result<String>;
 
+    /// Operator is resolved by `operator_token` of control API
     #[rpc(name = "set_config")]
-    fn set_config(&self, settings: String) -> Result<String>;
+    fn set_config(&self, settings: String, operator_token: String) -> Result<String>;
+
+    /// Settings engine is running with, including default values, with redacted credentials
+    #[rpc(name = "get_effective_config")]
+    fn get_effective_config(&self) -> Result<String>;
 
     #[rpc(name = "stats")]
     fn stats(&self) -> Result<String>;
-}
 
-pub enum ErrorCode {
-    StopperIsNone = 1,
-    UnableToSendSignal = 2,
-    FailedToSaveNewConfig = 3,
+    /// Withdrawals waiting for approval of second operator
+    #[rpc(name = "withdrawals")]
+    fn withdrawals(&self) -> Result<String>;
+
+    /// Operator is resolved by `operator_token` of control API
+    #[rpc(name = "approve_withdrawal")]
+    fn approve_withdrawal(&self, withdrawal_id: String, operator_token: String) -> Result<String>;
+
+    /// Operator is resolved by `operator_token` of control API
+    #[rpc(name = "reject_withdrawal")]
+    fn reject_withdrawal(&self, withdrawal_id: String, operator_token: String) -> Result<String>;
+
+    /// Active reasons of reduce-only mode in which only position reducing orders are allowed
+    #[rpc(name = "reduce_only")]
+    fn reduce_only(&self) -> Result<String>;
+
+    #[rpc(name = "set_reduce_only")]
+    fn set_reduce_only(&self, enabled: bool) -> Result<String>;
+
+    /// PnL of strategies per market decomposed into spread capture, inventory moves, fees and funding
+    #[rpc(name = "performance_attribution")]
+    fn performance_attribution(&self) -> Result<String>;
+
+    /// Reserved amounts and virtual balance diffs of strategies with sums by exchange accounts
+    #[rpc(name = "balance_trees")]
+    fn balance_trees(&self) -> Result<String>;
+
+    /// Stop quoting on market: resting orders are canceled and new ones aren't created until resume
+    #[rpc(name = "pause_market")]
+    fn pause_market(&self, exchange_account_id: String, currency_pair: String) -> Result<String>;
+
+    #[rpc(name = "resume_market")]
+    fn resume_market(&self, exchange_account_id: String, currency_pair: String) -> Result<String>;
+
+    /// Desired amounts of markets set at runtime which override max amounts from strategy settings
+    #[rpc(name = "desired_amounts")]
+    fn desired_amounts(&self) -> Result<String>;
+
+    #[rpc(name = "set_desired_amount")]
+    fn set_desired_amount(
+        &self,
+        exchange_account_id: String,
+        currency_pair: String,
+        amount: String,
+    ) -> Result<String>;
+
+    /// Rollout modes of markets with metrics observed since the last mode change
+    #[rpc(name = "market_modes")]
+    fn market_modes(&self) -> Result<String>;
+
+    /// Mode is one of `shadow`, `reduced` or `full`. Empty reduced size keeps the current one
+    #[rpc(name = "set_market_mode")]
+    fn set_market_mode(
+        &self,
+        exchange_account_id: String,
+        currency_pair: String,
+        mode: String,
+        reduced_size: String,
+    ) -> Result<String>;
+
+    /// Orders, balances, reservations and strategies persistent data in portable format
+    /// for moving engine to another host
+    #[rpc(name = "export_state")]
+    fn export_state(&self) -> BoxFuture<Result<String>>;
+
+    #[rpc(name = "import_state")]
+    fn import_state(&self, state: String) -> BoxFuture<Result<String>>;
+
+    /// Latency probes and error counts of REST hosts selected by exchange accounts
+    #[rpc(name = "host_stats")]
+    fn host_stats(&self) -> Result<String>;
+
+    /// Requests and opened keep-alive connections of REST clients of exchange accounts
+    #[rpc(name = "connection_pool_stats")]
+    fn connection_pool_stats(&self) -> Result<String>;
+
+    /// Queue and parsing times of websocket messages of exchange accounts
+    #[rpc(name = "parsing_stats")]
+    fn parsing_stats(&self) -> Result<String>;
+
+    /// Waiting times of order requests queued by rate limits of exchange accounts by priority
+    #[rpc(name = "request_queue_stats")]
+    fn request_queue_stats(&self) -> Result<String>;
+
+    /// Unknown enum values and missing fields in exchange payloads tolerated by connectors
+    #[rpc(name = "payload_anomalies")]
+    fn payload_anomalies(&self) -> Result<String>;
+
+    /// Catalog of stable error codes with their classes and descriptions
+    #[rpc(name = "error_codes")]
+    fn error_codes(&self) -> Result<String>;
+
+    /// Fills of orders known to engine in CSV format of tax tool: `koinly`, `cointracking`
+    /// or `lots` with FIFO acquisitions and disposals
+    #[rpc(name = "tax_export")]
+    fn tax_export(&self, format: String) -> Result<String>;
+
+    /// Volume of the last 30 days, maker rebates and distance to the next fee tier
+    /// of exchange accounts
+    #[rpc(name = "fee_tiers")]
+    fn fee_tiers(&self) -> Result<String>;
+
+    /// Starts compaction of event tables by retention policies in background
+    #[rpc(name = "compact_events")]
+    fn compact_events(&self) -> Result<String>;
+
+    /// State and results of the last compaction of event tables
+    #[rpc(name = "event_compaction")]
+    fn event_compaction(&self) -> Result<String>;
+
+    /// Plans of transfers between exchange accounts to reach target distribution of balances
+    #[rpc(name = "rebalancing_plans")]
+    fn rebalancing_plans(&self) -> Result<String>;
+
+    /// Plans transfers by current balances. Plan is executed only after approval of operator
+    #[rpc(name = "create_rebalancing_plan")]
+    fn create_rebalancing_plan(&self) -> Result<String>;
+
+    /// Transfers of approved plan are requested as withdrawals. Operator is resolved by
+    /// `operator_token` of control API
+    #[rpc(name = "approve_rebalancing_plan")]
+    fn approve_rebalancing_plan(&self, plan_id: String, operator_token: String) -> Result<String>;
+
+    /// Operator is resolved by `operator_token` of control API
+    #[rpc(name = "reject_rebalancing_plan")]
+    fn reject_rebalancing_plan(&self, plan_id: String, operator_token: String) -> Result<String>;
+
+    /// Spot inventory, perpetual hedge positions and basis PnL of hedges
+    #[rpc(name = "hedging")]
+    fn hedging(&self) -> Result<String>;
+
+    /// Value at risk of current exposures, their volatilities and results of stress scenarios
+    #[rpc(name = "value_at_risk")]
+    fn value_at_risk(&self) -> Result<String>;
+
+    /// PnL of current exposures if price of currency changes by `change` percents
+    #[rpc(name = "stress_test")]
+    fn stress_test(&self, currency_code: String, change: String) -> Result<String>;
+
+    /// Assets restricted by news and compliance signals
+    #[rpc(name = "news_restrictions")]
+    fn news_restrictions(&self) -> Result<String>;
+
+    /// Applies external signal in JSON, e.g. headline of news system or restriction of assets
+    #[rpc(name = "news_signal")]
+    fn news_signal(&self, signal: String) -> Result<String>;
 }
 
 pub fn server_side_error(code: ErrorCode) -> Error {
-    let reason = match code {
-        ErrorCode::StopperIsNone => "Server stopper is none",
-        ErrorCode::UnableToSendSignal => "Unable to send signal",
-        ErrorCode::FailedToSaveNewConfig => "Failed to save new config",
-    };
+    log::error!("Rest API error: {}", code.description());
+    Error::new(jsonrpc_core::ErrorCode::ServerError(code.code() as i64))
+}
+
+/// Not ready state is expected during engine starting so it isn't logged as error
+pub fn engine_is_not_ready_error(reason: String) -> Error {
+    Error {
+        code: jsonrpc_core::ErrorCode::ServerError(ErrorCode::EngineIsNotReady.code() as i64),
+        message: reason,
+        data: None,
+    }
+}
+
+pub fn withdrawal_request_error(reason: String) -> Error {
+    log::error!("Rest API error: {}", reason);
+    Error {
+        code: jsonrpc_core::ErrorCode::ServerError(ErrorCode::WithdrawalRequestFailed.code() as i64),
+        message: reason,
+        data: None,
+    }
+}
+
+pub fn market_request_error(reason: String) -> Error {
+    log::error!("Rest API error: {}", reason);
+    Error {
+        code: jsonrpc_core::ErrorCode::ServerError(ErrorCode::MarketRequestFailed.code() as i64),
+        message: reason,
+        data: None,
+    }
+}
+
+/// Attaches stable error code of engine error which caused request failure as error data
+pub fn with_error_code(mut error: Error, error_code: ErrorCode) -> Error {
+    error.data = Some(Value::from(error_code.code()));
+    error
+}
+
+pub fn state_transfer_error(reason: String) -> Error {
+    log::error!("Rest API error: {}", reason);
+    Error {
+        code: jsonrpc_core::ErrorCode::ServerError(ErrorCode::StateTransferFailed.code() as i64),
+        message: reason,
+        data: None,
+    }
+}
+
+pub fn export_error(reason: String) -> Error {
+    log::error!("Rest API error: {}", reason);
+    Error {
+        code: jsonrpc_core::ErrorCode::ServerError(ErrorCode::ExportFailed.code() as i64),
+        message: reason,
+        data: None,
+    }
+}
+
+pub fn compaction_error(reason: String) -> Error {
+    log::error!("Rest API error: {}", reason);
+    Error {
+        code: jsonrpc_core::ErrorCode::ServerError(ErrorCode::CompactionFailed.code() as i64),
+        message: reason,
+        data: None,
+    }
+}
+
+pub fn rebalancing_error(reason: String) -> Error {
+    log::error!("Rest API error: {}", reason);
+    Error {
+        code: jsonrpc_core::ErrorCode::ServerError(ErrorCode::RebalancingFailed.code() as i64),
+        message: reason,
+        data: None,
+    }
+}
+
+pub fn risk_request_error(reason: String) -> Error {
+    log::error!("Rest API error: {}", reason);
+    Error {
+        code: jsonrpc_core::ErrorCode::ServerError(ErrorCode::RiskRequestFailed.code() as i64),
+        message: reason,
+        data: None,
+    }
+}
+
+pub fn unauthorized_error(reason: String) -> Error {
     log::error!("Rest API error: {}", reason);
-    Error::new(jsonrpc_core::ErrorCode::ServerError(code as i64))
+    Error {
+        code: jsonrpc_core::ErrorCode::ServerError(ErrorCode::Unauthorized.code() as i64),
+        message: reason,
+        data: None,
+    }
 }
@@ -1,11 +1,12 @@
 /// This macro needs to generate an string ID for some structures like ClientOrder or ExchangeOrder.
 /// All IDs must be unique, here we use AtomicU64 static variable that initialize with current UNIX time(get_atomic_current_secs() function)
 /// Value cannot be "0" it means that the var isn't initialized.
+/// Ids can be made deterministic in tests by `mmb_utils::time::use_deterministic_ids`.
 /// # Example:
 /// ```
 /// use std::fmt;
 /// use std::fmt::{Display, Formatter};
-/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use std::sync::atomic::AtomicU64;
 ///
 /// use once_cell::sync::Lazy;
 /// use smallstr::SmallString;
@@ -32,7 +33,7 @@ macro_rules! impl_str_id {
 
         impl $type {
             pub fn unique_id() -> Self {
-                let new_id = paste::paste! { [<$type:snake:upper _ID>] }.fetch_add(1, Ordering::AcqRel);
+                let new_id = $crate::time::next_id(&paste::paste! { [<$type:snake:upper _ID>] });
                 $type(new_id.to_string().into())
             }
 
@@ -72,11 +73,12 @@ macro_rules! impl_str_id {
 /// This macro needs to generate an u64 ID for some structures like ProfitLossBalanceChange or Reservation.
 /// All IDs must be unique, here we use AtomicU64 static variable that initialize with current UNIX time(get_atomic_current_secs() function)
 /// Value cannot be "0" it means that the var isn't initialized.
+/// Ids can be made deterministic in tests by `mmb_utils::time::use_deterministic_ids`.
 /// # Example:
 /// ```
 /// use std::fmt;
 /// use std::fmt::{Display, Formatter};
-/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use std::sync::atomic::AtomicU64;
 ///
 /// use once_cell::sync::Lazy;
 /// use serde::{Deserialize, Serialize};
@@ -103,9 +105,24 @@ macro_rules! impl_u64_id {
         impl $type {
             /// Generate unique ID
             pub fn generate() -> Self {
-                let new_id = paste::paste! { [<$type:snake:upper _ID>] }.fetch_add(1, Ordering::AcqRel);
+                let new_id = $crate::time::next_id(&paste::paste! { [<$type:snake:upper _ID>] });
                 $type(new_id)
             }
+
+            /// Id with known value, e.g. received from another process
+            pub fn from_u64(value: u64) -> Self {
+                $type(value)
+            }
+
+            pub fn as_u64(&self) -> u64 {
+                self.0
+            }
+
+            /// Ids generated after call are greater than `id`, e.g. after ids are imported from another process
+            pub fn advance_past(id: Self) {
+                let _ = paste::paste! { [<$type:snake:upper _ID>] }
+                    .fetch_max(id.0 + 1, std::sync::atomic::Ordering::AcqRel);
+            }
         }
 
         impl Display for $type {
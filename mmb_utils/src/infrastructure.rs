@@ -2,6 +2,7 @@ use anyhow::{bail, Result};
 use bitflags::bitflags;
 use futures::Future;
 use futures::FutureExt;
+use once_cell::sync::OnceCell;
 use std::fmt::Arguments;
 use std::fmt::{Debug, Display};
 use std::panic;
@@ -17,6 +18,13 @@ use crate::panic::handle_future_panic;
 use crate::panic::set_panic_hook;
 use crate::OPERATION_CANCELED_MSG;
 
+static ERROR_CODE_FORMATTER: OnceCell<fn(&anyhow::Error) -> String> = OnceCell::new();
+
+/// Sets function which formats code of errors returned by spawned futures for logs
+pub fn set_error_code_formatter(formatter: fn(&anyhow::Error) -> String) {
+    let _ = ERROR_CODE_FORMATTER.set(formatter);
+}
+
 bitflags! {
     pub struct SpawnFutureFlags: u32 {
         /// Run graceful shutdown on cancel for this future, assuming some logical error (deny
@@ -167,7 +175,15 @@ async fn handle_action_outcome(
                     return FutureOutcome::new(action_name, future_id, CompletionReason::Canceled);
                 }
 
-                log::error!("{} returned error: {:?}", log_template, error);
+                match ERROR_CODE_FORMATTER.get() {
+                    Some(format_code) => log::error!(
+                        "{} returned error [{}]: {:?}",
+                        log_template,
+                        format_code(&error),
+                        error
+                    ),
+                    None => log::error!("{} returned error: {:?}", log_template, error),
+                }
                 FutureOutcome::new(action_name, future_id, CompletionReason::Error)
             }
         },
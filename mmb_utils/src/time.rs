@@ -1,8 +1,77 @@
-use std::sync::atomic::AtomicU64;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use chrono::Utc;
+
 use crate::DateTime;
 
+thread_local! {
+    static MOCK_NOW: Cell<Option<DateTime>> = const { Cell::new(None) };
+    static NEXT_MOCK_ID: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Current UTC time. It can be overridden for current thread by `set_mock_now` in tests
+pub fn now() -> DateTime {
+    MOCK_NOW.with(|x| x.get()).unwrap_or_else(Utc::now)
+}
+
+/// Guard of time override. Previous value is restored on drop
+#[must_use]
+pub struct MockNowGuard {
+    previous: Option<DateTime>,
+}
+
+impl Drop for MockNowGuard {
+    fn drop(&mut self) {
+        MOCK_NOW.with(|x| x.set(self.previous));
+    }
+}
+
+/// Override time returned by `now()` for current thread only, so tests on multi-thread runtime
+/// should set it inside every spawned task or use current thread runtime
+pub fn set_mock_now(time: DateTime) -> MockNowGuard {
+    MockNowGuard {
+        previous: MOCK_NOW.with(|x| x.replace(Some(time))),
+    }
+}
+
+/// Shift overridden time. Does nothing if time isn't overridden
+pub fn advance_mock_now(duration: chrono::Duration) {
+    MOCK_NOW.with(|x| x.set(x.get().map(|time| time + duration)));
+}
+
+/// Guard of deterministic ids generation. Previous state is restored on drop
+#[must_use]
+pub struct DeterministicIdsGuard {
+    previous: Option<u64>,
+}
+
+impl Drop for DeterministicIdsGuard {
+    fn drop(&mut self) {
+        NEXT_MOCK_ID.with(|x| x.set(self.previous));
+    }
+}
+
+/// Generate ids of all types by `impl_str_id!` and `impl_u64_id!` sequentially from `first_id`
+/// for current thread, so tests can assert exact ids
+pub fn use_deterministic_ids(first_id: u64) -> DeterministicIdsGuard {
+    DeterministicIdsGuard {
+        previous: NEXT_MOCK_ID.with(|x| x.replace(Some(first_id))),
+    }
+}
+
+/// Take next id from `counter` or from deterministic sequence if it's enabled for current thread
+pub fn next_id(counter: &AtomicU64) -> u64 {
+    NEXT_MOCK_ID.with(|x| match x.get() {
+        Some(id) => {
+            x.set(Some(id + 1));
+            id
+        }
+        None => counter.fetch_add(1, Ordering::AcqRel),
+    })
+}
+
 pub fn u64_to_date_time(src: u64) -> DateTime {
     (UNIX_EPOCH + Duration::from_millis(src)).into()
 }
@@ -33,3 +102,35 @@ pub fn get_atomic_current_secs() -> AtomicU64 {
             .as_secs(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn mock_now() {
+        let time = Utc.ymd(2021, 9, 20).and_hms(0, 0, 0);
+        {
+            let _guard = set_mock_now(time);
+            assert_eq!(now(), time);
+
+            advance_mock_now(chrono::Duration::seconds(5));
+            assert_eq!(now(), Utc.ymd(2021, 9, 20).and_hms(0, 0, 5));
+        }
+
+        assert_ne!(now(), Utc.ymd(2021, 9, 20).and_hms(0, 0, 5));
+    }
+
+    #[test]
+    fn deterministic_ids() {
+        let counter = get_atomic_current_secs();
+        {
+            let _guard = use_deterministic_ids(1);
+            assert_eq!(next_id(&counter), 1);
+            assert_eq!(next_id(&counter), 2);
+        }
+
+        assert!(next_id(&counter) > 2);
+    }
+}
@@ -6,9 +6,28 @@ pub struct AppConfig {
     pub address: String,
     pub database_url: String,
     pub refresh_data_interval_ms: u64,
+    /// Interval of saving indicators of all markets for history charts
+    pub indicators_record_interval_ms: u64,
+    /// Deadline of draining server on shutdown signal
+    pub shutdown_timeout_secs: u64,
+    pub ws_limits: WsLimits,
     pub markets: Vec<Market>,
 }
 
+/// Limits of websocket client session which protect server from misbehaving clients
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct WsLimits {
+    /// Maximum count of messages from client during `rate_interval_ms`, session is closed when it's exceeded
+    pub max_messages_per_interval: u32,
+    pub rate_interval_ms: u64,
+    /// Maximum count of active subscriptions of session
+    pub max_subscriptions: usize,
+    /// Session is closed if client doesn't send anything including pongs during this time
+    pub idle_timeout_ms: u64,
+    /// Interval of pings to client and checks of idle timeout
+    pub heartbeat_interval_ms: u64,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Market {
     pub exchange_id: String,
@@ -0,0 +1,32 @@
+use crate::services::history::HistoryService;
+use actix_web::web::{Data, Query};
+use actix_web::{get, Error, HttpResponse};
+use serde::Deserialize;
+use std::sync::Arc;
+
+const DEFAULT_SESSIONS_LIMIT: i32 = 20;
+
+#[derive(Deserialize)]
+pub struct SessionsQuery {
+    limit: Option<i32>,
+}
+
+#[get("/sessions")]
+pub async fn sessions(
+    query: Query<SessionsQuery>,
+    history_service: Data<Arc<HistoryService>>,
+) -> Result<HttpResponse, Error> {
+    let limit = query.limit.unwrap_or(DEFAULT_SESSIONS_LIMIT);
+    match history_service.get_session_summaries(limit).await {
+        Ok(records) => Ok(HttpResponse::Ok().json(
+            records
+                .into_iter()
+                .map(|record| record.json)
+                .collect::<Vec<_>>(),
+        )),
+        Err(e) => {
+            log::error!("Get session summaries error: {:?}", e);
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
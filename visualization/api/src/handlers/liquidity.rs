@@ -1,11 +1,18 @@
+use crate::services::indicators::IndicatorsService;
+use crate::services::liquidity::Amount;
 use crate::services::market_settings::MarketSettingsService;
-use actix_web::get;
 use actix_web::http::Error;
-use actix_web::web::Data;
+use actix_web::web::{Data, Query};
 use actix_web::HttpResponse;
+use actix_web::{get, put, web};
+use chrono::{Duration, TimeZone, Utc};
+use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
 
+const DEFAULT_INDICATORS_BUCKET_SECS: i64 = 60;
+const DEFAULT_INDICATORS_PERIOD_HOURS: i64 = 24;
+
 #[get("/supported-exchanges")]
 pub async fn supported_exchanges(
     market_settings_service: Data<Arc<MarketSettingsService>>,
@@ -13,3 +20,95 @@ pub async fn supported_exchanges(
     Ok(HttpResponse::Ok()
         .json(json!({ "supportedExchanges": &market_settings_service.supported_exchanges })))
 }
+
+#[get("/desired-amounts")]
+pub async fn desired_amounts(
+    market_settings_service: Data<Arc<MarketSettingsService>>,
+) -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok()
+        .json(json!({ "desiredAmounts": market_settings_service.get_desired_amounts() })))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DesiredAmountPayload {
+    exchange_id: String,
+    currency_pair: String,
+    desired_amount: Amount,
+}
+
+#[put("/desired-amounts")]
+pub async fn set_desired_amount(
+    payload: web::Json<DesiredAmountPayload>,
+    market_settings_service: Data<Arc<MarketSettingsService>>,
+) -> Result<HttpResponse, Error> {
+    if payload.desired_amount.is_sign_negative() {
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    match market_settings_service.set_desired_amount(
+        &payload.exchange_id,
+        &payload.currency_pair,
+        payload.desired_amount,
+    ) {
+        true => {
+            log::info!(
+                "Desired amount of {} {} is set to {}",
+                payload.exchange_id,
+                payload.currency_pair,
+                payload.desired_amount
+            );
+            Ok(HttpResponse::Ok().finish())
+        }
+        false => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndicatorsQuery {
+    exchange_id: String,
+    currency_pair: String,
+    /// Unix timestamp in seconds, the last day by default
+    from: Option<i64>,
+    /// Unix timestamp in seconds, now by default
+    to: Option<i64>,
+    bucket_secs: Option<i64>,
+}
+
+/// History of market indicators downsampled to buckets for charting
+#[get("/indicators")]
+pub async fn indicators(
+    query: Query<IndicatorsQuery>,
+    indicators_service: Data<Arc<IndicatorsService>>,
+) -> Result<HttpResponse, Error> {
+    let bucket_secs = query.bucket_secs.unwrap_or(DEFAULT_INDICATORS_BUCKET_SECS);
+    if bucket_secs <= 0 {
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+    let to = query
+        .to
+        .map(|x| Utc.timestamp(x, 0))
+        .unwrap_or_else(Utc::now);
+    let from = query
+        .from
+        .map(|x| Utc.timestamp(x, 0))
+        .unwrap_or_else(|| to - Duration::hours(DEFAULT_INDICATORS_PERIOD_HOURS));
+
+    match indicators_service
+        .get_history(
+            &query.exchange_id,
+            &query.currency_pair,
+            from,
+            to,
+            bucket_secs,
+        )
+        .await
+    {
+        Ok(points) => Ok(HttpResponse::Ok().json(json!({ "indicators": points }))),
+        Err(e) => {
+            log::error!("Get indicators history error: {:?}", e);
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod account;
 pub mod configuration;
+pub mod history;
 pub mod liquidity;
 pub mod ws;
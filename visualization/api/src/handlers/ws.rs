@@ -1,12 +1,34 @@
+use crate::config::WsLimits;
 use crate::services::token::TokenService;
+use crate::ws::actors::subscription_manager::SubscriptionManager;
 use crate::ws::actors::ws_client_session::WsClientSession;
-use actix_web::{web, Error, HttpRequest, Responder};
+use crate::ws::broker_messages::GetSubscriptionsMetrics;
+use actix::Addr;
+use actix_web::{get, web, Error, HttpRequest, HttpResponse, Responder};
 use actix_web_actors::ws::start;
 
 pub async fn ws_client(
     req: HttpRequest,
     stream: web::Payload,
     token_service: web::Data<TokenService>,
+    ws_limits: web::Data<WsLimits>,
 ) -> Result<impl Responder, Error> {
-    start(WsClientSession::new(token_service), &req, stream)
+    start(
+        WsClientSession::new(token_service, **ws_limits),
+        &req,
+        stream,
+    )
+}
+
+#[get("/subscriptions")]
+pub async fn subscriptions_metrics(
+    subscription_manager: web::Data<Addr<SubscriptionManager>>,
+) -> Result<HttpResponse, Error> {
+    match subscription_manager.send(GetSubscriptionsMetrics).await {
+        Ok(metrics) => Ok(HttpResponse::Ok().json(metrics)),
+        Err(e) => {
+            log::error!("Failure to get subscriptions metrics. {e:?}");
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
 }
@@ -50,6 +50,9 @@ async fn main() -> std::io::Result<()> {
         enforcer,
         config.markets,
         config.refresh_data_interval_ms,
+        config.indicators_record_interval_ms,
+        config.shutdown_timeout_secs,
+        config.ws_limits,
     )
     .await
 }
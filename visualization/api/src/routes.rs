@@ -1,6 +1,10 @@
 use crate::handlers::account::{client_domain, client_type, login, refresh_token};
 use crate::handlers::configuration::{get, save, validate};
-use crate::handlers::liquidity::supported_exchanges;
+use crate::handlers::history::sessions;
+use crate::handlers::liquidity::{
+    desired_amounts, indicators, set_desired_amount, supported_exchanges,
+};
+use crate::handlers::ws::subscriptions_metrics;
 use crate::ws_client;
 use actix_web::web;
 use actix_web::web::ServiceConfig;
@@ -19,7 +23,15 @@ pub(crate) fn routes(app: &mut ServiceConfig) {
                     .service(client_domain)
                     .service(refresh_token),
             )
-            .service(web::scope("/liquidity").service(supported_exchanges))
+            .service(
+                web::scope("/liquidity")
+                    .service(supported_exchanges)
+                    .service(desired_amounts)
+                    .service(set_desired_amount)
+                    .service(indicators),
+            )
+            .service(web::scope("/history").service(sessions))
+            .service(web::scope("/ws").service(subscriptions_metrics))
             .service(
                 web::scope("/configuration")
                     .service(get)
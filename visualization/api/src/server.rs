@@ -1,8 +1,10 @@
-use crate::config::Market;
+use crate::config::{Market, WsLimits};
 use crate::middleware::auth::TokenAuth;
 use crate::routes::routes;
 use crate::services::account::AccountService;
 use crate::services::auth::AuthService;
+use crate::services::history::HistoryService;
+use crate::services::indicators::{IndicatorsRecord, IndicatorsService};
 use crate::services::market_settings::MarketSettingsService;
 use crate::services::settings::SettingsService;
 use crate::services::token::TokenService;
@@ -10,13 +12,18 @@ use crate::ws::actors::error_listener::ErrorListener;
 use crate::ws::actors::new_data_listener::NewDataListener;
 use crate::ws::actors::subscription_manager::SubscriptionManager;
 use crate::ws::broker_messages::{
-    ClearSubscriptions, GatherSubscriptions, GetLiquiditySubscriptions, SubscriptionErrorMessage,
+    Flush, GetLiquiditySubscriptions, GetOrderBookDebugSubscriptions, NewOrderBookDebugDataMessage,
+    ServerShutdown, SubscriptionErrorMessage,
 };
+use crate::ws::commands::liquidity::get_indicators;
 use crate::ws::subscribes::liquidity::{LiquiditySubscription, Subscription};
 use crate::{LiquidityService, NewLiquidityDataMessage};
 use actix::{spawn, Actor, Addr};
+use actix_broker::{Broker, SystemBroker};
 use actix_cors::Cors;
+use actix_web::dev::ServerHandle;
 use actix_web::middleware::Logger;
+use actix_web::rt::task::JoinHandle;
 use actix_web::web::Data;
 use actix_web::{App, HttpServer};
 use casbin::Enforcer;
@@ -38,6 +45,9 @@ pub async fn start(
     enforcer: Enforcer,
     markets: Vec<Market>,
     refresh_data_interval_ms: u64,
+    indicators_record_interval_ms: u64,
+    shutdown_timeout_secs: u64,
+    ws_limits: WsLimits,
 ) -> std::io::Result<()> {
     log::info!("Starting server at {address}");
     let connection_pool = PgPoolOptions::new()
@@ -59,18 +69,27 @@ pub async fn start(
     let subscription_manager = SubscriptionManager::default().start();
     let auth_service = Arc::new(AuthService::new(enforcer));
     let market_settings_service = Arc::new(MarketSettingsService::from(markets));
+    let history_service = Arc::new(HistoryService::new(connection_pool.clone()));
+    let indicators_service = Arc::new(IndicatorsService::new(connection_pool.clone()));
     let settings_service = Arc::new(SettingsService::new(connection_pool));
 
-    spawn(data_provider(
-        subscription_manager,
+    spawn(indicators_recorder(
+        liquidity_service.clone(),
+        market_settings_service.clone(),
+        indicators_service.clone(),
+        indicators_record_interval_ms,
+    ));
+
+    let data_provider = spawn(data_provider(
+        subscription_manager.clone(),
         liquidity_service,
         market_settings_service.clone(),
-        new_data_listener,
-        error_listener,
+        new_data_listener.clone(),
+        error_listener.clone(),
         refresh_data_interval_ms,
     ));
 
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         let cors = Cors::permissive();
 
         App::new()
@@ -83,10 +102,85 @@ pub async fn start(
             .app_data(Data::new(token_service.clone()))
             .app_data(Data::new(market_settings_service.clone()))
             .app_data(Data::new(settings_service.clone()))
+            .app_data(Data::new(history_service.clone()))
+            .app_data(Data::new(indicators_service.clone()))
+            .app_data(Data::new(ws_limits))
+            .app_data(Data::new(subscription_manager.clone()))
     })
     .bind(address)?
-    .run()
-    .await
+    .disable_signals()
+    .shutdown_timeout(shutdown_timeout_secs)
+    .run();
+
+    spawn(drain_on_shutdown_signal(
+        server.handle(),
+        data_provider,
+        new_data_listener,
+        error_listener,
+        Duration::from_secs(shutdown_timeout_secs),
+    ));
+
+    server.await
+}
+
+/// Drains server on SIGTERM or Ctrl-C for zero-downtime deploys: stops accepting connections,
+/// delivers pending messages to websocket clients, closes their sessions and stops server
+/// within `shutdown_timeout`
+async fn drain_on_shutdown_signal(
+    server_handle: ServerHandle,
+    data_provider: JoinHandle<()>,
+    new_data_listener: Addr<NewDataListener>,
+    error_listener: Addr<ErrorListener>,
+    shutdown_timeout: Duration,
+) {
+    wait_shutdown_signal().await;
+    log::info!("Shutdown signal received, draining server");
+
+    let stopped = server_handle.stop(true);
+    data_provider.abort();
+
+    let drain = async {
+        // Mailboxes are handled in order, so all pending liquidity data and errors are issued
+        // to websocket sessions before they are closed
+        if let Err(e) = new_data_listener.send(Flush).await {
+            log::error!("Failure to flush new data listener. {e:?}");
+        }
+        if let Err(e) = error_listener.send(Flush).await {
+            log::error!("Failure to flush error listener. {e:?}");
+        }
+        Broker::<SystemBroker>::issue_async(ServerShutdown);
+        stopped.await;
+    };
+
+    if timeout(shutdown_timeout, drain).await.is_err() {
+        log::warn!("Server isn't drained in {shutdown_timeout:?}, stopping it forcibly");
+        server_handle.stop(false).await;
+    }
+    log::info!("Server stopped");
+}
+
+async fn wait_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use actix_web::rt::signal::unix::{signal, SignalKind};
+        use futures::future::select;
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                select(
+                    Box::pin(terminate.recv()),
+                    Box::pin(actix_web::rt::signal::ctrl_c()),
+                )
+                .await;
+                return;
+            }
+            Err(e) => log::error!("Failure to listen SIGTERM. {e:?}"),
+        }
+    }
+
+    if let Err(e) = actix_web::rt::signal::ctrl_c().await {
+        log::error!("Failure to listen Ctrl-C. {e:?}");
+    }
 }
 
 async fn data_provider(
@@ -100,28 +194,6 @@ async fn data_provider(
     let mut interval = time::interval(Duration::from_millis(refresh_data_interval_ms));
     loop {
         log::debug!("Data provider loop");
-        match subscription_manager.try_send(ClearSubscriptions) {
-            Ok(()) => {
-                log::debug!("ClearSubscriptions ok");
-            }
-            Err(e) => {
-                log::error!("Failure to ClearSubscriptions. {e:?}");
-                interval.tick().await;
-                continue;
-            }
-        }
-
-        match subscription_manager.try_send(GatherSubscriptions) {
-            Ok(()) => {
-                log::debug!("GatherSubscriptions ok");
-            }
-            Err(e) => {
-                log::error!("Failure GatherSubscriptions. {e:?}");
-                interval.tick().await;
-                continue;
-            }
-        }
-
         let subscriptions_request = subscription_manager.send(GetLiquiditySubscriptions);
 
         let response = timeout(Duration::from_millis(1000), subscriptions_request).await;
@@ -201,6 +273,104 @@ async fn data_provider(
                 }
             }
         }
+
+        provide_order_book_debug_data(
+            &subscription_manager,
+            &liquidity_service,
+            &new_data_listener,
+            &error_listener,
+        )
+        .await;
+        interval.tick().await;
+    }
+}
+
+/// Sends the latest comparison of order book claimed by exchange and local snapshot
+/// to debug subscribers
+async fn provide_order_book_debug_data(
+    subscription_manager: &Addr<SubscriptionManager>,
+    liquidity_service: &LiquidityService,
+    new_data_listener: &Addr<NewDataListener>,
+    error_listener: &Addr<ErrorListener>,
+) {
+    let subscriptions_request = subscription_manager.send(GetOrderBookDebugSubscriptions);
+    let subscriptions = match timeout(Duration::from_millis(1000), subscriptions_request).await {
+        Ok(Ok(subscriptions)) => subscriptions,
+        Ok(Err(e)) => {
+            log::error!("Failure GetOrderBookDebugSubscriptions. {e:?}");
+            return;
+        }
+        Err(_) => {
+            log::error!("GetOrderBookDebugSubscriptions timeout");
+            return;
+        }
+    };
+
+    for sub in subscriptions {
+        match liquidity_service
+            .get_order_book_comparison(&sub.exchange_id, &sub.currency_pair)
+            .await
+        {
+            Ok(comparison) => new_data_listener
+                .try_send(NewOrderBookDebugDataMessage {
+                    subscription: sub,
+                    data: comparison,
+                })
+                .unwrap_or_else(|e| log::error!("NewOrderBookDebugDataMessage failure {e:?}")),
+            Err(e) => {
+                log::error!("Failure to load order book comparison from database. Filters: {sub:?}. Error: {e:?}");
+                let message = SubscriptionErrorMessage {
+                    subscription: sub.get_hash(),
+                    message: "Internal server error".to_string(),
+                };
+                error_listener
+                    .try_send(message)
+                    .unwrap_or_else(|e| log::error!("Send error message failure {e:?}"));
+            }
+        }
+    }
+}
+
+/// Saves indicators of all configured markets regardless of subscriptions, so history charts
+/// have no gaps while nobody watches the dashboard
+async fn indicators_recorder(
+    liquidity_service: LiquidityService,
+    market_settings_service: Arc<MarketSettingsService>,
+    indicators_service: Arc<IndicatorsService>,
+    indicators_record_interval_ms: u64,
+) {
+    let mut interval = time::interval(Duration::from_millis(indicators_record_interval_ms));
+    loop {
         interval.tick().await;
+        for market in market_settings_service.get_desired_amounts() {
+            let mut liquidity_data = match liquidity_service
+                .get_liquidity_data(&market.exchange_id, &market.currency_pair, 0)
+                .await
+            {
+                Ok(liquidity_data) => liquidity_data,
+                Err(e) => {
+                    log::debug!(
+                        "Unable to load liquidity data of {} {} for indicators: {e:?}",
+                        market.exchange_id,
+                        market.currency_pair
+                    );
+                    continue;
+                }
+            };
+            liquidity_data.desired_amount = market.desired_amount;
+
+            let record = IndicatorsRecord {
+                indicators: get_indicators(&liquidity_data),
+                exchange_id: market.exchange_id,
+                currency_pair: market.currency_pair,
+            };
+            if let Err(e) = indicators_service.save(&record).await {
+                log::error!(
+                    "Failure to save indicators of {} {}: {e:?}",
+                    record.exchange_id,
+                    record.currency_pair
+                );
+            }
+        }
     }
 }
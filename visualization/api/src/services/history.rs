@@ -0,0 +1,22 @@
+use crate::services::liquidity::EventRecord;
+use sqlx::{Pool, Postgres};
+
+/// Data Provider for history of trading sessions
+#[derive(Clone)]
+pub struct HistoryService {
+    pool: Pool<Postgres>,
+}
+
+impl HistoryService {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Summaries of the last trading sessions, the newest first
+    pub async fn get_session_summaries(&self, limit: i32) -> Result<Vec<EventRecord>, sqlx::Error> {
+        sqlx::query_as::<Postgres, EventRecord>(include_str!("sql/get_session_summaries.sql"))
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+}
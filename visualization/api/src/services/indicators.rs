@@ -0,0 +1,186 @@
+use crate::services::liquidity::Amount;
+use crate::ws::commands::liquidity::Indicators;
+use chrono::{DateTime, TimeZone, Utc};
+use itertools::Itertools;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Pool, Postgres};
+
+/// Indicators of market saved on every record interval
+#[derive(Serialize, Deserialize)]
+pub struct IndicatorsRecord {
+    pub exchange_id: String,
+    pub currency_pair: String,
+    pub indicators: Indicators,
+}
+
+#[derive(sqlx::FromRow)]
+struct IndicatorsRow {
+    insert_time: DateTime<Utc>,
+    json: Value,
+}
+
+/// Average indicators of market in time bucket
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IndicatorsPoint {
+    /// Start of time bucket in RFC 3339
+    pub time: String,
+    #[serde(flatten)]
+    pub indicators: Indicators,
+}
+
+/// Storage of indicators history for charting
+#[derive(Clone)]
+pub struct IndicatorsService {
+    pool: Pool<Postgres>,
+}
+
+impl IndicatorsService {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn save(&self, record: &IndicatorsRecord) -> Result<(), sqlx::Error> {
+        let json = serde_json::to_value(record).expect("Failure to serialize indicators");
+        sqlx::query(include_str!("sql/insert_indicators.sql"))
+            .bind(json)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Indicators of market in interval `[from, to)` averaged by buckets of `bucket_secs` seconds
+    pub async fn get_history(
+        &self,
+        exchange_id: &str,
+        currency_pair: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        bucket_secs: i64,
+    ) -> Result<Vec<IndicatorsPoint>, sqlx::Error> {
+        let rows =
+            sqlx::query_as::<Postgres, IndicatorsRow>(include_str!("sql/get_indicators.sql"))
+                .bind(exchange_id)
+                .bind(currency_pair)
+                .bind(from)
+                .bind(to)
+                .fetch_all(&self.pool)
+                .await?;
+
+        let points = rows
+            .into_iter()
+            .filter_map(
+                |row| match serde_json::from_value::<IndicatorsRecord>(row.json) {
+                    Ok(record) => Some((row.insert_time, record.indicators)),
+                    Err(e) => {
+                        log::error!("Incorrect database indicators data: {e:?}");
+                        None
+                    }
+                },
+            )
+            .collect_vec();
+
+        Ok(downsample(points, bucket_secs))
+    }
+}
+
+/// Groups points ordered by time into buckets of `bucket_secs` seconds aligned to unix epoch.
+/// Optional indicators are averaged over points where they are known
+fn downsample(points: Vec<(DateTime<Utc>, Indicators)>, bucket_secs: i64) -> Vec<IndicatorsPoint> {
+    let bucket_secs = bucket_secs.max(1);
+    points
+        .into_iter()
+        .group_by(move |(time, _)| time.timestamp().div_euclid(bucket_secs) * bucket_secs)
+        .into_iter()
+        .map(|(bucket_start, bucket)| {
+            let indicators = bucket.map(|(_, indicators)| indicators).collect_vec();
+            IndicatorsPoint {
+                time: Utc.timestamp(bucket_start, 0).to_rfc3339(),
+                indicators: average(&indicators),
+            }
+        })
+        .collect()
+}
+
+fn average(indicators: &[Indicators]) -> Indicators {
+    fn avg(values: impl Iterator<Item = Decimal>) -> Option<Decimal> {
+        let values = values.collect_vec();
+        match values.is_empty() {
+            true => None,
+            false => Some(values.iter().sum::<Decimal>() / Decimal::from(values.len())),
+        }
+    }
+
+    fn avg_optional(
+        indicators: &[Indicators],
+        value: impl Fn(&Indicators) -> Option<Amount>,
+    ) -> Option<Amount> {
+        avg(indicators.iter().filter_map(value))
+    }
+
+    Indicators {
+        volume_pct: avg(indicators.iter().map(|x| x.volume_pct)).unwrap_or_default(),
+        bid_pct: avg(indicators.iter().map(|x| x.bid_pct)).unwrap_or_default(),
+        ask_pct: avg(indicators.iter().map(|x| x.ask_pct)).unwrap_or_default(),
+        spread: avg_optional(indicators, |x| x.spread),
+        total_volume: avg_optional(indicators, |x| x.total_volume),
+        total_bid: avg_optional(indicators, |x| x.total_bid),
+        total_ask: avg_optional(indicators, |x| x.total_ask),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn indicators(spread: Option<Decimal>, volume_pct: Decimal) -> Indicators {
+        Indicators {
+            volume_pct,
+            bid_pct: volume_pct / dec!(2),
+            ask_pct: volume_pct / dec!(2),
+            spread,
+            total_volume: None,
+            total_bid: None,
+            total_ask: None,
+        }
+    }
+
+    #[test]
+    fn downsample_by_minute_buckets() {
+        let start = Utc.ymd(2022, 10, 30).and_hms(12, 0, 0);
+        let points = vec![
+            (start, indicators(Some(dec!(0.2)), dec!(80))),
+            (
+                start + chrono::Duration::seconds(20),
+                indicators(None, dec!(100)),
+            ),
+            (
+                start + chrono::Duration::seconds(40),
+                indicators(Some(dec!(0.4)), dec!(90)),
+            ),
+            (
+                start + chrono::Duration::seconds(130),
+                indicators(Some(dec!(1)), dec!(50)),
+            ),
+        ];
+
+        let downsampled = downsample(points, 60);
+
+        assert_eq!(
+            downsampled,
+            vec![
+                IndicatorsPoint {
+                    time: start.to_rfc3339(),
+                    indicators: indicators(Some(dec!(0.3)), dec!(90)),
+                },
+                IndicatorsPoint {
+                    time: (start + chrono::Duration::minutes(2)).to_rfc3339(),
+                    indicators: indicators(Some(dec!(1)), dec!(50)),
+                },
+            ]
+        );
+    }
+}
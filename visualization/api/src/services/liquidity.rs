@@ -60,6 +60,15 @@ pub struct OrderBookSnapshotRecord {
 #[derive(Deserialize, Clone)]
 pub struct OrderBookOrderRecord;
 
+/// Order book claimed by exchange and local snapshot of engine just before applying it
+#[derive(Deserialize, Clone)]
+pub struct OrderBookComparisonRecord {
+    pub exchange_id: String,
+    pub currency_pair: String,
+    pub raw: OrderBookSnapshotRecord,
+    pub local: OrderBookSnapshotRecord,
+}
+
 #[derive(Deserialize, Clone)]
 pub struct PriceLevelRecord {
     #[serde(deserialize_with = "deserialize_number_from_string")]
@@ -158,6 +167,28 @@ impl LiquidityService {
         Ok(result)
     }
 
+    pub async fn get_order_book_comparison(
+        &self,
+        exchange_id: &str,
+        currency_pair: &str,
+    ) -> Result<OrderBookComparisonRecord, sqlx::Error> {
+        let sql = include_str!("sql/get_order_book_comparison.sql");
+        let record = sqlx::query_as::<Postgres, EventRecord>(sql)
+            .bind(exchange_id)
+            .bind(currency_pair)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let result: OrderBookComparisonRecord =
+            serde_json::from_value(record.json).unwrap_or_else(|_| {
+                panic!(
+                    "Incorrect database order book comparison data. ID: {:?}",
+                    record.id
+                )
+            });
+        Ok(result)
+    }
+
     pub async fn get_transactions(
         &self,
         exchange_id: &str,
@@ -1,17 +1,28 @@
 use crate::config::Market;
 use crate::services::liquidity::Amount;
 use itertools::Itertools;
+use serde::Serialize;
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::sync::RwLock;
 
 #[derive(Clone)]
 pub struct MarketInfo {
     pub desired_amount: Amount,
 }
 
-#[derive(Clone)]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DesiredAmount {
+    pub exchange_id: String,
+    pub currency_pair: String,
+    pub desired_amount: Amount,
+}
+
+/// Desired amounts are initialized by max amounts from config and can be changed at runtime,
+/// new values are used in the next liquidity data refresh
 pub struct MarketSettingsService {
-    pub exchanges: HashMap<String, HashMap<String, MarketInfo>>,
+    exchanges: RwLock<HashMap<String, HashMap<String, MarketInfo>>>,
     pub supported_exchanges: Vec<Value>,
 }
 
@@ -51,7 +62,7 @@ impl From<Vec<Market>> for MarketSettingsService {
             exchanges.insert(market.exchange_id, currency_pairs);
         }
         Self {
-            exchanges,
+            exchanges: RwLock::new(exchanges),
             supported_exchanges,
         }
     }
@@ -59,9 +70,56 @@ impl From<Vec<Market>> for MarketSettingsService {
 
 impl MarketSettingsService {
     pub fn get_desired_amount(&self, exchange_id: &str, currency_pair: &str) -> Option<Amount> {
-        match self.exchanges.get(exchange_id) {
+        match self
+            .exchanges
+            .read()
+            .expect("Failure to lock market settings")
+            .get(exchange_id)
+        {
             None => None,
             Some(exchange) => exchange.get(currency_pair).map(|info| info.desired_amount),
         }
     }
+
+    pub fn get_desired_amounts(&self) -> Vec<DesiredAmount> {
+        self.exchanges
+            .read()
+            .expect("Failure to lock market settings")
+            .iter()
+            .flat_map(|(exchange_id, currency_pairs)| {
+                currency_pairs
+                    .iter()
+                    .map(|(currency_pair, info)| DesiredAmount {
+                        exchange_id: exchange_id.clone(),
+                        currency_pair: currency_pair.clone(),
+                        desired_amount: info.desired_amount,
+                    })
+            })
+            .sorted_by(|a, b| {
+                (&a.exchange_id, &a.currency_pair).cmp(&(&b.exchange_id, &b.currency_pair))
+            })
+            .collect_vec()
+    }
+
+    /// Returns `false` if market isn't configured
+    pub fn set_desired_amount(
+        &self,
+        exchange_id: &str,
+        currency_pair: &str,
+        desired_amount: Amount,
+    ) -> bool {
+        match self
+            .exchanges
+            .write()
+            .expect("Failure to lock market settings")
+            .get_mut(exchange_id)
+            .and_then(|exchange| exchange.get_mut(currency_pair))
+        {
+            None => false,
+            Some(info) => {
+                info.desired_amount = desired_amount;
+                true
+            }
+        }
+    }
 }
@@ -1,5 +1,7 @@
 pub mod account;
 pub mod auth;
+pub mod history;
+pub mod indicators;
 pub mod liquidity;
 pub mod market_settings;
 pub mod settings;
@@ -0,0 +1,7 @@
+SELECT insert_time, json
+FROM liquidity_indicators
+WHERE ((json ->> 'exchange_id')::text = $1)
+  AND ((json ->> 'currency_pair')::text = $2)
+  AND insert_time >= $3
+  AND insert_time < $4
+ORDER BY insert_time, id
\ No newline at end of file
@@ -0,0 +1,6 @@
+SELECT id, json
+FROM order_book_comparisons
+WHERE ((json ->> 'exchange_id')::text = $1)
+  AND ((json ->> 'currency_pair')::text = $2)
+ORDER BY insert_time DESC, id DESC
+LIMIT 1
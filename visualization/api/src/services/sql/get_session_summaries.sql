@@ -0,0 +1,4 @@
+SELECT id, json
+FROM session_summaries
+ORDER BY insert_time DESC, id DESC
+LIMIT $1
\ No newline at end of file
@@ -1,6 +1,8 @@
 SELECT id, json
-FROM transactions
-WHERE ((json -> 'market_id' ->> 'exchange_id')::text = $1)
-  AND ((json -> 'market_id' ->> 'currency_pair')::text = $2)
+FROM (SELECT DISTINCT ON (json ->> 'transaction_id') id, json, insert_time
+      FROM transactions
+      WHERE ((json -> 'market_id' ->> 'exchange_id')::text = $1)
+        AND ((json -> 'market_id' ->> 'currency_pair')::text = $2)
+      ORDER BY json ->> 'transaction_id', (json ->> 'revision')::bigint DESC) AS last_revisions
 ORDER BY insert_time DESC, id DESC
 LIMIT $3
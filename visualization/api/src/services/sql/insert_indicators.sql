@@ -0,0 +1,2 @@
+INSERT INTO liquidity_indicators(json)
+VALUES ($1)
\ No newline at end of file
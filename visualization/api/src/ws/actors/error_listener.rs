@@ -1,7 +1,7 @@
-use crate::ws::broker_messages::{ClientErrorResponseMessage, SubscriptionErrorMessage};
+use crate::ws::broker_messages::{ClientErrorResponseMessage, Flush, SubscriptionErrorMessage};
+use crate::ws::protocol::{ErrorResponse, ERROR};
 use actix::{Actor, Context, Handler};
 use actix_broker::BrokerIssue;
-use serde_json::json;
 
 #[derive(Default)]
 pub struct ErrorListener;
@@ -24,10 +24,19 @@ impl Handler<SubscriptionErrorMessage> for ErrorListener {
 
     fn handle(&mut self, data: SubscriptionErrorMessage, _ctx: &mut Context<Self>) -> Self::Result {
         let error = ClientErrorResponseMessage {
-            command: "Error",
+            command: ERROR,
             subscription: data.subscription,
-            content: json!({"message" : data.message}),
+            content: serde_json::to_value(ErrorResponse {
+                message: data.message,
+            })
+            .expect("Failure to serialize error response"),
         };
         self.issue_system_async(error);
     }
 }
+
+impl Handler<Flush> for ErrorListener {
+    type Result = ();
+
+    fn handle(&mut self, _msg: Flush, _ctx: &mut Context<Self>) -> Self::Result {}
+}
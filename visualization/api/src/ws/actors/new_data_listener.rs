@@ -1,5 +1,10 @@
-use crate::ws::broker_messages::{LiquidityResponseMessage, NewLiquidityDataMessage};
+use crate::ws::broker_messages::{
+    Flush, LiquidityResponseMessage, NewLiquidityDataMessage, NewOrderBookDebugDataMessage,
+    OrderBookDebugResponseMessage,
+};
 use crate::ws::commands::liquidity::LiquidityResponseBody;
+use crate::ws::commands::order_book_debug::OrderBookDebugResponseBody;
+use crate::ws::protocol::{UPDATE_ORDERS_STATE, UPDATE_ORDER_BOOK_DEBUG};
 use actix::{Actor, Context, Handler};
 use actix_broker::BrokerIssue;
 
@@ -25,10 +30,32 @@ impl Handler<NewLiquidityDataMessage> for NewDataListener {
     fn handle(&mut self, data: NewLiquidityDataMessage, _ctx: &mut Context<Self>) -> Self::Result {
         let body: LiquidityResponseBody = LiquidityResponseBody::from(data.data);
         let liquidity_response_message = LiquidityResponseMessage {
-            command: "UpdateOrdersState",
+            command: UPDATE_ORDERS_STATE,
             body,
             subscription: data.subscription,
         };
         self.issue_system_async(liquidity_response_message);
     }
 }
+
+impl Handler<NewOrderBookDebugDataMessage> for NewDataListener {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        data: NewOrderBookDebugDataMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        self.issue_system_async(OrderBookDebugResponseMessage {
+            command: UPDATE_ORDER_BOOK_DEBUG,
+            body: OrderBookDebugResponseBody::from(data.data),
+            subscription: data.subscription,
+        });
+    }
+}
+
+impl Handler<Flush> for NewDataListener {
+    type Result = ();
+
+    fn handle(&mut self, _msg: Flush, _ctx: &mut Context<Self>) -> Self::Result {}
+}
@@ -1,21 +1,80 @@
 use crate::ws::actors::ws_client_session::WsClientSession;
 use crate::ws::broker_messages::{
-    ClearSubscriptions, ClientConnected, ClientDisconnected, GatherSubscriptions,
-    GetLiquiditySubscriptions, GetSessionLiquiditySubscription,
+    ClientConnected, ClientDisconnected, GetLiquiditySubscriptions, GetOrderBookDebugSubscriptions,
+    GetSubscriptionsMetrics, SessionLiquiditySubscriptionChanged,
+    SessionOrderBookDebugSubscriptionChanged,
 };
 use crate::ws::subscribes::liquidity::LiquiditySubscription;
-use actix::{
-    Actor, ActorFutureExt, Addr, Context, ContextFutureSpawner, Handler, MessageResult, Supervised,
-    SystemService, WrapFuture,
-};
+use crate::ws::subscribes::order_book_debug::OrderBookDebugSubscription;
+use actix::{Actor, Addr, Context, Handler, MessageResult, Supervised, SystemService};
 use actix_broker::BrokerSubscribe;
-use futures::future::join_all;
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 
+/// Tracks subscriptions of websocket sessions. Sessions report changes of their subscriptions,
+/// so data provider never waits for dead clients while gathering subscriptions
 #[derive(Default, Clone)]
 pub struct SubscriptionManager {
     clients: HashSet<Addr<WsClientSession>>,
-    liquidity_subscriptions: HashSet<LiquiditySubscription>,
+    liquidity_subscriptions: HashMap<Addr<WsClientSession>, LiquiditySubscription>,
+    order_book_debug_subscriptions: HashMap<Addr<WsClientSession>, OrderBookDebugSubscription>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionsMetrics {
+    pub clients: usize,
+    pub subscribed_clients: usize,
+    /// Count of distinct liquidity subscriptions which are loaded from database on every refresh
+    pub liquidity_subscriptions: usize,
+    /// Count of distinct order book debug subscriptions
+    pub order_book_debug_subscriptions: usize,
+}
+
+impl SubscriptionManager {
+    fn metrics(&self) -> SubscriptionsMetrics {
+        SubscriptionsMetrics {
+            clients: self.clients.len(),
+            subscribed_clients: self.liquidity_subscriptions.len(),
+            liquidity_subscriptions: self
+                .liquidity_subscriptions
+                .values()
+                .collect::<HashSet<_>>()
+                .len(),
+            order_book_debug_subscriptions: self
+                .order_book_debug_subscriptions
+                .values()
+                .collect::<HashSet<_>>()
+                .len(),
+        }
+    }
+
+    fn remove_client(&mut self, client: &Addr<WsClientSession>) {
+        self.clients.remove(client);
+        self.liquidity_subscriptions.remove(client);
+        self.order_book_debug_subscriptions.remove(client);
+    }
+
+    /// Safety net for sessions which are stopped without `ClientDisconnected` delivered
+    fn remove_disconnected_clients(&mut self) {
+        let disconnected = self
+            .clients
+            .iter()
+            .filter(|client| !client.connected())
+            .cloned()
+            .collect::<Vec<_>>();
+        if disconnected.is_empty() {
+            return;
+        }
+
+        log::warn!(
+            "Removing {} disconnected clients from subscription manager",
+            disconnected.len()
+        );
+        for client in &disconnected {
+            self.remove_client(client);
+        }
+    }
 }
 
 impl Actor for SubscriptionManager {
@@ -24,6 +83,8 @@ impl Actor for SubscriptionManager {
     fn started(&mut self, ctx: &mut Self::Context) {
         self.subscribe_system_async::<ClientConnected>(ctx);
         self.subscribe_system_async::<ClientDisconnected>(ctx);
+        self.subscribe_system_async::<SessionLiquiditySubscriptionChanged>(ctx);
+        self.subscribe_system_async::<SessionOrderBookDebugSubscriptionChanged>(ctx);
         log::info!("Subscription Manager started");
     }
 
@@ -36,7 +97,7 @@ impl Handler<ClientConnected> for SubscriptionManager {
     type Result = ();
     fn handle(&mut self, msg: ClientConnected, _ctx: &mut Context<Self>) {
         self.clients.insert(msg.data);
-        log::debug!("Client added to subscription manager")
+        log::debug!("Client added to subscription manager. {:?}", self.metrics())
     }
 }
 
@@ -44,51 +105,58 @@ impl Handler<ClientDisconnected> for SubscriptionManager {
     type Result = ();
 
     fn handle(&mut self, msg: ClientDisconnected, _ctx: &mut Context<Self>) {
-        self.clients.remove(&msg.data);
-        log::debug!("Client removed from subscription manager")
+        self.remove_client(&msg.data);
+        log::debug!(
+            "Client removed from subscription manager. {:?}",
+            self.metrics()
+        )
     }
 }
 
-/// Get all clients subscriptions
-impl Handler<GatherSubscriptions> for SubscriptionManager {
+impl Handler<SessionLiquiditySubscriptionChanged> for SubscriptionManager {
     type Result = ();
-    fn handle(&mut self, _msg: GatherSubscriptions, ctx: &mut Context<Self>) -> Self::Result {
-        log::debug!("GatherSubscriptions executed");
-        let futures = self
-            .clients
-            .iter()
-            .map(|client| client.send(GetSessionLiquiditySubscription));
-
-        join_all(futures)
-            .into_actor(self)
-            .map(|messages, current_actor, _| {
-                for message in messages {
-                    match message {
-                        Ok(message) => match message {
-                            Some(liquidity_subscription) => {
-                                let _ = current_actor
-                                    .liquidity_subscriptions
-                                    .insert(liquidity_subscription);
-                            }
-                            None => {
-                                // client doesn't have liquidity subscription
-                            }
-                        },
-                        Err(e) => log::error!("Invalid subscription message {e:?}"),
-                    }
-                }
-            })
-            .wait(ctx);
-        log::debug!("GatherSubscriptions finished");
+
+    fn handle(&mut self, msg: SessionLiquiditySubscriptionChanged, _ctx: &mut Context<Self>) {
+        if !self.clients.contains(&msg.client) {
+            log::debug!("Subscription of unknown client is ignored");
+            return;
+        }
+
+        match msg.subscription {
+            Some(subscription) => {
+                self.liquidity_subscriptions
+                    .insert(msg.client, subscription);
+            }
+            None => {
+                self.liquidity_subscriptions.remove(&msg.client);
+            }
+        }
+        log::debug!("Liquidity subscription changed. {:?}", self.metrics())
     }
 }
 
-/// Clear subscription cache
-impl Handler<ClearSubscriptions> for SubscriptionManager {
+impl Handler<SessionOrderBookDebugSubscriptionChanged> for SubscriptionManager {
     type Result = ();
-    fn handle(&mut self, _msg: ClearSubscriptions, _ctx: &mut Context<Self>) -> Self::Result {
-        log::debug!("ClearSubscriptions executed");
-        self.liquidity_subscriptions.clear();
+
+    fn handle(&mut self, msg: SessionOrderBookDebugSubscriptionChanged, _ctx: &mut Context<Self>) {
+        if !self.clients.contains(&msg.client) {
+            log::debug!("Subscription of unknown client is ignored");
+            return;
+        }
+
+        match msg.subscription {
+            Some(subscription) => {
+                self.order_book_debug_subscriptions
+                    .insert(msg.client, subscription);
+            }
+            None => {
+                self.order_book_debug_subscriptions.remove(&msg.client);
+            }
+        }
+        log::debug!(
+            "Order book debug subscription changed. {:?}",
+            self.metrics()
+        )
     }
 }
 
@@ -100,7 +168,33 @@ impl Handler<GetLiquiditySubscriptions> for SubscriptionManager {
         _ctx: &mut Context<Self>,
     ) -> Self::Result {
         log::debug!("GetLiquiditySubscriptions executed");
-        MessageResult(self.liquidity_subscriptions.clone())
+        self.remove_disconnected_clients();
+        MessageResult(self.liquidity_subscriptions.values().cloned().collect())
+    }
+}
+
+impl Handler<GetOrderBookDebugSubscriptions> for SubscriptionManager {
+    type Result = MessageResult<GetOrderBookDebugSubscriptions>;
+    fn handle(
+        &mut self,
+        _msg: GetOrderBookDebugSubscriptions,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        self.remove_disconnected_clients();
+        MessageResult(
+            self.order_book_debug_subscriptions
+                .values()
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+impl Handler<GetSubscriptionsMetrics> for SubscriptionManager {
+    type Result = MessageResult<GetSubscriptionsMetrics>;
+    fn handle(&mut self, _msg: GetSubscriptionsMetrics, _ctx: &mut Context<Self>) -> Self::Result {
+        self.remove_disconnected_clients();
+        MessageResult(self.metrics())
     }
 }
 
@@ -1,34 +1,71 @@
 use crate::ws::broker_messages::{
-    ClientConnected, ClientDisconnected, ClientErrorResponseMessage,
-    GetSessionLiquiditySubscription, LiquidityResponseMessage,
+    ClientConnected, ClientDisconnected, ClientErrorResponseMessage, LiquidityResponseMessage,
+    OrderBookDebugResponseMessage, ServerShutdown, SessionLiquiditySubscriptionChanged,
+    SessionOrderBookDebugSubscriptionChanged,
 };
-use actix::{Actor, ActorContext, AsyncContext, Handler, MessageResult, StreamHandler};
+use actix::{Actor, ActorContext, AsyncContext, Handler, StreamHandler};
 use actix_broker::{BrokerIssue, BrokerSubscribe};
 use actix_web::web::Data;
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
+use crate::config::WsLimits;
 use crate::services::token::TokenService;
+use crate::ws::protocol::{
+    Auth, Authorized, ClientCommand, CommandError, ErrorResponse, AUTHORIZED, ERROR,
+    PROTOCOL_VERSION,
+};
+use crate::ws::rate_limiter::MessageRateLimiter;
 use crate::ws::subscribes::liquidity::{LiquiditySubscription, Subscription};
-use actix_web_actors::ws::{Message, ProtocolError, WebsocketContext};
-use serde::Deserialize;
-use serde_json::{json, Value};
+use crate::ws::subscribes::order_book_debug::OrderBookDebugSubscription;
+use actix_web_actors::ws::{CloseCode, CloseReason, Message, ProtocolError, WebsocketContext};
+use serde::Serialize;
+use serde_json::Value;
 
 pub struct WsClientSession {
     subscriptions: HashSet<u64>,
     subscribed_liquidity: Option<LiquiditySubscription>,
+    subscribed_order_book_debug: Option<OrderBookDebugSubscription>,
     token_service: Data<TokenService>,
     is_auth: bool,
+    limits: WsLimits,
+    rate_limiter: MessageRateLimiter,
+    last_activity: Instant,
 }
 
 impl WsClientSession {
-    pub fn new(token_service: Data<TokenService>) -> Self {
+    pub fn new(token_service: Data<TokenService>, limits: WsLimits) -> Self {
         Self {
             subscriptions: HashSet::new(),
             subscribed_liquidity: None,
+            subscribed_order_book_debug: None,
             token_service,
             is_auth: false,
+            limits,
+            rate_limiter: MessageRateLimiter::new(
+                limits.max_messages_per_interval,
+                Duration::from_millis(limits.rate_interval_ms),
+            ),
+            last_activity: Instant::now(),
         }
     }
+
+    /// Pings client and closes session if client is idle for longer than idle timeout
+    fn start_heartbeat(&self, ctx: &mut WebsocketContext<Self>) {
+        let idle_timeout = Duration::from_millis(self.limits.idle_timeout_ms);
+        ctx.run_interval(
+            Duration::from_millis(self.limits.heartbeat_interval_ms),
+            move |session, ctx| {
+                if session.last_activity.elapsed() > idle_timeout {
+                    log::info!("Websocket client is idle for {idle_timeout:?}, closing session");
+                    session.reject_by_policy(ctx, "Idle timeout is exceeded".to_owned());
+                    return;
+                }
+
+                ctx.ping(b"");
+            },
+        );
+    }
 }
 
 /// Websocket client session
@@ -37,7 +74,10 @@ impl Actor for WsClientSession {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         self.subscribe_system_async::<LiquidityResponseMessage>(ctx);
+        self.subscribe_system_async::<OrderBookDebugResponseMessage>(ctx);
         self.subscribe_system_async::<ClientErrorResponseMessage>(ctx);
+        self.subscribe_system_async::<ServerShutdown>(ctx);
+        self.start_heartbeat(ctx);
         let message = ClientConnected {
             data: ctx.address(),
         };
@@ -45,7 +85,12 @@ impl Actor for WsClientSession {
         log::info!("Websocket client connected");
     }
 
+    /// Subscriptions are released on any stop of session including errors and timeouts,
+    /// subscription manager releases them on its side by `ClientDisconnected`
     fn stopped(&mut self, ctx: &mut Self::Context) {
+        self.release_liquidity_subscription();
+        self.release_order_book_debug_subscription();
+        self.subscriptions.clear();
         let message = ClientDisconnected {
             data: ctx.address(),
         };
@@ -85,6 +130,21 @@ impl Handler<LiquidityResponseMessage> for WsClientSession {
     }
 }
 
+impl Handler<OrderBookDebugResponseMessage> for WsClientSession {
+    type Result = ();
+    fn handle(
+        &mut self,
+        msg: OrderBookDebugResponseMessage,
+        ctx: &mut WebsocketContext<Self>,
+    ) -> Self::Result {
+        if !self.is_auth || self.subscribed_order_book_debug.as_ref() != Some(&msg.subscription) {
+            return;
+        }
+
+        send_response(ctx, msg.command, &msg.body);
+    }
+}
+
 impl Handler<ClientErrorResponseMessage> for WsClientSession {
     type Result = ();
     fn handle(
@@ -98,15 +158,16 @@ impl Handler<ClientErrorResponseMessage> for WsClientSession {
     }
 }
 
-impl Handler<GetSessionLiquiditySubscription> for WsClientSession {
-    type Result = MessageResult<GetSessionLiquiditySubscription>;
+/// Client is asked to reconnect, so it continues with other instance of server after deploy
+impl Handler<ServerShutdown> for WsClientSession {
+    type Result = ();
 
-    fn handle(
-        &mut self,
-        _msg: GetSessionLiquiditySubscription,
-        _ctx: &mut WebsocketContext<Self>,
-    ) -> Self::Result {
-        MessageResult(self.subscribed_liquidity.clone())
+    fn handle(&mut self, _msg: ServerShutdown, ctx: &mut WebsocketContext<Self>) -> Self::Result {
+        close(
+            ctx,
+            CloseCode::Restart,
+            "Server is shutting down".to_owned(),
+        );
     }
 }
 
@@ -123,8 +184,21 @@ impl StreamHandler<Result<Message, ProtocolError>> for WsClientSession {
             }
         };
 
+        let now = Instant::now();
+        self.last_activity = now;
+        if !self.rate_limiter.try_acquire(now) {
+            log::warn!("Websocket client exceeded messages rate limit, closing session");
+            self.reject_by_policy(ctx, "Messages rate limit is exceeded".to_owned());
+            return;
+        }
+
         let msg = match msg {
             Message::Text(message) => message.to_string(),
+            Message::Ping(bytes) => {
+                ctx.pong(&bytes);
+                return;
+            }
+            Message::Pong(_) => return,
             Message::Close(reason) => {
                 log::info!("Socket close message: Reason: {reason:?}");
                 ctx.stop();
@@ -137,75 +211,176 @@ impl StreamHandler<Result<Message, ProtocolError>> for WsClientSession {
             }
         };
 
-        let mut slices = msg.splitn(2, '|');
-        let command = slices.next();
-        let body = slices.next().unwrap_or("");
-
-        match command {
-            None => {
-                log::error!("Failure get command from message: Message: {msg:?}");
-                ctx.stop();
-            }
-            Some(command) => self.route(command, body, ctx),
+        match ClientCommand::parse(&msg) {
+            Ok(command) => self.route(command, ctx),
+            Err(error) => self.reject(ctx, &msg, error),
         }
     }
 }
 
-#[derive(Clone, Deserialize)]
-struct Auth {
-    token: String,
-}
-
 impl WsClientSession {
-    fn route(&mut self, command: &str, body: &str, ctx: &mut WebsocketContext<WsClientSession>) {
+    fn route(&mut self, command: ClientCommand, ctx: &mut WebsocketContext<WsClientSession>) {
         match command {
-            // Authorization
-            "Auth" => self.auth(ctx, body),
-            // Subscription for one record of order book (20 orders) and last 20 transactions
-            "SubscribeLiquidity" => self.subscribe_liquidity(ctx, body),
-            // Unsubscribe from "SubscribeLiquidity"
-            "UnsubscribeLiquidity" => self.unsubscribe_liquidity(),
-            _ => {
-                log::error!("Unknown command: {command}, body: {body}");
+            ClientCommand::Auth(auth) => self.auth(ctx, auth),
+            ClientCommand::SubscribeLiquidity(subscription) => {
+                self.subscribe_liquidity(ctx, subscription)
+            }
+            ClientCommand::UnsubscribeLiquidity => self.unsubscribe_liquidity(ctx),
+            ClientCommand::SubscribeOrderBookDebug(subscription) => {
+                self.subscribe_order_book_debug(ctx, subscription)
             }
+            ClientCommand::UnsubscribeOrderBookDebug => self.unsubscribe_order_book_debug(ctx),
         };
     }
 
-    fn auth(&mut self, ctx: &mut WebsocketContext<WsClientSession>, body: &str) {
-        match serde_json::from_str::<Auth>(body) {
-            Ok(auth) => {
-                let res = self.token_service.parse_access_token(&auth.token);
-                self.is_auth = res.is_ok();
-                send_message(ctx, "Authorized", json!({"value": self.is_auth}));
-            }
-            Err(e) => {
-                ctx.stop();
-                log::error!("Failed to create Auth from: {body}.  Error: {e:?}")
-            }
-        };
+    /// Unknown commands are reported to client and ignored, so newer client keeps working with
+    /// older server. Session is closed after malformed or incompatible command
+    fn reject(
+        &mut self,
+        ctx: &mut WebsocketContext<WsClientSession>,
+        msg: &str,
+        error: CommandError,
+    ) {
+        log::error!("Failed to handle message: {msg}. Error: {error}");
+        send_error(ctx, error.to_string());
+        match error {
+            CommandError::UnknownCommand(_) => {}
+            CommandError::InvalidBody { .. } | CommandError::UnsupportedVersion(_) => ctx.stop(),
+        }
     }
 
-    fn subscribe_liquidity(&mut self, ctx: &mut WebsocketContext<WsClientSession>, body: &str) {
-        match serde_json::from_str::<LiquiditySubscription>(body) {
-            Ok(subscription) => {
-                self.subscriptions.insert(subscription.get_hash());
-                self.subscribed_liquidity = Some(subscription);
-            }
-            Err(e) => {
-                ctx.stop();
-                log::error!("Failed to create LiquiditySubscription from: {body}. Error: {e:?}")
-            }
-        };
+    fn auth(&mut self, ctx: &mut WebsocketContext<WsClientSession>, auth: Auth) {
+        let res = self.token_service.parse_access_token(&auth.token);
+        self.is_auth = res.is_ok();
+        send_response(
+            ctx,
+            AUTHORIZED,
+            &Authorized {
+                value: self.is_auth,
+                version: PROTOCOL_VERSION,
+            },
+        );
     }
 
-    fn unsubscribe_liquidity(&mut self) {
-        match &self.subscribed_liquidity {
-            None => {}
-            Some(subscription) => {
-                self.subscriptions.remove(&subscription.get_hash());
-                self.subscribed_liquidity = None;
-            }
+    fn subscribe_liquidity(
+        &mut self,
+        ctx: &mut WebsocketContext<WsClientSession>,
+        subscription: LiquiditySubscription,
+    ) {
+        // Session has single liquidity subscription, so new one replaces previous
+        self.release_liquidity_subscription();
+        if self.subscriptions.len() >= self.limits.max_subscriptions {
+            send_error(
+                ctx,
+                format!(
+                    "Subscriptions limit {} is exceeded",
+                    self.limits.max_subscriptions
+                ),
+            );
+            self.notify_liquidity_subscription_changed(ctx);
+            return;
+        }
+
+        self.subscriptions.insert(subscription.get_hash());
+        self.subscribed_liquidity = Some(subscription);
+        self.notify_liquidity_subscription_changed(ctx);
+    }
+
+    fn unsubscribe_liquidity(&mut self, ctx: &mut WebsocketContext<WsClientSession>) {
+        self.release_liquidity_subscription();
+        self.notify_liquidity_subscription_changed(ctx);
+    }
+
+    fn release_liquidity_subscription(&mut self) {
+        if let Some(subscription) = self.subscribed_liquidity.take() {
+            self.subscriptions.remove(&subscription.get_hash());
+        }
+    }
+
+    fn notify_liquidity_subscription_changed(
+        &mut self,
+        ctx: &mut WebsocketContext<WsClientSession>,
+    ) {
+        self.issue_system_async(SessionLiquiditySubscriptionChanged {
+            client: ctx.address(),
+            subscription: self.subscribed_liquidity.clone(),
+        });
+    }
+
+    fn subscribe_order_book_debug(
+        &mut self,
+        ctx: &mut WebsocketContext<WsClientSession>,
+        subscription: OrderBookDebugSubscription,
+    ) {
+        // Session has single order book debug subscription, so new one replaces previous
+        self.release_order_book_debug_subscription();
+        if self.subscriptions.len() >= self.limits.max_subscriptions {
+            send_error(
+                ctx,
+                format!(
+                    "Subscriptions limit {} is exceeded",
+                    self.limits.max_subscriptions
+                ),
+            );
+            self.notify_order_book_debug_subscription_changed(ctx);
+            return;
         }
+
+        self.subscriptions.insert(subscription.get_hash());
+        self.subscribed_order_book_debug = Some(subscription);
+        self.notify_order_book_debug_subscription_changed(ctx);
+    }
+
+    fn unsubscribe_order_book_debug(&mut self, ctx: &mut WebsocketContext<WsClientSession>) {
+        self.release_order_book_debug_subscription();
+        self.notify_order_book_debug_subscription_changed(ctx);
+    }
+
+    fn release_order_book_debug_subscription(&mut self) {
+        if let Some(subscription) = self.subscribed_order_book_debug.take() {
+            self.subscriptions.remove(&subscription.get_hash());
+        }
+    }
+
+    fn notify_order_book_debug_subscription_changed(
+        &mut self,
+        ctx: &mut WebsocketContext<WsClientSession>,
+    ) {
+        self.issue_system_async(SessionOrderBookDebugSubscriptionChanged {
+            client: ctx.address(),
+            subscription: self.subscribed_order_book_debug.clone(),
+        });
+    }
+}
+
+impl WsClientSession {
+    /// Closes session violating protocol policy with explanation for client
+    fn reject_by_policy(&mut self, ctx: &mut WebsocketContext<WsClientSession>, reason: String) {
+        send_error(ctx, reason.clone());
+        close(ctx, CloseCode::Policy, reason);
+    }
+}
+
+fn close(ctx: &mut WebsocketContext<WsClientSession>, code: CloseCode, reason: String) {
+    ctx.close(Some(CloseReason {
+        code,
+        description: Some(reason),
+    }));
+    ctx.stop();
+}
+
+fn send_error(ctx: &mut WebsocketContext<WsClientSession>, message: String) {
+    send_response(ctx, ERROR, &ErrorResponse { message });
+}
+
+fn send_response(
+    ctx: &mut WebsocketContext<WsClientSession>,
+    command: &str,
+    body: &impl Serialize,
+) {
+    match serde_json::to_value(body) {
+        Ok(content) => send_message(ctx, command, content),
+        Err(e) => log::error!("Failure convert to json. Error: {e:?}"),
     }
 }
 
@@ -1,7 +1,10 @@
-use crate::services::liquidity::LiquidityData;
+use crate::services::liquidity::{LiquidityData, OrderBookComparisonRecord};
+use crate::ws::actors::subscription_manager::SubscriptionsMetrics;
 use crate::ws::actors::ws_client_session::WsClientSession;
 use crate::ws::commands::liquidity::LiquidityResponseBody;
+use crate::ws::commands::order_book_debug::OrderBookDebugResponseBody;
 use crate::ws::subscribes::liquidity::LiquiditySubscription;
+use crate::ws::subscribes::order_book_debug::OrderBookDebugSubscription;
 use actix::prelude::*;
 use serde_json::Value;
 use std::collections::HashSet;
@@ -14,6 +17,14 @@ pub struct LiquidityResponseMessage {
     pub subscription: LiquiditySubscription,
 }
 
+#[derive(Clone, Message)]
+#[rtype(result = "()")]
+pub struct OrderBookDebugResponseMessage {
+    pub command: &'static str,
+    pub body: OrderBookDebugResponseBody,
+    pub subscription: OrderBookDebugSubscription,
+}
+
 #[derive(Clone, Message)]
 #[rtype(result = "()")]
 pub struct ClientErrorResponseMessage {
@@ -22,6 +33,16 @@ pub struct ClientErrorResponseMessage {
     pub content: Value,
 }
 
+/// Server is shutting down, websocket sessions have to be closed
+#[derive(Clone, Message)]
+#[rtype(result = "()")]
+pub struct ServerShutdown;
+
+/// Actor responds to it after handling of all messages received earlier
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Flush;
+
 #[derive(Clone, Message)]
 #[rtype(result = "()")]
 pub struct NewLiquidityDataMessage {
@@ -33,6 +54,17 @@ pub struct NewLiquidityDataMessage {
 #[rtype(result = "HashSet<LiquiditySubscription>")]
 pub struct GetLiquiditySubscriptions;
 
+#[derive(Clone, Message)]
+#[rtype(result = "()")]
+pub struct NewOrderBookDebugDataMessage {
+    pub data: OrderBookComparisonRecord,
+    pub subscription: OrderBookDebugSubscription,
+}
+
+#[derive(Clone, Message)]
+#[rtype(result = "HashSet<OrderBookDebugSubscription>")]
+pub struct GetOrderBookDebugSubscriptions;
+
 #[derive(Clone, Message)]
 #[rtype(result = "()")]
 pub struct ClientConnected {
@@ -45,17 +77,25 @@ pub struct ClientDisconnected {
     pub data: Addr<WsClientSession>,
 }
 
+/// Liquidity subscription of session is changed, `None` if session is unsubscribed
 #[derive(Clone, Message)]
 #[rtype(result = "()")]
-pub struct GatherSubscriptions;
+pub struct SessionLiquiditySubscriptionChanged {
+    pub client: Addr<WsClientSession>,
+    pub subscription: Option<LiquiditySubscription>,
+}
 
+/// Order book debug subscription of session is changed, `None` if session is unsubscribed
 #[derive(Clone, Message)]
-#[rtype(result = "Option<LiquiditySubscription>")]
-pub struct GetSessionLiquiditySubscription;
+#[rtype(result = "()")]
+pub struct SessionOrderBookDebugSubscriptionChanged {
+    pub client: Addr<WsClientSession>,
+    pub subscription: Option<OrderBookDebugSubscription>,
+}
 
 #[derive(Clone, Message)]
-#[rtype(result = "()")]
-pub struct ClearSubscriptions;
+#[rtype(result = "SubscriptionsMetrics")]
+pub struct GetSubscriptionsMetrics;
 
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -29,12 +29,14 @@ pub struct OrderStateAndTransactions {
 }
 
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct Orders {
     pub orders: Vec<Order>,
     pub snapshot: Vec<(Price, Amount)>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct Order {
     pub amount: Amount,
     pub price: Price,
@@ -65,7 +67,7 @@ pub struct Trade {
     pub side: Option<TransactionTradeSide>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Indicators {
     pub volume_pct: Decimal,
@@ -112,12 +114,7 @@ impl From<LiquidityData> for LiquidityResponseBody {
                 }),
             });
 
-        let indicators = get_indicators(
-            &liquidity_data,
-            &buy_snapshot,
-            &sell_snapshot,
-            liquidity_data.desired_amount,
-        );
+        let indicators = get_indicators(&liquidity_data);
 
         let transactions = liquidity_data
             .transactions
@@ -171,12 +168,7 @@ impl From<LiquidityData> for LiquidityResponseBody {
     }
 }
 
-fn get_indicators(
-    liquidity_data: &LiquidityData,
-    buy_snapshot: &[(Price, Amount)],
-    sell_snapshot: &[(Price, Amount)],
-    desired_amount: Amount,
-) -> Indicators {
+pub fn get_indicators(liquidity_data: &LiquidityData) -> Indicators {
     fn get_total(data: &LiquidityData, side: LiquidityOrderSide) -> Option<Amount> {
         let mut iter = data
             .order_book
@@ -194,8 +186,9 @@ fn get_indicators(
             .unwrap_or_else(|| panic!("Error partial_cmp {} {}", a, b))
     }
 
-    let top_bid_price = buy_snapshot.iter().map(|x| x.0).max_by(cmp_prices);
-    let top_ask_price = sell_snapshot.iter().map(|x| x.0).min_by(cmp_prices);
+    let snapshot = &liquidity_data.order_book.snapshot;
+    let top_bid_price = snapshot.bids.iter().map(|x| x.price).max_by(cmp_prices);
+    let top_ask_price = snapshot.asks.iter().map(|x| x.price).min_by(cmp_prices);
 
     let total_bid = get_total(liquidity_data, LiquidityOrderSide::Buy);
     let total_ask = get_total(liquidity_data, LiquidityOrderSide::Sell);
@@ -205,7 +198,7 @@ fn get_indicators(
         top_ask_price,
         total_bid,
         total_ask,
-        desired_amount,
+        liquidity_data.desired_amount,
     );
 
     Indicators {
@@ -0,0 +1,176 @@
+use crate::services::liquidity::{
+    Amount, OrderBookComparisonRecord, OrderBookSnapshotRecord, Price, PriceLevelRecord,
+};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderBookDebugResponseBody {
+    pub exchange_id: String,
+    pub currency_pair: String,
+    /// Order book claimed by exchange
+    pub raw: OrderBookSides,
+    /// Local snapshot just before applying of order book claimed by exchange
+    pub local: OrderBookSides,
+    pub divergences: Vec<PriceLevelDivergence>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderBookSides {
+    pub asks: Vec<(Price, Amount)>,
+    pub bids: Vec<(Price, Amount)>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum OrderBookSide {
+    Ask,
+    Bid,
+}
+
+/// Price level with different amounts in raw and local order books,
+/// `None` amount means that price level is absent in order book
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceLevelDivergence {
+    pub side: OrderBookSide,
+    pub price: Price,
+    pub raw_amount: Option<Amount>,
+    pub local_amount: Option<Amount>,
+}
+
+impl From<OrderBookComparisonRecord> for OrderBookDebugResponseBody {
+    fn from(comparison: OrderBookComparisonRecord) -> Self {
+        let mut divergences = side_divergences(
+            OrderBookSide::Ask,
+            &comparison.raw.asks,
+            &comparison.local.asks,
+        );
+        divergences.extend(side_divergences(
+            OrderBookSide::Bid,
+            &comparison.raw.bids,
+            &comparison.local.bids,
+        ));
+
+        Self {
+            exchange_id: comparison.exchange_id,
+            currency_pair: comparison.currency_pair,
+            raw: comparison.raw.into(),
+            local: comparison.local.into(),
+            divergences,
+        }
+    }
+}
+
+impl From<OrderBookSnapshotRecord> for OrderBookSides {
+    fn from(snapshot: OrderBookSnapshotRecord) -> Self {
+        let levels = |levels: Vec<PriceLevelRecord>| {
+            levels
+                .into_iter()
+                .map(|level| (level.price, level.amount))
+                .collect_vec()
+        };
+        Self {
+            asks: levels(snapshot.asks),
+            bids: levels(snapshot.bids),
+        }
+    }
+}
+
+/// Compares price levels starting from the best price. Levels are limited by depth of raw
+/// order book, so deeper levels of local snapshot aren't reported as divergences
+fn side_divergences(
+    side: OrderBookSide,
+    raw: &[PriceLevelRecord],
+    local: &[PriceLevelRecord],
+) -> Vec<PriceLevelDivergence> {
+    let is_in_raw_depth = |price: Price| match (raw.last(), side) {
+        (None, _) => true,
+        (Some(worst), OrderBookSide::Ask) => price <= worst.price,
+        (Some(worst), OrderBookSide::Bid) => price >= worst.price,
+    };
+
+    let mut levels: BTreeMap<Price, (Option<Amount>, Option<Amount>)> = BTreeMap::new();
+    for level in raw {
+        levels.entry(level.price).or_default().0 = Some(level.amount);
+    }
+    for level in local.iter().filter(|x| is_in_raw_depth(x.price)) {
+        levels.entry(level.price).or_default().1 = Some(level.amount);
+    }
+
+    let divergences = levels
+        .into_iter()
+        .filter(|(_, (raw_amount, local_amount))| raw_amount != local_amount)
+        .map(|(price, (raw_amount, local_amount))| PriceLevelDivergence {
+            side,
+            price,
+            raw_amount,
+            local_amount,
+        });
+    match side {
+        OrderBookSide::Ask => divergences.collect(),
+        OrderBookSide::Bid => divergences.rev().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn levels(levels: &[(Price, Amount)]) -> Vec<PriceLevelRecord> {
+        levels
+            .iter()
+            .map(|&(price, amount)| PriceLevelRecord { price, amount })
+            .collect()
+    }
+
+    #[test]
+    fn divergences_within_raw_depth() {
+        let raw = levels(&[
+            (dec!(100), dec!(1)),
+            (dec!(101), dec!(2)),
+            (dec!(102), dec!(3)),
+        ]);
+        let local = levels(&[
+            (dec!(99), dec!(5)),
+            (dec!(100), dec!(1)),
+            (dec!(101), dec!(1.5)),
+            (dec!(103), dec!(4)),
+        ]);
+
+        let divergences = side_divergences(OrderBookSide::Ask, &raw, &local);
+
+        let divergence = |price, raw_amount, local_amount| PriceLevelDivergence {
+            side: OrderBookSide::Ask,
+            price,
+            raw_amount,
+            local_amount,
+        };
+        assert_eq!(
+            divergences,
+            vec![
+                divergence(dec!(99), None, Some(dec!(5))),
+                divergence(dec!(101), Some(dec!(2)), Some(dec!(1.5))),
+                divergence(dec!(102), Some(dec!(3)), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn bid_divergences_from_best_price() {
+        let raw = levels(&[(dec!(100), dec!(1)), (dec!(99), dec!(2))]);
+        let local = levels(&[
+            (dec!(101), dec!(1)),
+            (dec!(99), dec!(2)),
+            (dec!(98), dec!(1)),
+        ]);
+
+        let divergences = side_divergences(OrderBookSide::Bid, &raw, &local);
+
+        let prices = divergences.iter().map(|x| x.price).collect_vec();
+        assert_eq!(prices, vec![dec!(101), dec!(100)]);
+    }
+}
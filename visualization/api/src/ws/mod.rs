@@ -1,4 +1,8 @@
 pub mod actors;
 pub mod broker_messages;
 pub mod commands;
+pub mod protocol;
+pub mod rate_limiter;
 pub mod subscribes;
+#[cfg(test)]
+pub mod typescript;
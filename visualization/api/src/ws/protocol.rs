@@ -0,0 +1,362 @@
+//! Websocket protocol between visualization api and web client.
+//! Every message is `Command|body` where body is json of the type of the command.
+//! Client sends its protocol version in `Auth` command and receives `Error` if version isn't supported.
+//! TypeScript definitions of protocol in `visualization/web/src/protocol.d.ts` are generated from
+//! the types below, test `typescript_definitions_are_up_to_date` fails if they are outdated.
+
+use crate::ws::subscribes::liquidity::LiquiditySubscription;
+use crate::ws::subscribes::order_book_debug::OrderBookDebugSubscription;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+pub const AUTH: &str = "Auth";
+pub const SUBSCRIBE_LIQUIDITY: &str = "SubscribeLiquidity";
+pub const UNSUBSCRIBE_LIQUIDITY: &str = "UnsubscribeLiquidity";
+pub const SUBSCRIBE_ORDER_BOOK_DEBUG: &str = "SubscribeOrderBookDebug";
+pub const UNSUBSCRIBE_ORDER_BOOK_DEBUG: &str = "UnsubscribeOrderBookDebug";
+
+pub const AUTHORIZED: &str = "Authorized";
+pub const UPDATE_ORDERS_STATE: &str = "UpdateOrdersState";
+pub const UPDATE_ORDER_BOOK_DEBUG: &str = "UpdateOrderBookDebug";
+pub const ERROR: &str = "Error";
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Auth {
+    pub token: String,
+    pub version: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Authorized {
+    pub value: bool,
+    pub version: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    pub message: String,
+}
+
+pub enum ClientCommand {
+    /// Authorization
+    Auth(Auth),
+    /// Subscription for one record of order book (20 orders) and last 20 transactions
+    SubscribeLiquidity(LiquiditySubscription),
+    /// Unsubscribe from `SubscribeLiquidity`
+    UnsubscribeLiquidity,
+    /// Debug subscription for order book claimed by exchange side by side with local snapshot
+    /// and their divergences
+    SubscribeOrderBookDebug(OrderBookDebugSubscription),
+    /// Unsubscribe from `SubscribeOrderBookDebug`
+    UnsubscribeOrderBookDebug,
+}
+
+#[derive(Debug)]
+pub enum CommandError {
+    UnknownCommand(String),
+    InvalidBody {
+        command: String,
+        error: serde_json::Error,
+    },
+    UnsupportedVersion(u32),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::UnknownCommand(command) => write!(f, "Unknown command {command}"),
+            CommandError::InvalidBody { command, error } => {
+                write!(f, "Invalid body of command {command}: {error}")
+            }
+            CommandError::UnsupportedVersion(version) => write!(
+                f,
+                "Protocol version {version} isn't supported, server version is {PROTOCOL_VERSION}"
+            ),
+        }
+    }
+}
+
+impl ClientCommand {
+    pub fn parse(message: &str) -> Result<Self, CommandError> {
+        let (command, body) = message.split_once('|').unwrap_or((message, ""));
+
+        fn parse_body<'a, T: Deserialize<'a>>(
+            command: &str,
+            body: &'a str,
+        ) -> Result<T, CommandError> {
+            serde_json::from_str(body).map_err(|error| CommandError::InvalidBody {
+                command: command.to_owned(),
+                error,
+            })
+        }
+
+        match command {
+            AUTH => {
+                let auth: Auth = parse_body(command, body)?;
+                if auth.version != PROTOCOL_VERSION {
+                    return Err(CommandError::UnsupportedVersion(auth.version));
+                }
+                Ok(ClientCommand::Auth(auth))
+            }
+            SUBSCRIBE_LIQUIDITY => Ok(ClientCommand::SubscribeLiquidity(parse_body(
+                command, body,
+            )?)),
+            UNSUBSCRIBE_LIQUIDITY => Ok(ClientCommand::UnsubscribeLiquidity),
+            SUBSCRIBE_ORDER_BOOK_DEBUG => Ok(ClientCommand::SubscribeOrderBookDebug(parse_body(
+                command, body,
+            )?)),
+            UNSUBSCRIBE_ORDER_BOOK_DEBUG => Ok(ClientCommand::UnsubscribeOrderBookDebug),
+            _ => Err(CommandError::UnknownCommand(command.to_owned())),
+        }
+    }
+}
+
+/// TypeScript definitions are only generated by test `typescript_definitions_are_up_to_date`
+#[cfg(test)]
+mod typescript_definitions {
+    use super::*;
+    use crate::services::liquidity::{Amount, Price, TransactionOrderSide, TransactionTradeSide};
+    use crate::ws::commands::liquidity::{
+        Indicators, LiquidityResponseBody, Order, OrderStateAndTransactions, Orders, Trade,
+        Transaction,
+    };
+    use crate::ws::commands::order_book_debug::{
+        OrderBookDebugResponseBody, OrderBookSide, OrderBookSides, PriceLevelDivergence,
+    };
+    use crate::ws::typescript::{impl_ts_enum, impl_ts_interface, TsType};
+    use rust_decimal::Decimal;
+
+    impl_ts_interface!(Auth {
+        token: String,
+        version: u32,
+    });
+    impl_ts_interface!(LiquiditySubscription {
+        exchange_id: String,
+        currency_pair: String,
+    });
+    impl_ts_interface!(Authorized {
+        value: bool,
+        version: u32,
+    });
+    impl_ts_interface!(ErrorResponse { message: String });
+    impl_ts_enum!(TransactionOrderSide { Buy, Sell });
+    impl_ts_enum!(TransactionTradeSide { Buy, Sell });
+    impl_ts_interface!(Order {
+        amount: Amount,
+        price: Price,
+    });
+    impl_ts_interface!(Orders {
+        orders: Vec<Order>,
+        snapshot: Vec<(Price, Amount)>,
+    });
+    impl_ts_interface!(Trade {
+        exchange_name: String,
+        date_time: String,
+        price: Price,
+        amount: Amount,
+        exchange_order_id: String,
+        side: Option<TransactionTradeSide>,
+    });
+    impl_ts_interface!(Transaction {
+        id: String,
+        date_time: String,
+        price: Price,
+        amount: Amount,
+        hedged: Option<String>,
+        profit_loss_pct: Option<String>,
+        status: String,
+        trades: Vec<Trade>,
+        side: TransactionOrderSide,
+    });
+    impl_ts_interface!(Indicators {
+        volume_pct: Decimal,
+        bid_pct: Decimal,
+        ask_pct: Decimal,
+        spread: Option<Decimal>,
+        total_volume: Option<Amount>,
+        total_bid: Option<Amount>,
+        total_ask: Option<Amount>,
+    });
+    impl_ts_interface!(OrderStateAndTransactions {
+        exchange_name: String,
+        currency_code_pair: String,
+        desired_amount: Amount,
+        sell: Orders,
+        buy: Orders,
+        transactions: Vec<Transaction>,
+        indicators: Indicators,
+    });
+    impl_ts_interface!(LiquidityResponseBody {
+        orders_state_and_transactions: OrderStateAndTransactions,
+    });
+    impl_ts_interface!(OrderBookDebugSubscription {
+        exchange_id: String,
+        currency_pair: String,
+    });
+    impl_ts_enum!(OrderBookSide { Ask, Bid });
+    impl_ts_interface!(OrderBookSides {
+        asks: Vec<(Price, Amount)>,
+        bids: Vec<(Price, Amount)>,
+    });
+    impl_ts_interface!(PriceLevelDivergence {
+        side: OrderBookSide,
+        price: Price,
+        raw_amount: Option<Amount>,
+        local_amount: Option<Amount>,
+    });
+    impl_ts_interface!(OrderBookDebugResponseBody {
+        exchange_id: String,
+        currency_pair: String,
+        raw: OrderBookSides,
+        local: OrderBookSides,
+        divergences: Vec<PriceLevelDivergence>,
+    });
+
+    fn commands_declaration(name: &str, commands: &[(&str, String)]) -> String {
+        let commands = commands
+            .iter()
+            .map(|(command, body)| format!("  {command}: {body};\n"))
+            .collect::<String>();
+        format!("export interface {name} {{\n{commands}}}\n")
+    }
+
+    /// TypeScript definitions of all commands of protocol with their bodies
+    pub fn generate_typescript() -> String {
+        let declarations = [
+            Auth::ts_declaration(),
+            LiquiditySubscription::ts_declaration(),
+            Authorized::ts_declaration(),
+            ErrorResponse::ts_declaration(),
+            TransactionOrderSide::ts_declaration(),
+            TransactionTradeSide::ts_declaration(),
+            Order::ts_declaration(),
+            Orders::ts_declaration(),
+            Trade::ts_declaration(),
+            Transaction::ts_declaration(),
+            Indicators::ts_declaration(),
+            OrderStateAndTransactions::ts_declaration(),
+            LiquidityResponseBody::ts_declaration(),
+            OrderBookDebugSubscription::ts_declaration(),
+            OrderBookSide::ts_declaration(),
+            OrderBookSides::ts_declaration(),
+            PriceLevelDivergence::ts_declaration(),
+            OrderBookDebugResponseBody::ts_declaration(),
+        ];
+
+        let client_commands = commands_declaration(
+            "ClientCommands",
+            &[
+                (AUTH, Auth::ts_name()),
+                (SUBSCRIBE_LIQUIDITY, LiquiditySubscription::ts_name()),
+                (UNSUBSCRIBE_LIQUIDITY, "null".to_owned()),
+                (
+                    SUBSCRIBE_ORDER_BOOK_DEBUG,
+                    OrderBookDebugSubscription::ts_name(),
+                ),
+                (UNSUBSCRIBE_ORDER_BOOK_DEBUG, "null".to_owned()),
+            ],
+        );
+        let server_commands = commands_declaration(
+            "ServerCommands",
+            &[
+                (AUTHORIZED, Authorized::ts_name()),
+                (UPDATE_ORDERS_STATE, LiquidityResponseBody::ts_name()),
+                (
+                    UPDATE_ORDER_BOOK_DEBUG,
+                    OrderBookDebugResponseBody::ts_name(),
+                ),
+                (ERROR, ErrorResponse::ts_name()),
+            ],
+        );
+
+        let mut result = format!(
+            "// Generated from visualization/api/src/ws/protocol.rs, don't edit it manually.\n\
+             // Regenerate by `UPDATE_PROTOCOL_DEFINITIONS=1 cargo test -p api typescript_definitions`\n\
+             \n\
+             export type ProtocolVersion = {PROTOCOL_VERSION};\n"
+        );
+        for declaration in declarations.into_iter().flatten() {
+            result.push('\n');
+            result.push_str(&declaration);
+        }
+        for declaration in [client_commands, server_commands] {
+            result.push('\n');
+            result.push_str(&declaration);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::typescript_definitions::generate_typescript;
+    use super::*;
+
+    const TYPESCRIPT_DEFINITIONS_PATH: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../web/src/protocol.d.ts");
+
+    #[test]
+    fn typescript_definitions_are_up_to_date() {
+        let generated = generate_typescript();
+        if std::env::var("UPDATE_PROTOCOL_DEFINITIONS").is_ok() {
+            std::fs::write(TYPESCRIPT_DEFINITIONS_PATH, &generated)
+                .expect("Failure to write typescript definitions");
+            return;
+        }
+
+        let current = std::fs::read_to_string(TYPESCRIPT_DEFINITIONS_PATH).unwrap_or_default();
+        assert!(
+            current == generated,
+            "Typescript definitions of websocket protocol are outdated, regenerate them by \
+             `UPDATE_PROTOCOL_DEFINITIONS=1 cargo test -p api typescript_definitions`"
+        );
+    }
+
+    #[test]
+    fn parse_client_commands() {
+        let auth = ClientCommand::parse(r#"Auth|{"token":"token","version":1}"#);
+        assert!(matches!(auth, Ok(ClientCommand::Auth(auth)) if auth.token == "token"));
+
+        let subscribe = ClientCommand::parse(
+            r#"SubscribeLiquidity|{"exchangeId":"Binance","currencyPair":"btc/usdt"}"#,
+        );
+        assert!(matches!(
+            subscribe,
+            Ok(ClientCommand::SubscribeLiquidity(subscription)) if subscription.exchange_id == "Binance"
+        ));
+
+        assert!(matches!(
+            ClientCommand::parse("UnsubscribeLiquidity|"),
+            Ok(ClientCommand::UnsubscribeLiquidity)
+        ));
+
+        let subscribe = ClientCommand::parse(
+            r#"SubscribeOrderBookDebug|{"exchangeId":"Binance","currencyPair":"btc/usdt"}"#,
+        );
+        assert!(matches!(
+            subscribe,
+            Ok(ClientCommand::SubscribeOrderBookDebug(subscription)) if subscription.currency_pair == "btc/usdt"
+        ));
+    }
+
+    #[test]
+    fn parse_invalid_client_commands() {
+        assert!(matches!(
+            ClientCommand::parse(r#"Auth|{"token":"token","version":0}"#),
+            Err(CommandError::UnsupportedVersion(0))
+        ));
+        assert!(matches!(
+            ClientCommand::parse(r#"Auth|{"token":"token"}"#),
+            Err(CommandError::InvalidBody { .. })
+        ));
+        assert!(matches!(
+            ClientCommand::parse("SubscribeVolume|"),
+            Err(CommandError::UnknownCommand(command)) if command == "SubscribeVolume"
+        ));
+    }
+}
@@ -0,0 +1,51 @@
+use std::time::{Duration, Instant};
+
+/// Fixed window limiter of incoming messages of websocket session
+pub struct MessageRateLimiter {
+    max_messages: u32,
+    interval: Duration,
+    window_start: Instant,
+    messages_in_window: u32,
+}
+
+impl MessageRateLimiter {
+    pub fn new(max_messages: u32, interval: Duration) -> Self {
+        Self {
+            max_messages,
+            interval,
+            window_start: Instant::now(),
+            messages_in_window: 0,
+        }
+    }
+
+    /// Registers message received at `now`, returns `false` if limit of current window is exceeded
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.window_start) >= self.interval {
+            self.window_start = now;
+            self.messages_in_window = 0;
+        }
+
+        self.messages_in_window += 1;
+        self.messages_in_window <= self.max_messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_is_reset_in_next_window() {
+        let interval = Duration::from_secs(1);
+        let mut limiter = MessageRateLimiter::new(2, interval);
+        let start = limiter.window_start;
+
+        assert!(limiter.try_acquire(start));
+        assert!(limiter.try_acquire(start + Duration::from_millis(500)));
+        assert!(!limiter.try_acquire(start + Duration::from_millis(900)));
+
+        assert!(limiter.try_acquire(start + interval));
+        assert!(limiter.try_acquire(start + interval + Duration::from_millis(1)));
+        assert!(!limiter.try_acquire(start + interval + Duration::from_millis(2)));
+    }
+}
@@ -1 +1,2 @@
 pub mod liquidity;
+pub mod order_book_debug;
@@ -0,0 +1,21 @@
+use crate::ws::subscribes::liquidity::Subscription;
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Subscription for comparison of order book claimed by exchange and local snapshot of market
+#[derive(Clone, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderBookDebugSubscription {
+    pub exchange_id: String,
+    pub currency_pair: String,
+}
+
+impl Subscription for OrderBookDebugSubscription {
+    fn get_hash(&self) -> u64 {
+        let mut s = DefaultHasher::new();
+        "orderBookDebugSubscription".hash(&mut s);
+        self.hash(&mut s);
+        s.finish()
+    }
+}
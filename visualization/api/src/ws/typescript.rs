@@ -0,0 +1,143 @@
+use rust_decimal::Decimal;
+
+/// Rust type of websocket protocol which has TypeScript definition
+pub trait TsType {
+    /// Name of type or type expression which is used in fields of other types
+    fn ts_name() -> String;
+
+    /// Declaration of type, `None` for builtin types
+    fn ts_declaration() -> Option<String> {
+        None
+    }
+}
+
+macro_rules! impl_ts_builtin {
+    ($ts_name:literal, $($ty:ty),+) => {
+        $(
+            impl TsType for $ty {
+                fn ts_name() -> String {
+                    $ts_name.to_owned()
+                }
+            }
+        )+
+    };
+}
+
+impl_ts_builtin!("string", String);
+impl_ts_builtin!("boolean", bool);
+impl_ts_builtin!("number", u32, u64, i64);
+// Decimal is serialized as string to keep precision
+impl_ts_builtin!("string", Decimal);
+
+impl<T: TsType> TsType for Option<T> {
+    fn ts_name() -> String {
+        format!("{} | null", T::ts_name())
+    }
+}
+
+impl<T: TsType> TsType for Vec<T> {
+    fn ts_name() -> String {
+        format!("{}[]", T::ts_name())
+    }
+}
+
+impl<A: TsType, B: TsType> TsType for (A, B) {
+    fn ts_name() -> String {
+        format!("[{}, {}]", A::ts_name(), B::ts_name())
+    }
+}
+
+pub fn camel_case(snake_case: &str) -> String {
+    let mut result = String::with_capacity(snake_case.len());
+    let mut is_upper = false;
+    for c in snake_case.chars() {
+        match c {
+            '_' => is_upper = true,
+            _ if is_upper => {
+                result.extend(c.to_uppercase());
+                is_upper = false;
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Implements `TsType` for struct serialized with `#[serde(rename_all = "camelCase")]`.
+/// All fields of struct have to be listed with their types, otherwise it doesn't compile
+macro_rules! impl_ts_interface {
+    ($ty:ident { $($field:ident: $field_ty:ty),* $(,)? }) => {
+        impl $crate::ws::typescript::TsType for $ty {
+            fn ts_name() -> String {
+                stringify!($ty).to_owned()
+            }
+
+            fn ts_declaration() -> Option<String> {
+                #[allow(dead_code)]
+                fn check_fields(value: &$ty) {
+                    let $ty { $($field),* } = value;
+                    $(let _: &$field_ty = $field;)*
+                }
+
+                let fields = [$(
+                    format!(
+                        "  {}: {};\n",
+                        $crate::ws::typescript::camel_case(stringify!($field)),
+                        <$field_ty as $crate::ws::typescript::TsType>::ts_name()
+                    )
+                ),*];
+                Some(format!(
+                    "export interface {} {{\n{}}}\n",
+                    stringify!($ty),
+                    fields.concat()
+                ))
+            }
+        }
+    };
+}
+
+/// Implements `TsType` for enum with unit variants serialized by names.
+/// All variants of enum have to be listed, otherwise it doesn't compile
+macro_rules! impl_ts_enum {
+    ($ty:ident { $($variant:ident),+ $(,)? }) => {
+        impl $crate::ws::typescript::TsType for $ty {
+            fn ts_name() -> String {
+                stringify!($ty).to_owned()
+            }
+
+            fn ts_declaration() -> Option<String> {
+                #[allow(dead_code)]
+                fn check_variants(value: &$ty) {
+                    match value {
+                        $($ty::$variant => {}),+
+                    }
+                }
+
+                let variants = [$(format!("\"{}\"", stringify!($variant))),+];
+                Some(format!(
+                    "export type {} = {};\n",
+                    stringify!($ty),
+                    variants.join(" | ")
+                ))
+            }
+        }
+    };
+}
+
+pub(crate) use impl_ts_enum;
+pub(crate) use impl_ts_interface;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camel_case_field_names() {
+        assert_eq!(camel_case("exchange_id"), "exchangeId");
+        assert_eq!(
+            camel_case("orders_state_and_transactions"),
+            "ordersStateAndTransactions"
+        );
+        assert_eq!(camel_case("price"), "price");
+    }
+}
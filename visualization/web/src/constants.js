@@ -1,4 +1,6 @@
 export default {
+  // Has to match PROTOCOL_VERSION of visualization/api/src/ws/protocol.rs
+  wsProtocolVersion: 1,
   clientType: {
     ico: "Ico",
     exchange: "Exchange",
@@ -0,0 +1,124 @@
+// Generated from visualization/api/src/ws/protocol.rs, don't edit it manually.
+// Regenerate by `UPDATE_PROTOCOL_DEFINITIONS=1 cargo test -p api typescript_definitions`
+
+export type ProtocolVersion = 1;
+
+export interface Auth {
+  token: string;
+  version: number;
+}
+
+export interface LiquiditySubscription {
+  exchangeId: string;
+  currencyPair: string;
+}
+
+export interface Authorized {
+  value: boolean;
+  version: number;
+}
+
+export interface ErrorResponse {
+  message: string;
+}
+
+export type TransactionOrderSide = "Buy" | "Sell";
+
+export type TransactionTradeSide = "Buy" | "Sell";
+
+export interface Order {
+  amount: string;
+  price: string;
+}
+
+export interface Orders {
+  orders: Order[];
+  snapshot: [string, string][];
+}
+
+export interface Trade {
+  exchangeName: string;
+  dateTime: string;
+  price: string;
+  amount: string;
+  exchangeOrderId: string;
+  side: TransactionTradeSide | null;
+}
+
+export interface Transaction {
+  id: string;
+  dateTime: string;
+  price: string;
+  amount: string;
+  hedged: string | null;
+  profitLossPct: string | null;
+  status: string;
+  trades: Trade[];
+  side: TransactionOrderSide;
+}
+
+export interface Indicators {
+  volumePct: string;
+  bidPct: string;
+  askPct: string;
+  spread: string | null;
+  totalVolume: string | null;
+  totalBid: string | null;
+  totalAsk: string | null;
+}
+
+export interface OrderStateAndTransactions {
+  exchangeName: string;
+  currencyCodePair: string;
+  desiredAmount: string;
+  sell: Orders;
+  buy: Orders;
+  transactions: Transaction[];
+  indicators: Indicators;
+}
+
+export interface LiquidityResponseBody {
+  ordersStateAndTransactions: OrderStateAndTransactions;
+}
+
+export interface OrderBookDebugSubscription {
+  exchangeId: string;
+  currencyPair: string;
+}
+
+export type OrderBookSide = "Ask" | "Bid";
+
+export interface OrderBookSides {
+  asks: [string, string][];
+  bids: [string, string][];
+}
+
+export interface PriceLevelDivergence {
+  side: OrderBookSide;
+  price: string;
+  rawAmount: string | null;
+  localAmount: string | null;
+}
+
+export interface OrderBookDebugResponseBody {
+  exchangeId: string;
+  currencyPair: string;
+  raw: OrderBookSides;
+  local: OrderBookSides;
+  divergences: PriceLevelDivergence[];
+}
+
+export interface ClientCommands {
+  Auth: Auth;
+  SubscribeLiquidity: LiquiditySubscription;
+  UnsubscribeLiquidity: null;
+  SubscribeOrderBookDebug: OrderBookDebugSubscription;
+  UnsubscribeOrderBookDebug: null;
+}
+
+export interface ServerCommands {
+  Authorized: Authorized;
+  UpdateOrdersState: LiquidityResponseBody;
+  UpdateOrderBookDebug: OrderBookDebugResponseBody;
+  Error: ErrorResponse;
+}
@@ -1,6 +1,7 @@
 import { Container } from "unstated";
 import Sockette from "sockette";
 import config from "../config.js";
+import constants from "../constants.js";
 import { groupBy } from "../controls/functions";
 import CryptolpAxios from "../cryptolpaxios";
 import { toast } from "react-toastify";
@@ -123,7 +124,7 @@ class WsContainer extends Container {
       "auth",
       { auth: true },
       "Auth",
-      JSON.stringify({ token })
+      JSON.stringify({ token, version: constants.wsProtocolVersion })
     );
   }
 